@@ -12,12 +12,34 @@ use std::{
 };
 
 use fuzzing_lib::{
-    fuzzer::{FuzzingDirective, FuzzingStrategy},
+    crypto::signing::{apply_signature_violation, SignatureViolation},
+    format::{
+        decoder::{create_decoder::decode_create_response_body, decode_session_setup_response},
+        encoder::convert_to_async_request,
+        HEADER_LENGTH,
+    },
+    fuzzer::{
+        cancel_fuzzer::{sample_cancel_target, CancelTarget},
+        durable_handle_fuzzer::{
+            sample_durable_reconnect_mutations, sample_reconnect_credentials, DurableHandleVersion,
+            DurableReconnectMutation,
+        },
+        handshake::session_scenario_fuzzer::SessionScenario,
+        header_fuzzer::AsyncIdStrategy,
+        FuzzingDirective, FuzzingStrategy,
+    },
+    kerberos::KerberosConfig,
     networking::{
-        packets,
+        connect,
+        connection_context::{ConnectionContext, SequenceViolation},
+        kdc, packets,
         state_transition_engine::{ResponseType, State},
     },
-    smb2::requests::RequestType,
+    ntlmssp::credentials::{Credentials, Identity},
+    smb2::{
+        helper_functions::negotiate_context::{Ciphers, CompressionAlgorithms},
+        requests::RequestType,
+    },
 };
 
 pub fn main() {
@@ -54,52 +76,192 @@ pub fn map_input_to_fuzzing_directive(args: Vec<String>) -> FuzzingDirective {
     fuzzing_directive.fuzzing_strategy =
         Some(FuzzingStrategy::map_string_to_fuzzing_strategy(&args[2]));
     fuzzing_directive.state = Some(State::map_string_to_state(&args[3]));
+    let mut optional_args = args[4..].iter().peekable();
+    while let Some(flag) = optional_args.next() {
+        match flag.as_str() {
+            "-async" | "--async" | "--Async" => {
+                fuzzing_directive.async_id =
+                    Some(AsyncIdStrategy::map_string_to_async_id_strategy(
+                        optional_args.next_if(|value| !value.starts_with('-')),
+                    ));
+            }
+            "-sv" | "--sequence_violation" | "--Sequence_violation" => {
+                fuzzing_directive.sequence_violation =
+                    Some(SequenceViolation::map_string_to_sequence_violation(
+                        optional_args.next().expect("Missing sequence violation."),
+                    ));
+            }
+            "-bs" | "--bad_signature" | "--Bad_signature" => {
+                fuzzing_directive.signature_violation =
+                    Some(SignatureViolation::map_string_to_signature_violation(
+                        optional_args.next().expect("Missing signature violation."),
+                    ));
+            }
+            "-enc" | "--encrypt" | "--Encrypt" => {
+                fuzzing_directive.session_cipher = Some(Ciphers::map_string_to_cipher(
+                    optional_args.next().expect("Missing cipher."),
+                ));
+            }
+            "-tf" | "--transform_fuzzing" | "--Transform_fuzzing" => {
+                fuzzing_directive.transform_header_fuzzing_strategy =
+                    Some(FuzzingStrategy::map_string_to_fuzzing_strategy(
+                        optional_args
+                            .next()
+                            .expect("Missing transform header fuzzing strategy."),
+                    ));
+            }
+            "-pt" | "--preauth_tampering" | "--Preauth_tampering" => {
+                fuzzing_directive.preauth_tampering = true;
+            }
+            "-cmp" | "--compress" | "--Compress" => {
+                fuzzing_directive.compression_algorithm =
+                    Some(CompressionAlgorithms::map_string_to_compression_algorithm(
+                        optional_args
+                            .next()
+                            .expect("Missing compression algorithm."),
+                    ));
+                fuzzing_directive.chained_compression = optional_args
+                    .next_if(|value| value.as_str() == "chained")
+                    .is_some();
+                fuzzing_directive.forced_compression = optional_args
+                    .next_if(|value| value.as_str() == "force")
+                    .is_some();
+            }
+            "-cf" | "--compression_fuzzing" | "--Compression_fuzzing" => {
+                fuzzing_directive.compression_fuzzing_strategy =
+                    Some(FuzzingStrategy::map_string_to_fuzzing_strategy(
+                        optional_args
+                            .next()
+                            .expect("Missing compression fuzzing strategy."),
+                    ));
+            }
+            "-cred" | "--credentials" | "--Credentials" => {
+                fuzzing_directive.credentials = Credentials::map_string_to_credentials(
+                    optional_args.next().expect("Missing user."),
+                    optional_args.next().expect("Missing password."),
+                );
+            }
+            "-id" | "--identity" | "--Identity" => {
+                fuzzing_directive.credentials = Identity::map_string_to_credentials(
+                    optional_args.next().expect("Missing identity."),
+                );
+            }
+            "-krb" | "--kerberos" | "--Kerberos" => {
+                fuzzing_directive.kerberos = Some(KerberosConfig::map_string_to_kerberos_config(
+                    optional_args.next().expect("Missing KDC address."),
+                    optional_args.next().expect("Missing service principal."),
+                ));
+            }
+            "-ss" | "--session_scenario" | "--Session_scenario" => {
+                fuzzing_directive.session_scenario =
+                    Some(SessionScenario::map_string_to_session_scenario(
+                        optional_args.next().expect("Missing session scenario."),
+                    ));
+            }
+            "-dh" | "--durable_handle" | "--Durable_handle" => {
+                fuzzing_directive.durable_handle_version =
+                    DurableHandleVersion::map_string_to_durable_handle_version(
+                        optional_args
+                            .next()
+                            .expect("Missing durable handle version."),
+                    );
+            }
+            "-sf" | "--spnego_fuzzing" | "--Spnego_fuzzing" => {
+                fuzzing_directive.spnego_fuzzing_strategy =
+                    Some(FuzzingStrategy::map_string_to_fuzzing_strategy(
+                        optional_args
+                            .next()
+                            .expect("Missing SPNEGO fuzzing strategy."),
+                    ));
+            }
+            "-hf" | "--header_fuzzing" | "--Header_fuzzing" => {
+                fuzzing_directive.header_fuzzing_strategy =
+                    Some(FuzzingStrategy::map_string_to_fuzzing_strategy(
+                        optional_args
+                            .next()
+                            .expect("Missing header fuzzing strategy."),
+                    ));
+            }
+            _ => panic!("Invalid optional parameter."),
+        }
+    }
 
     fuzzing_directive
 }
 
 /// Checks whether the message to be fuzzed is the correct one for the state.
 /// In later versions this won't be needed as the tool will enable to fuzz each
-/// message in each state.
+/// message in each state. The session setup messages of a session scenario are
+/// sent once the session is established.
 pub fn correct_message_for_state(directive: &FuzzingDirective) -> bool {
     if let Some(message) = directive.message.as_ref() {
         if let Some(state) = directive.state.as_ref() {
             match state {
                 State::Initial => {
-                    if let RequestType::Negotiate(_) | RequestType::Echo(_) = message {
+                    if let RequestType::Negotiate(_)
+                    | RequestType::Echo(_)
+                    | RequestType::Cancel(_) = message
+                    {
                         return true;
                     }
                 }
                 State::Negotiate => {
-                    if let RequestType::SessionSetupNeg(_) | RequestType::Echo(_) = message {
+                    if let RequestType::SessionSetupNeg(_)
+                    | RequestType::Echo(_)
+                    | RequestType::Cancel(_) = message
+                    {
                         return true;
                     }
                 }
                 State::SessionSetupNeg => {
-                    if let RequestType::SessionSetupAuth(_) | RequestType::Echo(_) = message {
+                    if let RequestType::SessionSetupAuth(_)
+                    | RequestType::Echo(_)
+                    | RequestType::Cancel(_) = message
+                    {
                         return true;
                     }
                 }
                 State::SessionSetupAuth => {
-                    if let RequestType::TreeConnect(_) | RequestType::Echo(_) = message {
+                    if let (
+                        Some(_),
+                        RequestType::SessionSetupNeg(_) | RequestType::SessionSetupAuth(_),
+                    ) = (&directive.session_scenario, message)
+                    {
+                        return true;
+                    }
+                    if let RequestType::TreeConnect(_)
+                    | RequestType::Echo(_)
+                    | RequestType::Cancel(_) = message
+                    {
                         return true;
                     }
                 }
                 State::TreeConnect => {
-                    if let RequestType::Create(_) | RequestType::Echo(_) = message {
+                    if let RequestType::Create(_)
+                    | RequestType::ChangeNotify(_)
+                    | RequestType::OplockBreak(_)
+                    | RequestType::Compound(_)
+                    | RequestType::Echo(_)
+                    | RequestType::Cancel(_) = message
+                    {
                         return true;
                     }
                 }
-                State::Create => {
+                State::Create | State::DurableReconnect => {
                     if let RequestType::QueryInfo(_)
                     | RequestType::Close(_)
-                    | RequestType::Echo(_) = message
+                    | RequestType::Lock(_)
+                    | RequestType::Flush(_)
+                    | RequestType::Echo(_)
+                    | RequestType::Cancel(_) = message
                     {
                         return true;
                     }
                 }
-                State::Close => {
-                    if let RequestType::Create(_) | RequestType::Echo(_) = message {
+                State::Close | State::DurableOpen => {
+                    if let RequestType::Create(_) | RequestType::Echo(_) | RequestType::Cancel(_) =
+                        message
+                    {
                         return true;
                     }
                 }
@@ -121,9 +283,23 @@ pub fn execute_fuzzing_process(directive: FuzzingDirective, ip_address: &str, po
     while tcp_error.is_none() {
         match TcpStream::connect(ip_address.to_owned() + ":" + port) {
             Ok(mut stream) => {
+                stream
+                    .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+                    .expect("Failed to set read time out.");
                 if let Some(state) = directive.state.as_ref() {
-                    let state_response = state.go_to_state(&mut stream);
-                    fuzz_message_with_strategy(&mut stream, directive.clone(), &state_response);
+                    let mut context = ConnectionContext::default();
+                    context.session_cipher = directive.session_cipher.clone();
+                    context.tamper_preauth_integrity_hash = directive.preauth_tampering;
+                    context.credentials = directive.credentials.clone();
+                    context.kerberos = directive.kerberos.clone();
+                    context.durable_handle_version = directive.durable_handle_version.clone();
+                    let state_response = state.go_to_state(&mut stream, &mut context);
+                    fuzz_message_with_strategy(
+                        &mut stream,
+                        &mut context,
+                        directive.clone(),
+                        &state_response,
+                    );
                 } else {
                     panic!("Fuzzing directive is missing the state parameter.")
                 }
@@ -143,44 +319,136 @@ pub fn execute_fuzzing_process(directive: FuzzingDirective, ip_address: &str, po
 /// Matches the message type and executes the appropriate the fuzzing strategy and sending order.
 pub fn fuzz_message_with_strategy(
     stream: &mut TcpStream,
+    context: &mut ConnectionContext,
     directive: FuzzingDirective,
     state_response: &ResponseType,
 ) {
-    if let Some(message) = directive.message {
+    if let Some(message) = &directive.message {
+        if let (
+            Some(scenario),
+            RequestType::SessionSetupNeg(_) | RequestType::SessionSetupAuth(_),
+        ) = (&directive.session_scenario, message)
+        {
+            send_fuzzed_session_scenario(
+                stream,
+                context,
+                state_response,
+                scenario,
+                directive.fuzzing_strategy.clone().unwrap(),
+                &directive,
+            );
+            return;
+        }
         match message {
-            RequestType::Negotiate(_) => {
-                send_fuzzed_negotiate(stream, directive.fuzzing_strategy.unwrap())
-            }
-            RequestType::SessionSetupNeg(_) => {
-                send_fuzzed_session_setup_negotiate(stream, directive.fuzzing_strategy.unwrap())
-            }
+            RequestType::Negotiate(_) => send_fuzzed_negotiate(
+                stream,
+                context,
+                directive.fuzzing_strategy.clone().unwrap(),
+                &directive,
+            ),
+            RequestType::SessionSetupNeg(_) => send_fuzzed_session_setup_negotiate(
+                stream,
+                context,
+                directive.fuzzing_strategy.clone().unwrap(),
+                &directive,
+            ),
             RequestType::SessionSetupAuth(_) => send_fuzzed_session_setup_authenticate(
                 stream,
+                context,
                 state_response,
-                directive.fuzzing_strategy.unwrap(),
+                directive.fuzzing_strategy.clone().unwrap(),
+                &directive,
             ),
             RequestType::TreeConnect(_) => send_fuzzed_tree_connect_request(
                 stream,
+                context,
                 state_response,
-                directive.fuzzing_strategy.unwrap(),
+                directive.fuzzing_strategy.clone().unwrap(),
+                &directive,
             ),
-            RequestType::Create(_) => send_fuzzed_create_request(
+            RequestType::Create(_) => match state_response {
+                ResponseType::DurableOpen(_) => send_fuzzed_durable_reconnect_request(
+                    stream,
+                    context,
+                    state_response,
+                    directive.fuzzing_strategy.clone().unwrap(),
+                    &directive,
+                ),
+                _ => send_fuzzed_create_request(
+                    stream,
+                    context,
+                    state_response,
+                    directive.fuzzing_strategy.clone().unwrap(),
+                    &directive,
+                ),
+            },
+            RequestType::QueryInfo(_) => send_fuzzed_query_info_request(
                 stream,
+                context,
                 state_response,
-                directive.fuzzing_strategy.unwrap(),
+                directive.fuzzing_strategy.clone().unwrap(),
+                &directive,
             ),
-            RequestType::QueryInfo(_) => send_fuzzed_query_info_request(
+            RequestType::Close(_) => send_fuzzed_close_request(
                 stream,
+                context,
                 state_response,
-                directive.fuzzing_strategy.unwrap(),
+                directive.fuzzing_strategy.clone().unwrap(),
+                &directive,
             ),
-            RequestType::Close(_) => send_fuzzed_close_request(
+            RequestType::Echo(_) => send_fuzzed_echo_request(
+                stream,
+                context,
+                directive.fuzzing_strategy.clone().unwrap(),
+                &directive,
+            ),
+            RequestType::Lock(_) => send_fuzzed_lock_request(
                 stream,
+                context,
                 state_response,
-                directive.fuzzing_strategy.unwrap(),
+                directive.fuzzing_strategy.clone().unwrap(),
+                &directive,
             ),
-            RequestType::Echo(_) => {
-                send_fuzzed_echo_request(stream, directive.fuzzing_strategy.unwrap())
+            RequestType::Flush(_) => send_fuzzed_flush_request(
+                stream,
+                context,
+                state_response,
+                directive.fuzzing_strategy.clone().unwrap(),
+                &directive,
+            ),
+            RequestType::Cancel(_) => send_fuzzed_cancel_request(
+                stream,
+                context,
+                state_response,
+                directive.fuzzing_strategy.clone().unwrap(),
+                &directive,
+            ),
+            RequestType::ChangeNotify(_) => send_fuzzed_change_notify_request(
+                stream,
+                context,
+                state_response,
+                directive.fuzzing_strategy.clone().unwrap(),
+                &directive,
+            ),
+            RequestType::OplockBreak(_) => send_fuzzed_oplock_break_acknowledgment(
+                stream,
+                context,
+                state_response,
+                directive.fuzzing_strategy.clone().unwrap(),
+                &directive,
+            ),
+            RequestType::Compound(_) => send_fuzzed_compound_request(
+                stream,
+                context,
+                state_response,
+                directive.fuzzing_strategy.clone().unwrap(),
+                &directive,
+            ),
+            RequestType::SetInfo(_) | RequestType::Logoff(_) => {
+                unreachable!("Set Info and Logoff are only sent to race a pending Change Notify.")
+            }
+            RequestType::Read(_) => {
+                unreachable!("Read is only sent as part of a Compound request.")
             }
         }
     } else {
@@ -188,57 +456,241 @@ pub fn fuzz_message_with_strategy(
     }
 }
 
-/// Prepares the fuzzed negotiate message and sends it wrapped in a TCP packet.
-pub fn send_fuzzed_negotiate(stream: &mut TcpStream, strategy: FuzzingStrategy) {
-    let negotiate_request: Vec<u8> = packets::prepare_negotiate_packet(Some(strategy));
-    if stream.write_all(&negotiate_request[..]).is_err() {
+/// Sends the request wrapped in a TCP packet. The credit charge and message id of the request
+/// are allocated from the connection context and violated if a sequence violation is given.
+/// If a SPNEGO fuzzing strategy is given, the GSS layer of session setup requests is fuzzed.
+/// If a header fuzzing strategy is given,
+/// the header of the request is fuzzed accordingly. If an async id strategy is given,
+/// the request is sent with the ASYNC_COMMAND flag and the corresponding async id.
+/// Finally, the request is signed if signing is established, and its signature is
+/// violated if a signature violation is given. If encryption is established, the request
+/// is encrypted instead of signed, and its transform header is fuzzed if a strategy is given.
+/// Fuzzed negotiate and session setup requests are added to the preauth integrity hash as plain
+/// SMB2 messages, before they are compressed or encrypted.
+pub fn write_request(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    request: Vec<u8>,
+    directive: &FuzzingDirective,
+) {
+    let request = context.sequence_request(request);
+    let request = match &directive.spnego_fuzzing_strategy {
+        Some(strategy) => packets::fuzz_packet_spnego(request, strategy),
+        None => request,
+    };
+    let request = match &directive.sequence_violation {
+        Some(violation) => context.apply_sequence_violation(request, violation),
+        None => request,
+    };
+    let request = match &directive.header_fuzzing_strategy {
+        Some(strategy) => packets::fuzz_packet_header(request, strategy),
+        None => request,
+    };
+    let request = match &directive.async_id {
+        Some(strategy) => convert_to_async_request(request, &strategy.unpack_async_id()),
+        None => request,
+    };
+    let request = match context.encryption_key {
+        Some(_) => request,
+        None => context.sign_request(request),
+    };
+    let request = match &directive.signature_violation {
+        Some(violation) => apply_signature_violation(request, violation),
+        None => request,
+    };
+    context.update_preauth_integrity_hash(&request);
+    let request = match &directive.compression_algorithm {
+        Some(algorithm)
+            if !directive.forced_compression
+                && !context
+                    .negotiated_connection
+                    .as_ref()
+                    .is_some_and(|connection| {
+                        connection.supports_compression(algorithm, directive.chained_compression)
+                    }) =>
+        {
+            println!(
+                "The server did not negotiate the compression, sending the message uncompressed."
+            );
+            request
+        }
+        Some(algorithm) => packets::compress_packet(
+            request,
+            algorithm,
+            directive.chained_compression,
+            directive.compression_fuzzing_strategy.as_ref(),
+        ),
+        None => request,
+    };
+    let request = context.encrypt_request(
+        request,
+        directive.transform_header_fuzzing_strategy.as_ref(),
+    );
+
+    if stream.write_all(&request[..]).is_err() {
         println!("Reset Connection.");
     }
 }
 
+/// Prepares the fuzzed negotiate message and sends it wrapped in a TCP packet.
+pub fn send_fuzzed_negotiate(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    strategy: FuzzingStrategy,
+    directive: &FuzzingDirective,
+) {
+    let negotiate_request: Vec<u8> = packets::prepare_negotiate_packet(Some(strategy));
+    write_request(stream, context, negotiate_request, directive);
+}
+
 /// Prepares the fuzzed session setup negotiate message and sends it wrapped in a TCP packet.
-pub fn send_fuzzed_session_setup_negotiate(stream: &mut TcpStream, strategy: FuzzingStrategy) {
-    let session_setup_negotiate_request: Vec<u8> =
-        packets::prepare_session_setup_negotiate_packet(Some(strategy));
-    if stream
-        .write_all(&session_setup_negotiate_request[..])
-        .is_err()
-    {
-        println!("Reset Connection.");
-    }
+/// If Kerberos is configured, the Kerberos session setup message with the AP-REQ for the
+/// service ticket obtained from the KDC is fuzzed instead.
+pub fn send_fuzzed_session_setup_negotiate(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    strategy: FuzzingStrategy,
+    directive: &FuzzingDirective,
+) {
+    let session_setup_negotiate_request: Vec<u8> = match &context.kerberos {
+        Some(config) => {
+            let service_ticket = match kdc::obtain_service_ticket(config, &context.credentials) {
+                Ok(service_ticket) => service_ticket,
+                Err(e) => {
+                    println!("Failed to obtain the service ticket: {}", e);
+                    return;
+                }
+            };
+            packets::prepare_session_setup_kerberos_packet(Some(strategy), &service_ticket).0
+        }
+        None => packets::prepare_session_setup_negotiate_packet(Some(strategy)),
+    };
+    write_request(stream, context, session_setup_negotiate_request, directive);
 }
 
 /// Prepares the fuzzed session setup authenticate message and sends it wrapped in a TCP packet.
 pub fn send_fuzzed_session_setup_authenticate(
     stream: &mut TcpStream,
+    context: &mut ConnectionContext,
     state_response: &ResponseType,
     strategy: FuzzingStrategy,
+    directive: &FuzzingDirective,
 ) {
     let (body, session_id) = match state_response {
         ResponseType::SessionSetupNeg((body, session_id)) => (body, session_id),
         _ => panic!("Invalid ResponseType."),
     };
 
-    let session_setup_authenticate_request: Vec<u8> =
+    let (session_setup_authenticate_request, _) =
         packets::prepare_session_setup_authenticate_packet(
             Some(strategy),
             session_id.clone(),
             body.clone(),
+            &context.credentials,
         );
 
-    if stream
-        .write_all(&session_setup_authenticate_request[..])
-        .is_err()
-    {
-        println!("Reset Connection.");
+    write_request(
+        stream,
+        context,
+        session_setup_authenticate_request,
+        directive,
+    );
+}
+
+/// Carries out the session scenario on the session established in the session setup authenticate state.
+/// The re-authentication is sent on the established connection, the binding and the reconnect on a
+/// second connection to the server. The scenario always authenticates with NTLM.
+pub fn send_fuzzed_session_scenario(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    state_response: &ResponseType,
+    scenario: &SessionScenario,
+    strategy: FuzzingStrategy,
+    directive: &FuzzingDirective,
+) {
+    let session_id = match state_response {
+        ResponseType::SessionSetupAuth(session_id) => session_id.clone(),
+        _ => panic!("Invalid ResponseType."),
+    };
+
+    if context.kerberos.is_some() {
+        println!(
+            "The session scenario authenticates with NTLM, even though Kerberos is configured."
+        );
+    }
+
+    match scenario {
+        SessionScenario::Reauthentication => send_fuzzed_session_scenario_request(
+            stream, context, scenario, session_id, strategy, directive,
+        ),
+        SessionScenario::Binding | SessionScenario::Reconnect => {
+            let (mut channel, mut channel_context) =
+                match connect::open_session_channel(stream, context, scenario) {
+                    Ok(channel) => channel,
+                    Err(e) => {
+                        println!("Failed to open the second connection: {}", e);
+                        return;
+                    }
+                };
+            send_fuzzed_session_scenario_request(
+                &mut channel,
+                &mut channel_context,
+                scenario,
+                session_id,
+                strategy,
+                directive,
+            );
+        }
     }
 }
 
+/// Prepares the fuzzed session setup message of the session scenario and sends it wrapped in a TCP packet.
+/// To fuzz the session setup authenticate message, the session setup negotiate message of the scenario
+/// is sent first and the fuzzed message authenticates against the challenge of its response.
+pub fn send_fuzzed_session_scenario_request(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    scenario: &SessionScenario,
+    session_id: Vec<u8>,
+    strategy: FuzzingStrategy,
+    directive: &FuzzingDirective,
+) {
+    let session_setup_request = if let Some(RequestType::SessionSetupNeg(_)) = directive.message {
+        packets::prepare_session_scenario_negotiate_packet(Some(strategy), scenario, session_id)
+    } else {
+        let response = connect::send_session_scenario_negotiate_request_and_get_response(
+            stream,
+            context,
+            scenario,
+            session_id.clone(),
+            None,
+        );
+        if response.len() < 4 + HEADER_LENGTH {
+            println!("Invalid session setup response 1.");
+            return;
+        }
+        let (response_header, response_body) = decode_session_setup_response(response);
+        packets::prepare_session_scenario_authenticate_packet(
+            Some(strategy),
+            scenario,
+            session_id,
+            response_header.session_id,
+            response_body,
+            &context.credentials,
+        )
+        .0
+    };
+
+    write_request(stream, context, session_setup_request, directive);
+}
+
 /// Prepares the fuzzed tree connect message and sends it wrapped in a TCP packet.
 pub fn send_fuzzed_tree_connect_request(
     stream: &mut TcpStream,
+    context: &mut ConnectionContext,
     state_response: &ResponseType,
     strategy: FuzzingStrategy,
+    directive: &FuzzingDirective,
 ) {
     let session_id = match state_response {
         ResponseType::SessionSetupAuth(session_id) => session_id,
@@ -248,16 +700,16 @@ pub fn send_fuzzed_tree_connect_request(
     let tree_connect_request: Vec<u8> =
         packets::prepare_tree_connect_packet(Some(strategy), session_id.clone());
 
-    if stream.write_all(&tree_connect_request[..]).is_err() {
-        println!("Reset Connection.");
-    }
+    write_request(stream, context, tree_connect_request, directive);
 }
 
 /// Prepares the fuzzed create message and sends it wrapped in a TCP packet.
 pub fn send_fuzzed_create_request(
     stream: &mut TcpStream,
+    context: &mut ConnectionContext,
     state_response: &ResponseType,
     strategy: FuzzingStrategy,
+    directive: &FuzzingDirective,
 ) {
     let (session_id, tree_id) = match state_response {
         ResponseType::TreeConnect(ids) => ids,
@@ -267,16 +719,60 @@ pub fn send_fuzzed_create_request(
     let create_request: Vec<u8> =
         packets::prepare_create_packet(Some(strategy), session_id.clone(), tree_id.clone());
 
-    if stream.write_all(&create_request[..]).is_err() {
-        println!("Reset Connection.");
-    }
+    write_request(stream, context, create_request, directive);
+}
+
+/// Drops the connection of the durable open, reconnects and sends the fuzzed create message that
+/// reclaims the durable handle wrapped in a TCP packet. The predefined strategy samples the mutations
+/// of the reclaim, with which the reclaiming session is also authenticated as a different user.
+pub fn send_fuzzed_durable_reconnect_request(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    state_response: &ResponseType,
+    strategy: FuzzingStrategy,
+    directive: &FuzzingDirective,
+) {
+    let durable_open = match state_response {
+        ResponseType::DurableOpen((_, _, durable_open)) => durable_open,
+        _ => panic!("Invalid ResponseType."),
+    };
+
+    let mutations = match strategy {
+        FuzzingStrategy::Predefined => sample_durable_reconnect_mutations(),
+        _ => Vec::new(),
+    };
+    let credentials = if mutations.contains(&DurableReconnectMutation::DifferentUser) {
+        sample_reconnect_credentials(&context.credentials)
+    } else {
+        context.credentials.clone()
+    };
+    let (session_id, tree_id) =
+        match connect::reconnect_after_connection_loss(stream, context, credentials) {
+            Ok(ids) => ids,
+            Err(e) => {
+                println!("Failed to reconnect to the server: {}", e);
+                return;
+            }
+        };
+
+    let durable_reconnect_request = packets::prepare_durable_reconnect_packet(
+        Some(strategy),
+        session_id,
+        tree_id,
+        durable_open,
+        &mutations,
+    );
+
+    write_request(stream, context, durable_reconnect_request, directive);
 }
 
 /// Prepares the fuzzed query info message and sends it wrapped in a TCP packet.
 pub fn send_fuzzed_query_info_request(
     stream: &mut TcpStream,
+    context: &mut ConnectionContext,
     state_response: &ResponseType,
     strategy: FuzzingStrategy,
+    directive: &FuzzingDirective,
 ) {
     let (session_id, tree_id, file_id) = match state_response {
         ResponseType::Create(ids) => ids,
@@ -290,16 +786,22 @@ pub fn send_fuzzed_query_info_request(
         file_id.clone(),
     );
 
-    if stream.write_all(&query_info_request[..]).is_err() {
-        println!("Reset Connection.");
+    write_request(stream, context, query_info_request.clone(), directive);
+    match connect::receive_response(stream, context) {
+        Ok(response) => {
+            connect::report_potentially_leaked_query_info_bytes(&query_info_request, &response)
+        }
+        Err(e) => println!("Failed to receive Query Info response: {}", e),
     }
 }
 
 /// Prepares the fuzzed close message and sends it wrapped in a TCP packet.
 pub fn send_fuzzed_close_request(
     stream: &mut TcpStream,
+    context: &mut ConnectionContext,
     state_response: &ResponseType,
     strategy: FuzzingStrategy,
+    directive: &FuzzingDirective,
 ) {
     let (session_id, tree_id, file_id) = match state_response {
         ResponseType::Create(ids) => ids,
@@ -313,18 +815,211 @@ pub fn send_fuzzed_close_request(
         file_id.clone(),
     );
 
-    if stream.write_all(&close_request[..]).is_err() {
-        println!("Reset Connection.");
+    write_request(stream, context, close_request, directive);
+}
+
+/// Prepares the fuzzed lock message and sends it wrapped in a TCP packet.
+pub fn send_fuzzed_lock_request(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    state_response: &ResponseType,
+    strategy: FuzzingStrategy,
+    directive: &FuzzingDirective,
+) {
+    let (session_id, tree_id, file_id) = match state_response {
+        ResponseType::Create(ids) => ids,
+        _ => panic!("Invalid ResponseType."),
+    };
+
+    let lock_request: Vec<u8> = packets::prepare_lock_packet(
+        Some(strategy),
+        session_id.clone(),
+        tree_id.clone(),
+        file_id.clone(),
+    );
+
+    write_request(stream, context, lock_request, directive);
+}
+
+/// Prepares the fuzzed flush message and sends it wrapped in a TCP packet.
+pub fn send_fuzzed_flush_request(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    state_response: &ResponseType,
+    strategy: FuzzingStrategy,
+    directive: &FuzzingDirective,
+) {
+    let (session_id, tree_id, file_id) = match state_response {
+        ResponseType::Create(ids) => ids,
+        _ => panic!("Invalid ResponseType."),
+    };
+
+    let flush_request: Vec<u8> = packets::prepare_flush_packet(
+        Some(strategy),
+        session_id.clone(),
+        tree_id.clone(),
+        file_id.clone(),
+    );
+
+    write_request(stream, context, flush_request, directive);
+}
+
+/// Prepares the fuzzed cancel message and sends it wrapped in a TCP packet.
+/// If the state provides a tree connect, a change notify for the root directory of the share
+/// is left pending and the cancel targets it by the message id of its request or by the async id
+/// of its interim response. Otherwise, or if sampled so, the cancel targets the next message id
+/// of the connection context, which has not been allocated to a request yet.
+pub fn send_fuzzed_cancel_request(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    state_response: &ResponseType,
+    strategy: FuzzingStrategy,
+    directive: &FuzzingDirective,
+) {
+    let (session_id, tree_id) = match state_response {
+        ResponseType::Initial | ResponseType::Negotiate => (vec![0; 8], None),
+        ResponseType::SessionSetupNeg((_, session_id)) => (session_id.clone(), None),
+        ResponseType::SessionSetupAuth(session_id) => (session_id.clone(), None),
+        ResponseType::TreeConnect((session_id, tree_id)) => (session_id.clone(), Some(tree_id)),
+        ResponseType::Create((session_id, tree_id, _)) => (session_id.clone(), Some(tree_id)),
+        ResponseType::Close((session_id, tree_id)) => (session_id.clone(), Some(tree_id)),
+        ResponseType::DurableOpen((session_id, tree_id, _)) => (session_id.clone(), Some(tree_id)),
+    };
+    let pending_change_notify = tree_id.and_then(|tree_id| {
+        connect::pend_change_notify(stream, context, session_id.clone(), tree_id.clone())
+    });
+
+    let cancel_request: Vec<u8> = match (
+        &pending_change_notify,
+        sample_cancel_target(pending_change_notify.is_some()),
+    ) {
+        (Some((_, message_id)), CancelTarget::PendingMessageId) => {
+            packets::prepare_cancel_packet(Some(strategy), session_id, *message_id)
+        }
+        (Some((async_id, message_id)), CancelTarget::PendingAsyncId) => {
+            packets::prepare_async_cancel_packet(
+                Some(strategy),
+                session_id,
+                async_id.clone(),
+                *message_id,
+            )
+        }
+        _ => packets::prepare_cancel_packet(Some(strategy), session_id, context.next_message_id),
+    };
+
+    write_request(stream, context, cancel_request, directive);
+    if let Some((async_id, _)) = pending_change_notify {
+        connect::await_async_completion(stream, context, &async_id);
+    }
+}
+
+/// Opens the root directory of the share, sends the fuzzed change notify message for it
+/// and races the pending notify with a set info, close or logoff request.
+pub fn send_fuzzed_change_notify_request(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    state_response: &ResponseType,
+    strategy: FuzzingStrategy,
+    directive: &FuzzingDirective,
+) {
+    let (session_id, tree_id) = match state_response {
+        ResponseType::TreeConnect(ids) => ids,
+        _ => panic!("Invalid ResponseType."),
+    };
+
+    let directory_create_response = connect::send_directory_create_request_and_get_response(
+        stream,
+        context,
+        session_id.clone(),
+        tree_id.clone(),
+    );
+    if directory_create_response.len() < 4 + HEADER_LENGTH + 88 {
+        println!("Failed to open the directory.");
+        return;
+    }
+    let file_id =
+        decode_create_response_body(directory_create_response[4 + HEADER_LENGTH..].to_vec())
+            .file_id;
+
+    let change_notify_request = packets::prepare_change_notify_packet(
+        Some(strategy),
+        session_id.clone(),
+        tree_id.clone(),
+        file_id.clone(),
+    );
+
+    write_request(stream, context, change_notify_request, directive);
+    if let Some(async_id) = connect::receive_change_notify_interim_response(stream, context) {
+        connect::race_pending_change_notify(
+            stream,
+            context,
+            session_id.clone(),
+            tree_id.clone(),
+            file_id,
+            &async_id,
+        );
+    }
+}
+
+/// Prepares the fuzzed compound message that operates on the default file
+/// and sends it wrapped in a single TCP packet.
+pub fn send_fuzzed_compound_request(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    state_response: &ResponseType,
+    strategy: FuzzingStrategy,
+    directive: &FuzzingDirective,
+) {
+    let (session_id, tree_id) = match state_response {
+        ResponseType::TreeConnect(ids) => ids,
+        _ => panic!("Invalid ResponseType."),
+    };
+
+    let compound_request: Vec<u8> =
+        packets::prepare_compound_packet(Some(strategy), session_id.clone(), tree_id.clone());
+
+    write_request(stream, context, compound_request, directive);
+}
+
+/// Provokes an oplock break by opening the same file from a second connection
+/// and sends the fuzzed acknowledgment of the break notification.
+pub fn send_fuzzed_oplock_break_acknowledgment(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    state_response: &ResponseType,
+    strategy: FuzzingStrategy,
+    directive: &FuzzingDirective,
+) {
+    let (session_id, tree_id) = match state_response {
+        ResponseType::TreeConnect(ids) => ids,
+        _ => panic!("Invalid ResponseType."),
+    };
+
+    if let Some(notification) =
+        connect::provoke_oplock_break(stream, context, session_id.clone(), tree_id.clone())
+    {
+        let oplock_break_request = packets::prepare_oplock_break_packet(
+            Some(strategy),
+            session_id.clone(),
+            tree_id.clone(),
+            notification,
+        );
+
+        write_request(stream, context, oplock_break_request, directive);
+        connect::receive_oplock_break_response(stream, context);
     }
 }
 
 /// Prepares the fuzzed echo message and sends it wrapped in a TCP packet.
-pub fn send_fuzzed_echo_request(stream: &mut TcpStream, fuzzing_strategy: FuzzingStrategy) {
+pub fn send_fuzzed_echo_request(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    fuzzing_strategy: FuzzingStrategy,
+    directive: &FuzzingDirective,
+) {
     let echo_request = packets::prepare_echo_packet(Some(fuzzing_strategy));
 
-    if stream.write_all(&echo_request[..]).is_err() {
-        println!("Reset Connection.");
-    }
+    write_request(stream, context, echo_request, directive);
 }
 
 /// Prints the help message.
@@ -336,7 +1031,7 @@ The Fuzzamba fuzzer can be called by the following command:
     OR
     carg run -- [flags]
 
-    NOTE: Each of the three flag types has to be provided!
+    NOTE: Each of the three flag types has to be provided! Optional flags follow the state.
           For now, a message has to be sent in the correct state.
           The state is indicated after each flag below as [STATE].
 
@@ -346,10 +1041,16 @@ The Fuzzamba fuzzer can be called by the following command:
             -sn | --session_setup_neg | --Session_setup_neg [negotiate]
             -sa | --session_setup_auth | --Session_setup_auth [session setup neg]
             -t | --tree_connect | --Tree_connect [session setup auth]
-            -cr | --create | --Create [tree connect]
-            -q | --query_info | --Query_info [create]
-            -cl | --close | --Close [create]
+            -cr | --create | --Create [tree connect, durable open]
+            -q | --query_info | --Query_info [create, durable reconnect]
+            -cl | --close | --Close [create, durable reconnect]
+            -l | --lock | --Lock [create, durable reconnect]
+            -f | --flush | --Flush [create, durable reconnect]
+            -cn | --change_notify | --Change_notify [tree connect]
+            -ob | --oplock_break | --Oplock_break [tree connect]
+            -co | --compound | --Compound [tree connect]
             -e | --echo | --Echo [any]
+            -ca | --cancel | --Cancel [any]
 
         fuzzing strategy:
             -pre | --predefined | --Predefined
@@ -364,6 +1065,76 @@ The Fuzzamba fuzzer can be called by the following command:
             -tree_state
             -create_state
             -close_state
+            -durable_open_state
+            -durable_reconnect_state
+
+        optional:
+            -async | --async | --Async [async id]
+                Sends the fuzzed message with the ASYNC_COMMAND flag and the given async id
+                (decimal or 0x-prefixed hex). Without an id or with "random", it is sampled.
+            -hf | --header_fuzzing | --Header_fuzzing [fuzzing strategy]
+                Fuzzes the SMB2 header of the fuzzed message with the given strategy,
+                independently of the strategy for the message body.
+            -sv | --sequence_violation | --Sequence_violation [exhaustion | duplicate | skip]
+                Violates the credit and message id sequencing of the fuzzed message by charging
+                more credits than granted, reusing the previous message id or skipping the
+                window of granted credits.
+            -bs | --bad_signature | --Bad_signature [zero | random | flip | unsigned]
+                Violates the signature of the fuzzed message by zeroing it, replacing it with
+                random bytes, flipping a bit or clearing the SIGNED flag.
+                Messages are signed once a session key is established.
+            -enc | --encrypt | --Encrypt [aes128ccm | aes128gcm | aes256ccm | aes256gcm]
+                Forces the given cipher on the session in place of the cipher the server
+                selected. By default, the session is encrypted with the negotiated cipher once
+                a session key is established. The fuzzed message is encrypted after it has been mutated.
+            -tf | --transform_fuzzing | --Transform_fuzzing [fuzzing strategy]
+                Fuzzes the transform header of the encrypted message with the given strategy
+                (original message size, flags, session id and nonce) before it is encrypted.
+            -pt | --preauth_tampering | --Preauth_tampering
+                Flips a random bit of every negotiate and session setup message before it is
+                added to the SHA-512 preauth integrity hash, so that the SMB 3.1.1 signing and
+                encryption keys are derived from a hash that diverges from the server's.
+            -cmp | --compress | --Compress [none | lznt1 | lz77 | lz77huffman | patternv1] [chained] [force]
+                Compresses the fuzzed message with the given algorithm after it has been signed
+                and before it is encrypted. The unchained transform keeps the SMB2 header
+                uncompressed, the chained transform sends leading and trailing runs of bytes
+                as Pattern_V1 payloads. The message is only compressed if the server negotiated
+                the algorithm (and chained compression), unless the compression is forced.
+            -cf | --compression_fuzzing | --Compression_fuzzing [fuzzing strategy]
+                Fuzzes the compression transform of the compressed message with the given
+                strategy (segment sizes, offsets, algorithms, payload chaining) and malforms
+                the compressed data specifically for its algorithm.
+            -cred | --credentials | --Credentials [domain\user] [password]
+                Authenticates the session setup with NTLMv2 for the given user and password.
+                The domain is optional and defaults to WORKGROUP. Once the authentication
+                succeeds, signing and encryption are established with the exported session key.
+            -id | --identity | --Identity [user | guest | anonymous]
+                Authenticates the session setup as the default user, as explicit Guest login or
+                anonymously with empty LM and NT responses. Sessions that the server flags as
+                guest or null session are neither signed nor encrypted.
+            -krb | --kerberos | --Kerberos [kdc address] [service principal]
+                Authenticates the session setup with Kerberos instead of NTLM. The ticket for the
+                service principal (e.g. cifs/server.domain.local) is obtained from the KDC (port 88
+                by default) for the user of the credentials, whose domain is the realm. The fuzzed
+                session setup negotiate message carries the AP-REQ, whose authenticator and ticket
+                fields are fuzzed structurally. Signing and encryption use the AP-REP subkey.
+            -ss | --session_scenario | --Session_scenario [reauth | binding | reconnect]
+                Sends the fuzzed session setup message (-sn or -sa) on the session established in the
+                session setup auth state. The session is re-authenticated on the same connection, or a
+                second connection binds to it with signed binding requests or reconnects with the
+                session as PreviousSessionId. The predefined strategy mutates the flags, the
+                PreviousSessionId, the session id, the channel, the security mode and the capabilities.
+                The scenario always authenticates with NTLM, also if Kerberos is configured.
+            -dh | --durable_handle | --Durable_handle [v1 | v2 | persistent]
+                Opens the file in the durable states with a DHnQ, DH2Q or persistent DH2Q context
+                and a lease (v2 by default). In the durable open state, the fuzzed create message
+                reclaims the handle with DHnC or DH2C after the connection was dropped and the
+                session re-established. The predefined strategy sends a wrong CreateGuid, a stale
+                FileId or a mismatched lease key, or reconnects as a different user.
+            -sf | --spnego_fuzzing | --Spnego_fuzzing [fuzzing strategy]
+                Fuzzes the SPNEGO token in the security buffer of the fuzzed session setup message
+                with the given strategy (mechanism OIDs, reqFlags, mechListMIC, DER lengths, tag
+                classes and nesting), independently of the NTLM message inside.
 "#
     );
 }
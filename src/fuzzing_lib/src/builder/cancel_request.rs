@@ -0,0 +1,42 @@
+use crate::smb2::{header, requests};
+
+/// Builds a cancel request for the outstanding sync request with the given message id.
+/// A cancel request does not consume a message id of its own, and its tree id is set to 0.
+pub fn build_cancel_request(
+    session_id: Vec<u8>,
+    message_id: u64,
+) -> (Option<header::SyncHeader>, Option<requests::cancel::Cancel>) {
+    (
+        Some(super::build_sync_header(
+            header::Commands::Cancel,
+            0,
+            0,
+            None,
+            Some(session_id),
+            message_id,
+        )),
+        Some(requests::cancel::Cancel::default()),
+    )
+}
+
+/// Builds a cancel request for the pending async operation with the given async id.
+pub fn build_async_cancel_request(
+    session_id: Vec<u8>,
+    async_id: Vec<u8>,
+    message_id: u64,
+) -> (
+    Option<header::AsyncHeader>,
+    Option<requests::cancel::Cancel>,
+) {
+    (
+        Some(super::build_async_header(
+            header::Commands::Cancel,
+            0,
+            0,
+            async_id,
+            Some(session_id),
+            message_id,
+        )),
+        Some(requests::cancel::Cancel::default()),
+    )
+}
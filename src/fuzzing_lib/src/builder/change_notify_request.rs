@@ -0,0 +1,52 @@
+use crate::smb2::{
+    header,
+    requests::{
+        self,
+        change_notify::{ChangeNotifyFlags, CompletionFilter},
+    },
+};
+
+pub const DEFAULT_OUTPUT_BUFFER_LENGTH: &[u8; 4] = b"\x00\x10\x00\x00";
+
+/// Builds a working default change notify request.
+pub fn build_default_change_notify_request(
+    tree_id: Vec<u8>,
+    session_id: Vec<u8>,
+    file_id: Vec<u8>,
+) -> (
+    Option<header::SyncHeader>,
+    Option<requests::change_notify::ChangeNotify>,
+) {
+    (
+        Some(super::build_sync_header(
+            header::Commands::ChangeNotify,
+            1,
+            7776,
+            Some(tree_id),
+            Some(session_id),
+            10,
+        )),
+        Some(build_default_change_notify_request_body(file_id)),
+    )
+}
+
+/// Builds a working default change notify request body that watches the whole
+/// directory tree for name, attribute and last write changes.
+pub fn build_default_change_notify_request_body(
+    file_id: Vec<u8>,
+) -> requests::change_notify::ChangeNotify {
+    let mut change_notify = requests::change_notify::ChangeNotify::default();
+
+    change_notify.flags = ChangeNotifyFlags::WatchTree.unpack_byte_code();
+    change_notify.output_buffer_length = DEFAULT_OUTPUT_BUFFER_LENGTH.to_vec();
+    change_notify.file_id = file_id;
+    change_notify.completion_filter =
+        CompletionFilter::return_sum_of_chosen_completion_filters(vec![
+            CompletionFilter::FileName,
+            CompletionFilter::DirName,
+            CompletionFilter::Attributes,
+            CompletionFilter::LastWrite,
+        ]);
+
+    change_notify
+}
@@ -0,0 +1,128 @@
+use crate::{
+    format::encoder::compound_encoder::chain_compound_entries,
+    smb2::{
+        header,
+        requests::{
+            self,
+            compound::{CompoundEntry, RELATED_FILE_ID},
+            RequestType,
+        },
+    },
+};
+
+use super::{
+    create_request::build_default_create_request_body,
+    read_request::build_default_read_request_body,
+};
+
+/// Builds a working default compound request that creates the default file,
+/// reads from it and closes it. The read and close are related operations
+/// that inherit the file id of the create.
+pub fn build_default_compound_request(tree_id: Vec<u8>, session_id: Vec<u8>) -> Vec<CompoundEntry> {
+    let mut close = requests::close::Close::default();
+    close.file_id = RELATED_FILE_ID.to_vec();
+
+    build_compound_request(
+        vec![
+            RequestType::Create(build_default_create_request_body()),
+            RequestType::Read(build_default_read_request_body(RELATED_FILE_ID.to_vec())),
+            RequestType::Close(close),
+        ],
+        vec![false, true, true],
+        tree_id,
+        session_id,
+    )
+}
+
+/// Builds a compound request from the given bodies. The entries are built with message id 0,
+/// since sequencing the request assigns the message ids of all entries.
+/// The entries with a true related flag are sent as related operations.
+pub fn build_compound_request(
+    bodies: Vec<RequestType>,
+    related: Vec<bool>,
+    tree_id: Vec<u8>,
+    session_id: Vec<u8>,
+) -> Vec<CompoundEntry> {
+    let entries = bodies
+        .into_iter()
+        .zip(related)
+        .map(|(body, is_related)| {
+            let mut header = super::build_sync_header(
+                map_request_type_to_command(&body),
+                1,
+                7936,
+                Some(tree_id.clone()),
+                Some(session_id.clone()),
+                0,
+            );
+            if is_related {
+                set_related_operations_flag(&mut header);
+            }
+            CompoundEntry::new(header, body)
+        })
+        .collect();
+
+    chain_compound_entries(entries)
+}
+
+/// Adds the SMB2_FLAGS_RELATED_OPERATIONS flag to the flags of the header.
+pub fn set_related_operations_flag(header: &mut header::SyncHeader) {
+    if let Some(flags) = header.generic.flags.first_mut() {
+        *flags |= header::Flags::RelatedOperations.unpack_byte_code()[0];
+    }
+}
+
+/// Maps the request type to the command of its header.
+pub fn map_request_type_to_command(request: &RequestType) -> header::Commands {
+    match request {
+        RequestType::Negotiate(_) => header::Commands::Negotiate,
+        RequestType::SessionSetupNeg(_) | RequestType::SessionSetupAuth(_) => {
+            header::Commands::SessionSetup
+        }
+        RequestType::TreeConnect(_) => header::Commands::TreeConnect,
+        RequestType::Create(_) => header::Commands::Create,
+        RequestType::QueryInfo(_) => header::Commands::QueryInfo,
+        RequestType::Close(_) => header::Commands::Close,
+        RequestType::Echo(_) => header::Commands::Echo,
+        RequestType::Lock(_) => header::Commands::Lock,
+        RequestType::Flush(_) => header::Commands::Flush,
+        RequestType::Cancel(_) => header::Commands::Cancel,
+        RequestType::ChangeNotify(_) => header::Commands::ChangeNotify,
+        RequestType::SetInfo(_) => header::Commands::SetInfo,
+        RequestType::Logoff(_) => header::Commands::Logoff,
+        RequestType::OplockBreak(_) => header::Commands::OplockBreak,
+        RequestType::Read(_) => header::Commands::Read,
+        RequestType::Compound(entries) => entries
+            .first()
+            .map(|entry| map_request_type_to_command(&entry.body))
+            .unwrap_or(header::Commands::Echo),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{
+        decoder::split_compound_response, encoder::compound_encoder::serialize_compound_request,
+    };
+
+    #[test]
+    fn test_build_default_compound_request() {
+        let entries = build_default_compound_request(vec![1; 4], vec![2; 8]);
+
+        assert_eq!(3, entries.len());
+        assert_eq!(vec![0x10, 0, 0, 0], entries[0].header.generic.flags);
+        assert_eq!(vec![0x14, 0, 0, 0], entries[1].header.generic.flags);
+        assert_eq!(vec![0; 4], entries[2].header.generic.next_command);
+
+        let compound_request = serialize_compound_request(&entries);
+        let split_entries = split_compound_response(&compound_request[4..]);
+
+        assert_eq!(3, split_entries.len());
+        assert_eq!(0, split_entries[1].len() % 8);
+        assert_eq!(
+            header::Commands::Close.unpack_byte_code(),
+            split_entries[2][12..14].to_vec()
+        );
+    }
+}
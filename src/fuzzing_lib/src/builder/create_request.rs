@@ -1,16 +1,30 @@
-use crate::smb2::{
-    header,
-    helper_functions::fields::OplockLevel,
-    helper_functions::file_attributes::FileAttributes,
-    requests::{
-        self,
-        create::{
-            create_options::CreateOptions, file_access_mask::FileAccessMask, CreateDisposition,
-            ImpersonationLevel, ShareAccess,
+use crate::{
+    format::encoder::{
+        create_context_encoder::{serialize_create_context_data, serialize_create_contexts},
+        create_encoder::calculate_create_contexts_offset,
+    },
+    smb2::{
+        header,
+        helper_functions::fields::{LeaseState, OplockLevel},
+        helper_functions::file_attributes::FileAttributes,
+        requests::{
+            self,
+            create::{
+                create_context::{
+                    AppInstanceId, AppInstanceVersion, CreateContext, CreateContextData,
+                    CreateContextName, DurableHandleReconnectV2, DurableHandleRequestV2,
+                    FileFullEaInformation, RequestLease,
+                },
+                create_options::CreateOptions,
+                file_access_mask::FileAccessMask,
+                CreateDisposition, ImpersonationLevel, ShareAccess,
+            },
         },
     },
 };
 
+use super::set_info_request::DEFAULT_LAST_WRITE_TIME;
+
 pub const DEFAULT_BUFFER: &[u8; 28] =
     b"\x72\x00\x65\x00\x61\x00\x64\x00\x5f\x00\x74\x00\x65\x00\x73\x00\
 \x74\x00\x2e\x00\x74\x00\x78\x00\x74\x00\x00\x00";
@@ -66,6 +80,174 @@ pub fn build_default_create_request_body() -> requests::create::Create {
     create
 }
 
+/// Builds a create request that opens the default file with a batch oplock.
+/// Any further open of the file from another connection breaks the oplock.
+pub fn build_oplock_create_request(
+    tree_id: Vec<u8>,
+    session_id: Vec<u8>,
+) -> (Option<header::SyncHeader>, Option<requests::create::Create>) {
+    let mut create = build_default_create_request_body();
+    create.requested_oplock_level = OplockLevel::Batch.unpack_byte_code();
+
+    (
+        Some(super::build_sync_header(
+            header::Commands::Create,
+            1,
+            7968,
+            Some(tree_id),
+            Some(session_id),
+            4,
+        )),
+        Some(create),
+    )
+}
+
+/// The lease key of the default lease requests.
+pub const DEFAULT_LEASE_KEY: &[u8; 16] =
+    b"\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c\x0d\x0e\x0f\x10";
+
+/// The create guid of the default durable handle v2 requests.
+pub const DEFAULT_CREATE_GUID: &[u8; 16] =
+    b"\x10\x0f\x0e\x0d\x0c\x0b\x0a\x09\x08\x07\x06\x05\x04\x03\x02\x01";
+
+/// A self-relative security descriptor with a NULL DACL that grants full access to everyone.
+pub const DEFAULT_SECURITY_DESCRIPTOR: &[u8; 20] =
+    b"\x01\x00\x04\x80\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+
+/// Builds valid default data for the create context with the given name.
+pub fn build_default_create_context_data(name: CreateContextName) -> CreateContextData {
+    match name {
+        CreateContextName::EaBuffer => {
+            CreateContextData::EaBuffer(vec![FileFullEaInformation::from_name_and_value(
+                "FUZZ",
+                b"smb2".to_vec(),
+            )])
+        }
+        CreateContextName::SdBuffer => {
+            CreateContextData::SdBuffer(DEFAULT_SECURITY_DESCRIPTOR.to_vec())
+        }
+        CreateContextName::DurableHandleRequest => {
+            CreateContextData::DurableHandleRequest(vec![0; 16])
+        }
+        CreateContextName::DurableHandleReconnect => {
+            CreateContextData::DurableHandleReconnect(vec![0; 16])
+        }
+        CreateContextName::AllocationSize => {
+            CreateContextData::AllocationSize(4096u64.to_le_bytes().to_vec())
+        }
+        CreateContextName::QueryMaximalAccessRequest => {
+            CreateContextData::QueryMaximalAccessRequest(Vec::new())
+        }
+        CreateContextName::TimewarpToken => {
+            CreateContextData::TimewarpToken(DEFAULT_LAST_WRITE_TIME.to_le_bytes().to_vec())
+        }
+        CreateContextName::QueryOnDiskId => CreateContextData::QueryOnDiskId,
+        CreateContextName::RequestLease => {
+            let mut request_lease = RequestLease::default();
+            request_lease.lease_key = DEFAULT_LEASE_KEY.to_vec();
+            request_lease.lease_state = LeaseState::return_sum_of_chosen_lease_states(vec![
+                LeaseState::ReadCaching,
+                LeaseState::HandleCaching,
+            ]);
+
+            CreateContextData::RequestLease(request_lease)
+        }
+        CreateContextName::DurableHandleRequestV2 => {
+            let mut durable_request = DurableHandleRequestV2::default();
+            durable_request.create_guid = DEFAULT_CREATE_GUID.to_vec();
+
+            CreateContextData::DurableHandleRequestV2(durable_request)
+        }
+        CreateContextName::DurableHandleReconnectV2 => {
+            let mut durable_reconnect = DurableHandleReconnectV2::default();
+            durable_reconnect.file_id = vec![0; 16];
+            durable_reconnect.create_guid = DEFAULT_CREATE_GUID.to_vec();
+
+            CreateContextData::DurableHandleReconnectV2(durable_reconnect)
+        }
+        CreateContextName::AppInstanceId => {
+            let mut app_instance_id = AppInstanceId::default();
+            app_instance_id.app_instance_id = DEFAULT_CREATE_GUID.to_vec();
+
+            CreateContextData::AppInstanceId(app_instance_id)
+        }
+        CreateContextName::AppInstanceVersion => {
+            let mut app_instance_version = AppInstanceVersion::default();
+            app_instance_version.app_instance_version_high = 1u64.to_le_bytes().to_vec();
+            app_instance_version.app_instance_version_low = vec![0; 8];
+
+            CreateContextData::AppInstanceVersion(app_instance_version)
+        }
+        CreateContextName::Posix => CreateContextData::Posix(0o644u32.to_le_bytes().to_vec()),
+    }
+}
+
+/// Builds the create context that carries the given typed data.
+pub fn build_create_context(data: &CreateContextData) -> CreateContext {
+    CreateContext::from_name_and_data(data.name(), serialize_create_context_data(data))
+}
+
+/// Appends the create contexts to the create request and sets
+/// the create contexts offset and length accordingly.
+pub fn attach_create_contexts(create: &mut requests::create::Create, contexts: Vec<CreateContext>) {
+    if contexts.is_empty() {
+        create.create_contexts_offset = vec![0; 4];
+        create.create_contexts_length = vec![0; 4];
+    } else {
+        create.create_contexts_offset = (calculate_create_contexts_offset(create.buffer.len())
+            as u32)
+            .to_le_bytes()
+            .to_vec();
+        create.create_contexts_length = (serialize_create_contexts(&contexts).len() as u32)
+            .to_le_bytes()
+            .to_vec();
+    }
+    create.create_contexts = contexts;
+}
+
+/// Builds a create request that opens the root directory of the share.
+/// The directory handle is needed for requests like change notify that are only valid on directories.
+pub fn build_directory_create_request(
+    tree_id: Vec<u8>,
+    session_id: Vec<u8>,
+) -> (Option<header::SyncHeader>, Option<requests::create::Create>) {
+    (
+        Some(super::build_sync_header(
+            header::Commands::Create,
+            1,
+            7968,
+            Some(tree_id),
+            Some(session_id),
+            4,
+        )),
+        Some(build_directory_create_request_body()),
+    )
+}
+
+/// Builds a create request body that opens the root directory of the share
+/// with the permission to list its contents and change its attributes.
+pub fn build_directory_create_request_body() -> requests::create::Create {
+    let mut create = build_default_create_request_body();
+
+    create.desired_access = FileAccessMask::return_sum_of_chosen_file_access_masks(vec![
+        FileAccessMask::ReadData,
+        FileAccessMask::ReadAttributes,
+        FileAccessMask::WriteAttributes,
+        FileAccessMask::Synchronize,
+    ]);
+    create.share_access = ShareAccess::return_sum_of_chosen_share_access(vec![
+        ShareAccess::ShareRead,
+        ShareAccess::ShareWrite,
+        ShareAccess::ShareDelete,
+    ]);
+    create.create_options =
+        CreateOptions::return_sum_of_chosen_create_options(vec![CreateOptions::DirectoryFile]);
+    create.name_length = vec![0; 2];
+    create.buffer = vec![0];
+
+    create
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -89,4 +271,20 @@ mod tests {
         assert_eq!(vec![0; 4], body.create_contexts_length);
         assert_eq!(DEFAULT_BUFFER.to_vec(), body.buffer,)
     }
+
+    #[test]
+    fn test_attach_create_contexts() {
+        let mut body = build_default_create_request_body();
+        let create_context = build_create_context(&build_default_create_context_data(
+            CreateContextName::RequestLease,
+        ));
+        attach_create_contexts(&mut body, vec![create_context]);
+
+        assert_eq!(b"\x98\x00\x00\x00".to_vec(), body.create_contexts_offset);
+        assert_eq!(b"\x38\x00\x00\x00".to_vec(), body.create_contexts_length);
+        assert_eq!(
+            b"\x20\x00\x00\x00".to_vec(),
+            body.create_contexts[0].data_length
+        );
+    }
 }
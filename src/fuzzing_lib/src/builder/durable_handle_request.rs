@@ -0,0 +1,193 @@
+//! Builds the create requests that open a file with a durable or persistent handle and
+//! reclaim the handle after the connection was lost. The durable open is protected by a
+//! read and handle caching lease, which the reclaim presents with the same lease key.
+
+use super::create_request::{
+    attach_create_contexts, build_create_context, build_default_create_context_data,
+    build_default_create_request_body,
+};
+use crate::format::encoder::create_context_encoder::chain_create_contexts;
+use crate::fuzzer::durable_handle_fuzzer::DurableHandleVersion;
+use crate::smb2::{
+    header,
+    requests::{
+        self,
+        create::create_context::{
+            CreateContext, CreateContextData, CreateContextName, DurableHandleReconnectV2,
+            DurableHandleRequestV2,
+        },
+    },
+};
+
+/// The SMB2_DHANDLE_FLAG_PERSISTENT flag of the durable handle v2 contexts.
+pub const DHANDLE_FLAG_PERSISTENT: u32 = 0x00000002;
+
+/// The timeout in milliseconds for which the server reserves the durable handle v2.
+pub const DEFAULT_DURABLE_TIMEOUT: u32 = 60000;
+
+/// The durable open that the client reclaims after the connection was lost.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DurableOpen {
+    /// The version of the durable handle that was requested.
+    pub version: DurableHandleVersion,
+    /// The file id the server returned for the durable open.
+    pub file_id: Vec<u8>,
+    /// The create guid that identifies the create request of a durable handle v2.
+    pub create_guid: Vec<u8>,
+    /// The key of the lease that protects the durable open.
+    pub lease_key: Vec<u8>,
+}
+
+impl DurableOpen {
+    /// Creates a new durable open of the given version with a random create guid and lease key,
+    /// so that the opens of successive runs do not collide on the server.
+    pub fn new(version: DurableHandleVersion) -> Self {
+        DurableOpen {
+            version,
+            file_id: vec![0; 16],
+            create_guid: rand::random::<[u8; 16]>().to_vec(),
+            lease_key: rand::random::<[u8; 16]>().to_vec(),
+        }
+    }
+}
+
+/// Builds a create request that opens the default file with a durable handle of the version of the open.
+pub fn build_durable_create_request(
+    tree_id: Vec<u8>,
+    session_id: Vec<u8>,
+    durable_open: &DurableOpen,
+) -> (Option<header::SyncHeader>, Option<requests::create::Create>) {
+    let durable_request = match durable_open.version {
+        DurableHandleVersion::V1 => CreateContextData::DurableHandleRequest(vec![0; 16]),
+        DurableHandleVersion::V2 | DurableHandleVersion::Persistent => {
+            let mut durable_request = DurableHandleRequestV2::default();
+            durable_request.timeout = DEFAULT_DURABLE_TIMEOUT.to_le_bytes().to_vec();
+            if durable_open.version == DurableHandleVersion::Persistent {
+                durable_request.flags = DHANDLE_FLAG_PERSISTENT.to_le_bytes().to_vec();
+            }
+            durable_request.create_guid = durable_open.create_guid.clone();
+
+            CreateContextData::DurableHandleRequestV2(durable_request)
+        }
+    };
+
+    build_create_request_with_contexts(
+        tree_id,
+        session_id,
+        vec![
+            build_lease_context(&durable_open.lease_key),
+            build_create_context(&durable_request),
+        ],
+    )
+}
+
+/// Builds a create request that reclaims the durable open with the reconnect context of its version.
+pub fn build_durable_reconnect_request(
+    tree_id: Vec<u8>,
+    session_id: Vec<u8>,
+    durable_open: &DurableOpen,
+) -> (Option<header::SyncHeader>, Option<requests::create::Create>) {
+    build_create_request_with_contexts(
+        tree_id,
+        session_id,
+        build_durable_reconnect_contexts(durable_open),
+    )
+}
+
+/// Builds the lease and the reconnect context with which the durable open is reclaimed.
+pub fn build_durable_reconnect_contexts(durable_open: &DurableOpen) -> Vec<CreateContext> {
+    vec![
+        build_lease_context(&durable_open.lease_key),
+        build_create_context(&build_durable_reconnect_context_data(durable_open)),
+    ]
+}
+
+/// Builds the data of the reconnect context of the durable open.
+pub fn build_durable_reconnect_context_data(durable_open: &DurableOpen) -> CreateContextData {
+    match durable_open.version {
+        DurableHandleVersion::V1 => {
+            CreateContextData::DurableHandleReconnect(durable_open.file_id.clone())
+        }
+        DurableHandleVersion::V2 | DurableHandleVersion::Persistent => {
+            let mut durable_reconnect = DurableHandleReconnectV2::default();
+            durable_reconnect.file_id = durable_open.file_id.clone();
+            durable_reconnect.create_guid = durable_open.create_guid.clone();
+            if durable_open.version == DurableHandleVersion::Persistent {
+                durable_reconnect.flags = DHANDLE_FLAG_PERSISTENT.to_le_bytes().to_vec();
+            }
+
+            CreateContextData::DurableHandleReconnectV2(durable_reconnect)
+        }
+    }
+}
+
+/// Builds the read and handle caching lease request with the given lease key.
+pub fn build_lease_context(lease_key: &[u8]) -> CreateContext {
+    match build_default_create_context_data(CreateContextName::RequestLease) {
+        CreateContextData::RequestLease(mut request_lease) => {
+            request_lease.lease_key = lease_key.to_vec();
+            build_create_context(&CreateContextData::RequestLease(request_lease))
+        }
+        _ => unreachable!("The default lease context carries a lease request."),
+    }
+}
+
+/// Builds a create request for the default file with the given create contexts.
+pub fn build_create_request_with_contexts(
+    tree_id: Vec<u8>,
+    session_id: Vec<u8>,
+    create_contexts: Vec<CreateContext>,
+) -> (Option<header::SyncHeader>, Option<requests::create::Create>) {
+    let mut create = build_default_create_request_body();
+    attach_create_contexts(&mut create, chain_create_contexts(create_contexts));
+
+    (
+        Some(super::build_sync_header(
+            header::Commands::Create,
+            1,
+            7968,
+            Some(tree_id),
+            Some(session_id),
+            4,
+        )),
+        Some(create),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_durable_reconnect_request() {
+        let mut durable_open = DurableOpen::new(DurableHandleVersion::Persistent);
+        durable_open.file_id = vec![0x11; 16];
+
+        let (_, body) = build_durable_reconnect_request(vec![1; 4], vec![2; 8], &durable_open);
+        let body = body.unwrap();
+
+        assert_eq!(2, body.create_contexts.len());
+        assert_eq!(b"RqLs".to_vec(), body.create_contexts[0].name);
+        assert_eq!(
+            durable_open.lease_key,
+            body.create_contexts[0].data[..16].to_vec()
+        );
+        assert_eq!(b"DH2C".to_vec(), body.create_contexts[1].name);
+        assert_eq!(
+            [
+                vec![0x11; 16],
+                durable_open.create_guid.clone(),
+                vec![0x02, 0, 0, 0]
+            ]
+            .concat(),
+            body.create_contexts[1].data
+        );
+
+        durable_open.version = DurableHandleVersion::V1;
+        let (_, body) = build_durable_reconnect_request(vec![1; 4], vec![2; 8], &durable_open);
+        let body = body.unwrap();
+
+        assert_eq!(b"DHnC".to_vec(), body.create_contexts[1].name);
+        assert_eq!(vec![0x11; 16], body.create_contexts[1].data);
+    }
+}
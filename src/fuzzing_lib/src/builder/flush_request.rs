@@ -0,0 +1,23 @@
+use crate::smb2::{header, requests};
+
+/// Builds a working default flush request.
+pub fn build_default_flush_request(
+    tree_id: Vec<u8>,
+    session_id: Vec<u8>,
+    file_id: Vec<u8>,
+) -> (Option<header::SyncHeader>, Option<requests::flush::Flush>) {
+    let mut flush = requests::flush::Flush::default();
+    flush.file_id = file_id;
+
+    (
+        Some(super::build_sync_header(
+            header::Commands::Flush,
+            1,
+            7808,
+            Some(tree_id),
+            Some(session_id),
+            9,
+        )),
+        Some(flush),
+    )
+}
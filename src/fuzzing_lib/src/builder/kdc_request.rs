@@ -0,0 +1,219 @@
+//! Builds the AS-REQ and the TGS-REQ with which the client obtains the ticket granting ticket
+//! and the service ticket of the SMB server from the KDC.
+
+use rand::Rng;
+
+use crate::crypto::kerberos;
+use crate::format::encoder::kerberos_encoder::{
+    encode_ap_req, encode_authenticator, encode_bit_flags, encode_encrypted_data, encode_integer,
+    encode_kdc_req_body, encode_pa_enc_ts_enc,
+};
+use crate::fuzzer::create_random_byte_array_of_predefined_length;
+use crate::kerberos::{
+    current_kerberos_time, ApReq, Authenticator, Checksum, EncryptedData, EncryptionKey,
+    EncryptionType, KdcOptions, KdcReq, KdcReqBody, PaData, PaEncTsEnc, PrincipalName,
+    ServiceTicket, AS_REQ, KEY_USAGE_PA_ENC_TIMESTAMP, KEY_USAGE_TGS_REQ_AUTHENTICATOR,
+    KEY_USAGE_TGS_REQ_AUTH_CKSUM, NT_PRINCIPAL, NT_SRV_INST, PA_ENC_TIMESTAMP, PA_TGS_REQ, TGS_REQ,
+};
+
+/// The end time of the requested tickets, the latest time that fits into a signed 32-bit timestamp.
+pub const TICKET_TILL: &[u8; 15] = b"20370913024805Z";
+
+/// Builds the AS-REQ for the ticket granting ticket of the user in the realm.
+/// Without pre-authentication data, the KDC answers with the ETYPE-INFO2 of the user.
+pub fn build_as_req(user_name: &str, realm: &str, padata: Vec<PaData>, nonce: u32) -> KdcReq {
+    let mut request = KdcReq::default();
+    request.msg_type = vec![AS_REQ];
+    request.padata = padata;
+
+    let mut body = build_kdc_req_body(realm, nonce);
+    body.cname = Some(PrincipalName::from_string(NT_PRINCIPAL, user_name));
+    body.sname = Some(PrincipalName::from_string(
+        NT_SRV_INST,
+        &format!("krbtgt/{}", realm),
+    ));
+    request.req_body = body;
+
+    request
+}
+
+/// Builds the PA-ENC-TIMESTAMP, the current time encrypted with the long-term key of the user.
+pub fn build_pa_enc_timestamp(etype: &EncryptionType, key: &[u8]) -> PaData {
+    let (patimestamp, pausec) = current_kerberos_time();
+    let mut timestamp = PaEncTsEnc::default();
+    timestamp.patimestamp = patimestamp;
+    timestamp.pausec = encode_integer(pausec as i64);
+
+    let mut encrypted_timestamp = EncryptedData::default();
+    encrypted_timestamp.etype = etype.unpack_byte_code();
+    encrypted_timestamp.cipher = seal(
+        etype,
+        key,
+        KEY_USAGE_PA_ENC_TIMESTAMP,
+        &encode_pa_enc_ts_enc(&timestamp),
+    );
+
+    let mut padata = PaData::default();
+    padata.padata_type = vec![PA_ENC_TIMESTAMP];
+    padata.padata_value = encode_encrypted_data(&encrypted_timestamp);
+
+    padata
+}
+
+/// Builds the TGS-REQ for the ticket of the service principal. The ticket granting ticket is
+/// presented in the PA-TGS-REQ, whose authenticator carries the checksum of the request body.
+pub fn build_tgs_req(tgt: &ServiceTicket, service_principal: &str, nonce: u32) -> KdcReq {
+    let realm = String::from_utf8_lossy(&tgt.crealm).to_string();
+    let mut body = build_kdc_req_body(&realm, nonce);
+    body.sname = Some(PrincipalName::from_string(NT_SRV_INST, service_principal));
+
+    let etype = EncryptionType::map_byte_code_to_encryption_type(&tgt.session_key.key_type)
+        .unwrap_or(EncryptionType::Aes256CtsHmacSha196);
+    let mut cksum = Checksum::default();
+    cksum.cksum_type = etype.unpack_checksum_type();
+    cksum.checksum = kerberos::checksum(
+        &etype,
+        &tgt.session_key.key_value,
+        KEY_USAGE_TGS_REQ_AUTH_CKSUM,
+        &encode_kdc_req_body(&body),
+    );
+
+    let authenticator = build_authenticator(tgt, Some(cksum), None);
+    let mut ap_req = ApReq::default();
+    ap_req.ap_options = encode_bit_flags(0);
+    ap_req.ticket = tgt.ticket.clone();
+    ap_req.authenticator = seal_authenticator(
+        &authenticator,
+        &tgt.session_key,
+        KEY_USAGE_TGS_REQ_AUTHENTICATOR,
+    );
+
+    let mut padata = PaData::default();
+    padata.padata_type = vec![PA_TGS_REQ];
+    padata.padata_value = encode_ap_req(&ap_req);
+
+    let mut request = KdcReq::default();
+    request.msg_type = vec![TGS_REQ];
+    request.padata = vec![padata];
+    request.req_body = body;
+
+    request
+}
+
+/// Builds the request body that the AS-REQ and the TGS-REQ share. It requests a forwardable,
+/// renewable ticket in one of the supported encryption types.
+pub fn build_kdc_req_body(realm: &str, nonce: u32) -> KdcReqBody {
+    let mut body = KdcReqBody::default();
+    body.kdc_options = encode_bit_flags(
+        (KdcOptions::FORWARDABLE | KdcOptions::RENEWABLE | KdcOptions::CANONICALIZE).bits(),
+    );
+    body.realm = realm.as_bytes().to_vec();
+    body.till = TICKET_TILL.to_vec();
+    body.nonce = encode_integer(nonce as i64);
+    body.etype = EncryptionType::supported()
+        .iter()
+        .map(|etype| etype.unpack_byte_code())
+        .collect();
+
+    body
+}
+
+/// Builds the authenticator of the client of the ticket with the current time.
+pub fn build_authenticator(
+    ticket: &ServiceTicket,
+    cksum: Option<Checksum>,
+    subkey: Option<EncryptionKey>,
+) -> Authenticator {
+    let (ctime, cusec) = current_kerberos_time();
+    let mut authenticator = Authenticator::default();
+    authenticator.crealm = ticket.crealm.clone();
+    authenticator.cname = ticket.cname.clone();
+    authenticator.cksum = cksum;
+    authenticator.cusec = encode_integer(cusec as i64);
+    authenticator.ctime = ctime;
+    authenticator.subkey = subkey;
+
+    authenticator
+}
+
+/// Encrypts the authenticator with the session key of the ticket for the key usage.
+pub fn seal_authenticator(
+    authenticator: &Authenticator,
+    session_key: &EncryptionKey,
+    key_usage: u32,
+) -> EncryptedData {
+    let etype = EncryptionType::map_byte_code_to_encryption_type(&session_key.key_type)
+        .unwrap_or(EncryptionType::Aes256CtsHmacSha196);
+
+    let mut encrypted_authenticator = EncryptedData::default();
+    encrypted_authenticator.etype = etype.unpack_byte_code();
+    encrypted_authenticator.cipher = seal(
+        &etype,
+        &session_key.key_value,
+        key_usage,
+        &encode_authenticator(authenticator),
+    );
+
+    encrypted_authenticator
+}
+
+/// Encrypts the plaintext with a random confounder.
+pub fn seal(etype: &EncryptionType, key: &[u8], key_usage: u32, plaintext: &[u8]) -> Vec<u8> {
+    let confounder =
+        create_random_byte_array_of_predefined_length(kerberos::confounder_length(etype) as u32);
+
+    kerberos::encrypt(etype, key, key_usage, plaintext, &confounder)
+}
+
+/// Returns a random nonce of 31 bits, which every KDC accepts as positive UInt32.
+pub fn generate_nonce() -> u32 {
+    rand::thread_rng().gen_range(0..i32::MAX as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::decoder::kerberos_decoder::{decode_encrypted_data, decode_integer};
+    use crate::kerberos::Ticket;
+
+    #[test]
+    fn test_build_tgs_req() {
+        let tgt = ServiceTicket {
+            cname: PrincipalName::from_string(NT_PRINCIPAL, "alice"),
+            crealm: b"DOMAIN.LOCAL".to_vec(),
+            ticket: Ticket::default(),
+            session_key: EncryptionKey {
+                key_type: EncryptionType::Aes256CtsHmacSha196.unpack_byte_code(),
+                key_value: vec![0x11; 32],
+            },
+        };
+
+        let request = build_tgs_req(&tgt, "cifs/server.domain.local", 42);
+
+        assert_eq!(vec![TGS_REQ], request.msg_type);
+        assert_eq!(Some(42), decode_integer(&request.req_body.nonce));
+        assert_eq!(
+            Some(PrincipalName::from_string(
+                NT_SRV_INST,
+                "cifs/server.domain.local"
+            )),
+            request.req_body.sname
+        );
+        assert_eq!(vec![PA_TGS_REQ], request.padata[0].padata_type);
+    }
+
+    #[test]
+    fn test_build_pa_enc_timestamp() {
+        let key = vec![0x22; 32];
+        let padata = build_pa_enc_timestamp(&EncryptionType::Aes256CtsHmacSha196, &key);
+        let encrypted = decode_encrypted_data(&padata.padata_value).unwrap();
+
+        assert!(kerberos::decrypt(
+            &EncryptionType::Aes256CtsHmacSha196,
+            &key,
+            KEY_USAGE_PA_ENC_TIMESTAMP,
+            &encrypted.cipher
+        )
+        .is_some());
+    }
+}
@@ -0,0 +1,41 @@
+use crate::smb2::{
+    header,
+    requests::{
+        self,
+        lock::{LockElement, LockFlags},
+    },
+};
+
+/// Builds a working default lock request.
+pub fn build_default_lock_request(
+    tree_id: Vec<u8>,
+    session_id: Vec<u8>,
+    file_id: Vec<u8>,
+) -> (Option<header::SyncHeader>, Option<requests::lock::Lock>) {
+    (
+        Some(super::build_sync_header(
+            header::Commands::Lock,
+            1,
+            7840,
+            Some(tree_id),
+            Some(session_id),
+            8,
+        )),
+        Some(build_default_lock_request_body(file_id)),
+    )
+}
+
+/// Builds a working default lock request body that exclusively locks the first byte of the file.
+pub fn build_default_lock_request_body(file_id: Vec<u8>) -> requests::lock::Lock {
+    let mut lock = requests::lock::Lock::default();
+
+    lock.lock_count = vec![1, 0];
+    lock.file_id = file_id;
+    lock.locks.push(LockElement::from_range(
+        0,
+        1,
+        vec![LockFlags::ExclusiveLock, LockFlags::FailImmediately],
+    ));
+
+    lock
+}
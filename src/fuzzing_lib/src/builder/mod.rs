@@ -1,10 +1,22 @@
-use crate::smb2::{header, requests};
+use crate::smb2::{header, logoff, requests};
 
+pub mod cancel_request;
+pub mod change_notify_request;
+pub mod compound_request;
 pub mod create_request;
+pub mod durable_handle_request;
+pub mod flush_request;
+pub mod kdc_request;
+pub mod lock_request;
 pub mod negotiate_request;
+pub mod oplock_break_request;
 pub mod query_info_request;
+pub mod read_request;
+pub mod session_scenario_request;
 pub mod session_setup_authenticate_request;
+pub mod session_setup_kerberos_request;
 pub mod session_setup_negotiate_request;
+pub mod set_info_request;
 pub mod tree_connect_request;
 
 /// Builds a sync header with the corresponding parameters.
@@ -41,6 +53,30 @@ pub fn build_sync_header(
     header
 }
 
+/// Builds an async header with the corresponding parameters by converting the sync header.
+/// The async id identifies a pending operation on the server and replaces the
+/// reserved and tree id fields of the sync header.
+pub fn build_async_header(
+    command: header::Commands,
+    credit_charge: u16,
+    credit_request: u16,
+    async_id: Vec<u8>,
+    session_id: Option<Vec<u8>>,
+    message_id: u64,
+) -> header::AsyncHeader {
+    header::AsyncHeader::from_sync_header(
+        build_sync_header(
+            command,
+            credit_charge,
+            credit_request,
+            None,
+            session_id,
+            message_id,
+        ),
+        async_id,
+    )
+}
+
 /// Creates a complete create request.
 pub fn build_default_echo_request() -> (Option<header::SyncHeader>, Option<requests::echo::Echo>) {
     (
@@ -77,6 +113,23 @@ pub fn build_close_request(
     )
 }
 
+/// Creates a complete logoff request.
+pub fn build_logoff_request(
+    session_id: Vec<u8>,
+) -> (Option<header::SyncHeader>, Option<logoff::LogOff>) {
+    (
+        Some(build_sync_header(
+            header::Commands::Logoff,
+            1,
+            7712,
+            None,
+            Some(session_id),
+            12,
+        )),
+        Some(logoff::LogOff::default()),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -99,4 +152,22 @@ mod tests {
         assert_eq!(vec![1, 0, 0, 0, 0, 0, 0, 0], result.session_id);
         assert_eq!(vec![10, 0, 0, 0, 0, 0, 0, 0], result.generic.message_id);
     }
+
+    #[test]
+    fn test_build_async_header() {
+        let result = build_async_header(
+            header::Commands::Cancel,
+            0,
+            0,
+            vec![8, 0, 0, 0, 0, 0, 0, 0],
+            Some(vec![1, 0, 0, 0, 0, 0, 0, 0]),
+            10,
+        );
+
+        assert_eq!(vec![12, 0], result.generic.command);
+        assert_eq!(vec![0x12, 0, 0, 0], result.generic.flags);
+        assert_eq!(vec![8, 0, 0, 0, 0, 0, 0, 0], result.async_id);
+        assert_eq!(vec![1, 0, 0, 0, 0, 0, 0, 0], result.session_id);
+        assert_eq!(vec![10, 0, 0, 0, 0, 0, 0, 0], result.generic.message_id);
+    }
 }
@@ -1,3 +1,4 @@
+use crate::crypto::signing::SigningAlgorithm;
 use crate::smb2::{
     header,
     helper_functions::{
@@ -5,7 +6,7 @@ use crate::smb2::{
         negotiate_context::{
             Ciphers, CompressionAlgorithms, CompressionCapabilities, ContextType,
             EncryptionCapabilities, NegotiateContext, NetnameNegotiateContextId,
-            PreauthIntegrityCapabilities,
+            PreauthIntegrityCapabilities, SigningCapabilities,
         },
     },
     requests::{self, negotiate::Dialects},
@@ -66,6 +67,7 @@ pub fn build_default_negotiate_context_list() -> Vec<NegotiateContext> {
         build_default_preauthentication_context(),
         build_default_compression_context(),
         build_default_netname_context_id(),
+        build_default_signing_context(),
     ]
 }
 
@@ -154,6 +156,28 @@ pub fn build_default_netname_context_id() -> NegotiateContext {
     netname
 }
 
+/// Builds the working default signing context, which prefers the AES-CMAC
+/// signing algorithm of the SMB 3.x dialects over AES-GMAC and HMAC-SHA256.
+pub fn build_default_signing_context() -> NegotiateContext {
+    let mut signing = NegotiateContext::default();
+    let mut signing_caps = SigningCapabilities::default();
+
+    signing_caps.signing_algorithm_count = b"\x03\x00".to_vec();
+    signing_caps.signing_algorithms = vec![
+        SigningAlgorithm::AesCmac.unpack_byte_code(),
+        SigningAlgorithm::AesGmac.unpack_byte_code(),
+        SigningAlgorithm::HmacSha256.unpack_byte_code(),
+    ];
+
+    let signing_context = ContextType::SigningCapabilities(signing_caps);
+
+    signing.context_type = signing_context.unpack_byte_code();
+    signing.data_length = b"\x08\x00".to_vec();
+    signing.data = Some(signing_context);
+
+    signing
+}
+
 #[cfg(test)]
 mod tests {
     // use super::*;
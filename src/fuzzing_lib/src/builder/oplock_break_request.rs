@@ -0,0 +1,53 @@
+use crate::smb2::{
+    header,
+    requests::oplock_break::{LeaseBreakAcknowledgment, OplockBreak, OplockBreakAcknowledgment},
+    responses::oplock_break::BreakNotification,
+};
+
+/// Builds a working default acknowledgment for the given break notification.
+pub fn build_default_oplock_break_request(
+    tree_id: Vec<u8>,
+    session_id: Vec<u8>,
+    notification: BreakNotification,
+) -> (Option<header::SyncHeader>, Option<OplockBreak>) {
+    (
+        Some(super::build_sync_header(
+            header::Commands::OplockBreak,
+            1,
+            7680,
+            Some(tree_id),
+            Some(session_id),
+            13,
+        )),
+        Some(build_default_oplock_break_request_body(notification)),
+    )
+}
+
+/// Builds the acknowledgment body that accepts the oplock level or lease state
+/// the server breaks to. A notification that could not be decoded is answered
+/// with an oplock break acknowledgment to level none.
+pub fn build_default_oplock_break_request_body(notification: BreakNotification) -> OplockBreak {
+    match notification {
+        BreakNotification::Oplock(oplock_break) => {
+            let mut acknowledgment = OplockBreakAcknowledgment::default();
+            acknowledgment.oplock_level = oplock_break.oplock_level;
+            acknowledgment.file_id = oplock_break.file_id;
+
+            OplockBreak::Oplock(acknowledgment)
+        }
+        BreakNotification::Lease(lease_break) => {
+            let mut acknowledgment = LeaseBreakAcknowledgment::default();
+            acknowledgment.lease_key = lease_break.lease_key;
+            acknowledgment.lease_state = lease_break.new_lease_state;
+
+            OplockBreak::Lease(acknowledgment)
+        }
+        BreakNotification::Raw(_) => {
+            let mut acknowledgment = OplockBreakAcknowledgment::default();
+            acknowledgment.oplock_level = vec![0];
+            acknowledgment.file_id = vec![0; 16];
+
+            OplockBreak::Oplock(acknowledgment)
+        }
+    }
+}
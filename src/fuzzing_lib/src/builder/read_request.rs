@@ -0,0 +1,15 @@
+use crate::smb2::requests;
+
+/// The number of bytes the default read request reads from the beginning of the file.
+pub const DEFAULT_READ_LENGTH: u32 = 0x1000;
+
+/// Builds a working default read request body that reads from the beginning of the file.
+pub fn build_default_read_request_body(file_id: Vec<u8>) -> requests::read::Read {
+    let mut read = requests::read::Read::default();
+
+    read.length = DEFAULT_READ_LENGTH.to_le_bytes().to_vec();
+    read.offset = vec![0; 8];
+    read.file_id = file_id;
+
+    read
+}
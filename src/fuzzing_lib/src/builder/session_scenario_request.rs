@@ -0,0 +1,97 @@
+//! Builds the session setup requests of the stateful scenarios on an established session:
+//! the re-authentication and the binding carry the established session id in the header,
+//! the binding additionally sets SMB2_SESSION_FLAG_BINDING, and the reconnect names the
+//! established session as PreviousSessionId of a new session.
+
+use super::{
+    session_setup_authenticate_request::build_default_session_setup_authenticate_request,
+    session_setup_negotiate_request::build_default_session_setup_negotiate_request,
+};
+use crate::fuzzer::handshake::session_scenario_fuzzer::SessionScenario;
+use crate::ntlmssp::{challenge::Challenge, credentials::Credentials};
+use crate::smb2::{
+    header::SyncHeader,
+    requests::session_setup::{Flags, SessionSetup},
+};
+
+/// Builds the first session setup request of the scenario on the established session.
+pub fn build_session_scenario_negotiate_request(
+    scenario: &SessionScenario,
+    session_id: &[u8],
+) -> (Option<SyncHeader>, Option<SessionSetup>) {
+    let (mut header, mut body) = build_default_session_setup_negotiate_request();
+    if let (Some(header), Some(body)) = (header.as_mut(), body.as_mut()) {
+        apply_session_scenario(header, body, scenario, session_id);
+    }
+
+    (header, body)
+}
+
+/// Builds the second session setup request of the scenario on the established session, which
+/// authenticates with the credentials in the session the server answered the first request with.
+/// The session key of the authentication is returned alongside the request.
+pub fn build_session_scenario_authenticate_request(
+    scenario: &SessionScenario,
+    session_id: &[u8],
+    response_session_id: Vec<u8>,
+    server_challenge_struct: Challenge,
+    challenge_message: &[u8],
+    credentials: &Credentials,
+) -> (Option<SyncHeader>, Option<SessionSetup>, Vec<u8>) {
+    let (mut header, mut body, session_key) = build_default_session_setup_authenticate_request(
+        response_session_id,
+        server_challenge_struct,
+        challenge_message,
+        credentials,
+    );
+    if let (Some(header), Some(body)) = (header.as_mut(), body.as_mut()) {
+        apply_session_scenario(header, body, scenario, session_id);
+    }
+
+    (header, body, session_key)
+}
+
+/// Sets the fields of the session setup request that carry the scenario on the established session.
+pub fn apply_session_scenario(
+    header: &mut SyncHeader,
+    session_setup: &mut SessionSetup,
+    scenario: &SessionScenario,
+    session_id: &[u8],
+) {
+    match scenario {
+        SessionScenario::Reauthentication => {
+            header.session_id = session_id.to_vec();
+        }
+        SessionScenario::Binding => {
+            header.session_id = session_id.to_vec();
+            session_setup.flags = Flags::SessionSetupBinding.unpack_byte_code();
+        }
+        SessionScenario::Reconnect => {
+            session_setup.previous_session_id = session_id.to_vec();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_session_scenario_negotiate_request() {
+        let session_id = b"\x11\x00\x00\x28\x00\x30\x00\x00".to_vec();
+
+        let (header, body) =
+            build_session_scenario_negotiate_request(&SessionScenario::Binding, &session_id);
+        let (header, body) = (header.unwrap(), body.unwrap());
+        assert_eq!(session_id, header.session_id);
+        assert_eq!(vec![0x01], body.flags);
+        assert_eq!(vec![0; 8], body.previous_session_id);
+
+        let (header, body) =
+            build_session_scenario_negotiate_request(&SessionScenario::Reconnect, &session_id);
+        let (header, body) = (header.unwrap(), body.unwrap());
+        assert_eq!(vec![0; 8], header.session_id);
+        assert_eq!(vec![0x00], body.flags);
+        assert_eq!(session_id, body.previous_session_id);
+    }
+}
@@ -2,21 +2,30 @@ pub mod security_buffer;
 
 use crate::{
     format::encoder::security_blob_encoder::encode_security_authentication,
-    ntlmssp,
+    ntlmssp::{self, credentials::Credentials},
     smb2::{header, helper_functions::fields, requests},
 };
 
 const SECURITY_BUFFER_OFFSET: &[u8; 2] = b"\x58\x00";
-const SECURITY_BUFFER_LENGTH: &[u8; 2] = b"\x56\x01";
 
-/// Builds a working default session setup 2 request.
+/// Builds a working default session setup 2 request that authenticates with the credentials.
+/// The session key of the authentication is returned alongside the request.
 pub fn build_default_session_setup_authenticate_request(
     session_id: Vec<u8>,
     server_challenge_struct: ntlmssp::challenge::Challenge,
+    challenge_message: &[u8],
+    credentials: &Credentials,
 ) -> (
     Option<header::SyncHeader>,
     Option<requests::session_setup::SessionSetup>,
+    Vec<u8>,
 ) {
+    let (body, session_key) = build_default_session_setup_authenticate_request_body(
+        server_challenge_struct,
+        challenge_message,
+        credentials,
+    );
+
     (
         Some(super::build_sync_header(
             header::Commands::SessionSetup,
@@ -26,17 +35,24 @@ pub fn build_default_session_setup_authenticate_request(
             Some(session_id),
             2,
         )),
-        Some(build_default_session_setup_authenticate_request_body(
-            server_challenge_struct,
-        )),
+        Some(body),
+        session_key,
     )
 }
 
-/// Builds a working default session setup 2 request body.
+/// Builds a working default session setup 2 request body and returns it with the session key.
 pub fn build_default_session_setup_authenticate_request_body(
     server_challenge_struct: ntlmssp::challenge::Challenge,
-) -> requests::session_setup::SessionSetup {
+    challenge_message: &[u8],
+    credentials: &Credentials,
+) -> (requests::session_setup::SessionSetup, Vec<u8>) {
     let mut session_setup = requests::session_setup::SessionSetup::default();
+    let (security_buffer, session_key) =
+        security_buffer::build_session_setup_authenticate_request_security_buffer(
+            server_challenge_struct,
+            challenge_message,
+            credentials,
+        );
 
     session_setup.flags = requests::session_setup::Flags::Zero.unpack_byte_code();
     session_setup.security_mode = fields::SecurityMode::NegotiateSigningEnabled.unpack_byte_code(1);
@@ -44,15 +60,12 @@ pub fn build_default_session_setup_authenticate_request_body(
         requests::session_setup::Capabilities::GlobalCapDfs.unpack_byte_code();
     session_setup.channel = vec![0; 4];
     session_setup.security_buffer_offset = SECURITY_BUFFER_OFFSET.to_vec();
-    session_setup.security_buffer_length = SECURITY_BUFFER_LENGTH.to_vec();
     session_setup.previous_session_id = vec![0; 8];
-    session_setup.buffer = encode_security_authentication(
-        security_buffer::build_session_setup_authenticate_request_security_buffer(
-            server_challenge_struct,
-        ),
-    );
+    session_setup.buffer = encode_security_authentication(security_buffer);
+    session_setup.security_buffer_length =
+        (session_setup.buffer.len() as u16).to_le_bytes().to_vec();
 
-    session_setup
+    (session_setup, session_key)
 }
 
 #[cfg(test)]
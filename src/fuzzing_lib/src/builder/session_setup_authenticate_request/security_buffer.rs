@@ -1,176 +1,304 @@
 use ntlmssp::{AvId, AvPair};
 
 use crate::{
-    format::encoder::security_blob_encoder::encode_authenticate_blob,
+    crypto::ntlm,
+    format::encoder::security_blob_encoder::{encode_authenticate_blob, serialize_ntlm_challenge},
+    fuzzer::create_random_byte_array_of_predefined_length,
     gss,
-    ntlmssp::{self, authenticate::NtlmV2Response, negotiate_flags::NegotiateFlags, MessageType},
+    ntlmssp::{
+        self,
+        authenticate::{NtlmV2ClientChallenge, NtlmV2Response},
+        credentials::{Credentials, Identity},
+        negotiate_flags::NegotiateFlags,
+        MessageType,
+    },
 };
 
-const LM_CHALLENGE_RESPONSE_OFFSET: &[u8; 4] = b"\x58\x00\x00\x00";
-const NTLM_CHALLENGE_RESPONSE_OFFSET: &[u8; 4] = b"\x70\x00\x00\x00";
-const DEFAULT_GSS_PREFIX: &[u8; 16] =
-    b"\xa1\x82\x01\x52\x30\x82\x01\x4e\xa2\x82\x01\x4a\x04\x82\x01\x46";
+use super::super::session_setup_negotiate_request::initial_ntlm_negotiate_message;
+
+/// The offset of the payload of the AUTHENTICATE_MESSAGE, which directly follows the MIC.
+const PAYLOAD_OFFSET: usize = 0x58;
+/// The length of the LmChallengeResponse, which is Z(24) if the server sent a time stamp.
+const LM_CHALLENGE_RESPONSE_LENGTH: usize = 24;
+/// The MsvAvFlags value that indicates that the AUTHENTICATE_MESSAGE carries a MIC.
+const MIC_PRESENT: u32 = 0x00000002;
 
 /// Builds a working default session setup 2 request security buffer.
+/// Returns the security buffer and the exported session key, which is the SMB2 session key.
 pub fn build_session_setup_authenticate_request_security_buffer(
     server_challenge_struct: ntlmssp::challenge::Challenge,
-) -> gss::NegTokenResp {
+    challenge_message: &[u8],
+    credentials: &Credentials,
+) -> (gss::NegTokenResp, Vec<u8>) {
     let mut neg_token_response = gss::NegTokenResp::default();
-    let mut ntlm_header = ntlmssp::Header::default();
-    let authenticate = build_authenticate_message(server_challenge_struct);
+    let (authenticate, exported_session_key) =
+        build_authenticate_message(server_challenge_struct, challenge_message, credentials);
+
+    neg_token_response.response_token = encode_authenticate_blob(wrap_authenticate(authenticate));
 
+    (neg_token_response, exported_session_key)
+}
+
+/// Wraps the authenticate message in the NTLMSSP header.
+pub fn wrap_authenticate(authenticate: ntlmssp::authenticate::Authenticate) -> ntlmssp::Header {
+    let mut ntlm_header = ntlmssp::Header::default();
     let message_type = MessageType::Authenticate(Box::new(authenticate));
     ntlm_header.message_type = message_type.unpack_byte_code();
     ntlm_header.message = Some(message_type);
-    neg_token_response.state = DEFAULT_GSS_PREFIX.to_vec();
-    neg_token_response.response_token = encode_authenticate_blob(ntlm_header);
 
-    neg_token_response
+    ntlm_header
 }
 
-/// Builds a working default ntlmv2 authenticate message.
+/// Builds the ntlmv2 authenticate message for the credentials as specified in MS-NLMP 3.1.5.1.2.
+/// The NTProofStr proves the knowledge of the password, and with NTLMSSP_NEGOTIATE_KEY_EXCH a random
+/// session key is exchanged. The MIC covers the initial negotiate message, the challenge message
+/// and this message. Returns the message and the exported session key.
 pub fn build_authenticate_message(
     server_challenge_struct: ntlmssp::challenge::Challenge,
-) -> ntlmssp::authenticate::Authenticate {
-    let mut authenticate = ntlmssp::authenticate::Authenticate::default();
+    challenge_message: &[u8],
+    credentials: &Credentials,
+) -> (ntlmssp::authenticate::Authenticate, Vec<u8>) {
+    if credentials.identity == Identity::Anonymous {
+        return build_anonymous_authenticate_message(server_challenge_struct, credentials);
+    }
 
-    authenticate.payload.lm_challenge_response = vec![0; 24];
-    authenticate
-        .lm_challenge_response_fields
-        .lm_challenge_response_len = b"\x18\x00".to_vec();
-    authenticate
-        .lm_challenge_response_fields
-        .lm_challenge_response_max_len = b"\x18\x00".to_vec();
-    authenticate
-        .lm_challenge_response_fields
-        .lm_challenge_response_buffer_offset = LM_CHALLENGE_RESPONSE_OFFSET.to_vec();
-
-    let mut ntlmv2_response = build_ntlmv2_response(get_server_time(&server_challenge_struct));
-    authenticate
-        .nt_challenge_response_fields
-        .nt_challenge_response_len = calculate_ntlmv2_challenge_size(&ntlmv2_response);
-    authenticate
-        .nt_challenge_response_fields
-        .nt_challenge_response_max_len = authenticate
-        .nt_challenge_response_fields
-        .nt_challenge_response_len
-        .clone();
-    authenticate
-        .nt_challenge_response_fields
-        .nt_challenge_response_buffer_offset = NTLM_CHALLENGE_RESPONSE_OFFSET.to_vec();
-
-    authenticate.payload.domain_name =
-        b"\x57\x00\x4f\x00\x52\x00\x4b\x00\x47\x00\x52\x00\x4f\x00\x55\x00\x50\x00".to_vec();
-    authenticate.domain_name_fields.domain_name_len = b"\x12\x00".to_vec();
-    authenticate.domain_name_fields.domain_name_max_len = b"\x12\x00".to_vec();
-    authenticate.domain_name_fields.domain_name_buffer_offset = b"\x28\x01\x00\x00".to_vec();
-
-    authenticate.payload.user_name = b"\x74\x00\x6f\x00\x6d\x00".to_vec();
-    authenticate.user_name_fields.user_name_len = b"\x06\x00".to_vec();
-    authenticate.user_name_fields.user_name_max_len = b"\x06\x00".to_vec();
-    authenticate.user_name_fields.user_name_buffer_offset = b"\x3a\x01\x00\x00".to_vec();
-
-    authenticate.payload.workstation = b"\x54\x00\x4f\x00\x4d\x00".to_vec();
-    authenticate.workstation_fields.workstation_len = b"\x06\x00".to_vec();
-    authenticate.workstation_fields.workstation_max_len = b"\x06\x00".to_vec();
-    authenticate.workstation_fields.workstation_buffer_offset = b"\x40\x01\x00\x00".to_vec();
-
-    authenticate
-        .encrypted_random_session_key_fields
-        .encrypted_random_session_key_len = vec![0; 2];
-    authenticate
-        .encrypted_random_session_key_fields
-        .encrypted_random_session_key_max_len = vec![0; 2];
-    authenticate
-        .encrypted_random_session_key_fields
-        .encrypted_random_session_key_buffer_offset = b"\x46\x01\x00\x00".to_vec();
-
-    let flags =
-        { NegotiateFlags::NEG_NTLM | NegotiateFlags::NEG_UNICODE | NegotiateFlags::NEG_VERSION };
+    let mut authenticate = ntlmssp::authenticate::Authenticate::default();
 
+    let flags = select_negotiate_flags(&server_challenge_struct.negotiate_flags);
     authenticate.negotiate_flags = flags.bits().to_le_bytes().to_vec();
 
+    let mut ntlmv2_response = build_ntlmv2_response(
+        get_server_time(&server_challenge_struct),
+        &server_challenge_struct.payload.target_info,
+    );
+    ntlmv2_response
+        .ntlmv2_client_challenge
+        .challenge_from_client = create_random_byte_array_of_predefined_length(8);
+
+    let response_key_nt = ntlm::ntowfv2(
+        &credentials.password,
+        &credentials.user_name,
+        &credentials.domain_name,
+    );
+    ntlmv2_response.response = ntlm::compute_nt_proof_str(
+        &response_key_nt,
+        &server_challenge_struct.server_challenge,
+        &serialize_ntlm_challenge(ntlmv2_response.ntlmv2_client_challenge.clone()),
+    );
+    let session_base_key =
+        ntlm::compute_session_base_key(&response_key_nt, &ntlmv2_response.response);
+
+    let exported_session_key = if flags.contains(NegotiateFlags::NEG_KEY_EXCH) {
+        let exported_session_key =
+            create_random_byte_array_of_predefined_length(ntlm::NTLM_KEY_LENGTH as u32);
+        authenticate.payload.encrypted_random_session_key =
+            ntlm::encrypt_random_session_key(&session_base_key, &exported_session_key);
+        exported_session_key
+    } else {
+        session_base_key
+    };
+
+    authenticate.payload.lm_challenge_response = vec![0; LM_CHALLENGE_RESPONSE_LENGTH];
+    authenticate.payload.domain_name = ntlm::encode_utf16le(&credentials.domain_name);
+    authenticate.payload.user_name = ntlm::encode_utf16le(&credentials.user_name);
+    authenticate.payload.workstation = ntlm::encode_utf16le(&credentials.workstation);
+    authenticate.payload.nt_challenge_response = ntlmv2_response;
+    set_payload_fields(&mut authenticate);
+
     authenticate.version.product_major_version = vec![6];
     authenticate.version.product_minor_version = vec![1];
     authenticate.version.product_build = vec![0; 2];
-    authenticate.version.ntlm_revision_current = vec![15, 0, 0, 0];
+    authenticate.version.ntlm_revision_current = vec![15];
 
-    authenticate.mic = vec![0; 16];
+    authenticate.mic = vec![0; ntlm::NTLM_KEY_LENGTH];
+    authenticate.mic = ntlm::compute_mic(
+        &exported_session_key,
+        &initial_ntlm_negotiate_message(),
+        challenge_message,
+        &encode_authenticate_blob(wrap_authenticate(authenticate.clone())),
+    );
 
-    ntlmv2_response.response = vec![0; 16];
+    (authenticate, exported_session_key)
+}
 
-    authenticate.mic = vec![0; 16];
+/// Builds the anonymous authenticate message as specified in MS-NLMP 3.1.5.1.2: the LmChallengeResponse
+/// is Z(1), the NtChallengeResponse, the user and the domain name are empty and NTLMSSP_NEGOTIATE_ANONYMOUS
+/// is set. No session key is exchanged, so the session base key Z(16) is returned and the MIC stays zeroed.
+pub fn build_anonymous_authenticate_message(
+    server_challenge_struct: ntlmssp::challenge::Challenge,
+    credentials: &Credentials,
+) -> (ntlmssp::authenticate::Authenticate, Vec<u8>) {
+    let mut authenticate = ntlmssp::authenticate::Authenticate::default();
 
-    authenticate.payload.nt_challenge_response = ntlmv2_response;
+    let flags = (select_negotiate_flags(&server_challenge_struct.negotiate_flags)
+        - NegotiateFlags::NEG_KEY_EXCH)
+        | NegotiateFlags::ANONYMOUS;
+    authenticate.negotiate_flags = flags.bits().to_le_bytes().to_vec();
 
-    authenticate
-}
+    authenticate.payload.lm_challenge_response = vec![0];
+    authenticate.payload.nt_challenge_response = NtlmV2Response {
+        response: Vec::new(),
+        ntlmv2_client_challenge: NtlmV2ClientChallenge {
+            resp_type: Vec::new(),
+            hi_resp_type: Vec::new(),
+            reserved1: Vec::new(),
+            reserved2: Vec::new(),
+            time_stamp: Vec::new(),
+            challenge_from_client: Vec::new(),
+            reserved3: Vec::new(),
+            av_pairs: Vec::new(),
+            padding: Vec::new(),
+        },
+    };
+    authenticate.payload.workstation = ntlm::encode_utf16le(&credentials.workstation);
+    set_payload_fields(&mut authenticate);
 
-/// Calculates the size of the ntlmv2 challenge and returns it as a 2 byte little endian vector.
-pub fn calculate_ntlmv2_challenge_size(ntlmv2_response: &NtlmV2Response) -> Vec<u8> {
-    let mut size: u16 = 44;
-    for pair in ntlmv2_response.ntlmv2_client_challenge.av_pairs.iter() {
-        size += (4 + pair.value.len()) as u16;
-    }
+    authenticate.version.product_major_version = vec![6];
+    authenticate.version.product_minor_version = vec![1];
+    authenticate.version.product_build = vec![0; 2];
+    authenticate.version.ntlm_revision_current = vec![15];
+    authenticate.mic = vec![0; ntlm::NTLM_KEY_LENGTH];
 
-    size.to_le_bytes().to_vec()
+    (authenticate, vec![0; ntlm::NTLM_KEY_LENGTH])
 }
 
-/// Builds the NTLMv2 client challenge.
-pub fn build_ntlmv2_response(server_time_stamp: Vec<u8>) -> NtlmV2Response {
-    let mut response = NtlmV2Response::default();
-    response.ntlmv2_client_challenge.time_stamp = server_time_stamp.clone();
-    let mut nb_domain_name = AvPair::default();
-    nb_domain_name.av_id = Some(AvId::MsvAvNbDomainName);
-    nb_domain_name.av_len = b"\x16\x00".to_vec();
-    nb_domain_name.value =
-        b"\x52\x00\x41\x00\x53\x00\x50\x00\x42\x00\x45\x00\x52\x00\x52\x00\x59\x00\x50\x00\x49\x00"
-            .to_vec();
+/// Selects the negotiate flags of the authenticate message: the flags the server chose in the
+/// challenge message out of the ones the client offers, while Unicode, NTLM and the version are kept.
+pub fn select_negotiate_flags(challenge_flags: &[u8]) -> NegotiateFlags {
+    let mut flag_bytes = [0; 4];
+    let length = challenge_flags.len().min(4);
+    flag_bytes[..length].copy_from_slice(&challenge_flags[..length]);
+    let challenge_flags = NegotiateFlags::from_bits_truncate(u32::from_le_bytes(flag_bytes));
+
+    let offered_flags = NegotiateFlags::NEG_56
+        | NegotiateFlags::NEG_KEY_EXCH
+        | NegotiateFlags::NEG_128
+        | NegotiateFlags::NEG_TARGET_INFO
+        | NegotiateFlags::NEG_EXTENDED_SESSION_SEC
+        | NegotiateFlags::NEG_ALWAYS_SIGN
+        | NegotiateFlags::NEG_SIGN
+        | NegotiateFlags::REQ_TARGET;
+
+    (challenge_flags & offered_flags)
+        | NegotiateFlags::NEG_NTLM
+        | NegotiateFlags::NEG_UNICODE
+        | NegotiateFlags::NEG_VERSION
+}
 
-    let mut nb_computer_name = AvPair::default();
-    nb_computer_name.av_id = Some(AvId::MsvAvNbComputerName);
-    nb_computer_name.av_len = b"\x16\x00".to_vec();
-    nb_computer_name.value =
-        b"\x52\x00\x41\x00\x53\x00\x50\x00\x42\x00\x45\x00\x52\x00\x52\x00\x59\x00\x50\x00\x49\x00"
-            .to_vec();
+/// Sets the length and offset fields of the payload, which is laid out in the order
+/// LmChallengeResponse, NtChallengeResponse, DomainName, UserName, Workstation
+/// and EncryptedRandomSessionKey.
+pub fn set_payload_fields(authenticate: &mut ntlmssp::authenticate::Authenticate) {
+    let payload = &authenticate.payload;
+    let nt_challenge_response_length = payload.nt_challenge_response.response.len()
+        + serialize_ntlm_challenge(
+            payload
+                .nt_challenge_response
+                .ntlmv2_client_challenge
+                .clone(),
+        )
+        .len();
+    let lengths = [
+        payload.lm_challenge_response.len(),
+        nt_challenge_response_length,
+        payload.domain_name.len(),
+        payload.user_name.len(),
+        payload.workstation.len(),
+        payload.encrypted_random_session_key.len(),
+    ];
 
-    let mut dns_domain_name = AvPair::default();
-    dns_domain_name.av_id = Some(AvId::MsvAvDnsDomainName);
-    dns_domain_name.av_len = b"\x02\x00".to_vec();
-    dns_domain_name.value = vec![0; 2];
+    let mut fields: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+    let mut offset = PAYLOAD_OFFSET;
+    for length in lengths.iter() {
+        fields.push((
+            (*length as u16).to_le_bytes().to_vec(),
+            (offset as u32).to_le_bytes().to_vec(),
+        ));
+        offset += length;
+    }
 
-    let mut dns_computer_name = AvPair::default();
-    dns_computer_name.av_id = Some(AvId::MsvAvDnsComputerName);
-    dns_computer_name.av_len = b"\x16\x00".to_vec();
-    dns_computer_name.value =
-        b"\x72\x00\x61\x00\x73\x00\x70\x00\x62\x00\x65\x00\x72\x00\x72\x00\x79\x00\x70\x00\x69\x00"
-            .to_vec();
+    let lm = &mut authenticate.lm_challenge_response_fields;
+    lm.lm_challenge_response_len = fields[0].0.clone();
+    lm.lm_challenge_response_max_len = fields[0].0.clone();
+    lm.lm_challenge_response_buffer_offset = fields[0].1.clone();
+
+    let nt = &mut authenticate.nt_challenge_response_fields;
+    nt.nt_challenge_response_len = fields[1].0.clone();
+    nt.nt_challenge_response_max_len = fields[1].0.clone();
+    nt.nt_challenge_response_buffer_offset = fields[1].1.clone();
+
+    let domain = &mut authenticate.domain_name_fields;
+    domain.domain_name_len = fields[2].0.clone();
+    domain.domain_name_max_len = fields[2].0.clone();
+    domain.domain_name_buffer_offset = fields[2].1.clone();
+
+    let user = &mut authenticate.user_name_fields;
+    user.user_name_len = fields[3].0.clone();
+    user.user_name_max_len = fields[3].0.clone();
+    user.user_name_buffer_offset = fields[3].1.clone();
+
+    let workstation = &mut authenticate.workstation_fields;
+    workstation.workstation_len = fields[4].0.clone();
+    workstation.workstation_max_len = fields[4].0.clone();
+    workstation.workstation_buffer_offset = fields[4].1.clone();
+
+    let key = &mut authenticate.encrypted_random_session_key_fields;
+    key.encrypted_random_session_key_len = fields[5].0.clone();
+    key.encrypted_random_session_key_max_len = fields[5].0.clone();
+    key.encrypted_random_session_key_buffer_offset = fields[5].1.clone();
+}
 
-    let mut time_stamp = AvPair::default();
-    time_stamp.av_id = Some(AvId::MsvAvTimeStamp);
-    time_stamp.av_len = b"\x08\x00".to_vec();
-    time_stamp.value = server_time_stamp;
+/// Builds the NTLMv2 client challenge from the target info of the server. The AV pairs of the
+/// server are kept, MsvAvFlags announces the MIC and MsvAvTargetName names the cifs service
+/// of the server, before the list is terminated with MsvAvEOL.
+pub fn build_ntlmv2_response(server_time_stamp: Vec<u8>, target_info: &[AvPair]) -> NtlmV2Response {
+    let mut response = NtlmV2Response::default();
+    response.ntlmv2_client_challenge.time_stamp = server_time_stamp;
+
+    let mut av_flags: u32 = MIC_PRESENT;
+    let mut av_pairs: Vec<AvPair> = Vec::new();
+    for pair in target_info.iter() {
+        match pair.av_id {
+            Some(AvId::MsvAvEol) | Some(AvId::MsvAvTargetName) | None => {}
+            Some(AvId::MsvAvFlags) => {
+                let mut value = [0; 4];
+                let length = pair.value.len().min(4);
+                value[..length].copy_from_slice(&pair.value[..length]);
+                av_flags |= u32::from_le_bytes(value);
+            }
+            _ => av_pairs.push(pair.clone()),
+        }
+    }
 
-    let mut target = AvPair::default();
-    target.av_id = Some(AvId::MsvAvTargetName);
-    target.av_len = b"\x24\x00".to_vec();
-    target.value = b"\x63\x00\x69\x00\x66\x00\x73\x00\x2f\x00\x31\x00\x39\x00\x32\x00\x2e\x00\
-                     \x31\x00\x36\x00\x38\x00\x2e\x00\x30\x00\x2e\x00\x31\x00\x37\x00\x31\x00"
-        .to_vec();
+    let mut flags = AvPair::default();
+    flags.av_id = Some(AvId::MsvAvFlags);
+    flags.av_len = b"\x04\x00".to_vec();
+    flags.value = av_flags.to_le_bytes().to_vec();
+    av_pairs.push(flags);
+
+    let server_name = target_info
+        .iter()
+        .find(|pair| pair.av_id == Some(AvId::MsvAvDnsComputerName))
+        .or_else(|| {
+            target_info
+                .iter()
+                .find(|pair| pair.av_id == Some(AvId::MsvAvNbComputerName))
+        });
+    if let Some(server_name) = server_name {
+        let mut target = AvPair::default();
+        target.av_id = Some(AvId::MsvAvTargetName);
+        target.value = ntlm::encode_utf16le("cifs/");
+        target.value.extend_from_slice(&server_name.value);
+        target.av_len = (target.value.len() as u16).to_le_bytes().to_vec();
+        av_pairs.push(target);
+    }
 
     let mut eol = AvPair::default();
     eol.av_id = Some(AvId::MsvAvEol);
     eol.av_len = vec![0; 2];
+    av_pairs.push(eol);
 
-    response.ntlmv2_client_challenge.av_pairs = vec![
-        nb_domain_name,
-        nb_computer_name,
-        dns_domain_name,
-        dns_computer_name,
-        time_stamp,
-        target,
-        eol,
-    ];
+    response.ntlmv2_client_challenge.av_pairs = av_pairs;
 
     response
 }
@@ -185,3 +313,150 @@ pub fn get_server_time(challenge: &ntlmssp::challenge::Challenge) -> Vec<u8> {
 
     panic!("Missing timestamp from server challenge.");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::decoder::security_blob_decoder::{
+        decode_security_response, extract_ntlm_challenge_message,
+    };
+
+    fn build_challenge() -> (ntlmssp::challenge::Challenge, Vec<u8>) {
+        let security_response = b"\xa1\x81\xce\x30\x81\xcb\xa0\x03\x0a\x01\x01\xa1\x0c\x06\x0a\x2b\
+                                  \x06\x01\x04\x01\x82\x37\x02\x02\x0a\xa2\x81\xb5\x04\x81\xb2\x4e\
+                                  \x54\x4c\x4d\x53\x53\x50\x00\x02\x00\x00\x00\x16\x00\x16\x00\x38\
+                                  \x00\x00\x00\x15\x82\x8a\x62\x8d\x51\x0b\x30\x2d\x45\x71\xe0\x00\
+                                  \x00\x00\x00\x00\x00\x00\x00\x64\x00\x64\x00\x4e\x00\x00\x00\x06\
+                                  \x01\x00\x00\x00\x00\x00\x0f\x52\x00\x41\x00\x53\x00\x50\x00\x42\
+                                  \x00\x45\x00\x52\x00\x52\x00\x59\x00\x50\x00\x49\x00\x02\x00\x16\
+                                  \x00\x52\x00\x41\x00\x53\x00\x50\x00\x42\x00\x45\x00\x52\x00\x52\
+                                  \x00\x59\x00\x50\x00\x49\x00\x01\x00\x16\x00\x52\x00\x41\x00\x53\
+                                  \x00\x50\x00\x42\x00\x45\x00\x52\x00\x52\x00\x59\x00\x50\x00\x49\
+                                  \x00\x04\x00\x02\x00\x00\x00\x03\x00\x16\x00\x72\x00\x61\x00\x73\
+                                  \x00\x70\x00\x62\x00\x65\x00\x72\x00\x72\x00\x79\x00\x70\x00\x69\
+                                  \x00\x07\x00\x08\x00\x60\x16\xad\x6d\x47\x21\xd7\x01\x00\x00\x00\
+                                  \x00"
+            .to_vec();
+        let challenge = match decode_security_response(security_response.clone()).message {
+            Some(MessageType::Challenge(challenge)) => challenge,
+            _ => panic!("Invalid challenge message."),
+        };
+
+        (challenge, extract_ntlm_challenge_message(security_response))
+    }
+
+    #[test]
+    fn test_build_authenticate_message() {
+        let (challenge, challenge_message) = build_challenge();
+        let credentials = Credentials::map_string_to_credentials("RASPI\\tom", "secret");
+
+        let (authenticate, exported_session_key) =
+            build_authenticate_message(challenge.clone(), &challenge_message, &credentials);
+
+        let response_key_nt = ntlm::ntowfv2("secret", "tom", "RASPI");
+        let client_challenge_blob = serialize_ntlm_challenge(
+            authenticate
+                .payload
+                .nt_challenge_response
+                .ntlmv2_client_challenge
+                .clone(),
+        );
+        let nt_proof_str = ntlm::compute_nt_proof_str(
+            &response_key_nt,
+            &challenge.server_challenge,
+            &client_challenge_blob,
+        );
+        let session_base_key = ntlm::compute_session_base_key(&response_key_nt, &nt_proof_str);
+
+        assert_eq!(
+            nt_proof_str,
+            authenticate.payload.nt_challenge_response.response
+        );
+        assert_eq!(
+            exported_session_key,
+            ntlm::rc4(
+                &session_base_key,
+                &authenticate.payload.encrypted_random_session_key
+            )
+        );
+        assert_eq!(
+            b"\x58\x00\x00\x00".to_vec(),
+            authenticate
+                .lm_challenge_response_fields
+                .lm_challenge_response_buffer_offset
+        );
+        assert_eq!(
+            b"\x70\x00\x00\x00".to_vec(),
+            authenticate
+                .nt_challenge_response_fields
+                .nt_challenge_response_buffer_offset
+        );
+
+        let mut unprotected_authenticate = authenticate.clone();
+        unprotected_authenticate.mic = vec![0; 16];
+        assert_eq!(
+            ntlm::compute_mic(
+                &exported_session_key,
+                &initial_ntlm_negotiate_message(),
+                &challenge_message,
+                &encode_authenticate_blob(wrap_authenticate(unprotected_authenticate)),
+            ),
+            authenticate.mic
+        );
+    }
+
+    #[test]
+    fn test_build_anonymous_authenticate_message() {
+        let (challenge, challenge_message) = build_challenge();
+
+        let (authenticate, session_key) =
+            build_authenticate_message(challenge, &challenge_message, &Credentials::anonymous());
+        let flags = NegotiateFlags::from_bits_truncate(u32::from_le_bytes([
+            authenticate.negotiate_flags[0],
+            authenticate.negotiate_flags[1],
+            authenticate.negotiate_flags[2],
+            authenticate.negotiate_flags[3],
+        ]));
+
+        assert!(flags.contains(NegotiateFlags::ANONYMOUS));
+        assert!(!flags.contains(NegotiateFlags::NEG_KEY_EXCH));
+        assert_eq!(vec![0], authenticate.payload.lm_challenge_response);
+        assert_eq!(
+            b"\x00\x00".to_vec(),
+            authenticate
+                .nt_challenge_response_fields
+                .nt_challenge_response_len
+        );
+        assert_eq!(
+            b"\x00\x00".to_vec(),
+            authenticate.user_name_fields.user_name_len
+        );
+        assert_eq!(vec![0; 16], session_key);
+    }
+
+    #[test]
+    fn test_build_ntlmv2_response() {
+        let (challenge, _) = build_challenge();
+        let response = build_ntlmv2_response(vec![0; 8], &challenge.payload.target_info);
+        let av_ids: Vec<Option<AvId>> = response
+            .ntlmv2_client_challenge
+            .av_pairs
+            .iter()
+            .map(|pair| pair.av_id.clone())
+            .collect();
+
+        assert_eq!(
+            vec![
+                Some(AvId::MsvAvNbDomainName),
+                Some(AvId::MsvAvNbComputerName),
+                Some(AvId::MsvAvDnsDomainName),
+                Some(AvId::MsvAvDnsComputerName),
+                Some(AvId::MsvAvTimeStamp),
+                Some(AvId::MsvAvFlags),
+                Some(AvId::MsvAvTargetName),
+                Some(AvId::MsvAvEol),
+            ],
+            av_ids
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! Builds the session setup request that authenticates with Kerberos (MS-KILE 3.2.5.1):
+//! the AP-REQ with the service ticket of the SMB server is the mechToken of the SPNEGO
+//! NegTokenInit, so that the session is authenticated in a single round trip.
+
+use super::kdc_request::{build_authenticator, generate_nonce, seal_authenticator};
+use crate::crypto::kerberos;
+use crate::format::decoder::{
+    kerberos_decoder::{decode_ap_rep, decode_enc_ap_rep_part, decode_kerberos_gss_token},
+    spnego_decoder::decode_negotiation_token,
+};
+use crate::format::encoder::{
+    kerberos_encoder::{
+        encode_ap_req, encode_bit_flags, encode_integer, encode_kerberos_gss_token,
+    },
+    spnego_encoder::encode_neg_token_init,
+};
+use crate::fuzzer::create_random_byte_array_of_predefined_length;
+use crate::gss::{self, NegotiationToken, KERBEROS_OID, MS_KERBEROS_OID};
+use crate::kerberos::{
+    ApOptions, ApReq, Authenticator, Checksum, EncryptionKey, EncryptionType, GssFlags,
+    ServiceTicket, GSS_AP_REQ_TOKEN_ID, GSS_CHECKSUM_TYPE, KEY_USAGE_AP_REP_ENC_PART,
+    KEY_USAGE_AP_REQ_AUTHENTICATOR,
+};
+use crate::smb2::{header, helper_functions::fields, requests};
+
+const SECURITY_BUFFER_OFFSET: &[u8; 2] = b"\x58\x00";
+
+/// The length of the session key that SMB takes from the Kerberos key.
+const SMB_SESSION_KEY_LENGTH: usize = 16;
+
+/// Builds a working default session setup request that authenticates with the service ticket.
+/// The subkey of the authenticator is returned alongside the request.
+pub fn build_default_session_setup_kerberos_request(
+    service_ticket: &ServiceTicket,
+) -> (
+    Option<header::SyncHeader>,
+    Option<requests::session_setup::SessionSetup>,
+    EncryptionKey,
+) {
+    let (authenticator, subkey) = build_default_ap_req_authenticator(service_ticket);
+    let ap_req = build_ap_req(service_ticket, &authenticator);
+
+    (
+        Some(super::build_sync_header(
+            header::Commands::SessionSetup,
+            1,
+            8192,
+            None,
+            None,
+            1,
+        )),
+        Some(build_session_setup_kerberos_request_body(
+            build_kerberos_security_blob(&ap_req),
+        )),
+        subkey,
+    )
+}
+
+/// Builds the authenticator of the AP-REQ with a random subkey in the encryption type of the
+/// session key, the GSS-API checksum and a random sequence number. Returns it with the subkey.
+pub fn build_default_ap_req_authenticator(
+    service_ticket: &ServiceTicket,
+) -> (Authenticator, EncryptionKey) {
+    let etype =
+        EncryptionType::map_byte_code_to_encryption_type(&service_ticket.session_key.key_type)
+            .unwrap_or(EncryptionType::Aes256CtsHmacSha196);
+    let mut subkey = EncryptionKey::default();
+    subkey.key_type = etype.unpack_byte_code();
+    subkey.key_value = create_random_byte_array_of_predefined_length(etype.key_length() as u32);
+
+    let mut authenticator = build_authenticator(
+        service_ticket,
+        Some(build_gss_checksum(
+            GssFlags::MUTUAL
+                | GssFlags::REPLAY
+                | GssFlags::SEQUENCE
+                | GssFlags::CONF
+                | GssFlags::INTEG,
+        )),
+        Some(subkey.clone()),
+    );
+    authenticator.seq_number = encode_integer(generate_nonce() as i64);
+
+    (authenticator, subkey)
+}
+
+/// Builds the GSS-API checksum of the authenticator (RFC 4121 4.1.1): the length of the
+/// channel bindings, the MD5 hash of the channel bindings (zero without bindings) and the flags.
+pub fn build_gss_checksum(flags: GssFlags) -> Checksum {
+    let mut checksum = Checksum::default();
+    checksum.cksum_type = GSS_CHECKSUM_TYPE.to_vec();
+    checksum.checksum = 16u32.to_le_bytes().to_vec();
+    checksum.checksum.append(&mut vec![0; 16]);
+    checksum
+        .checksum
+        .extend_from_slice(&flags.bits().to_le_bytes());
+
+    checksum
+}
+
+/// Builds the AP-REQ that presents the service ticket with the encrypted authenticator and requests mutual authentication.
+pub fn build_ap_req(service_ticket: &ServiceTicket, authenticator: &Authenticator) -> ApReq {
+    let mut ap_req = ApReq::default();
+    ap_req.ap_options = encode_bit_flags(ApOptions::MUTUAL_REQUIRED.bits());
+    ap_req.ticket = service_ticket.ticket.clone();
+    ap_req.authenticator = seal_authenticator(
+        authenticator,
+        &service_ticket.session_key,
+        KEY_USAGE_AP_REQ_AUTHENTICATOR,
+    );
+
+    ap_req
+}
+
+/// Builds the security blob around the AP-REQ: the Kerberos GSS-API token as the mechToken
+/// of the NegTokenInit, which offers the Microsoft and the standard Kerberos OID.
+pub fn build_kerberos_security_blob(ap_req: &ApReq) -> Vec<u8> {
+    let mut token = gss::NegTokenInit::default();
+    token.mech_types = vec![MS_KERBEROS_OID.to_vec(), KERBEROS_OID.to_vec()];
+    token.mech_token = encode_kerberos_gss_token(GSS_AP_REQ_TOKEN_ID, &encode_ap_req(ap_req));
+
+    encode_neg_token_init(token)
+}
+
+/// Builds the session setup request body around the security blob.
+pub fn build_session_setup_kerberos_request_body(
+    security_blob: Vec<u8>,
+) -> requests::session_setup::SessionSetup {
+    let mut session_setup = requests::session_setup::SessionSetup::default();
+
+    session_setup.flags = requests::session_setup::Flags::Zero.unpack_byte_code();
+    session_setup.security_mode = fields::SecurityMode::NegotiateSigningEnabled.unpack_byte_code(1);
+    session_setup.capabilities =
+        requests::session_setup::Capabilities::GlobalCapDfs.unpack_byte_code();
+    session_setup.channel = vec![0; 4];
+    session_setup.security_buffer_offset = SECURITY_BUFFER_OFFSET.to_vec();
+    session_setup.previous_session_id = vec![0; 8];
+    session_setup.buffer = security_blob;
+    session_setup.security_buffer_length =
+        (session_setup.buffer.len() as u16).to_le_bytes().to_vec();
+
+    session_setup
+}
+
+/// Derives the session key from the security blob of the session setup response: the AP-REP in the
+/// NegTokenResp is decrypted with the session key of the ticket and its acceptor subkey is taken,
+/// or the subkey of the authenticator if the server sent none. SMB uses the first 16 bytes of the key.
+pub fn derive_kerberos_session_key(
+    security_blob: &[u8],
+    service_ticket: &ServiceTicket,
+    initiator_subkey: &EncryptionKey,
+) -> Option<Vec<u8>> {
+    let response_token = match decode_negotiation_token(security_blob)? {
+        NegotiationToken::NegTokenResp(token) => token.response_token,
+        _ => return None,
+    };
+    let (_, ap_rep) = decode_kerberos_gss_token(&response_token)?;
+    let enc_part = decode_ap_rep(&ap_rep)?;
+    let etype =
+        EncryptionType::map_byte_code_to_encryption_type(&service_ticket.session_key.key_type)?;
+    let plaintext = kerberos::decrypt(
+        &etype,
+        &service_ticket.session_key.key_value,
+        KEY_USAGE_AP_REP_ENC_PART,
+        &enc_part.cipher,
+    )?;
+    let mut session_key = decode_enc_ap_rep_part(&plaintext)?
+        .subkey
+        .unwrap_or_else(|| initiator_subkey.clone())
+        .key_value;
+    session_key.truncate(SMB_SESSION_KEY_LENGTH);
+
+    Some(session_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::kerberos;
+    use crate::format::decoder::{
+        kerberos_decoder::decode_kerberos_gss_token, spnego_decoder::decode_negotiation_token,
+    };
+    use crate::format::encoder::{
+        kerberos_encoder::{
+            encode_authenticator, encode_encrypted_data, encode_encryption_key, AP_REP_TAG,
+            ENC_AP_REP_PART_TAG, GENERALIZED_TIME_TAG, INTEGER_TAG,
+        },
+        spnego_encoder::{
+            encode_context_field, encode_der_element, encode_neg_token_resp, encode_optional_field,
+            SEQUENCE_TAG,
+        },
+    };
+    use crate::kerberos::{
+        EncryptedData, PrincipalName, Ticket, AP_REP, GSS_AP_REP_TOKEN_ID, NT_PRINCIPAL, PVNO,
+    };
+
+    #[test]
+    fn test_build_kerberos_security_blob() {
+        let service_ticket = ServiceTicket {
+            cname: PrincipalName::from_string(NT_PRINCIPAL, "alice"),
+            crealm: b"DOMAIN.LOCAL".to_vec(),
+            ticket: Ticket::default(),
+            session_key: EncryptionKey {
+                key_type: EncryptionType::Aes128CtsHmacSha196.unpack_byte_code(),
+                key_value: vec![0x11; 16],
+            },
+        };
+        let (authenticator, subkey) = build_default_ap_req_authenticator(&service_ticket);
+        let ap_req = build_ap_req(&service_ticket, &authenticator);
+
+        assert_eq!(16, subkey.key_value.len());
+        assert_eq!(
+            Some(encode_authenticator(&authenticator)),
+            kerberos::decrypt(
+                &EncryptionType::Aes128CtsHmacSha196,
+                &service_ticket.session_key.key_value,
+                KEY_USAGE_AP_REQ_AUTHENTICATOR,
+                &ap_req.authenticator.cipher
+            )
+        );
+
+        let mech_token = match decode_negotiation_token(&build_kerberos_security_blob(&ap_req)) {
+            Some(NegotiationToken::NegTokenInit(token)) => token.mech_token,
+            _ => panic!("Invalid security blob."),
+        };
+        assert_eq!(
+            Some((GSS_AP_REQ_TOKEN_ID.to_vec(), encode_ap_req(&ap_req))),
+            decode_kerberos_gss_token(&mech_token)
+        );
+    }
+
+    #[test]
+    fn test_derive_kerberos_session_key() {
+        let service_ticket = ServiceTicket {
+            cname: PrincipalName::from_string(NT_PRINCIPAL, "alice"),
+            crealm: b"DOMAIN.LOCAL".to_vec(),
+            ticket: Ticket::default(),
+            session_key: EncryptionKey {
+                key_type: EncryptionType::Aes256CtsHmacSha196.unpack_byte_code(),
+                key_value: vec![0x11; 32],
+            },
+        };
+        let initiator_subkey = EncryptionKey {
+            key_type: EncryptionType::Aes256CtsHmacSha196.unpack_byte_code(),
+            key_value: vec![0x22; 32],
+        };
+        let acceptor_subkey = EncryptionKey {
+            key_type: EncryptionType::Aes256CtsHmacSha196.unpack_byte_code(),
+            key_value: (0..32).collect(),
+        };
+        let build_security_blob = |subkey: Option<&EncryptionKey>| {
+            let mut fields = encode_optional_field(0, GENERALIZED_TIME_TAG, b"20261018120000Z");
+            fields.append(&mut encode_optional_field(1, INTEGER_TAG, &[0x00]));
+            if let Some(subkey) = subkey {
+                fields.append(&mut encode_context_field(2, &encode_encryption_key(subkey)));
+            }
+            let enc_ap_rep_part = encode_der_element(
+                ENC_AP_REP_PART_TAG,
+                &encode_der_element(SEQUENCE_TAG, &fields),
+            );
+            let mut enc_part = EncryptedData::default();
+            enc_part.etype = EncryptionType::Aes256CtsHmacSha196.unpack_byte_code();
+            enc_part.cipher = kerberos::encrypt(
+                &EncryptionType::Aes256CtsHmacSha196,
+                &service_ticket.session_key.key_value,
+                KEY_USAGE_AP_REP_ENC_PART,
+                &enc_ap_rep_part,
+                &[0x33; 16],
+            );
+
+            let mut fields = encode_optional_field(0, INTEGER_TAG, &[PVNO]);
+            fields.append(&mut encode_optional_field(1, INTEGER_TAG, &[AP_REP]));
+            fields.append(&mut encode_context_field(
+                2,
+                &encode_encrypted_data(&enc_part),
+            ));
+            let ap_rep = encode_der_element(AP_REP_TAG, &encode_der_element(SEQUENCE_TAG, &fields));
+
+            let mut token = gss::NegTokenResp::default();
+            token.response_token = encode_kerberos_gss_token(GSS_AP_REP_TOKEN_ID, &ap_rep);
+            encode_neg_token_resp(token)
+        };
+
+        assert_eq!(
+            Some((0..16).collect::<Vec<u8>>()),
+            derive_kerberos_session_key(
+                &build_security_blob(Some(&acceptor_subkey)),
+                &service_ticket,
+                &initiator_subkey
+            )
+        );
+        assert_eq!(
+            Some(vec![0x22; 16]),
+            derive_kerberos_session_key(
+                &build_security_blob(None),
+                &service_ticket,
+                &initiator_subkey
+            )
+        );
+    }
+
+    #[test]
+    fn test_build_gss_checksum() {
+        assert_eq!(
+            b"\x10\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+              \x22\x00\x00\x00"
+                .to_vec(),
+            build_gss_checksum(GssFlags::MUTUAL | GssFlags::INTEG).checksum
+        );
+    }
+}
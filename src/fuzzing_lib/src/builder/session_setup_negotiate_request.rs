@@ -1,13 +1,13 @@
-use crate::smb2::{header, helper_functions::fields, requests};
+use crate::{
+    format::encoder::security_blob_encoder::{encode_initial_security_blob, encode_negotiate_blob},
+    ntlmssp::{self, negotiate::Negotiate, negotiate_flags::NegotiateFlags, MessageType},
+    smb2::{header, helper_functions::fields, requests},
+};
 
-pub const INITIAL_SECURITY_BLOB: &[u8;74] = b"\x60\x48\x06\x06\x2b\x06\x01\x05\x05\x02\xa0\x3e\x30\x3c\xa0\x0e\
-                                             \x30\x0c\x06\x0a\x2b\x06\x01\x04\x01\x82\x37\x02\x02\x0a\xa2\x2a\
-                                             \x04\x28\x4e\x54\x4c\x4d\x53\x53\x50\x00\x01\x00\x00\x00\x05\x02\
-                                             \x00\x00\x00\x00\x00\x00\x28\x00\x00\x00\x00\x00\x00\x00\x28\x00\
-                                             \x00\x00\x06\x01\x00\x00\x00\x00\x00\x0f";
+/// The offset of the payload of the NEGOTIATE_MESSAGE, which directly follows the version.
+pub const NEGOTIATE_PAYLOAD_OFFSET: u32 = 0x28;
 
 pub const DEFAULT_BUFFER_OFFSET: &[u8; 2] = b"\x58\x00";
-pub const DEFAULT_BUFFER_LENGTH: &[u8; 2] = b"\x4a\x00";
 
 /// Builds a working default session setup 1 request.
 pub fn build_default_session_setup_negotiate_request() -> (
@@ -27,6 +27,68 @@ pub fn build_default_session_setup_negotiate_request() -> (
     )
 }
 
+/// Builds the default NTLM NEGOTIATE_MESSAGE, which offers NTLMv2 with extended session security,
+/// signing, 128-bit and 56-bit keys and the key exchange. Neither a domain nor a workstation is supplied.
+pub fn build_default_ntlm_negotiate_message() -> Negotiate {
+    let mut negotiate = Negotiate::default();
+
+    let flags = NegotiateFlags::NEG_56
+        | NegotiateFlags::NEG_KEY_EXCH
+        | NegotiateFlags::NEG_128
+        | NegotiateFlags::NEG_VERSION
+        | NegotiateFlags::NEG_EXTENDED_SESSION_SEC
+        | NegotiateFlags::NEG_ALWAYS_SIGN
+        | NegotiateFlags::NEG_NTLM
+        | NegotiateFlags::NEG_SIGN
+        | NegotiateFlags::REQ_TARGET
+        | NegotiateFlags::NEG_UNICODE;
+    negotiate.negotiate_flags = flags.bits().to_le_bytes().to_vec();
+    set_negotiate_payload_fields(&mut negotiate);
+    negotiate.version.product_major_version = vec![6];
+    negotiate.version.product_minor_version = vec![1];
+    negotiate.version.product_build = vec![0; 2];
+    negotiate.version.ntlm_revision_current = vec![15];
+
+    negotiate
+}
+
+/// Sets the length and offset fields of the payload, in which the domain name precedes the workstation name.
+pub fn set_negotiate_payload_fields(negotiate: &mut Negotiate) {
+    let domain_name_len = (negotiate.payload.domain_name.len() as u16).to_le_bytes();
+    let workstation_len = (negotiate.payload.workstation_name.len() as u16).to_le_bytes();
+
+    negotiate.domain_name_fields.domain_name_len = domain_name_len.to_vec();
+    negotiate.domain_name_fields.domain_name_max_len = domain_name_len.to_vec();
+    negotiate.domain_name_fields.domain_name_buffer_offset =
+        NEGOTIATE_PAYLOAD_OFFSET.to_le_bytes().to_vec();
+    negotiate.workstation_fields.workstation_len = workstation_len.to_vec();
+    negotiate.workstation_fields.workstation_max_len = workstation_len.to_vec();
+    negotiate.workstation_fields.workstation_buffer_offset = (NEGOTIATE_PAYLOAD_OFFSET
+        + negotiate.payload.domain_name.len() as u32)
+        .to_le_bytes()
+        .to_vec();
+}
+
+/// Wraps the negotiate message in the NTLMSSP header.
+pub fn wrap_negotiate(negotiate: Negotiate) -> ntlmssp::Header {
+    let mut ntlm_header = ntlmssp::Header::default();
+    let message_type = MessageType::Negotiate(negotiate);
+    ntlm_header.message_type = message_type.unpack_byte_code();
+    ntlm_header.message = Some(message_type);
+
+    ntlm_header
+}
+
+/// Builds the initial security blob of the session setup 1 request around the negotiate message.
+pub fn build_initial_security_blob(negotiate: Negotiate) -> Vec<u8> {
+    encode_initial_security_blob(wrap_negotiate(negotiate))
+}
+
+/// Returns the serialized default NTLM NEGOTIATE_MESSAGE, which is covered by the MIC.
+pub fn initial_ntlm_negotiate_message() -> Vec<u8> {
+    encode_negotiate_blob(wrap_negotiate(build_default_ntlm_negotiate_message()))
+}
+
 /// Builds a working default session setup 1 request body.
 pub fn build_default_session_setup_negotiate_request_body() -> requests::session_setup::SessionSetup
 {
@@ -39,9 +101,33 @@ pub fn build_default_session_setup_negotiate_request_body() -> requests::session
     ]);
     session_req.channel = vec![0; 4];
     session_req.security_buffer_offset = DEFAULT_BUFFER_OFFSET.to_vec();
-    session_req.security_buffer_length = DEFAULT_BUFFER_LENGTH.to_vec();
-    session_req.buffer = INITIAL_SECURITY_BLOB.to_vec();
+    session_req.buffer = build_initial_security_blob(build_default_ntlm_negotiate_message());
+    session_req.security_buffer_length = (session_req.buffer.len() as u16).to_le_bytes().to_vec();
     session_req.previous_session_id = vec![0; 8];
 
     session_req
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_initial_security_blob() {
+        let expected_blob = b"\x60\x48\x06\x06\x2b\x06\x01\x05\x05\x02\xa0\x3e\x30\x3c\xa0\x0e\
+                              \x30\x0c\x06\x0a\x2b\x06\x01\x04\x01\x82\x37\x02\x02\x0a\xa2\x2a\
+                              \x04\x28\x4e\x54\x4c\x4d\x53\x53\x50\x00\x01\x00\x00\x00\x15\x82\
+                              \x08\xe2\x00\x00\x00\x00\x28\x00\x00\x00\x00\x00\x00\x00\x28\x00\
+                              \x00\x00\x06\x01\x00\x00\x00\x00\x00\x0f"
+            .to_vec();
+
+        assert_eq!(
+            expected_blob,
+            build_initial_security_blob(build_default_ntlm_negotiate_message())
+        );
+        assert_eq!(
+            expected_blob[34..].to_vec(),
+            initial_ntlm_negotiate_message()
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{
+    format::encoder::set_info_encoder::serialize_file_basic_information,
+    smb2::{
+        header,
+        requests::{
+            self, query_info::file_information_class::FileInformationClass, query_info::InfoType,
+        },
+        responses::query_info::file_information::FileBasicInformation,
+    },
+};
+
+/// The last write time that is set by the default set info request (2021-01-01 00:00:00 UTC).
+pub const DEFAULT_LAST_WRITE_TIME: u64 = 0x01d6_dfd1_0c35_8000;
+
+/// Builds a working default set info request.
+pub fn build_default_set_info_request(
+    tree_id: Vec<u8>,
+    session_id: Vec<u8>,
+    file_id: Vec<u8>,
+) -> (
+    Option<header::SyncHeader>,
+    Option<requests::set_info::SetInfo>,
+) {
+    (
+        Some(super::build_sync_header(
+            header::Commands::SetInfo,
+            1,
+            7744,
+            Some(tree_id),
+            Some(session_id),
+            11,
+        )),
+        Some(build_default_set_info_request_body(file_id)),
+    )
+}
+
+/// Builds a working default set info request body that changes the last write time
+/// of the file and leaves all other basic information untouched.
+pub fn build_default_set_info_request_body(file_id: Vec<u8>) -> requests::set_info::SetInfo {
+    let mut set_info = requests::set_info::SetInfo::default();
+
+    let mut basic_information = FileBasicInformation::default();
+    basic_information.creation_time = vec![0; 8];
+    basic_information.last_access_time = vec![0; 8];
+    basic_information.last_write_time = DEFAULT_LAST_WRITE_TIME.to_le_bytes().to_vec();
+    basic_information.change_time = vec![0; 8];
+    basic_information.file_attributes = vec![0; 4];
+    basic_information.reserved = vec![0; 4];
+
+    set_info.info_type = InfoType::File.unpack_byte_code();
+    set_info.file_info_class = FileInformationClass::FileBasicInformation.unpack_byte_code();
+    set_info.buffer = serialize_file_basic_information(&basic_information);
+    set_info.buffer_length = (set_info.buffer.len() as u32).to_le_bytes().to_vec();
+    set_info.file_id = file_id;
+
+    set_info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_default_set_info_request_body() {
+        let body = build_default_set_info_request_body(vec![0; 16]);
+
+        assert_eq!(vec![1], body.info_type);
+        assert_eq!(vec![4], body.file_info_class);
+        assert_eq!(vec![40, 0, 0, 0], body.buffer_length);
+        assert_eq!(40, body.buffer.len());
+        assert_eq!(
+            DEFAULT_LAST_WRITE_TIME.to_le_bytes().to_vec(),
+            body.buffer[16..24].to_vec()
+        );
+    }
+}
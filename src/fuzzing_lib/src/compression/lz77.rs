@@ -0,0 +1,209 @@
+//! Plain LZ77 compression as specified in MS-XCA 2.3 and 2.4.
+//! A 32 bit flag word precedes each group of 32 literals and matches, whose bits are read
+//! from the most significant bit on. Matches are encoded in 16 bits (13 bits offset, 3 bits
+//! length) and longer lengths continue in shared half bytes, bytes, and 16 or 32 bit values.
+
+use super::{MatchFinder, MIN_MATCH_LENGTH};
+
+/// The maximum distance of a match.
+pub const MAX_OFFSET: usize = 8192;
+
+/// Compresses the data with plain LZ77.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut compressed: Vec<u8> = vec![0; 4];
+    let mut finder = MatchFinder::new(data);
+    let mut flags: u32 = 0;
+    let mut flag_count = 0;
+    let mut flag_position = 0;
+    let mut last_length_half_byte: Option<usize> = None;
+    let mut position = 0;
+
+    while position < data.len() {
+        match finder.find_longest_match(position, 0, MAX_OFFSET, data.len()) {
+            Some((offset, length)) => {
+                let match_offset = (offset - 1) as u16;
+                let mut match_length = length - MIN_MATCH_LENGTH;
+
+                if match_length < 7 {
+                    compressed.extend_from_slice(
+                        &((match_offset << 3) | match_length as u16).to_le_bytes(),
+                    );
+                } else {
+                    compressed.extend_from_slice(&((match_offset << 3) | 7).to_le_bytes());
+                    match_length -= 7;
+                    let half_byte = match_length.min(15) as u8;
+                    match last_length_half_byte.take() {
+                        Some(half_byte_position) => {
+                            compressed[half_byte_position] |= half_byte << 4
+                        }
+                        None => {
+                            last_length_half_byte = Some(compressed.len());
+                            compressed.push(half_byte);
+                        }
+                    }
+
+                    if match_length >= 15 {
+                        match_length -= 15;
+                        if match_length < 255 {
+                            compressed.push(match_length as u8);
+                        } else {
+                            compressed.push(255);
+                            let total_length = length - MIN_MATCH_LENGTH;
+                            if total_length <= u16::MAX as usize {
+                                compressed.extend_from_slice(&(total_length as u16).to_le_bytes());
+                            } else {
+                                compressed.extend_from_slice(&[0, 0]);
+                                compressed.extend_from_slice(&(total_length as u32).to_le_bytes());
+                            }
+                        }
+                    }
+                }
+
+                flags = (flags << 1) | 1;
+                position += length;
+            }
+            None => {
+                compressed.push(data[position]);
+                flags <<= 1;
+                position += 1;
+            }
+        }
+
+        flag_count += 1;
+        if flag_count == 32 {
+            compressed[flag_position..flag_position + 4].copy_from_slice(&flags.to_le_bytes());
+            flag_count = 0;
+            flag_position = compressed.len();
+            compressed.extend_from_slice(&[0; 4]);
+        }
+    }
+
+    // The unused flag bits are set, so that the decompression ends with a match at the end of the input.
+    let remaining_bits = 32 - flag_count;
+    let flags = ((flags as u64) << remaining_bits) | ((1u64 << remaining_bits) - 1);
+    compressed[flag_position..flag_position + 4].copy_from_slice(&(flags as u32).to_le_bytes());
+
+    compressed
+}
+
+/// Decompresses plain LZ77 data. The decompression ends when the input is consumed.
+/// Returns None if the input ends within a match, a match points before the output
+/// or the output exceeds the original size.
+pub fn decompress(data: &[u8], original_size: usize) -> Option<Vec<u8>> {
+    let mut decompressed: Vec<u8> = Vec::new();
+    let mut flags: u32 = 0;
+    let mut flag_count = 0;
+    let mut last_length_half_byte: Option<usize> = None;
+    let mut position = 0;
+
+    loop {
+        if flag_count == 0 {
+            if position + 4 > data.len() {
+                return Some(decompressed);
+            }
+            flags = u32::from_le_bytes([
+                data[position],
+                data[position + 1],
+                data[position + 2],
+                data[position + 3],
+            ]);
+            position += 4;
+            flag_count = 32;
+        }
+        flag_count -= 1;
+
+        if position == data.len() {
+            return Some(decompressed);
+        }
+
+        if flags & (1 << flag_count) == 0 {
+            if decompressed.len() == original_size {
+                return None;
+            }
+            decompressed.push(data[position]);
+            position += 1;
+            continue;
+        }
+
+        let match_bytes = u16::from_le_bytes([data[position], *data.get(position + 1)?]) as usize;
+        position += 2;
+        let mut match_length = match_bytes % 8;
+        let match_offset = match_bytes / 8 + 1;
+
+        if match_length == 7 {
+            match_length = match last_length_half_byte.take() {
+                Some(half_byte_position) => (data[half_byte_position] / 16) as usize,
+                None => {
+                    last_length_half_byte = Some(position);
+                    position += 1;
+                    (*data.get(position - 1)? % 16) as usize
+                }
+            };
+
+            if match_length == 15 {
+                match_length = *data.get(position)? as usize;
+                position += 1;
+                if match_length == 255 {
+                    match_length =
+                        u16::from_le_bytes([*data.get(position)?, *data.get(position + 1)?])
+                            as usize;
+                    position += 2;
+                    if match_length == 0 {
+                        let length_bytes = data.get(position..position + 4)?;
+                        match_length = u32::from_le_bytes([
+                            length_bytes[0],
+                            length_bytes[1],
+                            length_bytes[2],
+                            length_bytes[3],
+                        ]) as usize;
+                        position += 4;
+                    }
+                    if match_length < 15 + 7 {
+                        return None;
+                    }
+                    match_length -= 15 + 7;
+                }
+                match_length += 15;
+            }
+            match_length += 7;
+        }
+        match_length += MIN_MATCH_LENGTH;
+
+        if match_offset > decompressed.len() || decompressed.len() + match_length > original_size {
+            return None;
+        }
+        for _ in 0..match_length {
+            decompressed.push(decompressed[decompressed.len() - match_offset]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_and_decompress() {
+        let expected = hex::decode("ffffff1f61626317000fff2601").unwrap();
+
+        assert_eq!(expected, compress(&b"abc".repeat(100)));
+        assert_eq!(Some(b"abc".repeat(100)), decompress(&expected, 300));
+        assert_eq!(None, decompress(&expected, 299));
+
+        let text = b"abcdefghijklmnopqrstuvwxyz".to_vec();
+        let compressed = compress(&text);
+        assert_eq!(vec![0x3f, 0, 0, 0], compressed[..4].to_vec());
+        assert_eq!(Some(text.clone()), decompress(&compressed, text.len()));
+
+        let mut data: Vec<u8> = (0..20000).map(|index| (index * 13 % 253) as u8).collect();
+        data.extend_from_slice(&[0x42; 70000]);
+        data.extend_from_slice(&b"xyzxyz".repeat(10));
+        assert_eq!(Some(data.clone()), decompress(&compress(&data), data.len()));
+    }
+
+    #[test]
+    fn test_decompress_malformed_data() {
+        assert_eq!(None, decompress(&[0, 0, 0, 0x80, 0xf8, 0xff], 100));
+        assert_eq!(None, decompress(&[0, 0, 0, 0x40, 0x61, 0x07, 0x00], 100));
+    }
+}
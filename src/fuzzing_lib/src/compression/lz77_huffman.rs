@@ -0,0 +1,481 @@
+//! LZ77+Huffman compression as specified in MS-XCA 2.1 and 2.2.
+//! The data is compressed in blocks of 65536 bytes. Every block starts with a 256 byte table
+//! holding the 4 bit code lengths of the 512 symbols (literals 0 to 255, matches 256 to 511),
+//! followed by the canonical Huffman codes of the block. The bit stream is stored in 16 bit
+//! little endian words, and the extra length bytes of long matches are interleaved with them
+//! at the position the decompression reads them.
+
+use std::collections::{BinaryHeap, VecDeque};
+
+use std::cmp::Reverse;
+
+use super::{MatchFinder, MIN_MATCH_LENGTH};
+
+/// The number of uncompressed bytes in a block.
+pub const BLOCK_SIZE: usize = 65536;
+/// The maximum distance of a match.
+pub const MAX_OFFSET: usize = 65535;
+/// The length of the code length table at the start of every block.
+pub const TABLE_LENGTH: usize = 256;
+/// The number of Huffman symbols.
+const SYMBOL_COUNT: usize = 512;
+/// The maximum length of a Huffman code in bits.
+const MAX_CODE_LENGTH: u8 = 15;
+/// The symbol that ends the compressed data.
+const END_OF_STREAM: usize = 256;
+
+/// A literal or a match of a block.
+enum Token {
+    Literal(u8),
+    Match { offset: usize, length: usize },
+}
+
+/// Compresses the data with LZ77+Huffman.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut compressed: Vec<u8> = Vec::new();
+    let mut finder = MatchFinder::new(data);
+    let mut block_start = 0;
+
+    loop {
+        let block_end = (block_start + BLOCK_SIZE).min(data.len());
+        let last_block = block_end == data.len();
+
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut position = block_start;
+        while position < block_end {
+            match finder.find_longest_match(position, block_start, MAX_OFFSET, block_end - position)
+            {
+                Some((offset, length)) => {
+                    tokens.push(Token::Match { offset, length });
+                    position += length;
+                }
+                None => {
+                    tokens.push(Token::Literal(data[position]));
+                    position += 1;
+                }
+            }
+        }
+
+        let mut frequencies = vec![0u32; SYMBOL_COUNT];
+        for token in tokens.iter() {
+            frequencies[token_symbol(token)] += 1;
+        }
+        if last_block {
+            frequencies[END_OF_STREAM] += 1;
+        }
+
+        let code_lengths = build_code_lengths(&frequencies);
+        let codes = assign_canonical_codes(&code_lengths)
+            .expect("Length limited Huffman codes are never oversubscribed.");
+        for pair in code_lengths.chunks(2) {
+            compressed.push(pair[0] | (pair[1] << 4));
+        }
+
+        let mut writer = BitWriter::new(&mut compressed);
+        for token in tokens.iter() {
+            let symbol = token_symbol(token);
+            writer.write_bits(&mut compressed, code_lengths[symbol] as u32, codes[symbol]);
+
+            if let Token::Match { offset, length } = token {
+                let match_length = length - MIN_MATCH_LENGTH;
+                if match_length >= 15 {
+                    if match_length - 15 < 255 {
+                        compressed.push((match_length - 15) as u8);
+                    } else {
+                        compressed.push(255);
+                        compressed.extend_from_slice(&(match_length as u16).to_le_bytes());
+                    }
+                }
+
+                let offset_bit_length = offset_bit_length(*offset);
+                writer.write_bits(
+                    &mut compressed,
+                    offset_bit_length,
+                    (*offset - (1 << offset_bit_length)) as u32,
+                );
+            }
+        }
+        if last_block {
+            writer.write_bits(
+                &mut compressed,
+                code_lengths[END_OF_STREAM] as u32,
+                codes[END_OF_STREAM],
+            );
+        }
+        writer.flush(&mut compressed);
+
+        if last_block {
+            return compressed;
+        }
+        block_start = block_end;
+    }
+}
+
+/// Decompresses LZ77+Huffman data to the original size.
+/// Returns None if the data ends early, holds an invalid code or a match points before the output.
+pub fn decompress(data: &[u8], original_size: usize) -> Option<Vec<u8>> {
+    let mut decompressed: Vec<u8> = Vec::new();
+    let mut position = 0;
+
+    while decompressed.len() < original_size {
+        let table = data.get(position..position + TABLE_LENGTH)?;
+        let mut code_lengths: Vec<u8> = Vec::new();
+        for byte in table.iter() {
+            code_lengths.push(byte & 0x0f);
+            code_lengths.push(byte >> 4);
+        }
+        let decoding_table = build_decoding_table(&code_lengths)?;
+
+        let mut reader = BitReader::new(data, position + TABLE_LENGTH)?;
+        let block_end = decompressed.len() + BLOCK_SIZE;
+
+        while decompressed.len() < block_end && decompressed.len() < original_size {
+            let (symbol, code_length) = decoding_table[reader.peek_bits(15) as usize];
+            if code_length == 0 {
+                return None;
+            }
+            reader.consume_bits(code_length as u32)?;
+
+            let symbol = symbol as usize;
+            if symbol < 256 {
+                decompressed.push(symbol as u8);
+                continue;
+            }
+
+            let mut match_length = (symbol - 256) % 16;
+            let offset_bit_length = ((symbol - 256) / 16) as u32;
+            if match_length == 15 {
+                match_length = reader.read_byte()? as usize;
+                if match_length == 255 {
+                    match_length = reader.read_u16()? as usize;
+                    if match_length < 15 {
+                        return None;
+                    }
+                    match_length -= 15;
+                }
+                match_length += 15;
+            }
+            match_length += MIN_MATCH_LENGTH;
+
+            let match_offset =
+                reader.peek_bits(offset_bit_length) as usize + (1 << offset_bit_length);
+            reader.consume_bits(offset_bit_length)?;
+
+            if match_offset > decompressed.len()
+                || decompressed.len() + match_length > original_size
+            {
+                return None;
+            }
+            for _ in 0..match_length {
+                decompressed.push(decompressed[decompressed.len() - match_offset]);
+            }
+        }
+
+        position = reader.position;
+    }
+
+    Some(decompressed)
+}
+
+/// Returns the Huffman symbol of a literal or match.
+fn token_symbol(token: &Token) -> usize {
+    match token {
+        Token::Literal(byte) => *byte as usize,
+        Token::Match { offset, length } => {
+            256 + ((offset_bit_length(*offset) as usize) << 4) + (length - MIN_MATCH_LENGTH).min(15)
+        }
+    }
+}
+
+/// Returns the index of the highest set bit of the match offset.
+fn offset_bit_length(offset: usize) -> u32 {
+    usize::BITS - 1 - offset.leading_zeros()
+}
+
+/// Builds Huffman code lengths of at most 15 bits for the symbol frequencies.
+/// If the codes get too long, the frequencies are flattened until they fit.
+pub fn build_code_lengths(frequencies: &[u32]) -> Vec<u8> {
+    let mut frequencies = frequencies.to_vec();
+
+    loop {
+        let code_lengths = build_huffman_code_lengths(&frequencies);
+        if code_lengths.iter().all(|length| *length <= MAX_CODE_LENGTH) {
+            return code_lengths;
+        }
+
+        for frequency in frequencies.iter_mut().filter(|frequency| **frequency > 0) {
+            *frequency = (*frequency >> 1) | 1;
+        }
+    }
+}
+
+/// Builds unrestricted Huffman code lengths. A single used symbol gets a code of one bit.
+fn build_huffman_code_lengths(frequencies: &[u32]) -> Vec<u8> {
+    let mut code_lengths = vec![0u8; frequencies.len()];
+    let mut parents: Vec<usize> = Vec::new();
+    let mut heap = BinaryHeap::new();
+
+    for (symbol, frequency) in frequencies.iter().enumerate() {
+        if *frequency > 0 {
+            heap.push(Reverse((*frequency as u64, parents.len(), symbol)));
+            parents.push(usize::MAX);
+        }
+    }
+    let leaves: Vec<usize> = heap.iter().map(|Reverse((_, _, symbol))| *symbol).collect();
+
+    if leaves.len() == 1 {
+        code_lengths[leaves[0]] = 1;
+        return code_lengths;
+    }
+
+    let mut node_symbols: Vec<usize> = vec![usize::MAX; parents.len()];
+    for Reverse((_, node, symbol)) in heap.iter() {
+        node_symbols[*node] = *symbol;
+    }
+
+    while heap.len() > 1 {
+        let Reverse((first_weight, first_node, _)) = heap.pop().unwrap();
+        let Reverse((second_weight, second_node, _)) = heap.pop().unwrap();
+        let parent = parents.len();
+        parents.push(usize::MAX);
+        parents[first_node] = parent;
+        parents[second_node] = parent;
+        heap.push(Reverse((first_weight + second_weight, parent, usize::MAX)));
+    }
+
+    for (node, symbol) in node_symbols.iter().enumerate() {
+        let mut depth = 0;
+        let mut current = node;
+        while parents[current] != usize::MAX {
+            current = parents[current];
+            depth += 1;
+        }
+        code_lengths[*symbol] = depth.min(u8::MAX as usize) as u8;
+    }
+
+    code_lengths
+}
+
+/// Assigns the canonical Huffman codes in the order of code length and symbol.
+/// Returns None if the code lengths are oversubscribed.
+pub fn assign_canonical_codes(code_lengths: &[u8]) -> Option<Vec<u32>> {
+    let mut codes = vec![0u32; code_lengths.len()];
+    let mut next_code: u32 = 0;
+
+    for length in 1..=MAX_CODE_LENGTH {
+        for (symbol, code_length) in code_lengths.iter().enumerate() {
+            if *code_length == length {
+                if next_code >= 1 << length {
+                    return None;
+                }
+                codes[symbol] = next_code;
+                next_code += 1;
+            }
+        }
+        next_code <<= 1;
+    }
+
+    Some(codes)
+}
+
+/// Builds the table that maps every 15 bit prefix of the bit stream to its symbol and code length.
+/// Returns None if the code lengths are oversubscribed.
+fn build_decoding_table(code_lengths: &[u8]) -> Option<Vec<(u16, u8)>> {
+    let codes = assign_canonical_codes(code_lengths)?;
+    let mut decoding_table = vec![(0u16, 0u8); 1 << MAX_CODE_LENGTH];
+
+    for (symbol, code_length) in code_lengths.iter().enumerate() {
+        if *code_length == 0 {
+            continue;
+        }
+        let shift = MAX_CODE_LENGTH - code_length;
+        let first_entry = (codes[symbol] << shift) as usize;
+        for entry in decoding_table[first_entry..first_entry + (1 << shift)].iter_mut() {
+            *entry = (symbol as u16, *code_length);
+        }
+    }
+
+    Some(decoding_table)
+}
+
+/// Writes the bit stream of a block in 16 bit little endian words. The words are reserved
+/// in the output at the moment the decompression loads them, so that the extra length bytes,
+/// which are appended to the output directly, end up where the decompression reads them.
+struct BitWriter {
+    word_positions: VecDeque<usize>,
+    accumulator: u32,
+    accumulated_bits: u32,
+    bits_written: usize,
+    words_reserved: usize,
+}
+
+impl BitWriter {
+    /// Creates a new bit writer, reserving the two words the decompression loads initially.
+    fn new(output: &mut Vec<u8>) -> Self {
+        let mut writer = BitWriter {
+            word_positions: VecDeque::new(),
+            accumulator: 0,
+            accumulated_bits: 0,
+            bits_written: 0,
+            words_reserved: 0,
+        };
+        writer.reserve_word(output);
+        writer.reserve_word(output);
+
+        writer
+    }
+
+    fn reserve_word(&mut self, output: &mut Vec<u8>) {
+        self.word_positions.push_back(output.len());
+        output.extend_from_slice(&[0, 0]);
+        self.words_reserved += 1;
+    }
+
+    /// Writes the lowest bits of the value, starting with the most significant one.
+    fn write_bits(&mut self, output: &mut Vec<u8>, count: u32, value: u32) {
+        for bit in (0..count).rev() {
+            self.accumulator = (self.accumulator << 1) | ((value >> bit) & 1);
+            self.accumulated_bits += 1;
+            self.bits_written += 1;
+
+            if self.accumulated_bits == 16 {
+                self.write_word(output);
+            }
+        }
+
+        while self.bits_written > 16 * (self.words_reserved - 1) {
+            self.reserve_word(output);
+        }
+    }
+
+    fn write_word(&mut self, output: &mut [u8]) {
+        if let Some(position) = self.word_positions.pop_front() {
+            output[position..position + 2]
+                .copy_from_slice(&(self.accumulator as u16).to_le_bytes());
+        }
+        self.accumulator = 0;
+        self.accumulated_bits = 0;
+    }
+
+    /// Writes the last partial word. The remaining reserved words stay zero.
+    fn flush(&mut self, output: &mut [u8]) {
+        if self.accumulated_bits > 0 {
+            self.accumulator <<= 16 - self.accumulated_bits;
+            self.write_word(output);
+        }
+    }
+}
+
+/// Reads the bit stream of a block as the decompression in MS-XCA 2.2.4 does, keeping at least
+/// 16 bits of the stream loaded and reading the extra length bytes after the loaded words.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    next_bits: u32,
+    extra_bit_count: i32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8], position: usize) -> Option<Self> {
+        let mut reader = BitReader {
+            data,
+            position,
+            next_bits: 0,
+            extra_bit_count: 16,
+        };
+        reader.next_bits = (reader.read_u16()? as u32) << 16;
+        reader.next_bits |= reader.read_u16()? as u32;
+
+        Some(reader)
+    }
+
+    fn peek_bits(&self, count: u32) -> u32 {
+        if count == 0 {
+            0
+        } else {
+            self.next_bits >> (32 - count)
+        }
+    }
+
+    fn consume_bits(&mut self, count: u32) -> Option<()> {
+        if count == 0 {
+            return Some(());
+        }
+
+        self.next_bits <<= count;
+        self.extra_bit_count -= count as i32;
+        if self.extra_bit_count < 0 {
+            self.next_bits |= (self.read_u16()? as u32) << (-self.extra_bit_count);
+            self.extra_bit_count += 16;
+        }
+
+        Some(())
+    }
+
+    fn read_byte(&mut self) -> Option<u8> {
+        let byte = *self.data.get(self.position)?;
+        self.position += 1;
+
+        Some(byte)
+    }
+
+    fn read_u16(&mut self) -> Option<u16> {
+        let bytes = self.data.get(self.position..self.position + 2)?;
+        self.position += 2;
+
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assign_canonical_codes() {
+        assert_eq!(
+            Some(vec![0b10, 0b0, 0b110, 0b111]),
+            assign_canonical_codes(&[2, 1, 3, 3])
+        );
+        assert_eq!(None, assign_canonical_codes(&[1, 1, 1]));
+    }
+
+    #[test]
+    fn test_build_code_lengths() {
+        let mut frequencies = vec![0u32; SYMBOL_COUNT];
+        for (symbol, frequency) in frequencies.iter_mut().enumerate().take(40) {
+            *frequency = 1 << (symbol % 31);
+        }
+
+        let code_lengths = build_code_lengths(&frequencies);
+        assert!(code_lengths.iter().all(|length| *length <= MAX_CODE_LENGTH));
+        assert!(assign_canonical_codes(&code_lengths).is_some());
+    }
+
+    #[test]
+    fn test_compress_and_decompress() {
+        let data = b"abc".repeat(100);
+        let compressed = compress(&data);
+        assert_eq!(0x23, compressed[0x31]);
+        assert_eq!(0x02, compressed[0x80]);
+        assert_eq!(
+            hex::decode("a8dc0000ff2601").unwrap(),
+            compressed[TABLE_LENGTH..].to_vec()
+        );
+        assert_eq!(Some(data), decompress(&compressed, 300));
+
+        assert_eq!(Some(Vec::new()), decompress(&compress(&[]), 0));
+
+        let mut data: Vec<u8> = (0..70000).map(|index| (index * 31 % 241) as u8).collect();
+        data.extend_from_slice(&[0x42; 1000]);
+        data.extend_from_slice(&b"lz77 huffman ".repeat(500));
+        assert_eq!(Some(data.clone()), decompress(&compress(&data), data.len()));
+    }
+
+    #[test]
+    fn test_decompress_malformed_data() {
+        assert_eq!(None, decompress(&[0; 100], 10));
+        assert_eq!(None, decompress(&[0; TABLE_LENGTH + 4], 10));
+        assert_eq!(None, decompress(&[0x11; TABLE_LENGTH + 4], 10));
+    }
+}
@@ -0,0 +1,205 @@
+//! LZNT1 compression as specified in MS-XCA 2.5.
+//! The data is compressed in chunks of 4096 bytes. Every chunk starts with a 2 byte header
+//! holding the chunk size, a signature and whether the chunk is compressed. Within a compressed
+//! chunk, a flag byte precedes each group of eight literals and copy tokens, whose split between
+//! offset and length bits depends on the position within the chunk.
+
+use super::{MatchFinder, MIN_MATCH_LENGTH};
+
+/// The number of uncompressed bytes in a chunk.
+pub const CHUNK_SIZE: usize = 4096;
+/// The signature (0b011) in bits 12 to 14 of the chunk header.
+const CHUNK_SIGNATURE: u16 = 0x3000;
+/// Bit 15 of the chunk header marks compressed chunks.
+const CHUNK_COMPRESSED: u16 = 0x8000;
+
+/// Returns the number of bits by which the offset part of a copy token exceeds four bits
+/// at the given position (> 0) within the uncompressed chunk.
+pub fn calculate_offset_shift(position_in_chunk: usize) -> u32 {
+    let mut shift = 0;
+    let mut position = position_in_chunk - 1;
+    while position >= 0x10 {
+        position >>= 1;
+        shift += 1;
+    }
+
+    shift
+}
+
+/// Compresses the data with LZNT1. Chunks that do not shrink are stored uncompressed.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut compressed: Vec<u8> = Vec::new();
+    let mut finder = MatchFinder::new(data);
+
+    for chunk_start in (0..data.len()).step_by(CHUNK_SIZE) {
+        let chunk_end = (chunk_start + CHUNK_SIZE).min(data.len());
+        let mut chunk: Vec<u8> = Vec::new();
+        let mut position = chunk_start;
+
+        while position < chunk_end {
+            let flag_position = chunk.len();
+            chunk.push(0);
+
+            for bit in 0..8 {
+                if position >= chunk_end {
+                    break;
+                }
+
+                let position_in_chunk = position - chunk_start;
+                let found_match = if position_in_chunk == 0 {
+                    None
+                } else {
+                    let shift = calculate_offset_shift(position_in_chunk);
+                    finder.find_longest_match(
+                        position,
+                        chunk_start,
+                        1 << (4 + shift),
+                        (0xfff >> shift) + MIN_MATCH_LENGTH,
+                    )
+                };
+
+                match found_match {
+                    Some((offset, length)) if position + length <= chunk_end => {
+                        let shift = calculate_offset_shift(position_in_chunk);
+                        let token =
+                            (((offset - 1) << (12 - shift)) | (length - MIN_MATCH_LENGTH)) as u16;
+                        chunk.extend_from_slice(&token.to_le_bytes());
+                        chunk[flag_position] |= 1 << bit;
+                        position += length;
+                    }
+                    _ => {
+                        chunk.push(data[position]);
+                        position += 1;
+                    }
+                }
+            }
+        }
+
+        let chunk_length = chunk_end - chunk_start;
+        if chunk.len() < chunk_length {
+            let header = CHUNK_COMPRESSED | CHUNK_SIGNATURE | (chunk.len() - 1) as u16;
+            compressed.extend_from_slice(&header.to_le_bytes());
+            compressed.append(&mut chunk);
+        } else {
+            let header = CHUNK_SIGNATURE | (chunk_length - 1) as u16;
+            compressed.extend_from_slice(&header.to_le_bytes());
+            compressed.extend_from_slice(&data[chunk_start..chunk_end]);
+        }
+    }
+
+    compressed
+}
+
+/// Decompresses LZNT1 data. A chunk header of zero terminates the data.
+/// Returns None if a chunk exceeds the data, a copy token points before its chunk
+/// or the output exceeds the original size.
+pub fn decompress(data: &[u8], original_size: usize) -> Option<Vec<u8>> {
+    let mut decompressed: Vec<u8> = Vec::new();
+    let mut position = 0;
+
+    while position + 2 <= data.len() {
+        let header = u16::from_le_bytes([data[position], data[position + 1]]);
+        position += 2;
+        if header == 0 {
+            break;
+        }
+
+        let chunk_end = position + (header & 0xfff) as usize + 1;
+        if chunk_end > data.len() {
+            return None;
+        }
+
+        if header & CHUNK_COMPRESSED == 0 {
+            if decompressed.len() + chunk_end - position > original_size {
+                return None;
+            }
+            decompressed.extend_from_slice(&data[position..chunk_end]);
+            position = chunk_end;
+            continue;
+        }
+
+        let chunk_start = decompressed.len();
+        while position < chunk_end {
+            let flags = data[position];
+            position += 1;
+
+            for bit in 0..8 {
+                if position >= chunk_end {
+                    break;
+                }
+
+                if flags & (1 << bit) == 0 {
+                    if decompressed.len() == original_size {
+                        return None;
+                    }
+                    decompressed.push(data[position]);
+                    position += 1;
+                    continue;
+                }
+
+                if position + 2 > chunk_end {
+                    return None;
+                }
+                let token = u16::from_le_bytes([data[position], data[position + 1]]) as usize;
+                position += 2;
+
+                let position_in_chunk = decompressed.len() - chunk_start;
+                if position_in_chunk == 0 {
+                    return None;
+                }
+                let shift = calculate_offset_shift(position_in_chunk);
+                let length = (token & (0xfff >> shift)) + MIN_MATCH_LENGTH;
+                let offset = (token >> (12 - shift)) + 1;
+                if offset > position_in_chunk || decompressed.len() + length > original_size {
+                    return None;
+                }
+
+                for _ in 0..length {
+                    decompressed.push(decompressed[decompressed.len() - offset]);
+                }
+            }
+        }
+    }
+
+    Some(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calculate_offset_shift() {
+        assert_eq!(0, calculate_offset_shift(1));
+        assert_eq!(0, calculate_offset_shift(16));
+        assert_eq!(1, calculate_offset_shift(17));
+        assert_eq!(8, calculate_offset_shift(4096));
+    }
+
+    #[test]
+    fn test_compress_and_decompress() {
+        let compressed = compress(&b"abc".repeat(100));
+        assert_eq!(
+            vec![0x05, 0xb0, 0x08, 0x61, 0x62, 0x63, 0x26, 0x21],
+            compressed
+        );
+        assert_eq!(Some(b"abc".repeat(100)), decompress(&compressed, 300));
+        assert_eq!(None, decompress(&compressed, 299));
+
+        let mut data: Vec<u8> = (0..10000).map(|index| (index * 7 % 251) as u8).collect();
+        data.extend_from_slice(&[0x42; 5000]);
+        assert_eq!(Some(data.clone()), decompress(&compress(&data), data.len()));
+
+        let incompressible: Vec<u8> = (0..=255).collect();
+        let compressed = compress(&incompressible);
+        assert_eq!(vec![0xff, 0x30], compressed[..2].to_vec());
+        assert_eq!(Some(incompressible), decompress(&compressed, 256));
+        assert_eq!(None, decompress(&compressed, 255));
+    }
+
+    #[test]
+    fn test_decompress_malformed_data() {
+        assert_eq!(None, decompress(&[0x10, 0xb0, 0x00], 100));
+        assert_eq!(None, decompress(&[0x02, 0xb0, 0x01, 0x00, 0x00], 100));
+    }
+}
@@ -0,0 +1,348 @@
+//! This module contains the compression algorithms of the SMB2 COMPRESSION_TRANSFORM_HEADER
+//! as specified in MS-XCA (LZNT1, plain LZ77 and LZ77+Huffman) and MS-SMB2 (Pattern_V1),
+//! and builds the chained and unchained compression transforms of SMB2 messages.
+
+use std::collections::HashMap;
+
+use crate::smb2::{
+    compression_transform::{
+        ChainedCompressionTransform, ChainedPayload, CompressionTransform,
+        UnchainedCompressionTransform,
+    },
+    helper_functions::negotiate_context::CompressionAlgorithms,
+};
+
+pub mod lz77;
+pub mod lz77_huffman;
+pub mod lznt1;
+pub mod pattern_v1;
+
+/// The minimum length of a match in all LZ77 based algorithms.
+pub const MIN_MATCH_LENGTH: usize = 3;
+/// The minimum length of a run of repeated bytes that is sent as a Pattern_V1 payload
+/// at the front or the back of a chained compression transform.
+pub const MIN_PATTERN_LENGTH: usize = 32;
+/// The number of earlier positions that are compared when searching the longest match.
+const MAX_CHAIN_DEPTH: usize = 256;
+
+/// Compresses the data with the compression algorithm. No compression returns the data unchanged.
+/// Pattern_V1 encodes the data as repetitions of its first byte and is therefore only lossless for runs.
+pub fn compress(data: &[u8], algorithm: &CompressionAlgorithms) -> Vec<u8> {
+    match algorithm {
+        CompressionAlgorithms::None => data.to_vec(),
+        CompressionAlgorithms::Lznt1 => lznt1::compress(data),
+        CompressionAlgorithms::Lz77 => lz77::compress(data),
+        CompressionAlgorithms::Lz77Huffman => lz77_huffman::compress(data),
+        CompressionAlgorithms::PatternV1 => pattern_v1::compress(data),
+    }
+}
+
+/// Decompresses the data with the compression algorithm.
+/// Returns None if the compressed data is malformed or its output exceeds the original size,
+/// so that the sizes announced by the server bound the memory of the decompression.
+pub fn decompress(
+    data: &[u8],
+    algorithm: &CompressionAlgorithms,
+    original_size: usize,
+) -> Option<Vec<u8>> {
+    match algorithm {
+        CompressionAlgorithms::None if data.len() > original_size => None,
+        CompressionAlgorithms::None => Some(data.to_vec()),
+        CompressionAlgorithms::Lznt1 => lznt1::decompress(data, original_size),
+        CompressionAlgorithms::Lz77 => lz77::decompress(data, original_size),
+        CompressionAlgorithms::Lz77Huffman => lz77_huffman::decompress(data, original_size),
+        CompressionAlgorithms::PatternV1 => pattern_v1::decompress(data, original_size),
+    }
+}
+
+/// Builds the unchained compression transform of the message (without the netbios session prefix).
+/// The first offset bytes of the message are sent uncompressed, the rest is compressed.
+pub fn build_unchained_compression_transform(
+    message: &[u8],
+    algorithm: &CompressionAlgorithms,
+    offset: usize,
+) -> UnchainedCompressionTransform {
+    let offset = offset.min(message.len());
+    let mut transform = UnchainedCompressionTransform::default();
+
+    transform.original_compressed_segment_size =
+        ((message.len() - offset) as u32).to_le_bytes().to_vec();
+    transform.compression_algorithm = algorithm.unpack_byte_code();
+    transform.offset = (offset as u32).to_le_bytes().to_vec();
+    transform.uncompressed_data = message[..offset].to_vec();
+    transform.compressed_data = compress(&message[offset..], algorithm);
+
+    transform
+}
+
+/// Builds the chained compression transform of the message (without the netbios session prefix).
+/// Runs of repeated bytes at the front and the back of the message are sent as Pattern_V1
+/// payloads, the remaining data is compressed with the algorithm if that reduces its size.
+pub fn build_chained_compression_transform(
+    message: &[u8],
+    algorithm: &CompressionAlgorithms,
+) -> ChainedCompressionTransform {
+    let mut transform = ChainedCompressionTransform::default();
+    transform.original_compressed_segment_size = (message.len() as u32).to_le_bytes().to_vec();
+
+    let front = pattern_v1::count_leading_repetitions(message);
+    let front = if front >= MIN_PATTERN_LENGTH {
+        front
+    } else {
+        0
+    };
+    let back = pattern_v1::count_trailing_repetitions(&message[front..]);
+    let back = if back >= MIN_PATTERN_LENGTH { back } else { 0 };
+    let middle = &message[front..message.len() - back];
+
+    if front > 0 {
+        transform.payloads.push(build_chained_payload(
+            &message[..front],
+            &CompressionAlgorithms::PatternV1,
+        ));
+    }
+    if !middle.is_empty() {
+        let compressed_payload = build_chained_payload(middle, algorithm);
+        if compressed_payload.payload_data.len() + compressed_payload.original_payload_size.len()
+            < middle.len()
+        {
+            transform.payloads.push(compressed_payload);
+        } else {
+            transform
+                .payloads
+                .push(build_chained_payload(middle, &CompressionAlgorithms::None));
+        }
+    }
+    if back > 0 {
+        transform.payloads.push(build_chained_payload(
+            &message[message.len() - back..],
+            &CompressionAlgorithms::PatternV1,
+        ));
+    }
+
+    transform
+}
+
+/// Builds a single payload of the chained compression transform. The original payload size
+/// is only present for the LZNT1, LZ77 and LZ77+Huffman algorithms and counts to the length.
+pub fn build_chained_payload(data: &[u8], algorithm: &CompressionAlgorithms) -> ChainedPayload {
+    let mut payload = ChainedPayload::default();
+
+    payload.compression_algorithm = algorithm.unpack_byte_code();
+    payload.payload_data = compress(data, algorithm);
+    if has_original_payload_size(algorithm) {
+        payload.original_payload_size = (data.len() as u32).to_le_bytes().to_vec();
+    }
+    payload.length = ((payload.original_payload_size.len() + payload.payload_data.len()) as u32)
+        .to_le_bytes()
+        .to_vec();
+
+    payload
+}
+
+/// Checks whether a chained payload of the algorithm carries the original payload size.
+pub fn has_original_payload_size(algorithm: &CompressionAlgorithms) -> bool {
+    matches!(
+        algorithm,
+        CompressionAlgorithms::Lznt1
+            | CompressionAlgorithms::Lz77
+            | CompressionAlgorithms::Lz77Huffman
+    )
+}
+
+/// Decompresses the compression transform to the original message.
+/// Returns None if an algorithm is unknown, the compressed data is malformed or the
+/// decompressed data exceeds the OriginalCompressedSegmentSize. Chained payloads are
+/// additionally bounded by their OriginalPayloadSize, if they carry one.
+pub fn decompress_transform(transform: &CompressionTransform) -> Option<Vec<u8>> {
+    match transform {
+        CompressionTransform::Unchained(unchained) => {
+            let algorithm = map_byte_code_to_algorithm(&unchained.compression_algorithm)?;
+            let mut message = unchained.uncompressed_data.clone();
+            message.append(&mut decompress(
+                &unchained.compressed_data,
+                &algorithm,
+                read_size(&unchained.original_compressed_segment_size),
+            )?);
+
+            Some(message)
+        }
+        CompressionTransform::Chained(chained) => {
+            let segment_size = read_size(&chained.original_compressed_segment_size);
+            let mut message: Vec<u8> = Vec::new();
+            for payload in chained.payloads.iter() {
+                let algorithm = map_byte_code_to_algorithm(&payload.compression_algorithm)?;
+                let remaining_size = segment_size - message.len();
+                let original_size = if has_original_payload_size(&algorithm) {
+                    read_size(&payload.original_payload_size).min(remaining_size)
+                } else {
+                    remaining_size
+                };
+                message.append(&mut decompress(
+                    &payload.payload_data,
+                    &algorithm,
+                    original_size,
+                )?);
+            }
+
+            Some(message)
+        }
+    }
+}
+
+/// Maps the byte code of a compression algorithm id to the algorithm, if it is known.
+pub fn map_byte_code_to_algorithm(byte_code: &[u8]) -> Option<CompressionAlgorithms> {
+    match byte_code {
+        [0, 0] => Some(CompressionAlgorithms::None),
+        [1, 0] => Some(CompressionAlgorithms::Lznt1),
+        [2, 0] => Some(CompressionAlgorithms::Lz77),
+        [3, 0] => Some(CompressionAlgorithms::Lz77Huffman),
+        [4, 0] => Some(CompressionAlgorithms::PatternV1),
+        _ => None,
+    }
+}
+
+/// Reads a little endian size field of up to four bytes.
+fn read_size(size: &[u8]) -> usize {
+    let mut size_bytes = [0; 4];
+    let length = size.len().min(4);
+    size_bytes[..length].copy_from_slice(&size[..length]);
+
+    u32::from_le_bytes(size_bytes) as usize
+}
+
+/// Finds the longest earlier match of the data at a position with hash chains
+/// over the first three bytes of every position.
+pub struct MatchFinder<'a> {
+    data: &'a [u8],
+    chains: HashMap<[u8; 3], Vec<usize>>,
+    next_position: usize,
+}
+
+impl<'a> MatchFinder<'a> {
+    /// Creates a new match finder for the data.
+    pub fn new(data: &'a [u8]) -> Self {
+        MatchFinder {
+            data,
+            chains: HashMap::new(),
+            next_position: 0,
+        }
+    }
+
+    /// Returns the offset and length of the longest match of the data at the position that starts
+    /// at or after the window start, at most max offset bytes before the position. Matches may
+    /// overlap the position. Matches shorter than the minimum match length are not returned.
+    pub fn find_longest_match(
+        &mut self,
+        position: usize,
+        window_start: usize,
+        max_offset: usize,
+        max_length: usize,
+    ) -> Option<(usize, usize)> {
+        while self.next_position < position {
+            if let Some(key) = self.key_at(self.next_position) {
+                self.chains.entry(key).or_default().push(self.next_position);
+            }
+            self.next_position += 1;
+        }
+
+        let max_length = max_length.min(self.data.len() - position);
+        let key = self.key_at(position)?;
+        let mut best_match: Option<(usize, usize)> = None;
+
+        for &candidate in self.chains.get(&key)?.iter().rev().take(MAX_CHAIN_DEPTH) {
+            if candidate < window_start || position - candidate > max_offset {
+                break;
+            }
+
+            let mut length = 0;
+            while length < max_length
+                && self.data[candidate + length] == self.data[position + length]
+            {
+                length += 1;
+            }
+
+            if length >= MIN_MATCH_LENGTH && best_match.is_none_or(|(_, best)| length > best) {
+                best_match = Some((position - candidate, length));
+                if length == max_length {
+                    break;
+                }
+            }
+        }
+
+        best_match
+    }
+
+    fn key_at(&self, position: usize) -> Option<[u8; 3]> {
+        self.data
+            .get(position..position + MIN_MATCH_LENGTH)
+            .map(|bytes| [bytes[0], bytes[1], bytes[2]])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_chained_compression_transform() {
+        let mut message = vec![0; 100];
+        message.append(&mut b"abcdefgh".repeat(20));
+        message.append(&mut vec![0xff; 40]);
+
+        let transform = build_chained_compression_transform(&message, &CompressionAlgorithms::Lz77);
+        let algorithms: Vec<Vec<u8>> = transform
+            .payloads
+            .iter()
+            .map(|payload| payload.compression_algorithm.clone())
+            .collect();
+
+        assert_eq!(
+            vec![
+                CompressionAlgorithms::PatternV1.unpack_byte_code(),
+                CompressionAlgorithms::Lz77.unpack_byte_code(),
+                CompressionAlgorithms::PatternV1.unpack_byte_code(),
+            ],
+            algorithms
+        );
+        assert_eq!(
+            Some(message),
+            decompress_transform(&CompressionTransform::Chained(transform))
+        );
+    }
+
+    #[test]
+    fn test_decompress_oversized_chained_payload() {
+        let message = vec![0x42; 64];
+        let mut transform =
+            build_chained_compression_transform(&message, &CompressionAlgorithms::Lz77);
+        transform.payloads[0].payload_data = vec![0x42, 0, 0, 0, 0xff, 0xff, 0xff, 0xff];
+
+        assert_eq!(
+            None,
+            decompress_transform(&CompressionTransform::Chained(transform))
+        );
+    }
+
+    #[test]
+    fn test_build_unchained_compression_transform() {
+        let message = b"header bytes that stay, then a payload payload payload payload".to_vec();
+
+        for algorithm in [
+            CompressionAlgorithms::None,
+            CompressionAlgorithms::Lznt1,
+            CompressionAlgorithms::Lz77,
+            CompressionAlgorithms::Lz77Huffman,
+        ]
+        .iter()
+        {
+            let transform = build_unchained_compression_transform(&message, algorithm, 12);
+
+            assert_eq!(b"header bytes".to_vec(), transform.uncompressed_data);
+            assert_eq!(
+                Some(message.clone()),
+                decompress_transform(&CompressionTransform::Unchained(transform))
+            );
+        }
+    }
+}
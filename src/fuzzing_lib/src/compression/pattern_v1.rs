@@ -0,0 +1,73 @@
+//! Pattern_V1 compression as specified in MS-SMB2 2.2.42.2.2.
+//! The payload holds a single byte that is repeated a number of times.
+
+/// The length of a Pattern_V1 payload.
+pub const PAYLOAD_LENGTH: usize = 8;
+
+/// Compresses the data as repetitions of its first byte. The payload consists of the pattern,
+/// two reserved bytes, one reserved byte and the number of repetitions.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut compressed = vec![*data.first().unwrap_or(&0), 0, 0, 0];
+    compressed.extend_from_slice(&(data.len() as u32).to_le_bytes());
+
+    compressed
+}
+
+/// Decompresses a Pattern_V1 payload. Returns None if the payload is too short
+/// or the number of repetitions exceeds the original size.
+pub fn decompress(data: &[u8], original_size: usize) -> Option<Vec<u8>> {
+    let payload = data.get(..PAYLOAD_LENGTH)?;
+    let repetitions = u32::from_le_bytes([payload[4], payload[5], payload[6], payload[7]]) as usize;
+    if repetitions > original_size {
+        return None;
+    }
+
+    Some(vec![payload[0]; repetitions])
+}
+
+/// Counts how often the first byte of the data is repeated at its front.
+pub fn count_leading_repetitions(data: &[u8]) -> usize {
+    match data.first() {
+        Some(first) => data.iter().take_while(|byte| *byte == first).count(),
+        None => 0,
+    }
+}
+
+/// Counts how often the last byte of the data is repeated at its back.
+pub fn count_trailing_repetitions(data: &[u8]) -> usize {
+    match data.last() {
+        Some(last) => data.iter().rev().take_while(|byte| *byte == last).count(),
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_and_decompress() {
+        let compressed = compress(&[0xab; 300]);
+
+        assert_eq!(vec![0xab, 0, 0, 0, 0x2c, 0x01, 0, 0], compressed);
+        assert_eq!(Some(vec![0xab; 300]), decompress(&compressed, 300));
+        assert_eq!(None, decompress(&[0xab, 0, 0, 0], 300));
+    }
+
+    #[test]
+    fn test_decompress_oversized_pattern() {
+        let oversized = [0xab, 0, 0, 0, 0xff, 0xff, 0xff, 0xff];
+
+        assert_eq!(None, decompress(&oversized, 300));
+        assert_eq!(None, decompress(&compress(&[0xab; 301]), 300));
+    }
+
+    #[test]
+    fn test_count_repetitions() {
+        let data = [1, 1, 1, 2, 3, 3];
+
+        assert_eq!(3, count_leading_repetitions(&data));
+        assert_eq!(2, count_trailing_repetitions(&data));
+        assert_eq!(0, count_leading_repetitions(&[]));
+    }
+}
@@ -0,0 +1,363 @@
+//! SMB 3.x message encryption with the SMB2 TRANSFORM_HEADER as specified in MS-SMB2 3.1.4.3.
+//! Messages are encrypted with AES-128/256-CCM or AES-128/256-GCM. The transform header
+//! from the nonce onward is authenticated as additional data, the tag is its signature.
+
+use aes::{Aes128, Aes256};
+use aes_gcm::{
+    aead::{
+        consts::{U11, U16},
+        generic_array::{typenum::Unsigned, GenericArray},
+        AeadInPlace, KeyInit,
+    },
+    Aes128Gcm, Aes256Gcm,
+};
+use ccm::Ccm;
+
+use super::{derive_key, KEY_LENGTH_128};
+use crate::format::{
+    decoder::{decode_transform_header, is_transform_message},
+    encoder::{serialize_netbios_session_prefix, serialize_transform_header},
+    TRANSFORM_HEADER_LENGTH,
+};
+use crate::fuzzer::create_random_byte_array_of_predefined_length;
+use crate::smb2::{
+    header::TransformHeader, helper_functions::negotiate_context::Ciphers,
+    responses::negotiate::DialectRevision,
+};
+
+/// The length of the derived keys in bits for the 256-bit ciphers.
+pub const KEY_LENGTH_256: u32 = 256;
+/// The offset of the nonce in the transform header. Everything from here on is additional authenticated data.
+pub const AAD_OFFSET: usize = 20;
+
+type Aes128Ccm = Ccm<Aes128, U16, U11>;
+type Aes256Ccm = Ccm<Aes256, U16, U11>;
+
+/// Returns the length of the nonce in bytes. AES-CCM uses 11 bytes, AES-GCM uses 12 bytes.
+pub fn nonce_length(cipher: &Ciphers) -> usize {
+    match cipher {
+        Ciphers::Aes128Ccm | Ciphers::Aes256Ccm => 11,
+        Ciphers::Aes128Gcm | Ciphers::Aes256Gcm => 12,
+    }
+}
+
+/// Returns the length of the encryption keys in bits.
+pub fn key_length(cipher: &Ciphers) -> u32 {
+    match cipher {
+        Ciphers::Aes128Ccm | Ciphers::Aes128Gcm => KEY_LENGTH_128,
+        Ciphers::Aes256Ccm | Ciphers::Aes256Gcm => KEY_LENGTH_256,
+    }
+}
+
+/// Derives the encryption key (client to server) and the decryption key (server to client)
+/// from the session key. For the SMB 3.1.1 dialect, the preauth integrity hash of the session
+/// is the KDF context. The 2.x dialects do not support encryption and are treated like SMB 3.0.
+pub fn derive_encryption_keys(
+    session_key: &[u8],
+    dialect: &DialectRevision,
+    preauth_integrity_hash: &[u8],
+    cipher: &Ciphers,
+) -> (Vec<u8>, Vec<u8>) {
+    let length = key_length(cipher);
+
+    match dialect {
+        DialectRevision::Smb311 => (
+            derive_key(
+                session_key,
+                b"SMBC2SCipherKey\x00",
+                preauth_integrity_hash,
+                length,
+            ),
+            derive_key(
+                session_key,
+                b"SMBS2CCipherKey\x00",
+                preauth_integrity_hash,
+                length,
+            ),
+        ),
+        _ => (
+            derive_key(session_key, b"SMB2AESCCM\x00", b"ServerIn \x00", length),
+            derive_key(session_key, b"SMB2AESCCM\x00", b"ServerOut\x00", length),
+        ),
+    }
+}
+
+/// Builds the transform header for a message of the given size with a random nonce.
+/// The bytes of the 16 byte nonce field that the cipher does not use are zero.
+pub fn build_transform_header(
+    original_message_size: usize,
+    session_id: Vec<u8>,
+    cipher: &Ciphers,
+) -> TransformHeader {
+    let mut header = TransformHeader::default();
+
+    header.nonce = create_random_byte_array_of_predefined_length(nonce_length(cipher) as u32);
+    header.nonce.resize(16, 0);
+    header.original_message_size = (original_message_size as u32).to_le_bytes().to_vec();
+    header.session_id = session_id;
+
+    header
+}
+
+/// Encrypts the message (without the netbios session prefix) with the given transform header.
+/// The header is authenticated as it is, so that fuzzed header fields carry a valid signature.
+/// Returns the serialized transform header followed by the encrypted message.
+pub fn encrypt_message(
+    message: &[u8],
+    mut header: TransformHeader,
+    encryption_key: &[u8],
+    cipher: &Ciphers,
+) -> Vec<u8> {
+    header.signature = vec![0; 16];
+    let serialized_header = serialize_transform_header(&header);
+    let additional_data = serialized_header.get(AAD_OFFSET..).unwrap_or(&[]);
+    let nonce = pad_nonce(&header.nonce, cipher);
+
+    let mut encrypted_message = message.to_vec();
+    header.signature = match cipher {
+        Ciphers::Aes128Ccm => seal::<Aes128Ccm>(
+            encryption_key,
+            &nonce,
+            additional_data,
+            &mut encrypted_message,
+        ),
+        Ciphers::Aes128Gcm => seal::<Aes128Gcm>(
+            encryption_key,
+            &nonce,
+            additional_data,
+            &mut encrypted_message,
+        ),
+        Ciphers::Aes256Ccm => seal::<Aes256Ccm>(
+            encryption_key,
+            &nonce,
+            additional_data,
+            &mut encrypted_message,
+        ),
+        Ciphers::Aes256Gcm => seal::<Aes256Gcm>(
+            encryption_key,
+            &nonce,
+            additional_data,
+            &mut encrypted_message,
+        ),
+    };
+
+    let mut transform_message = serialize_transform_header(&header);
+    transform_message.append(&mut encrypted_message);
+
+    transform_message
+}
+
+/// Encrypts the serialized request (including the netbios session prefix) for the session
+/// of its first header. Returns the transform message with a recomputed netbios session prefix.
+pub fn encrypt_request(
+    request: Vec<u8>,
+    header: TransformHeader,
+    encryption_key: &[u8],
+    cipher: &Ciphers,
+) -> Vec<u8> {
+    let mut transform_message = encrypt_message(&request[4..], header, encryption_key, cipher);
+
+    let mut encrypted_request = serialize_netbios_session_prefix(transform_message.len());
+    encrypted_request.append(&mut transform_message);
+
+    encrypted_request
+}
+
+/// Decrypts the transform message (without the netbios session prefix).
+/// Returns None if the message is not a transform message or fails to authenticate.
+pub fn decrypt_message(
+    transform_message: &[u8],
+    decryption_key: &[u8],
+    cipher: &Ciphers,
+) -> Option<Vec<u8>> {
+    if !is_transform_message(transform_message) {
+        return None;
+    }
+
+    let header = decode_transform_header(transform_message[..TRANSFORM_HEADER_LENGTH].to_vec());
+    let additional_data = &transform_message[AAD_OFFSET..TRANSFORM_HEADER_LENGTH];
+    let nonce = pad_nonce(&header.nonce, cipher);
+
+    let mut message = transform_message[TRANSFORM_HEADER_LENGTH..].to_vec();
+    let authenticated = match cipher {
+        Ciphers::Aes128Ccm => open::<Aes128Ccm>(
+            decryption_key,
+            &nonce,
+            additional_data,
+            &mut message,
+            &header.signature,
+        ),
+        Ciphers::Aes128Gcm => open::<Aes128Gcm>(
+            decryption_key,
+            &nonce,
+            additional_data,
+            &mut message,
+            &header.signature,
+        ),
+        Ciphers::Aes256Ccm => open::<Aes256Ccm>(
+            decryption_key,
+            &nonce,
+            additional_data,
+            &mut message,
+            &header.signature,
+        ),
+        Ciphers::Aes256Gcm => open::<Aes256Gcm>(
+            decryption_key,
+            &nonce,
+            additional_data,
+            &mut message,
+            &header.signature,
+        ),
+    };
+
+    if authenticated {
+        Some(message)
+    } else {
+        None
+    }
+}
+
+/// Takes the bytes of the nonce field the cipher uses. Fuzzed nonces that are too short are padded with zeros.
+fn pad_nonce(nonce: &[u8], cipher: &Ciphers) -> Vec<u8> {
+    let mut padded_nonce = nonce.to_vec();
+    padded_nonce.resize(nonce_length(cipher), 0);
+
+    padded_nonce
+}
+
+/// Encrypts the buffer in place and returns the authentication tag.
+fn seal<A: AeadInPlace + KeyInit>(
+    key: &[u8],
+    nonce: &[u8],
+    additional_data: &[u8],
+    buffer: &mut [u8],
+) -> Vec<u8> {
+    let cipher = A::new_from_slice(key).expect("Invalid encryption key length.");
+
+    cipher
+        .encrypt_in_place_detached(
+            GenericArray::from_slice(&nonce[..A::NonceSize::USIZE]),
+            additional_data,
+            buffer,
+        )
+        .expect("Encryption failed.")
+        .to_vec()
+}
+
+/// Decrypts the buffer in place and returns whether the authentication tag is valid.
+fn open<A: AeadInPlace + KeyInit>(
+    key: &[u8],
+    nonce: &[u8],
+    additional_data: &[u8],
+    buffer: &mut [u8],
+    tag: &[u8],
+) -> bool {
+    let cipher = match A::new_from_slice(key) {
+        Ok(cipher) => cipher,
+        Err(_) => return false,
+    };
+
+    cipher
+        .decrypt_in_place_detached(
+            GenericArray::from_slice(&nonce[..A::NonceSize::USIZE]),
+            additional_data,
+            buffer,
+            GenericArray::from_slice(tag),
+        )
+        .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_test_header(cipher: &Ciphers) -> TransformHeader {
+        let mut header = TransformHeader::default();
+        header.nonce = vec![0x33; nonce_length(cipher)];
+        header.nonce.resize(16, 0);
+        header.original_message_size = 5u32.to_le_bytes().to_vec();
+        header.session_id = vec![1; 8];
+
+        header
+    }
+
+    #[test]
+    fn test_derive_encryption_keys() {
+        let (encryption_key, decryption_key) = derive_encryption_keys(
+            &[0x11; 16],
+            &DialectRevision::Smb30,
+            &[],
+            &Ciphers::Aes128Ccm,
+        );
+
+        assert_eq!(
+            hex::decode("802d75cc3b7b7239ec7a995c480ae29b").unwrap(),
+            encryption_key
+        );
+        assert_eq!(16, decryption_key.len());
+        assert_ne!(encryption_key, decryption_key);
+
+        let (encryption_key, _) = derive_encryption_keys(
+            &[0x11; 16],
+            &DialectRevision::Smb311,
+            &[0x44; 64],
+            &Ciphers::Aes256Gcm,
+        );
+        assert_eq!(32, encryption_key.len());
+    }
+
+    #[test]
+    fn test_encrypt_message() {
+        let expected_messages = [
+            (
+                Ciphers::Aes128Ccm,
+                "6cd8089f06",
+                "9000c5b6b5d53c924a8bb546c313a12b",
+            ),
+            (
+                Ciphers::Aes128Gcm,
+                "e96085e414",
+                "6fdd5bd305d4b64117c8857bc0fd8f86",
+            ),
+        ];
+
+        for (cipher, ciphertext, signature) in expected_messages.iter() {
+            let transform_message =
+                encrypt_message(b"hello", build_test_header(cipher), &[0x22; 16], cipher);
+
+            assert_eq!(TRANSFORM_HEADER_LENGTH + 5, transform_message.len());
+            assert_eq!(
+                hex::decode(signature).unwrap(),
+                transform_message[4..20].to_vec()
+            );
+            assert_eq!(
+                hex::decode(ciphertext).unwrap(),
+                transform_message[TRANSFORM_HEADER_LENGTH..].to_vec()
+            );
+        }
+    }
+
+    #[test]
+    fn test_decrypt_message() {
+        for cipher in [
+            Ciphers::Aes128Ccm,
+            Ciphers::Aes128Gcm,
+            Ciphers::Aes256Ccm,
+            Ciphers::Aes256Gcm,
+        ]
+        .iter()
+        {
+            let key = vec![0x22; key_length(cipher) as usize / 8];
+            let header = build_transform_header(5, vec![1; 8], cipher);
+            let mut transform_message = encrypt_message(b"hello", header, &key, cipher);
+
+            assert_eq!(
+                Some(b"hello".to_vec()),
+                decrypt_message(&transform_message, &key, cipher)
+            );
+
+            transform_message[AAD_OFFSET] ^= 1;
+            assert_eq!(None, decrypt_message(&transform_message, &key, cipher));
+        }
+    }
+}
@@ -0,0 +1,464 @@
+//! The Kerberos encryption types of RFC 3962 (aes256-cts-hmac-sha1-96 and aes128-cts-hmac-sha1-96)
+//! and RFC 4757 (rc4-hmac). The AES keys are derived from the password with PBKDF2-HMAC-SHA1 and
+//! the simplified profile of RFC 3961, which derives the encryption, integrity and checksum keys
+//! per key usage. The RC4 key is the NT hash of the password.
+
+use aes::cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit};
+use aes::{Aes128, Aes256};
+use hmac::{Hmac, Mac};
+use md5::{Digest, Md5};
+use sha1::Sha1;
+
+use super::ntlm::{hmac_md5, ntowfv1, rc4};
+use crate::kerberos::EncryptionType;
+
+/// The AES block size and the length of the confounder of the AES encryption types.
+const AES_BLOCK_SIZE: usize = 16;
+/// The length of the truncated HMAC-SHA1 of the AES encryption types.
+const AES_MAC_LENGTH: usize = 12;
+/// The length of the confounder of the RC4 encryption type.
+const RC4_CONFOUNDER_LENGTH: usize = 8;
+/// The length of the HMAC-MD5 checksum of the RC4 encryption type.
+const RC4_MAC_LENGTH: usize = 16;
+/// The default PBKDF2 iteration count of the AES string-to-key function.
+pub const DEFAULT_ITERATION_COUNT: u32 = 4096;
+
+/// Derives the long-term key of the principal from the password and the salt, which defaults
+/// to the realm concatenated with the components of the principal name.
+pub fn string_to_key(
+    etype: &EncryptionType,
+    password: &str,
+    salt: &[u8],
+    iteration_count: u32,
+) -> Vec<u8> {
+    match etype {
+        EncryptionType::Aes256CtsHmacSha196 | EncryptionType::Aes128CtsHmacSha196 => {
+            let tkey = pbkdf2_hmac_sha1(
+                password.as_bytes(),
+                salt,
+                iteration_count,
+                etype.key_length(),
+            );
+            derive_key(&tkey, b"kerberos")
+        }
+        EncryptionType::Rc4Hmac => ntowfv1(password),
+    }
+}
+
+/// Encrypts the plaintext with the key for the key usage. The confounder has to have the
+/// length of the confounder of the encryption type, i.e. 16 bytes for AES and 8 bytes for RC4.
+pub fn encrypt(
+    etype: &EncryptionType,
+    key: &[u8],
+    key_usage: u32,
+    plaintext: &[u8],
+    confounder: &[u8],
+) -> Vec<u8> {
+    let mut data = confounder.to_vec();
+    data.extend_from_slice(plaintext);
+
+    match etype {
+        EncryptionType::Aes256CtsHmacSha196 | EncryptionType::Aes128CtsHmacSha196 => {
+            let encryption_key = derive_key(key, &usage_constant(key_usage, 0xaa));
+            let integrity_key = derive_key(key, &usage_constant(key_usage, 0x55));
+
+            let mut cipher = aes_cts_encrypt(&encryption_key, &data);
+            cipher.append(&mut hmac_sha1(&integrity_key, &data)[..AES_MAC_LENGTH].to_vec());
+            cipher
+        }
+        EncryptionType::Rc4Hmac => {
+            let usage_key = hmac_md5(key, &[&translate_rc4_usage(key_usage).to_le_bytes()]);
+            let mut checksum = hmac_md5(&usage_key, &[&data]);
+            let encryption_key = hmac_md5(&usage_key, &[&checksum]);
+
+            checksum.append(&mut rc4(&encryption_key, &data));
+            checksum
+        }
+    }
+}
+
+/// Decrypts the cipher text with the key for the key usage and strips the confounder.
+/// Returns None if the cipher text is too short or its integrity check fails.
+pub fn decrypt(
+    etype: &EncryptionType,
+    key: &[u8],
+    key_usage: u32,
+    cipher: &[u8],
+) -> Option<Vec<u8>> {
+    match etype {
+        EncryptionType::Aes256CtsHmacSha196 | EncryptionType::Aes128CtsHmacSha196 => {
+            if cipher.len() < AES_BLOCK_SIZE + AES_MAC_LENGTH {
+                return None;
+            }
+            let encryption_key = derive_key(key, &usage_constant(key_usage, 0xaa));
+            let integrity_key = derive_key(key, &usage_constant(key_usage, 0x55));
+            let (cipher, mac) = cipher.split_at(cipher.len() - AES_MAC_LENGTH);
+
+            let data = aes_cts_decrypt(&encryption_key, cipher);
+            if hmac_sha1(&integrity_key, &data)[..AES_MAC_LENGTH] != *mac {
+                return None;
+            }
+            Some(data[AES_BLOCK_SIZE..].to_vec())
+        }
+        EncryptionType::Rc4Hmac => {
+            if cipher.len() < RC4_MAC_LENGTH + RC4_CONFOUNDER_LENGTH {
+                return None;
+            }
+            let usage_key = hmac_md5(key, &[&translate_rc4_usage(key_usage).to_le_bytes()]);
+            let (checksum, cipher) = cipher.split_at(RC4_MAC_LENGTH);
+            let encryption_key = hmac_md5(&usage_key, &[checksum]);
+
+            let data = rc4(&encryption_key, cipher);
+            if hmac_md5(&usage_key, &[&data]) != checksum {
+                return None;
+            }
+            Some(data[RC4_CONFOUNDER_LENGTH..].to_vec())
+        }
+    }
+}
+
+/// Calculates the keyed checksum of the encryption type over the data for the key usage:
+/// hmac-sha1-96-aes256/aes128 for AES and hmac-md5 for RC4.
+pub fn checksum(etype: &EncryptionType, key: &[u8], key_usage: u32, data: &[u8]) -> Vec<u8> {
+    match etype {
+        EncryptionType::Aes256CtsHmacSha196 | EncryptionType::Aes128CtsHmacSha196 => {
+            let checksum_key = derive_key(key, &usage_constant(key_usage, 0x99));
+            hmac_sha1(&checksum_key, data)[..AES_MAC_LENGTH].to_vec()
+        }
+        EncryptionType::Rc4Hmac => {
+            let signature_key = hmac_md5(key, &[b"signaturekey\x00"]);
+            let mut hasher = Md5::new();
+            hasher.update(translate_rc4_usage(key_usage).to_le_bytes());
+            hasher.update(data);
+            hmac_md5(&signature_key, &[&hasher.finalize()])
+        }
+    }
+}
+
+/// Returns the length of the confounder of the encryption type.
+pub fn confounder_length(etype: &EncryptionType) -> usize {
+    match etype {
+        EncryptionType::Aes256CtsHmacSha196 | EncryptionType::Aes128CtsHmacSha196 => AES_BLOCK_SIZE,
+        EncryptionType::Rc4Hmac => RC4_CONFOUNDER_LENGTH,
+    }
+}
+
+/// Derives a key from the base key and the constant as specified in RFC 3961 5.1:
+/// DK(Key, Constant) = random-to-key(DR(Key, Constant)), where DR encrypts the n-folded
+/// constant repeatedly. The random-to-key function of AES is the identity.
+pub fn derive_key(base_key: &[u8], constant: &[u8]) -> Vec<u8> {
+    let mut block = n_fold(constant, AES_BLOCK_SIZE);
+    let mut derived_key: Vec<u8> = Vec::new();
+
+    while derived_key.len() < base_key.len() {
+        block = aes_encrypt_block(base_key, &block);
+        derived_key.extend_from_slice(&block);
+    }
+    derived_key.truncate(base_key.len());
+
+    derived_key
+}
+
+/// Stretches or folds the input to the given length in bytes as specified in RFC 3961 5.1:
+/// The input is repeated, each repetition rotated right by 13 bits, up to the least common
+/// multiple of both lengths and the blocks of the output length are added with end-around carry.
+pub fn n_fold(input: &[u8], length: usize) -> Vec<u8> {
+    let lcm = input.len() * length / greatest_common_divisor(input.len(), length);
+    let input_bits = input.len() * 8;
+
+    let mut repeated: Vec<u8> = Vec::with_capacity(lcm);
+    for repetition in 0..lcm / input.len() {
+        let rotation = 13 * repetition % input_bits;
+        for byte_index in 0..input.len() {
+            // The bit that ends up at the most significant bit of the byte after the rotation.
+            let source_bit = (byte_index * 8 + input_bits - rotation) % input_bits;
+            let byte = (0..8).fold(0u8, |byte, bit| {
+                let index = (source_bit + bit) % input_bits;
+                (byte << 1) | ((input[index / 8] >> (7 - index % 8)) & 1)
+            });
+            repeated.push(byte);
+        }
+    }
+
+    let mut folded = vec![0u8; length];
+    for chunk in repeated.chunks(length) {
+        let mut carry: u16 = 0;
+        for index in (0..length).rev() {
+            let sum = folded[index] as u16 + chunk[index] as u16 + carry;
+            folded[index] = sum as u8;
+            carry = sum >> 8;
+        }
+        let mut index = length;
+        while carry != 0 {
+            index = if index == 0 { length - 1 } else { index - 1 };
+            let sum = folded[index] as u16 + carry;
+            folded[index] = sum as u8;
+            carry = sum >> 8;
+        }
+    }
+
+    folded
+}
+
+/// Encrypts the data with AES in CBC mode with ciphertext stealing and a zero IV (RFC 3962 5):
+/// the last two blocks are swapped and the last block is truncated to the length of the data.
+pub fn aes_cts_encrypt(key: &[u8], data: &[u8]) -> Vec<u8> {
+    if data.len() <= AES_BLOCK_SIZE {
+        let mut block = data.to_vec();
+        block.resize(AES_BLOCK_SIZE, 0);
+        return aes_encrypt_block(key, &block);
+    }
+
+    let mut cipher: Vec<u8> = Vec::new();
+    let mut previous = vec![0u8; AES_BLOCK_SIZE];
+    for chunk in data.chunks(AES_BLOCK_SIZE) {
+        let block: Vec<u8> = (0..AES_BLOCK_SIZE)
+            .map(|index| chunk.get(index).unwrap_or(&0) ^ previous[index])
+            .collect();
+        previous = aes_encrypt_block(key, &block);
+        cipher.extend_from_slice(&previous);
+    }
+
+    let last_length = data.len() - (cipher.len() - AES_BLOCK_SIZE);
+    let second_last_start = cipher.len() - 2 * AES_BLOCK_SIZE;
+    let mut stolen = cipher[..second_last_start].to_vec();
+    stolen.extend_from_slice(&cipher[second_last_start + AES_BLOCK_SIZE..]);
+    stolen.extend_from_slice(&cipher[second_last_start..second_last_start + last_length]);
+
+    stolen
+}
+
+/// Decrypts the data that was encrypted with AES in CBC mode with ciphertext stealing and a zero IV.
+pub fn aes_cts_decrypt(key: &[u8], cipher: &[u8]) -> Vec<u8> {
+    if cipher.len() <= AES_BLOCK_SIZE {
+        return aes_decrypt_block(key, cipher);
+    }
+
+    let block_count = cipher.len().div_ceil(AES_BLOCK_SIZE);
+    let second_last_start = (block_count - 2) * AES_BLOCK_SIZE;
+    let last_length = cipher.len() - second_last_start - AES_BLOCK_SIZE;
+
+    let mut data: Vec<u8> = Vec::new();
+    let mut previous = vec![0u8; AES_BLOCK_SIZE];
+    for block in cipher[..second_last_start].chunks(AES_BLOCK_SIZE) {
+        let decrypted = aes_decrypt_block(key, block);
+        data.extend(decrypted.iter().zip(previous.iter()).map(|(a, b)| a ^ b));
+        previous = block.to_vec();
+    }
+
+    let last_cipher = &cipher[second_last_start + AES_BLOCK_SIZE..];
+    let decrypted = aes_decrypt_block(
+        key,
+        &cipher[second_last_start..second_last_start + AES_BLOCK_SIZE],
+    );
+    let mut second_last_cipher = last_cipher.to_vec();
+    second_last_cipher.extend_from_slice(&decrypted[last_length..]);
+
+    let second_last_data = aes_decrypt_block(key, &second_last_cipher);
+    data.extend(
+        second_last_data
+            .iter()
+            .zip(previous.iter())
+            .map(|(a, b)| a ^ b),
+    );
+    data.extend(last_cipher.iter().zip(decrypted.iter()).map(|(a, b)| a ^ b));
+
+    data
+}
+
+/// Encrypts a single block with AES-128 or AES-256, depending on the length of the key.
+fn aes_encrypt_block(key: &[u8], block: &[u8]) -> Vec<u8> {
+    let mut block = GenericArray::clone_from_slice(block);
+    if key.len() == 32 {
+        Aes256::new(GenericArray::from_slice(key)).encrypt_block(&mut block);
+    } else {
+        Aes128::new(GenericArray::from_slice(key)).encrypt_block(&mut block);
+    }
+
+    block.to_vec()
+}
+
+/// Decrypts a single block with AES-128 or AES-256, depending on the length of the key.
+fn aes_decrypt_block(key: &[u8], block: &[u8]) -> Vec<u8> {
+    let mut block = GenericArray::clone_from_slice(block);
+    if key.len() == 32 {
+        Aes256::new(GenericArray::from_slice(key)).decrypt_block(&mut block);
+    } else {
+        Aes128::new(GenericArray::from_slice(key)).decrypt_block(&mut block);
+    }
+
+    block.to_vec()
+}
+
+/// Derives the key from the password and the salt with PBKDF2, using HMAC-SHA1 as the PRF.
+fn pbkdf2_hmac_sha1(password: &[u8], salt: &[u8], iteration_count: u32, length: usize) -> Vec<u8> {
+    let mut derived_key: Vec<u8> = Vec::new();
+    let mut block_index: u32 = 1;
+
+    while derived_key.len() < length {
+        let mut salted = salt.to_vec();
+        salted.extend_from_slice(&block_index.to_be_bytes());
+        let mut u = hmac_sha1(password, &salted);
+        let mut block = u.clone();
+        for _ in 1..iteration_count {
+            u = hmac_sha1(password, &u);
+            block.iter_mut().zip(u.iter()).for_each(|(a, b)| *a ^= b);
+        }
+        derived_key.append(&mut block);
+        block_index += 1;
+    }
+    derived_key.truncate(length);
+
+    derived_key
+}
+
+/// Calculates HMAC-SHA1 of the data.
+fn hmac_sha1(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac =
+        <Hmac<Sha1> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length.");
+    mac.update(data);
+
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Returns the constant of the key usage and the derived key type, e.g. 0xaa for the encryption key.
+fn usage_constant(key_usage: u32, key_type: u8) -> Vec<u8> {
+    let mut constant = key_usage.to_be_bytes().to_vec();
+    constant.push(key_type);
+
+    constant
+}
+
+/// Translates the key usage to the message type of RC4-HMAC (RFC 4757 3):
+/// the encrypted parts of the AS-REP and of the TGS-REP share the message type 8.
+fn translate_rc4_usage(key_usage: u32) -> u32 {
+    match key_usage {
+        3 | 9 => 8,
+        23 => 13,
+        _ => key_usage,
+    }
+}
+
+fn greatest_common_divisor(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        greatest_common_divisor(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_n_fold() {
+        assert_eq!(
+            hex::decode("be072631276b1955").unwrap(),
+            n_fold(b"012345", 8)
+        );
+        assert_eq!(
+            hex::decode("78a07b6caf85fa").unwrap(),
+            n_fold(b"password", 7)
+        );
+        assert_eq!(
+            hex::decode("6b65726265726f737b9b5b2b93132b93").unwrap(),
+            n_fold(b"kerberos", 16)
+        );
+    }
+
+    #[test]
+    fn test_string_to_key() {
+        assert_eq!(
+            hex::decode("42263c6e89f4fc28b8df68ee09799f15").unwrap(),
+            string_to_key(
+                &EncryptionType::Aes128CtsHmacSha196,
+                "password",
+                b"ATHENA.MIT.EDUraeburn",
+                1
+            )
+        );
+        assert_eq!(
+            hex::decode("55a6ac740ad17b4846941051e1e8b0a7548d93b0ab30a8bc3ff16280382b8c2a")
+                .unwrap(),
+            string_to_key(
+                &EncryptionType::Aes256CtsHmacSha196,
+                "password",
+                b"ATHENA.MIT.EDUraeburn",
+                1200
+            )
+        );
+    }
+
+    #[test]
+    fn test_aes_cts() {
+        let key = b"chicken teriyaki";
+        let short = b"I would like the ";
+        let long = b"I would like the General Gau's ";
+
+        assert_eq!(
+            hex::decode("c6353568f2bf8cb4d8a580362da7ff7f97").unwrap(),
+            aes_cts_encrypt(key, short)
+        );
+        assert_eq!(
+            hex::decode("fc00783e0efdb2c1d445d4c8eff7ed2297687268d6ecccc0c07b25e25ecfe5").unwrap(),
+            aes_cts_encrypt(key, long)
+        );
+        assert_eq!(
+            short.to_vec(),
+            aes_cts_decrypt(key, &aes_cts_encrypt(key, short))
+        );
+        assert_eq!(
+            long.to_vec(),
+            aes_cts_decrypt(key, &aes_cts_encrypt(key, long))
+        );
+    }
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        let key: Vec<u8> = (0..32).collect();
+        let cipher = encrypt(
+            &EncryptionType::Aes256CtsHmacSha196,
+            &key,
+            11,
+            b"hello kerberos",
+            &[7; 16],
+        );
+        assert_eq!(
+            hex::decode(
+                "ab21eb638957f4b385948c1d1224f77e7d93eb183b523199581b35c339c4014b9583631b6631366fea60"
+            )
+            .unwrap(),
+            cipher
+        );
+        assert_eq!(
+            Some(b"hello kerberos".to_vec()),
+            decrypt(&EncryptionType::Aes256CtsHmacSha196, &key, 11, &cipher)
+        );
+        assert_eq!(
+            None,
+            decrypt(&EncryptionType::Aes256CtsHmacSha196, &key, 12, &cipher)
+        );
+
+        let rc4_key = string_to_key(&EncryptionType::Rc4Hmac, "secret", b"", 0);
+        let cipher = encrypt(&EncryptionType::Rc4Hmac, &rc4_key, 3, b"ticket", &[1; 8]);
+        assert_eq!(
+            Some(b"ticket".to_vec()),
+            decrypt(&EncryptionType::Rc4Hmac, &rc4_key, 3, &cipher)
+        );
+    }
+
+    #[test]
+    fn test_checksum() {
+        let key: Vec<u8> = (0..32).collect();
+
+        assert_eq!(
+            hex::decode("0131eafe2b2fb9052b6dc299").unwrap(),
+            checksum(&EncryptionType::Aes256CtsHmacSha196, &key, 6, b"req body")
+        );
+        assert_eq!(
+            16,
+            checksum(&EncryptionType::Rc4Hmac, &key[..16], 6, b"req body").len()
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! This module contains the cryptographic primitives of SMB 2 and 3.
+//! Keys are derived from the session key with the SP800-108 key derivation
+//! function in counter mode, using HMAC-SHA256 as the PRF.
+//! For the SMB 3.1.1 dialect, the KDF context is the SHA-512 preauth integrity hash.
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256, Sha512};
+
+pub mod encryption;
+pub mod kerberos;
+pub mod ntlm;
+pub mod signing;
+
+/// The length of the derived keys in bits for the 128-bit algorithms.
+pub const KEY_LENGTH_128: u32 = 128;
+/// The length of the SHA-512 preauth integrity hash in bytes.
+pub const PREAUTH_INTEGRITY_HASH_LENGTH: usize = 64;
+
+/// Derives a key of the given length (in bits) from the key derivation key
+/// with the SP800-108 KDF in counter mode as specified in MS-SMB2 3.1.4.2.
+/// Label and context are passed including their terminating null bytes.
+pub fn derive_key(key: &[u8], label: &[u8], context: &[u8], length: u32) -> Vec<u8> {
+    let mut derived_key: Vec<u8> = Vec::new();
+    let mut counter: u32 = 1;
+
+    while derived_key.len() * 8 < length as usize {
+        let mut prf =
+            Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length.");
+        prf.update(&counter.to_be_bytes());
+        prf.update(label);
+        prf.update(&[0]);
+        prf.update(context);
+        prf.update(&length.to_be_bytes());
+        derived_key.extend_from_slice(&prf.finalize().into_bytes());
+        counter += 1;
+    }
+    derived_key.truncate(length as usize / 8);
+
+    derived_key
+}
+
+/// Continues the preauth integrity hash chain with the message (without the netbios
+/// session prefix) as specified in MS-SMB2 3.2.5.2: H(i) = SHA-512(H(i-1) || message).
+pub fn calculate_preauth_integrity_hash(previous_hash: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha512::new();
+    hasher.update(previous_hash);
+    hasher.update(message);
+
+    hasher.finalize().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_key() {
+        assert_eq!(
+            hex::decode("475ef3a477bf873ce3770ad9fe1ad93e").unwrap(),
+            derive_key(
+                &[0x11; 16],
+                b"SMB2AESCMAC\x00",
+                b"SmbSign\x00",
+                KEY_LENGTH_128
+            )
+        );
+        assert_eq!(32, derive_key(&[0x11; 16], b"label\x00", b"", 256).len());
+    }
+
+    #[test]
+    fn test_calculate_preauth_integrity_hash() {
+        assert_eq!(
+            hex::decode(
+                "044885ca0ef30fb49c4d27b2b3dbcf0742faa5774d10173143d9645772002e93\
+                 4e5e3821779ef432bfc1ca8dde018e8a7bbf47698e49bf90996d999ee4d2d574"
+            )
+            .unwrap(),
+            calculate_preauth_integrity_hash(&[0; PREAUTH_INTEGRITY_HASH_LENGTH], b"hello")
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! NTLMv2 authentication as specified in MS-NLMP 3.3.2.
+//! The response key is derived from the password with NTOWFv2. It proves the knowledge of the
+//! password over the server challenge and the client challenge blob (NTProofStr) and yields the
+//! session base key. With NTLMSSP_NEGOTIATE_KEY_EXCH, the client chooses a random exported
+//! session key and sends it RC4-encrypted with the session base key. The MIC protects the
+//! NEGOTIATE_MESSAGE, CHALLENGE_MESSAGE and AUTHENTICATE_MESSAGE with the exported session key.
+
+use hmac::{Hmac, Mac};
+use md4::{Digest, Md4};
+use md5::Md5;
+
+/// The length of the NTLM keys, the NTProofStr and the MIC in bytes.
+pub const NTLM_KEY_LENGTH: usize = 16;
+
+/// Encodes the string in UTF-16 little endian, the character set of NTLM with NTLMSSP_NEGOTIATE_UNICODE.
+pub fn encode_utf16le(string: &str) -> Vec<u8> {
+    string
+        .encode_utf16()
+        .flat_map(|character| character.to_le_bytes())
+        .collect()
+}
+
+/// Calculates HMAC-MD5 of the concatenated data.
+pub fn hmac_md5(key: &[u8], data: &[&[u8]]) -> Vec<u8> {
+    let mut mac = Hmac::<Md5>::new_from_slice(key).expect("HMAC accepts keys of any length.");
+    for part in data.iter() {
+        mac.update(part);
+    }
+
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Calculates the NT hash of the password: MD4(UNICODE(Password)).
+pub fn ntowfv1(password: &str) -> Vec<u8> {
+    Md4::digest(encode_utf16le(password)).to_vec()
+}
+
+/// Derives the NTLMv2 response key from the password, the user name and the domain name:
+/// HMAC_MD5(MD4(UNICODE(Password)), UNICODE(ConcatenationOf(Uppercase(User), UserDom))).
+pub fn ntowfv2(password: &str, user_name: &str, domain_name: &str) -> Vec<u8> {
+    let identity = encode_utf16le(&format!("{}{}", user_name.to_uppercase(), domain_name));
+
+    hmac_md5(&ntowfv1(password), &[&identity])
+}
+
+/// Calculates the NTProofStr over the server challenge and the serialized client challenge blob (temp):
+/// HMAC_MD5(ResponseKeyNT, ConcatenationOf(CHALLENGE_MESSAGE.ServerChallenge, temp)).
+pub fn compute_nt_proof_str(
+    response_key_nt: &[u8],
+    server_challenge: &[u8],
+    client_challenge_blob: &[u8],
+) -> Vec<u8> {
+    hmac_md5(response_key_nt, &[server_challenge, client_challenge_blob])
+}
+
+/// Calculates the session base key, which is also the key exchange key of NTLMv2:
+/// HMAC_MD5(ResponseKeyNT, NTProofStr).
+pub fn compute_session_base_key(response_key_nt: &[u8], nt_proof_str: &[u8]) -> Vec<u8> {
+    hmac_md5(response_key_nt, &[nt_proof_str])
+}
+
+/// Encrypts the exported session key with the key exchange key for NTLMSSP_NEGOTIATE_KEY_EXCH:
+/// RC4K(KeyExchangeKey, ExportedSessionKey).
+pub fn encrypt_random_session_key(key_exchange_key: &[u8], exported_session_key: &[u8]) -> Vec<u8> {
+    rc4(key_exchange_key, exported_session_key)
+}
+
+/// Calculates the MIC over the three NTLM messages, where the MIC field of the
+/// AUTHENTICATE_MESSAGE is zeroed: HMAC_MD5(ExportedSessionKey, ConcatenationOf(
+/// NEGOTIATE_MESSAGE, CHALLENGE_MESSAGE, AUTHENTICATE_MESSAGE)).
+pub fn compute_mic(
+    exported_session_key: &[u8],
+    negotiate_message: &[u8],
+    challenge_message: &[u8],
+    authenticate_message: &[u8],
+) -> Vec<u8> {
+    hmac_md5(
+        exported_session_key,
+        &[negotiate_message, challenge_message, authenticate_message],
+    )
+}
+
+/// Encrypts or decrypts the data with the RC4 stream cipher.
+pub fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut state: Vec<u8> = (0..=255).collect();
+    let mut j: u8 = 0;
+    for i in 0..256 {
+        j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+        state.swap(i, j as usize);
+    }
+
+    let mut i: u8 = 0;
+    let mut j: u8 = 0;
+    data.iter()
+        .map(|byte| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(state[i as usize]);
+            state.swap(i as usize, j as usize);
+            let key_byte = state[state[i as usize].wrapping_add(state[j as usize]) as usize];
+            byte ^ key_byte
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The NTLMv2 example of MS-NLMP 4.2.4.
+    struct Setup {
+        response_key_nt: Vec<u8>,
+        server_challenge: Vec<u8>,
+        client_challenge_blob: Vec<u8>,
+    }
+
+    impl Setup {
+        fn new() -> Self {
+            let mut client_challenge_blob =
+                hex::decode("01010000000000000000000000000000aaaaaaaaaaaaaaaa00000000").unwrap();
+            client_challenge_blob.append(&mut hex::decode("02000c00").unwrap());
+            client_challenge_blob.append(&mut encode_utf16le("Domain"));
+            client_challenge_blob.append(&mut hex::decode("01000c00").unwrap());
+            client_challenge_blob.append(&mut encode_utf16le("Server"));
+            client_challenge_blob.append(&mut vec![0; 8]);
+
+            Setup {
+                response_key_nt: ntowfv2("Password", "User", "Domain"),
+                server_challenge: hex::decode("0123456789abcdef").unwrap(),
+                client_challenge_blob,
+            }
+        }
+    }
+
+    #[test]
+    fn test_ntowfv2() {
+        assert_eq!(
+            hex::decode("0c868a403bfd7a93a3001ef22ef02e3f").unwrap(),
+            Setup::new().response_key_nt
+        );
+    }
+
+    #[test]
+    fn test_compute_nt_proof_str_and_session_base_key() {
+        let setup = Setup::new();
+        let nt_proof_str = compute_nt_proof_str(
+            &setup.response_key_nt,
+            &setup.server_challenge,
+            &setup.client_challenge_blob,
+        );
+
+        assert_eq!(
+            hex::decode("68cd0ab851e51c96aabc927bebef6a1c").unwrap(),
+            nt_proof_str
+        );
+        assert_eq!(
+            hex::decode("8de40ccadbc14a82f15cb0ad0de95ca3").unwrap(),
+            compute_session_base_key(&setup.response_key_nt, &nt_proof_str)
+        );
+    }
+
+    #[test]
+    fn test_encrypt_random_session_key() {
+        let session_base_key = hex::decode("8de40ccadbc14a82f15cb0ad0de95ca3").unwrap();
+
+        assert_eq!(
+            hex::decode("c5dad2544fc9799094ce1ce90bc9d03e").unwrap(),
+            encrypt_random_session_key(&session_base_key, &[0x55; 16])
+        );
+    }
+}
@@ -0,0 +1,366 @@
+//! SMB2 message signing as specified in MS-SMB2 3.1.4.1.
+//! Depending on the dialect and the SIGNING_CAPABILITIES negotiate context,
+//! messages are signed with HMAC-SHA256, AES-128-CMAC or AES-128-GMAC.
+
+use aes::Aes128;
+use aes_gcm::{
+    aead::{AeadInPlace, KeyInit},
+    Aes128Gcm, Nonce,
+};
+use cmac::Cmac;
+use hmac::{Hmac, Mac};
+use rand::{
+    distributions::{Distribution, Standard},
+    Rng,
+};
+use sha2::Sha256;
+
+use super::{derive_key, KEY_LENGTH_128};
+use crate::format::{convert_byte_array_to_int, decoder::split_compound_response, HEADER_LENGTH};
+use crate::fuzzer::create_random_byte_array_of_predefined_length;
+use crate::smb2::{
+    header::{Commands, Flags},
+    responses::negotiate::DialectRevision,
+};
+
+/// The offset of the signature field in the SMB2 header.
+pub const SIGNATURE_OFFSET: usize = 48;
+/// The length of the signature field in the SMB2 header.
+pub const SIGNATURE_LENGTH: usize = 16;
+
+/// The signing algorithms of SMB2 and SMB3.
+///
+/// *HMAC-SHA256*:
+///     - Used by the SMB 2.0.2 and SMB 2.1 dialects.
+///
+/// *AES-CMAC*:
+///     - Used by the SMB 3.x dialect family.
+///
+/// *AES-GMAC*:
+///     - Used by the SMB 3.1.1 dialect if negotiated via the SIGNING_CAPABILITIES context.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SigningAlgorithm {
+    HmacSha256,
+    AesCmac,
+    AesGmac,
+}
+
+impl SigningAlgorithm {
+    /// Unpacks the byte code of the signing algorithm id (2 bytes).
+    pub fn unpack_byte_code(&self) -> Vec<u8> {
+        match self {
+            SigningAlgorithm::HmacSha256 => b"\x00\x00".to_vec(),
+            SigningAlgorithm::AesCmac => b"\x01\x00".to_vec(),
+            SigningAlgorithm::AesGmac => b"\x02\x00".to_vec(),
+        }
+    }
+
+    /// Maps the byte code of a signing algorithm id to the corresponding signing algorithm.
+    pub fn map_byte_code_to_signing_algorithm(byte_code: Vec<u8>) -> SigningAlgorithm {
+        match byte_code.as_slice() {
+            [0, 0] => SigningAlgorithm::HmacSha256,
+            [1, 0] => SigningAlgorithm::AesCmac,
+            [2, 0] => SigningAlgorithm::AesGmac,
+            _ => panic!("Invalid signing algorithm."),
+        }
+    }
+
+    /// Selects the signing algorithm for the negotiated dialect. For the SMB 3.1.1 dialect,
+    /// the algorithm id the server chose in the SIGNING_CAPABILITIES context takes precedence.
+    pub fn select_signing_algorithm(
+        dialect: &DialectRevision,
+        signing_algorithm_id: Option<&[u8]>,
+    ) -> SigningAlgorithm {
+        match dialect {
+            DialectRevision::Smb202 | DialectRevision::Smb21 | DialectRevision::Wildcard => {
+                SigningAlgorithm::HmacSha256
+            }
+            DialectRevision::Smb30 | DialectRevision::Smb302 => SigningAlgorithm::AesCmac,
+            DialectRevision::Smb311 => match signing_algorithm_id {
+                Some([0, 0]) => SigningAlgorithm::HmacSha256,
+                Some([2, 0]) => SigningAlgorithm::AesGmac,
+                _ => SigningAlgorithm::AesCmac,
+            },
+        }
+    }
+}
+
+impl Distribution<SigningAlgorithm> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> SigningAlgorithm {
+        match rng.gen_range(0..=2) {
+            0 => SigningAlgorithm::HmacSha256,
+            1 => SigningAlgorithm::AesCmac,
+            _ => SigningAlgorithm::AesGmac,
+        }
+    }
+}
+
+/// Derives the signing key from the session key. The SMB 2.x dialects sign with the
+/// session key itself, the SMB 3.x dialects derive the key with the SP800-108 KDF.
+/// For the SMB 3.1.1 dialect, the preauth integrity hash of the session is the KDF context.
+pub fn derive_signing_key(
+    session_key: &[u8],
+    dialect: &DialectRevision,
+    preauth_integrity_hash: &[u8],
+) -> Vec<u8> {
+    match dialect {
+        DialectRevision::Smb202 | DialectRevision::Smb21 | DialectRevision::Wildcard => {
+            session_key.to_vec()
+        }
+        DialectRevision::Smb30 | DialectRevision::Smb302 => derive_key(
+            session_key,
+            b"SMB2AESCMAC\x00",
+            b"SmbSign\x00",
+            KEY_LENGTH_128,
+        ),
+        DialectRevision::Smb311 => derive_key(
+            session_key,
+            b"SMBSigningKey\x00",
+            preauth_integrity_hash,
+            KEY_LENGTH_128,
+        ),
+    }
+}
+
+/// Calculates the signature of a single SMB2 message (without the netbios session prefix).
+/// The signature field of the message is expected to be zero.
+pub fn calculate_signature(
+    message: &[u8],
+    signing_key: &[u8],
+    algorithm: &SigningAlgorithm,
+) -> Vec<u8> {
+    match algorithm {
+        SigningAlgorithm::HmacSha256 => {
+            let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(signing_key)
+                .expect("HMAC accepts keys of any length.");
+            mac.update(message);
+            mac.finalize().into_bytes()[..SIGNATURE_LENGTH].to_vec()
+        }
+        SigningAlgorithm::AesCmac => {
+            let mut mac = <Cmac<Aes128> as Mac>::new_from_slice(signing_key)
+                .expect("AES-CMAC requires a 128-bit signing key.");
+            mac.update(message);
+            mac.finalize().into_bytes().to_vec()
+        }
+        SigningAlgorithm::AesGmac => {
+            let cipher = Aes128Gcm::new_from_slice(signing_key)
+                .expect("AES-GMAC requires a 128-bit signing key.");
+            let nonce = build_gmac_nonce(message);
+            cipher
+                .encrypt_in_place_detached(Nonce::from_slice(&nonce), message, &mut [])
+                .expect("AES-GMAC signing failed.")
+                .to_vec()
+        }
+    }
+}
+
+/// Builds the 12 byte AES-GMAC nonce from the message id of the message. The first bit
+/// of the last four bytes marks responses, the second bit marks cancel requests.
+pub fn build_gmac_nonce(message: &[u8]) -> Vec<u8> {
+    let mut nonce = message[24..32].to_vec();
+    let mut role: u32 = 0;
+
+    if message[16] & Flags::ServerToRedir.unpack_byte_code()[0] != 0 {
+        role |= 0x01;
+    }
+    if message[12..14] == Commands::Cancel.unpack_byte_code()[..] {
+        role |= 0x02;
+    }
+    nonce.append(&mut role.to_le_bytes().to_vec());
+
+    nonce
+}
+
+/// Signs every message of the serialized (compound) request including the netbios session prefix.
+/// The SIGNED flag is set and the signature covers the message up to the next compounded message.
+pub fn sign_request(
+    mut request: Vec<u8>,
+    signing_key: &[u8],
+    algorithm: &SigningAlgorithm,
+) -> Vec<u8> {
+    let mut offset = 4;
+
+    while request.len() >= offset + HEADER_LENGTH {
+        let next_command =
+            convert_byte_array_to_int(request[offset + 20..offset + 24].to_vec(), false) as usize;
+        let end = if next_command >= HEADER_LENGTH && offset + next_command <= request.len() {
+            offset + next_command
+        } else {
+            request.len()
+        };
+
+        request[offset + 16] |= Flags::Signed.unpack_byte_code()[0];
+        let signature_range =
+            offset + SIGNATURE_OFFSET..offset + SIGNATURE_OFFSET + SIGNATURE_LENGTH;
+        request[signature_range.clone()].copy_from_slice(&[0; SIGNATURE_LENGTH]);
+        let signature = calculate_signature(&request[offset..end], signing_key, algorithm);
+        request[signature_range].copy_from_slice(&signature);
+
+        if end == request.len() {
+            break;
+        }
+        offset = end;
+    }
+
+    request
+}
+
+/// Verifies the signature of a single SMB2 message (without the netbios session prefix).
+pub fn verify_signature(message: &[u8], signing_key: &[u8], algorithm: &SigningAlgorithm) -> bool {
+    if message.len() < HEADER_LENGTH {
+        return false;
+    }
+
+    let mut unsigned_message = message.to_vec();
+    unsigned_message[SIGNATURE_OFFSET..SIGNATURE_OFFSET + SIGNATURE_LENGTH]
+        .copy_from_slice(&[0; SIGNATURE_LENGTH]);
+
+    calculate_signature(&unsigned_message, signing_key, algorithm)
+        == message[SIGNATURE_OFFSET..SIGNATURE_OFFSET + SIGNATURE_LENGTH]
+}
+
+/// Verifies the signatures of all signed messages in the (compound) response
+/// including the netbios session prefix. Unsigned messages are not checked.
+pub fn verify_response(response: &[u8], signing_key: &[u8], algorithm: &SigningAlgorithm) -> bool {
+    if response.len() < 4 + HEADER_LENGTH {
+        return true;
+    }
+
+    split_compound_response(&response[4..])
+        .iter()
+        .filter(|message| message[16] & Flags::Signed.unpack_byte_code()[0] != 0)
+        .all(|message| verify_signature(message, signing_key, algorithm))
+}
+
+/// The deliberate violations of the signature of a request.
+///
+/// *Zeroed*:
+///     - The SIGNED flag is set, but the signature is zero.
+///
+/// *Random*:
+///     - The signature is replaced by random bytes.
+///
+/// *Bit Flip*:
+///     - A single bit of the signature is flipped.
+///
+/// *Unsigned*:
+///     - The signature is kept, but the SIGNED flag is cleared.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SignatureViolation {
+    Zeroed,
+    Random,
+    BitFlip,
+    Unsigned,
+}
+
+impl SignatureViolation {
+    /// Maps a user input string to a signature violation.
+    pub fn map_string_to_signature_violation(violation: &str) -> Self {
+        match violation {
+            "zero" => SignatureViolation::Zeroed,
+            "random" => SignatureViolation::Random,
+            "flip" => SignatureViolation::BitFlip,
+            "unsigned" => SignatureViolation::Unsigned,
+            _ => panic!("Invalid signature violation."),
+        }
+    }
+}
+
+/// Applies the signature violation to the first message of the signed request
+/// including the netbios session prefix.
+pub fn apply_signature_violation(mut request: Vec<u8>, violation: &SignatureViolation) -> Vec<u8> {
+    if request.len() < 4 + HEADER_LENGTH {
+        return request;
+    }
+
+    let signed_flag = Flags::Signed.unpack_byte_code()[0];
+    let signature_range = 4 + SIGNATURE_OFFSET..4 + SIGNATURE_OFFSET + SIGNATURE_LENGTH;
+    match violation {
+        SignatureViolation::Zeroed => {
+            request[4 + 16] |= signed_flag;
+            request[signature_range].copy_from_slice(&[0; SIGNATURE_LENGTH]);
+        }
+        SignatureViolation::Random => {
+            request[4 + 16] |= signed_flag;
+            request[signature_range].copy_from_slice(
+                &create_random_byte_array_of_predefined_length(SIGNATURE_LENGTH as u32),
+            );
+        }
+        SignatureViolation::BitFlip => {
+            let mut rng = rand::thread_rng();
+            request[4 + 16] |= signed_flag;
+            request[signature_range.start + rng.gen_range(0..SIGNATURE_LENGTH)] ^=
+                1 << rng.gen_range(0..8);
+        }
+        SignatureViolation::Unsigned => request[4 + 16] &= !signed_flag,
+    }
+
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        builder::build_sync_header,
+        format::encoder::serialize_request,
+        smb2::requests::{echo::Echo, RequestType},
+    };
+
+    fn build_echo_request() -> Vec<u8> {
+        let header = build_sync_header(Commands::Echo, 1, 1, None, Some(vec![1; 8]), 6);
+
+        serialize_request(&header, &RequestType::Echo(Echo::default()))
+    }
+
+    #[test]
+    fn test_sign_request() {
+        let signing_key = [0x22; 16];
+        let expected_signatures = [
+            (
+                SigningAlgorithm::HmacSha256,
+                "de9fd5ba38b884a586a407f9bf0a0619",
+            ),
+            (
+                SigningAlgorithm::AesCmac,
+                "43f32c202697b9b67eaa53fa0ec212b7",
+            ),
+            (
+                SigningAlgorithm::AesGmac,
+                "69ec09c0593ca1a4885a36543bb78c32",
+            ),
+        ];
+
+        for (algorithm, expected_signature) in expected_signatures.iter() {
+            let signed_request = sign_request(build_echo_request(), &signing_key, algorithm);
+
+            assert_eq!(0x18, signed_request[4 + 16]);
+            assert_eq!(
+                hex::decode(expected_signature).unwrap(),
+                signed_request[52..68].to_vec()
+            );
+            assert!(verify_response(&signed_request, &signing_key, algorithm));
+            assert!(!verify_response(
+                &apply_signature_violation(signed_request, &SignatureViolation::BitFlip),
+                &signing_key,
+                algorithm
+            ));
+        }
+    }
+
+    #[test]
+    fn test_select_signing_algorithm() {
+        assert_eq!(
+            SigningAlgorithm::HmacSha256,
+            SigningAlgorithm::select_signing_algorithm(&DialectRevision::Smb21, Some(&[2, 0]))
+        );
+        assert_eq!(
+            SigningAlgorithm::AesCmac,
+            SigningAlgorithm::select_signing_algorithm(&DialectRevision::Smb311, None)
+        );
+        assert_eq!(
+            SigningAlgorithm::AesGmac,
+            SigningAlgorithm::select_signing_algorithm(&DialectRevision::Smb311, Some(&[2, 0]))
+        );
+    }
+}
@@ -1,18 +1,37 @@
 use crate::smb2::{header, responses};
 
-use crate::format::{decoder::negotiate_decoder::decode_negotiate_response_body, HEADER_LENGTH};
+use crate::format::{
+    convert_byte_array_to_int, decoder::negotiate_decoder::decode_negotiate_response_body,
+    HEADER_LENGTH, TRANSFORM_HEADER_LENGTH,
+};
 
-use self::session_setup_decoder::decode_session_setup_response_body;
+use self::{
+    change_notify_decoder::decode_change_notify_response_body,
+    error_decoder::decode_error_response_body,
+    oplock_break_decoder::decode_break_notification_body,
+    query_info_decoder::decode_query_info_response_body,
+    session_setup_decoder::decode_session_setup_response_body,
+};
 
+pub mod change_notify_decoder;
+pub mod compression_decoder;
 pub mod create_decoder;
+pub mod error_decoder;
+pub mod flush_decoder;
+pub mod kerberos_decoder;
+pub mod lock_decoder;
 pub mod negotiate_decoder;
+pub mod oplock_break_decoder;
+pub mod query_info_decoder;
 pub mod security_blob_decoder;
 pub mod session_setup_decoder;
+pub mod spnego_decoder;
 
 /// Decodes the complete negotiate response from the server.
+/// The body is None if it is malformed.
 pub fn decode_negotiate_response(
     encoded_response: Vec<u8>,
-) -> (header::SyncHeader, responses::negotiate::Negotiate) {
+) -> (header::SyncHeader, Option<responses::negotiate::Negotiate>) {
     (
         decode_response_header(encoded_response[4..HEADER_LENGTH + 4].to_vec()),
         decode_negotiate_response_body(encoded_response[HEADER_LENGTH + 4..].to_vec()),
@@ -29,6 +48,172 @@ pub fn decode_session_setup_response(
     )
 }
 
+/// Decodes the complete query info response from the server.
+pub fn decode_query_info_response(
+    encoded_response: Vec<u8>,
+) -> (header::SyncHeader, responses::query_info::QueryInfo) {
+    (
+        decode_response_header(encoded_response[4..HEADER_LENGTH + 4].to_vec()),
+        decode_query_info_response_body(encoded_response[HEADER_LENGTH + 4..].to_vec()),
+    )
+}
+
+/// Decodes the interim response with which the server announces the asynchronous
+/// processing of a request.
+pub fn decode_interim_response(
+    encoded_response: Vec<u8>,
+) -> (header::AsyncHeader, responses::error::Error) {
+    (
+        decode_async_response_header(encoded_response[4..HEADER_LENGTH + 4].to_vec()),
+        decode_error_response_body(encoded_response[HEADER_LENGTH + 4..].to_vec()),
+    )
+}
+
+/// Decodes the complete change notify response from the server.
+pub fn decode_change_notify_response(
+    encoded_response: Vec<u8>,
+) -> (header::AsyncHeader, responses::change_notify::ChangeNotify) {
+    (
+        decode_async_response_header(encoded_response[4..HEADER_LENGTH + 4].to_vec()),
+        decode_change_notify_response_body(encoded_response[HEADER_LENGTH + 4..].to_vec()),
+    )
+}
+
+/// Decodes the oplock or lease break notification the server sent unsolicited.
+pub fn decode_break_notification(
+    encoded_response: Vec<u8>,
+) -> (
+    header::SyncHeader,
+    responses::oplock_break::BreakNotification,
+) {
+    (
+        decode_response_header(encoded_response[4..HEADER_LENGTH + 4].to_vec()),
+        decode_break_notification_body(encoded_response[HEADER_LENGTH + 4..].to_vec()),
+    )
+}
+
+/// Decodes the compound response from the server. Each response of the chain
+/// is returned with its decoded sync or async header and its encoded body.
+pub fn decode_compound_response(encoded_response: Vec<u8>) -> Vec<(header::Header, Vec<u8>)> {
+    split_compound_response(&encoded_response[4..])
+        .into_iter()
+        .map(|response| {
+            (
+                decode_header(response[..HEADER_LENGTH].to_vec()),
+                response[HEADER_LENGTH..].to_vec(),
+            )
+        })
+        .collect()
+}
+
+/// Splits the compound response (without the netbios session prefix) along the NextCommand
+/// fields of its headers. The last response extends to the end of the frame.
+/// Splitting stops at a NextCommand field that does not point to a complete header.
+pub fn split_compound_response(encoded_responses: &[u8]) -> Vec<Vec<u8>> {
+    let mut responses: Vec<Vec<u8>> = Vec::new();
+    let mut remaining_responses = encoded_responses;
+
+    while remaining_responses.len() >= HEADER_LENGTH {
+        let next_command = convert_byte_array_to_int(remaining_responses[20..24].to_vec(), false);
+        let next_command = next_command as usize;
+
+        if next_command < HEADER_LENGTH || next_command + HEADER_LENGTH > remaining_responses.len()
+        {
+            responses.push(remaining_responses.to_vec());
+            break;
+        }
+        responses.push(remaining_responses[..next_command].to_vec());
+        remaining_responses = &remaining_responses[next_command..];
+    }
+
+    responses
+}
+
+/// Checks whether the encoded response (including the netbios session prefix) is an
+/// oplock or lease break notification. The server sends these unsolicited
+/// with the MessageId 0xFFFFFFFFFFFFFFFF.
+pub fn is_break_notification(encoded_response: &[u8]) -> bool {
+    encoded_response.len() >= HEADER_LENGTH + 4
+        && encoded_response[16..18] == header::Commands::OplockBreak.unpack_byte_code()[..]
+        && encoded_response[28..36] == [0xff; 8]
+}
+
+/// Checks whether the SMB2_FLAGS_ASYNC_COMMAND flag is set in the encoded header.
+pub fn is_async_header(encoded_header: &[u8]) -> bool {
+    encoded_header[16] & 0x02 != 0
+}
+
+/// Checks whether the encoded response (including the netbios session prefix) is an
+/// interim response that announces the asynchronous processing of a request.
+pub fn is_interim_response(encoded_response: &[u8]) -> bool {
+    encoded_response.len() >= HEADER_LENGTH + 4
+        && is_async_header(&encoded_response[4..])
+        && encoded_response[12..16] == header::STATUS_PENDING[..]
+}
+
+/// Decodes the sync or async SMB header of server responses depending on the ASYNC_COMMAND flag.
+pub fn decode_header(encoded_header: Vec<u8>) -> header::Header {
+    if is_async_header(&encoded_header) {
+        header::Header::Async(decode_async_response_header(encoded_header))
+    } else {
+        header::Header::Sync(decode_response_header(encoded_header))
+    }
+}
+
+/// Decodes the SMB Async Header of server responses.
+pub fn decode_async_response_header(encoded_header: Vec<u8>) -> header::AsyncHeader {
+    let mut response_header = header::AsyncHeader::default();
+
+    response_header.generic.protocol_id = encoded_header[..4].to_vec();
+    response_header.generic.structure_size = encoded_header[4..6].to_vec();
+    response_header.generic.credit_charge = encoded_header[6..8].to_vec();
+    response_header.generic.status = encoded_header[8..12].to_vec();
+    response_header.generic.command = encoded_header[12..14].to_vec();
+    response_header.generic.credit = encoded_header[14..16].to_vec();
+    response_header.generic.flags = encoded_header[16..20].to_vec();
+    response_header.generic.next_command = encoded_header[20..24].to_vec();
+    response_header.generic.message_id = encoded_header[24..32].to_vec();
+    response_header.async_id = encoded_header[32..40].to_vec();
+    response_header.session_id = encoded_header[40..48].to_vec();
+    response_header.signature = encoded_header[48..64].to_vec();
+
+    response_header
+}
+
+/// Checks whether the message (without the netbios session prefix) starts with an SMB2 TRANSFORM_HEADER.
+pub fn is_transform_message(encoded_message: &[u8]) -> bool {
+    encoded_message.len() >= TRANSFORM_HEADER_LENGTH
+        && encoded_message[..4] == header::TRANSFORM_PROTOCOL_ID[..]
+}
+
+/// Decodes the SMB2 TRANSFORM_HEADER of encrypted messages.
+pub fn decode_transform_header(encoded_header: Vec<u8>) -> header::TransformHeader {
+    let mut transform_header = header::TransformHeader::default();
+
+    transform_header.protocol_id = encoded_header[..4].to_vec();
+    transform_header.signature = encoded_header[4..20].to_vec();
+    transform_header.nonce = encoded_header[20..36].to_vec();
+    transform_header.original_message_size = encoded_header[36..40].to_vec();
+    transform_header.reserved = encoded_header[40..42].to_vec();
+    transform_header.flags = encoded_header[42..44].to_vec();
+    transform_header.session_id = encoded_header[44..52].to_vec();
+
+    transform_header
+}
+
+/// Decodes the SMB Sync Header of client requests.
+/// Unlike in responses, the status bytes hold the channel sequence and the reserved field.
+pub fn decode_request_header(encoded_header: Vec<u8>) -> header::SyncHeader {
+    let mut request_header = decode_response_header(encoded_header.clone());
+
+    request_header.generic.status = Vec::new();
+    request_header.generic.channel_sequence = encoded_header[8..10].to_vec();
+    request_header.generic.reserved = encoded_header[10..12].to_vec();
+    request_header.reserved = encoded_header[32..36].to_vec();
+
+    request_header
+}
+
 /// Decodes the SMB Sync Header of server responses.
 pub fn decode_response_header(encoded_header: Vec<u8>) -> header::SyncHeader {
     let mut response_header = header::SyncHeader::default();
@@ -100,4 +285,38 @@ mod tests {
 
         assert_eq!(vec![0; 16], decoded_header.signature);
     }
+
+    #[test]
+    fn test_decode_async_response_header() {
+        let encoded_header: Vec<u8> = vec![
+            b"\xfe\x53\x4d\x42\x40\x00\x01\x00\x03\x01\x00\x00\x0f\x00\x01\x00".to_vec(),
+            b"\x03\x00\x00\x00\x00\x00\x00\x00\x0a\x00\x00\x00\x00\x00\x00\x00".to_vec(),
+            b"\x2a\x00\x00\x00\x00\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00".to_vec(),
+            b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00".to_vec(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        assert!(is_async_header(&encoded_header));
+        assert!(matches!(
+            decode_header(encoded_header.clone()),
+            header::Header::Async(_)
+        ));
+
+        let decoded_header = decode_async_response_header(encoded_header);
+
+        assert_eq!(
+            header::STATUS_PENDING.to_vec(),
+            decoded_header.generic.status
+        );
+        assert_eq!(vec![0x0f, 0], decoded_header.generic.command);
+        assert_eq!(
+            vec![10, 0, 0, 0, 0, 0, 0, 0],
+            decoded_header.generic.message_id
+        );
+        assert_eq!(vec![42, 0, 0, 0, 0, 0, 0, 0], decoded_header.async_id);
+        assert_eq!(vec![1, 0, 0, 0, 0, 0, 0, 0], decoded_header.session_id);
+        assert_eq!(vec![0; 16], decoded_header.signature);
+    }
 }
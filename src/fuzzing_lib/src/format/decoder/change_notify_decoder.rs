@@ -0,0 +1,85 @@
+use crate::smb2::responses::{self, change_notify::FileNotifyInformation};
+
+use crate::format::convert_byte_array_to_int;
+
+/// Takes the little endian encoded change notify response from the server and populates the corresponding
+/// Change Notify Response struct.
+///
+/// Note: The output buffer is decoded separately.
+pub fn decode_change_notify_response_body(
+    encoded_body: Vec<u8>,
+) -> responses::change_notify::ChangeNotify {
+    let mut change_notify_response = responses::change_notify::ChangeNotify::default();
+
+    change_notify_response.structure_size = encoded_body[..2].to_vec();
+    change_notify_response.output_buffer_offset = encoded_body[2..4].to_vec();
+    change_notify_response.output_buffer_length = encoded_body[4..8].to_vec();
+    change_notify_response.buffer = encoded_body[8..].to_vec();
+
+    change_notify_response
+}
+
+/// Decodes the chained FILE_NOTIFY_INFORMATION entries of a change notify output buffer.
+/// Decoding stops at the last entry, at a truncated entry or at a next entry offset
+/// that does not advance through the buffer.
+pub fn decode_file_notify_information(buffer: Vec<u8>) -> Vec<FileNotifyInformation> {
+    let mut entries: Vec<FileNotifyInformation> = Vec::new();
+    let mut entry_offset: usize = 0;
+
+    while buffer.len() >= entry_offset + 12 {
+        let entry = &buffer[entry_offset..];
+        let file_name_length = convert_byte_array_to_int(entry[8..12].to_vec(), false) as usize;
+        let next_entry_offset = convert_byte_array_to_int(entry[..4].to_vec(), false) as usize;
+
+        entries.push(FileNotifyInformation {
+            next_entry_offset: entry[..4].to_vec(),
+            action: entry[4..8].to_vec(),
+            file_name_length: entry[8..12].to_vec(),
+            file_name: entry[12..(12 + file_name_length).min(entry.len())].to_vec(),
+        });
+
+        if next_entry_offset == 0 {
+            break;
+        }
+        entry_offset += next_entry_offset;
+    }
+
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_change_notify_response_body() {
+        let mut expected_response = responses::change_notify::ChangeNotify::default();
+        expected_response.output_buffer_offset = vec![0x48, 0];
+        expected_response.output_buffer_length = vec![0; 4];
+        expected_response.buffer = vec![0];
+
+        assert_eq!(
+            expected_response,
+            decode_change_notify_response_body(b"\x09\x00\x48\x00\x00\x00\x00\x00\x00".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_decode_file_notify_information() {
+        let buffer: Vec<u8> = vec![
+            b"\x10\x00\x00\x00\x01\x00\x00\x00\x02\x00\x00\x00\x61\x00\x00\x00".to_vec(),
+            b"\x00\x00\x00\x00\x03\x00\x00\x00\x04\x00\x00\x00\x62\x00\x63\x00".to_vec(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let entries = decode_file_notify_information(buffer);
+
+        assert_eq!(2, entries.len());
+        assert_eq!(vec![1, 0, 0, 0], entries[0].action);
+        assert_eq!(vec![0x61, 0], entries[0].file_name);
+        assert_eq!(vec![3, 0, 0, 0], entries[1].action);
+        assert_eq!(vec![0x62, 0, 0x63, 0], entries[1].file_name);
+    }
+}
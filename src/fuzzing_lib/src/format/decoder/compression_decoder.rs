@@ -0,0 +1,128 @@
+use crate::compression::{has_original_payload_size, map_byte_code_to_algorithm};
+use crate::format::convert_byte_array_to_int;
+use crate::smb2::compression_transform::{
+    ChainedCompressionTransform, ChainedPayload, CompressionTransform,
+    UnchainedCompressionTransform, COMPRESSION_FLAG_CHAINED, COMPRESSION_PROTOCOL_ID,
+};
+
+/// The length of the compression transform header in bytes.
+pub const COMPRESSION_TRANSFORM_HEADER_LENGTH: usize = 16;
+/// The length of the chained compression transform header in bytes.
+const CHAINED_HEADER_LENGTH: usize = 8;
+/// The length of a chained payload header without the original payload size in bytes.
+const CHAINED_PAYLOAD_HEADER_LENGTH: usize = 8;
+
+/// Checks whether the message (without the netbios session prefix) starts with an SMB2 COMPRESSION_TRANSFORM_HEADER.
+pub fn is_compressed_message(encoded_message: &[u8]) -> bool {
+    encoded_message.len() >= CHAINED_HEADER_LENGTH
+        && encoded_message[..4] == COMPRESSION_PROTOCOL_ID[..]
+}
+
+/// Decodes the compression transform of a compressed message. The transform is chained
+/// if the flags of the first payload header are set to SMB2_COMPRESSION_FLAG_CHAINED.
+/// Returns None if the message is too short for its headers.
+pub fn decode_compression_transform(encoded_message: Vec<u8>) -> Option<CompressionTransform> {
+    if encoded_message.get(10..12)? == &COMPRESSION_FLAG_CHAINED[..] {
+        decode_chained_compression_transform(encoded_message).map(CompressionTransform::Chained)
+    } else {
+        decode_unchained_compression_transform(encoded_message).map(CompressionTransform::Unchained)
+    }
+}
+
+/// Decodes the unchained compression transform header and splits the data at the offset.
+pub fn decode_unchained_compression_transform(
+    encoded_message: Vec<u8>,
+) -> Option<UnchainedCompressionTransform> {
+    let mut transform = UnchainedCompressionTransform::default();
+
+    transform.protocol_id = encoded_message.get(..4)?.to_vec();
+    transform.original_compressed_segment_size = encoded_message.get(4..8)?.to_vec();
+    transform.compression_algorithm = encoded_message.get(8..10)?.to_vec();
+    transform.flags = encoded_message.get(10..12)?.to_vec();
+    transform.offset = encoded_message.get(12..16)?.to_vec();
+
+    let data_start = COMPRESSION_TRANSFORM_HEADER_LENGTH
+        + convert_byte_array_to_int(transform.offset.clone(), false) as usize;
+    transform.uncompressed_data = encoded_message
+        .get(COMPRESSION_TRANSFORM_HEADER_LENGTH..data_start)?
+        .to_vec();
+    transform.compressed_data = encoded_message[data_start..].to_vec();
+
+    Some(transform)
+}
+
+/// Decodes the chained compression transform header and all of its payloads.
+pub fn decode_chained_compression_transform(
+    encoded_message: Vec<u8>,
+) -> Option<ChainedCompressionTransform> {
+    let mut transform = ChainedCompressionTransform::default();
+
+    transform.protocol_id = encoded_message.get(..4)?.to_vec();
+    transform.original_compressed_segment_size = encoded_message.get(4..8)?.to_vec();
+
+    let mut position = CHAINED_HEADER_LENGTH;
+    while position < encoded_message.len() {
+        let mut payload = ChainedPayload::default();
+
+        payload.compression_algorithm = encoded_message.get(position..position + 2)?.to_vec();
+        payload.flags = encoded_message.get(position + 2..position + 4)?.to_vec();
+        payload.length = encoded_message.get(position + 4..position + 8)?.to_vec();
+        position += CHAINED_PAYLOAD_HEADER_LENGTH;
+
+        let payload_end =
+            position + convert_byte_array_to_int(payload.length.clone(), false) as usize;
+        let mut data = encoded_message.get(position..payload_end)?.to_vec();
+        let carries_original_payload_size =
+            map_byte_code_to_algorithm(&payload.compression_algorithm)
+                .is_some_and(|algorithm| has_original_payload_size(&algorithm));
+        if carries_original_payload_size {
+            if data.len() < 4 {
+                return None;
+            }
+            payload.original_payload_size = data.drain(..4).collect();
+        }
+        payload.payload_data = data;
+        position = payload_end;
+
+        transform.payloads.push(payload);
+    }
+
+    Some(transform)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::{
+        build_chained_compression_transform, build_unchained_compression_transform,
+    };
+    use crate::format::encoder::compression_encoder::serialize_compression_transform;
+    use crate::smb2::helper_functions::negotiate_context::CompressionAlgorithms;
+
+    #[test]
+    fn test_decode_compression_transform() {
+        let mut message = vec![0; 64];
+        message.append(&mut b"compress me, compress me, compress me".to_vec());
+
+        let unchained = CompressionTransform::Unchained(build_unchained_compression_transform(
+            &message,
+            &CompressionAlgorithms::Lznt1,
+            64,
+        ));
+        assert_eq!(
+            Some(unchained.clone()),
+            decode_compression_transform(serialize_compression_transform(&unchained))
+        );
+
+        let chained = CompressionTransform::Chained(build_chained_compression_transform(
+            &message,
+            &CompressionAlgorithms::Lz77,
+        ));
+        assert_eq!(
+            Some(chained.clone()),
+            decode_compression_transform(serialize_compression_transform(&chained))
+        );
+
+        assert_eq!(None, decode_compression_transform(vec![0xfc, 0x53]));
+    }
+}
@@ -0,0 +1,31 @@
+use crate::smb2::responses;
+
+/// Takes the little endian encoded error response from the server and populates the corresponding
+/// Error Response struct.
+pub fn decode_error_response_body(encoded_body: Vec<u8>) -> responses::error::Error {
+    let mut error_response = responses::error::Error::default();
+
+    error_response.structure_size = encoded_body[..2].to_vec();
+    error_response.error_context_count = encoded_body[2..3].to_vec();
+    error_response.reserved = encoded_body[3..4].to_vec();
+    error_response.byte_count = encoded_body[4..8].to_vec();
+    error_response.error_data = encoded_body[8..].to_vec();
+
+    error_response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_error_response_body() {
+        let mut expected_response = responses::error::Error::default();
+        expected_response.error_data = vec![0];
+
+        assert_eq!(
+            expected_response,
+            decode_error_response_body(b"\x09\x00\x00\x00\x00\x00\x00\x00\x00".to_vec())
+        );
+    }
+}
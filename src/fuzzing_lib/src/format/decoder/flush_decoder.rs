@@ -0,0 +1,25 @@
+use crate::smb2::responses;
+
+/// Takes the little endian encoded flush response from the server and populates the corresponding
+/// Flush Response struct.
+pub fn decode_flush_response_body(encoded_body: Vec<u8>) -> responses::flush::Flush {
+    let mut flush_response = responses::flush::Flush::default();
+
+    flush_response.structure_size = encoded_body[..2].to_vec();
+    flush_response.reserved = encoded_body[2..4].to_vec();
+
+    flush_response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_flush_response_body() {
+        assert_eq!(
+            responses::flush::Flush::default(),
+            decode_flush_response_body(b"\x04\x00\x00\x00".to_vec())
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! Decodes the ASN.1 DER encoded Kerberos messages the client receives from the KDC and the server.
+//! Malformed messages are rejected with None instead of being sliced blindly.
+
+use super::spnego_decoder::{
+    decode_der_element, decode_der_elements, decode_fields, decode_primitive,
+};
+use crate::format::encoder::{
+    kerberos_encoder::{
+        AP_REP_TAG, AS_REP_TAG, ENC_AP_REP_PART_TAG, ENC_AS_REP_PART_TAG, ENC_TGS_REP_PART_TAG,
+        KRB_ERROR_TAG, TGS_REP_TAG, TICKET_TAG,
+    },
+    spnego_encoder::{INITIAL_CONTEXT_TOKEN_TAG, OID_TAG, SEQUENCE_TAG},
+};
+use crate::gss::KERBEROS_OID;
+use crate::kerberos::{
+    EncApRepPart, EncKdcRepPart, EncryptedData, EncryptionKey, EtypeInfo2Entry, KdcRep, KrbError,
+    PaData, PrincipalName, Ticket, PA_ETYPE_INFO2,
+};
+
+/// Decodes the AS-REP or the TGS-REP.
+pub fn decode_kdc_rep(buffer: &[u8]) -> Option<KdcRep> {
+    let content = match decode_der_element(buffer)? {
+        (AS_REP_TAG, content) | (TGS_REP_TAG, content) => content,
+        _ => return None,
+    };
+    let mut reply = KdcRep::default();
+
+    for (field, value) in decode_fields(content)? {
+        match field {
+            0 => reply.pvno = decode_primitive(value)?,
+            1 => reply.msg_type = decode_primitive(value)?,
+            2 => reply.padata = decode_padata_sequence(value)?,
+            3 => reply.crealm = decode_primitive(value)?,
+            4 => reply.cname = decode_principal_name(value)?,
+            5 => reply.ticket = decode_ticket(value)?,
+            6 => reply.enc_part = decode_encrypted_data(value)?,
+            _ => {}
+        }
+    }
+
+    Some(reply)
+}
+
+/// Decodes the decrypted EncASRepPart or EncTGSRepPart.
+pub fn decode_enc_kdc_rep_part(buffer: &[u8]) -> Option<EncKdcRepPart> {
+    let content = match decode_der_element(buffer)? {
+        (ENC_AS_REP_PART_TAG, content) | (ENC_TGS_REP_PART_TAG, content) => content,
+        _ => return None,
+    };
+    let mut part = EncKdcRepPart::default();
+
+    for (field, value) in decode_fields(content)? {
+        match field {
+            0 => part.key = decode_encryption_key(value)?,
+            2 => part.nonce = decode_primitive(value)?,
+            _ => {}
+        }
+    }
+
+    Some(part)
+}
+
+/// Decodes the KRB-ERROR.
+pub fn decode_krb_error(buffer: &[u8]) -> Option<KrbError> {
+    let content = match decode_der_element(buffer)? {
+        (KRB_ERROR_TAG, content) => content,
+        _ => return None,
+    };
+    let mut error = KrbError::default();
+
+    for (field, value) in decode_fields(content)? {
+        match field {
+            6 => error.error_code = decode_primitive(value)?,
+            11 => error.e_text = decode_primitive(value)?,
+            12 => error.e_data = decode_primitive(value)?,
+            _ => {}
+        }
+    }
+
+    Some(error)
+}
+
+/// Decodes the ETYPE-INFO2 from the METHOD-DATA in the e-data of a KRB-ERROR.
+/// Returns an empty list if the KDC sent no ETYPE-INFO2.
+pub fn decode_etype_info2(method_data: &[u8]) -> Option<Vec<EtypeInfo2Entry>> {
+    let padata = decode_padata_sequence(method_data)?;
+    let etype_info2 = match padata
+        .iter()
+        .find(|padata| padata.padata_type == [PA_ETYPE_INFO2])
+    {
+        Some(padata) => &padata.padata_value,
+        None => return Some(Vec::new()),
+    };
+
+    let entries = match decode_der_element(etype_info2)? {
+        (SEQUENCE_TAG, entries) => decode_der_elements(entries)?,
+        _ => return None,
+    };
+    let mut etype_info = Vec::new();
+    for (_, entry) in entries {
+        let mut info = EtypeInfo2Entry::default();
+        for (field, value) in decode_sequence_content(entry)? {
+            match field {
+                0 => info.etype = decode_primitive(value)?,
+                1 => info.salt = decode_primitive(value)?,
+                2 => info.s2kparams = decode_primitive(value)?,
+                _ => {}
+            }
+        }
+        etype_info.push(info);
+    }
+
+    Some(etype_info)
+}
+
+/// Decodes the AP-REP and returns its encrypted part.
+pub fn decode_ap_rep(buffer: &[u8]) -> Option<EncryptedData> {
+    let content = match decode_der_element(buffer)? {
+        (AP_REP_TAG, content) => content,
+        _ => return None,
+    };
+
+    decode_fields(content)?
+        .into_iter()
+        .find(|(field, _)| *field == 2)
+        .and_then(|(_, value)| decode_encrypted_data(value))
+}
+
+/// Decodes the decrypted EncAPRepPart.
+pub fn decode_enc_ap_rep_part(buffer: &[u8]) -> Option<EncApRepPart> {
+    let content = match decode_der_element(buffer)? {
+        (ENC_AP_REP_PART_TAG, content) => content,
+        _ => return None,
+    };
+    let mut part = EncApRepPart::default();
+
+    for (field, value) in decode_fields(content)? {
+        if field == 2 {
+            part.subkey = Some(decode_encryption_key(value)?);
+        }
+    }
+
+    Some(part)
+}
+
+/// Decodes the Kerberos GSS-API token (RFC 4121 4.1) and returns its token id and the inner message.
+/// The AP-REP of the server is sent without the GSS-API framing by some implementations,
+/// in which case the token id is empty.
+pub fn decode_kerberos_gss_token(buffer: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    match decode_der_element(buffer)? {
+        (INITIAL_CONTEXT_TOKEN_TAG, content) => match decode_der_element(content)? {
+            (OID_TAG, oid) if oid == KERBEROS_OID => {
+                let inner = content.get(2 + oid.len()..)?;
+                Some((inner.get(..2)?.to_vec(), inner.get(2..)?.to_vec()))
+            }
+            _ => None,
+        },
+        _ => Some((Vec::new(), buffer.to_vec())),
+    }
+}
+
+/// Decodes the ticket.
+pub fn decode_ticket(value: &[u8]) -> Option<Ticket> {
+    let content = match decode_der_element(value)? {
+        (TICKET_TAG, content) => content,
+        _ => return None,
+    };
+    let mut ticket = Ticket::default();
+
+    for (field, value) in decode_fields(content)? {
+        match field {
+            0 => ticket.tkt_vno = decode_primitive(value)?,
+            1 => ticket.realm = decode_primitive(value)?,
+            2 => ticket.sname = decode_principal_name(value)?,
+            3 => ticket.enc_part = decode_encrypted_data(value)?,
+            _ => {}
+        }
+    }
+
+    Some(ticket)
+}
+
+/// Decodes the principal name.
+pub fn decode_principal_name(value: &[u8]) -> Option<PrincipalName> {
+    let mut name = PrincipalName::default();
+
+    for (field, value) in decode_fields(value)? {
+        match field {
+            0 => name.name_type = decode_primitive(value)?,
+            1 => {
+                name.name_string = match decode_der_element(value)? {
+                    (SEQUENCE_TAG, components) => decode_der_elements(components)?
+                        .into_iter()
+                        .map(|(_, component)| component.to_vec())
+                        .collect(),
+                    _ => return None,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Some(name)
+}
+
+/// Decodes the encrypted data.
+pub fn decode_encrypted_data(value: &[u8]) -> Option<EncryptedData> {
+    let mut data = EncryptedData::default();
+
+    for (field, value) in decode_fields(value)? {
+        match field {
+            0 => data.etype = decode_primitive(value)?,
+            1 => data.kvno = decode_primitive(value)?,
+            2 => data.cipher = decode_primitive(value)?,
+            _ => {}
+        }
+    }
+
+    Some(data)
+}
+
+/// Decodes the encryption key.
+pub fn decode_encryption_key(value: &[u8]) -> Option<EncryptionKey> {
+    let mut key = EncryptionKey::default();
+
+    for (field, value) in decode_fields(value)? {
+        match field {
+            0 => key.key_type = decode_primitive(value)?,
+            1 => key.key_value = decode_primitive(value)?,
+            _ => {}
+        }
+    }
+
+    Some(key)
+}
+
+/// Decodes the SEQUENCE OF PA-DATA, e.g. the padata of the KDC-REP or the METHOD-DATA of the KRB-ERROR.
+pub fn decode_padata_sequence(buffer: &[u8]) -> Option<Vec<PaData>> {
+    let elements = match decode_der_element(buffer)? {
+        (SEQUENCE_TAG, elements) => decode_der_elements(elements)?,
+        _ => return None,
+    };
+    let mut padata = Vec::new();
+
+    for (_, element) in elements {
+        let mut entry = PaData::default();
+        for (field, value) in decode_sequence_content(element)? {
+            match field {
+                1 => entry.padata_type = decode_primitive(value)?,
+                2 => entry.padata_value = decode_primitive(value)?,
+                _ => {}
+            }
+        }
+        padata.push(entry);
+    }
+
+    Some(padata)
+}
+
+/// Decodes the content of an INTEGER, the big endian two's complement of the value.
+/// Returns None for empty contents and values beyond 64 bits.
+pub fn decode_integer(content: &[u8]) -> Option<i64> {
+    if content.is_empty() || content.len() > 8 {
+        return None;
+    }
+    let initial: i64 = if content[0] & 0x80 != 0 { -1 } else { 0 };
+
+    Some(
+        content
+            .iter()
+            .fold(initial, |value, byte| (value << 8) | *byte as i64),
+    )
+}
+
+/// Decodes the fields of the content of a SEQUENCE, e.g. of an element of a SEQUENCE OF.
+/// Returns the number of the context specific field and its encoded value.
+fn decode_sequence_content(content: &[u8]) -> Option<Vec<(u8, &[u8])>> {
+    Some(
+        decode_der_elements(content)?
+            .into_iter()
+            .map(|(tag, value)| (tag & 0x1f, value))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::encoder::kerberos_encoder::{
+        encode_integer, encode_pa_data, encode_ticket, INTEGER_TAG,
+    };
+    use crate::format::encoder::spnego_encoder::{
+        encode_der_element, encode_optional_field, GENERAL_STRING_TAG, OCTET_STRING_TAG,
+    };
+    use crate::kerberos::NT_SRV_INST;
+
+    #[test]
+    fn test_decode_ticket_roundtrip() {
+        let mut ticket = Ticket::default();
+        ticket.realm = b"DOMAIN.LOCAL".to_vec();
+        ticket.sname = PrincipalName::from_string(NT_SRV_INST, "cifs/server.domain.local");
+        ticket.enc_part.etype = vec![0x12];
+        ticket.enc_part.kvno = vec![0x02];
+        ticket.enc_part.cipher = vec![0x42; 200];
+
+        assert_eq!(Some(ticket.clone()), decode_ticket(&encode_ticket(&ticket)));
+        assert_eq!(None, decode_ticket(&encode_ticket(&ticket)[..100]));
+    }
+
+    #[test]
+    fn test_decode_krb_error_with_etype_info2() {
+        let mut entry = encode_optional_field(0, INTEGER_TAG, &[0x12]);
+        entry.append(&mut encode_optional_field(
+            1,
+            GENERAL_STRING_TAG,
+            b"DOMAIN.LOCALalice",
+        ));
+        let etype_info2 =
+            encode_der_element(SEQUENCE_TAG, &encode_der_element(SEQUENCE_TAG, &entry));
+        let mut padata = PaData::default();
+        padata.padata_type = vec![PA_ETYPE_INFO2];
+        padata.padata_value = etype_info2;
+        let method_data = encode_der_element(SEQUENCE_TAG, &encode_pa_data(&padata));
+
+        let mut fields = encode_optional_field(0, INTEGER_TAG, &[0x05]);
+        fields.append(&mut encode_optional_field(1, INTEGER_TAG, &[0x1e]));
+        fields.append(&mut encode_optional_field(6, INTEGER_TAG, &[0x19]));
+        fields.append(&mut encode_optional_field(
+            12,
+            OCTET_STRING_TAG,
+            &method_data,
+        ));
+        let buffer = encode_der_element(KRB_ERROR_TAG, &encode_der_element(SEQUENCE_TAG, &fields));
+
+        let error = decode_krb_error(&buffer).unwrap();
+        assert_eq!(Some(25), decode_integer(&error.error_code));
+
+        let etype_info = decode_etype_info2(&error.e_data).unwrap();
+        assert_eq!(1, etype_info.len());
+        assert_eq!(vec![0x12], etype_info[0].etype);
+        assert_eq!(b"DOMAIN.LOCALalice".to_vec(), etype_info[0].salt);
+    }
+
+    #[test]
+    fn test_decode_integer() {
+        for value in [0, 5, 128, 0x8003, -138, i32::MAX as i64].iter() {
+            assert_eq!(Some(*value), decode_integer(&encode_integer(*value)));
+        }
+        assert_eq!(None, decode_integer(&[]));
+    }
+}
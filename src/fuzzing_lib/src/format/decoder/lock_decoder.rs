@@ -0,0 +1,25 @@
+use crate::smb2::responses;
+
+/// Takes the little endian encoded lock response from the server and populates the corresponding
+/// Lock Response struct.
+pub fn decode_lock_response_body(encoded_body: Vec<u8>) -> responses::lock::Lock {
+    let mut lock_response = responses::lock::Lock::default();
+
+    lock_response.structure_size = encoded_body[..2].to_vec();
+    lock_response.reserved = encoded_body[2..4].to_vec();
+
+    lock_response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_lock_response_body() {
+        assert_eq!(
+            responses::lock::Lock::default(),
+            decode_lock_response_body(b"\x04\x00\x00\x00".to_vec())
+        );
+    }
+}
@@ -3,7 +3,7 @@ use crate::smb2::{
         fields::SecurityMode,
         negotiate_context::{
             CompressionCapabilities, ContextType, EncryptionCapabilities, NegotiateContext,
-            NetnameNegotiateContextId, PreauthIntegrityCapabilities, RdmaTransformCapabilities,
+            PreauthIntegrityCapabilities, RdmaTransformCapabilities, SigningCapabilities,
             TransportCapabilities,
         },
     },
@@ -14,50 +14,69 @@ use crate::smb2::{
 use crate::format::{convert_byte_array_to_int, HEADER_LENGTH};
 
 /// Decodes the the negotiate response.
-pub fn decode_negotiate_response_body(encoded_body: Vec<u8>) -> responses::negotiate::Negotiate {
+/// Returns None if the fixed fields or the security buffer exceed the response, the security
+/// mode or dialect is unknown, or the negotiate contexts of the SMB 3.1.1 dialect do not start
+/// behind the security buffer within the response.
+pub fn decode_negotiate_response_body(
+    encoded_body: Vec<u8>,
+) -> Option<responses::negotiate::Negotiate> {
     let mut negotiate_response = responses::negotiate::Negotiate::default();
 
-    negotiate_response.structure_size = encoded_body[..2].to_vec();
+    let fixed_fields = encoded_body.get(..64)?;
+    if !matches!(fixed_fields[2], 1..=3)
+        || !matches!(
+            fixed_fields[4..6],
+            [2, 2] | [16, 2] | [0, 3] | [2, 3] | [17, 3] | [255, 2]
+        )
+    {
+        return None;
+    }
+
+    negotiate_response.structure_size = fixed_fields[..2].to_vec();
     negotiate_response.security_mode = Some(SecurityMode::map_byte_code_to_mode(
-        encoded_body[2..4].to_vec(),
+        fixed_fields[2..4].to_vec(),
     ));
     negotiate_response.dialect_revision = Some(DialectRevision::map_byte_code_to_dialect(
-        encoded_body[4..6].to_vec(),
+        fixed_fields[4..6].to_vec(),
     ));
-    negotiate_response.negotiate_context_count = encoded_body[6..8].to_vec();
-    negotiate_response.server_guid = encoded_body[8..24].to_vec();
-    negotiate_response.capabilities = encoded_body[24..28].to_vec();
-    negotiate_response.max_transact_size = encoded_body[28..32].to_vec();
-    negotiate_response.max_read_size = encoded_body[32..36].to_vec();
-    negotiate_response.max_write_size = encoded_body[36..40].to_vec();
-    negotiate_response.system_time = encoded_body[40..48].to_vec();
-    negotiate_response.server_start_time = encoded_body[48..56].to_vec();
-    negotiate_response.security_buffer_offset = encoded_body[56..58].to_vec();
-    negotiate_response.security_buffer_length = encoded_body[58..60].to_vec();
-    negotiate_response.negotiate_context_offset = encoded_body[60..64].to_vec();
+    negotiate_response.negotiate_context_count = fixed_fields[6..8].to_vec();
+    negotiate_response.server_guid = fixed_fields[8..24].to_vec();
+    negotiate_response.capabilities = fixed_fields[24..28].to_vec();
+    negotiate_response.max_transact_size = fixed_fields[28..32].to_vec();
+    negotiate_response.max_read_size = fixed_fields[32..36].to_vec();
+    negotiate_response.max_write_size = fixed_fields[36..40].to_vec();
+    negotiate_response.system_time = fixed_fields[40..48].to_vec();
+    negotiate_response.server_start_time = fixed_fields[48..56].to_vec();
+    negotiate_response.security_buffer_offset = fixed_fields[56..58].to_vec();
+    negotiate_response.security_buffer_length = fixed_fields[58..60].to_vec();
+    negotiate_response.negotiate_context_offset = fixed_fields[60..64].to_vec();
 
     let buffer_end_index = 64
         + convert_byte_array_to_int(negotiate_response.security_buffer_length.clone(), false)
             as usize;
-    negotiate_response.buffer = encoded_body[64..buffer_end_index].to_vec();
+    negotiate_response.buffer = encoded_body.get(64..buffer_end_index)?.to_vec();
 
-    if let DialectRevision::Smb311 = negotiate_response.dialect_revision.clone().unwrap() {
+    if let Some(DialectRevision::Smb311) = negotiate_response.dialect_revision {
         // The negotiate contexts need to be 8 byte aligned. Therefore, the optional padding size needs to be calculated from
         // from the index of the last buffer byte.
         let padding_end_index =
-            convert_byte_array_to_int(negotiate_response.negotiate_context_offset.clone(), false)
-                as usize
-                - HEADER_LENGTH;
-        negotiate_response.padding = encoded_body[buffer_end_index..padding_end_index].to_vec();
+            (convert_byte_array_to_int(negotiate_response.negotiate_context_offset.clone(), false)
+                as usize)
+                .checked_sub(HEADER_LENGTH)?;
+        negotiate_response.padding = encoded_body
+            .get(buffer_end_index..padding_end_index)?
+            .to_vec();
 
         negotiate_response.negotiate_context_list =
             decode_negotiate_response_context(encoded_body, &negotiate_response, padding_end_index);
     }
 
-    negotiate_response
+    Some(negotiate_response)
 }
 
 /// Decodes the negotiate contexts of the negotiate response.
+/// The decoding stops at the first context whose type and length exceed the response.
+/// Contexts whose data does not match their type and length are skipped.
 pub fn decode_negotiate_response_context(
     encoded_body: Vec<u8>,
     negotiate_response: &responses::negotiate::Negotiate,
@@ -69,159 +88,269 @@ pub fn decode_negotiate_response_context(
     let mut current_context_offset = start_index;
 
     for _ in 0..context_count {
-        let neg_context = decode_generic_context(&encoded_body, current_context_offset);
+        let data_length =
+            match encoded_body.get(current_context_offset + 2..current_context_offset + 4) {
+                Some(data_length) => convert_byte_array_to_int(data_length.to_vec(), false),
+                None => break,
+            };
+        if let Some(neg_context) = decode_generic_context(&encoded_body, current_context_offset) {
+            context_list.push(neg_context);
+        }
         // the context size is made up of the fields: (type, length, reserved) = 8 byte and data.
-        let context_size =
-            (convert_byte_array_to_int(neg_context.data_length.clone(), false) + 8) as usize;
+        let context_size = (data_length + 8) as usize;
         let next_context_offset_without_padding = current_context_offset + context_size;
         current_context_offset = next_context_offset_without_padding
             + calculate_alignment_padding(next_context_offset_without_padding);
-        context_list.push(neg_context);
     }
 
     context_list
 }
 
 /// Calculates the padding size for 8 byte alignment.
+/// An offset that is already aligned is not padded.
 pub fn calculate_alignment_padding(offset_without_padding: usize) -> usize {
-    8 - offset_without_padding % 8
+    (8 - offset_without_padding % 8) % 8
 }
 
 /// Creates a new generic negotiate context and deligates the specific handling of its type
-/// to the subroutines.
-pub fn decode_generic_context(encoded_body: &[u8], start_index: usize) -> NegotiateContext {
+/// to the subroutines. The subroutines only decode the data of the context, so that the counts
+/// of a context cannot reach into the following contexts.
+/// Returns None if the data exceeds the response or does not match the counts of the context.
+pub fn decode_generic_context(encoded_body: &[u8], start_index: usize) -> Option<NegotiateContext> {
     let mut neg_context = NegotiateContext::default();
-    neg_context.context_type = encoded_body[start_index..start_index + 2].to_vec();
-    neg_context.data_length = encoded_body[start_index + 2..start_index + 4].to_vec();
+    neg_context.context_type = encoded_body.get(start_index..start_index + 2)?.to_vec();
+    neg_context.data_length = encoded_body.get(start_index + 2..start_index + 4)?.to_vec();
+    let data_length = convert_byte_array_to_int(neg_context.data_length.clone(), false) as usize;
+    let context_data = encoded_body.get(start_index + 8..start_index + 8 + data_length)?;
     let context_type_struct =
         ContextType::map_byte_code_to_context_type(neg_context.context_type.clone());
 
     match context_type_struct {
         ContextType::PreauthIntegrityCapabilities(mut preauth) => {
-            decode_preauth_context(&mut preauth, encoded_body, start_index + 8);
+            decode_preauth_context(&mut preauth, context_data)?;
             neg_context.data = Some(ContextType::PreauthIntegrityCapabilities(preauth));
         }
         ContextType::EncryptionCapabilities(mut encrypt) => {
-            decode_encryption_context(&mut encrypt, encoded_body, start_index + 8);
+            decode_encryption_context(&mut encrypt, context_data)?;
             neg_context.data = Some(ContextType::EncryptionCapabilities(encrypt));
         }
         ContextType::CompressionCapabilities(mut compress) => {
-            decode_compression_context(&mut compress, encoded_body, start_index + 8);
+            decode_compression_context(&mut compress, context_data)?;
             neg_context.data = Some(ContextType::CompressionCapabilities(compress));
         }
         ContextType::NetnameNegotiateContextId(mut netname) => {
-            decode_netname_context(
-                &mut netname,
-                encoded_body,
-                start_index + 8,
-                neg_context.data_length.clone(),
-            );
+            netname.net_name = context_data.to_vec();
             neg_context.data = Some(ContextType::NetnameNegotiateContextId(netname));
         }
         ContextType::TransportCapabilities(mut transport) => {
-            decode_transport_context(&mut transport, encoded_body, start_index + 8);
+            decode_transport_context(&mut transport, context_data)?;
             neg_context.data = Some(ContextType::TransportCapabilities(transport));
         }
         ContextType::RdmaTransformCapabilities(mut rdma) => {
-            decode_rdma_transform_context(&mut rdma, encoded_body, start_index + 8);
+            decode_rdma_transform_context(&mut rdma, context_data)?;
             neg_context.data = Some(ContextType::RdmaTransformCapabilities(rdma));
         }
+        ContextType::SigningCapabilities(mut signing) => {
+            decode_signing_context(&mut signing, context_data)?;
+            neg_context.data = Some(ContextType::SigningCapabilities(signing));
+        }
+        ContextType::Unknown { context_type, .. } => {
+            neg_context.data = Some(ContextType::Unknown {
+                context_type,
+                data: context_data.to_vec(),
+            });
+        }
     }
 
-    neg_context
+    Some(neg_context)
+}
+
+/// Decodes the list of two byte algorithm ids at the start index of the context data.
+/// Returns None if the announced count exceeds the context data.
+pub fn decode_algorithm_ids(
+    context_data: &[u8],
+    start_index: usize,
+    count: &[u8],
+) -> Option<Vec<Vec<u8>>> {
+    let count = convert_byte_array_to_int(count.to_vec(), false) as usize;
+    let ids = context_data.get(start_index..start_index + 2 * count)?;
+
+    Some(ids.chunks(2).map(|id| id.to_vec()).collect())
 }
 
 /// Decodes the PreauthIntegrityCapabilities.
 pub fn decode_preauth_context(
     preauth_cap: &mut PreauthIntegrityCapabilities,
-    encoded_body: &[u8],
-    start_index: usize,
-) {
-    preauth_cap.salt_length = encoded_body[start_index + 2..start_index + 4].to_vec();
+    context_data: &[u8],
+) -> Option<()> {
+    preauth_cap.hash_algorithm_count = context_data.get(..2)?.to_vec();
+    preauth_cap.salt_length = context_data.get(2..4)?.to_vec();
+    preauth_cap.hash_algorithms =
+        decode_algorithm_ids(context_data, 4, &preauth_cap.hash_algorithm_count)?;
+    let salt_index = 4 + 2 * preauth_cap.hash_algorithms.len();
     let salt_length = convert_byte_array_to_int(preauth_cap.salt_length.clone(), false) as usize;
-    preauth_cap.salt = encoded_body[start_index + 6..start_index + 6 + salt_length].to_vec();
+    preauth_cap.salt = context_data
+        .get(salt_index..salt_index + salt_length)?
+        .to_vec();
+
+    Some(())
 }
 
 /// Decodes the Encryption Capabilities.
 pub fn decode_encryption_context(
     encrypt_cap: &mut EncryptionCapabilities,
-    encoded_body: &[u8],
-    start_index: usize,
-) {
-    encrypt_cap.cipher_count = encoded_body[start_index..start_index + 2].to_vec();
-    let cipher_index = start_index + 2;
-    for counter in 0..convert_byte_array_to_int(encrypt_cap.cipher_count.clone(), false) {
-        encrypt_cap.ciphers.push(
-            encoded_body
-                [cipher_index + (2 * counter as usize)..cipher_index + 2 + (2 * counter as usize)]
-                .to_vec(),
-        );
-    }
+    context_data: &[u8],
+) -> Option<()> {
+    encrypt_cap.cipher_count = context_data.get(..2)?.to_vec();
+    encrypt_cap.ciphers = decode_algorithm_ids(context_data, 2, &encrypt_cap.cipher_count)?;
+
+    Some(())
 }
 
 /// Decodes the Compression Capabilities.
 pub fn decode_compression_context(
     compress_cap: &mut CompressionCapabilities,
-    encoded_body: &[u8],
-    start_index: usize,
-) {
-    compress_cap.compression_algorithm_count = encoded_body[start_index..start_index + 2].to_vec();
-    compress_cap.padding = encoded_body[start_index + 2..start_index + 4].to_vec();
-    compress_cap.flags = encoded_body[start_index + 4..start_index + 8].to_vec();
-    let algo_index = start_index + 8;
-    for counter in
-        0..convert_byte_array_to_int(compress_cap.compression_algorithm_count.clone(), false)
-    {
-        compress_cap.compression_algorithms.push(
-            encoded_body
-                [algo_index + (2 * counter as usize)..algo_index + 2 + (2 * counter as usize)]
-                .to_vec(),
-        );
-    }
-}
-
-/// Decodes the Netname Context Id.
-pub fn decode_netname_context(
-    netname: &mut NetnameNegotiateContextId,
-    encoded_body: &[u8],
-    start_index: usize,
-    name_length_in_bytes: Vec<u8>,
-) {
-    let name_length = convert_byte_array_to_int(name_length_in_bytes, false) as usize;
-    netname.net_name = encoded_body[start_index..name_length + start_index].to_vec();
+    context_data: &[u8],
+) -> Option<()> {
+    compress_cap.compression_algorithm_count = context_data.get(..2)?.to_vec();
+    compress_cap.padding = context_data.get(2..4)?.to_vec();
+    compress_cap.flags = context_data.get(4..8)?.to_vec();
+    compress_cap.compression_algorithms =
+        decode_algorithm_ids(context_data, 8, &compress_cap.compression_algorithm_count)?;
+
+    Some(())
 }
 
 /// Decodes the Transport Capabilities.
 pub fn decode_transport_context(
     transport_cap: &mut TransportCapabilities,
-    encoded_body: &[u8],
-    start_index: usize,
-) {
-    transport_cap.reserved = encoded_body[start_index..start_index + 4].to_vec();
+    context_data: &[u8],
+) -> Option<()> {
+    transport_cap.reserved = context_data.get(..4)?.to_vec();
+
+    Some(())
 }
 
 /// Decodes the RDMA Transform Capabilities
 pub fn decode_rdma_transform_context(
     rdma_cap: &mut RdmaTransformCapabilities,
-    encoded_body: &[u8],
-    start_index: usize,
-) {
-    rdma_cap.transform_count = encoded_body[start_index..start_index + 2].to_vec();
-    rdma_cap.reserved1 = encoded_body[start_index + 2..start_index + 4].to_vec();
-    rdma_cap.reserved2 = encoded_body[start_index + 4..start_index + 8].to_vec();
-    let id_index = start_index + 8;
-    for counter in 0..convert_byte_array_to_int(rdma_cap.transform_count.clone(), false) {
-        rdma_cap.rdma_transform_ids.push(
-            encoded_body[id_index + (2 * counter as usize)..id_index + 2 + (2 * counter as usize)]
-                .to_vec(),
-        );
-    }
+    context_data: &[u8],
+) -> Option<()> {
+    rdma_cap.transform_count = context_data.get(..2)?.to_vec();
+    rdma_cap.reserved1 = context_data.get(2..4)?.to_vec();
+    rdma_cap.reserved2 = context_data.get(4..8)?.to_vec();
+    rdma_cap.rdma_transform_ids = decode_algorithm_ids(context_data, 8, &rdma_cap.transform_count)?;
+
+    Some(())
+}
+
+/// Decodes the Signing Capabilities.
+pub fn decode_signing_context(
+    signing_cap: &mut SigningCapabilities,
+    context_data: &[u8],
+) -> Option<()> {
+    signing_cap.signing_algorithm_count = context_data.get(..2)?.to_vec();
+    signing_cap.signing_algorithms =
+        decode_algorithm_ids(context_data, 2, &signing_cap.signing_algorithm_count)?;
+
+    Some(())
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::format::encoder::negotiate_encoder::{
+        navigate_to_corresponding_serializer, serialize_negotiate_contexts,
+    };
+
+    #[test]
+    fn test_decode_negotiate_response_context() {
+        let mut preauth = PreauthIntegrityCapabilities::default();
+        preauth.salt_length = vec![4, 0];
+        preauth.salt = vec![0x5a; 4];
+
+        let mut signing = SigningCapabilities::default();
+        signing.signing_algorithm_count = vec![3, 0];
+        signing.signing_algorithms = vec![vec![2, 0], vec![1, 0], vec![0, 0]];
+
+        let mut rdma = RdmaTransformCapabilities::default();
+        rdma.transform_count = vec![1, 0];
+        rdma.rdma_transform_ids = vec![vec![1, 0]];
+
+        let context_list: Vec<NegotiateContext> = vec![
+            ContextType::PreauthIntegrityCapabilities(preauth),
+            ContextType::SigningCapabilities(signing),
+            ContextType::RdmaTransformCapabilities(rdma),
+        ]
+        .into_iter()
+        .map(|data| {
+            let mut context = NegotiateContext::default();
+            context.context_type = data.unpack_byte_code();
+            context.data_length = (navigate_to_corresponding_serializer(&data).len() as u16)
+                .to_le_bytes()
+                .to_vec();
+            context.data = Some(data);
+            context
+        })
+        .collect();
+
+        let mut negotiate_response = responses::negotiate::Negotiate::default();
+        negotiate_response.negotiate_context_count = vec![3, 0];
+
+        // The signing context ends 8 byte aligned, so that no padding precedes the rdma context.
+        assert_eq!(
+            context_list,
+            decode_negotiate_response_context(
+                serialize_negotiate_contexts(context_list.clone(), 0),
+                &negotiate_response,
+                0
+            )
+        );
+        assert_eq!(0, calculate_alignment_padding(40));
+        assert_eq!(2, calculate_alignment_padding(38));
+    }
+
+    #[test]
+    fn test_decode_malformed_negotiate_response_body() {
+        let mut encoded_body = vec![0; 64];
+        encoded_body[2] = 1;
+        encoded_body[4..6].copy_from_slice(&[0x11, 0x03]);
+        encoded_body[6] = 2;
+        encoded_body[60] = (HEADER_LENGTH + 64) as u8;
+        // A preauth context whose hash algorithm count exceeds its data, followed by a valid
+        // encryption context.
+        encoded_body.extend_from_slice(&[1, 0, 6, 0, 0, 0, 0, 0, 0xff, 0xff, 0, 0, 1, 0, 0, 0]);
+        encoded_body.extend_from_slice(&[2, 0, 4, 0, 0, 0, 0, 0, 1, 0, 2, 0]);
+
+        let decoded_negotiate_response =
+            decode_negotiate_response_body(encoded_body.clone()).unwrap();
+        assert_eq!(1, decoded_negotiate_response.negotiate_context_list.len());
+        assert_eq!(
+            vec![2, 0],
+            decoded_negotiate_response.negotiate_context_list[0].context_type
+        );
+
+        let mut low_context_offset = encoded_body.clone();
+        low_context_offset[60] = 16;
+        assert_eq!(None, decode_negotiate_response_body(low_context_offset));
+
+        let mut oversized_security_buffer = encoded_body.clone();
+        oversized_security_buffer[58..60].copy_from_slice(&[0xff, 0xff]);
+        assert_eq!(
+            None,
+            decode_negotiate_response_body(oversized_security_buffer)
+        );
+
+        let mut unknown_dialect = encoded_body.clone();
+        unknown_dialect[4..6].copy_from_slice(&[0x42, 0x42]);
+        assert_eq!(None, decode_negotiate_response_body(unknown_dialect));
+
+        assert_eq!(
+            None,
+            decode_negotiate_response_body(encoded_body[..63].to_vec())
+        );
+    }
 
     #[test]
     fn test_decode_negotiate_response_body() {
@@ -244,7 +373,8 @@ mod tests {
         .flatten()
         .collect();
 
-        let decoded_negotiate_response = decode_negotiate_response_body(encoded_negotiate_response);
+        let decoded_negotiate_response =
+            decode_negotiate_response_body(encoded_negotiate_response).unwrap();
 
         assert_eq!(vec![65, 0], decoded_negotiate_response.structure_size);
 
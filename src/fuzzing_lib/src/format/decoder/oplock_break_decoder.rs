@@ -0,0 +1,112 @@
+use crate::smb2::responses::{
+    self,
+    oplock_break::{BreakNotification, LeaseBreakNotification, LeaseBreakResponse},
+};
+
+/// Takes the little endian encoded break notification from the server and populates
+/// the oplock or lease break notification according to the structure size.
+/// Notifications of unknown structure size are returned raw.
+pub fn decode_break_notification_body(encoded_body: Vec<u8>) -> BreakNotification {
+    match (encoded_body.get(..2), encoded_body.len()) {
+        (Some(b"\x18\x00"), length) if length >= 24 => {
+            BreakNotification::Oplock(decode_oplock_break_body(encoded_body))
+        }
+        (Some(b"\x2c\x00"), length) if length >= 44 => {
+            BreakNotification::Lease(decode_lease_break_notification_body(encoded_body))
+        }
+        _ => BreakNotification::Raw(encoded_body),
+    }
+}
+
+/// Takes the little endian encoded oplock break notification or response from the server
+/// and populates the corresponding Oplock Break struct.
+pub fn decode_oplock_break_body(encoded_body: Vec<u8>) -> responses::oplock_break::OplockBreak {
+    let mut oplock_break = responses::oplock_break::OplockBreak::default();
+
+    oplock_break.structure_size = encoded_body[..2].to_vec();
+    oplock_break.oplock_level = encoded_body[2..3].to_vec();
+    oplock_break.reserved = encoded_body[3..4].to_vec();
+    oplock_break.reserved_2 = encoded_body[4..8].to_vec();
+    oplock_break.file_id = encoded_body[8..24].to_vec();
+
+    oplock_break
+}
+
+/// Takes the little endian encoded lease break notification from the server
+/// and populates the corresponding Lease Break Notification struct.
+pub fn decode_lease_break_notification_body(encoded_body: Vec<u8>) -> LeaseBreakNotification {
+    let mut lease_break = LeaseBreakNotification::default();
+
+    lease_break.structure_size = encoded_body[..2].to_vec();
+    lease_break.new_epoch = encoded_body[2..4].to_vec();
+    lease_break.flags = encoded_body[4..8].to_vec();
+    lease_break.lease_key = encoded_body[8..24].to_vec();
+    lease_break.current_lease_state = encoded_body[24..28].to_vec();
+    lease_break.new_lease_state = encoded_body[28..32].to_vec();
+    lease_break.break_reason = encoded_body[32..36].to_vec();
+    lease_break.access_mask_hint = encoded_body[36..40].to_vec();
+    lease_break.share_mask_hint = encoded_body[40..44].to_vec();
+
+    lease_break
+}
+
+/// Takes the little endian encoded lease break response from the server
+/// and populates the corresponding Lease Break Response struct.
+pub fn decode_lease_break_response_body(encoded_body: Vec<u8>) -> LeaseBreakResponse {
+    let mut lease_break = LeaseBreakResponse::default();
+
+    lease_break.structure_size = encoded_body[..2].to_vec();
+    lease_break.reserved = encoded_body[2..4].to_vec();
+    lease_break.flags = encoded_body[4..8].to_vec();
+    lease_break.lease_key = encoded_body[8..24].to_vec();
+    lease_break.lease_state = encoded_body[24..28].to_vec();
+    lease_break.lease_duration = encoded_body[28..36].to_vec();
+
+    lease_break
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_break_notification_body() {
+        let mut encoded_oplock_break = b"\x18\x00\x01\x00\x00\x00\x00\x00".to_vec();
+        encoded_oplock_break.append(&mut vec![7; 16]);
+
+        let mut expected_oplock_break = responses::oplock_break::OplockBreak::default();
+        expected_oplock_break.oplock_level = vec![1];
+        expected_oplock_break.file_id = vec![7; 16];
+
+        assert_eq!(
+            BreakNotification::Oplock(expected_oplock_break),
+            decode_break_notification_body(encoded_oplock_break)
+        );
+
+        let encoded_lease_break: Vec<u8> = vec![
+            b"\x2c\x00\x02\x00\x01\x00\x00\x00".to_vec(),
+            vec![9; 16],
+            b"\x07\x00\x00\x00\x01\x00\x00\x00".to_vec(),
+            vec![0; 12],
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        match decode_break_notification_body(encoded_lease_break) {
+            BreakNotification::Lease(lease_break) => {
+                assert_eq!(vec![2, 0], lease_break.new_epoch);
+                assert_eq!(vec![1, 0, 0, 0], lease_break.flags);
+                assert_eq!(vec![9; 16], lease_break.lease_key);
+                assert_eq!(vec![7, 0, 0, 0], lease_break.current_lease_state);
+                assert_eq!(vec![1, 0, 0, 0], lease_break.new_lease_state);
+            }
+            _ => panic!("Lease break notification was not decoded."),
+        }
+
+        assert_eq!(
+            BreakNotification::Raw(vec![0x09, 0x00]),
+            decode_break_notification_body(vec![0x09, 0x00])
+        );
+    }
+}
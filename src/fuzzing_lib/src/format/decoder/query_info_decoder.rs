@@ -0,0 +1,326 @@
+use crate::smb2::responses::{
+    self,
+    query_info::{
+        file_information::{
+            FileAllInformation, FileBasicInformation, FileInternalInformation,
+            FileNetworkOpenInformation, FilePositionInformation, FileStandardInformation,
+        },
+        fs_information::{
+            FileFsAttributeInformation, FileFsDeviceInformation, FileFsFullSizeInformation,
+            FileFsSizeInformation, FileFsVolumeInformation,
+        },
+        OutputBuffer,
+    },
+};
+
+use crate::format::convert_byte_array_to_int;
+
+/// Takes the little endian encoded query info response from the server and populates the corresponding
+/// Query Info Response struct.
+///
+/// Note: The output buffer is decoded separately.
+pub fn decode_query_info_response_body(encoded_body: Vec<u8>) -> responses::query_info::QueryInfo {
+    let mut query_info_response = responses::query_info::QueryInfo::default();
+
+    query_info_response.structure_size = encoded_body[..2].to_vec();
+    query_info_response.output_buffer_offset = encoded_body[2..4].to_vec();
+    query_info_response.output_buffer_length = encoded_body[4..8].to_vec();
+    query_info_response.buffer = encoded_body[8..].to_vec();
+
+    query_info_response
+}
+
+/// Decodes the output buffer of a query info response according to the info type and the
+/// file info class of the corresponding request.
+/// Returns the typed information structure and all bytes that follow the structure.
+/// Buffers that are too short for their structure are returned raw.
+///
+/// The following information classes are deliberately kept raw:
+/// *Fixed-size file and file system classes without a typed structure*:
+///     - Access, alignment, attribute tag, compression, EA, id, mode and pipe information,
+///       file system control, object id and sector size information. Only the structure
+///       is kept raw, the bytes that follow it are returned for the leak detection.
+/// *Variable-length classes*:
+///     - Alternate and normalized name, full EA and stream information, security descriptors
+///       and quota entries. Their length is not known without interpreting the lists,
+///       so the whole buffer is kept raw.
+pub fn decode_query_info_output_buffer(
+    info_type: Vec<u8>,
+    file_info_class: Vec<u8>,
+    buffer: Vec<u8>,
+) -> (OutputBuffer, Vec<u8>) {
+    let (output_buffer, consumed_length) = match (info_type.first(), file_info_class.first()) {
+        (Some(1), Some(4)) if buffer.len() >= 40 => (
+            OutputBuffer::FileBasic(decode_file_basic_information(&buffer)),
+            40,
+        ),
+        (Some(1), Some(5)) if buffer.len() >= 24 => (
+            OutputBuffer::FileStandard(decode_file_standard_information(&buffer)),
+            24,
+        ),
+        (Some(1), Some(6)) if buffer.len() >= 8 => (
+            OutputBuffer::FileInternal(FileInternalInformation {
+                index_number: buffer[..8].to_vec(),
+            }),
+            8,
+        ),
+        (Some(1), Some(14)) if buffer.len() >= 8 => (
+            OutputBuffer::FilePosition(FilePositionInformation {
+                current_byte_offset: buffer[..8].to_vec(),
+            }),
+            8,
+        ),
+        (Some(1), Some(18)) if buffer.len() >= 100 => {
+            let file_all_information = decode_file_all_information(&buffer);
+            let consumed_length = 100 + file_all_information.file_name.len();
+            (
+                OutputBuffer::FileAll(Box::new(file_all_information)),
+                consumed_length,
+            )
+        }
+        (Some(1), Some(34)) if buffer.len() >= 56 => (
+            OutputBuffer::FileNetworkOpen(decode_file_network_open_information(&buffer)),
+            56,
+        ),
+        (Some(2), Some(1)) if buffer.len() >= 18 => {
+            let volume_information = decode_fs_volume_information(&buffer);
+            let consumed_length = 18 + volume_information.volume_label.len();
+            (OutputBuffer::FsVolume(volume_information), consumed_length)
+        }
+        (Some(2), Some(3)) if buffer.len() >= 24 => (
+            OutputBuffer::FsSize(FileFsSizeInformation {
+                total_allocation_units: buffer[..8].to_vec(),
+                available_allocation_units: buffer[8..16].to_vec(),
+                sectors_per_allocation_unit: buffer[16..20].to_vec(),
+                bytes_per_sector: buffer[20..24].to_vec(),
+            }),
+            24,
+        ),
+        (Some(2), Some(4)) if buffer.len() >= 8 => (
+            OutputBuffer::FsDevice(FileFsDeviceInformation {
+                device_type: buffer[..4].to_vec(),
+                characteristics: buffer[4..8].to_vec(),
+            }),
+            8,
+        ),
+        (Some(2), Some(5)) if buffer.len() >= 12 => {
+            let attribute_information = decode_fs_attribute_information(&buffer);
+            let consumed_length = 12 + attribute_information.file_system_name.len();
+            (
+                OutputBuffer::FsAttribute(attribute_information),
+                consumed_length,
+            )
+        }
+        (Some(2), Some(7)) if buffer.len() >= 32 => (
+            OutputBuffer::FsFullSize(FileFsFullSizeInformation {
+                total_allocation_units: buffer[..8].to_vec(),
+                caller_available_allocation_units: buffer[8..16].to_vec(),
+                actual_available_allocation_units: buffer[16..24].to_vec(),
+                sectors_per_allocation_unit: buffer[24..28].to_vec(),
+                bytes_per_sector: buffer[28..32].to_vec(),
+            }),
+            32,
+        ),
+        (Some(1), Some(7 | 8 | 16 | 17)) if buffer.len() >= 4 => {
+            (OutputBuffer::Raw(buffer[..4].to_vec()), 4)
+        }
+        (Some(1), Some(23 | 35)) if buffer.len() >= 8 => {
+            (OutputBuffer::Raw(buffer[..8].to_vec()), 8)
+        }
+        (Some(1), Some(25)) if buffer.len() >= 12 => (OutputBuffer::Raw(buffer[..12].to_vec()), 12),
+        (Some(1), Some(28)) if buffer.len() >= 16 => (OutputBuffer::Raw(buffer[..16].to_vec()), 16),
+        (Some(1), Some(59)) if buffer.len() >= 24 => (OutputBuffer::Raw(buffer[..24].to_vec()), 24),
+        (Some(2), Some(11)) if buffer.len() >= 28 => (OutputBuffer::Raw(buffer[..28].to_vec()), 28),
+        (Some(1), Some(24)) if buffer.len() >= 40 => (OutputBuffer::Raw(buffer[..40].to_vec()), 40),
+        (Some(2), Some(6)) if buffer.len() >= 48 => (OutputBuffer::Raw(buffer[..48].to_vec()), 48),
+        (Some(2), Some(8)) if buffer.len() >= 64 => (OutputBuffer::Raw(buffer[..64].to_vec()), 64),
+        _ => return (OutputBuffer::Raw(buffer), Vec::new()),
+    };
+
+    (output_buffer, buffer[consumed_length..].to_vec())
+}
+
+/// Collects all bytes of a decoded output buffer that the server should not have filled,
+/// i.e. non-zero reserved fields and any data following the information structure.
+/// A non-empty result hints at uninitialized memory being leaked to the client.
+pub fn find_potentially_leaked_bytes(
+    output_buffer: &OutputBuffer,
+    trailing_bytes: &[u8],
+) -> Vec<u8> {
+    let mut leaked_bytes: Vec<u8> = output_buffer
+        .reserved_bytes()
+        .into_iter()
+        .filter(|byte| *byte != 0)
+        .collect();
+    leaked_bytes.extend(trailing_bytes.iter().filter(|byte| **byte != 0));
+
+    leaked_bytes
+}
+
+/// Decodes a FILE_BASIC_INFORMATION structure.
+pub fn decode_file_basic_information(buffer: &[u8]) -> FileBasicInformation {
+    let mut basic_information = FileBasicInformation::default();
+
+    basic_information.creation_time = buffer[..8].to_vec();
+    basic_information.last_access_time = buffer[8..16].to_vec();
+    basic_information.last_write_time = buffer[16..24].to_vec();
+    basic_information.change_time = buffer[24..32].to_vec();
+    basic_information.file_attributes = buffer[32..36].to_vec();
+    basic_information.reserved = buffer[36..40].to_vec();
+
+    basic_information
+}
+
+/// Decodes a FILE_STANDARD_INFORMATION structure.
+pub fn decode_file_standard_information(buffer: &[u8]) -> FileStandardInformation {
+    let mut standard_information = FileStandardInformation::default();
+
+    standard_information.allocation_size = buffer[..8].to_vec();
+    standard_information.end_of_file = buffer[8..16].to_vec();
+    standard_information.number_of_links = buffer[16..20].to_vec();
+    standard_information.delete_pending = buffer[20..21].to_vec();
+    standard_information.directory = buffer[21..22].to_vec();
+    standard_information.reserved = buffer[22..24].to_vec();
+
+    standard_information
+}
+
+/// Decodes a FILE_NETWORK_OPEN_INFORMATION structure.
+pub fn decode_file_network_open_information(buffer: &[u8]) -> FileNetworkOpenInformation {
+    let mut network_open_information = FileNetworkOpenInformation::default();
+
+    network_open_information.creation_time = buffer[..8].to_vec();
+    network_open_information.last_access_time = buffer[8..16].to_vec();
+    network_open_information.last_write_time = buffer[16..24].to_vec();
+    network_open_information.change_time = buffer[24..32].to_vec();
+    network_open_information.allocation_size = buffer[32..40].to_vec();
+    network_open_information.end_of_file = buffer[40..48].to_vec();
+    network_open_information.file_attributes = buffer[48..52].to_vec();
+    network_open_information.reserved = buffer[52..56].to_vec();
+
+    network_open_information
+}
+
+/// Decodes a FILE_ALL_INFORMATION structure.
+/// The file name is cut at the end of the buffer if the announced length exceeds it.
+pub fn decode_file_all_information(buffer: &[u8]) -> FileAllInformation {
+    let mut all_information = FileAllInformation::default();
+
+    all_information.basic_information = decode_file_basic_information(&buffer[..40]);
+    all_information.standard_information = decode_file_standard_information(&buffer[40..64]);
+    all_information.internal_information.index_number = buffer[64..72].to_vec();
+    all_information.ea_size = buffer[72..76].to_vec();
+    all_information.access_flags = buffer[76..80].to_vec();
+    all_information.position_information.current_byte_offset = buffer[80..88].to_vec();
+    all_information.mode = buffer[88..92].to_vec();
+    all_information.alignment_requirement = buffer[92..96].to_vec();
+    all_information.file_name_length = buffer[96..100].to_vec();
+    all_information.file_name = read_variable_field(buffer, 100, &all_information.file_name_length);
+
+    all_information
+}
+
+/// Decodes a FILE_FS_VOLUME_INFORMATION structure.
+pub fn decode_fs_volume_information(buffer: &[u8]) -> FileFsVolumeInformation {
+    let mut volume_information = FileFsVolumeInformation::default();
+
+    volume_information.volume_creation_time = buffer[..8].to_vec();
+    volume_information.volume_serial_number = buffer[8..12].to_vec();
+    volume_information.volume_label_length = buffer[12..16].to_vec();
+    volume_information.supports_objects = buffer[16..17].to_vec();
+    volume_information.reserved = buffer[17..18].to_vec();
+    volume_information.volume_label =
+        read_variable_field(buffer, 18, &volume_information.volume_label_length);
+
+    volume_information
+}
+
+/// Decodes a FILE_FS_ATTRIBUTE_INFORMATION structure.
+pub fn decode_fs_attribute_information(buffer: &[u8]) -> FileFsAttributeInformation {
+    let mut attribute_information = FileFsAttributeInformation::default();
+
+    attribute_information.file_system_attributes = buffer[..4].to_vec();
+    attribute_information.maximum_component_name_length = buffer[4..8].to_vec();
+    attribute_information.file_system_name_length = buffer[8..12].to_vec();
+    attribute_information.file_system_name =
+        read_variable_field(buffer, 12, &attribute_information.file_system_name_length);
+
+    attribute_information
+}
+
+/// Reads a variable-length field that starts at the given offset and whose length
+/// is given by a little endian length field. The field is cut at the end of the buffer.
+fn read_variable_field(buffer: &[u8], offset: usize, length: &[u8]) -> Vec<u8> {
+    let length = convert_byte_array_to_int(length.to_vec(), false) as usize;
+    let end = buffer.len().min(offset.saturating_add(length));
+
+    buffer[offset..end].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_query_info_response_body() {
+        let encoded_query_info_response =
+            b"\x09\x00\x48\x00\x08\x00\x00\x00\x00\x10\x00\x00\x00\x00\x00\x00".to_vec();
+
+        let mut expected_query_info_response = responses::query_info::QueryInfo::default();
+        expected_query_info_response.output_buffer_offset = b"\x48\x00".to_vec();
+        expected_query_info_response.output_buffer_length = b"\x08\x00\x00\x00".to_vec();
+        expected_query_info_response.buffer = b"\x00\x10\x00\x00\x00\x00\x00\x00".to_vec();
+
+        assert_eq!(
+            expected_query_info_response,
+            decode_query_info_response_body(encoded_query_info_response)
+        );
+    }
+
+    #[test]
+    fn test_decode_query_info_output_buffer_finds_leaked_bytes() {
+        let mut encoded_standard_information =
+            b"\x00\x00\x10\x00\x00\x00\x00\x00\x0e\x00\x00\x00\x00\x00\x00\x00\
+            \x01\x00\x00\x00\x00\x00\xde\xad"
+                .to_vec();
+        encoded_standard_information.append(&mut b"\x00\xbe\xef".to_vec());
+
+        let (output_buffer, trailing_bytes) =
+            decode_query_info_output_buffer(vec![1], vec![5], encoded_standard_information);
+
+        let mut expected_standard_information = FileStandardInformation::default();
+        expected_standard_information.allocation_size =
+            b"\x00\x00\x10\x00\x00\x00\x00\x00".to_vec();
+        expected_standard_information.end_of_file = b"\x0e\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        expected_standard_information.number_of_links = b"\x01\x00\x00\x00".to_vec();
+        expected_standard_information.delete_pending = vec![0];
+        expected_standard_information.directory = vec![0];
+        expected_standard_information.reserved = b"\xde\xad".to_vec();
+
+        assert_eq!(
+            OutputBuffer::FileStandard(expected_standard_information),
+            output_buffer
+        );
+        assert_eq!(b"\x00\xbe\xef".to_vec(), trailing_bytes);
+        assert_eq!(
+            b"\xde\xad\xbe\xef".to_vec(),
+            find_potentially_leaked_bytes(&output_buffer, &trailing_bytes)
+        );
+    }
+
+    #[test]
+    fn test_decode_query_info_output_buffer_with_short_buffer() {
+        assert_eq!(
+            (OutputBuffer::Raw(vec![0; 4]), Vec::new()),
+            decode_query_info_output_buffer(vec![1], vec![4], vec![0; 4])
+        );
+        assert_eq!(
+            (OutputBuffer::Raw(vec![1; 4]), vec![0xcc; 2]),
+            decode_query_info_output_buffer(vec![1], vec![8], vec![1, 1, 1, 1, 0xcc, 0xcc])
+        );
+        assert_eq!(
+            (OutputBuffer::Raw(vec![0; 4]), Vec::new()),
+            decode_query_info_output_buffer(Vec::new(), Vec::new(), vec![0; 4])
+        );
+    }
+}
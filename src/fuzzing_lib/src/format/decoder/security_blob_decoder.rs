@@ -1,6 +1,7 @@
 use crate::{
-    format::convert_byte_array_to_int,
-    ntlmssp::{self, challenge::Challenge, AvId, AvPair, MessageType},
+    format::{convert_byte_array_to_int, decoder::spnego_decoder::decode_negotiation_token},
+    gss::NegotiationToken,
+    ntlmssp::{self, challenge::Challenge, negotiate::Negotiate, AvId, AvPair, MessageType},
 };
 
 /// Decodes the NTLMSSP security response body.
@@ -50,6 +51,73 @@ pub fn decode_security_response(security_response: Vec<u8>) -> ntlmssp::Header {
     header
 }
 
+/// Extracts the raw NTLM CHALLENGE_MESSAGE from the security response, which ends with the target info.
+/// The raw message is part of the MIC of the AUTHENTICATE_MESSAGE.
+pub fn extract_ntlm_challenge_message(security_response: Vec<u8>) -> Vec<u8> {
+    let mut ntlmssp_response = remove_gss_wrapper(security_response);
+    let end_of_message = convert_byte_array_to_int(ntlmssp_response[44..48].to_vec(), false)
+        as usize
+        + convert_byte_array_to_int(ntlmssp_response[40..42].to_vec(), false) as usize;
+    ntlmssp_response.truncate(end_of_message);
+
+    ntlmssp_response
+}
+
+/// Decodes the NTLM NEGOTIATE_MESSAGE. The payload is read according to the length and offset fields,
+/// as far as it is contained in the message. The version is only present if the message is long enough.
+pub fn decode_negotiate_message(negotiate_message: &[u8]) -> ntlmssp::Header {
+    let mut header = ntlmssp::Header::default();
+
+    header.signature = negotiate_message[..8].to_vec();
+    header.message_type = negotiate_message[8..12].to_vec();
+
+    let mut negotiate = Negotiate::default();
+
+    negotiate.negotiate_flags = negotiate_message[12..16].to_vec();
+
+    negotiate.domain_name_fields.domain_name_len = negotiate_message[16..18].to_vec();
+    negotiate.domain_name_fields.domain_name_max_len = negotiate_message[18..20].to_vec();
+    negotiate.domain_name_fields.domain_name_buffer_offset = negotiate_message[20..24].to_vec();
+
+    negotiate.workstation_fields.workstation_len = negotiate_message[24..26].to_vec();
+    negotiate.workstation_fields.workstation_max_len = negotiate_message[26..28].to_vec();
+    negotiate.workstation_fields.workstation_buffer_offset = negotiate_message[28..32].to_vec();
+
+    if negotiate_message.len() >= 40 {
+        negotiate.version.product_major_version = negotiate_message[32..33].to_vec();
+        negotiate.version.product_minor_version = negotiate_message[33..34].to_vec();
+        negotiate.version.product_build = negotiate_message[34..36].to_vec();
+        negotiate.version.reserved = negotiate_message[36..39].to_vec();
+        negotiate.version.ntlm_revision_current = negotiate_message[39..40].to_vec();
+    } else {
+        negotiate.version.reserved = Vec::new();
+    }
+
+    negotiate.payload.domain_name = decode_payload_field(
+        negotiate_message,
+        &negotiate.domain_name_fields.domain_name_buffer_offset,
+        &negotiate.domain_name_fields.domain_name_len,
+    );
+    negotiate.payload.workstation_name = decode_payload_field(
+        negotiate_message,
+        &negotiate.workstation_fields.workstation_buffer_offset,
+        &negotiate.workstation_fields.workstation_len,
+    );
+
+    header.message = Some(MessageType::Negotiate(negotiate));
+
+    header
+}
+
+/// Decodes the payload field at the given offset with the given length, truncated to the message.
+pub fn decode_payload_field(message: &[u8], offset: &[u8], length: &[u8]) -> Vec<u8> {
+    let start = (convert_byte_array_to_int(offset.to_vec(), false) as usize).min(message.len());
+    let end =
+        (start + convert_byte_array_to_int(length.to_vec(), false) as usize).min(message.len());
+
+    message[start..end].to_vec()
+}
+
 /// Decodes the AvPairs of the target info.
 pub fn decode_target_info(
     ntlmssp_response: Vec<u8>,
@@ -78,9 +146,15 @@ pub fn decode_target_info(
     av_pairs
 }
 
-/// Removes the ASN.1 encoded gss wrapper.
+/// Removes the ASN.1 encoded gss wrapper by decoding the SPNEGO token
+/// and returns the token of the mechanism.
 pub fn remove_gss_wrapper(security_response: Vec<u8>) -> Vec<u8> {
-    security_response[31..].to_vec()
+    match decode_negotiation_token(&security_response) {
+        Some(NegotiationToken::NegTokenResp(token)) => token.response_token,
+        Some(NegotiationToken::NegTokenInit(token)) => token.mech_token,
+        Some(NegotiationToken::NegTokenInit2(token)) => token.mech_token,
+        None => panic!("Invalid SPNEGO token."),
+    }
 }
 
 #[cfg(test)]
@@ -202,4 +276,43 @@ mod tests {
             decode_target_info(setup.ntlm_byte_code, 78, 178)
         );
     }
+
+    #[test]
+    fn test_decode_negotiate_message() {
+        let negotiate_message = b"\x4e\x54\x4c\x4d\x53\x53\x50\x00\x01\x00\x00\x00\x15\xb2\x08\xe2\
+                                  \x03\x00\x03\x00\x28\x00\x00\x00\x02\x00\x02\x00\x2b\x00\x00\x00\
+                                  \x06\x01\x00\x00\x00\x00\x00\x0f\x44\x4f\x4d\x50\x43";
+
+        let mut negotiate = Negotiate::default();
+        negotiate.negotiate_flags = b"\x15\xb2\x08\xe2".to_vec();
+        negotiate.domain_name_fields.domain_name_len = b"\x03\x00".to_vec();
+        negotiate.domain_name_fields.domain_name_max_len = b"\x03\x00".to_vec();
+        negotiate.domain_name_fields.domain_name_buffer_offset = b"\x28\x00\x00\x00".to_vec();
+        negotiate.workstation_fields.workstation_len = b"\x02\x00".to_vec();
+        negotiate.workstation_fields.workstation_max_len = b"\x02\x00".to_vec();
+        negotiate.workstation_fields.workstation_buffer_offset = b"\x2b\x00\x00\x00".to_vec();
+        negotiate.version.product_major_version = b"\x06".to_vec();
+        negotiate.version.product_minor_version = b"\x01".to_vec();
+        negotiate.version.product_build = b"\x00\x00".to_vec();
+        negotiate.version.ntlm_revision_current = b"\x0f".to_vec();
+        negotiate.payload.domain_name = b"DOM".to_vec();
+        negotiate.payload.workstation_name = b"PC".to_vec();
+
+        let mut header = ntlmssp::Header::default();
+        let message_type = MessageType::Negotiate(negotiate);
+        header.message_type = message_type.unpack_byte_code();
+        header.message = Some(message_type);
+
+        assert_eq!(header, decode_negotiate_message(negotiate_message));
+    }
+
+    #[test]
+    fn test_extract_ntlm_challenge_message() {
+        let setup = Setup::new();
+
+        assert_eq!(
+            setup.ntlm_byte_code[..178].to_vec(),
+            extract_ntlm_challenge_message(setup.complete_byte_code)
+        );
+    }
 }
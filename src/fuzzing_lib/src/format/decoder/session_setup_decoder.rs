@@ -59,4 +59,17 @@ mod tests {
             decode_session_setup_response_body(encoded_session_response_body)
         );
     }
+
+    #[test]
+    fn test_decode_guest_and_null_session_flags() {
+        let guest_response =
+            decode_session_setup_response_body(b"\x09\x00\x01\x00\x48\x00\x00\x00".to_vec());
+        assert!(guest_response.is_guest());
+        assert!(!guest_response.is_null());
+
+        let null_response =
+            decode_session_setup_response_body(b"\x09\x00\x02\x00\x48\x00\x00\x00".to_vec());
+        assert!(null_response.is_null());
+        assert!(!null_response.is_guest());
+    }
 }
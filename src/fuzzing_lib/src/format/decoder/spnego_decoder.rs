@@ -0,0 +1,259 @@
+//! Decodes the ASN.1 DER encoded SPNEGO negotiation tokens. Malformed tokens,
+//! e.g. with lengths beyond the buffer, are rejected instead of being sliced blindly.
+
+use crate::format::encoder::spnego_encoder::{
+    INITIAL_CONTEXT_TOKEN_TAG, NEG_TOKEN_INIT_TAG, NEG_TOKEN_RESP_TAG, OID_TAG, SEQUENCE_TAG,
+};
+use crate::gss::{
+    NegHints, NegTokenInit, NegTokenInit2, NegTokenResp, NegotiationToken, SPNEGO_OID,
+};
+
+/// Decodes the negotiation token. Initial tokens are expected within the GSS-API initial context token.
+/// Returns None if the token is malformed.
+pub fn decode_negotiation_token(buffer: &[u8]) -> Option<NegotiationToken> {
+    let (tag, content) = decode_der_element(buffer)?;
+
+    match tag {
+        INITIAL_CONTEXT_TOKEN_TAG => {
+            let elements = decode_der_elements(content)?;
+            match elements.as_slice() {
+                [(OID_TAG, oid), (NEG_TOKEN_INIT_TAG, token), ..] if oid == SPNEGO_OID => {
+                    decode_neg_token_init(token)
+                }
+                _ => None,
+            }
+        }
+        NEG_TOKEN_INIT_TAG => decode_neg_token_init(content),
+        NEG_TOKEN_RESP_TAG => decode_neg_token_resp(content).map(NegotiationToken::NegTokenResp),
+        _ => None,
+    }
+}
+
+/// Decodes the SEQUENCE of the NegTokenInit or NegTokenInit2. The token is a NegTokenInit2
+/// if it carries the NegHints in field [3] or the mechListMIC in field [4].
+pub fn decode_neg_token_init(content: &[u8]) -> Option<NegotiationToken> {
+    let mut token = NegTokenInit2::default();
+    let mut is_init2 = false;
+
+    for (field, value) in decode_fields(content)? {
+        match field {
+            0 => token.mech_types = decode_mech_types(value)?,
+            1 => token.req_flags = decode_primitive(value)?,
+            2 => token.mech_token = decode_primitive(value)?,
+            3 => match decode_der_element(value)? {
+                (SEQUENCE_TAG, hints) => {
+                    token.neg_hints = decode_neg_hints(hints)?;
+                    is_init2 = true;
+                }
+                (_, mech_list_mic) => token.mech_list_mic = mech_list_mic.to_vec(),
+            },
+            4 => {
+                token.mech_list_mic = decode_primitive(value)?;
+                is_init2 = true;
+            }
+            _ => {}
+        }
+    }
+
+    if is_init2 {
+        Some(NegotiationToken::NegTokenInit2(token))
+    } else {
+        let mut init = NegTokenInit::default();
+        init.mech_types = token.mech_types;
+        init.req_flags = token.req_flags;
+        init.mech_token = token.mech_token;
+        init.mech_list_mic = token.mech_list_mic;
+
+        Some(NegotiationToken::NegTokenInit(init))
+    }
+}
+
+/// Decodes the SEQUENCE of the NegTokenResp.
+pub fn decode_neg_token_resp(content: &[u8]) -> Option<NegTokenResp> {
+    let mut token = NegTokenResp::default();
+
+    for (field, value) in decode_fields(content)? {
+        match field {
+            0 => token.state = decode_primitive(value)?,
+            1 => token.supported_mech = decode_primitive(value)?,
+            2 => token.response_token = decode_primitive(value)?,
+            3 => token.mech_list_mic = decode_primitive(value)?,
+            _ => {}
+        }
+    }
+
+    Some(token)
+}
+
+/// Decodes the SEQUENCE of the NegHints.
+pub fn decode_neg_hints(content: &[u8]) -> Option<NegHints> {
+    let mut neg_hints = NegHints::default();
+
+    for (field, value) in decode_der_elements(content)? {
+        match field & 0x1f {
+            0 => neg_hints.hint_name = decode_primitive(value)?,
+            1 => neg_hints.hint_address = decode_primitive(value)?,
+            _ => {}
+        }
+    }
+
+    Some(neg_hints)
+}
+
+/// Decodes the OIDs of the MechTypeList.
+pub fn decode_mech_types(value: &[u8]) -> Option<Vec<Vec<u8>>> {
+    match decode_der_element(value)? {
+        (SEQUENCE_TAG, oids) => Some(
+            decode_der_elements(oids)?
+                .into_iter()
+                .filter(|(tag, _)| *tag == OID_TAG)
+                .map(|(_, oid)| oid.to_vec())
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Decodes the fields of the SEQUENCE that wraps the fields of a token.
+/// Returns the number of the context specific field and its encoded value.
+pub fn decode_fields(content: &[u8]) -> Option<Vec<(u8, &[u8])>> {
+    match decode_der_element(content)? {
+        (SEQUENCE_TAG, fields) => Some(
+            decode_der_elements(fields)?
+                .into_iter()
+                .map(|(tag, value)| (tag & 0x1f, value))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Decodes the content of the primitive element that is the value of a field.
+pub fn decode_primitive(value: &[u8]) -> Option<Vec<u8>> {
+    decode_der_element(value).map(|(_, content)| content.to_vec())
+}
+
+/// Decodes the consecutive elements of the content.
+pub fn decode_der_elements(mut content: &[u8]) -> Option<Vec<(u8, &[u8])>> {
+    let mut elements = Vec::new();
+
+    while !content.is_empty() {
+        let (tag, content_start, content_end) = decode_der_header(content)?;
+        elements.push((tag, &content[content_start..content_end]));
+        content = &content[content_end..];
+    }
+
+    Some(elements)
+}
+
+/// Decodes the tag and the content of the first element in the buffer.
+pub fn decode_der_element(buffer: &[u8]) -> Option<(u8, &[u8])> {
+    let (tag, content_start, content_end) = decode_der_header(buffer)?;
+
+    Some((tag, &buffer[content_start..content_end]))
+}
+
+/// Decodes the tag and the bounds of the content of the first element in the buffer.
+/// Lengths are accepted in the short and in the long form of up to four bytes.
+/// Returns None if the content exceeds the buffer.
+pub fn decode_der_header(buffer: &[u8]) -> Option<(u8, usize, usize)> {
+    let tag = *buffer.first()?;
+    let first_length_byte = *buffer.get(1)? as usize;
+
+    let (length, content_start) = if first_length_byte < 0x80 {
+        (first_length_byte, 2)
+    } else {
+        let length_bytes = first_length_byte & 0x7f;
+        if length_bytes == 0 || length_bytes > 4 {
+            return None;
+        }
+        let length = buffer
+            .get(2..2 + length_bytes)?
+            .iter()
+            .fold(0, |length, byte| (length << 8) | *byte as usize);
+        (length, 2 + length_bytes)
+    };
+
+    let content_end = content_start.checked_add(length)?;
+    if content_end > buffer.len() {
+        return None;
+    }
+
+    Some((tag, content_start, content_end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::encoder::spnego_encoder::encode_negotiation_token;
+    use crate::gss::{KERBEROS_OID, MS_KERBEROS_OID, NTLMSSP_OID};
+
+    #[test]
+    fn test_decode_neg_token_init2() {
+        let mut buffer = b"\x60\x5e\x06\x06\x2b\x06\x01\x05\x05\x02\xa0\x54\x30\x52\xa0\x24\
+                           \x30\x22\x06\x09\x2a\x86\x48\x82\xf7\x12\x01\x02\x02\x06\x09\x2a\
+                           \x86\x48\x86\xf7\x12\x01\x02\x02\x06\x0a\x2b\x06\x01\x04\x01\x82\
+                           \x37\x02\x02\x0a\xa3\x2a\x30\x28\xa0\x26\x1b\x24"
+            .to_vec();
+        buffer.extend_from_slice(b"not_defined_in_RFC4178@please_ignore");
+
+        let mut token = NegTokenInit2::default();
+        token.mech_types = vec![
+            MS_KERBEROS_OID.to_vec(),
+            KERBEROS_OID.to_vec(),
+            NTLMSSP_OID.to_vec(),
+        ];
+        token.neg_hints.hint_name = b"not_defined_in_RFC4178@please_ignore".to_vec();
+
+        assert_eq!(
+            Some(NegotiationToken::NegTokenInit2(token.clone())),
+            decode_negotiation_token(&buffer)
+        );
+        assert_eq!(
+            buffer,
+            encode_negotiation_token(NegotiationToken::NegTokenInit2(token))
+        );
+    }
+
+    #[test]
+    fn test_decode_neg_token_resp() {
+        let buffer = b"\xa1\x1c\x30\x1a\xa0\x03\x0a\x01\x01\xa1\x0c\x06\x0a\x2b\x06\x01\
+                       \x04\x01\x82\x37\x02\x02\x0a\xa2\x05\x04\x03\x01\x02\x03";
+
+        let mut token = NegTokenResp::default();
+        token.state = vec![1];
+        token.supported_mech = NTLMSSP_OID.to_vec();
+        token.response_token = vec![1, 2, 3];
+
+        assert_eq!(
+            Some(NegotiationToken::NegTokenResp(token)),
+            decode_negotiation_token(buffer)
+        );
+    }
+
+    #[test]
+    fn test_decode_neg_token_init_roundtrip() {
+        let mut token = NegTokenInit::default();
+        token.mech_types = vec![NTLMSSP_OID.to_vec()];
+        token.req_flags = vec![0x01, 0x02];
+        token.mech_token = vec![0x42; 300];
+        token.mech_list_mic = vec![0x17; 16];
+        let token = NegotiationToken::NegTokenInit(token);
+
+        assert_eq!(
+            Some(token.clone()),
+            decode_negotiation_token(&encode_negotiation_token(token))
+        );
+    }
+
+    #[test]
+    fn test_decode_malformed_token() {
+        assert_eq!(None, decode_negotiation_token(b""));
+        assert_eq!(None, decode_negotiation_token(b"\xa1\x82\xff\xff\x30\x00"));
+        assert_eq!(None, decode_negotiation_token(b"\xa1\x04\x30\x02\xa0\x05"));
+        assert_eq!(
+            None,
+            decode_negotiation_token(b"\xa1\x85\x01\x01\x01\x01\x01")
+        );
+    }
+}
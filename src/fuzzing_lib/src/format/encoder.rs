@@ -1,24 +1,46 @@
 use self::{
-    close_encoder::serialize_close_request_body, create_encoder::serialize_create_request_body,
+    cancel_encoder::serialize_cancel_request_body,
+    change_notify_encoder::serialize_change_notify_request_body,
+    close_encoder::serialize_close_request_body, compound_encoder::serialize_compound_entries,
+    create_encoder::serialize_create_request_body,
     echo_encoder::serialize_serialize_echo_request_body,
+    flush_encoder::serialize_flush_request_body, lock_encoder::serialize_lock_request_body,
+    logoff_encoder::serialize_logoff_request_body,
     negotiate_encoder::serialize_negotiate_request_body,
+    oplock_break_encoder::serialize_oplock_break_request_body,
     query_info_encoder::serialize_query_info_request_body,
+    read_encoder::serialize_read_request_body,
     session_setup_encoder::serialize_session_setup_request_body,
+    set_info_encoder::serialize_set_info_request_body,
     tree_connect_encoder::serialize_tree_connect_request_body,
 };
 
+use crate::format::HEADER_LENGTH;
 use crate::smb2::{
-    header::{GenericHeader, SyncHeader},
+    header::{AsyncHeader, Flags, GenericHeader, Header, SyncHeader, TransformHeader},
     requests::RequestType,
 };
 
+pub mod cancel_encoder;
+pub mod change_notify_encoder;
 pub mod close_encoder;
+pub mod compound_encoder;
+pub mod compression_encoder;
+pub mod create_context_encoder;
 pub mod create_encoder;
 pub mod echo_encoder;
+pub mod flush_encoder;
+pub mod kerberos_encoder;
+pub mod lock_encoder;
+pub mod logoff_encoder;
 pub mod negotiate_encoder;
+pub mod oplock_break_encoder;
 pub mod query_info_encoder;
+pub mod read_encoder;
 pub mod security_blob_encoder;
 pub mod session_setup_encoder;
+pub mod set_info_encoder;
+pub mod spnego_encoder;
 pub mod tree_connect_encoder;
 
 /// Serializes the netbios session prefix by calculating the packet size.
@@ -35,8 +57,60 @@ pub fn serialize_netbios_session_prefix(packet_size: usize) -> Vec<u8> {
 /// Serializes the complete negotiate request.
 pub fn serialize_request(header: &SyncHeader, body: &RequestType) -> Vec<u8> {
     let mut packet = serialize_sync_header(header);
+    packet.append(&mut serialize_request_body(body));
 
-    packet.append(&mut match body {
+    let mut request = serialize_netbios_session_prefix(packet.len());
+    request.append(&mut packet);
+
+    request
+}
+
+/// Serializes the complete request with an async header.
+pub fn serialize_async_request(header: &AsyncHeader, body: &RequestType) -> Vec<u8> {
+    let mut packet = serialize_async_header(header);
+    packet.append(&mut serialize_request_body(body));
+
+    let mut request = serialize_netbios_session_prefix(packet.len());
+    request.append(&mut packet);
+
+    request
+}
+
+/// Converts a serialized sync request into an async request with the given async id.
+/// The ASYNC_COMMAND flag is set and the reserved and tree id fields are replaced by the async id.
+/// Requests that are too short to carry a complete SMB2 header are returned unchanged.
+pub fn convert_to_async_request(mut request: Vec<u8>, async_id: &[u8]) -> Vec<u8> {
+    if request.len() < 4 + HEADER_LENGTH || async_id.len() != 8 {
+        return request;
+    }
+
+    request[4 + 16] |= Flags::AsyncCommand.unpack_byte_code()[0];
+    request[4 + 32..4 + 40].copy_from_slice(async_id);
+
+    request
+}
+
+/// Replaces the sync or async header of a serialized request by the given header.
+/// The netbios session prefix is recalculated, since the new header may differ in length.
+/// Requests that are too short to carry a complete SMB2 header are returned unchanged.
+pub fn replace_header(request: Vec<u8>, header: &Header) -> Vec<u8> {
+    if request.len() < 4 + HEADER_LENGTH {
+        return request;
+    }
+
+    let mut packet = serialize_header(header);
+    packet.extend_from_slice(&request[4 + HEADER_LENGTH..]);
+
+    let mut replaced_request = serialize_netbios_session_prefix(packet.len());
+    replaced_request.append(&mut packet);
+
+    replaced_request
+}
+
+/// Serializes the request body according to its request type.
+/// The body of a compound request is the chain of its entries including their headers.
+pub fn serialize_request_body(body: &RequestType) -> Vec<u8> {
+    match body {
         RequestType::Negotiate(negotiate) => serialize_negotiate_request_body(&negotiate),
         RequestType::SessionSetupNeg(session_setup)
         | RequestType::SessionSetupAuth(session_setup) => {
@@ -49,12 +123,18 @@ pub fn serialize_request(header: &SyncHeader, body: &RequestType) -> Vec<u8> {
         RequestType::QueryInfo(query_info) => serialize_query_info_request_body(query_info),
         RequestType::Close(close) => serialize_close_request_body(&close),
         RequestType::Echo(echo) => serialize_serialize_echo_request_body(&echo),
-    });
-
-    let mut request = serialize_netbios_session_prefix(packet.len());
-    request.append(&mut packet);
-
-    request
+        RequestType::Lock(lock) => serialize_lock_request_body(lock),
+        RequestType::Flush(flush) => serialize_flush_request_body(flush),
+        RequestType::Cancel(cancel) => serialize_cancel_request_body(cancel),
+        RequestType::ChangeNotify(change_notify) => {
+            serialize_change_notify_request_body(change_notify)
+        }
+        RequestType::SetInfo(set_info) => serialize_set_info_request_body(set_info),
+        RequestType::Logoff(logoff) => serialize_logoff_request_body(logoff),
+        RequestType::OplockBreak(oplock_break) => serialize_oplock_break_request_body(oplock_break),
+        RequestType::Read(read) => serialize_read_request_body(read),
+        RequestType::Compound(entries) => serialize_compound_entries(entries),
+    }
 }
 
 /// Serializes the generic part of the SMB header.
@@ -88,6 +168,41 @@ pub fn serialize_sync_header(header: &SyncHeader) -> Vec<u8> {
     serialized_header
 }
 
+/// Serializes the async variant of the SMB header.
+pub fn serialize_async_header(header: &AsyncHeader) -> Vec<u8> {
+    let mut serialized_header: Vec<u8> = Vec::new();
+
+    serialized_header.append(&mut serialize_generic_header(&header.generic));
+    serialized_header.append(&mut header.async_id.clone());
+    serialized_header.append(&mut header.session_id.clone());
+    serialized_header.append(&mut header.signature.clone());
+
+    serialized_header
+}
+
+/// Serializes the sync or async SMB header.
+pub fn serialize_header(header: &Header) -> Vec<u8> {
+    match header {
+        Header::Sync(sync_header) => serialize_sync_header(sync_header),
+        Header::Async(async_header) => serialize_async_header(async_header),
+    }
+}
+
+/// Serializes the SMB2 TRANSFORM_HEADER of encrypted messages.
+pub fn serialize_transform_header(header: &TransformHeader) -> Vec<u8> {
+    let mut serialized_header: Vec<u8> = Vec::new();
+
+    serialized_header.append(&mut header.protocol_id.clone());
+    serialized_header.append(&mut header.signature.clone());
+    serialized_header.append(&mut header.nonce.clone());
+    serialized_header.append(&mut header.original_message_size.clone());
+    serialized_header.append(&mut header.reserved.clone());
+    serialized_header.append(&mut header.flags.clone());
+    serialized_header.append(&mut header.session_id.clone());
+
+    serialized_header
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -147,4 +262,82 @@ mod tests {
 
         assert_eq!(expected_byte_array, serialize_sync_header(&sync_header));
     }
+
+    #[test]
+    fn test_serialize_async_header() {
+        let setup = Setup::new();
+        let mut async_header = AsyncHeader::default();
+        async_header.generic = setup.generic_header;
+        async_header.async_id = vec![1, 0, 0, 0, 0, 0, 0, 0];
+        async_header.session_id = vec![0; 8];
+        async_header.signature = vec![0; 16];
+
+        let mut expected_byte_array: Vec<u8> = b"\xfe\x53\x4d\x42\x40\x00\x01\x00".to_vec();
+        expected_byte_array.append(&mut vec![0; 24]);
+        expected_byte_array.append(&mut vec![1, 0, 0, 0, 0, 0, 0, 0]);
+        expected_byte_array.append(&mut vec![0; 24]);
+
+        assert_eq!(expected_byte_array, serialize_async_header(&async_header));
+    }
+
+    #[test]
+    fn test_convert_to_async_request() {
+        let setup = Setup::new();
+        let mut sync_header = SyncHeader::default();
+        sync_header.generic = setup.generic_header;
+        sync_header.generic.flags = b"\x08\x00\x00\x00".to_vec();
+        sync_header.tree_id = vec![1; 4];
+        sync_header.session_id = vec![2; 8];
+        sync_header.signature = vec![0; 16];
+
+        let mut async_header =
+            AsyncHeader::from_sync_header(sync_header.clone(), vec![3, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(b"\x0a\x00\x00\x00".to_vec(), async_header.generic.flags);
+
+        let mut sync_request = serialize_netbios_session_prefix(HEADER_LENGTH + 2);
+        sync_request.append(&mut serialize_header(&Header::Sync(sync_header)));
+        sync_request.append(&mut vec![4, 0]);
+
+        let mut expected_async_request = serialize_netbios_session_prefix(HEADER_LENGTH + 2);
+        expected_async_request.append(&mut serialize_header(&Header::Async(async_header.clone())));
+        expected_async_request.append(&mut vec![4, 0]);
+
+        assert_eq!(
+            expected_async_request,
+            convert_to_async_request(sync_request, &async_header.async_id)
+        );
+
+        async_header.async_id = vec![0; 4];
+        assert_eq!(
+            vec![0; 3],
+            convert_to_async_request(vec![0; 3], &async_header.async_id)
+        );
+    }
+
+    #[test]
+    fn test_replace_header() {
+        let setup = Setup::new();
+        let mut sync_header = SyncHeader::default();
+        sync_header.generic = setup.generic_header;
+        sync_header.tree_id = vec![1; 4];
+        sync_header.session_id = vec![2; 8];
+        sync_header.signature = vec![0; 16];
+
+        let mut request = serialize_netbios_session_prefix(HEADER_LENGTH + 2);
+        request.append(&mut serialize_sync_header(&sync_header));
+        request.append(&mut vec![4, 0]);
+
+        assert_eq!(
+            sync_header,
+            crate::format::decoder::decode_request_header(request[4..68].to_vec())
+        );
+
+        let mut replaced_header = sync_header.clone();
+        replaced_header.generic.credit_charge = vec![0xff; 4];
+
+        let replaced_request = replace_header(request, &Header::Sync(replaced_header));
+        assert_eq!(vec![0, 0, 0, 68], replaced_request[..4].to_vec());
+        assert_eq!(vec![0xff; 4], replaced_request[10..14].to_vec());
+        assert_eq!(vec![4, 0], replaced_request[70..].to_vec());
+    }
 }
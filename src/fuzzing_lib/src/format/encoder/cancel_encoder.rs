@@ -0,0 +1,11 @@
+use crate::smb2::requests::cancel::Cancel;
+
+/// Serializes the cancel request body.
+pub fn serialize_cancel_request_body(request: &Cancel) -> Vec<u8> {
+    let mut serialized_request: Vec<u8> = Vec::new();
+
+    serialized_request.append(&mut request.structure_size.clone());
+    serialized_request.append(&mut request.reserved.clone());
+
+    serialized_request
+}
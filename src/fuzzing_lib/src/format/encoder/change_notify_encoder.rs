@@ -0,0 +1,15 @@
+use crate::smb2::requests::change_notify::ChangeNotify;
+
+/// Serializes the change notify request body.
+pub fn serialize_change_notify_request_body(request: &ChangeNotify) -> Vec<u8> {
+    let mut serialized_request: Vec<u8> = Vec::new();
+
+    serialized_request.append(&mut request.structure_size.clone());
+    serialized_request.append(&mut request.flags.clone());
+    serialized_request.append(&mut request.output_buffer_length.clone());
+    serialized_request.append(&mut request.file_id.clone());
+    serialized_request.append(&mut request.completion_filter.clone());
+    serialized_request.append(&mut request.reserved.clone());
+
+    serialized_request
+}
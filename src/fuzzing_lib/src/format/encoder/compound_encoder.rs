@@ -0,0 +1,56 @@
+use crate::smb2::requests::{
+    compound::CompoundEntry, create::create_context::align_to_eight_bytes,
+};
+
+use super::{serialize_netbios_session_prefix, serialize_request_body, serialize_sync_header};
+
+/// Serializes the complete compound request. All entries are sent in a single netbios frame.
+pub fn serialize_compound_request(entries: &[CompoundEntry]) -> Vec<u8> {
+    let mut packet = serialize_compound_entries(entries);
+
+    let mut request = serialize_netbios_session_prefix(packet.len());
+    request.append(&mut packet);
+
+    request
+}
+
+/// Serializes the entries of a compound request. Every entry but the last is padded
+/// to the next 8-byte boundary. The NextCommand fields are serialized as given,
+/// which allows chains whose NextCommand fields do not match the actual layout.
+pub fn serialize_compound_entries(entries: &[CompoundEntry]) -> Vec<u8> {
+    let mut serialized_entries: Vec<u8> = Vec::new();
+
+    for (index, entry) in entries.iter().enumerate() {
+        let mut serialized_entry = serialize_compound_entry(entry);
+        if index + 1 < entries.len() {
+            serialized_entry.resize(align_to_eight_bytes(serialized_entry.len()), 0);
+        }
+        serialized_entries.append(&mut serialized_entry);
+    }
+
+    serialized_entries
+}
+
+/// Serializes the header and body of a single compound entry.
+pub fn serialize_compound_entry(entry: &CompoundEntry) -> Vec<u8> {
+    let mut serialized_entry = serialize_sync_header(&entry.header);
+    serialized_entry.append(&mut serialize_request_body(&entry.body));
+
+    serialized_entry
+}
+
+/// Sets the NextCommand field of each entry to the 8-byte aligned offset
+/// of the following header. The NextCommand field of the last entry is set to 0.
+pub fn chain_compound_entries(mut entries: Vec<CompoundEntry>) -> Vec<CompoundEntry> {
+    let entry_count = entries.len();
+
+    for (index, entry) in entries.iter_mut().enumerate() {
+        entry.header.generic.next_command = vec![0; 4];
+        if index + 1 < entry_count {
+            let entry_length = align_to_eight_bytes(serialize_compound_entry(entry).len());
+            entry.header.generic.next_command = (entry_length as u32).to_le_bytes().to_vec();
+        }
+    }
+
+    entries
+}
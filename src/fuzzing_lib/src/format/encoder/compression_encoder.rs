@@ -0,0 +1,57 @@
+use crate::smb2::compression_transform::{
+    ChainedCompressionTransform, ChainedPayload, CompressionTransform,
+    UnchainedCompressionTransform,
+};
+
+/// Serializes the compression transform of a compressed message.
+pub fn serialize_compression_transform(transform: &CompressionTransform) -> Vec<u8> {
+    match transform {
+        CompressionTransform::Unchained(unchained) => {
+            serialize_unchained_compression_transform(unchained)
+        }
+        CompressionTransform::Chained(chained) => serialize_chained_compression_transform(chained),
+    }
+}
+
+/// Serializes the unchained compression transform header followed by the uncompressed and the compressed data.
+pub fn serialize_unchained_compression_transform(
+    transform: &UnchainedCompressionTransform,
+) -> Vec<u8> {
+    let mut serialized_transform: Vec<u8> = Vec::new();
+
+    serialized_transform.append(&mut transform.protocol_id.clone());
+    serialized_transform.append(&mut transform.original_compressed_segment_size.clone());
+    serialized_transform.append(&mut transform.compression_algorithm.clone());
+    serialized_transform.append(&mut transform.flags.clone());
+    serialized_transform.append(&mut transform.offset.clone());
+    serialized_transform.append(&mut transform.uncompressed_data.clone());
+    serialized_transform.append(&mut transform.compressed_data.clone());
+
+    serialized_transform
+}
+
+/// Serializes the chained compression transform header followed by all of its payloads.
+pub fn serialize_chained_compression_transform(transform: &ChainedCompressionTransform) -> Vec<u8> {
+    let mut serialized_transform: Vec<u8> = Vec::new();
+
+    serialized_transform.append(&mut transform.protocol_id.clone());
+    serialized_transform.append(&mut transform.original_compressed_segment_size.clone());
+    for payload in transform.payloads.iter() {
+        serialized_transform.append(&mut serialize_chained_payload(payload));
+    }
+
+    serialized_transform
+}
+
+/// Serializes a single payload header and its data.
+pub fn serialize_chained_payload(payload: &ChainedPayload) -> Vec<u8> {
+    let mut serialized_payload: Vec<u8> = Vec::new();
+
+    serialized_payload.append(&mut payload.compression_algorithm.clone());
+    serialized_payload.append(&mut payload.flags.clone());
+    serialized_payload.append(&mut payload.length.clone());
+    serialized_payload.append(&mut payload.original_payload_size.clone());
+    serialized_payload.append(&mut payload.payload_data.clone());
+
+    serialized_payload
+}
@@ -0,0 +1,218 @@
+use crate::smb2::requests::create::create_context::{
+    align_to_eight_bytes, CreateContext, CreateContextData, FileFullEaInformation,
+};
+
+/// Serializes the list of create contexts. Every create context but the last
+/// is padded to the next 8-byte boundary, so that the following context is aligned.
+/// The Next fields are serialized as given, which allows chains whose Next fields
+/// do not match the actual layout.
+pub fn serialize_create_contexts(create_contexts: &[CreateContext]) -> Vec<u8> {
+    let mut serialized_contexts: Vec<u8> = Vec::new();
+
+    for (index, create_context) in create_contexts.iter().enumerate() {
+        let mut serialized_context = serialize_create_context(create_context);
+        if index + 1 < create_contexts.len() {
+            serialized_context.resize(align_to_eight_bytes(serialized_context.len()), 0);
+        }
+        serialized_contexts.append(&mut serialized_context);
+    }
+
+    serialized_contexts
+}
+
+/// Serializes a single create context. If the context carries data,
+/// the name is padded so that the data starts on an 8-byte boundary.
+pub fn serialize_create_context(create_context: &CreateContext) -> Vec<u8> {
+    let mut serialized_context: Vec<u8> = Vec::new();
+
+    serialized_context.append(&mut create_context.next.clone());
+    serialized_context.append(&mut create_context.name_offset.clone());
+    serialized_context.append(&mut create_context.name_length.clone());
+    serialized_context.append(&mut create_context.reserved.clone());
+    serialized_context.append(&mut create_context.data_offset.clone());
+    serialized_context.append(&mut create_context.data_length.clone());
+    serialized_context.append(&mut create_context.name.clone());
+
+    if !create_context.data.is_empty() {
+        serialized_context.resize(align_to_eight_bytes(serialized_context.len()), 0);
+        serialized_context.append(&mut create_context.data.clone());
+    }
+
+    serialized_context
+}
+
+/// Sets the Next field of each create context to the 8-byte aligned offset
+/// of the following context. The Next field of the last context is set to 0.
+pub fn chain_create_contexts(mut create_contexts: Vec<CreateContext>) -> Vec<CreateContext> {
+    let context_count = create_contexts.len();
+
+    for (index, create_context) in create_contexts.iter_mut().enumerate() {
+        create_context.next = vec![0; 4];
+        if index + 1 < context_count {
+            let context_length =
+                align_to_eight_bytes(serialize_create_context(create_context).len());
+            create_context.next = (context_length as u32).to_le_bytes().to_vec();
+        }
+    }
+
+    create_contexts
+}
+
+/// Serializes the typed data of a create context.
+pub fn serialize_create_context_data(data: &CreateContextData) -> Vec<u8> {
+    let mut serialized_data: Vec<u8> = Vec::new();
+
+    match data {
+        CreateContextData::EaBuffer(ea_entries) => {
+            serialized_data.append(&mut serialize_file_full_ea_information_entries(ea_entries));
+        }
+        CreateContextData::SdBuffer(bytes)
+        | CreateContextData::DurableHandleRequest(bytes)
+        | CreateContextData::DurableHandleReconnect(bytes)
+        | CreateContextData::AllocationSize(bytes)
+        | CreateContextData::QueryMaximalAccessRequest(bytes)
+        | CreateContextData::TimewarpToken(bytes)
+        | CreateContextData::Posix(bytes) => serialized_data.append(&mut bytes.clone()),
+        CreateContextData::QueryOnDiskId => {}
+        CreateContextData::RequestLease(request_lease) => {
+            serialized_data.append(&mut request_lease.lease_key.clone());
+            serialized_data.append(&mut request_lease.lease_state.clone());
+            serialized_data.append(&mut request_lease.lease_flags.clone());
+            serialized_data.append(&mut request_lease.lease_duration.clone());
+            serialized_data.append(&mut request_lease.parent_lease_key.clone());
+            serialized_data.append(&mut request_lease.epoch.clone());
+            serialized_data.append(&mut request_lease.reserved.clone());
+        }
+        CreateContextData::DurableHandleRequestV2(durable_request) => {
+            serialized_data.append(&mut durable_request.timeout.clone());
+            serialized_data.append(&mut durable_request.flags.clone());
+            serialized_data.append(&mut durable_request.reserved.clone());
+            serialized_data.append(&mut durable_request.create_guid.clone());
+        }
+        CreateContextData::DurableHandleReconnectV2(durable_reconnect) => {
+            serialized_data.append(&mut durable_reconnect.file_id.clone());
+            serialized_data.append(&mut durable_reconnect.create_guid.clone());
+            serialized_data.append(&mut durable_reconnect.flags.clone());
+        }
+        CreateContextData::AppInstanceId(app_instance_id) => {
+            serialized_data.append(&mut app_instance_id.structure_size.clone());
+            serialized_data.append(&mut app_instance_id.reserved.clone());
+            serialized_data.append(&mut app_instance_id.app_instance_id.clone());
+        }
+        CreateContextData::AppInstanceVersion(app_instance_version) => {
+            serialized_data.append(&mut app_instance_version.structure_size.clone());
+            serialized_data.append(&mut app_instance_version.reserved.clone());
+            serialized_data.append(&mut app_instance_version.padding.clone());
+            serialized_data.append(&mut app_instance_version.app_instance_version_high.clone());
+            serialized_data.append(&mut app_instance_version.app_instance_version_low.clone());
+        }
+    }
+
+    serialized_data
+}
+
+/// Serializes a list of FILE_FULL_EA_INFORMATION entries. Every entry but the last
+/// is padded to the next 4-byte boundary, and its NextEntryOffset is set to its padded length.
+/// The NextEntryOffset of the last entry is 0.
+pub fn serialize_file_full_ea_information_entries(ea_entries: &[FileFullEaInformation]) -> Vec<u8> {
+    let mut serialized_entries: Vec<u8> = Vec::new();
+
+    for (index, ea_entry) in ea_entries.iter().enumerate() {
+        let mut serialized_entry: Vec<u8> = vec![0; 4];
+        serialized_entry.append(&mut ea_entry.flags.clone());
+        serialized_entry.append(&mut ea_entry.ea_name_length.clone());
+        serialized_entry.append(&mut ea_entry.ea_value_length.clone());
+        serialized_entry.append(&mut ea_entry.ea_name.clone());
+        serialized_entry.append(&mut ea_entry.ea_value.clone());
+
+        if index + 1 < ea_entries.len() {
+            let entry_length = (serialized_entry.len() + 3) & !3;
+            serialized_entry.resize(entry_length, 0);
+            serialized_entry[..4].copy_from_slice(&(entry_length as u32).to_le_bytes());
+        }
+        serialized_entries.append(&mut serialized_entry);
+    }
+
+    serialized_entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smb2::requests::create::create_context::CreateContextName;
+
+    #[test]
+    fn test_serialize_chained_create_contexts() {
+        let create_contexts = chain_create_contexts(vec![
+            CreateContext::from_name_and_data(CreateContextName::DurableHandleRequest, vec![0; 16]),
+            CreateContext::from_name_and_data(CreateContextName::QueryOnDiskId, Vec::new()),
+            CreateContext::from_name_and_data(CreateContextName::AllocationSize, vec![1; 8]),
+        ]);
+
+        let expected_create_contexts = vec![
+            b"\x28\x00\x00\x00\x10\x00\x04\x00\x00\x00\x18\x00\x10\x00\x00\x00DHnQ".to_vec(),
+            vec![0; 4],
+            vec![0; 16],
+            b"\x18\x00\x00\x00\x10\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00QFid".to_vec(),
+            vec![0; 4],
+            b"\x00\x00\x00\x00\x10\x00\x04\x00\x00\x00\x18\x00\x08\x00\x00\x00AlSi".to_vec(),
+            vec![0; 4],
+            vec![1; 8],
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<u8>>();
+
+        assert_eq!(
+            expected_create_contexts,
+            serialize_create_contexts(&create_contexts)
+        );
+    }
+
+    #[test]
+    fn test_serialize_file_full_ea_information_entries() {
+        let ea_entries = vec![
+            FileFullEaInformation::from_name_and_value("A", vec![1]),
+            FileFullEaInformation::from_name_and_value("B", vec![2]),
+        ];
+
+        assert_eq!(
+            vec![12, 0, 0, 0, 0, 1, 1, 0, b'A', 0, 1, 0, 0, 0, 0, 0, 0, 1, 1, 0, b'B', 0, 2],
+            serialize_file_full_ea_information_entries(&ea_entries)
+        );
+    }
+
+    #[test]
+    fn test_decode_chained_file_full_ea_information_entries() {
+        let ea_entries = vec![
+            FileFullEaInformation::from_name_and_value("FUZZ", vec![1, 2, 3]),
+            FileFullEaInformation::from_name_and_value("B", vec![4]),
+        ];
+        let serialized_entries = serialize_file_full_ea_information_entries(&ea_entries);
+
+        let mut decoded_entries: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+        let mut offset = 0;
+        loop {
+            let entry = &serialized_entries[offset..];
+            let next_entry_offset =
+                u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]) as usize;
+            let name_length = entry[5] as usize;
+            let value_length = u16::from_le_bytes([entry[6], entry[7]]) as usize;
+            decoded_entries.push((
+                entry[8..8 + name_length].to_vec(),
+                entry[9 + name_length..9 + name_length + value_length].to_vec(),
+            ));
+
+            if next_entry_offset == 0 {
+                break;
+            }
+            assert_eq!(0, next_entry_offset % 4);
+            offset += next_entry_offset;
+        }
+
+        assert_eq!(
+            vec![(b"FUZZ".to_vec(), vec![1, 2, 3]), (b"B".to_vec(), vec![4])],
+            decoded_entries
+        );
+    }
+}
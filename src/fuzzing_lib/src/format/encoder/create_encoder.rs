@@ -1,4 +1,12 @@
-use crate::smb2::requests::create::Create;
+use crate::{
+    format::HEADER_LENGTH,
+    smb2::requests::create::{create_context::align_to_eight_bytes, Create},
+};
+
+use super::create_context_encoder::serialize_create_contexts;
+
+/// The length of the create request body without the variable-length buffer.
+const FIXED_BODY_LENGTH: usize = 56;
 
 /// Serializes the create request body.
 pub fn serialize_create_request_body(request: &Create) -> Vec<u8> {
@@ -21,5 +29,19 @@ pub fn serialize_create_request_body(request: &Create) -> Vec<u8> {
     serialized_request.append(&mut request.create_contexts_length.clone());
     serialized_request.append(&mut request.buffer.clone());
 
+    if !request.create_contexts.is_empty() {
+        serialized_request.resize(
+            align_to_eight_bytes(HEADER_LENGTH + serialized_request.len()) - HEADER_LENGTH,
+            0,
+        );
+        serialized_request.append(&mut serialize_create_contexts(&request.create_contexts));
+    }
+
     serialized_request
 }
+
+/// Returns the offset from the beginning of the SMB2 header to the first create context,
+/// which follows the 8-byte aligned file name in the buffer.
+pub fn calculate_create_contexts_offset(buffer_length: usize) -> usize {
+    align_to_eight_bytes(HEADER_LENGTH + FIXED_BODY_LENGTH + buffer_length)
+}
@@ -0,0 +1,13 @@
+use crate::smb2::requests::flush::Flush;
+
+/// Serializes the flush request body.
+pub fn serialize_flush_request_body(request: &Flush) -> Vec<u8> {
+    let mut serialized_request: Vec<u8> = Vec::new();
+
+    serialized_request.append(&mut request.structure_size.clone());
+    serialized_request.append(&mut request.reserved_1.clone());
+    serialized_request.append(&mut request.reserved_2.clone());
+    serialized_request.append(&mut request.file_id.clone());
+
+    serialized_request
+}
@@ -0,0 +1,336 @@
+//! Serializes the Kerberos messages with ASN.1 DER. Every structure is a SEQUENCE of context specific
+//! fields, wrapped in its APPLICATION tag where RFC 4120 defines one. Empty fields are omitted.
+
+use super::spnego_encoder::{
+    encode_context_field, encode_der_element, encode_optional_field, BIT_STRING_TAG,
+    GENERAL_STRING_TAG, INITIAL_CONTEXT_TOKEN_TAG, OCTET_STRING_TAG, OID_TAG, SEQUENCE_TAG,
+};
+use crate::gss::KERBEROS_OID;
+use crate::kerberos::{
+    ApReq, Authenticator, Checksum, EncryptedData, EncryptionKey, KdcReq, KdcReqBody, PaData,
+    PaEncTsEnc, PrincipalName, Ticket,
+};
+
+pub const INTEGER_TAG: u8 = 0x02;
+pub const GENERALIZED_TIME_TAG: u8 = 0x18;
+/// The tag of the Ticket ([APPLICATION 1]).
+pub const TICKET_TAG: u8 = 0x61;
+/// The tag of the Authenticator ([APPLICATION 2]).
+pub const AUTHENTICATOR_TAG: u8 = 0x62;
+/// The tag of the AS-REQ ([APPLICATION 10]).
+pub const AS_REQ_TAG: u8 = 0x6a;
+/// The tag of the AS-REP ([APPLICATION 11]).
+pub const AS_REP_TAG: u8 = 0x6b;
+/// The tag of the TGS-REQ ([APPLICATION 12]).
+pub const TGS_REQ_TAG: u8 = 0x6c;
+/// The tag of the TGS-REP ([APPLICATION 13]).
+pub const TGS_REP_TAG: u8 = 0x6d;
+/// The tag of the AP-REQ ([APPLICATION 14]).
+pub const AP_REQ_TAG: u8 = 0x6e;
+/// The tag of the AP-REP ([APPLICATION 15]).
+pub const AP_REP_TAG: u8 = 0x6f;
+/// The tag of the EncASRepPart ([APPLICATION 25]).
+pub const ENC_AS_REP_PART_TAG: u8 = 0x79;
+/// The tag of the EncTGSRepPart ([APPLICATION 26]).
+pub const ENC_TGS_REP_PART_TAG: u8 = 0x7a;
+/// The tag of the EncAPRepPart ([APPLICATION 27]).
+pub const ENC_AP_REP_PART_TAG: u8 = 0x7b;
+/// The tag of the KRB-ERROR ([APPLICATION 30]).
+pub const KRB_ERROR_TAG: u8 = 0x7e;
+
+/// Serializes the AS-REQ or the TGS-REQ with the given APPLICATION tag.
+pub fn encode_kdc_req(tag: u8, request: &KdcReq) -> Vec<u8> {
+    let mut fields = encode_optional_field(1, INTEGER_TAG, &request.pvno);
+    fields.append(&mut encode_optional_field(
+        2,
+        INTEGER_TAG,
+        &request.msg_type,
+    ));
+    if !request.padata.is_empty() {
+        let padata: Vec<u8> = request.padata.iter().flat_map(encode_pa_data).collect();
+        fields.append(&mut encode_context_field(
+            3,
+            &encode_der_element(SEQUENCE_TAG, &padata),
+        ));
+    }
+    fields.append(&mut encode_context_field(
+        4,
+        &encode_kdc_req_body(&request.req_body),
+    ));
+
+    encode_der_element(tag, &encode_der_element(SEQUENCE_TAG, &fields))
+}
+
+/// Serializes the KDC-REQ-BODY. Its encoding is the input of the checksum of the TGS-REQ authenticator.
+pub fn encode_kdc_req_body(body: &KdcReqBody) -> Vec<u8> {
+    let mut fields = encode_optional_field(0, BIT_STRING_TAG, &body.kdc_options);
+    if let Some(cname) = &body.cname {
+        fields.append(&mut encode_context_field(1, &encode_principal_name(cname)));
+    }
+    fields.append(&mut encode_optional_field(
+        2,
+        GENERAL_STRING_TAG,
+        &body.realm,
+    ));
+    if let Some(sname) = &body.sname {
+        fields.append(&mut encode_context_field(3, &encode_principal_name(sname)));
+    }
+    fields.append(&mut encode_optional_field(
+        5,
+        GENERALIZED_TIME_TAG,
+        &body.till,
+    ));
+    fields.append(&mut encode_optional_field(7, INTEGER_TAG, &body.nonce));
+    let etypes: Vec<u8> = body
+        .etype
+        .iter()
+        .flat_map(|etype| encode_der_element(INTEGER_TAG, etype))
+        .collect();
+    fields.append(&mut encode_context_field(
+        8,
+        &encode_der_element(SEQUENCE_TAG, &etypes),
+    ));
+
+    encode_der_element(SEQUENCE_TAG, &fields)
+}
+
+/// Serializes the AP-REQ.
+pub fn encode_ap_req(request: &ApReq) -> Vec<u8> {
+    let mut fields = encode_optional_field(0, INTEGER_TAG, &request.pvno);
+    fields.append(&mut encode_optional_field(
+        1,
+        INTEGER_TAG,
+        &request.msg_type,
+    ));
+    fields.append(&mut encode_optional_field(
+        2,
+        BIT_STRING_TAG,
+        &request.ap_options,
+    ));
+    fields.append(&mut encode_context_field(
+        3,
+        &encode_ticket(&request.ticket),
+    ));
+    fields.append(&mut encode_context_field(
+        4,
+        &encode_encrypted_data(&request.authenticator),
+    ));
+
+    encode_der_element(AP_REQ_TAG, &encode_der_element(SEQUENCE_TAG, &fields))
+}
+
+/// Serializes the AP-REQ within the Kerberos GSS-API initial context token (RFC 4121 4.1),
+/// which is the mechToken of the SPNEGO NegTokenInit.
+pub fn encode_kerberos_gss_token(token_id: &[u8], ap_req: &[u8]) -> Vec<u8> {
+    let mut content = encode_der_element(OID_TAG, KERBEROS_OID);
+    content.extend_from_slice(token_id);
+    content.extend_from_slice(ap_req);
+
+    encode_der_element(INITIAL_CONTEXT_TOKEN_TAG, &content)
+}
+
+/// Serializes the authenticator, the plaintext of the authenticator of the AP-REQ.
+pub fn encode_authenticator(authenticator: &Authenticator) -> Vec<u8> {
+    let mut fields = encode_optional_field(0, INTEGER_TAG, &authenticator.authenticator_vno);
+    fields.append(&mut encode_optional_field(
+        1,
+        GENERAL_STRING_TAG,
+        &authenticator.crealm,
+    ));
+    fields.append(&mut encode_context_field(
+        2,
+        &encode_principal_name(&authenticator.cname),
+    ));
+    if let Some(cksum) = &authenticator.cksum {
+        fields.append(&mut encode_context_field(3, &encode_checksum(cksum)));
+    }
+    fields.append(&mut encode_optional_field(
+        4,
+        INTEGER_TAG,
+        &authenticator.cusec,
+    ));
+    fields.append(&mut encode_optional_field(
+        5,
+        GENERALIZED_TIME_TAG,
+        &authenticator.ctime,
+    ));
+    if let Some(subkey) = &authenticator.subkey {
+        fields.append(&mut encode_context_field(6, &encode_encryption_key(subkey)));
+    }
+    fields.append(&mut encode_optional_field(
+        7,
+        INTEGER_TAG,
+        &authenticator.seq_number,
+    ));
+
+    encode_der_element(
+        AUTHENTICATOR_TAG,
+        &encode_der_element(SEQUENCE_TAG, &fields),
+    )
+}
+
+/// Serializes the ticket.
+pub fn encode_ticket(ticket: &Ticket) -> Vec<u8> {
+    let mut fields = encode_optional_field(0, INTEGER_TAG, &ticket.tkt_vno);
+    fields.append(&mut encode_optional_field(
+        1,
+        GENERAL_STRING_TAG,
+        &ticket.realm,
+    ));
+    fields.append(&mut encode_context_field(
+        2,
+        &encode_principal_name(&ticket.sname),
+    ));
+    fields.append(&mut encode_context_field(
+        3,
+        &encode_encrypted_data(&ticket.enc_part),
+    ));
+
+    encode_der_element(TICKET_TAG, &encode_der_element(SEQUENCE_TAG, &fields))
+}
+
+/// Serializes the principal name.
+pub fn encode_principal_name(name: &PrincipalName) -> Vec<u8> {
+    let mut fields = encode_optional_field(0, INTEGER_TAG, &name.name_type);
+    let name_string: Vec<u8> = name
+        .name_string
+        .iter()
+        .flat_map(|component| encode_der_element(GENERAL_STRING_TAG, component))
+        .collect();
+    fields.append(&mut encode_context_field(
+        1,
+        &encode_der_element(SEQUENCE_TAG, &name_string),
+    ));
+
+    encode_der_element(SEQUENCE_TAG, &fields)
+}
+
+/// Serializes the encrypted data.
+pub fn encode_encrypted_data(data: &EncryptedData) -> Vec<u8> {
+    let mut fields = encode_optional_field(0, INTEGER_TAG, &data.etype);
+    fields.append(&mut encode_optional_field(1, INTEGER_TAG, &data.kvno));
+    fields.append(&mut encode_optional_field(
+        2,
+        OCTET_STRING_TAG,
+        &data.cipher,
+    ));
+
+    encode_der_element(SEQUENCE_TAG, &fields)
+}
+
+/// Serializes the encryption key.
+pub fn encode_encryption_key(key: &EncryptionKey) -> Vec<u8> {
+    let mut fields = encode_optional_field(0, INTEGER_TAG, &key.key_type);
+    fields.append(&mut encode_optional_field(
+        1,
+        OCTET_STRING_TAG,
+        &key.key_value,
+    ));
+
+    encode_der_element(SEQUENCE_TAG, &fields)
+}
+
+/// Serializes the checksum.
+pub fn encode_checksum(checksum: &Checksum) -> Vec<u8> {
+    let mut fields = encode_optional_field(0, INTEGER_TAG, &checksum.cksum_type);
+    fields.append(&mut encode_optional_field(
+        1,
+        OCTET_STRING_TAG,
+        &checksum.checksum,
+    ));
+
+    encode_der_element(SEQUENCE_TAG, &fields)
+}
+
+/// Serializes an element of the pre-authentication data.
+pub fn encode_pa_data(padata: &PaData) -> Vec<u8> {
+    let mut fields = encode_optional_field(1, INTEGER_TAG, &padata.padata_type);
+    fields.append(&mut encode_optional_field(
+        2,
+        OCTET_STRING_TAG,
+        &padata.padata_value,
+    ));
+
+    encode_der_element(SEQUENCE_TAG, &fields)
+}
+
+/// Serializes the timestamp of the pre-authentication, the plaintext of PA-ENC-TIMESTAMP.
+pub fn encode_pa_enc_ts_enc(timestamp: &PaEncTsEnc) -> Vec<u8> {
+    let mut fields = encode_optional_field(0, GENERALIZED_TIME_TAG, &timestamp.patimestamp);
+    fields.append(&mut encode_optional_field(
+        1,
+        INTEGER_TAG,
+        &timestamp.pausec,
+    ));
+
+    encode_der_element(SEQUENCE_TAG, &fields)
+}
+
+/// Serializes the content of an INTEGER, the minimal big endian two's complement of the value.
+pub fn encode_integer(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let mut start = 0;
+    while start < bytes.len() - 1
+        && ((bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+
+    bytes[start..].to_vec()
+}
+
+/// Serializes the flags as the content of a BIT STRING of 32 bits without unused bits.
+pub fn encode_bit_flags(flags: u32) -> Vec<u8> {
+    let mut encoded = vec![0];
+    encoded.extend_from_slice(&flags.to_be_bytes());
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kerberos::{NT_PRINCIPAL, PVNO};
+
+    #[test]
+    fn test_encode_integer() {
+        assert_eq!(vec![0x00], encode_integer(0));
+        assert_eq!(vec![0x7f], encode_integer(127));
+        assert_eq!(vec![0x00, 0x80], encode_integer(128));
+        assert_eq!(vec![0x00, 0x80, 0x03], encode_integer(0x8003));
+        assert_eq!(vec![0xff, 0x76], encode_integer(-138));
+        assert_eq!(vec![0xff], encode_integer(-1));
+    }
+
+    #[test]
+    fn test_encode_principal_name() {
+        let name = PrincipalName {
+            name_type: vec![NT_PRINCIPAL],
+            name_string: vec![b"alice".to_vec()],
+        };
+
+        assert_eq!(
+            b"\x30\x10\xa0\x03\x02\x01\x01\xa1\x09\x30\x07\x1b\x05alice".to_vec(),
+            encode_principal_name(&name)
+        );
+    }
+
+    #[test]
+    fn test_encode_ticket() {
+        let mut ticket = Ticket::default();
+        ticket.realm = b"A".to_vec();
+        ticket.sname.name_string = vec![b"b".to_vec()];
+        ticket.enc_part.etype = vec![0x12];
+        ticket.enc_part.kvno = vec![PVNO];
+        ticket.enc_part.cipher = vec![0x42; 2];
+
+        assert_eq!(
+            b"\x61\x30\x30\x2e\xa0\x03\x02\x01\x05\xa1\x03\x1b\x01A\xa2\x0e\x30\x0c\xa0\x03\
+              \x02\x01\x01\xa1\x05\x30\x03\x1b\x01b\xa3\x12\x30\x10\xa0\x03\x02\x01\x12\xa1\
+              \x03\x02\x01\x05\xa2\x04\x04\x02\x42\x42"
+                .to_vec(),
+            encode_ticket(&ticket)
+        );
+    }
+}
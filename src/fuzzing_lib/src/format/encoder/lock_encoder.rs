@@ -0,0 +1,60 @@
+use crate::smb2::requests::lock::{Lock, LockElement};
+
+/// Serializes the lock request body.
+pub fn serialize_lock_request_body(request: &Lock) -> Vec<u8> {
+    let mut serialized_request: Vec<u8> = Vec::new();
+
+    serialized_request.append(&mut request.structure_size.clone());
+    serialized_request.append(&mut request.lock_count.clone());
+    serialized_request.append(&mut request.lock_sequence.clone());
+    serialized_request.append(&mut request.file_id.clone());
+
+    for lock in request.locks.iter() {
+        serialized_request.append(&mut serialize_lock_element(lock));
+    }
+
+    serialized_request
+}
+
+/// Serializes a single lock element.
+pub fn serialize_lock_element(lock: &LockElement) -> Vec<u8> {
+    let mut serialized_lock: Vec<u8> = Vec::new();
+
+    serialized_lock.append(&mut lock.offset.clone());
+    serialized_lock.append(&mut lock.length.clone());
+    serialized_lock.append(&mut lock.flags.clone());
+    serialized_lock.append(&mut lock.reserved.clone());
+
+    serialized_lock
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smb2::requests::lock::LockFlags;
+
+    #[test]
+    fn test_serialize_lock_request_body() {
+        let mut lock_request = Lock::default();
+        lock_request.lock_count = vec![1, 0];
+        lock_request.file_id = vec![0; 16];
+        lock_request
+            .locks
+            .push(LockElement::from_range(1, 2, vec![LockFlags::SharedLock]));
+
+        let expected_lock_request = vec![
+            b"\x30\x00\x01\x00\x00\x00\x00\x00".to_vec(),
+            vec![0; 16],
+            b"\x01\x00\x00\x00\x00\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00".to_vec(),
+            b"\x01\x00\x00\x00\x00\x00\x00\x00".to_vec(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<u8>>();
+
+        assert_eq!(
+            expected_lock_request,
+            serialize_lock_request_body(&lock_request)
+        );
+    }
+}
@@ -0,0 +1,11 @@
+use crate::smb2::logoff::LogOff;
+
+/// Serializes the logoff request body.
+pub fn serialize_logoff_request_body(request: &LogOff) -> Vec<u8> {
+    let mut serialized_request: Vec<u8> = Vec::new();
+
+    serialized_request.append(&mut request.structure_size.clone());
+    serialized_request.append(&mut request.reserved.clone());
+
+    serialized_request
+}
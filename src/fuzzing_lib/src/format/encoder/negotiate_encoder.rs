@@ -2,7 +2,7 @@ use crate::smb2::{
     helper_functions::negotiate_context::{
         CompressionCapabilities, ContextType, EncryptionCapabilities, NegotiateContext,
         NetnameNegotiateContextId, PreauthIntegrityCapabilities, RdmaTransformCapabilities,
-        TransportCapabilities,
+        SigningCapabilities, TransportCapabilities,
     },
     requests::negotiate::Negotiate,
 };
@@ -81,6 +81,8 @@ pub fn navigate_to_corresponding_serializer(context_type: &ContextType) -> Vec<u
             serialize_transport_capabilities(transport)
         }
         ContextType::RdmaTransformCapabilities(rdma) => serialize_rdma_transform_capabilities(rdma),
+        ContextType::SigningCapabilities(signing) => serialize_signing_capabilities(signing),
+        ContextType::Unknown { data, .. } => data.clone(),
     }
 }
 
@@ -148,6 +150,23 @@ pub fn serialize_rdma_transform_capabilities(rdma: &RdmaTransformCapabilities) -
     serialized_rdma_transform
 }
 
+/// Serializes signing capabilities.
+pub fn serialize_signing_capabilities(signing: &SigningCapabilities) -> Vec<u8> {
+    let mut serialized_signing: Vec<u8> = Vec::new();
+
+    serialized_signing.append(&mut signing.signing_algorithm_count.clone());
+    serialized_signing.append(
+        &mut signing
+            .signing_algorithms
+            .iter()
+            .cloned()
+            .flatten()
+            .collect(),
+    );
+
+    serialized_signing
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -0,0 +1,42 @@
+use crate::smb2::requests::oplock_break::{
+    LeaseBreakAcknowledgment, OplockBreak, OplockBreakAcknowledgment,
+};
+
+/// Serializes the oplock or lease break acknowledgment body.
+pub fn serialize_oplock_break_request_body(request: &OplockBreak) -> Vec<u8> {
+    match request {
+        OplockBreak::Oplock(acknowledgment) => {
+            serialize_oplock_break_acknowledgment(acknowledgment)
+        }
+        OplockBreak::Lease(acknowledgment) => serialize_lease_break_acknowledgment(acknowledgment),
+    }
+}
+
+/// Serializes the oplock break acknowledgment.
+pub fn serialize_oplock_break_acknowledgment(
+    acknowledgment: &OplockBreakAcknowledgment,
+) -> Vec<u8> {
+    let mut serialized_request: Vec<u8> = Vec::new();
+
+    serialized_request.append(&mut acknowledgment.structure_size.clone());
+    serialized_request.append(&mut acknowledgment.oplock_level.clone());
+    serialized_request.append(&mut acknowledgment.reserved.clone());
+    serialized_request.append(&mut acknowledgment.reserved_2.clone());
+    serialized_request.append(&mut acknowledgment.file_id.clone());
+
+    serialized_request
+}
+
+/// Serializes the lease break acknowledgment.
+pub fn serialize_lease_break_acknowledgment(acknowledgment: &LeaseBreakAcknowledgment) -> Vec<u8> {
+    let mut serialized_request: Vec<u8> = Vec::new();
+
+    serialized_request.append(&mut acknowledgment.structure_size.clone());
+    serialized_request.append(&mut acknowledgment.reserved.clone());
+    serialized_request.append(&mut acknowledgment.flags.clone());
+    serialized_request.append(&mut acknowledgment.lease_key.clone());
+    serialized_request.append(&mut acknowledgment.lease_state.clone());
+    serialized_request.append(&mut acknowledgment.lease_duration.clone());
+
+    serialized_request
+}
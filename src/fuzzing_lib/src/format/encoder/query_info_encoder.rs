@@ -1,4 +1,4 @@
-use crate::smb2::requests::query_info::QueryInfo;
+use crate::smb2::requests::query_info::{QueryInfo, QueryQuotaInfo};
 
 /// Serializes a query info request from the corresponding struct.
 pub fn serialize_query_info_request_body(request: &QueryInfo) -> Vec<u8> {
@@ -19,3 +19,31 @@ pub fn serialize_query_info_request_body(request: &QueryInfo) -> Vec<u8> {
 
     serialized_request
 }
+
+/// Serializes the query quota info that is sent in the input buffer of quota requests.
+pub fn serialize_query_quota_info(quota_info: &QueryQuotaInfo) -> Vec<u8> {
+    let mut serialized_quota_info: Vec<u8> = Vec::new();
+
+    serialized_quota_info.append(&mut quota_info.return_single.clone());
+    serialized_quota_info.append(&mut quota_info.restart_scan.clone());
+    serialized_quota_info.append(&mut quota_info.reserved.clone());
+    serialized_quota_info.append(&mut quota_info.sid_list_length.clone());
+    serialized_quota_info.append(&mut quota_info.start_sid_length.clone());
+    serialized_quota_info.append(&mut quota_info.start_sid_offset.clone());
+    serialized_quota_info.append(&mut quota_info.sid_buffer.clone());
+
+    serialized_quota_info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize_query_quota_info() {
+        assert_eq!(
+            b"\x00\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00".to_vec(),
+            serialize_query_quota_info(&QueryQuotaInfo::default())
+        );
+    }
+}
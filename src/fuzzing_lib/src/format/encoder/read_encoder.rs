@@ -0,0 +1,21 @@
+use crate::smb2::requests::read::Read;
+
+/// Serializes the read request body.
+pub fn serialize_read_request_body(request: &Read) -> Vec<u8> {
+    let mut serialized_request: Vec<u8> = Vec::new();
+
+    serialized_request.append(&mut request.structure_size.clone());
+    serialized_request.append(&mut request.padding.clone());
+    serialized_request.append(&mut request.flags.clone());
+    serialized_request.append(&mut request.length.clone());
+    serialized_request.append(&mut request.offset.clone());
+    serialized_request.append(&mut request.file_id.clone());
+    serialized_request.append(&mut request.minimum_count.clone());
+    serialized_request.append(&mut request.channel.clone());
+    serialized_request.append(&mut request.remaining_bytes.clone());
+    serialized_request.append(&mut request.read_channel_info_offset.clone());
+    serialized_request.append(&mut request.read_channel_info_length.clone());
+    serialized_request.append(&mut request.buffer.clone());
+
+    serialized_request
+}
@@ -1,8 +1,9 @@
 use ntlmssp::MessageType;
 
+use super::spnego_encoder::{encode_neg_token_init, encode_neg_token_resp};
 use crate::{
-    gss,
-    ntlmssp::{self, authenticate::NtlmV2ClientChallenge, AvPair},
+    gss::{self, NTLMSSP_OID},
+    ntlmssp::{self, authenticate::NtlmV2ClientChallenge, negotiate::Negotiate, AvPair, Version},
 };
 
 /// Serializes the challenge's target info.
@@ -37,16 +38,67 @@ pub fn encode_authenticate_blob(mut ntlm_header: ntlmssp::Header) -> Vec<u8> {
     encoded
 }
 
-/// Serializes the authentication gss token.
-pub fn encode_security_authentication(mut token: gss::NegTokenResp) -> Vec<u8> {
+/// Serializes the initial security blob of the first session setup request: the NTLM
+/// NEGOTIATE_MESSAGE as the mechToken of the negTokenInit, wrapped in the GSS-API initial context token.
+pub fn encode_initial_security_blob(ntlm_header: ntlmssp::Header) -> Vec<u8> {
+    let mut token = gss::NegTokenInit::default();
+    token.mech_types = vec![NTLMSSP_OID.to_vec()];
+    token.mech_token = encode_negotiate_blob(ntlm_header);
+
+    encode_neg_token_init(token)
+}
+
+/// Serializes the NTLM negotiate blob.
+pub fn encode_negotiate_blob(mut ntlm_header: ntlmssp::Header) -> Vec<u8> {
+    let mut encoded: Vec<u8> = Vec::new();
+
+    let negotiate = match ntlm_header.message.unwrap() {
+        MessageType::Negotiate(negotiate) => negotiate,
+        _ => panic!("Invalid message type for negotiate encoding."),
+    };
+    encoded.append(&mut ntlm_header.signature);
+    encoded.append(&mut ntlm_header.message_type);
+    encoded.append(&mut encode_negotiate_message(negotiate));
+
+    encoded
+}
+
+/// Serializes the negotiate message.
+pub fn encode_negotiate_message(mut message: Negotiate) -> Vec<u8> {
+    let mut encoded: Vec<u8> = Vec::new();
+
+    encoded.append(&mut message.negotiate_flags);
+    encoded.append(&mut message.domain_name_fields.domain_name_len);
+    encoded.append(&mut message.domain_name_fields.domain_name_max_len);
+    encoded.append(&mut message.domain_name_fields.domain_name_buffer_offset);
+    encoded.append(&mut message.workstation_fields.workstation_len);
+    encoded.append(&mut message.workstation_fields.workstation_max_len);
+    encoded.append(&mut message.workstation_fields.workstation_buffer_offset);
+    encoded.append(&mut encode_version(message.version));
+    encoded.append(&mut message.payload.domain_name);
+    encoded.append(&mut message.payload.workstation_name);
+
+    encoded
+}
+
+/// Serializes the NTLM version.
+pub fn encode_version(mut version: Version) -> Vec<u8> {
     let mut encoded: Vec<u8> = Vec::new();
 
-    encoded.append(&mut token.state);
-    encoded.append(&mut token.response_token);
+    encoded.append(&mut version.product_major_version);
+    encoded.append(&mut version.product_minor_version);
+    encoded.append(&mut version.product_build);
+    encoded.append(&mut version.reserved);
+    encoded.append(&mut version.ntlm_revision_current);
 
     encoded
 }
 
+/// Serializes the authentication gss token.
+pub fn encode_security_authentication(token: gss::NegTokenResp) -> Vec<u8> {
+    encode_neg_token_resp(token)
+}
+
 /// Serializes the authenticate message.
 pub fn encode_authenticate_message(mut message: ntlmssp::authenticate::Authenticate) -> Vec<u8> {
     let mut encoded: Vec<u8> = Vec::new();
@@ -106,10 +158,7 @@ pub fn encode_authenticate_message(mut message: ntlmssp::authenticate::Authentic
             .encrypted_random_session_key_buffer_offset,
     );
     encoded.append(&mut message.negotiate_flags);
-    encoded.append(&mut message.version.product_major_version);
-    encoded.append(&mut message.version.product_minor_version);
-    encoded.append(&mut message.version.product_build);
-    encoded.append(&mut message.version.ntlm_revision_current);
+    encoded.append(&mut encode_version(message.version));
     encoded.append(&mut message.mic);
     encoded.append(&mut message.payload.lm_challenge_response);
 
@@ -142,6 +191,7 @@ pub fn serialize_ntlm_challenge(mut ntlm_challenge: NtlmV2ClientChallenge) -> Ve
         ntlm_challenge.av_pairs,
         false,
     ));
+    encoded.append(&mut ntlm_challenge.padding);
 
     encoded
 }
@@ -21,7 +21,9 @@ pub fn serialize_session_setup_request_body(request: &SessionSetup) -> Vec<u8> {
 mod tests {
 
     use super::*;
-    use crate::builder::session_setup_negotiate_request::INITIAL_SECURITY_BLOB;
+    use crate::builder::session_setup_negotiate_request::{
+        build_default_ntlm_negotiate_message, build_initial_security_blob,
+    };
 
     struct Setup {
         session_setup: SessionSetup,
@@ -37,7 +39,8 @@ mod tests {
             session_setup.security_buffer_offset = b"\x58\x00".to_vec();
             session_setup.security_buffer_length = b"\x4a\x00".to_vec();
             session_setup.previous_session_id = vec![0; 8];
-            session_setup.buffer = INITIAL_SECURITY_BLOB.to_vec();
+            session_setup.buffer =
+                build_initial_security_blob(build_default_ntlm_negotiate_message());
 
             Setup { session_setup }
         }
@@ -50,7 +53,9 @@ mod tests {
         let mut expected_byte_array = b"\x19\x00\x00\x01\x01\x00\x00\x00\x00\x00\x00\x00\
                                             \x58\x00\x4a\x00\x00\x00\x00\x00\x00\x00\x00\x00"
             .to_vec();
-        expected_byte_array.append(&mut INITIAL_SECURITY_BLOB.to_vec());
+        expected_byte_array.append(&mut build_initial_security_blob(
+            build_default_ntlm_negotiate_message(),
+        ));
 
         assert_eq!(
             expected_byte_array,
@@ -0,0 +1,34 @@
+use crate::smb2::{
+    requests::set_info::SetInfo, responses::query_info::file_information::FileBasicInformation,
+};
+
+/// Serializes the set info request body.
+pub fn serialize_set_info_request_body(request: &SetInfo) -> Vec<u8> {
+    let mut serialized_request: Vec<u8> = Vec::new();
+
+    serialized_request.append(&mut request.structure_size.clone());
+    serialized_request.append(&mut request.info_type.clone());
+    serialized_request.append(&mut request.file_info_class.clone());
+    serialized_request.append(&mut request.buffer_length.clone());
+    serialized_request.append(&mut request.buffer_offset.clone());
+    serialized_request.append(&mut request.reserved.clone());
+    serialized_request.append(&mut request.additional_information.clone());
+    serialized_request.append(&mut request.file_id.clone());
+    serialized_request.append(&mut request.buffer.clone());
+
+    serialized_request
+}
+
+/// Serializes the file basic information that is sent in the buffer of a set info request.
+pub fn serialize_file_basic_information(information: &FileBasicInformation) -> Vec<u8> {
+    let mut serialized_information: Vec<u8> = Vec::new();
+
+    serialized_information.append(&mut information.creation_time.clone());
+    serialized_information.append(&mut information.last_access_time.clone());
+    serialized_information.append(&mut information.last_write_time.clone());
+    serialized_information.append(&mut information.change_time.clone());
+    serialized_information.append(&mut information.file_attributes.clone());
+    serialized_information.append(&mut information.reserved.clone());
+
+    serialized_information
+}
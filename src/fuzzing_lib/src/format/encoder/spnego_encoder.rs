@@ -0,0 +1,231 @@
+//! Serializes the SPNEGO negotiation tokens with ASN.1 DER. The lengths of all elements are
+//! computed from their contents, in the short form below 128 bytes and in the minimal long form otherwise.
+
+use crate::gss::{
+    NegHints, NegTokenInit, NegTokenInit2, NegTokenResp, NegotiationToken, SPNEGO_OID,
+};
+
+/// The tag of the GSS-API initial context token ([APPLICATION 0]).
+pub const INITIAL_CONTEXT_TOKEN_TAG: u8 = 0x60;
+/// The tag of the negTokenInit choice ([0]).
+pub const NEG_TOKEN_INIT_TAG: u8 = 0xa0;
+/// The tag of the negTokenResp choice ([1]).
+pub const NEG_TOKEN_RESP_TAG: u8 = 0xa1;
+pub const SEQUENCE_TAG: u8 = 0x30;
+pub const OID_TAG: u8 = 0x06;
+pub const OCTET_STRING_TAG: u8 = 0x04;
+pub const BIT_STRING_TAG: u8 = 0x03;
+pub const ENUMERATED_TAG: u8 = 0x0a;
+pub const GENERAL_STRING_TAG: u8 = 0x1b;
+
+/// Serializes the negotiation token. Initial tokens are wrapped in the GSS-API initial context token.
+pub fn encode_negotiation_token(token: NegotiationToken) -> Vec<u8> {
+    match token {
+        NegotiationToken::NegTokenInit(token) => encode_neg_token_init(token),
+        NegotiationToken::NegTokenInit2(token) => encode_neg_token_init2(token),
+        NegotiationToken::NegTokenResp(token) => encode_neg_token_resp(token),
+    }
+}
+
+/// Serializes the NegTokenInit within the GSS-API initial context token.
+pub fn encode_neg_token_init(token: NegTokenInit) -> Vec<u8> {
+    let mut fields = encode_mech_types(0, &token.mech_types);
+    fields.append(&mut encode_optional_field(
+        1,
+        BIT_STRING_TAG,
+        &token.req_flags,
+    ));
+    fields.append(&mut encode_optional_field(
+        2,
+        OCTET_STRING_TAG,
+        &token.mech_token,
+    ));
+    fields.append(&mut encode_optional_field(
+        3,
+        OCTET_STRING_TAG,
+        &token.mech_list_mic,
+    ));
+
+    encode_initial_context_token(&fields)
+}
+
+/// Serializes the NegTokenInit2 within the GSS-API initial context token.
+pub fn encode_neg_token_init2(token: NegTokenInit2) -> Vec<u8> {
+    let mut fields = encode_mech_types(0, &token.mech_types);
+    fields.append(&mut encode_optional_field(
+        1,
+        BIT_STRING_TAG,
+        &token.req_flags,
+    ));
+    fields.append(&mut encode_optional_field(
+        2,
+        OCTET_STRING_TAG,
+        &token.mech_token,
+    ));
+    fields.append(&mut encode_neg_hints(3, &token.neg_hints));
+    fields.append(&mut encode_optional_field(
+        4,
+        OCTET_STRING_TAG,
+        &token.mech_list_mic,
+    ));
+
+    encode_initial_context_token(&fields)
+}
+
+/// Serializes the NegTokenResp. Empty fields are omitted.
+pub fn encode_neg_token_resp(token: NegTokenResp) -> Vec<u8> {
+    let mut fields = encode_optional_field(0, ENUMERATED_TAG, &token.state);
+    fields.append(&mut encode_optional_field(
+        1,
+        OID_TAG,
+        &token.supported_mech,
+    ));
+    fields.append(&mut encode_optional_field(
+        2,
+        OCTET_STRING_TAG,
+        &token.response_token,
+    ));
+    fields.append(&mut encode_optional_field(
+        3,
+        OCTET_STRING_TAG,
+        &token.mech_list_mic,
+    ));
+
+    encode_der_element(
+        NEG_TOKEN_RESP_TAG,
+        &encode_der_element(SEQUENCE_TAG, &fields),
+    )
+}
+
+/// Wraps the fields of an initial token in its SEQUENCE, the negTokenInit choice
+/// and the GSS-API initial context token with the SPNEGO OID.
+pub fn encode_initial_context_token(fields: &[u8]) -> Vec<u8> {
+    let mut content = encode_der_element(OID_TAG, SPNEGO_OID);
+    content.append(&mut encode_der_element(
+        NEG_TOKEN_INIT_TAG,
+        &encode_der_element(SEQUENCE_TAG, fields),
+    ));
+
+    encode_der_element(INITIAL_CONTEXT_TOKEN_TAG, &content)
+}
+
+/// Serializes the MechTypeList as the context specific field with the given number.
+pub fn encode_mech_types(field: u8, mech_types: &[Vec<u8>]) -> Vec<u8> {
+    if mech_types.is_empty() {
+        return Vec::new();
+    }
+
+    let oids: Vec<u8> = mech_types
+        .iter()
+        .flat_map(|oid| encode_der_element(OID_TAG, oid))
+        .collect();
+
+    encode_context_field(field, &encode_der_element(SEQUENCE_TAG, &oids))
+}
+
+/// Serializes the NegHints as the context specific field with the given number,
+/// if any hint is present.
+pub fn encode_neg_hints(field: u8, neg_hints: &NegHints) -> Vec<u8> {
+    if neg_hints.hint_name.is_empty() && neg_hints.hint_address.is_empty() {
+        return Vec::new();
+    }
+
+    let mut hints = encode_optional_field(0, GENERAL_STRING_TAG, &neg_hints.hint_name);
+    hints.append(&mut encode_optional_field(
+        1,
+        OCTET_STRING_TAG,
+        &neg_hints.hint_address,
+    ));
+
+    encode_context_field(field, &encode_der_element(SEQUENCE_TAG, &hints))
+}
+
+/// Serializes the value with the given tag as the context specific field with the given number.
+/// An empty value is omitted.
+pub fn encode_optional_field(field: u8, tag: u8, value: &[u8]) -> Vec<u8> {
+    if value.is_empty() {
+        return Vec::new();
+    }
+
+    encode_context_field(field, &encode_der_element(tag, value))
+}
+
+/// Serializes the encoded element as the constructed context specific field with the given number.
+pub fn encode_context_field(field: u8, element: &[u8]) -> Vec<u8> {
+    encode_der_element(0xa0 | field, element)
+}
+
+/// Serializes a DER element of the given tag and content.
+pub fn encode_der_element(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![tag];
+    encoded.append(&mut encode_der_length(content.len()));
+    encoded.extend_from_slice(content);
+
+    encoded
+}
+
+/// Serializes a DER length in the short form below 128 and in the minimal long form otherwise.
+pub fn encode_der_length(length: usize) -> Vec<u8> {
+    if length < 0x80 {
+        return vec![length as u8];
+    }
+
+    let bytes: Vec<u8> = (length as u64)
+        .to_be_bytes()
+        .iter()
+        .skip_while(|byte| **byte == 0)
+        .cloned()
+        .collect();
+    let mut encoded = vec![0x80 | bytes.len() as u8];
+    encoded.extend_from_slice(&bytes);
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gss::NTLMSSP_OID;
+
+    #[test]
+    fn test_encode_neg_token_init() {
+        let mut token = NegTokenInit::default();
+        token.mech_types = vec![NTLMSSP_OID.to_vec()];
+        token.mech_token = vec![0x42; 0x28];
+
+        let mut expected = b"\x60\x48\x06\x06\x2b\x06\x01\x05\x05\x02\xa0\x3e\x30\x3c\xa0\x0e\
+                             \x30\x0c\x06\x0a\x2b\x06\x01\x04\x01\x82\x37\x02\x02\x0a\xa2\x2a\
+                             \x04\x28"
+            .to_vec();
+        expected.append(&mut vec![0x42; 0x28]);
+
+        assert_eq!(expected, encode_neg_token_init(token));
+    }
+
+    #[test]
+    fn test_encode_neg_token_resp() {
+        let mut token = NegTokenResp::default();
+        token.response_token = vec![0x42; 0x0146];
+
+        let mut expected =
+            b"\xa1\x82\x01\x52\x30\x82\x01\x4e\xa2\x82\x01\x4a\x04\x82\x01\x46".to_vec();
+        expected.append(&mut vec![0x42; 0x0146]);
+        assert_eq!(expected, encode_neg_token_resp(token));
+
+        let mut token = NegTokenResp::default();
+        token.state = vec![1];
+        token.supported_mech = NTLMSSP_OID.to_vec();
+        assert_eq!(
+            b"\xa1\x15\x30\x13\xa0\x03\x0a\x01\x01\xa1\x0c\x06\x0a\x2b\x06\x01\x04\x01\x82\x37\x02\x02\x0a"
+                .to_vec(),
+            encode_neg_token_resp(token)
+        );
+    }
+
+    #[test]
+    fn test_encode_der_length() {
+        assert_eq!(vec![0x7f], encode_der_length(0x7f));
+        assert_eq!(vec![0x81, 0x80], encode_der_length(0x80));
+        assert_eq!(vec![0x82, 0x01, 0x00], encode_der_length(0x100));
+    }
+}
@@ -1,7 +1,10 @@
 pub mod decoder;
 pub mod encoder;
 
-const HEADER_LENGTH: usize = 64;
+/// The length of the SMB2 header in bytes.
+pub const HEADER_LENGTH: usize = 64;
+/// The length of the SMB2 TRANSFORM_HEADER in bytes.
+pub const TRANSFORM_HEADER_LENGTH: usize = 52;
 
 /// Converts an array of bytes to an u32 integer.
 pub fn convert_byte_array_to_int(mut arr: Vec<u8>, big_endian: bool) -> u32 {
@@ -0,0 +1,106 @@
+use rand::{
+    distributions::{Distribution, Standard},
+    Rng,
+};
+
+use crate::smb2::requests::cancel::Cancel;
+
+use super::create_random_byte_array_of_predefined_length;
+use super::create_random_byte_array_with_random_length;
+
+/// Fuzzes the cancel request with predefined values.
+pub fn fuzz_cancel_with_predefined_values() -> Cancel {
+    Cancel::default()
+}
+
+/// Fuzzes the cancel request with random values that comply to the size restrictions of certain fields.
+pub fn fuzz_cancel_with_random_fields() -> Cancel {
+    let mut cancel_request = Cancel::default();
+
+    cancel_request.structure_size = create_random_byte_array_of_predefined_length(2);
+    cancel_request.reserved = create_random_byte_array_of_predefined_length(2);
+
+    cancel_request
+}
+
+/// Fuzzes the cancel request with random values of random length.
+pub fn fuzz_cancel_completely_random() -> Cancel {
+    let mut cancel_request = Cancel::default();
+
+    cancel_request.structure_size = create_random_byte_array_with_random_length();
+    cancel_request.reserved = create_random_byte_array_with_random_length();
+
+    cancel_request
+}
+
+/// Samples the message id a cancel request is sent for.
+/// Besides the outstanding request itself, the cancel targets requests that
+/// have already been completed, that have not been sent yet or that can never exist.
+pub fn sample_cancel_message_id(outstanding_message_id: u64) -> u64 {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=4) {
+        0 => outstanding_message_id,
+        1 => outstanding_message_id.saturating_sub(rng.gen_range(1..=8)),
+        2 => outstanding_message_id.saturating_add(rng.gen_range(1..=8)),
+        3 => u64::MAX,
+        _ => rng.gen(),
+    }
+}
+
+/// Samples the async id a cancel request is sent for.
+/// Besides the id of the pending operation, the cancel targets
+/// slightly altered, zeroed and random async ids.
+pub fn sample_cancel_async_id(pending_async_id: Vec<u8>) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=3) {
+        0 => pending_async_id,
+        1 => {
+            let mut altered_async_id = pending_async_id;
+            if let Some(byte) = altered_async_id.first_mut() {
+                *byte = byte.wrapping_add(rng.gen_range(1..=u8::MAX));
+            }
+            altered_async_id
+        }
+        2 => vec![0; 8],
+        _ => create_random_byte_array_of_predefined_length(8),
+    }
+}
+
+/// The requests a cancel request is sent for.
+///
+/// *Pending Message Id*:
+///     - Cancels the pending change notify by the message id of its request.
+///
+/// *Pending Async Id*:
+///     - Cancels the pending change notify by the async id of its interim response.
+///
+/// *Unallocated Message Id*:
+///     - Cancels the next message id of the connection, which has not been allocated to a request yet.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CancelTarget {
+    PendingMessageId,
+    PendingAsyncId,
+    UnallocatedMessageId,
+}
+
+impl Distribution<CancelTarget> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> CancelTarget {
+        match rng.gen_range(0..=2) {
+            0 => CancelTarget::PendingMessageId,
+            1 => CancelTarget::PendingAsyncId,
+            _ => CancelTarget::UnallocatedMessageId,
+        }
+    }
+}
+
+/// Samples the request the cancel request is sent for.
+/// Without a pending operation, only the unallocated message id can be targeted.
+pub fn sample_cancel_target(has_pending_operation: bool) -> CancelTarget {
+    if has_pending_operation {
+        rand::random()
+    } else {
+        CancelTarget::UnallocatedMessageId
+    }
+}
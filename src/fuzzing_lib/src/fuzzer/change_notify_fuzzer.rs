@@ -0,0 +1,162 @@
+use rand::{
+    distributions::{Distribution, Standard},
+    Rng,
+};
+
+use crate::smb2::requests::change_notify::{ChangeNotify, ChangeNotifyFlags, CompletionFilter};
+
+use super::create_random_byte_array_of_predefined_length;
+use super::create_random_byte_array_with_random_length;
+
+/// The mask of all completion filter bits that are defined in MS-SMB2.
+pub const VALID_COMPLETION_FILTER_MASK: u32 = 0x00000fff;
+
+/// The output buffer length up to which a single credit suffices (64 KiB).
+pub const SINGLE_CREDIT_OUTPUT_BUFFER_LENGTH: u32 = 0x10000;
+
+/// Fuzzes the change notify request with predefined values.
+/// The flags, the output buffer length and the completion filter are sampled
+/// from valid, boundary and undefined values.
+pub fn fuzz_change_notify_with_predefined_values(file_id: Vec<u8>) -> ChangeNotify {
+    let mut change_notify_request = ChangeNotify::default();
+
+    change_notify_request.flags = sample_change_notify_flags();
+    change_notify_request.output_buffer_length = sample_output_buffer_length();
+    change_notify_request.file_id = file_id;
+    change_notify_request.completion_filter = sample_completion_filter();
+
+    change_notify_request
+}
+
+/// Samples the change notify flags. Besides no flags and WATCH_TREE,
+/// the flags contain undefined bits.
+pub fn sample_change_notify_flags() -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=2) {
+        0 => vec![0; 2],
+        1 => ChangeNotifyFlags::WatchTree.unpack_byte_code(),
+        _ => (rng.gen::<u16>() | 0x0002).to_le_bytes().to_vec(),
+    }
+}
+
+/// Samples the output buffer length. Lengths of zero or smaller than a single
+/// FILE_NOTIFY_INFORMATION entry force STATUS_NOTIFY_ENUM_DIR, while lengths beyond
+/// a single credit or the maximum transact size stress the credit charge validation.
+pub fn sample_output_buffer_length() -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    let output_buffer_length: u32 = match rng.gen_range(0..=4) {
+        0 => 0,
+        1 => rng.gen_range(1..16),
+        2 => rng.gen_range(16..=SINGLE_CREDIT_OUTPUT_BUFFER_LENGTH),
+        3 => SINGLE_CREDIT_OUTPUT_BUFFER_LENGTH + rng.gen_range(1..=0x100000),
+        _ => u32::MAX,
+    };
+
+    output_buffer_length.to_le_bytes().to_vec()
+}
+
+/// Samples the completion filter. It is either empty, a combination of valid filters,
+/// all valid filters at once or a combination of valid filters with undefined bits.
+pub fn sample_completion_filter() -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=3) {
+        0 => vec![0; 4],
+        1 => CompletionFilter::return_sum_of_chosen_completion_filters(
+            (0..rng.gen_range(1..=12))
+                .map(|_| rand::random::<CompletionFilter>())
+                .collect(),
+        ),
+        2 => VALID_COMPLETION_FILTER_MASK.to_le_bytes().to_vec(),
+        _ => (rng.gen::<u32>() | !VALID_COMPLETION_FILTER_MASK)
+            .to_le_bytes()
+            .to_vec(),
+    }
+}
+
+/// Fuzzes the change notify request with random values that comply to the size restrictions of certain fields.
+pub fn fuzz_change_notify_with_random_fields() -> ChangeNotify {
+    let mut change_notify_request = ChangeNotify::default();
+
+    change_notify_request.structure_size = create_random_byte_array_of_predefined_length(2);
+    change_notify_request.flags = create_random_byte_array_of_predefined_length(2);
+    change_notify_request.output_buffer_length = create_random_byte_array_of_predefined_length(4);
+    change_notify_request.file_id = create_random_byte_array_of_predefined_length(16);
+    change_notify_request.completion_filter = create_random_byte_array_of_predefined_length(4);
+    change_notify_request.reserved = create_random_byte_array_of_predefined_length(4);
+
+    change_notify_request
+}
+
+/// Fuzzes the change notify request with random values of random length.
+pub fn fuzz_change_notify_completely_random() -> ChangeNotify {
+    let mut change_notify_request = ChangeNotify::default();
+
+    change_notify_request.structure_size = create_random_byte_array_with_random_length();
+    change_notify_request.flags = create_random_byte_array_with_random_length();
+    change_notify_request.output_buffer_length = create_random_byte_array_with_random_length();
+    change_notify_request.file_id = create_random_byte_array_with_random_length();
+    change_notify_request.completion_filter = create_random_byte_array_with_random_length();
+    change_notify_request.reserved = create_random_byte_array_with_random_length();
+
+    change_notify_request
+}
+
+/// The operations that are sent while a change notify request is pending
+/// in order to race its completion.
+///
+/// *Set Info*:
+///     - Changes the last write time of the watched directory.
+///
+/// *Close*:
+///     - Closes the watched directory, which completes the notify with STATUS_NOTIFY_CLEANUP.
+///
+/// *Logoff*:
+///     - Terminates the session the notify was issued on.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RacingOperation {
+    SetInfo,
+    Close,
+    Logoff,
+}
+
+impl Distribution<RacingOperation> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> RacingOperation {
+        match rng.gen_range(0..=2) {
+            0 => RacingOperation::SetInfo,
+            1 => RacingOperation::Close,
+            _ => RacingOperation::Logoff,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::convert_byte_array_to_int;
+
+    #[test]
+    fn test_fuzz_change_notify_with_predefined_values() {
+        let change_notify_request = fuzz_change_notify_with_predefined_values(vec![0; 16]);
+
+        assert_eq!(2, change_notify_request.flags.len());
+        assert_eq!(4, change_notify_request.output_buffer_length.len());
+        assert_eq!(4, change_notify_request.completion_filter.len());
+        assert_eq!(vec![0; 16], change_notify_request.file_id);
+    }
+
+    #[test]
+    fn test_sample_completion_filter() {
+        for _ in 0..32 {
+            let completion_filter = convert_byte_array_to_int(sample_completion_filter(), false);
+
+            assert!(
+                completion_filter & VALID_COMPLETION_FILTER_MASK == completion_filter
+                    || completion_filter & !VALID_COMPLETION_FILTER_MASK
+                        == !VALID_COMPLETION_FILTER_MASK
+            );
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use rand::{
+    distributions::{Distribution, Standard},
+    Rng,
+};
+
+use crate::{
+    builder::{
+        compound_request::{build_compound_request, set_related_operations_flag},
+        create_request::build_default_create_request_body,
+        read_request::build_default_read_request_body,
+    },
+    format::{
+        convert_byte_array_to_int, encoder::compound_encoder::serialize_compound_entries,
+        HEADER_LENGTH,
+    },
+    smb2::requests::{
+        close::Close,
+        compound::{CompoundEntry, RELATED_FILE_ID},
+        flush::Flush,
+        RequestType,
+    },
+};
+
+use super::{
+    close_fuzzer, create_fuzzer, create_random_byte_array_of_predefined_length,
+    create_random_byte_array_with_random_length, flush_fuzzer, query_info_fuzzer, read_fuzzer,
+};
+
+/// The maximum number of requests that follow the initial create in a fuzzed compound chain.
+pub const MAX_COMPOUND_OPERATION_COUNT: u32 = 5;
+
+/// The operations that can follow the initial create in a compound chain.
+/// All of them act on the file id of the previous operation if they are related.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CompoundOperation {
+    Create,
+    Read,
+    QueryInfo,
+    Flush,
+    Close,
+}
+
+impl Distribution<CompoundOperation> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> CompoundOperation {
+        match rng.gen_range(0..=4) {
+            0 => CompoundOperation::Create,
+            1 => CompoundOperation::Read,
+            2 => CompoundOperation::QueryInfo,
+            3 => CompoundOperation::Flush,
+            _ => CompoundOperation::Close,
+        }
+    }
+}
+
+/// Fuzzes a compound request with predefined values. The chain starts with a create
+/// and continues with creates, reads, query infos, flushes and closes on the inherited handle,
+/// which are mixed related and unrelated operations. Reads and closes that follow a close
+/// use a handle that no longer exists. In half of the cases, the chain structure is corrupted.
+pub fn fuzz_compound_with_predefined_values(
+    tree_id: Vec<u8>,
+    session_id: Vec<u8>,
+) -> Vec<CompoundEntry> {
+    let mut rng = rand::thread_rng();
+
+    let mut bodies = vec![RequestType::Create(build_default_create_request_body())];
+    let mut related = vec![false];
+    for _ in 0..rng.gen_range(1..=MAX_COMPOUND_OPERATION_COUNT) {
+        bodies.push(build_related_operation_body(rand::random()));
+        related.push(rng.gen_bool(0.75));
+    }
+
+    let entries = build_compound_request(bodies, related, tree_id, session_id);
+
+    if rng.gen_bool(0.5) {
+        corrupt_compound_chain(entries, rand::random())
+    } else {
+        entries
+    }
+}
+
+/// Builds the body of an operation that targets the file id inherited from the previous operation.
+pub fn build_related_operation_body(operation: CompoundOperation) -> RequestType {
+    match operation {
+        CompoundOperation::Create => RequestType::Create(build_default_create_request_body()),
+        CompoundOperation::Read => RequestType::Read(if rand::thread_rng().gen_bool(0.5) {
+            build_default_read_request_body(RELATED_FILE_ID.to_vec())
+        } else {
+            read_fuzzer::fuzz_read_with_predefined_values(RELATED_FILE_ID.to_vec())
+        }),
+        CompoundOperation::QueryInfo => RequestType::QueryInfo(
+            query_info_fuzzer::fuzz_query_info_with_predefined_values(RELATED_FILE_ID.to_vec()),
+        ),
+        CompoundOperation::Flush => {
+            let mut flush = Flush::default();
+            flush.file_id = RELATED_FILE_ID.to_vec();
+            RequestType::Flush(flush)
+        }
+        CompoundOperation::Close => {
+            let mut close = Close::default();
+            close.file_id = RELATED_FILE_ID.to_vec();
+            RequestType::Close(close)
+        }
+    }
+}
+
+/// The corruptions of the compound chain structure.
+///
+/// *Next Command Beyond Frame*:
+///     - A NextCommand offset points beyond the end of the frame.
+///
+/// *Unaligned Next Command*:
+///     - A NextCommand offset is not 8-byte aligned and points into the middle of a request.
+///
+/// *Next Command Into Header*:
+///     - A NextCommand offset is smaller than the size of the SMB2 header.
+///
+/// *Premature End*:
+///     - A NextCommand offset in the middle of the chain is 0, so that the remaining requests trail the frame.
+///
+/// *Related First Entry*:
+///     - The first request is marked as related, although there is no operation to inherit from.
+///
+/// *Inconsistent Related Ids*:
+///     - A related request carries a session id and tree id that differ from the previous request.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CompoundCorruption {
+    NextCommandBeyondFrame,
+    UnalignedNextCommand,
+    NextCommandIntoHeader,
+    PrematureEnd,
+    RelatedFirstEntry,
+    InconsistentRelatedIds,
+}
+
+impl Distribution<CompoundCorruption> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> CompoundCorruption {
+        match rng.gen_range(0..=5) {
+            0 => CompoundCorruption::NextCommandBeyondFrame,
+            1 => CompoundCorruption::UnalignedNextCommand,
+            2 => CompoundCorruption::NextCommandIntoHeader,
+            3 => CompoundCorruption::PrematureEnd,
+            4 => CompoundCorruption::RelatedFirstEntry,
+            _ => CompoundCorruption::InconsistentRelatedIds,
+        }
+    }
+}
+
+/// Applies the given corruption to a chained compound request.
+pub fn corrupt_compound_chain(
+    mut entries: Vec<CompoundEntry>,
+    corruption: CompoundCorruption,
+) -> Vec<CompoundEntry> {
+    let mut rng = rand::thread_rng();
+    if entries.len() < 2 {
+        return entries;
+    }
+    let index = rng.gen_range(0..entries.len() - 1);
+    let frame_length = serialize_compound_entries(&entries).len() as u32;
+
+    match corruption {
+        CompoundCorruption::NextCommandBeyondFrame => {
+            entries[index].header.generic.next_command = match rng.gen_range(0..=1) {
+                0 => frame_length + 8 * rng.gen_range(1..=0x20),
+                _ => 0xffff_fff8,
+            }
+            .to_le_bytes()
+            .to_vec();
+        }
+        CompoundCorruption::UnalignedNextCommand => {
+            let next_command = convert_byte_array_to_int(
+                entries[index].header.generic.next_command.clone(),
+                false,
+            );
+            entries[index].header.generic.next_command =
+                (next_command - rng.gen_range(1..8)).to_le_bytes().to_vec();
+        }
+        CompoundCorruption::NextCommandIntoHeader => {
+            entries[index].header.generic.next_command = (8 * rng.gen_range(1..HEADER_LENGTH / 8)
+                as u32)
+                .to_le_bytes()
+                .to_vec();
+        }
+        CompoundCorruption::PrematureEnd => {
+            entries[index].header.generic.next_command = vec![0; 4];
+        }
+        CompoundCorruption::RelatedFirstEntry => {
+            set_related_operations_flag(&mut entries[0].header);
+        }
+        CompoundCorruption::InconsistentRelatedIds => {
+            let related_index = index + 1;
+            set_related_operations_flag(&mut entries[related_index].header);
+            entries[related_index].header.session_id =
+                create_random_byte_array_of_predefined_length(8);
+            entries[related_index].header.tree_id =
+                create_random_byte_array_of_predefined_length(4);
+        }
+    }
+
+    entries
+}
+
+/// Fuzzes a compound request whose bodies are fuzzed with random values that comply to the
+/// size restrictions of certain fields. The NextCommand fields are random 4 byte values.
+pub fn fuzz_compound_with_random_fields(
+    tree_id: Vec<u8>,
+    session_id: Vec<u8>,
+) -> Vec<CompoundEntry> {
+    let mut rng = rand::thread_rng();
+
+    let bodies: Vec<RequestType> = (0..=rng.gen_range(1..=MAX_COMPOUND_OPERATION_COUNT))
+        .map(|_| match rand::random::<CompoundOperation>() {
+            CompoundOperation::Create => {
+                RequestType::Create(create_fuzzer::fuzz_create_with_random_fields())
+            }
+            CompoundOperation::Read => {
+                RequestType::Read(read_fuzzer::fuzz_read_with_random_fields())
+            }
+            CompoundOperation::QueryInfo => {
+                RequestType::QueryInfo(query_info_fuzzer::fuzz_query_info_with_random_fields())
+            }
+            CompoundOperation::Flush => {
+                RequestType::Flush(flush_fuzzer::fuzz_flush_with_random_fields())
+            }
+            CompoundOperation::Close => {
+                RequestType::Close(close_fuzzer::fuzz_close_with_random_fields())
+            }
+        })
+        .collect();
+    let related = (0..bodies.len()).map(|_| rng.gen()).collect();
+
+    build_compound_request(bodies, related, tree_id, session_id)
+        .into_iter()
+        .map(|mut entry| {
+            entry.header.generic.next_command = create_random_byte_array_of_predefined_length(4);
+            entry
+        })
+        .collect()
+}
+
+/// Fuzzes a compound request whose bodies and NextCommand fields are random values of random length.
+pub fn fuzz_compound_completely_random(
+    tree_id: Vec<u8>,
+    session_id: Vec<u8>,
+) -> Vec<CompoundEntry> {
+    let mut rng = rand::thread_rng();
+
+    let bodies: Vec<RequestType> = (0..=rng.gen_range(1..=MAX_COMPOUND_OPERATION_COUNT))
+        .map(|_| match rand::random::<CompoundOperation>() {
+            CompoundOperation::Create => {
+                RequestType::Create(create_fuzzer::fuzz_create_completely_random())
+            }
+            CompoundOperation::Read => {
+                RequestType::Read(read_fuzzer::fuzz_read_completely_random())
+            }
+            CompoundOperation::QueryInfo => {
+                RequestType::QueryInfo(query_info_fuzzer::fuzz_query_info_completely_random())
+            }
+            CompoundOperation::Flush => {
+                RequestType::Flush(flush_fuzzer::fuzz_flush_completely_random())
+            }
+            CompoundOperation::Close => {
+                RequestType::Close(close_fuzzer::fuzz_close_completely_random())
+            }
+        })
+        .collect();
+    let related = (0..bodies.len()).map(|_| rng.gen()).collect();
+
+    build_compound_request(bodies, related, tree_id, session_id)
+        .into_iter()
+        .map(|mut entry| {
+            entry.header.generic.next_command = create_random_byte_array_with_random_length();
+            entry
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::compound_request::build_default_compound_request;
+
+    #[test]
+    fn test_corrupt_compound_chain_with_premature_end() {
+        let entries = corrupt_compound_chain(
+            build_default_compound_request(vec![0; 4], vec![0; 8]),
+            CompoundCorruption::PrematureEnd,
+        );
+
+        assert_eq!(
+            2,
+            entries
+                .iter()
+                .filter(|entry| entry.header.generic.next_command == vec![0; 4])
+                .count()
+        );
+    }
+
+    #[test]
+    fn test_corrupt_compound_chain_with_unaligned_next_command() {
+        let entries = corrupt_compound_chain(
+            build_default_compound_request(vec![0; 4], vec![0; 8]),
+            CompoundCorruption::UnalignedNextCommand,
+        );
+
+        assert!(entries.iter().any(|entry| convert_byte_array_to_int(
+            entry.header.generic.next_command.clone(),
+            false
+        ) % 8
+            != 0));
+    }
+}
@@ -0,0 +1,328 @@
+use rand::Rng;
+
+use crate::smb2::compression_transform::{
+    ChainedPayload, CompressionTransform, COMPRESSION_FLAG_CHAINED, COMPRESSION_FLAG_NONE,
+};
+
+use super::create_random_byte_array_of_predefined_length;
+use super::create_random_byte_array_with_random_length;
+use super::transform_header_fuzzer::sample_original_message_size;
+use super::FuzzingStrategy;
+
+/// Fuzzes the compression transform of a compressed request according to the fuzzing strategy.
+pub fn fuzz_compression_transform(
+    transform: CompressionTransform,
+    fuzzing_strategy: &FuzzingStrategy,
+) -> CompressionTransform {
+    match fuzzing_strategy {
+        FuzzingStrategy::Predefined => fuzz_compression_transform_with_predefined_values(transform),
+        FuzzingStrategy::RandomFields => fuzz_compression_transform_with_random_fields(transform),
+        FuzzingStrategy::CompletelyRandom => {
+            fuzz_compression_transform_completely_random(transform)
+        }
+    }
+}
+
+/// Fuzzes the compression transform with predefined values.
+/// The sizes, offsets and algorithms of the headers are mutated, chained payloads are
+/// duplicated or dropped, and the compressed data is malformed specifically for its algorithm.
+/// The protocol id is kept so that the request is still interpreted as a compressed message.
+pub fn fuzz_compression_transform_with_predefined_values(
+    transform: CompressionTransform,
+) -> CompressionTransform {
+    let mut rng = rand::thread_rng();
+
+    match transform {
+        CompressionTransform::Unchained(mut unchained) => {
+            if rng.gen_bool(0.5) {
+                unchained.original_compressed_segment_size =
+                    sample_original_message_size(&unchained.original_compressed_segment_size);
+            }
+            if rng.gen_bool(0.5) {
+                unchained.compression_algorithm = sample_compression_algorithm();
+            }
+            if rng.gen_bool(0.25) {
+                unchained.flags = sample_compression_flags();
+            }
+            if rng.gen_bool(0.5) {
+                unchained.offset = sample_offset(unchained.uncompressed_data.len());
+            }
+            if rng.gen_bool(0.5) {
+                unchained.compressed_data = malform_compressed_stream(
+                    unchained.compressed_data,
+                    &unchained.compression_algorithm,
+                );
+            }
+
+            CompressionTransform::Unchained(unchained)
+        }
+        CompressionTransform::Chained(mut chained) => {
+            if rng.gen_bool(0.5) {
+                chained.original_compressed_segment_size =
+                    sample_original_message_size(&chained.original_compressed_segment_size);
+            }
+            for payload in chained.payloads.iter_mut() {
+                if rng.gen_bool(0.5) {
+                    *payload = fuzz_chained_payload_with_predefined_values(payload.clone());
+                }
+            }
+            if !chained.payloads.is_empty() && rng.gen_bool(0.25) {
+                let index = rng.gen_range(0..chained.payloads.len());
+                let duplicate = chained.payloads[index].clone();
+                chained.payloads.insert(index, duplicate);
+            }
+            if !chained.payloads.is_empty() && rng.gen_bool(0.25) {
+                let index = rng.gen_range(0..chained.payloads.len());
+                chained.payloads.remove(index);
+            }
+
+            CompressionTransform::Chained(chained)
+        }
+    }
+}
+
+/// Fuzzes a single payload of a chained compression transform with predefined values.
+pub fn fuzz_chained_payload_with_predefined_values(mut payload: ChainedPayload) -> ChainedPayload {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=4) {
+        0 => payload.length = sample_original_message_size(&payload.length),
+        1 => {
+            payload.original_payload_size = if payload.original_payload_size.is_empty() {
+                create_random_byte_array_of_predefined_length(4)
+            } else {
+                sample_original_message_size(&payload.original_payload_size)
+            }
+        }
+        2 => payload.compression_algorithm = sample_compression_algorithm(),
+        3 => payload.flags = sample_compression_flags(),
+        _ => {
+            payload.payload_data =
+                malform_compressed_stream(payload.payload_data, &payload.compression_algorithm)
+        }
+    }
+
+    payload
+}
+
+/// Samples the compression algorithm id. Besides the defined ids, the first undefined id
+/// and the maximum are chosen.
+pub fn sample_compression_algorithm() -> Vec<u8> {
+    let algorithm: u16 = match rand::thread_rng().gen_range(0..=2) {
+        0 => rand::thread_rng().gen_range(0..=4),
+        1 => 5,
+        _ => u16::MAX,
+    };
+
+    algorithm.to_le_bytes().to_vec()
+}
+
+/// Samples the flags. The chained and the unchained flag are swapped in for each other,
+/// besides an undefined flag and the maximum.
+pub fn sample_compression_flags() -> Vec<u8> {
+    match rand::thread_rng().gen_range(0..=3) {
+        0 => COMPRESSION_FLAG_CHAINED.to_vec(),
+        1 => COMPRESSION_FLAG_NONE.to_vec(),
+        2 => b"\x02\x00".to_vec(),
+        _ => b"\xff\xff".to_vec(),
+    }
+}
+
+/// Samples the offset of the unchained compression transform. Besides offsets just below
+/// and above the actual offset, zero and the maximum are chosen.
+pub fn sample_offset(uncompressed_length: usize) -> Vec<u8> {
+    let offset = uncompressed_length as u32;
+
+    let sampled_offset: u32 = match rand::thread_rng().gen_range(0..=3) {
+        0 => offset.saturating_sub(1),
+        1 => offset.saturating_add(1),
+        2 => 0,
+        _ => u32::MAX,
+    };
+
+    sampled_offset.to_le_bytes().to_vec()
+}
+
+/// Malforms the compressed data. Besides flipped bits, truncation and trailing garbage,
+/// structures specific to the compression algorithm are corrupted: LZNT1 chunk headers,
+/// LZ77 match offsets and length escapes, LZ77+Huffman code length tables and
+/// Pattern_V1 repetitions.
+pub fn malform_compressed_stream(mut data: Vec<u8>, algorithm: &[u8]) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    if rng.gen_bool(0.5) {
+        match rng.gen_range(0..=2) {
+            0 if !data.is_empty() => {
+                let index = rng.gen_range(0..data.len());
+                data[index] ^= 1 << rng.gen_range(0..8);
+            }
+            1 => data.truncate(rng.gen_range(0..data.len().max(1))),
+            _ => data.append(&mut create_random_byte_array_of_predefined_length(
+                rng.gen_range(1..64),
+            )),
+        }
+
+        return data;
+    }
+
+    match algorithm {
+        [1, 0] => match rng.gen_range(0..=2) {
+            // A chunk header that claims more data than present.
+            0 => {
+                data.splice(..data.len().min(2), b"\xff\xbf".iter().cloned());
+            }
+            // A chunk header without the signature.
+            1 => {
+                data.splice(..data.len().min(2), b"\x10\x80".iter().cloned());
+            }
+            // A compressed chunk whose first token is a copy token.
+            _ => data.extend_from_slice(b"\x02\xb0\x01\xff\xff"),
+        },
+        [2, 0] => match rng.gen_range(0..=1) {
+            // A match whose offset points before the start of the output.
+            0 => data = b"\x00\x00\x00\x80\xf8\xff".to_vec(),
+            // A match with the maximum length escape.
+            _ => data = b"\x00\x00\x00\x40\x41\x07\x00\x0f\xff\x00\x00\xff\xff\xff\xff".to_vec(),
+        },
+        [3, 0] => {
+            data.resize(data.len().max(256), 0);
+            match rng.gen_range(0..=2) {
+                // A table without any code.
+                0 => data[..256].fill(0),
+                // An oversubscribed table.
+                1 => data[..256].fill(0x11),
+                // A table with a single code of the maximum length.
+                _ => {
+                    data[..256].fill(0);
+                    data[rng.gen_range(0..256)] = 0x0f;
+                }
+            }
+        }
+        [4, 0] => {
+            data.resize(data.len().max(8), 0);
+            let repetitions: u32 = match rng.gen_range(0..=1) {
+                0 => 0,
+                _ => u32::MAX,
+            };
+            data[4..8].copy_from_slice(&repetitions.to_le_bytes());
+        }
+        _ => data = create_random_byte_array_of_predefined_length(data.len() as u32),
+    }
+
+    data
+}
+
+/// Fuzzes the compression transform with random values that comply to the size restrictions
+/// of the fields. The compressed data is replaced with random data of the same length.
+/// The protocol id is kept.
+pub fn fuzz_compression_transform_with_random_fields(
+    transform: CompressionTransform,
+) -> CompressionTransform {
+    match transform {
+        CompressionTransform::Unchained(mut unchained) => {
+            unchained.original_compressed_segment_size =
+                create_random_byte_array_of_predefined_length(4);
+            unchained.compression_algorithm = create_random_byte_array_of_predefined_length(2);
+            unchained.flags = create_random_byte_array_of_predefined_length(2);
+            unchained.offset = create_random_byte_array_of_predefined_length(4);
+            unchained.compressed_data = create_random_byte_array_of_predefined_length(
+                unchained.compressed_data.len() as u32,
+            );
+
+            CompressionTransform::Unchained(unchained)
+        }
+        CompressionTransform::Chained(mut chained) => {
+            chained.original_compressed_segment_size =
+                create_random_byte_array_of_predefined_length(4);
+            for payload in chained.payloads.iter_mut() {
+                payload.compression_algorithm = create_random_byte_array_of_predefined_length(2);
+                payload.flags = create_random_byte_array_of_predefined_length(2);
+                payload.length = create_random_byte_array_of_predefined_length(4);
+                payload.original_payload_size = create_random_byte_array_of_predefined_length(
+                    payload.original_payload_size.len() as u32,
+                );
+                payload.payload_data = create_random_byte_array_of_predefined_length(
+                    payload.payload_data.len() as u32,
+                );
+            }
+
+            CompressionTransform::Chained(chained)
+        }
+    }
+}
+
+/// Fuzzes the compression transform with random values of random length. The protocol id is kept.
+pub fn fuzz_compression_transform_completely_random(
+    transform: CompressionTransform,
+) -> CompressionTransform {
+    match transform {
+        CompressionTransform::Unchained(mut unchained) => {
+            unchained.original_compressed_segment_size =
+                create_random_byte_array_with_random_length();
+            unchained.compression_algorithm = create_random_byte_array_with_random_length();
+            unchained.flags = create_random_byte_array_with_random_length();
+            unchained.offset = create_random_byte_array_with_random_length();
+            unchained.compressed_data = create_random_byte_array_with_random_length();
+
+            CompressionTransform::Unchained(unchained)
+        }
+        CompressionTransform::Chained(mut chained) => {
+            chained.original_compressed_segment_size =
+                create_random_byte_array_with_random_length();
+            for payload in chained.payloads.iter_mut() {
+                payload.compression_algorithm = create_random_byte_array_with_random_length();
+                payload.flags = create_random_byte_array_with_random_length();
+                payload.length = create_random_byte_array_with_random_length();
+                payload.original_payload_size = create_random_byte_array_with_random_length();
+                payload.payload_data = create_random_byte_array_with_random_length();
+            }
+
+            CompressionTransform::Chained(chained)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::build_unchained_compression_transform;
+    use crate::smb2::{
+        compression_transform::COMPRESSION_PROTOCOL_ID,
+        helper_functions::negotiate_context::CompressionAlgorithms,
+    };
+
+    #[test]
+    fn test_fuzz_compression_transform_with_predefined_values() {
+        let message = b"a message with a payload payload payload payload".repeat(4);
+        let transform = CompressionTransform::Unchained(build_unchained_compression_transform(
+            &message,
+            &CompressionAlgorithms::Lz77Huffman,
+            16,
+        ));
+
+        for _ in 0..32 {
+            match fuzz_compression_transform_with_predefined_values(transform.clone()) {
+                CompressionTransform::Unchained(fuzzed_transform) => {
+                    assert_eq!(
+                        COMPRESSION_PROTOCOL_ID.to_vec(),
+                        fuzzed_transform.protocol_id
+                    );
+                    assert_eq!(4, fuzzed_transform.original_compressed_segment_size.len());
+                    assert_eq!(2, fuzzed_transform.compression_algorithm.len());
+                    assert_eq!(4, fuzzed_transform.offset.len());
+                }
+                CompressionTransform::Chained(_) => panic!("The transform must stay unchained."),
+            }
+        }
+    }
+
+    #[test]
+    fn test_malform_compressed_stream() {
+        let data = crate::compression::pattern_v1::compress(&[0x42; 100]);
+
+        for _ in 0..32 {
+            let malformed = malform_compressed_stream(data.clone(), &[4, 0]);
+            assert!(malformed != data || malformed.is_empty());
+        }
+    }
+}
@@ -0,0 +1,393 @@
+use rand::{
+    distributions::{Distribution, Standard},
+    Rng,
+};
+
+use crate::{
+    builder::create_request::{build_create_context, build_default_create_context_data},
+    format::{
+        convert_byte_array_to_int, encoder::create_context_encoder::chain_create_contexts,
+        encoder::create_context_encoder::serialize_create_contexts,
+    },
+    smb2::{
+        helper_functions::fields::LeaseState,
+        requests::create::create_context::{
+            align_to_eight_bytes, CreateContext, CreateContextData, CreateContextName,
+            FileFullEaInformation, CREATE_CONTEXT_HEADER_LENGTH,
+        },
+    },
+};
+
+use super::create_random_byte_array_of_predefined_length;
+use super::create_random_byte_array_with_random_length;
+
+/// The maximum number of create contexts in a fuzzed chain.
+pub const MAX_CREATE_CONTEXT_COUNT: u32 = 6;
+
+/// Fuzzes a chain of create contexts with predefined values.
+/// The data of each context is sampled from valid, boundary and invalid values.
+/// In half of the cases, the structure of the chain is corrupted afterwards.
+pub fn fuzz_create_contexts_with_predefined_values() -> Vec<CreateContext> {
+    let mut rng = rand::thread_rng();
+
+    let create_contexts = chain_create_contexts(
+        (0..rng.gen_range(1..=MAX_CREATE_CONTEXT_COUNT))
+            .map(|_| build_create_context(&sample_create_context_data(rand::random())))
+            .collect(),
+    );
+
+    if rng.gen_bool(0.5) {
+        corrupt_create_context_chain(create_contexts, rand::random())
+    } else {
+        create_contexts
+    }
+}
+
+/// Samples the data of the create context with the given name.
+/// Starting from the valid default data, the fields that the server
+/// validates are replaced by boundary and undefined values.
+pub fn sample_create_context_data(name: CreateContextName) -> CreateContextData {
+    let mut rng = rand::thread_rng();
+
+    match build_default_create_context_data(name) {
+        CreateContextData::EaBuffer(_) => CreateContextData::EaBuffer(sample_ea_entries()),
+        CreateContextData::SdBuffer(security_descriptor) => {
+            CreateContextData::SdBuffer(match rng.gen_range(0..=2) {
+                0 => security_descriptor,
+                1 => security_descriptor[..rng.gen_range(0..20)].to_vec(),
+                _ => create_random_byte_array_with_random_length(),
+            })
+        }
+        CreateContextData::DurableHandleRequest(reserved) => {
+            CreateContextData::DurableHandleRequest(sample_fixed_length_data(reserved))
+        }
+        CreateContextData::DurableHandleReconnect(file_id) => {
+            CreateContextData::DurableHandleReconnect(sample_fixed_length_data(file_id))
+        }
+        CreateContextData::AllocationSize(allocation_size) => {
+            CreateContextData::AllocationSize(sample_fixed_length_data(allocation_size))
+        }
+        CreateContextData::QueryMaximalAccessRequest(_) => {
+            CreateContextData::QueryMaximalAccessRequest(match rng.gen_range(0..=2) {
+                0 => Vec::new(),
+                1 => create_random_byte_array_of_predefined_length(8),
+                _ => create_random_byte_array_of_predefined_length(rng.gen_range(1..8)),
+            })
+        }
+        CreateContextData::TimewarpToken(timestamp) => {
+            CreateContextData::TimewarpToken(sample_fixed_length_data(timestamp))
+        }
+        CreateContextData::QueryOnDiskId => CreateContextData::QueryOnDiskId,
+        CreateContextData::RequestLease(mut request_lease) => {
+            if rng.gen_bool(0.5) {
+                request_lease.parent_lease_key = create_random_byte_array_of_predefined_length(16);
+                request_lease.epoch = create_random_byte_array_of_predefined_length(2);
+                request_lease.reserved = vec![0; 2];
+            }
+            request_lease.lease_key = sample_fixed_length_data(request_lease.lease_key);
+            request_lease.lease_state = sample_requested_lease_state();
+            request_lease.lease_flags = sample_flags(0x00000004);
+
+            CreateContextData::RequestLease(request_lease)
+        }
+        CreateContextData::DurableHandleRequestV2(mut durable_request) => {
+            durable_request.timeout = sample_fixed_length_data(durable_request.timeout);
+            durable_request.flags = sample_flags(0x00000002);
+            durable_request.create_guid = sample_fixed_length_data(durable_request.create_guid);
+
+            CreateContextData::DurableHandleRequestV2(durable_request)
+        }
+        CreateContextData::DurableHandleReconnectV2(mut durable_reconnect) => {
+            durable_reconnect.file_id = sample_fixed_length_data(durable_reconnect.file_id);
+            durable_reconnect.create_guid = sample_fixed_length_data(durable_reconnect.create_guid);
+            durable_reconnect.flags = sample_flags(0x00000002);
+
+            CreateContextData::DurableHandleReconnectV2(durable_reconnect)
+        }
+        CreateContextData::AppInstanceId(mut app_instance_id) => {
+            app_instance_id.structure_size =
+                sample_fixed_length_data(app_instance_id.structure_size);
+            app_instance_id.app_instance_id =
+                sample_fixed_length_data(app_instance_id.app_instance_id);
+
+            CreateContextData::AppInstanceId(app_instance_id)
+        }
+        CreateContextData::AppInstanceVersion(mut app_instance_version) => {
+            app_instance_version.structure_size =
+                sample_fixed_length_data(app_instance_version.structure_size);
+            app_instance_version.app_instance_version_high =
+                sample_fixed_length_data(app_instance_version.app_instance_version_high);
+            app_instance_version.app_instance_version_low =
+                sample_fixed_length_data(app_instance_version.app_instance_version_low);
+
+            CreateContextData::AppInstanceVersion(app_instance_version)
+        }
+        CreateContextData::Posix(mode) => CreateContextData::Posix(sample_fixed_length_data(mode)),
+    }
+}
+
+/// Samples a fixed length field. Besides the valid value, the field is zeroed,
+/// filled with 0xFF, randomized or truncated.
+pub fn sample_fixed_length_data(data: Vec<u8>) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+    let length = data.len() as u32;
+
+    match rng.gen_range(0..=4) {
+        0 => data,
+        1 => vec![0; data.len()],
+        2 => vec![0xff; data.len()],
+        3 => create_random_byte_array_of_predefined_length(length),
+        _ => create_random_byte_array_of_predefined_length(rng.gen_range(0..=length)),
+    }
+}
+
+/// Samples 4 byte flags. Besides no flags and the single defined flag,
+/// the flags contain undefined bits.
+pub fn sample_flags(defined_flag: u32) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=2) {
+        0 => vec![0; 4],
+        1 => defined_flag.to_le_bytes().to_vec(),
+        _ => (rng.gen::<u32>() | !defined_flag).to_le_bytes().to_vec(),
+    }
+}
+
+/// Samples the requested lease state. Besides combinations of the caching states,
+/// the state contains undefined bits.
+pub fn sample_requested_lease_state() -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=1) {
+        0 => LeaseState::return_sum_of_chosen_lease_states(
+            (0..rng.gen_range(0..=3))
+                .map(|_| rand::random::<LeaseState>())
+                .collect(),
+        ),
+        _ => (rng.gen::<u32>() | 0x00000008).to_le_bytes().to_vec(),
+    }
+}
+
+/// Samples a list of extended attribute entries. The names are empty, of maximum length
+/// or contain invalid characters, and the lengths do not necessarily match the name and value.
+pub fn sample_ea_entries() -> Vec<FileFullEaInformation> {
+    let mut rng = rand::thread_rng();
+
+    (0..rng.gen_range(1..=4))
+        .map(|_| {
+            let name = match rng.gen_range(0..=3) {
+                0 => String::from("FUZZ"),
+                1 => String::new(),
+                2 => "A".repeat(255),
+                _ => String::from("*?<>|\""),
+            };
+            let mut ea_entry = FileFullEaInformation::from_name_and_value(
+                &name,
+                create_random_byte_array_of_predefined_length(rng.gen_range(0..=64)),
+            );
+            if rng.gen_bool(0.2) {
+                ea_entry.flags = vec![rng.gen()];
+            }
+            if rng.gen_bool(0.2) {
+                ea_entry.ea_name_length = vec![rng.gen()];
+                ea_entry.ea_value_length = create_random_byte_array_of_predefined_length(2);
+            }
+            ea_entry
+        })
+        .collect()
+}
+
+/// The corruptions of the create context chain structure.
+///
+/// *Cyclic Next*:
+///     - The Next field of the last context wraps around to the first context.
+///
+/// *Out Of Bounds Next*:
+///     - A Next field points beyond the end of the create context list.
+///
+/// *Unaligned Next*:
+///     - A Next field points into the middle of a create context.
+///
+/// *Overlapping Name And Data*:
+///     - The data offset of a context points to its name or into its header.
+///
+/// *Out Of Bounds Data*:
+///     - The data length of a context exceeds the create context list.
+///
+/// *Duplicate Tag*:
+///     - A create context is sent twice in the chain.
+///
+/// *Truncated Name*:
+///     - The name length of a context is shorter or longer than the tag.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ChainCorruption {
+    CyclicNext,
+    OutOfBoundsNext,
+    UnalignedNext,
+    OverlappingNameAndData,
+    OutOfBoundsData,
+    DuplicateTag,
+    TruncatedName,
+}
+
+impl Distribution<ChainCorruption> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> ChainCorruption {
+        match rng.gen_range(0..=6) {
+            0 => ChainCorruption::CyclicNext,
+            1 => ChainCorruption::OutOfBoundsNext,
+            2 => ChainCorruption::UnalignedNext,
+            3 => ChainCorruption::OverlappingNameAndData,
+            4 => ChainCorruption::OutOfBoundsData,
+            5 => ChainCorruption::DuplicateTag,
+            _ => ChainCorruption::TruncatedName,
+        }
+    }
+}
+
+/// Applies the given corruption to a chained list of create contexts.
+pub fn corrupt_create_context_chain(
+    mut create_contexts: Vec<CreateContext>,
+    corruption: ChainCorruption,
+) -> Vec<CreateContext> {
+    let mut rng = rand::thread_rng();
+    if create_contexts.is_empty() {
+        return create_contexts;
+    }
+    let index = rng.gen_range(0..create_contexts.len());
+    let chain_length = serialize_create_contexts(&create_contexts).len() as u32;
+
+    match corruption {
+        ChainCorruption::CyclicNext => {
+            if create_contexts.len() == 1 {
+                create_contexts.push(create_contexts[0].clone());
+                create_contexts = chain_create_contexts(create_contexts);
+            }
+            let last_index = create_contexts.len() - 1;
+            let offset_of_last_context: u32 = create_contexts[..last_index]
+                .iter()
+                .map(|context| convert_byte_array_to_int(context.next.clone(), false))
+                .sum();
+            create_contexts[last_index].next =
+                offset_of_last_context.wrapping_neg().to_le_bytes().to_vec();
+        }
+        ChainCorruption::OutOfBoundsNext => {
+            create_contexts[index].next = match rng.gen_range(0..=1) {
+                0 => align_to_eight_bytes(chain_length as usize + rng.gen_range(1..=0x100)) as u32,
+                _ => 0xffff_fff8,
+            }
+            .to_le_bytes()
+            .to_vec();
+        }
+        ChainCorruption::UnalignedNext => {
+            create_contexts[index].next = (rng.gen_range(1..chain_length.max(2)) | 1)
+                .to_le_bytes()
+                .to_vec();
+        }
+        ChainCorruption::OverlappingNameAndData => {
+            let data_offset = match rng.gen_range(0..=1) {
+                0 => CREATE_CONTEXT_HEADER_LENGTH,
+                _ => rng.gen_range(1..CREATE_CONTEXT_HEADER_LENGTH),
+            };
+            create_contexts[index].data_offset = data_offset.to_le_bytes().to_vec();
+            if create_contexts[index].data.is_empty() {
+                create_contexts[index].data_length = 8u32.to_le_bytes().to_vec();
+            }
+        }
+        ChainCorruption::OutOfBoundsData => {
+            create_contexts[index].data_length = match rng.gen_range(0..=1) {
+                0 => chain_length + rng.gen_range(1..=0x100),
+                _ => u32::MAX,
+            }
+            .to_le_bytes()
+            .to_vec();
+        }
+        ChainCorruption::DuplicateTag => {
+            let duplicate = create_contexts[index].clone();
+            create_contexts.insert(rng.gen_range(0..=create_contexts.len()), duplicate);
+            create_contexts = chain_create_contexts(create_contexts);
+        }
+        ChainCorruption::TruncatedName => {
+            let name_length = create_contexts[index].name.len() as u16;
+            create_contexts[index].name_length = match rng.gen_range(0..=2) {
+                0 => 0,
+                1 => rng.gen_range(1..name_length),
+                _ => name_length + rng.gen_range(1..=0x100),
+            }
+            .to_le_bytes()
+            .to_vec();
+        }
+    }
+
+    create_contexts
+}
+
+/// Fuzzes a chain of create contexts with random values that comply to the size restrictions of certain fields.
+pub fn fuzz_create_contexts_with_random_fields() -> Vec<CreateContext> {
+    (0..rand::thread_rng().gen_range(0..=MAX_CREATE_CONTEXT_COUNT))
+        .map(|_| {
+            let mut create_context = CreateContext::default();
+            create_context.next = create_random_byte_array_of_predefined_length(4);
+            create_context.name_offset = create_random_byte_array_of_predefined_length(2);
+            create_context.name_length = create_random_byte_array_of_predefined_length(2);
+            create_context.reserved = create_random_byte_array_of_predefined_length(2);
+            create_context.data_offset = create_random_byte_array_of_predefined_length(2);
+            create_context.data_length = create_random_byte_array_of_predefined_length(4);
+            create_context.name = create_random_byte_array_of_predefined_length(4);
+            create_context.data = create_random_byte_array_with_random_length();
+            create_context
+        })
+        .collect()
+}
+
+/// Fuzzes a chain of create contexts with random values of random length.
+pub fn fuzz_create_contexts_completely_random() -> Vec<CreateContext> {
+    (0..rand::thread_rng().gen_range(0..=MAX_CREATE_CONTEXT_COUNT))
+        .map(|_| {
+            let mut create_context = CreateContext::default();
+            create_context.next = create_random_byte_array_with_random_length();
+            create_context.name_offset = create_random_byte_array_with_random_length();
+            create_context.name_length = create_random_byte_array_with_random_length();
+            create_context.reserved = create_random_byte_array_with_random_length();
+            create_context.data_offset = create_random_byte_array_with_random_length();
+            create_context.data_length = create_random_byte_array_with_random_length();
+            create_context.name = create_random_byte_array_with_random_length();
+            create_context.data = create_random_byte_array_with_random_length();
+            create_context
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_default_chain() -> Vec<CreateContext> {
+        chain_create_contexts(vec![
+            build_create_context(&build_default_create_context_data(
+                CreateContextName::RequestLease,
+            )),
+            build_create_context(&build_default_create_context_data(
+                CreateContextName::QueryMaximalAccessRequest,
+            )),
+        ])
+    }
+
+    #[test]
+    fn test_corrupt_create_context_chain_with_cyclic_next() {
+        let create_contexts =
+            corrupt_create_context_chain(build_default_chain(), ChainCorruption::CyclicNext);
+
+        let first_next = convert_byte_array_to_int(create_contexts[0].next.clone(), false);
+        let last_next = convert_byte_array_to_int(create_contexts[1].next.clone(), false);
+        assert_eq!(0, first_next.wrapping_add(last_next));
+    }
+
+    #[test]
+    fn test_corrupt_create_context_chain_with_duplicate_tag() {
+        let create_contexts =
+            corrupt_create_context_chain(build_default_chain(), ChainCorruption::DuplicateTag);
+
+        assert_eq!(3, create_contexts.len());
+        assert_eq!(vec![0; 4], create_contexts[2].next);
+    }
+}
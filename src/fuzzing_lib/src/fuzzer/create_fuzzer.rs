@@ -1,13 +1,21 @@
 use rand::Rng;
 
-use crate::smb2::{
-    helper_functions::{fields::OplockLevel, file_attributes::FileAttributes},
-    requests::create::{
-        create_options::CreateOptions, file_access_mask::FileAccessMask, Create, CreateDisposition,
-        ImpersonationLevel, ShareAccess,
+use crate::{
+    builder::create_request::attach_create_contexts,
+    smb2::{
+        helper_functions::{fields::OplockLevel, file_attributes::FileAttributes},
+        requests::create::{
+            create_options::CreateOptions, file_access_mask::FileAccessMask, Create,
+            CreateDisposition, ImpersonationLevel, ShareAccess,
+        },
     },
 };
 
+use super::create_context_fuzzer::{
+    fuzz_create_contexts_completely_random, fuzz_create_contexts_with_predefined_values,
+    fuzz_create_contexts_with_random_fields,
+};
+
 use super::create_random_byte_array_of_predefined_length;
 use super::create_random_byte_array_with_random_length;
 
@@ -19,6 +27,7 @@ pub const DEFAULT_NAME_OFFSET: &[u8; 2] = b"\x78\x00";
 pub const DEFAULT_NAME_LENGTH: &[u8; 2] = b"\x1a\x00";
 
 /// Fuzzes the create request with predefined values.
+/// In most cases, a fuzzed chain of create contexts is appended to the file name.
 pub fn fuzz_create_with_predefined_values() -> Create {
     let mut create_request = Create::default();
 
@@ -31,9 +40,15 @@ pub fn fuzz_create_with_predefined_values() -> Create {
     create_request.create_options = sample_create_options();
     create_request.name_offset = DEFAULT_NAME_OFFSET.to_vec();
     create_request.name_length = DEFAULT_NAME_LENGTH.to_vec();
-    create_request.create_contexts_offset = vec![0; 4];
-    create_request.create_contexts_length = vec![0; 4];
     create_request.buffer = DEFAULT_BUFFER.to_vec();
+    if rand::thread_rng().gen_bool(0.8) {
+        attach_create_contexts(
+            &mut create_request,
+            fuzz_create_contexts_with_predefined_values(),
+        );
+    } else {
+        attach_create_contexts(&mut create_request, Vec::new());
+    }
 
     create_request
 }
@@ -102,6 +117,7 @@ pub fn fuzz_create_with_random_fields() -> Create {
     create_request.create_contexts_offset = create_random_byte_array_of_predefined_length(4);
     create_request.create_contexts_length = create_random_byte_array_of_predefined_length(4);
     create_request.buffer = create_random_byte_array_with_random_length();
+    create_request.create_contexts = fuzz_create_contexts_with_random_fields();
 
     create_request
 }
@@ -126,6 +142,7 @@ pub fn fuzz_create_completely_random() -> Create {
     create_request.create_contexts_offset = create_random_byte_array_with_random_length();
     create_request.create_contexts_length = create_random_byte_array_with_random_length();
     create_request.buffer = create_random_byte_array_with_random_length();
+    create_request.create_contexts = fuzz_create_contexts_completely_random();
 
     create_request
 }
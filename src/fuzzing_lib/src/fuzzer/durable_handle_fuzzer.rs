@@ -0,0 +1,290 @@
+use rand::{
+    distributions::{Distribution, Standard},
+    Rng,
+};
+
+use super::{
+    create_context_fuzzer::{sample_fixed_length_data, sample_flags},
+    create_random_byte_array_of_predefined_length, create_random_byte_array_with_random_length,
+};
+use crate::{
+    builder::{
+        create_request::build_create_context,
+        durable_handle_request::{
+            build_durable_reconnect_context_data, build_lease_context, DurableOpen,
+            DHANDLE_FLAG_PERSISTENT,
+        },
+    },
+    ntlmssp::credentials::Credentials,
+    smb2::requests::create::create_context::{
+        CreateContext, CreateContextData, DurableHandleReconnectV2, RequestLease,
+    },
+};
+
+/// The versions of the durable handle with which the file is opened.
+///
+/// *V1*:
+///     - The open is requested with SMB2_CREATE_DURABLE_HANDLE_REQUEST (DHnQ)
+///       and reclaimed with SMB2_CREATE_DURABLE_HANDLE_RECONNECT (DHnC).
+///
+/// *V2*:
+///     - The open is requested with SMB2_CREATE_DURABLE_HANDLE_REQUEST_V2 (DH2Q)
+///       and reclaimed with SMB2_CREATE_DURABLE_HANDLE_RECONNECT_V2 (DH2C).
+///
+/// *Persistent*:
+///     - The open is requested and reclaimed with the version 2 contexts
+///       and SMB2_DHANDLE_FLAG_PERSISTENT.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DurableHandleVersion {
+    V1,
+    V2,
+    Persistent,
+}
+
+impl DurableHandleVersion {
+    /// Maps a user input string to a durable handle version.
+    pub fn map_string_to_durable_handle_version(version: &str) -> Self {
+        match version {
+            "v1" => DurableHandleVersion::V1,
+            "v2" => DurableHandleVersion::V2,
+            "persistent" => DurableHandleVersion::Persistent,
+            _ => panic!("Invalid durable handle version."),
+        }
+    }
+}
+
+/// The mutations of the reclaim of a durable open.
+///
+/// *Wrong Create Guid*:
+///     - The create guid of the reconnect context does not match the one of the durable open.
+///       A version 1 open is reclaimed with a version 2 reconnect context instead.
+///
+/// *Stale File Id*:
+///     - The file id of the reconnect context does not match the one of the durable open.
+///
+/// *Mismatched Lease Key*:
+///     - The lease key does not match the one of the durable open, or the lease is omitted.
+///
+/// *Different User*:
+///     - The reclaiming session is authenticated as another user than the one that opened the file.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DurableReconnectMutation {
+    WrongCreateGuid,
+    StaleFileId,
+    MismatchedLeaseKey,
+    DifferentUser,
+}
+
+impl Distribution<DurableReconnectMutation> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> DurableReconnectMutation {
+        match rng.gen_range(0..=3) {
+            0 => DurableReconnectMutation::WrongCreateGuid,
+            1 => DurableReconnectMutation::StaleFileId,
+            2 => DurableReconnectMutation::MismatchedLeaseKey,
+            _ => DurableReconnectMutation::DifferentUser,
+        }
+    }
+}
+
+/// Samples between one and two distinct mutations of the reclaim.
+pub fn sample_durable_reconnect_mutations() -> Vec<DurableReconnectMutation> {
+    let mut rng = rand::thread_rng();
+    let mut mutations: Vec<DurableReconnectMutation> = Vec::new();
+
+    for _ in 0..rng.gen_range(1..=2) {
+        let mutation = rand::random();
+        if !mutations.contains(&mutation) {
+            mutations.push(mutation);
+        }
+    }
+
+    mutations
+}
+
+/// Samples the credentials with which the reclaiming session is authenticated
+/// instead of the credentials of the durable open.
+pub fn sample_reconnect_credentials(credentials: &Credentials) -> Credentials {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=2) {
+        0 => Credentials::guest(),
+        1 => Credentials::anonymous(),
+        _ => {
+            let mut other_user = credentials.clone();
+            other_user.user_name.push_str("_other");
+            other_user
+        }
+    }
+}
+
+/// Fuzzes the create contexts that reclaim the durable open with predefined values.
+/// The lease and the reconnect context of the durable open are mutated as given by the mutations,
+/// and the flags of a version 2 reconnect context are sampled in addition.
+pub fn fuzz_durable_reconnect_with_predefined_values(
+    durable_open: &DurableOpen,
+    mutations: &[DurableReconnectMutation],
+) -> Vec<CreateContext> {
+    let mut rng = rand::thread_rng();
+    let mut reconnect_data = build_durable_reconnect_context_data(durable_open);
+    let mut lease_context = Some(build_lease_context(&durable_open.lease_key));
+
+    for mutation in mutations {
+        match mutation {
+            DurableReconnectMutation::WrongCreateGuid => {
+                reconnect_data = match reconnect_data {
+                    CreateContextData::DurableHandleReconnect(file_id) => {
+                        let mut durable_reconnect = DurableHandleReconnectV2::default();
+                        durable_reconnect.file_id = file_id;
+                        durable_reconnect.create_guid =
+                            create_random_byte_array_of_predefined_length(16);
+
+                        CreateContextData::DurableHandleReconnectV2(durable_reconnect)
+                    }
+                    CreateContextData::DurableHandleReconnectV2(mut durable_reconnect) => {
+                        durable_reconnect.create_guid =
+                            sample_mismatched_id(&durable_reconnect.create_guid);
+
+                        CreateContextData::DurableHandleReconnectV2(durable_reconnect)
+                    }
+                    data => data,
+                }
+            }
+            DurableReconnectMutation::StaleFileId => {
+                reconnect_data = match reconnect_data {
+                    CreateContextData::DurableHandleReconnect(file_id) => {
+                        CreateContextData::DurableHandleReconnect(sample_mismatched_id(&file_id))
+                    }
+                    CreateContextData::DurableHandleReconnectV2(mut durable_reconnect) => {
+                        durable_reconnect.file_id =
+                            sample_mismatched_id(&durable_reconnect.file_id);
+
+                        CreateContextData::DurableHandleReconnectV2(durable_reconnect)
+                    }
+                    data => data,
+                }
+            }
+            DurableReconnectMutation::MismatchedLeaseKey => {
+                lease_context = if rng.gen_bool(0.25) {
+                    None
+                } else {
+                    Some(build_lease_context(&sample_mismatched_id(
+                        &durable_open.lease_key,
+                    )))
+                };
+            }
+            DurableReconnectMutation::DifferentUser => {}
+        }
+    }
+
+    if let CreateContextData::DurableHandleReconnectV2(durable_reconnect) = &mut reconnect_data {
+        if rng.gen_bool(0.25) {
+            durable_reconnect.flags = sample_flags(DHANDLE_FLAG_PERSISTENT);
+        }
+    }
+
+    lease_context
+        .into_iter()
+        .chain(std::iter::once(build_create_context(&reconnect_data)))
+        .collect()
+}
+
+/// Samples an identifier that deviates from the given one. Besides a flipped bit, the persistent
+/// or the volatile half is replaced, or the identifier is sampled like a fixed length field.
+pub fn sample_mismatched_id(id: &[u8]) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+    let mut mismatched_id = id.to_vec();
+    let half = id.len() / 2;
+
+    match rng.gen_range(0..=3) {
+        0 if !id.is_empty() => {
+            let index = rng.gen_range(0..id.len());
+            mismatched_id[index] ^= 1 << rng.gen_range(0..8);
+        }
+        1 => mismatched_id[..half]
+            .copy_from_slice(&create_random_byte_array_of_predefined_length(half as u32)),
+        2 => mismatched_id[half..].copy_from_slice(&create_random_byte_array_of_predefined_length(
+            (id.len() - half) as u32,
+        )),
+        _ => mismatched_id = sample_fixed_length_data(mismatched_id),
+    }
+
+    mismatched_id
+}
+
+/// Fuzzes the create contexts that reclaim the durable open with random values that
+/// comply to the size restrictions of the fields.
+pub fn fuzz_durable_reconnect_with_random_fields(durable_open: &DurableOpen) -> Vec<CreateContext> {
+    let mut request_lease = RequestLease::default();
+    request_lease.lease_key = create_random_byte_array_of_predefined_length(16);
+    request_lease.lease_state = create_random_byte_array_of_predefined_length(4);
+    request_lease.lease_flags = create_random_byte_array_of_predefined_length(4);
+
+    let reconnect_data = match build_durable_reconnect_context_data(durable_open) {
+        CreateContextData::DurableHandleReconnect(_) => CreateContextData::DurableHandleReconnect(
+            create_random_byte_array_of_predefined_length(16),
+        ),
+        _ => {
+            let mut durable_reconnect = DurableHandleReconnectV2::default();
+            durable_reconnect.file_id = create_random_byte_array_of_predefined_length(16);
+            durable_reconnect.create_guid = create_random_byte_array_of_predefined_length(16);
+            durable_reconnect.flags = create_random_byte_array_of_predefined_length(4);
+
+            CreateContextData::DurableHandleReconnectV2(durable_reconnect)
+        }
+    };
+
+    vec![
+        build_create_context(&CreateContextData::RequestLease(request_lease)),
+        build_create_context(&reconnect_data),
+    ]
+}
+
+/// Fuzzes the create contexts that reclaim the durable open with random values of random length.
+pub fn fuzz_durable_reconnect_completely_random() -> Vec<CreateContext> {
+    let mut request_lease = RequestLease::default();
+    request_lease.lease_key = create_random_byte_array_with_random_length();
+    request_lease.lease_state = create_random_byte_array_with_random_length();
+    request_lease.lease_flags = create_random_byte_array_with_random_length();
+    request_lease.lease_duration = create_random_byte_array_with_random_length();
+
+    let mut durable_reconnect = DurableHandleReconnectV2::default();
+    durable_reconnect.file_id = create_random_byte_array_with_random_length();
+    durable_reconnect.create_guid = create_random_byte_array_with_random_length();
+    durable_reconnect.flags = create_random_byte_array_with_random_length();
+
+    vec![
+        build_create_context(&CreateContextData::RequestLease(request_lease)),
+        build_create_context(&CreateContextData::DurableHandleReconnectV2(
+            durable_reconnect,
+        )),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_mismatched_id() {
+        let id = vec![0x5a; 16];
+
+        for _ in 0..32 {
+            assert!(sample_mismatched_id(&id).len() <= 16);
+        }
+    }
+
+    #[test]
+    fn test_fuzz_durable_reconnect_with_predefined_values() {
+        let durable_open = DurableOpen::new(DurableHandleVersion::V1);
+
+        for _ in 0..16 {
+            let create_contexts = fuzz_durable_reconnect_with_predefined_values(
+                &durable_open,
+                &[DurableReconnectMutation::WrongCreateGuid],
+            );
+
+            assert_eq!(b"RqLs".to_vec(), create_contexts[0].name);
+            assert_eq!(b"DH2C".to_vec(), create_contexts[1].name);
+        }
+    }
+}
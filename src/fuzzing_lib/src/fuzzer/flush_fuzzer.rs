@@ -0,0 +1,45 @@
+use rand::Rng;
+
+use crate::smb2::requests::flush::Flush;
+
+use super::create_random_byte_array_of_predefined_length;
+use super::create_random_byte_array_with_random_length;
+
+/// Fuzzes the flush request with predefined values.
+/// The file id is either the id of the opened file, a random id
+/// or the id 0xFFFFFFFFFFFFFFFF that is reserved for related compound operations.
+pub fn fuzz_flush_with_predefined_values(file_id: Vec<u8>) -> Flush {
+    let mut flush_request = Flush::default();
+
+    flush_request.file_id = match rand::thread_rng().gen_range(0..=2) {
+        0 => file_id,
+        1 => create_random_byte_array_of_predefined_length(16),
+        _ => vec![0xff; 16],
+    };
+
+    flush_request
+}
+
+/// Fuzzes the flush request with random values that comply to the size restrictions of certain fields.
+pub fn fuzz_flush_with_random_fields() -> Flush {
+    let mut flush_request = Flush::default();
+
+    flush_request.structure_size = create_random_byte_array_of_predefined_length(2);
+    flush_request.reserved_1 = create_random_byte_array_of_predefined_length(2);
+    flush_request.reserved_2 = create_random_byte_array_of_predefined_length(4);
+    flush_request.file_id = create_random_byte_array_of_predefined_length(16);
+
+    flush_request
+}
+
+/// Fuzzes the flush request with random values of random length.
+pub fn fuzz_flush_completely_random() -> Flush {
+    let mut flush_request = Flush::default();
+
+    flush_request.structure_size = create_random_byte_array_with_random_length();
+    flush_request.reserved_1 = create_random_byte_array_with_random_length();
+    flush_request.reserved_2 = create_random_byte_array_with_random_length();
+    flush_request.file_id = create_random_byte_array_with_random_length();
+
+    flush_request
+}
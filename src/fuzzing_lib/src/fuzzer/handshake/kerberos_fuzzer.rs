@@ -0,0 +1,388 @@
+use rand::Rng;
+
+use crate::{
+    builder::session_setup_kerberos_request::{
+        build_ap_req, build_default_ap_req_authenticator, build_gss_checksum,
+    },
+    crypto::kerberos,
+    format::encoder::kerberos_encoder::{encode_bit_flags, encode_integer},
+    fuzzer::{
+        create_random_byte_array_of_predefined_length, create_random_byte_array_with_random_length,
+    },
+    kerberos::{
+        format_kerberos_time, ApReq, Authenticator, Checksum, EncryptedData, EncryptionKey,
+        EncryptionType, GssFlags, PrincipalName, ServiceTicket, Ticket, PVNO,
+    },
+};
+
+/// Fuzzes the AP-REQ with predefined values. The AP-REQ is built for the service ticket, whereby
+/// the fields of the authenticator are mutated before it is sealed with the session key, so that
+/// the server decrypts them. Afterwards the AP options, the ticket and the encrypted authenticator are mutated.
+pub fn fuzz_kerberos_ap_req_with_predefined_values(service_ticket: &ServiceTicket) -> ApReq {
+    let mut rng = rand::thread_rng();
+    let (mut authenticator, _) = build_default_ap_req_authenticator(service_ticket);
+
+    if rng.gen_bool(0.75) {
+        fuzz_authenticator(&mut authenticator);
+    }
+    let mut ap_req = build_ap_req(service_ticket, &authenticator);
+    if rng.gen_bool(0.25) {
+        ap_req.ap_options = fuzz_ap_options();
+    }
+    if rng.gen_bool(0.25) {
+        fuzz_ticket(&mut ap_req.ticket);
+    }
+    if rng.gen_bool(0.25) {
+        fuzz_encrypted_data(&mut ap_req.authenticator);
+    }
+
+    ap_req
+}
+
+/// Fuzzes one of the fields of the authenticator. Besides malformed client names and realms,
+/// skewed or malformed times, out of range microseconds, a broken GSS-API checksum, a subkey of a
+/// wrong type or length, odd sequence numbers and a wrong version number are chosen.
+pub fn fuzz_authenticator(authenticator: &mut Authenticator) {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=7) {
+        0 => fuzz_principal_name(&mut authenticator.cname),
+        1 => authenticator.crealm = fuzz_realm(),
+        2 => {
+            authenticator.ctime = match rng.gen_range(0..=3) {
+                0 => format_kerberos_time(0),
+                1 => format_kerberos_time(u32::MAX as u64),
+                2 => b"99991332256161Z".to_vec(),
+                _ => create_random_byte_array_of_predefined_length(rng.gen_range(0..=32)),
+            }
+        }
+        3 => {
+            authenticator.cusec = match rng.gen_range(0..=2) {
+                0 => encode_integer(1_000_000),
+                1 => encode_integer(-1),
+                _ => create_random_byte_array_of_predefined_length(rng.gen_range(0..=16)),
+            }
+        }
+        4 => authenticator.cksum = fuzz_gss_checksum(),
+        5 => authenticator.subkey = fuzz_subkey(authenticator.subkey.take()),
+        6 => {
+            authenticator.seq_number = match rng.gen_range(0..=2) {
+                0 => encode_integer(-1),
+                1 => encode_integer(u32::MAX as i64 + 1),
+                _ => create_random_byte_array_of_predefined_length(rng.gen_range(0..=16)),
+            }
+        }
+        _ => authenticator.authenticator_vno = vec![PVNO ^ rng.gen_range(1..=u8::MAX)],
+    }
+}
+
+/// Fuzzes the GSS-API checksum of the authenticator. Besides a missing or foreign checksum,
+/// a wrong channel bindings length, truncated or oversized checksums, random flags and the
+/// delegation flag without delegated credentials are chosen.
+pub fn fuzz_gss_checksum() -> Option<Checksum> {
+    let mut rng = rand::thread_rng();
+    let mut checksum = build_gss_checksum(GssFlags::MUTUAL | GssFlags::INTEG);
+
+    match rng.gen_range(0..=5) {
+        0 => return None,
+        1 => {
+            checksum.cksum_type = encode_integer(rng.gen_range(-1..=0x10));
+        }
+        2 => {
+            checksum.checksum[..4].copy_from_slice(&rng.gen::<u32>().to_le_bytes());
+        }
+        3 => {
+            let length = rng.gen_range(0..checksum.checksum.len());
+            checksum.checksum.truncate(length);
+        }
+        4 => {
+            checksum
+                .checksum
+                .append(&mut create_random_byte_array_of_predefined_length(
+                    rng.gen_range(1..=64),
+                ));
+        }
+        _ => {
+            let flags = if rng.gen_bool(0.5) {
+                GssFlags::DELEG.bits() | GssFlags::MUTUAL.bits()
+            } else {
+                rng.gen::<u32>()
+            };
+            checksum.checksum[20..].copy_from_slice(&flags.to_le_bytes());
+        }
+    }
+
+    Some(checksum)
+}
+
+/// Fuzzes the subkey of the authenticator. Besides a missing subkey, an unsupported key type,
+/// a key type that does not match the session key and a key of a wrong length are chosen.
+pub fn fuzz_subkey(subkey: Option<EncryptionKey>) -> Option<EncryptionKey> {
+    let mut rng = rand::thread_rng();
+    let mut subkey = subkey.unwrap_or_else(EncryptionKey::default);
+
+    match rng.gen_range(0..=3) {
+        0 => return None,
+        1 => subkey.key_type = encode_integer(rng.gen_range(-0x100..=0x10)),
+        2 => {
+            subkey.key_type = if subkey.key_type == EncryptionType::Rc4Hmac.unpack_byte_code() {
+                EncryptionType::Aes256CtsHmacSha196.unpack_byte_code()
+            } else {
+                EncryptionType::Rc4Hmac.unpack_byte_code()
+            }
+        }
+        _ => {
+            subkey.key_value = create_random_byte_array_of_predefined_length(rng.gen_range(0..=64));
+        }
+    }
+
+    Some(subkey)
+}
+
+/// Fuzzes the AP options. Besides random flags, a BIT STRING with an invalid number of
+/// unused bits and an empty BIT STRING are chosen.
+pub fn fuzz_ap_options() -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=2) {
+        0 => encode_bit_flags(rng.gen()),
+        1 => {
+            let mut ap_options = encode_bit_flags(rng.gen());
+            ap_options[0] = rng.gen_range(8..=u8::MAX);
+            ap_options
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Fuzzes one of the fields of the ticket. Besides a wrong version number, a foreign realm
+/// and a malformed service name, the encrypted part is mutated.
+pub fn fuzz_ticket(ticket: &mut Ticket) {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=3) {
+        0 => ticket.tkt_vno = vec![PVNO ^ rng.gen_range(1..=u8::MAX)],
+        1 => ticket.realm = fuzz_realm(),
+        2 => fuzz_principal_name(&mut ticket.sname),
+        _ => fuzz_encrypted_data(&mut ticket.enc_part),
+    }
+}
+
+/// Fuzzes the encrypted data. Besides an unsupported or mismatching encryption type and a
+/// random key version number, the cipher is flipped at a random bit, truncated below the length
+/// of the confounder and the HMAC, extended or emptied.
+pub fn fuzz_encrypted_data(data: &mut EncryptedData) {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=5) {
+        0 => data.etype = encode_integer(rng.gen_range(-0x100..=0x10)),
+        1 => {
+            data.etype = EncryptionType::supported()
+                .into_iter()
+                .map(|etype| etype.unpack_byte_code())
+                .find(|etype| *etype != data.etype)
+                .unwrap_or_default()
+        }
+        2 => data.kvno = encode_integer(rng.gen_range(0..=u32::MAX as i64)),
+        3 if !data.cipher.is_empty() => {
+            let index = rng.gen_range(0..data.cipher.len());
+            data.cipher[index] ^= 1 << rng.gen_range(0..8);
+        }
+        4 => {
+            let etype = EncryptionType::map_byte_code_to_encryption_type(&data.etype)
+                .unwrap_or(EncryptionType::Aes256CtsHmacSha196);
+            let minimum_length = kerberos::confounder_length(&etype) + 12;
+            data.cipher
+                .truncate(rng.gen_range(0..minimum_length.min(data.cipher.len()).max(1)));
+        }
+        5 => data
+            .cipher
+            .append(&mut create_random_byte_array_of_predefined_length(
+                rng.gen_range(1..=64),
+            )),
+        _ => data.cipher = Vec::new(),
+    }
+}
+
+/// Fuzzes the principal name. Besides an undefined name type, an empty name,
+/// a huge name component and many name components are chosen.
+pub fn fuzz_principal_name(name: &mut PrincipalName) {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=3) {
+        0 => name.name_type = encode_integer(rng.gen_range(11..=0x100)),
+        1 => name.name_string = Vec::new(),
+        2 => name.name_string = vec![vec![b'A'; rng.gen_range(0x100..0x2000)]],
+        _ => {
+            name.name_string = (0..rng.gen_range(16..256))
+                .map(|_| create_random_byte_array_of_predefined_length(rng.gen_range(0..16)))
+                .collect()
+        }
+    }
+}
+
+/// Fuzzes a realm. Besides an empty realm, a lower case realm with
+/// unusual characters and a huge realm are chosen.
+pub fn fuzz_realm() -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=2) {
+        0 => Vec::new(),
+        1 => b"other.realm\x00@/..".to_vec(),
+        _ => vec![b'R'; rng.gen_range(0x100..0x2000)],
+    }
+}
+
+/// Fuzzes the AP-REQ with random values that comply to the size restrictions of the fields.
+/// The ticket and the authenticator consist of random cipher texts, since the keys are unknown.
+pub fn fuzz_kerberos_ap_req_with_random_fields() -> ApReq {
+    let mut rng = rand::thread_rng();
+    let mut ap_req = ApReq::default();
+
+    ap_req.pvno = create_random_byte_array_of_predefined_length(1);
+    ap_req.msg_type = create_random_byte_array_of_predefined_length(1);
+    ap_req.ap_options = create_random_byte_array_of_predefined_length(5);
+    ap_req.ticket.tkt_vno = create_random_byte_array_of_predefined_length(1);
+    ap_req.ticket.realm = create_random_byte_array_of_predefined_length(rng.gen_range(0..64));
+    ap_req.ticket.sname.name_type = create_random_byte_array_of_predefined_length(1);
+    ap_req.ticket.sname.name_string = (0..rng.gen_range(1..=3))
+        .map(|_| create_random_byte_array_of_predefined_length(rng.gen_range(1..32)))
+        .collect();
+    ap_req.ticket.enc_part.etype = create_random_byte_array_of_predefined_length(1);
+    ap_req.ticket.enc_part.kvno = create_random_byte_array_of_predefined_length(1);
+    ap_req.ticket.enc_part.cipher =
+        create_random_byte_array_of_predefined_length(rng.gen_range(28..1024));
+    ap_req.authenticator.etype = create_random_byte_array_of_predefined_length(1);
+    ap_req.authenticator.cipher =
+        create_random_byte_array_of_predefined_length(rng.gen_range(28..256));
+
+    ap_req
+}
+
+/// Fuzzes the AP-REQ with random values of random length.
+pub fn fuzz_kerberos_ap_req_completely_random() -> ApReq {
+    let mut ap_req = ApReq::default();
+
+    ap_req.pvno = create_random_byte_array_with_random_length();
+    ap_req.msg_type = create_random_byte_array_with_random_length();
+    ap_req.ap_options = create_random_byte_array_with_random_length();
+    ap_req.ticket.tkt_vno = create_random_byte_array_with_random_length();
+    ap_req.ticket.realm = create_random_byte_array_with_random_length();
+    ap_req.ticket.sname.name_type = create_random_byte_array_with_random_length();
+    ap_req.ticket.sname.name_string = vec![create_random_byte_array_with_random_length()];
+    ap_req.ticket.enc_part.etype = create_random_byte_array_with_random_length();
+    ap_req.ticket.enc_part.kvno = create_random_byte_array_with_random_length();
+    ap_req.ticket.enc_part.cipher = create_random_byte_array_with_random_length();
+    ap_req.authenticator.etype = create_random_byte_array_with_random_length();
+    ap_req.authenticator.kvno = create_random_byte_array_with_random_length();
+    ap_req.authenticator.cipher = create_random_byte_array_with_random_length();
+
+    ap_req
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        builder::session_setup_kerberos_request::build_kerberos_security_blob,
+        format::decoder::{
+            kerberos_decoder::decode_kerberos_gss_token, spnego_decoder::decode_negotiation_token,
+        },
+        gss::NegotiationToken,
+        kerberos::{
+            GSS_AP_REQ_TOKEN_ID, GSS_CHECKSUM_TYPE, KEY_USAGE_AP_REQ_AUTHENTICATOR, NT_PRINCIPAL,
+            NT_SRV_INST,
+        },
+    };
+
+    fn build_service_ticket() -> ServiceTicket {
+        let mut ticket = Ticket::default();
+        ticket.realm = b"DOMAIN.LOCAL".to_vec();
+        ticket.sname = PrincipalName::from_string(NT_SRV_INST, "cifs/server.domain.local");
+        ticket.enc_part.etype = EncryptionType::Aes256CtsHmacSha196.unpack_byte_code();
+        ticket.enc_part.cipher = vec![0x33; 64];
+
+        ServiceTicket {
+            cname: PrincipalName::from_string(NT_PRINCIPAL, "alice"),
+            crealm: b"DOMAIN.LOCAL".to_vec(),
+            ticket,
+            session_key: EncryptionKey {
+                key_type: EncryptionType::Aes256CtsHmacSha196.unpack_byte_code(),
+                key_value: vec![0x11; 32],
+            },
+        }
+    }
+
+    #[test]
+    fn test_fuzz_authenticator() {
+        let (default_authenticator, _) =
+            build_default_ap_req_authenticator(&build_service_ticket());
+        for _ in 0..32 {
+            let mut authenticator = default_authenticator.clone();
+            fuzz_authenticator(&mut authenticator);
+
+            assert_ne!(default_authenticator, authenticator);
+        }
+    }
+
+    #[test]
+    fn test_fuzzed_authenticator_is_sealed_with_session_key() {
+        let service_ticket = build_service_ticket();
+        let (mut authenticator, _) = build_default_ap_req_authenticator(&service_ticket);
+        fuzz_authenticator(&mut authenticator);
+        let ap_req = build_ap_req(&service_ticket, &authenticator);
+
+        assert!(kerberos::decrypt(
+            &EncryptionType::Aes256CtsHmacSha196,
+            &service_ticket.session_key.key_value,
+            KEY_USAGE_AP_REQ_AUTHENTICATOR,
+            &ap_req.authenticator.cipher
+        )
+        .is_some());
+    }
+
+    #[test]
+    fn test_fuzz_encrypted_data() {
+        let service_ticket = build_service_ticket();
+        for _ in 0..32 {
+            let mut enc_part = service_ticket.ticket.enc_part.clone();
+            fuzz_encrypted_data(&mut enc_part);
+
+            assert_ne!(service_ticket.ticket.enc_part, enc_part);
+        }
+    }
+
+    #[test]
+    fn test_fuzzed_ap_req_keeps_gss_wrapper() {
+        for ap_req in [
+            fuzz_kerberos_ap_req_with_predefined_values(&build_service_ticket()),
+            fuzz_kerberos_ap_req_with_random_fields(),
+            fuzz_kerberos_ap_req_completely_random(),
+        ] {
+            let mech_token = match decode_negotiation_token(&build_kerberos_security_blob(&ap_req))
+            {
+                Some(NegotiationToken::NegTokenInit(token)) => token.mech_token,
+                _ => panic!("Invalid security blob."),
+            };
+            assert_eq!(
+                Some(GSS_AP_REQ_TOKEN_ID.to_vec()),
+                decode_kerberos_gss_token(&mech_token).map(|(token_id, _)| token_id)
+            );
+        }
+    }
+
+    #[test]
+    fn test_fuzz_gss_checksum_keeps_checksum_type() {
+        for _ in 0..32 {
+            if let Some(checksum) = fuzz_gss_checksum() {
+                assert!(!checksum.cksum_type.is_empty());
+                if checksum.cksum_type == GSS_CHECKSUM_TYPE.to_vec() {
+                    assert_ne!(
+                        build_gss_checksum(GssFlags::MUTUAL | GssFlags::INTEG),
+                        checksum
+                    );
+                }
+            }
+        }
+    }
+}
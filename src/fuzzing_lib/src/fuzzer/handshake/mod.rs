@@ -1,3 +1,8 @@
+pub mod kerberos_fuzzer;
 pub mod negotiate_fuzzer;
+pub mod ntlm_authenticate_fuzzer;
+pub mod ntlm_negotiate_fuzzer;
+pub mod session_scenario_fuzzer;
 pub mod session_setup_fuzzer;
+pub mod spnego_fuzzer;
 pub mod tree_connect_fuzzer;
@@ -1,4 +1,5 @@
 use crate::{
+    format::convert_byte_array_to_int,
     format::encoder::negotiate_encoder::add_alignment_padding_if_necessary,
     fuzzer::create_random_byte_array_of_predefined_length,
     fuzzer::create_random_byte_array_with_random_length,
@@ -11,7 +12,42 @@ use crate::{
     },
 };
 
-use rand::Rng;
+use rand::{
+    distributions::{Distribution, Standard},
+    Rng,
+};
+
+/// The mutations of the negotiate context list that exercise the context list parser of the server.
+///
+/// *Unregistered Type*:
+///     - A context with an unregistered type id and random data is inserted.
+///
+/// *Oversized Data Length*:
+///     - The DataLength of a context exceeds the length of its data.
+///
+/// *Zero Length Data*:
+///     - The data of a context is omitted and its DataLength is zero.
+///
+/// *Duplicate Context*:
+///     - A context is repeated in the list.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum NegotiateContextMutation {
+    UnregisteredType,
+    OversizedDataLength,
+    ZeroLengthData,
+    DuplicateContext,
+}
+
+impl Distribution<NegotiateContextMutation> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> NegotiateContextMutation {
+        match rng.gen_range(0..=3) {
+            0 => NegotiateContextMutation::UnregisteredType,
+            1 => NegotiateContextMutation::OversizedDataLength,
+            2 => NegotiateContextMutation::ZeroLengthData,
+            _ => NegotiateContextMutation::DuplicateContext,
+        }
+    }
+}
 
 /// Fuzzes the negotiate request with predefined valid values and calculates sizes and offsets
 /// accordingly.
@@ -29,6 +65,12 @@ pub fn fuzz_negotiate_with_predefined_values() -> Negotiate {
     negotiate_request.security_mode = rand::random::<SecurityMode>().unpack_byte_code(2);
     negotiate_request.client_guid = vec![0; 16];
     negotiate_request.negotiate_context_list = sample_negotiate_contexts();
+    if rand::thread_rng().gen_bool(0.5) {
+        mutate_negotiate_contexts(
+            &mut negotiate_request.negotiate_context_list,
+            &rand::random(),
+        );
+    }
     negotiate_request.negotiate_context_count = (negotiate_request.negotiate_context_list.len()
         as u16)
         .to_le_bytes()
@@ -117,12 +159,7 @@ pub fn sample_negotiate_contexts() -> Vec<NegotiateContext> {
     let context_types = sample_context_types();
 
     for context_type in context_types.into_iter() {
-        let mut neg_context = NegotiateContext::default();
-        neg_context.context_type = context_type.unpack_byte_code();
-        neg_context.data_length = context_type.get_capability_data_length();
-        neg_context.data = Some(context_type);
-
-        negotiate_contexts.push(neg_context);
+        negotiate_contexts.push(wrap_context_type(context_type));
     }
 
     negotiate_contexts
@@ -140,9 +177,66 @@ pub fn sample_context_types() -> Vec<ContextType> {
     context_types
 }
 
+/// Applies the mutation to the negotiate context list. Mutations of an existing
+/// context insert an unregistered context first if the list is empty.
+pub fn mutate_negotiate_contexts(
+    negotiate_contexts: &mut Vec<NegotiateContext>,
+    mutation: &NegotiateContextMutation,
+) {
+    let mut rng = rand::thread_rng();
+
+    if negotiate_contexts.is_empty() || *mutation == NegotiateContextMutation::UnregisteredType {
+        let unknown_context = ContextType::Unknown {
+            context_type: ContextType::sample_unregistered_context_type(),
+            data: create_random_byte_array_of_predefined_length(rng.gen_range(0..100)),
+        };
+        let index = rng.gen_range(0..=negotiate_contexts.len());
+        negotiate_contexts.insert(index, wrap_context_type(unknown_context));
+    }
+    let index = rng.gen_range(0..negotiate_contexts.len());
+
+    match mutation {
+        NegotiateContextMutation::UnregisteredType => {}
+        NegotiateContextMutation::OversizedDataLength => {
+            let data_length =
+                convert_byte_array_to_int(negotiate_contexts[index].data_length.clone(), false)
+                    as u16;
+            negotiate_contexts[index].data_length = rng
+                .gen_range(data_length.saturating_add(1)..=u16::MAX)
+                .to_le_bytes()
+                .to_vec();
+        }
+        NegotiateContextMutation::ZeroLengthData => {
+            let context_type = negotiate_contexts[index].context_type.clone();
+            negotiate_contexts[index] = wrap_context_type(ContextType::Unknown {
+                context_type,
+                data: Vec::new(),
+            });
+        }
+        NegotiateContextMutation::DuplicateContext => {
+            let duplicate = negotiate_contexts[index].clone();
+            let duplicate_index = rng.gen_range(0..=negotiate_contexts.len());
+            negotiate_contexts.insert(duplicate_index, duplicate);
+        }
+    }
+}
+
+/// Wraps the context type into a negotiate context with the corresponding type and data length.
+pub fn wrap_context_type(context_type: ContextType) -> NegotiateContext {
+    let mut neg_context = NegotiateContext::default();
+    neg_context.context_type = context_type.unpack_byte_code();
+    neg_context.data_length = context_type.get_capability_data_length();
+    neg_context.data = Some(context_type);
+
+    neg_context
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::smb2::helper_functions::negotiate_context::{
+        TransportCapabilities, REGISTERED_CONTEXT_TYPES,
+    };
 
     #[test]
     fn test_fuzz_negotiate_with_predefined_values() {
@@ -168,43 +262,73 @@ mod tests {
 
     #[test]
     fn test_sample_negotiate_contexts() {
-        let expected_context_types: Vec<Vec<u8>> = vec![
-            vec![1, 0],
-            vec![2, 0],
-            vec![3, 0],
-            vec![5, 0],
-            vec![6, 0],
-            vec![7, 0],
-        ];
+        let expected_context_types: Vec<Vec<u8>> = REGISTERED_CONTEXT_TYPES
+            .iter()
+            .map(|context_type| context_type.to_le_bytes().to_vec())
+            .collect();
 
         let contexts = sample_negotiate_contexts();
 
         assert!(contexts.len() <= 10);
 
         for context in contexts.into_iter() {
-            assert!(expected_context_types.contains(&context.context_type));
             assert_eq!(vec![0; 4], context.reserved);
-            assert!(expected_context_types.contains(&context.data.unwrap().unpack_byte_code()));
+            match context.data.unwrap() {
+                ContextType::Unknown { context_type, .. } => {
+                    assert_eq!(context_type, context.context_type);
+                    assert!(!expected_context_types.contains(&context_type));
+                }
+                data => {
+                    assert!(expected_context_types.contains(&context.context_type));
+                    assert_eq!(data.unpack_byte_code(), context.context_type);
+                }
+            }
         }
     }
 
     #[test]
     fn test_sample_context_types() {
-        let expected_context_types: Vec<Vec<u8>> = vec![
-            vec![1, 0],
-            vec![2, 0],
-            vec![3, 0],
-            vec![5, 0],
-            vec![6, 0],
-            vec![7, 0],
-        ];
+        let expected_context_types: Vec<Vec<u8>> = REGISTERED_CONTEXT_TYPES
+            .iter()
+            .map(|context_type| context_type.to_le_bytes().to_vec())
+            .collect();
 
         let contexts = sample_context_types();
 
         assert!(contexts.len() <= 10);
 
         for context in sample_context_types().into_iter() {
-            assert!(expected_context_types.contains(&context.unpack_byte_code()));
+            assert_eq!(
+                !matches!(context, ContextType::Unknown { .. }),
+                expected_context_types.contains(&context.unpack_byte_code())
+            );
         }
     }
+
+    #[test]
+    fn test_mutate_negotiate_contexts() {
+        let mut negotiate_contexts: Vec<NegotiateContext> = Vec::new();
+        mutate_negotiate_contexts(
+            &mut negotiate_contexts,
+            &NegotiateContextMutation::DuplicateContext,
+        );
+        assert_eq!(2, negotiate_contexts.len());
+        assert_eq!(negotiate_contexts[0], negotiate_contexts[1]);
+
+        let mut negotiate_contexts = vec![wrap_context_type(ContextType::TransportCapabilities(
+            TransportCapabilities::default(),
+        ))];
+        mutate_negotiate_contexts(
+            &mut negotiate_contexts,
+            &NegotiateContextMutation::OversizedDataLength,
+        );
+        assert!(convert_byte_array_to_int(negotiate_contexts[0].data_length.clone(), false) > 4);
+
+        mutate_negotiate_contexts(
+            &mut negotiate_contexts,
+            &NegotiateContextMutation::ZeroLengthData,
+        );
+        assert_eq!(vec![6, 0], negotiate_contexts[0].context_type);
+        assert_eq!(vec![0, 0], negotiate_contexts[0].data_length);
+    }
 }
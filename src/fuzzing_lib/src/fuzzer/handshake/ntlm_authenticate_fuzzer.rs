@@ -0,0 +1,472 @@
+use rand::Rng;
+
+use super::ntlm_negotiate_fuzzer::{fuzz_version, sample_negotiate_flags};
+use crate::{
+    builder::{
+        session_setup_authenticate_request::security_buffer::{
+            build_authenticate_message, set_payload_fields, wrap_authenticate,
+        },
+        session_setup_negotiate_request::initial_ntlm_negotiate_message,
+    },
+    crypto::ntlm,
+    format::{convert_byte_array_to_int, encoder::security_blob_encoder::encode_authenticate_blob},
+    fuzzer::{
+        create_random_byte_array_of_predefined_length, create_random_byte_array_with_random_length,
+    },
+    ntlmssp::{
+        authenticate::{Authenticate, NtlmV2ClientChallenge},
+        challenge::Challenge,
+        credentials::Credentials,
+        AvId, AvPair,
+    },
+};
+
+/// The offset of the MIC in the AUTHENTICATE_MESSAGE.
+const MIC_OFFSET: u32 = 0x48;
+/// The offset of the payload in the AUTHENTICATE_MESSAGE, which directly follows the MIC.
+const PAYLOAD_OFFSET: u32 = 0x58;
+
+/// Fuzzes the NTLM authenticate message with predefined values. The message is built for the
+/// challenge and the credentials, before the AV pairs of the NTLMv2 client challenge, the negotiate flags,
+/// the version and the length, maximum length and offset fields of the payload are mutated.
+/// The MIC is either fuzzed or computed over the fuzzed message, so that it is consistent.
+pub fn fuzz_ntlm_authenticate_with_predefined_values(
+    server_challenge_struct: Challenge,
+    challenge_message: &[u8],
+    credentials: &Credentials,
+) -> Authenticate {
+    let mut rng = rand::thread_rng();
+    let (mut authenticate, exported_session_key) =
+        build_authenticate_message(server_challenge_struct, challenge_message, credentials);
+
+    if rng.gen_bool(0.5) {
+        fuzz_av_pairs(
+            &mut authenticate
+                .payload
+                .nt_challenge_response
+                .ntlmv2_client_challenge
+                .av_pairs,
+        );
+        set_payload_fields(&mut authenticate);
+    }
+    if rng.gen_bool(0.5) {
+        let flags = convert_byte_array_to_int(authenticate.negotiate_flags.clone(), false);
+        authenticate.negotiate_flags = sample_negotiate_flags(flags).to_le_bytes().to_vec();
+    }
+    if rng.gen_bool(0.25) {
+        fuzz_version(&mut authenticate.version);
+    }
+    fuzz_all_payload_fields(&mut authenticate);
+
+    if rng.gen_bool(0.5) {
+        authenticate.mic = fuzz_mic(authenticate.mic);
+    } else {
+        authenticate.mic = vec![0; ntlm::NTLM_KEY_LENGTH];
+        authenticate.mic = ntlm::compute_mic(
+            &exported_session_key,
+            &initial_ntlm_negotiate_message(),
+            challenge_message,
+            &encode_authenticate_blob(wrap_authenticate(authenticate.clone())),
+        );
+    }
+
+    authenticate
+}
+
+/// Mutates each length, maximum length and offset triple of the payload independently
+/// in a quarter of the cases.
+pub fn fuzz_all_payload_fields(authenticate: &mut Authenticate) {
+    let mut rng = rand::thread_rng();
+    let message_length =
+        encode_authenticate_blob(wrap_authenticate(authenticate.clone())).len() as u32;
+
+    if rng.gen_bool(0.25) {
+        let fields = &mut authenticate.lm_challenge_response_fields;
+        fuzz_payload_fields(
+            &mut fields.lm_challenge_response_len,
+            &mut fields.lm_challenge_response_max_len,
+            &mut fields.lm_challenge_response_buffer_offset,
+            message_length,
+        );
+    }
+    if rng.gen_bool(0.25) {
+        let fields = &mut authenticate.nt_challenge_response_fields;
+        fuzz_payload_fields(
+            &mut fields.nt_challenge_response_len,
+            &mut fields.nt_challenge_response_max_len,
+            &mut fields.nt_challenge_response_buffer_offset,
+            message_length,
+        );
+    }
+    if rng.gen_bool(0.25) {
+        let fields = &mut authenticate.domain_name_fields;
+        fuzz_payload_fields(
+            &mut fields.domain_name_len,
+            &mut fields.domain_name_max_len,
+            &mut fields.domain_name_buffer_offset,
+            message_length,
+        );
+    }
+    if rng.gen_bool(0.25) {
+        let fields = &mut authenticate.user_name_fields;
+        fuzz_payload_fields(
+            &mut fields.user_name_len,
+            &mut fields.user_name_max_len,
+            &mut fields.user_name_buffer_offset,
+            message_length,
+        );
+    }
+    if rng.gen_bool(0.25) {
+        let fields = &mut authenticate.workstation_fields;
+        fuzz_payload_fields(
+            &mut fields.workstation_len,
+            &mut fields.workstation_max_len,
+            &mut fields.workstation_buffer_offset,
+            message_length,
+        );
+    }
+    if rng.gen_bool(0.25) {
+        let fields = &mut authenticate.encrypted_random_session_key_fields;
+        fuzz_payload_fields(
+            &mut fields.encrypted_random_session_key_len,
+            &mut fields.encrypted_random_session_key_max_len,
+            &mut fields.encrypted_random_session_key_buffer_offset,
+            message_length,
+        );
+    }
+}
+
+/// Fuzzes a length, maximum length and offset triple of the payload. Besides a length
+/// that exceeds the maximum length, offsets beyond the message, offsets that overlap the
+/// fixed fields, the MIC or other payload fields, the maximum length and odd lengths are chosen.
+pub fn fuzz_payload_fields(
+    len: &mut Vec<u8>,
+    max_len: &mut Vec<u8>,
+    offset: &mut Vec<u8>,
+    message_length: u32,
+) {
+    let mut rng = rand::thread_rng();
+    let length = convert_byte_array_to_int(len.clone(), false) as u16;
+
+    match rng.gen_range(0..=4) {
+        0 => {
+            *max_len = length.to_le_bytes().to_vec();
+            *len = length
+                .saturating_add(rng.gen_range(1..=16))
+                .to_le_bytes()
+                .to_vec();
+        }
+        1 => {
+            let beyond_message = match rng.gen_range(0..=2) {
+                0 => message_length,
+                1 => message_length + rng.gen_range(1..=0x100),
+                _ => u32::MAX,
+            };
+            *offset = beyond_message.to_le_bytes().to_vec();
+        }
+        2 => {
+            let overlapping_offset = match rng.gen_range(0..=3) {
+                0 => 0,
+                1 => MIC_OFFSET,
+                2 => PAYLOAD_OFFSET,
+                _ => rng.gen_range(0..message_length.max(1)),
+            };
+            *offset = overlapping_offset.to_le_bytes().to_vec();
+        }
+        3 => {
+            *len = u16::MAX.to_le_bytes().to_vec();
+            *max_len = u16::MAX.to_le_bytes().to_vec();
+        }
+        _ => {
+            *len = (length | 1).to_le_bytes().to_vec();
+            *max_len = (length | 1).to_le_bytes().to_vec();
+        }
+    }
+}
+
+/// Fuzzes the AV pairs of the NTLMv2 client challenge. Besides a list without MsvAvEOL,
+/// duplicated AV pairs, a huge or inconsistent AvLen, MsvAvEOL in front of the list and
+/// a long list of AV pairs are chosen.
+pub fn fuzz_av_pairs(av_pairs: &mut Vec<AvPair>) {
+    let mut rng = rand::thread_rng();
+
+    if av_pairs.is_empty() {
+        av_pairs.push(build_av_pair(AvId::MsvAvEol, Vec::new()));
+    }
+
+    match rng.gen_range(0..=5) {
+        0 => av_pairs.retain(|pair| pair.av_id != Some(AvId::MsvAvEol)),
+        1 => {
+            let duplicate = av_pairs[rng.gen_range(0..av_pairs.len())].clone();
+            av_pairs.insert(rng.gen_range(0..av_pairs.len()), duplicate);
+        }
+        2 => {
+            let index = rng.gen_range(0..av_pairs.len());
+            av_pairs[index].av_len = u16::MAX.to_le_bytes().to_vec();
+        }
+        3 => {
+            let index = rng.gen_range(0..av_pairs.len());
+            let value_length = av_pairs[index].value.len() as u16;
+            let av_len = if rng.gen_bool(0.5) {
+                value_length.wrapping_add(rng.gen_range(1..=8))
+            } else {
+                value_length.wrapping_sub(rng.gen_range(1..=8))
+            };
+            av_pairs[index].av_len = av_len.to_le_bytes().to_vec();
+        }
+        4 => {
+            let eol = build_av_pair(
+                AvId::MsvAvEol,
+                create_random_byte_array_of_predefined_length(rng.gen_range(0..=4)),
+            );
+            av_pairs.insert(0, eol);
+        }
+        _ => {
+            let flags = build_av_pair(
+                AvId::MsvAvFlags,
+                rand::random::<u32>().to_le_bytes().to_vec(),
+            );
+            for _ in 0..rng.gen_range(64..1024) {
+                av_pairs.insert(0, flags.clone());
+            }
+        }
+    }
+}
+
+/// Builds an AV pair with the given id and value, whose AvLen is the length of the value.
+pub fn build_av_pair(av_id: AvId, value: Vec<u8>) -> AvPair {
+    let mut av_pair = AvPair::default();
+    av_pair.av_id = Some(av_id);
+    av_pair.av_len = (value.len() as u16).to_le_bytes().to_vec();
+    av_pair.value = value;
+
+    av_pair
+}
+
+/// Fuzzes the MIC. Besides a zeroed or random MIC, a MIC with a flipped bit
+/// and a missing MIC, which moves the payload, are chosen.
+pub fn fuzz_mic(mut mic: Vec<u8>) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=3) {
+        0 => vec![0; ntlm::NTLM_KEY_LENGTH],
+        1 => create_random_byte_array_of_predefined_length(ntlm::NTLM_KEY_LENGTH as u32),
+        2 if !mic.is_empty() => {
+            let index = rng.gen_range(0..mic.len());
+            mic[index] ^= 1 << rng.gen_range(0..8);
+            mic
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Samples a random AV id.
+pub fn sample_av_id() -> AvId {
+    match rand::thread_rng().gen_range(0..=10) {
+        0 => AvId::MsvAvEol,
+        1 => AvId::MsvAvNbComputerName,
+        2 => AvId::MsvAvNbDomainName,
+        3 => AvId::MsvAvDnsComputerName,
+        4 => AvId::MsvAvDnsDomainName,
+        5 => AvId::MsvAvDnsTreeName,
+        6 => AvId::MsvAvFlags,
+        7 => AvId::MsvAvTimeStamp,
+        8 => AvId::MsvAvSingleHost,
+        9 => AvId::MsvAvTargetName,
+        _ => AvId::MsvAvChannelBindings,
+    }
+}
+
+/// Fuzzes the NTLM authenticate message with random values that comply to the size restrictions of the fields.
+pub fn fuzz_ntlm_authenticate_with_random_fields() -> Authenticate {
+    let mut rng = rand::thread_rng();
+    let mut authenticate = Authenticate::default();
+
+    authenticate.payload.lm_challenge_response = create_random_byte_array_of_predefined_length(24);
+    authenticate.payload.nt_challenge_response.response =
+        create_random_byte_array_of_predefined_length(16);
+    let client_challenge = &mut authenticate
+        .payload
+        .nt_challenge_response
+        .ntlmv2_client_challenge;
+    client_challenge.resp_type = create_random_byte_array_of_predefined_length(1);
+    client_challenge.hi_resp_type = create_random_byte_array_of_predefined_length(1);
+    client_challenge.reserved1 = create_random_byte_array_of_predefined_length(2);
+    client_challenge.reserved2 = create_random_byte_array_of_predefined_length(4);
+    client_challenge.time_stamp = create_random_byte_array_of_predefined_length(8);
+    client_challenge.challenge_from_client = create_random_byte_array_of_predefined_length(8);
+    client_challenge.reserved3 = create_random_byte_array_of_predefined_length(4);
+    client_challenge.av_pairs = (0..rng.gen_range(0..16))
+        .map(|_| {
+            build_av_pair(
+                sample_av_id(),
+                create_random_byte_array_of_predefined_length(rng.gen_range(0..64)),
+            )
+        })
+        .collect();
+    client_challenge.padding = create_random_byte_array_of_predefined_length(4);
+    authenticate.payload.domain_name =
+        create_random_byte_array_of_predefined_length(rng.gen_range(0..64));
+    authenticate.payload.user_name =
+        create_random_byte_array_of_predefined_length(rng.gen_range(0..64));
+    authenticate.payload.workstation =
+        create_random_byte_array_of_predefined_length(rng.gen_range(0..64));
+    authenticate.payload.encrypted_random_session_key =
+        create_random_byte_array_of_predefined_length(16);
+    set_payload_fields(&mut authenticate);
+
+    authenticate.negotiate_flags = create_random_byte_array_of_predefined_length(4);
+    authenticate.version.product_major_version = create_random_byte_array_of_predefined_length(1);
+    authenticate.version.product_minor_version = create_random_byte_array_of_predefined_length(1);
+    authenticate.version.product_build = create_random_byte_array_of_predefined_length(2);
+    authenticate.version.reserved = create_random_byte_array_of_predefined_length(3);
+    authenticate.version.ntlm_revision_current = create_random_byte_array_of_predefined_length(1);
+    authenticate.mic = create_random_byte_array_of_predefined_length(16);
+
+    authenticate
+}
+
+/// Fuzzes the NTLM authenticate message with random values of random length.
+pub fn fuzz_ntlm_authenticate_completely_random() -> Authenticate {
+    let mut authenticate = Authenticate::default();
+
+    let lm = &mut authenticate.lm_challenge_response_fields;
+    lm.lm_challenge_response_len = create_random_byte_array_with_random_length();
+    lm.lm_challenge_response_max_len = create_random_byte_array_with_random_length();
+    lm.lm_challenge_response_buffer_offset = create_random_byte_array_with_random_length();
+    let nt = &mut authenticate.nt_challenge_response_fields;
+    nt.nt_challenge_response_len = create_random_byte_array_with_random_length();
+    nt.nt_challenge_response_max_len = create_random_byte_array_with_random_length();
+    nt.nt_challenge_response_buffer_offset = create_random_byte_array_with_random_length();
+    let domain = &mut authenticate.domain_name_fields;
+    domain.domain_name_len = create_random_byte_array_with_random_length();
+    domain.domain_name_max_len = create_random_byte_array_with_random_length();
+    domain.domain_name_buffer_offset = create_random_byte_array_with_random_length();
+    let user = &mut authenticate.user_name_fields;
+    user.user_name_len = create_random_byte_array_with_random_length();
+    user.user_name_max_len = create_random_byte_array_with_random_length();
+    user.user_name_buffer_offset = create_random_byte_array_with_random_length();
+    let workstation = &mut authenticate.workstation_fields;
+    workstation.workstation_len = create_random_byte_array_with_random_length();
+    workstation.workstation_max_len = create_random_byte_array_with_random_length();
+    workstation.workstation_buffer_offset = create_random_byte_array_with_random_length();
+    let key = &mut authenticate.encrypted_random_session_key_fields;
+    key.encrypted_random_session_key_len = create_random_byte_array_with_random_length();
+    key.encrypted_random_session_key_max_len = create_random_byte_array_with_random_length();
+    key.encrypted_random_session_key_buffer_offset = create_random_byte_array_with_random_length();
+
+    authenticate.negotiate_flags = create_random_byte_array_with_random_length();
+    authenticate.version.product_major_version = create_random_byte_array_with_random_length();
+    authenticate.version.product_minor_version = create_random_byte_array_with_random_length();
+    authenticate.version.product_build = create_random_byte_array_with_random_length();
+    authenticate.version.reserved = create_random_byte_array_with_random_length();
+    authenticate.version.ntlm_revision_current = create_random_byte_array_with_random_length();
+    authenticate.mic = create_random_byte_array_with_random_length();
+
+    authenticate.payload.lm_challenge_response = create_random_byte_array_with_random_length();
+    authenticate.payload.nt_challenge_response.response =
+        create_random_byte_array_with_random_length();
+    authenticate
+        .payload
+        .nt_challenge_response
+        .ntlmv2_client_challenge = fuzz_ntlmv2_client_challenge_completely_random();
+    authenticate.payload.domain_name = create_random_byte_array_with_random_length();
+    authenticate.payload.user_name = create_random_byte_array_with_random_length();
+    authenticate.payload.workstation = create_random_byte_array_with_random_length();
+    authenticate.payload.encrypted_random_session_key =
+        create_random_byte_array_with_random_length();
+
+    authenticate
+}
+
+/// Fuzzes the NTLMv2 client challenge with random values of random length.
+pub fn fuzz_ntlmv2_client_challenge_completely_random() -> NtlmV2ClientChallenge {
+    let mut client_challenge = NtlmV2ClientChallenge::default();
+
+    client_challenge.resp_type = create_random_byte_array_with_random_length();
+    client_challenge.hi_resp_type = create_random_byte_array_with_random_length();
+    client_challenge.reserved1 = create_random_byte_array_with_random_length();
+    client_challenge.reserved2 = create_random_byte_array_with_random_length();
+    client_challenge.time_stamp = create_random_byte_array_with_random_length();
+    client_challenge.challenge_from_client = create_random_byte_array_with_random_length();
+    client_challenge.reserved3 = create_random_byte_array_with_random_length();
+    client_challenge.padding = create_random_byte_array_with_random_length();
+
+    client_challenge
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        format::decoder::security_blob_decoder::remove_gss_wrapper,
+        fuzzer::handshake::session_setup_fuzzer::{
+            fuzz_session_setup_authenticate_completely_random,
+            fuzz_session_setup_authenticate_with_random_fields,
+        },
+    };
+
+    #[test]
+    fn test_fuzz_payload_fields() {
+        for _ in 0..32 {
+            let mut len = 16u16.to_le_bytes().to_vec();
+            let mut max_len = 16u16.to_le_bytes().to_vec();
+            let mut offset = PAYLOAD_OFFSET.to_le_bytes().to_vec();
+            fuzz_payload_fields(&mut len, &mut max_len, &mut offset, 0x100);
+
+            assert_eq!(2, len.len());
+            assert_eq!(2, max_len.len());
+            assert_eq!(4, offset.len());
+        }
+    }
+
+    #[test]
+    fn test_fuzz_av_pairs() {
+        for _ in 0..32 {
+            let mut av_pairs = vec![
+                build_av_pair(AvId::MsvAvFlags, vec![2, 0, 0, 0]),
+                build_av_pair(AvId::MsvAvEol, Vec::new()),
+            ];
+            fuzz_av_pairs(&mut av_pairs);
+
+            assert_ne!(
+                vec![
+                    build_av_pair(AvId::MsvAvFlags, vec![2, 0, 0, 0]),
+                    build_av_pair(AvId::MsvAvEol, Vec::new()),
+                ],
+                av_pairs
+            );
+        }
+    }
+
+    #[test]
+    fn test_fuzz_ntlm_authenticate_with_random_fields() {
+        let authenticate = fuzz_ntlm_authenticate_with_random_fields();
+        let encoded = encode_authenticate_blob(wrap_authenticate(authenticate.clone()));
+
+        let nt_offset = convert_byte_array_to_int(
+            authenticate
+                .nt_challenge_response_fields
+                .nt_challenge_response_buffer_offset,
+            false,
+        ) as usize;
+        assert_eq!(
+            authenticate.payload.nt_challenge_response.response,
+            encoded[nt_offset..nt_offset + 16].to_vec()
+        );
+    }
+
+    #[test]
+    fn test_fuzzed_authenticate_keeps_gss_wrapper() {
+        for session_setup in [
+            fuzz_session_setup_authenticate_with_random_fields(),
+            fuzz_session_setup_authenticate_completely_random(),
+        ] {
+            let ntlm_message = remove_gss_wrapper(session_setup.buffer);
+            assert_eq!(
+                b"NTLMSSP\x00\x03\x00\x00\x00".to_vec(),
+                ntlm_message[..12].to_vec()
+            );
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use rand::Rng;
+
+use crate::{
+    builder::session_setup_negotiate_request::{
+        build_default_ntlm_negotiate_message, set_negotiate_payload_fields,
+    },
+    format::convert_byte_array_to_int,
+    fuzzer::{
+        create_random_byte_array_of_predefined_length, create_random_byte_array_with_random_length,
+    },
+    ntlmssp::{negotiate::Negotiate, negotiate_flags::NegotiateFlags, Version},
+};
+
+/// Fuzzes the NTLM negotiate message with predefined values.
+/// The negotiate flags are sampled, OEM domain and workstation names are supplied
+/// with sampled names and possibly inconsistent fields, and the version is mutated.
+pub fn fuzz_ntlm_negotiate_with_predefined_values() -> Negotiate {
+    let mut rng = rand::thread_rng();
+    let mut negotiate = build_default_ntlm_negotiate_message();
+    let mut flags = sample_negotiate_flags(convert_byte_array_to_int(
+        negotiate.negotiate_flags.clone(),
+        false,
+    ));
+
+    if rng.gen_bool(0.5) {
+        flags |= NegotiateFlags::NEG_OEM_DOMAIN_SUPPLIED.bits();
+        negotiate.payload.domain_name = sample_oem_name();
+    }
+    if rng.gen_bool(0.5) {
+        flags |= NegotiateFlags::NEG_OEM_WORKSTATION_SUPPLIED.bits();
+        negotiate.payload.workstation_name = sample_oem_name();
+    }
+    negotiate.negotiate_flags = flags.to_le_bytes().to_vec();
+    set_negotiate_payload_fields(&mut negotiate);
+
+    if rng.gen_bool(0.25) {
+        negotiate.domain_name_fields.domain_name_len = sample_length_field();
+    }
+    if rng.gen_bool(0.25) {
+        negotiate.domain_name_fields.domain_name_buffer_offset = sample_offset_field();
+    }
+    if rng.gen_bool(0.25) {
+        negotiate.workstation_fields.workstation_len = sample_length_field();
+    }
+    if rng.gen_bool(0.25) {
+        negotiate.workstation_fields.workstation_buffer_offset = sample_offset_field();
+    }
+    if rng.gen_bool(0.5) {
+        fuzz_version(&mut negotiate.version);
+    }
+
+    negotiate
+}
+
+/// Samples the negotiate flags. Besides the default flags with single flags toggled,
+/// no flags, all defined flags, random combinations of defined flags and undefined flags are chosen.
+pub fn sample_negotiate_flags(default_flags: u32) -> u32 {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=4) {
+        0 => default_flags ^ (1 << rng.gen_range(0..32)),
+        1 => 0,
+        2 => NegotiateFlags::all().bits(),
+        3 => rng.gen::<u32>() & NegotiateFlags::all().bits(),
+        _ => default_flags | (!NegotiateFlags::all().bits() & rng.gen::<u32>()),
+    }
+}
+
+/// Samples an OEM name. Besides a regular name, empty, overlong, non-ASCII,
+/// null-terminated and format string names are chosen.
+pub fn sample_oem_name() -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=5) {
+        0 => b"WORKGROUP".to_vec(),
+        1 => Vec::new(),
+        2 => vec![b'A'; rng.gen_range(256..4096)],
+        3 => create_random_byte_array_of_predefined_length(rng.gen_range(1..64)),
+        4 => b"WORK\x00GROUP\x00".to_vec(),
+        _ => b"%s%s%n%x".to_vec(),
+    }
+}
+
+/// Samples a length field. Besides zero and the maximum, odd lengths are chosen.
+pub fn sample_length_field() -> Vec<u8> {
+    let length: u16 = match rand::thread_rng().gen_range(0..=2) {
+        0 => 0,
+        1 => u16::MAX,
+        _ => rand::thread_rng().gen_range(0..256) | 1,
+    };
+
+    length.to_le_bytes().to_vec()
+}
+
+/// Samples an offset field. Besides zero and the maximum, offsets into
+/// the fixed fields of the message are chosen.
+pub fn sample_offset_field() -> Vec<u8> {
+    let offset: u32 = match rand::thread_rng().gen_range(0..=2) {
+        0 => 0,
+        1 => u32::MAX,
+        _ => rand::thread_rng().gen_range(1..0x28),
+    };
+
+    offset.to_le_bytes().to_vec()
+}
+
+/// Fuzzes the version. Besides random product versions, the NTLM revisions 10, 15, 0 and 255
+/// are chosen, or the version is removed although NTLMSSP_NEGOTIATE_VERSION may be set.
+pub fn fuzz_version(version: &mut Version) {
+    let mut rng = rand::thread_rng();
+
+    if rng.gen_bool(0.2) {
+        version.product_major_version = Vec::new();
+        version.product_minor_version = Vec::new();
+        version.product_build = Vec::new();
+        version.reserved = Vec::new();
+        version.ntlm_revision_current = Vec::new();
+        return;
+    }
+
+    version.product_major_version = create_random_byte_array_of_predefined_length(1);
+    version.product_minor_version = create_random_byte_array_of_predefined_length(1);
+    version.product_build = create_random_byte_array_of_predefined_length(2);
+    if rng.gen_bool(0.25) {
+        version.reserved = create_random_byte_array_of_predefined_length(3);
+    }
+    version.ntlm_revision_current = match rng.gen_range(0..=3) {
+        0 => vec![0x0a],
+        1 => vec![0x0f],
+        2 => vec![0x00],
+        _ => vec![0xff],
+    };
+}
+
+/// Fuzzes the NTLM negotiate message with random values that comply to the size restrictions of the fields.
+pub fn fuzz_ntlm_negotiate_with_random_fields() -> Negotiate {
+    let mut negotiate = Negotiate::default();
+
+    negotiate.negotiate_flags = create_random_byte_array_of_predefined_length(4);
+    negotiate.domain_name_fields.domain_name_len = create_random_byte_array_of_predefined_length(2);
+    negotiate.domain_name_fields.domain_name_max_len =
+        create_random_byte_array_of_predefined_length(2);
+    negotiate.domain_name_fields.domain_name_buffer_offset =
+        create_random_byte_array_of_predefined_length(4);
+    negotiate.workstation_fields.workstation_len = create_random_byte_array_of_predefined_length(2);
+    negotiate.workstation_fields.workstation_max_len =
+        create_random_byte_array_of_predefined_length(2);
+    negotiate.workstation_fields.workstation_buffer_offset =
+        create_random_byte_array_of_predefined_length(4);
+    negotiate.version.product_major_version = create_random_byte_array_of_predefined_length(1);
+    negotiate.version.product_minor_version = create_random_byte_array_of_predefined_length(1);
+    negotiate.version.product_build = create_random_byte_array_of_predefined_length(2);
+    negotiate.version.reserved = create_random_byte_array_of_predefined_length(3);
+    negotiate.version.ntlm_revision_current = create_random_byte_array_of_predefined_length(1);
+    negotiate.payload.domain_name = create_random_byte_array_with_random_length();
+    negotiate.payload.workstation_name = create_random_byte_array_with_random_length();
+
+    negotiate
+}
+
+/// Fuzzes the NTLM negotiate message with random values of random length.
+pub fn fuzz_ntlm_negotiate_completely_random() -> Negotiate {
+    let mut negotiate = Negotiate::default();
+
+    negotiate.negotiate_flags = create_random_byte_array_with_random_length();
+    negotiate.domain_name_fields.domain_name_len = create_random_byte_array_with_random_length();
+    negotiate.domain_name_fields.domain_name_max_len =
+        create_random_byte_array_with_random_length();
+    negotiate.domain_name_fields.domain_name_buffer_offset =
+        create_random_byte_array_with_random_length();
+    negotiate.workstation_fields.workstation_len = create_random_byte_array_with_random_length();
+    negotiate.workstation_fields.workstation_max_len =
+        create_random_byte_array_with_random_length();
+    negotiate.workstation_fields.workstation_buffer_offset =
+        create_random_byte_array_with_random_length();
+    negotiate.version.product_major_version = create_random_byte_array_with_random_length();
+    negotiate.version.product_minor_version = create_random_byte_array_with_random_length();
+    negotiate.version.product_build = create_random_byte_array_with_random_length();
+    negotiate.version.reserved = create_random_byte_array_with_random_length();
+    negotiate.version.ntlm_revision_current = create_random_byte_array_with_random_length();
+    negotiate.payload.domain_name = create_random_byte_array_with_random_length();
+    negotiate.payload.workstation_name = create_random_byte_array_with_random_length();
+
+    negotiate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        builder::session_setup_negotiate_request::wrap_negotiate,
+        format::{
+            decoder::security_blob_decoder::decode_negotiate_message,
+            encoder::security_blob_encoder::encode_negotiate_blob,
+        },
+        ntlmssp::MessageType,
+    };
+
+    #[test]
+    fn test_fuzz_ntlm_negotiate_with_predefined_values() {
+        for _ in 0..32 {
+            let negotiate = fuzz_ntlm_negotiate_with_predefined_values();
+            let flags = convert_byte_array_to_int(negotiate.negotiate_flags.clone(), false);
+
+            assert!(
+                negotiate.payload.domain_name.is_empty()
+                    || flags & NegotiateFlags::NEG_OEM_DOMAIN_SUPPLIED.bits() != 0
+            );
+
+            let encoded = encode_negotiate_blob(wrap_negotiate(negotiate.clone()));
+            if !negotiate.version.ntlm_revision_current.is_empty() {
+                match decode_negotiate_message(&encoded).message {
+                    Some(MessageType::Negotiate(decoded)) => {
+                        assert_eq!(negotiate.negotiate_flags, decoded.negotiate_flags);
+                        assert_eq!(negotiate.version, decoded.version);
+                    }
+                    _ => panic!("Invalid message type."),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use rand::Rng;
+
+use super::super::{
+    create_random_byte_array_of_predefined_length, create_random_byte_array_with_random_length,
+};
+use crate::smb2::{
+    header::SyncHeader,
+    helper_functions::fields::SecurityMode,
+    requests::session_setup::{Flags, SessionSetup},
+};
+
+/// The stateful session setup scenarios on an established session.
+///
+/// *Reauthentication*:
+///     - The established session is re-authenticated on the same connection.
+///
+/// *Binding*:
+///     - A second connection binds to the established session with signed binding requests.
+///
+/// *Reconnect*:
+///     - A second connection sets up a new session with the established session as
+///       PreviousSessionId, so that the server tears down the established session.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SessionScenario {
+    Reauthentication,
+    Binding,
+    Reconnect,
+}
+
+impl SessionScenario {
+    /// Maps a user input string to a session scenario.
+    pub fn map_string_to_session_scenario(scenario: &str) -> Self {
+        match scenario {
+            "reauth" => SessionScenario::Reauthentication,
+            "binding" => SessionScenario::Binding,
+            "reconnect" => SessionScenario::Reconnect,
+            _ => panic!("Invalid session scenario."),
+        }
+    }
+}
+
+/// Fuzzes the fields of the session setup request that carry the session scenario with predefined values.
+/// Between one and three of the flags, the PreviousSessionId, the session id of the header, the channel,
+/// the security mode and the capabilities are mutated, while the security buffer stays valid.
+pub fn fuzz_session_scenario_with_predefined_values(
+    header: &mut SyncHeader,
+    session_setup: &mut SessionSetup,
+    scenario: &SessionScenario,
+    session_id: &[u8],
+) {
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..rng.gen_range(1..=3) {
+        match rng.gen_range(0..=5) {
+            0 => session_setup.flags = sample_session_setup_flags(scenario),
+            1 => session_setup.previous_session_id = sample_session_id(session_id),
+            2 => header.session_id = sample_session_id(session_id),
+            3 => session_setup.channel = rng.gen_range(1..=u32::MAX).to_le_bytes().to_vec(),
+            4 => {
+                session_setup.security_mode = match rng.gen_range(0..=2) {
+                    0 => SecurityMode::NegotiateSigningRequired.unpack_byte_code(1),
+                    1 => vec![0],
+                    _ => vec![rng.gen_range(3..=u8::MAX)],
+                }
+            }
+            _ => session_setup.capabilities = rng.gen::<u32>().to_le_bytes().to_vec(),
+        }
+    }
+}
+
+/// Samples the flags of the session setup request. The binding flag is inverted for the
+/// scenario, i.e. set when re-authenticating or reconnecting and cleared when binding,
+/// or undefined flags are set.
+pub fn sample_session_setup_flags(scenario: &SessionScenario) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    if rng.gen_bool(0.5) {
+        match scenario {
+            SessionScenario::Binding => Flags::Zero.unpack_byte_code(),
+            _ => Flags::SessionSetupBinding.unpack_byte_code(),
+        }
+    } else {
+        vec![rng.gen::<u8>() | 0x02]
+    }
+}
+
+/// Samples a session id related to the established session id. Besides the established session id,
+/// the session id with a flipped bit, a neighbouring session id, zero, all ones and a random session id are chosen.
+pub fn sample_session_id(session_id: &[u8]) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+    let mut established_session_id = [0u8; 8];
+    let length = session_id.len().min(8);
+    established_session_id[..length].copy_from_slice(&session_id[..length]);
+    let established_session_id = u64::from_le_bytes(established_session_id);
+
+    let sampled_session_id = match rng.gen_range(0..=5) {
+        0 => established_session_id,
+        1 => established_session_id ^ (1 << rng.gen_range(0..64)),
+        2 => established_session_id.wrapping_add(1),
+        3 => 0,
+        4 => u64::MAX,
+        _ => rng.gen(),
+    };
+
+    sampled_session_id.to_le_bytes().to_vec()
+}
+
+/// Fuzzes the fields of the session setup request that carry the session scenario with
+/// random values that comply to the size restrictions of the fields.
+pub fn fuzz_session_scenario_with_random_fields(
+    header: &mut SyncHeader,
+    session_setup: &mut SessionSetup,
+) {
+    header.session_id = create_random_byte_array_of_predefined_length(8);
+    session_setup.flags = create_random_byte_array_of_predefined_length(1);
+    session_setup.security_mode = create_random_byte_array_of_predefined_length(1);
+    session_setup.capabilities = create_random_byte_array_of_predefined_length(4);
+    session_setup.channel = create_random_byte_array_of_predefined_length(4);
+    session_setup.previous_session_id = create_random_byte_array_of_predefined_length(8);
+}
+
+/// Fuzzes the fields of the session setup request that carry the session scenario with random values of random length.
+/// The session id of the header keeps its size, so that the header remains decodable.
+pub fn fuzz_session_scenario_completely_random(
+    header: &mut SyncHeader,
+    session_setup: &mut SessionSetup,
+) {
+    header.session_id = create_random_byte_array_of_predefined_length(8);
+    session_setup.flags = create_random_byte_array_with_random_length();
+    session_setup.security_mode = create_random_byte_array_with_random_length();
+    session_setup.capabilities = create_random_byte_array_with_random_length();
+    session_setup.channel = create_random_byte_array_with_random_length();
+    session_setup.previous_session_id = create_random_byte_array_with_random_length();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_session_setup_flags() {
+        for _ in 0..32 {
+            let flags = sample_session_setup_flags(&SessionScenario::Binding);
+            assert_eq!(1, flags.len());
+            assert_ne!(Flags::SessionSetupBinding.unpack_byte_code(), flags);
+
+            let flags = sample_session_setup_flags(&SessionScenario::Reauthentication);
+            assert_ne!(Flags::Zero.unpack_byte_code(), flags);
+        }
+    }
+
+    #[test]
+    fn test_sample_session_id() {
+        for _ in 0..32 {
+            assert_eq!(
+                8,
+                sample_session_id(&[0x11, 0x22, 0x33, 0x44, 0, 0, 0, 0]).len()
+            );
+        }
+        assert_eq!(8, sample_session_id(&[]).len());
+    }
+}
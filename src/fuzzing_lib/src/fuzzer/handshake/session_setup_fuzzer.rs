@@ -1,9 +1,24 @@
 use super::super::create_random_byte_array_of_predefined_length;
 use super::super::create_random_byte_array_with_random_length;
+use super::kerberos_fuzzer::{
+    fuzz_kerberos_ap_req_completely_random, fuzz_kerberos_ap_req_with_predefined_values,
+    fuzz_kerberos_ap_req_with_random_fields,
+};
+use super::ntlm_authenticate_fuzzer::{
+    fuzz_ntlm_authenticate_completely_random, fuzz_ntlm_authenticate_with_predefined_values,
+    fuzz_ntlm_authenticate_with_random_fields,
+};
+use super::ntlm_negotiate_fuzzer::fuzz_ntlm_negotiate_with_predefined_values;
 use crate::builder::session_setup_authenticate_request::security_buffer;
-use crate::format::encoder::security_blob_encoder::encode_security_authentication;
+use crate::builder::session_setup_kerberos_request::build_kerberos_security_blob;
+use crate::builder::session_setup_negotiate_request::build_initial_security_blob;
+use crate::format::encoder::security_blob_encoder::{
+    encode_authenticate_blob, encode_security_authentication,
+};
 use crate::{
-    ntlmssp,
+    gss,
+    kerberos::{ApReq, ServiceTicket},
+    ntlmssp::{self, credentials::Credentials},
     smb2::{
         helper_functions::fields::SecurityMode,
         requests::session_setup::{Capabilities, Flags, SessionSetup},
@@ -11,18 +26,11 @@ use crate::{
 };
 
 const DEFAULT_INITIAL_BUFFER_OFFSET: &[u8; 2] = b"\x58\x00";
-const DEFAULT_INITIAL_BUFFER_LENGTH: &[u8; 2] = b"\x4a\x00";
 
 const DEFAULT_AUTH_BUFFER_OFFSET: &[u8; 2] = b"\x58\x00";
-const DEFAULT_AUTH_BUFFER_LENGTH: &[u8; 2] = b"\x56\x01";
-
-pub const INITIAL_SECURITY_BLOB: &[u8;74] = b"\x60\x48\x06\x06\x2b\x06\x01\x05\x05\x02\xa0\x3e\x30\x3c\xa0\x0e\
-                                             \x30\x0c\x06\x0a\x2b\x06\x01\x04\x01\x82\x37\x02\x02\x0a\xa2\x2a\
-                                             \x04\x28\x4e\x54\x4c\x4d\x53\x53\x50\x00\x01\x00\x00\x00\x05\x02\
-                                             \x00\x00\x00\x00\x00\x00\x28\x00\x00\x00\x00\x00\x00\x00\x28\x00\
-                                             \x00\x00\x06\x01\x00\x00\x00\x00\x00\x0f";
 
 /// Fuzzes the session setup 1 request with predefined values.
+/// The NTLM negotiate message in the security buffer is fuzzed with predefined values as well.
 pub fn fuzz_session_setup_negotiate_with_predefined_values() -> SessionSetup {
     let mut session_setup_request = SessionSetup::default();
 
@@ -31,16 +39,23 @@ pub fn fuzz_session_setup_negotiate_with_predefined_values() -> SessionSetup {
     session_setup_request.capabilities = Capabilities::GlobalCapDfs.unpack_byte_code();
     session_setup_request.channel = vec![0; 4];
     session_setup_request.security_buffer_offset = DEFAULT_INITIAL_BUFFER_OFFSET.to_vec();
-    session_setup_request.security_buffer_length = DEFAULT_INITIAL_BUFFER_LENGTH.to_vec();
-    session_setup_request.buffer = INITIAL_SECURITY_BLOB.to_vec();
+    session_setup_request.buffer =
+        build_initial_security_blob(fuzz_ntlm_negotiate_with_predefined_values());
+    session_setup_request.security_buffer_length = (session_setup_request.buffer.len() as u16)
+        .to_le_bytes()
+        .to_vec();
     session_setup_request.previous_session_id = vec![0; 8];
 
     session_setup_request
 }
 
 /// Fuzzes the session setup 2 request with predefined values.
+/// The NTLM authenticate message in the security buffer is built for the credentials
+/// and fuzzed with predefined values, while its GSS wrapper stays valid.
 pub fn fuzz_session_setup_authenticate_with_predefined_values(
     server_challenge_struct: ntlmssp::challenge::Challenge,
+    challenge_message: &[u8],
+    credentials: &Credentials,
 ) -> SessionSetup {
     let mut session_setup_request = SessionSetup::default();
 
@@ -48,19 +63,141 @@ pub fn fuzz_session_setup_authenticate_with_predefined_values(
     session_setup_request.security_mode = rand::random::<SecurityMode>().unpack_byte_code(1);
     session_setup_request.capabilities = Capabilities::GlobalCapDfs.unpack_byte_code();
     session_setup_request.channel = vec![0; 4];
-    session_setup_request.security_buffer_offset = DEFAULT_AUTH_BUFFER_OFFSET.to_vec();
-    session_setup_request.security_buffer_length = DEFAULT_AUTH_BUFFER_LENGTH.to_vec();
     session_setup_request.previous_session_id = vec![0; 8];
-    session_setup_request.buffer = encode_security_authentication(
-        security_buffer::build_session_setup_authenticate_request_security_buffer(
+    set_authenticate_security_buffer(
+        &mut session_setup_request,
+        fuzz_ntlm_authenticate_with_predefined_values(
             server_challenge_struct,
+            challenge_message,
+            credentials,
         ),
     );
 
     session_setup_request
 }
 
-/// Fuzz the session setup authenticate request with random fields with the size complying to the specifications.
+/// Fuzzes the session setup 2 request with random fields with the size complying to the specifications.
+/// The security buffer carries a random fields NTLM authenticate message in a valid GSS wrapper,
+/// so that the NTLM parser of the server is reached.
+pub fn fuzz_session_setup_authenticate_with_random_fields() -> SessionSetup {
+    let mut session_setup_request = SessionSetup::default();
+
+    session_setup_request.flags = create_random_byte_array_of_predefined_length(1);
+    session_setup_request.security_mode = create_random_byte_array_of_predefined_length(1);
+    session_setup_request.capabilities = create_random_byte_array_of_predefined_length(4);
+    session_setup_request.channel = create_random_byte_array_of_predefined_length(4);
+    session_setup_request.previous_session_id = create_random_byte_array_of_predefined_length(8);
+    set_authenticate_security_buffer(
+        &mut session_setup_request,
+        fuzz_ntlm_authenticate_with_random_fields(),
+    );
+
+    session_setup_request
+}
+
+/// Fuzzes the session setup 2 request with random fields of random size.
+/// The security buffer carries a completely random NTLM authenticate message in a valid GSS wrapper.
+pub fn fuzz_session_setup_authenticate_completely_random() -> SessionSetup {
+    let mut session_setup_request = SessionSetup::default();
+
+    session_setup_request.flags = create_random_byte_array_of_predefined_length(1);
+    session_setup_request.security_mode = create_random_byte_array_of_predefined_length(1);
+    session_setup_request.capabilities = create_random_byte_array_of_predefined_length(4);
+    session_setup_request.channel = create_random_byte_array_of_predefined_length(4);
+    session_setup_request.previous_session_id = create_random_byte_array_of_predefined_length(8);
+    set_authenticate_security_buffer(
+        &mut session_setup_request,
+        fuzz_ntlm_authenticate_completely_random(),
+    );
+
+    session_setup_request
+}
+
+/// Wraps the NTLM authenticate message in the NTLMSSP header and the NegTokenResp and sets it as the
+/// security buffer of the session setup request, together with the security buffer offset and length.
+pub fn set_authenticate_security_buffer(
+    session_setup_request: &mut SessionSetup,
+    authenticate: ntlmssp::authenticate::Authenticate,
+) {
+    let mut neg_token_response = gss::NegTokenResp::default();
+    neg_token_response.response_token =
+        encode_authenticate_blob(security_buffer::wrap_authenticate(authenticate));
+
+    session_setup_request.security_buffer_offset = DEFAULT_AUTH_BUFFER_OFFSET.to_vec();
+    session_setup_request.buffer = encode_security_authentication(neg_token_response);
+    session_setup_request.security_buffer_length = (session_setup_request.buffer.len() as u16)
+        .to_le_bytes()
+        .to_vec();
+}
+
+/// Fuzzes the Kerberos session setup request with predefined values.
+/// The AP-REQ in the security buffer is built for the service ticket and fuzzed with predefined values,
+/// while its SPNEGO and GSS-API wrappers stay valid.
+pub fn fuzz_session_setup_kerberos_with_predefined_values(
+    service_ticket: &ServiceTicket,
+) -> SessionSetup {
+    let mut session_setup_request = SessionSetup::default();
+
+    session_setup_request.flags = rand::random::<Flags>().unpack_byte_code();
+    session_setup_request.security_mode = rand::random::<SecurityMode>().unpack_byte_code(1);
+    session_setup_request.capabilities = Capabilities::GlobalCapDfs.unpack_byte_code();
+    session_setup_request.channel = vec![0; 4];
+    session_setup_request.previous_session_id = vec![0; 8];
+    set_kerberos_security_buffer(
+        &mut session_setup_request,
+        fuzz_kerberos_ap_req_with_predefined_values(service_ticket),
+    );
+
+    session_setup_request
+}
+
+/// Fuzzes the Kerberos session setup request with random fields with the size complying to the specifications.
+/// The security buffer carries a random fields AP-REQ in valid SPNEGO and GSS-API wrappers.
+pub fn fuzz_session_setup_kerberos_with_random_fields() -> SessionSetup {
+    let mut session_setup_request = SessionSetup::default();
+
+    session_setup_request.flags = create_random_byte_array_of_predefined_length(1);
+    session_setup_request.security_mode = create_random_byte_array_of_predefined_length(1);
+    session_setup_request.capabilities = create_random_byte_array_of_predefined_length(4);
+    session_setup_request.channel = create_random_byte_array_of_predefined_length(4);
+    session_setup_request.previous_session_id = create_random_byte_array_of_predefined_length(8);
+    set_kerberos_security_buffer(
+        &mut session_setup_request,
+        fuzz_kerberos_ap_req_with_random_fields(),
+    );
+
+    session_setup_request
+}
+
+/// Fuzzes the Kerberos session setup request with random fields of random size.
+/// The security buffer carries a completely random AP-REQ in valid SPNEGO and GSS-API wrappers.
+pub fn fuzz_session_setup_kerberos_completely_random() -> SessionSetup {
+    let mut session_setup_request = SessionSetup::default();
+
+    session_setup_request.flags = create_random_byte_array_of_predefined_length(1);
+    session_setup_request.security_mode = create_random_byte_array_of_predefined_length(1);
+    session_setup_request.capabilities = create_random_byte_array_of_predefined_length(4);
+    session_setup_request.channel = create_random_byte_array_of_predefined_length(4);
+    session_setup_request.previous_session_id = create_random_byte_array_of_predefined_length(8);
+    set_kerberos_security_buffer(
+        &mut session_setup_request,
+        fuzz_kerberos_ap_req_completely_random(),
+    );
+
+    session_setup_request
+}
+
+/// Wraps the AP-REQ in the GSS-API token and the NegTokenInit and sets it as the security buffer
+/// of the session setup request, together with the security buffer offset and length.
+pub fn set_kerberos_security_buffer(session_setup_request: &mut SessionSetup, ap_req: ApReq) {
+    session_setup_request.security_buffer_offset = DEFAULT_INITIAL_BUFFER_OFFSET.to_vec();
+    session_setup_request.buffer = build_kerberos_security_blob(&ap_req);
+    session_setup_request.security_buffer_length = (session_setup_request.buffer.len() as u16)
+        .to_le_bytes()
+        .to_vec();
+}
+
+/// Fuzz the session setup request with random fields with the size complying to the specifications.
 pub fn fuzz_session_setup_with_random_fields() -> SessionSetup {
     let mut session_setup_request = SessionSetup::default();
 
@@ -77,7 +214,7 @@ pub fn fuzz_session_setup_with_random_fields() -> SessionSetup {
     session_setup_request
 }
 
-/// Fuzz the session setup authenticate request with random fields of random size.
+/// Fuzz the session setup request with random fields of random size.
 pub fn fuzz_session_setup_completely_random() -> SessionSetup {
     let mut session_setup_request = SessionSetup::default();
 
@@ -0,0 +1,507 @@
+use rand::Rng;
+
+use crate::{
+    format::{
+        decoder::spnego_decoder::decode_der_header,
+        encoder::spnego_encoder::{encode_der_length, encode_negotiation_token, SEQUENCE_TAG},
+    },
+    fuzzer::{
+        create_random_byte_array_of_predefined_length, create_random_byte_array_with_random_length,
+        FuzzingStrategy,
+    },
+    gss::{
+        NegHints, NegotiationToken, KERBEROS_OID, MS_KERBEROS_OID, NEGOEX_OID, NTLMSSP_OID,
+        SPNEGO_OID,
+    },
+};
+
+/// A DER element of an encoded SPNEGO token, whose tag and length can be mutated
+/// independently of its content.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DerElement {
+    /// Tag (1 byte): The identifier octet with the class, the constructed bit and the tag number.
+    pub tag: u8,
+    /// Length (variable): The encoded length. If None, the DER length of the content is encoded.
+    pub length: Option<Vec<u8>>,
+    /// Content (variable): The bytes of a primitive element or the elements of a constructed element.
+    pub content: DerContent,
+    /// EndOfContents (2 bytes): Whether the content is followed by the end-of-contents octets
+    /// that terminate an element of indefinite length.
+    pub end_of_contents: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DerContent {
+    Primitive(Vec<u8>),
+    Constructed(Vec<DerElement>),
+}
+
+/// Fuzzes the GSS layer of the SPNEGO token according to the fuzzing strategy and returns
+/// the encoded token. The token of the mechanism, e.g. the NTLM message, is kept unchanged,
+/// so that it can be fuzzed independently.
+pub fn fuzz_spnego_token(token: NegotiationToken, fuzzing_strategy: &FuzzingStrategy) -> Vec<u8> {
+    match fuzzing_strategy {
+        FuzzingStrategy::Predefined => fuzz_spnego_token_with_predefined_values(token),
+        FuzzingStrategy::RandomFields => fuzz_spnego_token_with_random_fields(token),
+        FuzzingStrategy::CompletelyRandom => fuzz_spnego_token_completely_random(token),
+    }
+}
+
+/// Fuzzes the SPNEGO token with predefined values. The mechanism list is mutated with
+/// unknown, duplicated and truncated OIDs, the mechListMIC is sent without the token of the
+/// mechanism, and the DER encoding is malformed with oversized and indefinite lengths,
+/// wrong tag classes and deeply nested constructed elements.
+pub fn fuzz_spnego_token_with_predefined_values(token: NegotiationToken) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    let token = match token {
+        NegotiationToken::NegTokenInit(mut init) => {
+            if rng.gen_bool(0.5) {
+                init.mech_types = fuzz_mech_types(init.mech_types);
+            }
+            if rng.gen_bool(0.25) {
+                init.req_flags = sample_req_flags();
+            }
+            if rng.gen_bool(0.25) {
+                init.mech_list_mic = create_random_byte_array_of_predefined_length(16);
+                if rng.gen_bool(0.5) {
+                    init.mech_token = Vec::new();
+                }
+            }
+            NegotiationToken::NegTokenInit(init)
+        }
+        NegotiationToken::NegTokenInit2(mut init2) => {
+            if rng.gen_bool(0.5) {
+                init2.mech_types = fuzz_mech_types(init2.mech_types);
+            }
+            if rng.gen_bool(0.25) {
+                init2.req_flags = sample_req_flags();
+            }
+            if rng.gen_bool(0.25) {
+                init2.neg_hints = sample_neg_hints();
+            }
+            if rng.gen_bool(0.25) {
+                init2.mech_list_mic = create_random_byte_array_of_predefined_length(16);
+                if rng.gen_bool(0.5) {
+                    init2.mech_token = Vec::new();
+                }
+            }
+            NegotiationToken::NegTokenInit2(init2)
+        }
+        NegotiationToken::NegTokenResp(mut resp) => {
+            if rng.gen_bool(0.5) {
+                resp.state = sample_neg_state();
+            }
+            if rng.gen_bool(0.25) {
+                resp.supported_mech = sample_oid();
+            }
+            if rng.gen_bool(0.25) {
+                resp.mech_list_mic = create_random_byte_array_of_predefined_length(16);
+                if rng.gen_bool(0.5) {
+                    resp.response_token = Vec::new();
+                }
+            }
+            NegotiationToken::NegTokenResp(resp)
+        }
+    };
+
+    let mut tree = parse_der_tree(&encode_negotiation_token(token));
+    for _ in 0..rng.gen_range(0..=3) {
+        let mut index = rng.gen_range(0..count_der_elements(&tree));
+        if let Some(element) = select_der_element(&mut tree, &mut index) {
+            match rng.gen_range(0..=2) {
+                0 => malform_length(element),
+                1 => malform_tag(element),
+                _ => nest_element(element, rng.gen_range(16..=256)),
+            }
+        }
+    }
+
+    serialize_der_tree(&tree)
+}
+
+/// Fuzzes the mechanism list. Besides unknown, duplicated and truncated OIDs, an empty list,
+/// a long list and a list that prefers Kerberos and NEGOEX over NTLMSSP are chosen.
+pub fn fuzz_mech_types(mut mech_types: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=5) {
+        0 if !mech_types.is_empty() => {
+            let index = rng.gen_range(0..mech_types.len());
+            let duplicate = mech_types[index].clone();
+            mech_types.insert(rng.gen_range(0..=mech_types.len()), duplicate);
+        }
+        1 => mech_types.insert(rng.gen_range(0..=mech_types.len()), sample_oid()),
+        2 => mech_types.clear(),
+        3 => {
+            for _ in 0..rng.gen_range(64..512) {
+                mech_types.push(sample_oid());
+            }
+        }
+        4 => {
+            mech_types.insert(0, NEGOEX_OID.to_vec());
+            mech_types.insert(0, KERBEROS_OID.to_vec());
+            mech_types.insert(0, MS_KERBEROS_OID.to_vec());
+        }
+        _ => {
+            let truncated_oid = NTLMSSP_OID[..rng.gen_range(0..NTLMSSP_OID.len())].to_vec();
+            mech_types.insert(0, truncated_oid);
+        }
+    }
+
+    mech_types
+}
+
+/// Samples an OID. Besides the known mechanisms, unknown OIDs, OIDs that end within an arc,
+/// OIDs with non-minimal arcs and the SPNEGO OID itself are chosen.
+pub fn sample_oid() -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=6) {
+        0 => NTLMSSP_OID.to_vec(),
+        1 => KERBEROS_OID.to_vec(),
+        2 => SPNEGO_OID.to_vec(),
+        3 => {
+            let mut oid = b"\x2b\x06\x01\x04\x01\x82\x37".to_vec();
+            oid.append(&mut create_random_byte_array_of_predefined_length(
+                rng.gen_range(1..8),
+            ));
+            oid
+        }
+        4 => b"\x2b\x06\x01\x04\x01\x82".to_vec(),
+        5 => b"\x2b\x80\x80\x80\x06\x01\x05\x05\x02".to_vec(),
+        _ => vec![0xff; rng.gen_range(1..256)],
+    }
+}
+
+/// Samples the ReqFlags BIT STRING. Besides single flags, all flags, no flags and
+/// invalid numbers of unused bits are chosen.
+pub fn sample_req_flags() -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=3) {
+        0 => vec![0x01, 1 << rng.gen_range(1..8)],
+        1 => vec![0x01, 0xfe],
+        2 => vec![0x00],
+        _ => vec![rng.gen_range(8..=0xff), rand::random::<u8>()],
+    }
+}
+
+/// Samples the NegState. Besides the defined states, undefined and multi-byte states are chosen.
+pub fn sample_neg_state() -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=3) {
+        0 => vec![rng.gen_range(0..=3)],
+        1 => vec![rng.gen_range(4..=0xff)],
+        2 => vec![0x00, 0x01],
+        _ => vec![0xff; rng.gen_range(2..16)],
+    }
+}
+
+/// Samples the NegHints. Besides the name of the RFC 4178 placeholder, overlong,
+/// non-ASCII and format string names as well as a random address are chosen.
+pub fn sample_neg_hints() -> NegHints {
+    let mut rng = rand::thread_rng();
+    let mut neg_hints = NegHints::default();
+
+    neg_hints.hint_name = match rng.gen_range(0..=3) {
+        0 => b"not_defined_in_RFC4178@please_ignore".to_vec(),
+        1 => vec![b'A'; rng.gen_range(256..4096)],
+        2 => create_random_byte_array_of_predefined_length(rng.gen_range(1..64)),
+        _ => b"%s%s%n%x".to_vec(),
+    };
+    if rng.gen_bool(0.5) {
+        neg_hints.hint_address =
+            create_random_byte_array_of_predefined_length(rng.gen_range(1..64));
+    }
+
+    neg_hints
+}
+
+/// Malforms the length of the element. Besides oversized lengths in the short and in the long form,
+/// non-minimal long forms, lengths with too many length bytes and the indefinite form are chosen.
+pub fn malform_length(element: &mut DerElement) {
+    let mut rng = rand::thread_rng();
+    let content_length = serialize_der_content(&element.content).len();
+
+    element.length = Some(match rng.gen_range(0..=4) {
+        0 => encode_der_length(content_length + rng.gen_range(1..=0x100)),
+        1 => b"\x84\xff\xff\xff\xff".to_vec(),
+        2 => {
+            let mut length = vec![0x84];
+            length.extend_from_slice(&(content_length as u32).to_be_bytes());
+            length
+        }
+        3 => {
+            let mut length = vec![0x89];
+            length.append(&mut vec![0xff; 9]);
+            length
+        }
+        _ => {
+            element.end_of_contents = true;
+            vec![0x80]
+        }
+    });
+}
+
+/// Malforms the tag of the element. Besides other tag classes, the constructed bit is flipped
+/// or the tag number is replaced with a neighbouring universal tag, e.g. a SET instead of a SEQUENCE.
+pub fn malform_tag(element: &mut DerElement) {
+    let mut rng = rand::thread_rng();
+
+    element.tag = match rng.gen_range(0..=2) {
+        0 => (element.tag & 0x3f) | ((((element.tag >> 6) + rng.gen_range(1..4)) & 0x03) << 6),
+        1 => element.tag ^ 0x20,
+        _ => (element.tag & 0xe0) | ((element.tag & 0x1f) ^ 0x01),
+    };
+}
+
+/// Wraps the element in the given number of constructed elements, which repeat the tag
+/// of the element or are SEQUENCEs.
+pub fn nest_element(element: &mut DerElement, depth: u32) {
+    let tag = if element.tag & 0x20 != 0 && rand::thread_rng().gen_bool(0.5) {
+        element.tag
+    } else {
+        SEQUENCE_TAG
+    };
+
+    for _ in 0..depth {
+        let content = std::mem::replace(&mut element.content, DerContent::Constructed(Vec::new()));
+        let inner = DerElement {
+            tag: element.tag,
+            length: element.length.take(),
+            content,
+            end_of_contents: element.end_of_contents,
+        };
+        element.tag = tag;
+        element.end_of_contents = false;
+        element.content = DerContent::Constructed(vec![inner]);
+    }
+}
+
+/// Fuzzes the SPNEGO token with random values that comply to the size restrictions of the fields.
+/// The DER encoding stays valid.
+pub fn fuzz_spnego_token_with_random_fields(token: NegotiationToken) -> Vec<u8> {
+    encode_negotiation_token(randomize_token_fields(token))
+}
+
+/// Fuzzes the SPNEGO token with random values of random length. Besides the fields of the token,
+/// the tags and lengths of the DER elements are replaced with random bytes.
+pub fn fuzz_spnego_token_completely_random(token: NegotiationToken) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+    let mut tree = parse_der_tree(&encode_negotiation_token(randomize_token_fields(token)));
+
+    for element_index in 0..count_der_elements(&tree) {
+        let mut index = element_index;
+        if let Some(element) = select_der_element(&mut tree, &mut index) {
+            if rng.gen_bool(0.5) {
+                element.tag = rand::random::<u8>();
+            }
+            if rng.gen_bool(0.5) {
+                element.length = Some(create_random_byte_array_of_predefined_length(
+                    rng.gen_range(1..16),
+                ));
+            }
+        }
+    }
+
+    serialize_der_tree(&tree)
+}
+
+/// Replaces the fields of the token with random values. The token of the mechanism is kept.
+pub fn randomize_token_fields(token: NegotiationToken) -> NegotiationToken {
+    let mut rng = rand::thread_rng();
+    let mech_types = (0..rng.gen_range(1..8))
+        .map(|_| create_random_byte_array_of_predefined_length(rng.gen_range(1..16)))
+        .collect();
+
+    match token {
+        NegotiationToken::NegTokenInit(mut init) => {
+            init.mech_types = mech_types;
+            init.req_flags = create_random_byte_array_of_predefined_length(2);
+            init.mech_list_mic = create_random_byte_array_of_predefined_length(16);
+            NegotiationToken::NegTokenInit(init)
+        }
+        NegotiationToken::NegTokenInit2(mut init2) => {
+            init2.mech_types = mech_types;
+            init2.req_flags = create_random_byte_array_of_predefined_length(2);
+            init2.neg_hints.hint_name = create_random_byte_array_with_random_length();
+            init2.neg_hints.hint_address = create_random_byte_array_of_predefined_length(16);
+            init2.mech_list_mic = create_random_byte_array_of_predefined_length(16);
+            NegotiationToken::NegTokenInit2(init2)
+        }
+        NegotiationToken::NegTokenResp(mut resp) => {
+            resp.state = create_random_byte_array_of_predefined_length(1);
+            resp.supported_mech = create_random_byte_array_of_predefined_length(10);
+            resp.mech_list_mic = create_random_byte_array_of_predefined_length(16);
+            NegotiationToken::NegTokenResp(resp)
+        }
+    }
+}
+
+/// Parses the encoded token into a tree of DER elements. Elements with the constructed bit
+/// whose content consists of elements are constructed, all others are primitive.
+/// A buffer that is no DER element is kept as the content of a primitive element.
+pub fn parse_der_tree(buffer: &[u8]) -> DerElement {
+    let (tag, content) = match decode_der_header(buffer) {
+        Some((tag, content_start, content_end)) => (tag, &buffer[content_start..content_end]),
+        None => (
+            buffer.first().cloned().unwrap_or_default(),
+            buffer.get(1..).unwrap_or_default(),
+        ),
+    };
+    let children = if tag & 0x20 != 0 {
+        parse_der_elements(content)
+    } else {
+        None
+    };
+
+    DerElement {
+        tag,
+        length: None,
+        content: match children {
+            Some(children) => DerContent::Constructed(children),
+            None => DerContent::Primitive(content.to_vec()),
+        },
+        end_of_contents: false,
+    }
+}
+
+/// Parses the consecutive elements of the content. Returns None if the content
+/// does not consist of elements.
+pub fn parse_der_elements(mut content: &[u8]) -> Option<Vec<DerElement>> {
+    let mut elements = Vec::new();
+
+    while !content.is_empty() {
+        let (_, _, content_end) = decode_der_header(content)?;
+        elements.push(parse_der_tree(&content[..content_end]));
+        content = &content[content_end..];
+    }
+
+    Some(elements)
+}
+
+/// Counts the elements of the tree, including the root.
+pub fn count_der_elements(element: &DerElement) -> usize {
+    match &element.content {
+        DerContent::Primitive(_) => 1,
+        DerContent::Constructed(children) => {
+            1 + children.iter().map(count_der_elements).sum::<usize>()
+        }
+    }
+}
+
+/// Selects the element with the given index in the pre-order of the tree.
+pub fn select_der_element<'a>(
+    element: &'a mut DerElement,
+    index: &mut usize,
+) -> Option<&'a mut DerElement> {
+    if *index == 0 {
+        return Some(element);
+    }
+    *index -= 1;
+
+    if let DerContent::Constructed(children) = &mut element.content {
+        for child in children.iter_mut() {
+            if let Some(selected) = select_der_element(child, index) {
+                return Some(selected);
+            }
+        }
+    }
+
+    None
+}
+
+/// Serializes the tree of DER elements with the mutated tags and lengths.
+pub fn serialize_der_tree(element: &DerElement) -> Vec<u8> {
+    let mut content = serialize_der_content(&element.content);
+    let mut serialized = vec![element.tag];
+    match &element.length {
+        Some(length) => serialized.extend_from_slice(length),
+        None => serialized.append(&mut encode_der_length(content.len())),
+    }
+    serialized.append(&mut content);
+    if element.end_of_contents {
+        serialized.extend_from_slice(&[0, 0]);
+    }
+
+    serialized
+}
+
+/// Serializes the content of a DER element.
+pub fn serialize_der_content(content: &DerContent) -> Vec<u8> {
+    match content {
+        DerContent::Primitive(bytes) => bytes.clone(),
+        DerContent::Constructed(children) => children.iter().flat_map(serialize_der_tree).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        format::decoder::spnego_decoder::decode_negotiation_token,
+        gss::{NegTokenInit, NegTokenResp},
+    };
+
+    fn build_sample_tokens() -> Vec<NegotiationToken> {
+        let mut init = NegTokenInit::default();
+        init.mech_types = vec![NTLMSSP_OID.to_vec()];
+        init.mech_token = b"NTLMSSP\x00\x01\x00\x00\x00".to_vec();
+
+        let mut resp = NegTokenResp::default();
+        resp.response_token = vec![0x42; 0x146];
+
+        vec![
+            NegotiationToken::NegTokenInit(init),
+            NegotiationToken::NegTokenResp(resp),
+        ]
+    }
+
+    #[test]
+    fn test_parse_and_serialize_der_tree() {
+        for token in build_sample_tokens() {
+            let encoded = encode_negotiation_token(token);
+            let tree = parse_der_tree(&encoded);
+
+            assert_eq!(encoded, serialize_der_tree(&tree));
+            assert!(count_der_elements(&tree) >= 4);
+        }
+    }
+
+    #[test]
+    fn test_fuzz_spnego_token_keeps_mechanism_token() {
+        for token in build_sample_tokens() {
+            let mechanism_token = match &token {
+                NegotiationToken::NegTokenInit(init) => init.mech_token.clone(),
+                NegotiationToken::NegTokenInit2(init2) => init2.mech_token.clone(),
+                NegotiationToken::NegTokenResp(resp) => resp.response_token.clone(),
+            };
+
+            for _ in 0..32 {
+                let fuzzed = fuzz_spnego_token_with_random_fields(token.clone());
+                let decoded_token = match decode_negotiation_token(&fuzzed) {
+                    Some(NegotiationToken::NegTokenInit(init)) => init.mech_token,
+                    Some(NegotiationToken::NegTokenResp(resp)) => resp.response_token,
+                    _ => panic!("Invalid SPNEGO token."),
+                };
+                assert_eq!(mechanism_token, decoded_token);
+
+                fuzz_spnego_token_with_predefined_values(token.clone());
+                fuzz_spnego_token_completely_random(token.clone());
+            }
+        }
+    }
+
+    #[test]
+    fn test_nest_element() {
+        let mut element = parse_der_tree(b"\x04\x01\x42");
+        nest_element(&mut element, 2);
+
+        assert_eq!(
+            b"\x30\x05\x30\x03\x04\x01\x42".to_vec(),
+            serialize_der_tree(&element)
+        );
+    }
+}
@@ -0,0 +1,340 @@
+use rand::Rng;
+
+use crate::smb2::header::{Flags, SyncHeader};
+
+use super::create_random_byte_array_of_predefined_length;
+use super::create_random_byte_array_with_random_length;
+use super::FuzzingStrategy;
+
+/// The message id that is reserved for unsolicited oplock break notifications
+/// and must not be used by the client.
+pub const RESERVED_MESSAGE_ID: u64 = u64::MAX;
+
+/// Status values that are sampled for the channel sequence and reserved fields,
+/// which the SMB 2.0.2 and SMB 2.1 dialects interpret as the status of the request.
+const STATUS_VALUES: [u32; 4] = [0x00000103, 0x80000005, 0xc0000022, 0xc000000d];
+
+/// Defines the async id with which requests are sent if the ASYNC_COMMAND flag is forced.
+///
+/// *Fixed*:
+///     - Every request carries the given async id.
+///
+/// *Predefined*:
+///     - The async id is sampled from predefined values for each request.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum AsyncIdStrategy {
+    Fixed(u64),
+    Predefined,
+}
+
+impl AsyncIdStrategy {
+    /// Maps the optional user input to an async id strategy.
+    /// Without a value or with the value "random", the async id is sampled.
+    pub fn map_string_to_async_id_strategy(async_id: Option<&String>) -> AsyncIdStrategy {
+        match async_id.map(|id| id.as_str()) {
+            None | Some("random") => AsyncIdStrategy::Predefined,
+            Some(id) => match id.strip_prefix("0x") {
+                Some(hex_id) => AsyncIdStrategy::Fixed(
+                    u64::from_str_radix(hex_id, 16).expect("Invalid async id."),
+                ),
+                None => AsyncIdStrategy::Fixed(id.parse().expect("Invalid async id.")),
+            },
+        }
+    }
+
+    /// Returns the 8 byte async id according to the strategy.
+    pub fn unpack_async_id(&self) -> Vec<u8> {
+        match self {
+            AsyncIdStrategy::Fixed(async_id) => async_id.to_le_bytes().to_vec(),
+            AsyncIdStrategy::Predefined => sample_async_id(),
+        }
+    }
+}
+
+/// Samples the async id of a request that the client sends with the ASYNC_COMMAND flag.
+/// Besides zero, small ids that collide with ids the server hands out, the maximum id,
+/// ids with only the upper bits set and random ids are chosen.
+pub fn sample_async_id() -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    let async_id: u64 = match rng.gen_range(0..=4) {
+        0 => 0,
+        1 => rng.gen_range(1..=16),
+        2 => u64::MAX,
+        3 => 1 << rng.gen_range(32..64),
+        _ => rng.gen(),
+    };
+
+    async_id.to_le_bytes().to_vec()
+}
+
+/// Fuzzes the async id of a request that is sent with an async header according to the strategy.
+/// The predefined strategy samples the async id in half of the cases and keeps it otherwise.
+pub fn fuzz_async_id(async_id: Vec<u8>, strategy: &FuzzingStrategy) -> Vec<u8> {
+    match strategy {
+        FuzzingStrategy::Predefined => {
+            if rand::thread_rng().gen_bool(0.5) {
+                sample_async_id()
+            } else {
+                async_id
+            }
+        }
+        FuzzingStrategy::RandomFields => create_random_byte_array_of_predefined_length(8),
+        FuzzingStrategy::CompletelyRandom => create_random_byte_array_with_random_length(),
+    }
+}
+
+/// Fuzzes the sync header of a request with predefined values.
+/// Each field is mutated in half of the cases, while the protocol id, the command
+/// and the next command are kept so that the request body is still interpreted.
+pub fn fuzz_sync_header_with_predefined_values(mut header: SyncHeader) -> SyncHeader {
+    let mut rng = rand::thread_rng();
+
+    if rng.gen_bool(0.5) {
+        header.generic.structure_size = sample_structure_size();
+    }
+    if rng.gen_bool(0.5) {
+        header.generic.credit_charge = sample_credit_charge();
+    }
+    if rng.gen_bool(0.5) {
+        let (channel_sequence, reserved) = sample_channel_sequence_and_status();
+        header.generic.channel_sequence = channel_sequence;
+        header.generic.reserved = reserved;
+    }
+    if rng.gen_bool(0.5) {
+        header.generic.flags = sample_header_flags(header.generic.flags);
+    }
+    if rng.gen_bool(0.5) {
+        header.generic.message_id = sample_message_id(header.generic.message_id);
+    }
+    if rng.gen_bool(0.5) {
+        header.tree_id = sample_session_or_tree_id(header.tree_id);
+    }
+    if rng.gen_bool(0.5) {
+        header.session_id = sample_session_or_tree_id(header.session_id);
+    }
+
+    header
+}
+
+/// Samples the structure size of the header. Besides the valid size of 64,
+/// sizes just below and above, zero and the maximum are chosen.
+pub fn sample_structure_size() -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    let structure_size: u16 = match rng.gen_range(0..=4) {
+        0 => 64,
+        1 => 63,
+        2 => 65,
+        3 => 0,
+        _ => u16::MAX,
+    };
+
+    structure_size.to_le_bytes().to_vec()
+}
+
+/// Samples the credit charge. Besides the charges of zero and one, charges that
+/// exceed the granted credits and the maximum charge are chosen.
+pub fn sample_credit_charge() -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    let credit_charge: u16 = match rng.gen_range(0..=3) {
+        0 => 0,
+        1 => 1,
+        2 => rng.gen_range(2..=8192),
+        _ => u16::MAX,
+    };
+
+    credit_charge.to_le_bytes().to_vec()
+}
+
+/// Samples the channel sequence and reserved fields. Either the channel sequence
+/// is altered, or both fields together carry a status value as in the 2.x dialects.
+pub fn sample_channel_sequence_and_status() -> (Vec<u8>, Vec<u8>) {
+    let mut rng = rand::thread_rng();
+
+    let status: u32 = match rng.gen_range(0..=3) {
+        0 => rng.gen_range(1..=u16::MAX as u32),
+        1 => u16::MAX as u32,
+        2 => STATUS_VALUES[rng.gen_range(0..STATUS_VALUES.len())],
+        _ => rng.gen(),
+    };
+    let status = status.to_le_bytes();
+
+    (status[..2].to_vec(), status[2..].to_vec())
+}
+
+/// Samples the header flags. The flags are extended by the SIGNED flag without a signature,
+/// by a priority value or by the REPLAY_OPERATION flag, or replaced by a random combination of flags.
+pub fn sample_header_flags(mut flags: Vec<u8>) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    if flags.is_empty() {
+        flags = Flags::NoFlags.unpack_byte_code();
+    }
+    match rng.gen_range(0..=4) {
+        0 => flags[0] |= Flags::Signed.unpack_byte_code()[0],
+        1 => {
+            flags[0] = (flags[0] & !Flags::PriorityMask.unpack_byte_code()[0])
+                | (rng.gen_range(0..=7) << 4)
+        }
+        2 => flags[0] |= Flags::ReplayOperation.unpack_byte_code()[0],
+        3 => {
+            flags = Flags::return_sum_of_chosen_flags(
+                (0..rng.gen_range(1..=4))
+                    .map(|_| rand::random::<Flags>())
+                    .collect(),
+            )
+        }
+        _ => flags = rng.gen::<u32>().to_le_bytes().to_vec(),
+    }
+
+    flags
+}
+
+/// Samples the message id. Besides the given id, a reused id, an id far outside of the
+/// granted credit window and the id reserved for oplock break notifications are chosen.
+pub fn sample_message_id(message_id: Vec<u8>) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    let mut id_bytes = [0u8; 8];
+    let length = message_id.len().min(8);
+    id_bytes[..length].copy_from_slice(&message_id[..length]);
+    let id = u64::from_le_bytes(id_bytes);
+
+    let sampled_id: u64 = match rng.gen_range(0..=4) {
+        0 => id,
+        1 => id.saturating_sub(rng.gen_range(1..=4)),
+        2 => id.wrapping_add(rng.gen_range(0x10000..=0x1000000)),
+        3 => RESERVED_MESSAGE_ID,
+        _ => rng.gen(),
+    };
+
+    sampled_id.to_le_bytes().to_vec()
+}
+
+/// Samples the session or tree id. Besides the given id, a stale id that differs
+/// in the last byte, zero, the maximum and a foreign random id are chosen.
+pub fn sample_session_or_tree_id(id: Vec<u8>) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    let length = id.len();
+    match rng.gen_range(0..=4) {
+        0 => id,
+        1 => {
+            let mut stale_id = id;
+            if let Some(byte) = stale_id.last_mut() {
+                *byte = byte.wrapping_add(rng.gen_range(1..=u8::MAX));
+            }
+            stale_id
+        }
+        2 => vec![0; length],
+        3 => vec![0xff; length],
+        _ => create_random_byte_array_of_predefined_length(length as u32),
+    }
+}
+
+/// Fuzzes the sync header of a request with random values that comply to the size restrictions
+/// of the fields. The protocol id and the command are kept.
+pub fn fuzz_sync_header_with_random_fields(mut header: SyncHeader) -> SyncHeader {
+    header.generic.structure_size = create_random_byte_array_of_predefined_length(2);
+    header.generic.credit_charge = create_random_byte_array_of_predefined_length(2);
+    header.generic.channel_sequence = create_random_byte_array_of_predefined_length(2);
+    header.generic.reserved = create_random_byte_array_of_predefined_length(2);
+    header.generic.credit = create_random_byte_array_of_predefined_length(2);
+    header.generic.flags = create_random_byte_array_of_predefined_length(4);
+    header.generic.next_command = create_random_byte_array_of_predefined_length(4);
+    header.generic.message_id = create_random_byte_array_of_predefined_length(8);
+    header.reserved = create_random_byte_array_of_predefined_length(4);
+    header.tree_id = create_random_byte_array_of_predefined_length(4);
+    header.session_id = create_random_byte_array_of_predefined_length(8);
+    header.signature = create_random_byte_array_of_predefined_length(16);
+
+    header
+}
+
+/// Fuzzes the sync header of a request with random values of random length.
+/// The protocol id and the command are kept.
+pub fn fuzz_sync_header_completely_random(mut header: SyncHeader) -> SyncHeader {
+    header.generic.structure_size = create_random_byte_array_with_random_length();
+    header.generic.credit_charge = create_random_byte_array_with_random_length();
+    header.generic.channel_sequence = create_random_byte_array_with_random_length();
+    header.generic.reserved = create_random_byte_array_with_random_length();
+    header.generic.credit = create_random_byte_array_with_random_length();
+    header.generic.flags = create_random_byte_array_with_random_length();
+    header.generic.next_command = create_random_byte_array_with_random_length();
+    header.generic.message_id = create_random_byte_array_with_random_length();
+    header.reserved = create_random_byte_array_with_random_length();
+    header.tree_id = create_random_byte_array_with_random_length();
+    header.session_id = create_random_byte_array_with_random_length();
+    header.signature = create_random_byte_array_with_random_length();
+
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_string_to_async_id_strategy() {
+        assert_eq!(
+            AsyncIdStrategy::Predefined,
+            AsyncIdStrategy::map_string_to_async_id_strategy(None)
+        );
+        assert_eq!(
+            AsyncIdStrategy::Fixed(42),
+            AsyncIdStrategy::map_string_to_async_id_strategy(Some(&String::from("42")))
+        );
+        assert_eq!(
+            AsyncIdStrategy::Fixed(0xff),
+            AsyncIdStrategy::map_string_to_async_id_strategy(Some(&String::from("0xff")))
+        );
+        assert_eq!(
+            vec![0xff, 0, 0, 0, 0, 0, 0, 0],
+            AsyncIdStrategy::Fixed(0xff).unpack_async_id()
+        );
+    }
+
+    #[test]
+    fn test_fuzz_sync_header_with_predefined_values() {
+        let header = crate::builder::build_sync_header(
+            crate::smb2::header::Commands::Create,
+            1,
+            1,
+            Some(vec![1; 4]),
+            Some(vec![2; 8]),
+            4,
+        );
+
+        for _ in 0..32 {
+            let fuzzed_header = fuzz_sync_header_with_predefined_values(header.clone());
+
+            assert_eq!(
+                header.generic.protocol_id,
+                fuzzed_header.generic.protocol_id
+            );
+            assert_eq!(header.generic.command, fuzzed_header.generic.command);
+            assert_eq!(4, fuzzed_header.generic.flags.len());
+            assert_eq!(8, fuzzed_header.generic.message_id.len());
+            assert_eq!(4, fuzzed_header.tree_id.len());
+            assert_eq!(8, fuzzed_header.session_id.len());
+        }
+    }
+
+    #[test]
+    fn test_fuzz_async_id() {
+        let async_id = vec![8, 0, 0, 0, 0, 0, 0, 0];
+
+        for _ in 0..32 {
+            assert_eq!(
+                8,
+                fuzz_async_id(async_id.clone(), &FuzzingStrategy::Predefined).len()
+            );
+            assert_eq!(
+                8,
+                fuzz_async_id(async_id.clone(), &FuzzingStrategy::RandomFields).len()
+            );
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use rand::Rng;
+
+use crate::smb2::requests::lock::{Lock, LockElement, LockFlags};
+
+use super::create_random_byte_array_of_predefined_length;
+use super::create_random_byte_array_with_random_length;
+
+/// The maximum number of lock elements a fuzzed lock request carries.
+pub const MAX_LOCK_ELEMENTS: usize = 16;
+
+/// Fuzzes the lock request with predefined values.
+/// The lock elements are chosen from byte range sequences that stress the server's
+/// range arithmetic: overlapping ranges, zero-length locks, ranges wrapping at u64::MAX
+/// and unlocks of ranges that were never locked. The lock count is occasionally
+/// inconsistent with the actual number of lock elements.
+pub fn fuzz_lock_with_predefined_values(file_id: Vec<u8>) -> Lock {
+    let mut lock_request = Lock::default();
+    let mut rng = rand::thread_rng();
+
+    lock_request.lock_sequence = Lock::build_lock_sequence(rng.gen(), rng.gen_range(0..=64));
+    lock_request.file_id = file_id;
+    lock_request.locks = match rng.gen_range(0..=3) {
+        0 => build_overlapping_locks(),
+        1 => build_zero_length_locks(),
+        2 => build_wrapping_locks(),
+        _ => build_unlocks_without_lock(),
+    };
+    lock_request.lock_count = sample_lock_count(lock_request.locks.len());
+
+    lock_request
+}
+
+/// Builds lock elements whose ranges overlap each other.
+pub fn build_overlapping_locks() -> Vec<LockElement> {
+    let mut rng = rand::thread_rng();
+    let offset: u64 = rng.gen_range(0..0x10000);
+    let length: u64 = rng.gen_range(2..0x1000);
+
+    (0..rng.gen_range(2..MAX_LOCK_ELEMENTS))
+        .map(|_| {
+            LockElement::from_range(
+                offset + rng.gen_range(0..length),
+                length,
+                sample_valid_lock_flags(),
+            )
+        })
+        .collect()
+}
+
+/// Builds lock elements with a length of zero.
+pub fn build_zero_length_locks() -> Vec<LockElement> {
+    let mut rng = rand::thread_rng();
+
+    (0..rng.gen_range(1..MAX_LOCK_ELEMENTS))
+        .map(|_| LockElement::from_range(rng.gen(), 0, sample_valid_lock_flags()))
+        .collect()
+}
+
+/// Builds lock elements whose ranges end at or wrap around u64::MAX.
+pub fn build_wrapping_locks() -> Vec<LockElement> {
+    let mut rng = rand::thread_rng();
+
+    (0..rng.gen_range(1..MAX_LOCK_ELEMENTS))
+        .map(|_| {
+            let offset = u64::MAX - rng.gen_range(0..0x1000);
+            let length = (u64::MAX - offset) + rng.gen_range(0..=0x1000);
+            LockElement::from_range(offset, length, sample_valid_lock_flags())
+        })
+        .collect()
+}
+
+/// Builds unlock elements for ranges that were never locked.
+pub fn build_unlocks_without_lock() -> Vec<LockElement> {
+    let mut rng = rand::thread_rng();
+
+    (0..rng.gen_range(1..MAX_LOCK_ELEMENTS))
+        .map(|_| LockElement::from_range(rng.gen(), rng.gen(), vec![LockFlags::Unlock]))
+        .collect()
+}
+
+/// Samples one of the flag combinations that are valid for lock elements.
+pub fn sample_valid_lock_flags() -> Vec<LockFlags> {
+    match rand::thread_rng().gen_range(0..=4) {
+        0 => vec![LockFlags::SharedLock],
+        1 => vec![LockFlags::ExclusiveLock],
+        2 => vec![LockFlags::SharedLock, LockFlags::FailImmediately],
+        3 => vec![LockFlags::ExclusiveLock, LockFlags::FailImmediately],
+        _ => vec![LockFlags::Unlock],
+    }
+}
+
+/// Samples the lock count for the given number of lock elements.
+/// In half of the cases, the lock count does not match the actual number of elements.
+pub fn sample_lock_count(number_of_locks: usize) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    let lock_count = if rng.gen_bool(0.5) {
+        number_of_locks as u16
+    } else {
+        match rng.gen_range(0..=2) {
+            0 => 0,
+            1 => number_of_locks as u16 + rng.gen_range(1..=MAX_LOCK_ELEMENTS as u16),
+            _ => u16::MAX,
+        }
+    };
+
+    lock_count.to_le_bytes().to_vec()
+}
+
+/// Fuzzes the lock request with random values that comply to the size restrictions of certain fields.
+pub fn fuzz_lock_with_random_fields() -> Lock {
+    let mut lock_request = Lock::default();
+
+    lock_request.structure_size = create_random_byte_array_of_predefined_length(2);
+    lock_request.lock_count = create_random_byte_array_of_predefined_length(2);
+    lock_request.lock_sequence = create_random_byte_array_of_predefined_length(4);
+    lock_request.file_id = create_random_byte_array_of_predefined_length(16);
+
+    for _ in 0..rand::thread_rng().gen_range(0..MAX_LOCK_ELEMENTS) {
+        let mut lock_element = LockElement::default();
+        lock_element.offset = create_random_byte_array_of_predefined_length(8);
+        lock_element.length = create_random_byte_array_of_predefined_length(8);
+        lock_element.flags = create_random_byte_array_of_predefined_length(4);
+        lock_element.reserved = create_random_byte_array_of_predefined_length(4);
+        lock_request.locks.push(lock_element);
+    }
+
+    lock_request
+}
+
+/// Fuzzes the lock request with random values of random length.
+pub fn fuzz_lock_completely_random() -> Lock {
+    let mut lock_request = Lock::default();
+
+    lock_request.structure_size = create_random_byte_array_with_random_length();
+    lock_request.lock_count = create_random_byte_array_with_random_length();
+    lock_request.lock_sequence = create_random_byte_array_with_random_length();
+    lock_request.file_id = create_random_byte_array_with_random_length();
+
+    for _ in 0..rand::thread_rng().gen_range(0..MAX_LOCK_ELEMENTS) {
+        let mut lock_element = LockElement::default();
+        lock_element.offset = create_random_byte_array_with_random_length();
+        lock_element.length = create_random_byte_array_with_random_length();
+        lock_element.flags = create_random_byte_array_with_random_length();
+        lock_element.reserved = create_random_byte_array_with_random_length();
+        lock_request.locks.push(lock_element);
+    }
+
+    lock_request
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::convert_byte_array_to_int;
+    use std::convert::TryInto;
+
+    #[test]
+    fn test_build_wrapping_locks() {
+        for lock in build_wrapping_locks() {
+            let offset = u64::from_le_bytes(lock.offset[..].try_into().unwrap());
+            let length = u64::from_le_bytes(lock.length[..].try_into().unwrap());
+
+            assert!(offset
+                .checked_add(length)
+                .map_or(true, |end| end == u64::MAX));
+        }
+    }
+
+    #[test]
+    fn test_fuzz_lock_with_predefined_values() {
+        let lock_request = fuzz_lock_with_predefined_values(vec![0; 16]);
+
+        assert!(!lock_request.locks.is_empty());
+        assert_eq!(2, lock_request.lock_count.len());
+        assert!(convert_byte_array_to_int(lock_request.lock_sequence, false) >> 4 <= 64);
+    }
+}
@@ -1,14 +1,36 @@
 use rand::Rng;
 
 use crate::{
-    networking::state_transition_engine::State,
-    smb2::requests::{echo::Echo, RequestType},
+    crypto::signing::SignatureViolation,
+    fuzzer::{
+        durable_handle_fuzzer::DurableHandleVersion,
+        handshake::session_scenario_fuzzer::SessionScenario, header_fuzzer::AsyncIdStrategy,
+    },
+    kerberos::KerberosConfig,
+    networking::{connection_context::SequenceViolation, state_transition_engine::State},
+    ntlmssp::credentials::Credentials,
+    smb2::{
+        helper_functions::negotiate_context::{Ciphers, CompressionAlgorithms},
+        requests::{echo::Echo, RequestType},
+    },
 };
 
+pub mod cancel_fuzzer;
+pub mod change_notify_fuzzer;
 pub mod close_fuzzer;
+pub mod compound_fuzzer;
+pub mod compression_fuzzer;
+pub mod create_context_fuzzer;
 pub mod create_fuzzer;
+pub mod durable_handle_fuzzer;
+pub mod flush_fuzzer;
 pub mod handshake;
+pub mod header_fuzzer;
+pub mod lock_fuzzer;
+pub mod oplock_break_fuzzer;
 pub mod query_info_fuzzer;
+pub mod read_fuzzer;
+pub mod transform_header_fuzzer;
 
 /// The fuzzing directive tells the fuzzer which message to fuzz with which
 /// fuzzing strategy in which state how many times.
@@ -23,6 +45,49 @@ pub struct FuzzingDirective {
     /// Defines the desired state of the SMB protocol that is to be reached
     /// before the fuzzing process begins.
     pub state: Option<State>,
+    /// Defines the async id with which the fuzzed message is sent.
+    /// If set, the ASYNC_COMMAND flag is forced in the header of the fuzzed message.
+    pub async_id: Option<AsyncIdStrategy>,
+    /// Defines the fuzzing strategy for the header of the fuzzed message.
+    /// It is applied independently of the fuzzing strategy for the message body.
+    pub header_fuzzing_strategy: Option<FuzzingStrategy>,
+    /// Defines the violation of the credit and message id sequencing with which
+    /// the fuzzed message is sent. Without a violation, the message is sequenced correctly.
+    pub sequence_violation: Option<SequenceViolation>,
+    /// Defines how the signature of the fuzzed message is violated.
+    /// Without a violation, the message is signed correctly once signing is established.
+    pub signature_violation: Option<SignatureViolation>,
+    /// Defines the cipher with which the session is encrypted once a session key is established.
+    /// Without a cipher, the session is not encrypted.
+    pub session_cipher: Option<Ciphers>,
+    /// Defines the fuzzing strategy for the transform header of encrypted messages.
+    pub transform_header_fuzzing_strategy: Option<FuzzingStrategy>,
+    /// Defines whether the input of the preauth integrity hash is tampered with,
+    /// so that the keys of the SMB 3.1.1 dialect are derived from a diverging hash.
+    pub preauth_tampering: bool,
+    /// Defines the algorithm with which the fuzzed message is compressed.
+    /// Without an algorithm, the message is not compressed.
+    pub compression_algorithm: Option<CompressionAlgorithms>,
+    /// Defines whether the fuzzed message is sent in a chained instead of an unchained compression transform.
+    pub chained_compression: bool,
+    /// Defines whether the fuzzed message is compressed even if the server did not negotiate
+    /// the compression algorithm or chained compression.
+    pub forced_compression: bool,
+    /// Defines the fuzzing strategy for the compression transform of compressed messages.
+    pub compression_fuzzing_strategy: Option<FuzzingStrategy>,
+    /// Defines the credentials with which the session setup authenticates.
+    pub credentials: Credentials,
+    /// Defines the fuzzing strategy for the SPNEGO token in the security buffer of session setup messages.
+    /// It is applied independently of the fuzzing strategy for the NTLM message inside.
+    pub spnego_fuzzing_strategy: Option<FuzzingStrategy>,
+    /// Defines the KDC and the service principal with which the session setup authenticates via Kerberos.
+    /// Without a configuration, the session setup authenticates via NTLM.
+    pub kerberos: Option<KerberosConfig>,
+    /// Defines the scenario on the established session in which the fuzzed session setup message is sent.
+    /// Without a scenario, the session setup messages establish a new session.
+    pub session_scenario: Option<SessionScenario>,
+    /// Defines the version of the durable handle with which the file is opened in the durable states.
+    pub durable_handle_version: DurableHandleVersion,
 }
 
 impl FuzzingDirective {
@@ -33,6 +98,22 @@ impl FuzzingDirective {
             iterations: 100,
             fuzzing_strategy: None,
             state: None,
+            async_id: None,
+            header_fuzzing_strategy: None,
+            sequence_violation: None,
+            signature_violation: None,
+            session_cipher: None,
+            transform_header_fuzzing_strategy: None,
+            preauth_tampering: false,
+            compression_algorithm: None,
+            chained_compression: false,
+            forced_compression: false,
+            compression_fuzzing_strategy: None,
+            credentials: Credentials::default(),
+            spnego_fuzzing_strategy: None,
+            kerberos: None,
+            session_scenario: None,
+            durable_handle_version: DurableHandleVersion::V2,
         }
     }
 }
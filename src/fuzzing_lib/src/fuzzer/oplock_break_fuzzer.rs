@@ -0,0 +1,172 @@
+use rand::Rng;
+
+use crate::{
+    builder::oplock_break_request::build_default_oplock_break_request_body,
+    smb2::{
+        helper_functions::fields::{LeaseState, OplockLevel},
+        requests::oplock_break::{
+            LeaseBreakAcknowledgment, OplockBreak, OplockBreakAcknowledgment,
+        },
+        responses::oplock_break::BreakNotification,
+    },
+};
+
+use super::create_random_byte_array_of_predefined_length;
+use super::create_random_byte_array_with_random_length;
+
+/// Fuzzes the acknowledgment of the given break notification with predefined values.
+/// Oplock levels the server did not break to, lease states that exceed the new lease state
+/// and altered file ids or lease keys are sampled. In some cases, the acknowledgment type
+/// does not match the type of the notification.
+pub fn fuzz_oplock_break_with_predefined_values(notification: BreakNotification) -> OplockBreak {
+    let mut rng = rand::thread_rng();
+
+    match build_default_oplock_break_request_body(notification) {
+        OplockBreak::Oplock(mut acknowledgment) => {
+            if rng.gen_bool(0.2) {
+                return OplockBreak::Lease(fuzz_lease_break_acknowledgment(
+                    LeaseBreakAcknowledgment::default(),
+                ));
+            }
+            acknowledgment.oplock_level = sample_oplock_level(acknowledgment.oplock_level);
+            acknowledgment.file_id = sample_identifier(acknowledgment.file_id);
+
+            OplockBreak::Oplock(acknowledgment)
+        }
+        OplockBreak::Lease(acknowledgment) => {
+            if rng.gen_bool(0.2) {
+                let mut oplock_acknowledgment = OplockBreakAcknowledgment::default();
+                oplock_acknowledgment.oplock_level = sample_oplock_level(vec![0]);
+                oplock_acknowledgment.file_id = acknowledgment.lease_key;
+                return OplockBreak::Oplock(oplock_acknowledgment);
+            }
+
+            OplockBreak::Lease(fuzz_lease_break_acknowledgment(acknowledgment))
+        }
+    }
+}
+
+/// Fuzzes the lease key and lease state of the lease break acknowledgment.
+pub fn fuzz_lease_break_acknowledgment(
+    mut acknowledgment: LeaseBreakAcknowledgment,
+) -> LeaseBreakAcknowledgment {
+    acknowledgment.lease_key = sample_identifier(acknowledgment.lease_key);
+    acknowledgment.lease_state = sample_lease_state(acknowledgment.lease_state);
+
+    acknowledgment
+}
+
+/// Samples the acknowledged oplock level. Besides the level the server breaks to,
+/// the levels that are invalid in an acknowledgment and undefined levels are chosen.
+pub fn sample_oplock_level(oplock_level: Vec<u8>) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=2) {
+        0 => oplock_level,
+        1 => rand::random::<OplockLevel>().unpack_byte_code(),
+        _ => vec![rng.gen()],
+    }
+}
+
+/// Samples the acknowledged lease state. Besides the new lease state, the state is
+/// extended by caching states the server did not grant or contains undefined bits.
+pub fn sample_lease_state(lease_state: Vec<u8>) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=2) {
+        0 => lease_state,
+        1 => LeaseState::return_sum_of_chosen_lease_states(
+            (0..rng.gen_range(1..=3))
+                .map(|_| rand::random::<LeaseState>())
+                .collect(),
+        ),
+        _ => (rng.gen::<u32>() | 0x00000008).to_le_bytes().to_vec(),
+    }
+}
+
+/// Samples the file id or lease key the acknowledgment refers to.
+/// Besides the identifier of the notification, a slightly altered,
+/// a random and the reserved identifier 0xFF.. are chosen.
+pub fn sample_identifier(identifier: Vec<u8>) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    match rng.gen_range(0..=3) {
+        0 => identifier,
+        1 => {
+            let mut altered_identifier = identifier;
+            if let Some(byte) = altered_identifier.last_mut() {
+                *byte = byte.wrapping_add(rng.gen_range(1..=u8::MAX));
+            }
+            altered_identifier
+        }
+        2 => create_random_byte_array_of_predefined_length(16),
+        _ => vec![0xff; 16],
+    }
+}
+
+/// Fuzzes the acknowledgment of the given break notification with random values
+/// that comply to the size restrictions of certain fields.
+pub fn fuzz_oplock_break_with_random_fields(notification: BreakNotification) -> OplockBreak {
+    match notification {
+        BreakNotification::Lease(_) => {
+            let mut acknowledgment = LeaseBreakAcknowledgment::default();
+            acknowledgment.structure_size = create_random_byte_array_of_predefined_length(2);
+            acknowledgment.reserved = create_random_byte_array_of_predefined_length(2);
+            acknowledgment.flags = create_random_byte_array_of_predefined_length(4);
+            acknowledgment.lease_key = create_random_byte_array_of_predefined_length(16);
+            acknowledgment.lease_state = create_random_byte_array_of_predefined_length(4);
+            acknowledgment.lease_duration = create_random_byte_array_of_predefined_length(8);
+
+            OplockBreak::Lease(acknowledgment)
+        }
+        _ => {
+            let mut acknowledgment = OplockBreakAcknowledgment::default();
+            acknowledgment.structure_size = create_random_byte_array_of_predefined_length(2);
+            acknowledgment.oplock_level = create_random_byte_array_of_predefined_length(1);
+            acknowledgment.reserved = create_random_byte_array_of_predefined_length(1);
+            acknowledgment.reserved_2 = create_random_byte_array_of_predefined_length(4);
+            acknowledgment.file_id = create_random_byte_array_of_predefined_length(16);
+
+            OplockBreak::Oplock(acknowledgment)
+        }
+    }
+}
+
+/// Fuzzes the acknowledgment of the given break notification with random values of random length.
+pub fn fuzz_oplock_break_completely_random(notification: BreakNotification) -> OplockBreak {
+    match notification {
+        BreakNotification::Lease(_) => {
+            let mut acknowledgment = LeaseBreakAcknowledgment::default();
+            acknowledgment.structure_size = create_random_byte_array_with_random_length();
+            acknowledgment.reserved = create_random_byte_array_with_random_length();
+            acknowledgment.flags = create_random_byte_array_with_random_length();
+            acknowledgment.lease_key = create_random_byte_array_with_random_length();
+            acknowledgment.lease_state = create_random_byte_array_with_random_length();
+            acknowledgment.lease_duration = create_random_byte_array_with_random_length();
+
+            OplockBreak::Lease(acknowledgment)
+        }
+        _ => {
+            let mut acknowledgment = OplockBreakAcknowledgment::default();
+            acknowledgment.structure_size = create_random_byte_array_with_random_length();
+            acknowledgment.oplock_level = create_random_byte_array_with_random_length();
+            acknowledgment.reserved = create_random_byte_array_with_random_length();
+            acknowledgment.reserved_2 = create_random_byte_array_with_random_length();
+            acknowledgment.file_id = create_random_byte_array_with_random_length();
+
+            OplockBreak::Oplock(acknowledgment)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_identifier() {
+        for _ in 0..32 {
+            assert_eq!(16, sample_identifier(vec![1; 16]).len());
+        }
+    }
+}
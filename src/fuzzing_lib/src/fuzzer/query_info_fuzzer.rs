@@ -1,22 +1,106 @@
+use rand::Rng;
+
 use super::create_random_byte_array_of_predefined_length;
 use super::create_random_byte_array_with_random_length;
-use crate::smb2::requests::query_info::{InfoType, QueryInfo};
+use crate::format::encoder::query_info_encoder::serialize_query_quota_info;
+use crate::smb2::requests::query_info::{
+    additional_information::AdditionalInformation, file_information_class::FileInformationClass,
+    fs_information_class::FsInformationClass, InfoFlags, InfoType, QueryInfo, QueryQuotaInfo,
+};
 
 pub const DEFAULT_BUFFER_LENGTH: &[u8; 4] = b"\xff\xff\x00\x00";
 
+/// The offset of the input buffer from the beginning of the SMB2 header
+/// (64 byte header + 40 byte fixed query info body).
+pub const DEFAULT_INPUT_BUFFER_OFFSET: &[u8; 2] = b"\x68\x00";
+
+/// The output buffer length that is requested for security descriptors and quota entries.
+pub const VARIABLE_OUTPUT_BUFFER_LENGTH: u32 = 0x1000;
+
 /// Fuzzes the query info request with predefined values.
+/// The file info class, additional information and output buffer length
+/// are chosen to match the sampled info type.
 pub fn fuzz_query_info_with_predefined_values(file_id: Vec<u8>) -> QueryInfo {
     let mut query_info_request = QueryInfo::default();
+    let info_type = rand::random::<InfoType>();
 
-    query_info_request.info_type = rand::random::<InfoType>().unpack_byte_code();
-    query_info_request.output_buffer_length = DEFAULT_BUFFER_LENGTH.to_vec();
+    query_info_request.info_type = info_type.unpack_byte_code();
     query_info_request.flags = vec![0; 4];
     query_info_request.file_id = file_id;
     query_info_request.buffer = vec![0];
 
+    let output_buffer_length = match info_type {
+        InfoType::File => {
+            let file_info_class = rand::random::<FileInformationClass>();
+            if file_info_class == FileInformationClass::FileFullEaInformation {
+                query_info_request.flags = sample_info_flags();
+            }
+            query_info_request.file_info_class = file_info_class.unpack_byte_code();
+            file_info_class.output_buffer_length()
+        }
+        InfoType::FileSystem => {
+            let fs_info_class = rand::random::<FsInformationClass>();
+            query_info_request.file_info_class = fs_info_class.unpack_byte_code();
+            fs_info_class.output_buffer_length()
+        }
+        InfoType::InfoSecurity => {
+            query_info_request.file_info_class = vec![0];
+            query_info_request.additional_information = sample_additional_information();
+            VARIABLE_OUTPUT_BUFFER_LENGTH
+        }
+        InfoType::InfoQuota => {
+            let quota_info = serialize_query_quota_info(&QueryQuotaInfo::default());
+            query_info_request.file_info_class = vec![0];
+            query_info_request.input_buffer_offset = DEFAULT_INPUT_BUFFER_OFFSET.to_vec();
+            query_info_request.input_buffer_length =
+                (quota_info.len() as u32).to_le_bytes().to_vec();
+            query_info_request.buffer = quota_info;
+            VARIABLE_OUTPUT_BUFFER_LENGTH
+        }
+    };
+
+    query_info_request.output_buffer_length = sample_output_buffer_length(output_buffer_length);
+
     query_info_request
 }
 
+/// Samples an output buffer length around the size of the expected information structure.
+/// Lengths that are too small provoke the STATUS_INFO_LENGTH_MISMATCH or STATUS_BUFFER_OVERFLOW
+/// code paths, while lengths that are too large reveal uninitialized bytes in the response.
+pub fn sample_output_buffer_length(expected_length: u32) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    let output_buffer_length = match rng.gen_range(0..=2) {
+        0 => expected_length,
+        1 => expected_length.saturating_sub(rng.gen_range(1..=expected_length.max(1))),
+        _ => expected_length + rng.gen_range(1..=0x1000),
+    };
+
+    output_buffer_length.to_le_bytes().to_vec()
+}
+
+/// Samples flags for FileFullEaInformation queries.
+pub fn sample_info_flags() -> Vec<u8> {
+    let mut random_info_flags: Vec<InfoFlags> = Vec::new();
+
+    for _ in 0..rand::thread_rng().gen_range(0..3) {
+        random_info_flags.push(rand::random());
+    }
+
+    InfoFlags::return_sum_of_chosen_capabilities(random_info_flags)
+}
+
+/// Samples the security information flags of security queries.
+pub fn sample_additional_information() -> Vec<u8> {
+    let mut random_information: Vec<AdditionalInformation> = Vec::new();
+
+    for _ in 0..rand::thread_rng().gen_range(1..8) {
+        random_information.push(rand::random());
+    }
+
+    AdditionalInformation::return_sum_of_chosen_additional_information(random_information)
+}
+
 /// Fuzzes the query info request with random values that comply to the size restrictions of certain fields.
 pub fn fuzz_query_info_with_random_fields() -> QueryInfo {
     let mut query_info_request = QueryInfo::default();
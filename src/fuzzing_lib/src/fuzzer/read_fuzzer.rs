@@ -0,0 +1,75 @@
+use rand::Rng;
+
+use crate::{builder::read_request::build_default_read_request_body, smb2::requests::read::Read};
+
+use super::create_random_byte_array_of_predefined_length;
+use super::create_random_byte_array_with_random_length;
+
+/// Fuzzes the read request with predefined values.
+/// The length and offset are sampled from boundary values, and the padding
+/// places the read data inside or beyond the response.
+pub fn fuzz_read_with_predefined_values(file_id: Vec<u8>) -> Read {
+    let mut rng = rand::thread_rng();
+    let mut read_request = build_default_read_request_body(file_id);
+
+    read_request.padding = vec![match rng.gen_range(0..=2) {
+        0 => 0x50,
+        1 => 0,
+        _ => rng.gen(),
+    }];
+    read_request.length = match rng.gen_range(0..=3) {
+        0 => read_request.length,
+        1 => vec![0; 4],
+        2 => 0x10001u32.to_le_bytes().to_vec(),
+        _ => vec![0xff; 4],
+    };
+    read_request.offset = match rng.gen_range(0..=3) {
+        0 => read_request.offset,
+        1 => rng.gen_range(1..=0x10000u64).to_le_bytes().to_vec(),
+        2 => i64::MAX.to_le_bytes().to_vec(),
+        _ => vec![0xff; 8],
+    };
+    read_request.minimum_count = create_random_byte_array_of_predefined_length(4);
+
+    read_request
+}
+
+/// Fuzzes the read request with random values that comply to the size restrictions of certain fields.
+pub fn fuzz_read_with_random_fields() -> Read {
+    let mut read_request = Read::default();
+
+    read_request.structure_size = create_random_byte_array_of_predefined_length(2);
+    read_request.padding = create_random_byte_array_of_predefined_length(1);
+    read_request.flags = create_random_byte_array_of_predefined_length(1);
+    read_request.length = create_random_byte_array_of_predefined_length(4);
+    read_request.offset = create_random_byte_array_of_predefined_length(8);
+    read_request.file_id = create_random_byte_array_of_predefined_length(16);
+    read_request.minimum_count = create_random_byte_array_of_predefined_length(4);
+    read_request.channel = create_random_byte_array_of_predefined_length(4);
+    read_request.remaining_bytes = create_random_byte_array_of_predefined_length(4);
+    read_request.read_channel_info_offset = create_random_byte_array_of_predefined_length(2);
+    read_request.read_channel_info_length = create_random_byte_array_of_predefined_length(2);
+    read_request.buffer = create_random_byte_array_with_random_length();
+
+    read_request
+}
+
+/// Fuzzes the read request with random values of random length.
+pub fn fuzz_read_completely_random() -> Read {
+    let mut read_request = Read::default();
+
+    read_request.structure_size = create_random_byte_array_with_random_length();
+    read_request.padding = create_random_byte_array_with_random_length();
+    read_request.flags = create_random_byte_array_with_random_length();
+    read_request.length = create_random_byte_array_with_random_length();
+    read_request.offset = create_random_byte_array_with_random_length();
+    read_request.file_id = create_random_byte_array_with_random_length();
+    read_request.minimum_count = create_random_byte_array_with_random_length();
+    read_request.channel = create_random_byte_array_with_random_length();
+    read_request.remaining_bytes = create_random_byte_array_with_random_length();
+    read_request.read_channel_info_offset = create_random_byte_array_with_random_length();
+    read_request.read_channel_info_length = create_random_byte_array_with_random_length();
+    read_request.buffer = create_random_byte_array_with_random_length();
+
+    read_request
+}
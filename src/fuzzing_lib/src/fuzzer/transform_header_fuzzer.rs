@@ -0,0 +1,154 @@
+use rand::Rng;
+
+use crate::smb2::header::TransformHeader;
+
+use super::create_random_byte_array_of_predefined_length;
+use super::create_random_byte_array_with_random_length;
+use super::header_fuzzer::sample_session_or_tree_id;
+use super::FuzzingStrategy;
+
+/// Fuzzes the transform header of an encrypted request according to the fuzzing strategy.
+/// The header is fuzzed before the encryption, so that the signature covers the fuzzed fields.
+pub fn fuzz_transform_header(
+    header: TransformHeader,
+    fuzzing_strategy: &FuzzingStrategy,
+) -> TransformHeader {
+    match fuzzing_strategy {
+        FuzzingStrategy::Predefined => fuzz_transform_header_with_predefined_values(header),
+        FuzzingStrategy::RandomFields => fuzz_transform_header_with_random_fields(header),
+        FuzzingStrategy::CompletelyRandom => fuzz_transform_header_completely_random(header),
+    }
+}
+
+/// Fuzzes the transform header with predefined values.
+/// Each field is mutated in half of the cases, while the protocol id is kept
+/// so that the request is still interpreted as an encrypted message.
+pub fn fuzz_transform_header_with_predefined_values(
+    mut header: TransformHeader,
+) -> TransformHeader {
+    let mut rng = rand::thread_rng();
+
+    if rng.gen_bool(0.5) {
+        header.original_message_size = sample_original_message_size(&header.original_message_size);
+    }
+    if rng.gen_bool(0.5) {
+        header.flags = sample_transform_flags();
+    }
+    if rng.gen_bool(0.5) {
+        header.session_id = sample_session_or_tree_id(header.session_id);
+    }
+    if rng.gen_bool(0.5) {
+        header.nonce = sample_nonce(header.nonce);
+    }
+    if rng.gen_bool(0.5) {
+        header.reserved = create_random_byte_array_of_predefined_length(2);
+    }
+
+    header
+}
+
+/// Samples the original message size. Besides the actual size, sizes just below and above,
+/// zero, a size smaller than the SMB2 header and the maximum are chosen.
+pub fn sample_original_message_size(original_message_size: &[u8]) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    let mut size_bytes = [0; 4];
+    let length = original_message_size.len().min(4);
+    size_bytes[..length].copy_from_slice(&original_message_size[..length]);
+    let size = u32::from_le_bytes(size_bytes);
+
+    let sampled_size: u32 = match rng.gen_range(0..=5) {
+        0 => size.saturating_sub(1),
+        1 => size.saturating_add(1),
+        2 => 0,
+        3 => rng.gen_range(1..64),
+        4 => u32::MAX,
+        _ => size.saturating_add(rng.gen_range(0x10000..=0x1000000)),
+    };
+
+    sampled_size.to_le_bytes().to_vec()
+}
+
+/// Samples the flags or encryption algorithm field. Besides the valid value of 0x0001,
+/// zero, the other cipher ids and the maximum are chosen.
+pub fn sample_transform_flags() -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    let flags: u16 = match rng.gen_range(0..=3) {
+        0 => 0,
+        1 => rng.gen_range(2..=4),
+        2 => u16::MAX,
+        _ => rng.gen(),
+    };
+
+    flags.to_le_bytes().to_vec()
+}
+
+/// Samples the nonce. Besides an all-zero nonce, a nonce with the unused trailing
+/// bytes set, a nonce with the maximum value and a truncated nonce are chosen.
+pub fn sample_nonce(mut nonce: Vec<u8>) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+
+    let length = nonce.len();
+    match rng.gen_range(0..=3) {
+        0 => vec![0; length],
+        1 => {
+            if let Some(byte) = nonce.last_mut() {
+                *byte = rng.gen_range(1..=u8::MAX);
+            }
+            nonce
+        }
+        2 => vec![0xff; length],
+        _ => {
+            nonce.truncate(rng.gen_range(0..length.max(1)));
+            nonce
+        }
+    }
+}
+
+/// Fuzzes the transform header with random values that comply to the size restrictions
+/// of the fields. The protocol id is kept.
+pub fn fuzz_transform_header_with_random_fields(mut header: TransformHeader) -> TransformHeader {
+    header.nonce = create_random_byte_array_of_predefined_length(16);
+    header.original_message_size = create_random_byte_array_of_predefined_length(4);
+    header.reserved = create_random_byte_array_of_predefined_length(2);
+    header.flags = create_random_byte_array_of_predefined_length(2);
+    header.session_id = create_random_byte_array_of_predefined_length(8);
+
+    header
+}
+
+/// Fuzzes the transform header with random values of random length. The protocol id is kept.
+pub fn fuzz_transform_header_completely_random(mut header: TransformHeader) -> TransformHeader {
+    header.nonce = create_random_byte_array_with_random_length();
+    header.original_message_size = create_random_byte_array_with_random_length();
+    header.reserved = create_random_byte_array_with_random_length();
+    header.flags = create_random_byte_array_with_random_length();
+    header.session_id = create_random_byte_array_with_random_length();
+
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smb2::header::TRANSFORM_PROTOCOL_ID;
+
+    #[test]
+    fn test_fuzz_transform_header_with_predefined_values() {
+        let mut header = TransformHeader::default();
+        header.nonce = vec![0x33; 16];
+        header.original_message_size = 100u32.to_le_bytes().to_vec();
+        header.session_id = vec![1; 8];
+
+        for _ in 0..32 {
+            let fuzzed_header = fuzz_transform_header_with_predefined_values(header.clone());
+
+            assert_eq!(TRANSFORM_PROTOCOL_ID.to_vec(), fuzzed_header.protocol_id);
+            assert_eq!(4, fuzzed_header.original_message_size.len());
+            assert_eq!(2, fuzzed_header.flags.len());
+            assert_eq!(8, fuzzed_header.session_id.len());
+            assert!(fuzzed_header.nonce.len() <= 16);
+        }
+    }
+}
@@ -1,3 +1,100 @@
+//! Provides the SPNEGO tokens (RFC 4178 and MS-SPNG) with which the security mechanism
+//! of the session setup is negotiated. The tokens are serialized with ASN.1 DER.
+
+/// The SPNEGO OID 1.3.6.1.5.5.2 of the GSS-API initial context token.
+pub const SPNEGO_OID: &[u8; 6] = b"\x2b\x06\x01\x05\x05\x02";
+/// The OID 1.3.6.1.4.1.311.2.2.10 of the NTLMSSP mechanism.
+pub const NTLMSSP_OID: &[u8; 10] = b"\x2b\x06\x01\x04\x01\x82\x37\x02\x02\x0a";
+/// The OID 1.2.840.113554.1.2.2 of the Kerberos 5 mechanism.
+pub const KERBEROS_OID: &[u8; 9] = b"\x2a\x86\x48\x86\xf7\x12\x01\x02\x02";
+/// The OID 1.2.840.48018.1.2.2 of the Kerberos 5 mechanism as announced by Microsoft.
+pub const MS_KERBEROS_OID: &[u8; 9] = b"\x2a\x86\x48\x82\xf7\x12\x01\x02\x02";
+/// The OID 1.3.6.1.4.1.311.2.2.30 of the NEGOEX mechanism.
+pub const NEGOEX_OID: &[u8; 10] = b"\x2b\x06\x01\x04\x01\x82\x37\x02\x02\x1e";
+
+/// The SPNEGO negotiation token is either the initial token of the initiator (NegTokenInit),
+/// the initial token of the acceptor as defined by MS-SPNG (NegTokenInit2) or a subsequent token (NegTokenResp).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum NegotiationToken {
+    NegTokenInit(NegTokenInit),
+    NegTokenInit2(NegTokenInit2),
+    NegTokenResp(NegTokenResp),
+}
+
+/// The NegTokenInit is the first token of the initiator, wrapped in the GSS-API initial context token.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct NegTokenInit {
+    /// MechTypes (variable): The OIDs of the mechanisms the initiator supports, in the order of preference.
+    pub mech_types: Vec<Vec<u8>>,
+    /// ReqFlags (variable): The BIT STRING of context flags, including the unused bits byte. OPTIONAL
+    pub req_flags: Vec<u8>,
+    /// MechToken (variable): The optimistic token of the preferred mechanism. OPTIONAL
+    pub mech_token: Vec<u8>,
+    /// MechListMic (variable): The MIC token over the mechTypes. OPTIONAL
+    pub mech_list_mic: Vec<u8>,
+}
+
+impl NegTokenInit {
+    /// Creates a new instance of the neg token init.
+    pub fn default() -> Self {
+        NegTokenInit {
+            mech_types: Vec::new(),
+            req_flags: Vec::new(),
+            mech_token: Vec::new(),
+            mech_list_mic: Vec::new(),
+        }
+    }
+}
+
+/// The NegTokenInit2 is the token with which an acceptor initiates the negotiation, e.g. in the
+/// SMB2 NEGOTIATE response. It extends the NegTokenInit with the negotiation hints.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct NegTokenInit2 {
+    /// MechTypes (variable): The OIDs of the mechanisms the acceptor supports, in the order of preference.
+    pub mech_types: Vec<Vec<u8>>,
+    /// ReqFlags (variable): The BIT STRING of context flags, including the unused bits byte. OPTIONAL
+    pub req_flags: Vec<u8>,
+    /// MechToken (variable): The optimistic token of the preferred mechanism. OPTIONAL
+    pub mech_token: Vec<u8>,
+    /// NegHints (variable): The hints of the acceptor. OPTIONAL
+    pub neg_hints: NegHints,
+    /// MechListMic (variable): The MIC token over the mechTypes. OPTIONAL
+    pub mech_list_mic: Vec<u8>,
+}
+
+impl NegTokenInit2 {
+    /// Creates a new instance of the neg token init 2.
+    pub fn default() -> Self {
+        NegTokenInit2 {
+            mech_types: Vec::new(),
+            req_flags: Vec::new(),
+            mech_token: Vec::new(),
+            neg_hints: NegHints::default(),
+            mech_list_mic: Vec::new(),
+        }
+    }
+}
+
+/// The NegHints of the NegTokenInit2.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct NegHints {
+    /// HintName (variable): The GeneralString that names the acceptor, commonly
+    /// "not_defined_in_RFC4178@please_ignore". OPTIONAL
+    pub hint_name: Vec<u8>,
+    /// HintAddress (variable): The address of the acceptor. OPTIONAL
+    pub hint_address: Vec<u8>,
+}
+
+impl NegHints {
+    /// Creates a new instance of the neg hints.
+    pub fn default() -> Self {
+        NegHints {
+            hint_name: Vec::new(),
+            hint_address: Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct NegTokenResp {
     /// NegState (1 byte): Contains the state of the negotiation. (required in first reply)
@@ -22,3 +119,24 @@ impl NegTokenResp {
         }
     }
 }
+
+/// The state of the negotiation in the NegTokenResp.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum NegState {
+    AcceptCompleted,
+    AcceptIncomplete,
+    Reject,
+    RequestMic,
+}
+
+impl NegState {
+    /// Unpacks the byte code of the negotiation state.
+    pub fn unpack_byte_code(&self) -> Vec<u8> {
+        match self {
+            NegState::AcceptCompleted => vec![0],
+            NegState::AcceptIncomplete => vec![1],
+            NegState::Reject => vec![2],
+            NegState::RequestMic => vec![3],
+        }
+    }
+}
@@ -0,0 +1,637 @@
+//! Provides the Kerberos V5 messages (RFC 4120) with which the client obtains a service ticket
+//! from the KDC and authenticates the session setup with an AP-REQ (MS-KILE).
+//! The fields hold the contents of their DER elements, e.g. the big endian two's complement of an
+//! INTEGER or the characters of a KerberosString, so that they can be fuzzed independently of the ASN.1 structure.
+//! Empty fields and structures of type Option that are None are OPTIONAL and omitted.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The protocol version number of Kerberos V5.
+pub const PVNO: u8 = 5;
+
+/// The message types of the Kerberos messages.
+pub const AS_REQ: u8 = 10;
+pub const AS_REP: u8 = 11;
+pub const TGS_REQ: u8 = 12;
+pub const TGS_REP: u8 = 13;
+pub const AP_REQ: u8 = 14;
+pub const AP_REP: u8 = 15;
+pub const KRB_ERROR: u8 = 30;
+
+/// The name types of the principal names.
+pub const NT_PRINCIPAL: u8 = 1;
+pub const NT_SRV_INST: u8 = 2;
+
+/// The pre-authentication data types.
+pub const PA_TGS_REQ: u8 = 1;
+pub const PA_ENC_TIMESTAMP: u8 = 2;
+pub const PA_ETYPE_INFO2: u8 = 19;
+
+/// The error code with which the KDC requests the pre-authentication of the AS-REQ.
+pub const KDC_ERR_PREAUTH_REQUIRED: i64 = 25;
+
+/// The key usage numbers of RFC 4120 7.5.1.
+pub const KEY_USAGE_PA_ENC_TIMESTAMP: u32 = 1;
+pub const KEY_USAGE_AS_REP_ENC_PART: u32 = 3;
+pub const KEY_USAGE_TGS_REQ_AUTH_CKSUM: u32 = 6;
+pub const KEY_USAGE_TGS_REQ_AUTHENTICATOR: u32 = 7;
+pub const KEY_USAGE_TGS_REP_ENC_PART: u32 = 8;
+pub const KEY_USAGE_AP_REQ_AUTHENTICATOR: u32 = 11;
+pub const KEY_USAGE_AP_REP_ENC_PART: u32 = 12;
+
+/// The token id of the AP-REQ in the Kerberos GSS-API token (RFC 4121 4.1).
+pub const GSS_AP_REQ_TOKEN_ID: &[u8; 2] = b"\x01\x00";
+/// The token id of the AP-REP in the Kerberos GSS-API token.
+pub const GSS_AP_REP_TOKEN_ID: &[u8; 2] = b"\x02\x00";
+/// The checksum type of the authenticator checksum that carries the GSS-API channel bindings and flags.
+pub const GSS_CHECKSUM_TYPE: &[u8; 3] = b"\x00\x80\x03";
+
+bitflags! {
+    /// The KDCOptions of the KDC-REQ-BODY.
+    pub struct KdcOptions: u32 {
+        const FORWARDABLE = 0x4000_0000;
+        const FORWARDED = 0x2000_0000;
+        const PROXIABLE = 0x1000_0000;
+        const RENEWABLE = 0x0080_0000;
+        const CANONICALIZE = 0x0001_0000;
+        const RENEWABLE_OK = 0x0000_0010;
+    }
+}
+
+bitflags! {
+    /// The APOptions of the AP-REQ.
+    pub struct ApOptions: u32 {
+        const USE_SESSION_KEY = 0x4000_0000;
+        const MUTUAL_REQUIRED = 0x2000_0000;
+    }
+}
+
+bitflags! {
+    /// The context flags of the GSS-API checksum in the authenticator (RFC 4121 4.1.1.1).
+    pub struct GssFlags: u32 {
+        const DELEG = 0x01;
+        const MUTUAL = 0x02;
+        const REPLAY = 0x04;
+        const SEQUENCE = 0x08;
+        const CONF = 0x10;
+        const INTEG = 0x20;
+    }
+}
+
+/// The encryption types (RFC 3961 and RFC 4757) the client supports, in the order of preference.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum EncryptionType {
+    Aes256CtsHmacSha196,
+    Aes128CtsHmacSha196,
+    Rc4Hmac,
+}
+
+impl EncryptionType {
+    /// Unpack the byte code of the encryption type, the content of its INTEGER.
+    pub fn unpack_byte_code(&self) -> Vec<u8> {
+        match self {
+            EncryptionType::Aes256CtsHmacSha196 => vec![0x12],
+            EncryptionType::Aes128CtsHmacSha196 => vec![0x11],
+            EncryptionType::Rc4Hmac => vec![0x17],
+        }
+    }
+
+    /// Maps the byte code of the encryption type. Returns None for unsupported encryption types.
+    pub fn map_byte_code_to_encryption_type(byte_code: &[u8]) -> Option<Self> {
+        match byte_code {
+            [0x12] => Some(EncryptionType::Aes256CtsHmacSha196),
+            [0x11] => Some(EncryptionType::Aes128CtsHmacSha196),
+            [0x17] => Some(EncryptionType::Rc4Hmac),
+            _ => None,
+        }
+    }
+
+    /// Returns the length of the keys of the encryption type in bytes.
+    pub fn key_length(&self) -> usize {
+        match self {
+            EncryptionType::Aes256CtsHmacSha196 => 32,
+            EncryptionType::Aes128CtsHmacSha196 | EncryptionType::Rc4Hmac => 16,
+        }
+    }
+
+    /// Unpack the byte code of the keyed checksum type that belongs to the encryption type.
+    pub fn unpack_checksum_type(&self) -> Vec<u8> {
+        match self {
+            EncryptionType::Aes256CtsHmacSha196 => vec![0x10],
+            EncryptionType::Aes128CtsHmacSha196 => vec![0x0f],
+            EncryptionType::Rc4Hmac => vec![0xff, 0x76],
+        }
+    }
+
+    /// Returns all supported encryption types in the order of preference.
+    pub fn supported() -> Vec<Self> {
+        vec![
+            EncryptionType::Aes256CtsHmacSha196,
+            EncryptionType::Aes128CtsHmacSha196,
+            EncryptionType::Rc4Hmac,
+        ]
+    }
+}
+
+/// The KDC from which the tickets are obtained and the service principal of the SMB server.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct KerberosConfig {
+    /// The address of the KDC, e.g. "192.168.0.10:88". The port defaults to 88.
+    pub kdc_address: String,
+    /// The principal name of the SMB server, e.g. "cifs/server.domain.local".
+    pub service_principal: String,
+}
+
+impl KerberosConfig {
+    /// Maps the user input to the Kerberos configuration.
+    pub fn map_string_to_kerberos_config(kdc_address: &str, service_principal: &str) -> Self {
+        let kdc_address = if kdc_address.contains(':') {
+            kdc_address.to_string()
+        } else {
+            format!("{}:88", kdc_address)
+        };
+
+        KerberosConfig {
+            kdc_address,
+            service_principal: service_principal.to_string(),
+        }
+    }
+}
+
+/// PrincipalName: The name type and the components of the name.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PrincipalName {
+    /// name-type [0] Int32
+    pub name_type: Vec<u8>,
+    /// name-string [1] SEQUENCE OF KerberosString
+    pub name_string: Vec<Vec<u8>>,
+}
+
+impl PrincipalName {
+    /// Creates a new instance of the principal name.
+    pub fn default() -> Self {
+        PrincipalName {
+            name_type: vec![NT_PRINCIPAL],
+            name_string: Vec::new(),
+        }
+    }
+
+    /// Creates the principal name from its string representation, in which the components are separated by '/'.
+    pub fn from_string(name_type: u8, name: &str) -> Self {
+        PrincipalName {
+            name_type: vec![name_type],
+            name_string: name
+                .split('/')
+                .map(|component| component.as_bytes().to_vec())
+                .collect(),
+        }
+    }
+}
+
+/// EncryptedData: The cipher text of an encrypted structure.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EncryptedData {
+    /// etype [0] Int32
+    pub etype: Vec<u8>,
+    /// kvno [1] UInt32 OPTIONAL
+    pub kvno: Vec<u8>,
+    /// cipher [2] OCTET STRING
+    pub cipher: Vec<u8>,
+}
+
+impl EncryptedData {
+    /// Creates a new instance of the encrypted data.
+    pub fn default() -> Self {
+        EncryptedData {
+            etype: Vec::new(),
+            kvno: Vec::new(),
+            cipher: Vec::new(),
+        }
+    }
+}
+
+/// EncryptionKey: A key and its encryption type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EncryptionKey {
+    /// keytype [0] Int32
+    pub key_type: Vec<u8>,
+    /// keyvalue [1] OCTET STRING
+    pub key_value: Vec<u8>,
+}
+
+impl EncryptionKey {
+    /// Creates a new instance of the encryption key.
+    pub fn default() -> Self {
+        EncryptionKey {
+            key_type: Vec::new(),
+            key_value: Vec::new(),
+        }
+    }
+}
+
+/// Checksum: A checksum and its type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Checksum {
+    /// cksumtype [0] Int32
+    pub cksum_type: Vec<u8>,
+    /// checksum [1] OCTET STRING
+    pub checksum: Vec<u8>,
+}
+
+impl Checksum {
+    /// Creates a new instance of the checksum.
+    pub fn default() -> Self {
+        Checksum {
+            cksum_type: Vec::new(),
+            checksum: Vec::new(),
+        }
+    }
+}
+
+/// PA-DATA: An element of the pre-authentication data.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PaData {
+    /// padata-type [1] Int32
+    pub padata_type: Vec<u8>,
+    /// padata-value [2] OCTET STRING
+    pub padata_value: Vec<u8>,
+}
+
+impl PaData {
+    /// Creates a new instance of the pre-authentication data.
+    pub fn default() -> Self {
+        PaData {
+            padata_type: Vec::new(),
+            padata_value: Vec::new(),
+        }
+    }
+}
+
+/// Ticket [APPLICATION 1]: The ticket that the KDC issued for a service.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Ticket {
+    /// tkt-vno [0] INTEGER (5)
+    pub tkt_vno: Vec<u8>,
+    /// realm [1] Realm
+    pub realm: Vec<u8>,
+    /// sname [2] PrincipalName
+    pub sname: PrincipalName,
+    /// enc-part [3] EncryptedData, encrypted with the key of the service.
+    pub enc_part: EncryptedData,
+}
+
+impl Ticket {
+    /// Creates a new instance of the ticket.
+    pub fn default() -> Self {
+        Ticket {
+            tkt_vno: vec![PVNO],
+            realm: Vec::new(),
+            sname: PrincipalName::default(),
+            enc_part: EncryptedData::default(),
+        }
+    }
+}
+
+/// KDC-REQ-BODY: The body of the AS-REQ and the TGS-REQ.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct KdcReqBody {
+    /// kdc-options [0] KDCOptions, the BIT STRING including the unused bits byte.
+    pub kdc_options: Vec<u8>,
+    /// cname [1] PrincipalName OPTIONAL, used in the AS-REQ only.
+    pub cname: Option<PrincipalName>,
+    /// realm [2] Realm
+    pub realm: Vec<u8>,
+    /// sname [3] PrincipalName OPTIONAL
+    pub sname: Option<PrincipalName>,
+    /// till [5] KerberosTime
+    pub till: Vec<u8>,
+    /// nonce [7] UInt32
+    pub nonce: Vec<u8>,
+    /// etype [8] SEQUENCE OF Int32, in the order of preference.
+    pub etype: Vec<Vec<u8>>,
+}
+
+impl KdcReqBody {
+    /// Creates a new instance of the KDC request body.
+    pub fn default() -> Self {
+        KdcReqBody {
+            kdc_options: Vec::new(),
+            cname: None,
+            realm: Vec::new(),
+            sname: None,
+            till: Vec::new(),
+            nonce: Vec::new(),
+            etype: Vec::new(),
+        }
+    }
+}
+
+/// KDC-REQ: The AS-REQ [APPLICATION 10] and the TGS-REQ [APPLICATION 12].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct KdcReq {
+    /// pvno [1] INTEGER (5)
+    pub pvno: Vec<u8>,
+    /// msg-type [2] INTEGER (10 -- AS -- | 12 -- TGS --)
+    pub msg_type: Vec<u8>,
+    /// padata [3] SEQUENCE OF PA-DATA OPTIONAL
+    pub padata: Vec<PaData>,
+    /// req-body [4] KDC-REQ-BODY
+    pub req_body: KdcReqBody,
+}
+
+impl KdcReq {
+    /// Creates a new instance of the KDC request.
+    pub fn default() -> Self {
+        KdcReq {
+            pvno: vec![PVNO],
+            msg_type: Vec::new(),
+            padata: Vec::new(),
+            req_body: KdcReqBody::default(),
+        }
+    }
+}
+
+/// KDC-REP: The AS-REP [APPLICATION 11] and the TGS-REP [APPLICATION 13].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct KdcRep {
+    /// pvno [0] INTEGER (5)
+    pub pvno: Vec<u8>,
+    /// msg-type [1] INTEGER (11 -- AS -- | 13 -- TGS --)
+    pub msg_type: Vec<u8>,
+    /// padata [2] SEQUENCE OF PA-DATA OPTIONAL
+    pub padata: Vec<PaData>,
+    /// crealm [3] Realm
+    pub crealm: Vec<u8>,
+    /// cname [4] PrincipalName
+    pub cname: PrincipalName,
+    /// ticket [5] Ticket
+    pub ticket: Ticket,
+    /// enc-part [6] EncryptedData, which contains the EncKDCRepPart.
+    pub enc_part: EncryptedData,
+}
+
+impl KdcRep {
+    /// Creates a new instance of the KDC reply.
+    pub fn default() -> Self {
+        KdcRep {
+            pvno: vec![PVNO],
+            msg_type: Vec::new(),
+            padata: Vec::new(),
+            crealm: Vec::new(),
+            cname: PrincipalName::default(),
+            ticket: Ticket::default(),
+            enc_part: EncryptedData::default(),
+        }
+    }
+}
+
+/// EncKDCRepPart: The decrypted part of the KDC reply. Only the fields the client needs are kept.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EncKdcRepPart {
+    /// key [0] EncryptionKey, the session key of the ticket.
+    pub key: EncryptionKey,
+    /// nonce [2] UInt32, which has to match the nonce of the request.
+    pub nonce: Vec<u8>,
+}
+
+impl EncKdcRepPart {
+    /// Creates a new instance of the encrypted part of the KDC reply.
+    pub fn default() -> Self {
+        EncKdcRepPart {
+            key: EncryptionKey::default(),
+            nonce: Vec::new(),
+        }
+    }
+}
+
+/// KRB-ERROR [APPLICATION 30]: The error the KDC replies with. Only the fields the client needs are kept.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct KrbError {
+    /// error-code [6] Int32
+    pub error_code: Vec<u8>,
+    /// e-text [11] KerberosString OPTIONAL
+    pub e_text: Vec<u8>,
+    /// e-data [12] OCTET STRING OPTIONAL, the METHOD-DATA with the supported pre-authentication types.
+    pub e_data: Vec<u8>,
+}
+
+impl KrbError {
+    /// Creates a new instance of the Kerberos error.
+    pub fn default() -> Self {
+        KrbError {
+            error_code: Vec::new(),
+            e_text: Vec::new(),
+            e_data: Vec::new(),
+        }
+    }
+}
+
+/// ETYPE-INFO2-ENTRY: The salt and the string-to-key parameters of an encryption type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EtypeInfo2Entry {
+    /// etype [0] Int32
+    pub etype: Vec<u8>,
+    /// salt [1] KerberosString OPTIONAL
+    pub salt: Vec<u8>,
+    /// s2kparams [2] OCTET STRING OPTIONAL, the iteration count of the AES encryption types.
+    pub s2kparams: Vec<u8>,
+}
+
+impl EtypeInfo2Entry {
+    /// Creates a new instance of the etype info 2 entry.
+    pub fn default() -> Self {
+        EtypeInfo2Entry {
+            etype: Vec::new(),
+            salt: Vec::new(),
+            s2kparams: Vec::new(),
+        }
+    }
+}
+
+/// PA-ENC-TS-ENC: The timestamp that is encrypted with the key of the client for the pre-authentication.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PaEncTsEnc {
+    /// patimestamp [0] KerberosTime
+    pub patimestamp: Vec<u8>,
+    /// pausec [1] Microseconds OPTIONAL
+    pub pausec: Vec<u8>,
+}
+
+impl PaEncTsEnc {
+    /// Creates a new instance of the encrypted timestamp.
+    pub fn default() -> Self {
+        PaEncTsEnc {
+            patimestamp: Vec::new(),
+            pausec: Vec::new(),
+        }
+    }
+}
+
+/// AP-REQ [APPLICATION 14]: The ticket and the authenticator with which the client authenticates to a service.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ApReq {
+    /// pvno [0] INTEGER (5)
+    pub pvno: Vec<u8>,
+    /// msg-type [1] INTEGER (14)
+    pub msg_type: Vec<u8>,
+    /// ap-options [2] APOptions, the BIT STRING including the unused bits byte.
+    pub ap_options: Vec<u8>,
+    /// ticket [3] Ticket
+    pub ticket: Ticket,
+    /// authenticator [4] EncryptedData, which contains the Authenticator.
+    pub authenticator: EncryptedData,
+}
+
+impl ApReq {
+    /// Creates a new instance of the AP-REQ.
+    pub fn default() -> Self {
+        ApReq {
+            pvno: vec![PVNO],
+            msg_type: vec![AP_REQ],
+            ap_options: Vec::new(),
+            ticket: Ticket::default(),
+            authenticator: EncryptedData::default(),
+        }
+    }
+}
+
+/// Authenticator [APPLICATION 2]: Proves that the client knows the session key of the ticket.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Authenticator {
+    /// authenticator-vno [0] INTEGER (5)
+    pub authenticator_vno: Vec<u8>,
+    /// crealm [1] Realm
+    pub crealm: Vec<u8>,
+    /// cname [2] PrincipalName
+    pub cname: PrincipalName,
+    /// cksum [3] Checksum OPTIONAL
+    pub cksum: Option<Checksum>,
+    /// cusec [4] Microseconds
+    pub cusec: Vec<u8>,
+    /// ctime [5] KerberosTime
+    pub ctime: Vec<u8>,
+    /// subkey [6] EncryptionKey OPTIONAL
+    pub subkey: Option<EncryptionKey>,
+    /// seq-number [7] UInt32 OPTIONAL
+    pub seq_number: Vec<u8>,
+}
+
+impl Authenticator {
+    /// Creates a new instance of the authenticator.
+    pub fn default() -> Self {
+        Authenticator {
+            authenticator_vno: vec![PVNO],
+            crealm: Vec::new(),
+            cname: PrincipalName::default(),
+            cksum: None,
+            cusec: Vec::new(),
+            ctime: Vec::new(),
+            subkey: None,
+            seq_number: Vec::new(),
+        }
+    }
+}
+
+/// EncAPRepPart [APPLICATION 27]: The decrypted part of the AP-REP. Only the fields the client needs are kept.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EncApRepPart {
+    /// subkey [2] EncryptionKey OPTIONAL, the acceptor subkey.
+    pub subkey: Option<EncryptionKey>,
+}
+
+impl EncApRepPart {
+    /// Creates a new instance of the encrypted part of the AP-REP.
+    pub fn default() -> Self {
+        EncApRepPart { subkey: None }
+    }
+}
+
+/// The service ticket and its session key that the client obtained from the KDC.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ServiceTicket {
+    /// The name of the client in the realm of the client.
+    pub cname: PrincipalName,
+    /// The realm of the client.
+    pub crealm: Vec<u8>,
+    /// The ticket for the service.
+    pub ticket: Ticket,
+    /// The session key that the client shares with the service.
+    pub session_key: EncryptionKey,
+}
+
+/// Returns the current time as KerberosTime ("YYYYMMDDHHMMSSZ") and its microseconds.
+pub fn current_kerberos_time() -> (Vec<u8>, u32) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before the unix epoch.");
+
+    (format_kerberos_time(now.as_secs()), now.subsec_micros())
+}
+
+/// Formats the seconds since the unix epoch as KerberosTime, the GeneralizedTime "YYYYMMDDHHMMSSZ" in UTC.
+pub fn format_kerberos_time(seconds: u64) -> Vec<u8> {
+    let days = (seconds / 86400) as i64;
+    let seconds_of_day = seconds % 86400;
+
+    // Converts the days since the epoch to the proleptic Gregorian calendar date.
+    let shifted_days = days + 719_468;
+    let era = shifted_days.div_euclid(146_097);
+    let day_of_era = shifted_days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}{:02}{:02}{:02}{:02}{:02}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+    .into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_kerberos_time() {
+        assert_eq!(b"19700101000000Z".to_vec(), format_kerberos_time(0));
+        assert_eq!(
+            b"20370915024800Z".to_vec(),
+            format_kerberos_time(2136595680)
+        );
+        assert_eq!(
+            b"20240229235959Z".to_vec(),
+            format_kerberos_time(1709251199)
+        );
+    }
+
+    #[test]
+    fn test_map_string_to_kerberos_config() {
+        let config = KerberosConfig::map_string_to_kerberos_config(
+            "192.168.0.10",
+            "cifs/server.domain.local",
+        );
+
+        assert_eq!("192.168.0.10:88", config.kdc_address);
+        assert_eq!(
+            PrincipalName {
+                name_type: vec![NT_SRV_INST],
+                name_string: vec![b"cifs".to_vec(), b"server.domain.local".to_vec()],
+            },
+            PrincipalName::from_string(NT_SRV_INST, &config.service_principal)
+        );
+    }
+}
@@ -2,9 +2,12 @@
 extern crate bitflags;
 
 pub mod builder;
+pub mod compression;
+pub mod crypto;
 pub mod format;
 pub mod fuzzer;
 pub mod gss;
+pub mod kerberos;
 pub mod networking;
 pub mod ntlmssp;
 pub mod smb2;
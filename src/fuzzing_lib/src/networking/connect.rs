@@ -1,31 +1,54 @@
 //! This module establishes a direct TCP connection between client and host over port 445.
 //! It also performs the SMB handshake.
 
-use std::net::TcpStream;
+use std::net::{Shutdown, TcpStream};
 use std::{
     io::{Read, Write},
     time::Duration,
 };
 
-use super::state_transition_engine::State;
+use super::{kdc, state_transition_engine::State};
 
-use crate::fuzzer::FuzzingStrategy;
+use crate::builder::{
+    durable_handle_request::DurableOpen,
+    session_setup_kerberos_request::derive_kerberos_session_key,
+};
+use crate::compression;
+use crate::format::decoder::query_info_decoder::{
+    decode_query_info_output_buffer, decode_query_info_response_body, find_potentially_leaked_bytes,
+};
+use crate::format::decoder::{
+    compression_decoder::{decode_compression_transform, is_compressed_message},
+    create_decoder::decode_create_response_body,
+    decode_break_notification, decode_compound_response, decode_response_header,
+    decode_session_setup_response, is_async_header, is_break_notification, is_interim_response,
+    is_transform_message,
+};
+use crate::format::encoder::serialize_netbios_session_prefix;
+use crate::format::{convert_byte_array_to_int, HEADER_LENGTH};
+use crate::fuzzer::{
+    change_notify_fuzzer::RacingOperation, handshake::session_scenario_fuzzer::SessionScenario,
+    FuzzingStrategy,
+};
 
-use super::packets;
-use crate::smb2::responses;
+use super::{connection_context::ConnectionContext, packets};
+use crate::ntlmssp::credentials::Credentials;
+use crate::smb2::{header::Header, responses, responses::oplock_break::BreakNotification};
 
 pub fn go_to_session_setup_negotiate_state_and_fuzz_session_setup_2() {
     match TcpStream::connect("192.168.0.171:445") {
         Ok(mut stream) => {
+            let mut context = ConnectionContext::default();
             stream
                 .set_read_timeout(Some(Duration::from_secs(5)))
                 .expect("Failed to set read time out.");
             println!("Successfully connected to server in port 445.");
 
             let (response_body, session_id) =
-                State::go_to_session_setup_negotiate_state(&mut stream);
+                State::go_to_session_setup_negotiate_state(&mut stream, &mut context);
             send_session_setup_authenticate_request(
                 &mut stream,
+                &mut context,
                 response_body,
                 session_id,
                 Some(FuzzingStrategy::Predefined),
@@ -41,15 +64,18 @@ pub fn go_to_session_setup_negotiate_state_and_fuzz_session_setup_2() {
 pub fn go_to_session_setup_authenticate_state_and_fuzz_tree_connect() {
     match TcpStream::connect("192.168.0.171:445") {
         Ok(mut stream) => {
+            let mut context = ConnectionContext::default();
             stream
                 .set_read_timeout(Some(Duration::from_secs(5)))
                 .expect("Failed to set read time out.");
             println!("Successfully connected to server in port 445.");
 
-            let session_id = State::go_to_session_setup_authenticate_state(&mut stream);
+            let session_id =
+                State::go_to_session_setup_authenticate_state(&mut stream, &mut context);
 
             send_tree_connect_request_and_get_response(
                 &mut stream,
+                &mut context,
                 session_id,
                 Some(FuzzingStrategy::Predefined),
             );
@@ -64,15 +90,17 @@ pub fn go_to_session_setup_authenticate_state_and_fuzz_tree_connect() {
 pub fn go_to_tree_connect_state_and_fuzz_create() {
     match TcpStream::connect("192.168.0.171:445") {
         Ok(mut stream) => {
+            let mut context = ConnectionContext::default();
             stream
                 .set_read_timeout(Some(Duration::from_secs(5)))
                 .expect("Failed to set read time out.");
             println!("Successfully connected to server in port 445.");
 
-            let (session_id, tree_id) = State::go_to_tree_connect_state(&mut stream);
+            let (session_id, tree_id) = State::go_to_tree_connect_state(&mut stream, &mut context);
 
             send_create_request_and_get_response(
                 &mut stream,
+                &mut context,
                 session_id,
                 tree_id,
                 Some(FuzzingStrategy::Predefined),
@@ -88,15 +116,18 @@ pub fn go_to_tree_connect_state_and_fuzz_create() {
 pub fn go_to_create_state_and_fuzz_query_info() {
     match TcpStream::connect("192.168.0.171:445") {
         Ok(mut stream) => {
+            let mut context = ConnectionContext::default();
             stream
                 .set_read_timeout(Some(Duration::from_secs(5)))
                 .expect("Failed to set read time out.");
             println!("Successfully connected to server in port 445.");
 
-            let (session_id, tree_id, file_id) = State::go_to_create_state(&mut stream);
+            let (session_id, tree_id, file_id) =
+                State::go_to_create_state(&mut stream, &mut context);
 
             send_query_info_request(
                 &mut stream,
+                &mut context,
                 session_id,
                 tree_id,
                 file_id,
@@ -111,15 +142,24 @@ pub fn go_to_create_state_and_fuzz_query_info() {
 }
 
 /// Sends a negotiate request to the server.
-pub fn send_negotiate(stream: &mut TcpStream, fuzzing_strategy: Option<FuzzingStrategy>) {
+pub fn send_negotiate(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    fuzzing_strategy: Option<FuzzingStrategy>,
+) {
     let negotiate_request: Vec<u8> = packets::prepare_negotiate_packet(fuzzing_strategy);
 
-    if stream.write_all(&negotiate_request[..]).is_err() {
+    if stream
+        .write_all(&context.prepare_request(negotiate_request)[..])
+        .is_err()
+    {
         println!("Negotiate State Reset");
     } else {
-        let mut buffer: [u8; 300] = [0; 300];
-        match stream.read(&mut buffer) {
-            Ok(_) => (),
+        match receive_response(stream, context) {
+            Ok(response) => {
+                context.set_negotiated_connection(&response);
+                context.set_server_negotiation_token(&response);
+            }
             Err(e) => {
                 println!("Failed to receive Negotiate response: {}", e);
             }
@@ -130,44 +170,264 @@ pub fn send_negotiate(stream: &mut TcpStream, fuzzing_strategy: Option<FuzzingSt
 /// Sends a session setup 1 request and returns the server response.
 pub fn send_session_setup_negotiate_request_and_get_response(
     stream: &mut TcpStream,
+    context: &mut ConnectionContext,
     fuzzing_strategy: Option<FuzzingStrategy>,
-) -> [u8; 300] {
-    let mut buffer: [u8; 300] = [0; 300];
+) -> Vec<u8> {
+    let mut response = vec![0; 300];
     let session_setup_request_1 = packets::prepare_session_setup_negotiate_packet(fuzzing_strategy);
 
-    stream.write_all(&session_setup_request_1[..]).unwrap();
+    stream
+        .write_all(&context.prepare_request(session_setup_request_1)[..])
+        .unwrap();
     println!("Sent Session Setup Request 1, awaiting reply...");
-    match stream.read(&mut buffer) {
-        Ok(_) => println!("Successfully received session setup response 1 from server."),
+    match receive_response(stream, context) {
+        Ok(session_setup_response) => {
+            response = session_setup_response;
+            println!("Successfully received session setup response 1 from server.");
+        }
         Err(e) => {
             println!("Failed to receive session setup response: {}", e);
         }
     }
 
-    buffer
+    response
 }
 
-/// Sends a session setup 2 request.
+/// Sends a session setup 2 request. Once the default request authenticated successfully,
+/// signing and encryption are established with the session key, unless the server
+/// flagged the session as guest or anonymous session.
 pub fn send_session_setup_authenticate_request(
     stream: &mut TcpStream,
+    context: &mut ConnectionContext,
     session_setup_response_body: responses::session_setup::SessionSetup,
     session_id: Vec<u8>,
     fuzzing_strategy: Option<FuzzingStrategy>,
 ) {
-    let mut buffer: [u8; 300] = [0; 300];
-
-    let session_setup_request_2 = packets::prepare_session_setup_authenticate_packet(
+    let (session_setup_request_2, session_key) = packets::prepare_session_setup_authenticate_packet(
         fuzzing_strategy,
         session_id,
         session_setup_response_body,
+        &context.credentials,
     );
 
-    stream.write_all(&session_setup_request_2[..]).unwrap();
+    stream
+        .write_all(&context.prepare_request(session_setup_request_2)[..])
+        .unwrap();
     println!("Sent Session Setup Request 2, awaiting reply...");
-    match stream.read(&mut buffer) {
-        Ok(_) => println!("Successfully received session setup response 2 from server."),
+    match receive_response(stream, context) {
+        Ok(response) => {
+            println!("Successfully received session setup response 2 from server.");
+            context.set_session_flags(&response);
+            if let Some(session_key) = session_key {
+                if response.len() < 4 + HEADER_LENGTH || response[4 + 8..4 + 12] != [0; 4] {
+                    println!("Authentication failed, the session is not signed.");
+                } else if context.is_guest_or_null_session() {
+                    println!("Authenticated as guest or anonymous, the session is not signed.");
+                } else {
+                    context.establish_signing(&session_key, None);
+                    context.establish_encryption(&session_key);
+                }
+            }
+        }
+        Err(e) => {
+            println!("Failed to receive session setup 2 response: {}", e);
+        }
+    }
+}
+
+/// Sends a Kerberos session setup request with the AP-REQ for the service ticket of the configured
+/// service principal, which is obtained from the KDC first. Once the default request authenticated
+/// successfully, signing and encryption are established with the session key of the AP-REP.
+/// Returns the newly created session id.
+pub fn send_session_setup_kerberos_request(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    fuzzing_strategy: Option<FuzzingStrategy>,
+) -> Vec<u8> {
+    let config = context
+        .kerberos
+        .clone()
+        .expect("Kerberos is not configured.");
+    let service_ticket = match kdc::obtain_service_ticket(&config, &context.credentials) {
+        Ok(service_ticket) => service_ticket,
+        Err(e) => {
+            println!("Failed to obtain the service ticket: {}", e);
+            return vec![0; 8];
+        }
+    };
+    let (session_setup_request, subkey) =
+        packets::prepare_session_setup_kerberos_packet(fuzzing_strategy, &service_ticket);
+
+    stream
+        .write_all(&context.prepare_request(session_setup_request)[..])
+        .unwrap();
+    println!("Sent Kerberos Session Setup Request, awaiting reply...");
+    match receive_response(stream, context) {
+        Ok(response) => {
+            println!("Successfully received Kerberos session setup response from server.");
+            context.set_session_flags(&response);
+            if response.len() < 4 + HEADER_LENGTH {
+                return vec![0; 8];
+            }
+            if let Some(subkey) = subkey {
+                if response[4 + 8..4 + 12] != [0; 4] {
+                    println!("Kerberos authentication failed, the session is not signed.");
+                } else if context.is_guest_or_null_session() {
+                    println!("Authenticated as guest or anonymous, the session is not signed.");
+                } else {
+                    let (_, response_body) = decode_session_setup_response(response.clone());
+                    match derive_kerberos_session_key(
+                        &response_body.buffer,
+                        &service_ticket,
+                        &subkey,
+                    ) {
+                        Some(session_key) => {
+                            context.establish_signing(&session_key, None);
+                            context.establish_encryption(&session_key);
+                        }
+                        None => println!("Invalid AP-REP, the session is not signed."),
+                    }
+                }
+            }
+
+            decode_response_header(response[4..4 + HEADER_LENGTH].to_vec()).session_id
+        }
+        Err(e) => {
+            println!("Failed to receive Kerberos session setup response: {}", e);
+            vec![0; 8]
+        }
+    }
+}
+
+/// Opens the connection on which the session scenario is carried out and negotiates it.
+/// The re-authentication reuses the established connection, so that only the binding and the
+/// reconnect open a second connection to the server. The binding requests are signed with the
+/// signing key of the established session. The Kerberos configuration is not copied,
+/// since the scenario always authenticates with NTLM.
+pub fn open_session_channel(
+    stream: &TcpStream,
+    session_context: &ConnectionContext,
+    scenario: &SessionScenario,
+) -> std::io::Result<(TcpStream, ConnectionContext)> {
+    let mut channel = TcpStream::connect(stream.peer_addr()?)?;
+    channel.set_read_timeout(Some(Duration::from_secs(5)))?;
+    println!("Successfully opened a second connection to the server.");
+
+    let mut channel_context = ConnectionContext::default();
+    channel_context.session_cipher = session_context.session_cipher.clone();
+    channel_context.tamper_preauth_integrity_hash = session_context.tamper_preauth_integrity_hash;
+    channel_context.credentials = session_context.credentials.clone();
+    send_negotiate(&mut channel, &mut channel_context, None);
+
+    if *scenario == SessionScenario::Binding {
+        channel_context.signing_key = session_context.signing_key.clone();
+        channel_context.signing_algorithm = session_context.signing_algorithm.clone();
+    }
+
+    Ok((channel, channel_context))
+}
+
+/// Sends the first session setup request of the scenario on the established session
+/// and returns the server response.
+pub fn send_session_scenario_negotiate_request_and_get_response(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    scenario: &SessionScenario,
+    session_id: Vec<u8>,
+    fuzzing_strategy: Option<FuzzingStrategy>,
+) -> Vec<u8> {
+    let mut response = vec![0; 300];
+    let session_setup_request_1 =
+        packets::prepare_session_scenario_negotiate_packet(fuzzing_strategy, scenario, session_id);
+
+    stream
+        .write_all(&context.prepare_request(session_setup_request_1)[..])
+        .unwrap();
+    println!(
+        "Sent {:?} Session Setup Request 1, awaiting reply...",
+        scenario
+    );
+    match receive_response(stream, context) {
+        Ok(session_setup_response) => {
+            response = session_setup_response;
+            println!("Successfully received session setup response 1 from server.");
+        }
+        Err(e) => {
+            println!("Failed to receive session setup response: {}", e);
+        }
+    }
+
+    response
+}
+
+/// Sends the second session setup request of the scenario on the established session.
+/// Once the default request authenticated successfully, the keys are set up for the scenario:
+/// the re-authentication keeps the keys of the session, the binding derives the signing key
+/// of the channel and keeps the encryption keys of the session, and the reconnect establishes
+/// signing and encryption of the new session. Returns the session id of the response.
+pub fn send_session_scenario_authenticate_request(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    session_context: &ConnectionContext,
+    scenario: &SessionScenario,
+    session_id: Vec<u8>,
+    session_setup_response: Vec<u8>,
+    fuzzing_strategy: Option<FuzzingStrategy>,
+) -> Vec<u8> {
+    if session_setup_response.len() < 4 + HEADER_LENGTH {
+        println!("Invalid session setup response 1.");
+        return vec![0; 8];
+    }
+    let (response_header, response_body) = decode_session_setup_response(session_setup_response);
+    let (session_setup_request_2, session_key) =
+        packets::prepare_session_scenario_authenticate_packet(
+            fuzzing_strategy,
+            scenario,
+            session_id,
+            response_header.session_id,
+            response_body,
+            &context.credentials,
+        );
+
+    stream
+        .write_all(&context.prepare_request(session_setup_request_2)[..])
+        .unwrap();
+    println!(
+        "Sent {:?} Session Setup Request 2, awaiting reply...",
+        scenario
+    );
+    match receive_response(stream, context) {
+        Ok(response) => {
+            println!("Successfully received session setup response 2 from server.");
+            if response.len() < 4 + HEADER_LENGTH {
+                return vec![0; 8];
+            }
+            if response[4 + 8..4 + 12] != [0; 4] {
+                println!("{:?} failed.", scenario);
+            } else if !session_key.is_empty() {
+                match scenario {
+                    SessionScenario::Reauthentication => {}
+                    SessionScenario::Binding => {
+                        context.establish_signing(&session_key, None);
+                        context.encryption_cipher = session_context.encryption_cipher.clone();
+                        context.encryption_key = session_context.encryption_key.clone();
+                        context.decryption_key = session_context.decryption_key.clone();
+                    }
+                    SessionScenario::Reconnect => {
+                        context.set_session_flags(&response);
+                        if !context.is_guest_or_null_session() {
+                            context.establish_signing(&session_key, None);
+                            context.establish_encryption(&session_key);
+                        }
+                    }
+                }
+            }
+
+            decode_response_header(response[4..4 + HEADER_LENGTH].to_vec()).session_id
+        }
         Err(e) => {
             println!("Failed to receive session setup 2 response: {}", e);
+            vec![0; 8]
         }
     }
 }
@@ -175,78 +435,240 @@ pub fn send_session_setup_authenticate_request(
 /// Sends a tree connect request and returns the server response.
 pub fn send_tree_connect_request_and_get_response(
     stream: &mut TcpStream,
+    context: &mut ConnectionContext,
     session_id: Vec<u8>,
     fuzzing_strategy: Option<FuzzingStrategy>,
-) -> [u8; 300] {
-    let mut buffer: [u8; 300] = [0; 300];
-
+) -> Vec<u8> {
     let tree_connect_request = packets::prepare_tree_connect_packet(fuzzing_strategy, session_id);
 
-    stream.write_all(&tree_connect_request[..]).unwrap();
+    stream
+        .write_all(&context.prepare_request(tree_connect_request)[..])
+        .unwrap();
     println!("Sent Tree Connect request, awaiting reply...");
-    match stream.read(&mut buffer) {
-        Ok(_) => println!("Successfully received Tree Connect response from server."),
+    match receive_response(stream, context) {
+        Ok(response) => {
+            println!("Successfully received Tree Connect response from server.");
+            response
+        }
         Err(e) => {
             println!("Failed to receive Tree Connect response: {}", e);
+            Vec::new()
         }
     }
-
-    buffer
 }
 
 /// Sends a create request and returns the server response.
 pub fn send_create_request_and_get_response(
     stream: &mut TcpStream,
+    context: &mut ConnectionContext,
     session_id: Vec<u8>,
     tree_id: Vec<u8>,
     fuzzing_strategy: Option<FuzzingStrategy>,
-) -> [u8; 300] {
-    let mut buffer: [u8; 300] = [0; 300];
+) -> Vec<u8> {
     let create_request = packets::prepare_create_packet(fuzzing_strategy, session_id, tree_id);
 
-    stream.write_all(&create_request[..]).unwrap();
+    stream
+        .write_all(&context.prepare_request(create_request)[..])
+        .unwrap();
     println!("Sent Create request, awaiting reply...");
-    match stream.read(&mut buffer) {
-        Ok(_) => println!("Successfully received Create response from server."),
+    match receive_response(stream, context) {
+        Ok(response) => {
+            println!("Successfully received Create response from server.");
+            response
+        }
         Err(e) => {
             println!("Failed to receive Create response: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Sends a create request that opens the default file with the durable handle of the durable open
+/// and returns the server response. The file id of a successful response is stored in the durable open.
+pub fn send_durable_create_request_and_get_response(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    session_id: Vec<u8>,
+    tree_id: Vec<u8>,
+    durable_open: &mut DurableOpen,
+) -> Vec<u8> {
+    let create_request = packets::prepare_durable_create_packet(session_id, tree_id, durable_open);
+
+    stream
+        .write_all(&context.prepare_request(create_request)[..])
+        .unwrap();
+    println!("Sent durable Create request, awaiting reply...");
+    match receive_response(stream, context) {
+        Ok(response) => {
+            println!("Successfully received durable Create response from server.");
+            if response.len() >= 4 + HEADER_LENGTH + 88 && response[4 + 8..4 + 12] == [0; 4] {
+                let create_response =
+                    decode_create_response_body(response[4 + HEADER_LENGTH..].to_vec());
+                if create_response.create_contexts_length == [0; 4] {
+                    println!("The server did not grant the durable handle.");
+                }
+                durable_open.file_id = create_response.file_id;
+            } else {
+                println!("Failed to open the file with a durable handle.");
+            }
+
+            response
+        }
+        Err(e) => {
+            println!("Failed to receive durable Create response: {}", e);
+            Vec::new()
         }
     }
+}
+
+/// Drops the connection and sets up a new connection to the same server, on which a session is
+/// authenticated with the given credentials and the share is connected again. The stream and the
+/// context are replaced by the ones of the new connection. Returns the new session and tree id.
+pub fn reconnect_after_connection_loss(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    credentials: Credentials,
+) -> std::io::Result<(Vec<u8>, Vec<u8>)> {
+    let peer_address = stream.peer_addr()?;
+    if let Err(e) = stream.shutdown(Shutdown::Both) {
+        println!("Failed to drop the connection: {}", e);
+    }
+    let reconnected_stream = TcpStream::connect(peer_address)?;
+    reconnected_stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    println!("Dropped the connection and reconnected to the server.");
 
-    buffer
+    let mut reconnected_context = ConnectionContext::default();
+    reconnected_context.session_cipher = context.session_cipher.clone();
+    reconnected_context.tamper_preauth_integrity_hash = context.tamper_preauth_integrity_hash;
+    reconnected_context.kerberos = context.kerberos.clone();
+    reconnected_context.durable_handle_version = context.durable_handle_version.clone();
+    reconnected_context.credentials = credentials;
+
+    *stream = reconnected_stream;
+    *context = reconnected_context;
+
+    Ok(State::go_to_tree_connect_state(stream, context))
+}
+
+/// Sends a create request that reclaims the durable open and returns the server response.
+pub fn send_durable_reconnect_request_and_get_response(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    session_id: Vec<u8>,
+    tree_id: Vec<u8>,
+    durable_open: &DurableOpen,
+    fuzzing_strategy: Option<FuzzingStrategy>,
+) -> Vec<u8> {
+    let create_request = packets::prepare_durable_reconnect_packet(
+        fuzzing_strategy,
+        session_id,
+        tree_id,
+        durable_open,
+        &[],
+    );
+
+    stream
+        .write_all(&context.prepare_request(create_request)[..])
+        .unwrap();
+    println!("Sent durable reconnect Create request, awaiting reply...");
+    match receive_response(stream, context) {
+        Ok(response) => {
+            println!("Successfully received durable reconnect Create response from server.");
+            response
+        }
+        Err(e) => {
+            println!("Failed to receive durable reconnect Create response: {}", e);
+            Vec::new()
+        }
+    }
 }
 
 /// Sends a query info request.
 pub fn send_query_info_request(
     stream: &mut TcpStream,
+    context: &mut ConnectionContext,
     session_id: Vec<u8>,
     tree_id: Vec<u8>,
     file_id: Vec<u8>,
     fuzzing_strategy: Option<FuzzingStrategy>,
 ) {
-    let mut buffer: [u8; 300] = [0; 300];
     let query_info_request =
         packets::prepare_query_info_packet(fuzzing_strategy, session_id, tree_id, file_id);
 
-    stream.write_all(&query_info_request[..]).unwrap();
+    stream
+        .write_all(&context.prepare_request(query_info_request.clone())[..])
+        .unwrap();
     println!("Sent Query Info request, awaiting reply...");
-    match stream.read(&mut buffer) {
-        Ok(_) => println!("Successfully received Query Info response from server."),
+    match receive_response(stream, context) {
+        Ok(response) => {
+            println!("Successfully received Query Info response from server.");
+            report_potentially_leaked_query_info_bytes(&query_info_request, &response);
+        }
         Err(e) => {
             println!("Failed to receive Query Info response: {}", e);
         }
     }
 }
 
+/// Decodes the output buffer of a query info response (including the netbios session prefix)
+/// according to the info type and file info class of the request and reports the bytes the server
+/// potentially leaked in it. Only successful responses and responses with STATUS_BUFFER_OVERFLOW
+/// carry an output buffer, other responses and output buffers that exceed the response are ignored.
+pub fn report_potentially_leaked_query_info_bytes(request: &[u8], response: &[u8]) {
+    if request.len() < 4 + HEADER_LENGTH + 4
+        || response.len() < 4 + HEADER_LENGTH + 8
+        || !matches!(response[4 + 8..4 + 12], [0, 0, 0, 0] | [5, 0, 0, 0x80])
+    {
+        return;
+    }
+
+    let query_info_response =
+        decode_query_info_response_body(response[4 + HEADER_LENGTH..].to_vec());
+    let output_buffer_offset =
+        convert_byte_array_to_int(query_info_response.output_buffer_offset, false) as usize;
+    let output_buffer_length =
+        convert_byte_array_to_int(query_info_response.output_buffer_length, false) as usize;
+    let output_buffer = match response
+        .get(4 + output_buffer_offset..4 + output_buffer_offset + output_buffer_length)
+    {
+        Some(output_buffer) => output_buffer.to_vec(),
+        None => {
+            println!("Query Info output buffer exceeds the response.");
+            return;
+        }
+    };
+
+    let (decoded_output_buffer, trailing_bytes) = decode_query_info_output_buffer(
+        vec![request[4 + HEADER_LENGTH + 2]],
+        vec![request[4 + HEADER_LENGTH + 3]],
+        output_buffer,
+    );
+    let leaked_bytes = find_potentially_leaked_bytes(&decoded_output_buffer, &trailing_bytes);
+    if !leaked_bytes.is_empty() {
+        println!(
+            "Query Info response potentially leaks {} bytes: {:02x?}",
+            leaked_bytes.len(),
+            leaked_bytes
+        );
+    }
+}
+
 /// Sends an echo request.
-pub fn send_echo_request(stream: &mut TcpStream, fuzzing_strategy: Option<FuzzingStrategy>) {
-    let mut buffer: [u8; 300] = [0; 300];
+pub fn send_echo_request(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    fuzzing_strategy: Option<FuzzingStrategy>,
+) {
     let echo_request = packets::prepare_echo_packet(fuzzing_strategy);
 
-    stream.write_all(&echo_request[..]).unwrap();
+    stream
+        .write_all(&context.prepare_request(echo_request)[..])
+        .unwrap();
     println!("Sent Echo request, awaiting reply...");
-    match stream.read(&mut buffer) {
-        Ok(_) => println!("Successfully received Echo response from server."),
+    match receive_response(stream, context) {
+        Ok(_) => {
+            println!("Successfully received Echo response from server.");
+        }
         Err(e) => {
             println!("Failed to receive Echo response: {}", e);
         }
@@ -256,22 +678,394 @@ pub fn send_echo_request(stream: &mut TcpStream, fuzzing_strategy: Option<Fuzzin
 /// Sends a close request.
 pub fn send_close_request(
     stream: &mut TcpStream,
+    context: &mut ConnectionContext,
     session_id: Vec<u8>,
     tree_id: Vec<u8>,
     file_id: Vec<u8>,
     fuzzing_strategy: Option<FuzzingStrategy>,
 ) {
-    let mut buffer: [u8; 300] = [0; 300];
-
     let close_request =
         packets::prepare_close_packet(fuzzing_strategy, session_id, tree_id, file_id);
 
-    stream.write_all(&close_request[..]).unwrap();
+    stream
+        .write_all(&context.prepare_request(close_request)[..])
+        .unwrap();
     println!("Sent Close request, awaiting reply...");
-    match stream.read(&mut buffer) {
-        Ok(_) => println!("Successfully received Close response from server."),
+    match receive_response(stream, context) {
+        Ok(_) => {
+            println!("Successfully received Close response from server.");
+        }
         Err(e) => {
             println!("Failed to receive Close response: {}", e);
         }
     }
 }
+
+/// Sends a lock request.
+pub fn send_lock_request(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    session_id: Vec<u8>,
+    tree_id: Vec<u8>,
+    file_id: Vec<u8>,
+    fuzzing_strategy: Option<FuzzingStrategy>,
+) {
+    let lock_request = packets::prepare_lock_packet(fuzzing_strategy, session_id, tree_id, file_id);
+
+    stream
+        .write_all(&context.prepare_request(lock_request)[..])
+        .unwrap();
+    println!("Sent Lock request, awaiting reply...");
+    match receive_response(stream, context) {
+        Ok(_) => {
+            println!("Successfully received Lock response from server.");
+        }
+        Err(e) => {
+            println!("Failed to receive Lock response: {}", e);
+        }
+    }
+}
+
+/// Sends a flush request.
+pub fn send_flush_request(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    session_id: Vec<u8>,
+    tree_id: Vec<u8>,
+    file_id: Vec<u8>,
+    fuzzing_strategy: Option<FuzzingStrategy>,
+) {
+    let flush_request =
+        packets::prepare_flush_packet(fuzzing_strategy, session_id, tree_id, file_id);
+
+    stream
+        .write_all(&context.prepare_request(flush_request)[..])
+        .unwrap();
+    println!("Sent Flush request, awaiting reply...");
+    match receive_response(stream, context) {
+        Ok(_) => {
+            println!("Successfully received Flush response from server.");
+        }
+        Err(e) => {
+            println!("Failed to receive Flush response: {}", e);
+        }
+    }
+}
+
+/// Sends a compound request and prints the status of each response in the compound response.
+pub fn send_compound_request_and_decode_responses(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    session_id: Vec<u8>,
+    tree_id: Vec<u8>,
+    fuzzing_strategy: Option<FuzzingStrategy>,
+) {
+    let compound_request = packets::prepare_compound_packet(fuzzing_strategy, session_id, tree_id);
+
+    stream
+        .write_all(&context.prepare_request(compound_request)[..])
+        .unwrap();
+    println!("Sent Compound request, awaiting reply...");
+    match receive_response(stream, context) {
+        Ok(response) => {
+            for (response_header, _) in decode_compound_response(response) {
+                let generic_header = match response_header {
+                    Header::Sync(sync_header) => sync_header.generic,
+                    Header::Async(async_header) => async_header.generic,
+                };
+                println!(
+                    "Received compounded response for command {:?} with status {:?}.",
+                    generic_header.command, generic_header.status
+                );
+            }
+        }
+        Err(e) => println!("Failed to receive Compound response: {}", e),
+    }
+}
+
+/// Receives a single message from the stream, decrypts it if it is encrypted
+/// and adds the credits granted in it to the context. Negotiate and session setup
+/// responses are added to the preauth integrity hash.
+/// If signing is established, the signature of an unencrypted message is verified.
+pub fn receive_response(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+) -> std::io::Result<Vec<u8>> {
+    let response = receive_message(stream)?;
+    let encrypted = is_transform_message(&response[4..]);
+    let response = decompress_response(&context.decrypt_response(&response));
+    context.grant_credits(&response);
+    context.update_preauth_integrity_hash(&response);
+    if !encrypted && !context.verify_response(&response) {
+        println!("Received response with invalid signature.");
+    }
+
+    Ok(response)
+}
+
+/// Decompresses the response (including the netbios session prefix) if it is compressed.
+/// Returns the decompressed message with a recomputed netbios session prefix,
+/// or the response unchanged if it is not compressed or fails to decompress.
+pub fn decompress_response(response: &[u8]) -> Vec<u8> {
+    if response.len() < 4 || !is_compressed_message(&response[4..]) {
+        return response.to_vec();
+    }
+
+    let message_length = u32::from_be_bytes([0, response[1], response[2], response[3]]) as usize;
+    let compressed_message = response[4..(4 + message_length).min(response.len())].to_vec();
+
+    match decode_compression_transform(compressed_message)
+        .and_then(|transform| compression::decompress_transform(&transform))
+    {
+        Some(mut message) => {
+            let mut decompressed_response = serialize_netbios_session_prefix(message.len());
+            decompressed_response.append(&mut message);
+            decompressed_response
+        }
+        None => {
+            println!("Failed to decompress response.");
+            response.to_vec()
+        }
+    }
+}
+
+/// Receives a single message from the stream by reading the netbios session prefix
+/// and the number of bytes it announces.
+/// Returns the complete message including the netbios session prefix.
+pub fn receive_message(stream: &mut TcpStream) -> std::io::Result<Vec<u8>> {
+    let mut prefix: [u8; 4] = [0; 4];
+    stream.read_exact(&mut prefix)?;
+
+    let message_length = u32::from_be_bytes([0, prefix[1], prefix[2], prefix[3]]) as usize;
+    let mut message = prefix.to_vec();
+    message.resize(message_length + 4, 0);
+    stream.read_exact(&mut message[4..])?;
+
+    Ok(message)
+}
+
+/// Sends a create request that opens the root directory of the share and returns the server response.
+pub fn send_directory_create_request_and_get_response(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    session_id: Vec<u8>,
+    tree_id: Vec<u8>,
+) -> Vec<u8> {
+    let create_request = packets::prepare_directory_create_packet(session_id, tree_id);
+
+    stream
+        .write_all(&context.prepare_request(create_request)[..])
+        .unwrap();
+    println!("Sent directory Create request, awaiting reply...");
+    match receive_response(stream, context) {
+        Ok(response) => {
+            println!("Successfully received directory Create response from server.");
+            response
+        }
+        Err(e) => {
+            println!("Failed to receive directory Create response: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Opens the root directory of the share and sends a default change notify request for it.
+/// Returns the async id of the pending notify and the message id of its request,
+/// or None if the directory cannot be opened or the notify does not pend.
+pub fn pend_change_notify(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    session_id: Vec<u8>,
+    tree_id: Vec<u8>,
+) -> Option<(Vec<u8>, u64)> {
+    let directory_create_response = send_directory_create_request_and_get_response(
+        stream,
+        context,
+        session_id.clone(),
+        tree_id.clone(),
+    );
+    if directory_create_response.len() < 4 + HEADER_LENGTH + 88 {
+        println!("Failed to open the directory.");
+        return None;
+    }
+    let file_id =
+        decode_create_response_body(directory_create_response[4 + HEADER_LENGTH..].to_vec())
+            .file_id;
+
+    let change_notify_request =
+        packets::prepare_change_notify_packet(None, session_id, tree_id, file_id);
+    stream
+        .write_all(&context.prepare_request(change_notify_request)[..])
+        .unwrap();
+    let message_id = context.last_message_id?;
+
+    receive_change_notify_interim_response(stream, context).map(|async_id| (async_id, message_id))
+}
+
+/// Receives the interim response of the change notify request that was sent last.
+/// Returns the async id of the pending notify, or None if the server
+/// completed or rejected the request right away.
+pub fn receive_change_notify_interim_response(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+) -> Option<Vec<u8>> {
+    println!("Sent Change Notify request, awaiting interim reply...");
+    match receive_response(stream, context) {
+        Ok(response) if is_interim_response(&response) => {
+            println!("Change Notify is pending.");
+            Some(response[36..44].to_vec())
+        }
+        Ok(_) => {
+            println!("Change Notify completed without interim response.");
+            None
+        }
+        Err(e) => {
+            println!("Failed to receive Change Notify response: {}", e);
+            None
+        }
+    }
+}
+
+/// Waits for the final response of the pending operation with the given async id.
+/// Responses to other requests are skipped.
+/// Returns None if the connection is closed or no final response arrives before the read timeout.
+pub fn await_async_completion(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    async_id: &[u8],
+) -> Option<Vec<u8>> {
+    loop {
+        match receive_response(stream, context) {
+            Ok(response) => {
+                if is_break_notification(&response) {
+                    println!("Skipped break notification while awaiting async response.");
+                } else if response.len() >= 68
+                    && is_async_header(&response[4..])
+                    && response[36..44] == async_id[..]
+                    && !is_interim_response(&response)
+                {
+                    println!("Successfully received final async response from server.");
+                    return Some(response);
+                }
+            }
+            Err(e) => {
+                println!("Failed to receive final async response: {}", e);
+                return None;
+            }
+        }
+    }
+}
+
+/// Sends a randomly chosen operation that races the pending change notify
+/// with the given async id and waits for the completion of the notify.
+pub fn race_pending_change_notify(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    session_id: Vec<u8>,
+    tree_id: Vec<u8>,
+    file_id: Vec<u8>,
+    async_id: &[u8],
+) -> Option<Vec<u8>> {
+    let operation = rand::random::<RacingOperation>();
+    let racing_request =
+        packets::prepare_racing_operation_packet(operation.clone(), session_id, tree_id, file_id);
+
+    if stream
+        .write_all(&context.prepare_request(racing_request)[..])
+        .is_err()
+    {
+        println!("Reset Connection.");
+        return None;
+    }
+    println!(
+        "Sent {:?} request racing the pending Change Notify.",
+        operation
+    );
+
+    await_async_completion(stream, context, async_id)
+}
+
+/// Waits for an oplock or lease break notification the server sends unsolicited.
+/// Responses to outstanding requests are skipped.
+/// Returns None if the connection is closed or no notification arrives before the read timeout.
+pub fn receive_break_notification(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+) -> Option<Vec<u8>> {
+    loop {
+        match receive_response(stream, context) {
+            Ok(response) => {
+                if is_break_notification(&response) {
+                    println!("Received break notification from server.");
+                    return Some(response);
+                }
+            }
+            Err(e) => {
+                println!("Failed to receive break notification: {}", e);
+                return None;
+            }
+        }
+    }
+}
+
+/// Opens the default file with a batch oplock and opens the same file from a second connection
+/// to the same server, which logs on with the same credentials and configuration. Returns the break notification this provokes on the first connection,
+/// or None if no notification arrives.
+pub fn provoke_oplock_break(
+    stream: &mut TcpStream,
+    context: &mut ConnectionContext,
+    session_id: Vec<u8>,
+    tree_id: Vec<u8>,
+) -> Option<BreakNotification> {
+    let oplock_create_request = packets::prepare_oplock_create_packet(session_id, tree_id);
+    stream
+        .write_all(&context.prepare_request(oplock_create_request)[..])
+        .unwrap();
+    println!("Sent oplock Create request, awaiting reply...");
+    if let Err(e) = receive_response(stream, context) {
+        println!("Failed to receive oplock Create response: {}", e);
+        return None;
+    }
+
+    let mut second_stream = match stream.peer_addr().and_then(TcpStream::connect) {
+        Ok(second_stream) => second_stream,
+        Err(e) => {
+            println!("Failed to open second connection: {}", e);
+            return None;
+        }
+    };
+    second_stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .expect("Failed to set read time out.");
+    let mut second_context = ConnectionContext::default();
+    second_context.session_cipher = context.session_cipher.clone();
+    second_context.tamper_preauth_integrity_hash = context.tamper_preauth_integrity_hash;
+    second_context.kerberos = context.kerberos.clone();
+    second_context.credentials = context.credentials.clone();
+    let (second_session_id, second_tree_id) =
+        State::go_to_tree_connect_state(&mut second_stream, &mut second_context);
+    let conflicting_create_request =
+        packets::prepare_create_packet(None, second_session_id, second_tree_id);
+    second_stream
+        .write_all(&second_context.prepare_request(conflicting_create_request)[..])
+        .unwrap();
+    println!("Sent conflicting Create request on second connection.");
+
+    receive_break_notification(stream, context)
+        .map(|break_notification| decode_break_notification(break_notification).1)
+}
+
+/// Receives the response to the oplock break acknowledgment that was sent last.
+pub fn receive_oplock_break_response(stream: &mut TcpStream, context: &mut ConnectionContext) {
+    println!("Sent Oplock Break acknowledgment, awaiting reply...");
+    match receive_response(stream, context) {
+        Ok(response) if response.len() >= 4 + HEADER_LENGTH => println!(
+            "Received Oplock Break response with status {:?}.",
+            decode_response_header(response[4..4 + HEADER_LENGTH].to_vec())
+                .generic
+                .status
+        ),
+        Ok(_) => println!("Received an invalid Oplock Break response."),
+        Err(e) => println!("Failed to receive Oplock Break response: {}", e),
+    }
+}
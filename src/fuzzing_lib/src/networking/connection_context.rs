@@ -0,0 +1,801 @@
+//! The connection context tracks the credits the server grants on a connection
+//! and allocates the message ids of the requests that are sent on it.
+
+use rand::Rng;
+
+use crate::crypto::{
+    calculate_preauth_integrity_hash, encryption,
+    signing::{self, SigningAlgorithm},
+    PREAUTH_INTEGRITY_HASH_LENGTH,
+};
+use crate::format::decoder::spnego_decoder::decode_negotiation_token;
+use crate::format::{
+    convert_byte_array_to_int,
+    decoder::{
+        is_transform_message, negotiate_decoder::decode_negotiate_response_body,
+        split_compound_response,
+    },
+    encoder::serialize_netbios_session_prefix,
+    HEADER_LENGTH,
+};
+use crate::fuzzer::{
+    durable_handle_fuzzer::DurableHandleVersion, transform_header_fuzzer::fuzz_transform_header,
+    FuzzingStrategy,
+};
+use crate::gss::{NegTokenInit2, NegotiationToken};
+use crate::kerberos::KerberosConfig;
+use crate::networking::negotiated_connection::NegotiatedConnection;
+use crate::ntlmssp::credentials::Credentials;
+use crate::smb2::{
+    header::{Commands, Flags, PROTOCOL_ID},
+    helper_functions::negotiate_context::Ciphers,
+    responses::{negotiate::DialectRevision, session_setup::SessionFlags},
+};
+
+/// The number of payload bytes that a single credit covers (64 KiB).
+pub const CREDIT_PAYLOAD_SIZE: u32 = 0x10000;
+
+/// The connection context holds the credit and message id state of a connection
+/// as well as the negotiated connection and the signing and encryption state of the session.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ConnectionContext {
+    /// The message id that is allocated to the next request.
+    pub next_message_id: u64,
+    /// The credits granted by the server that have not been consumed yet.
+    pub available_credits: u32,
+    /// The message id of the most recently sequenced request.
+    pub last_message_id: Option<u64>,
+    /// The parameters the server selected in the negotiate response, including the
+    /// values of the negotiate contexts of the SMB 3.1.1 dialect.
+    pub negotiated_connection: Option<NegotiatedConnection>,
+    /// The key with which requests are signed once signing is established.
+    pub signing_key: Option<Vec<u8>>,
+    /// The algorithm with which requests are signed once signing is established.
+    pub signing_algorithm: Option<SigningAlgorithm>,
+    /// The cipher that is forced on the session in place of the negotiated cipher, so that the
+    /// server is also confronted with ciphers it did not negotiate.
+    pub session_cipher: Option<Ciphers>,
+    /// The cipher with which the session is encrypted once encryption is established.
+    pub encryption_cipher: Option<Ciphers>,
+    /// The key with which requests are encrypted once encryption is established.
+    pub encryption_key: Option<Vec<u8>>,
+    /// The key with which responses are decrypted once encryption is established.
+    pub decryption_key: Option<Vec<u8>>,
+    /// The preauth integrity hash of the connection over the negotiate request and response.
+    pub preauth_integrity_hash: Vec<u8>,
+    /// The preauth integrity hash of the session, which continues the hash of the connection
+    /// over the session setup requests and responses.
+    pub session_preauth_integrity_hash: Option<Vec<u8>>,
+    /// Whether a random bit of every message is flipped before it is added to the preauth integrity hash.
+    pub tamper_preauth_integrity_hash: bool,
+    /// The credentials with which the session setup authenticates.
+    pub credentials: Credentials,
+    /// The SPNEGO token of the negotiate response, which announces the mechanisms
+    /// and the hints of the server. A plain NegTokenInit is stored without hints.
+    pub server_negotiation_token: Option<NegTokenInit2>,
+    /// The session flags of the successful session setup response, which tell
+    /// whether the server authenticated the session as guest or as anonymous (null) session.
+    pub session_flags: Option<SessionFlags>,
+    /// The KDC and the service principal with which the session setup authenticates via Kerberos.
+    /// Without a configuration, the session setup authenticates via NTLM.
+    pub kerberos: Option<KerberosConfig>,
+    /// The version of the durable handle with which the file is opened in the durable open state.
+    pub durable_handle_version: DurableHandleVersion,
+}
+
+impl ConnectionContext {
+    /// Creates a new connection context. Before the first response arrives,
+    /// the client holds a single credit for the negotiate request.
+    pub fn default() -> Self {
+        ConnectionContext {
+            next_message_id: 0,
+            available_credits: 1,
+            last_message_id: None,
+            negotiated_connection: None,
+            signing_key: None,
+            signing_algorithm: None,
+            session_cipher: None,
+            encryption_cipher: None,
+            encryption_key: None,
+            decryption_key: None,
+            preauth_integrity_hash: vec![0; PREAUTH_INTEGRITY_HASH_LENGTH],
+            session_preauth_integrity_hash: None,
+            tamper_preauth_integrity_hash: false,
+            credentials: Credentials::default(),
+            server_negotiation_token: None,
+            session_flags: None,
+            kerberos: None,
+            durable_handle_version: DurableHandleVersion::V2,
+        }
+    }
+
+    /// Returns the credit charge of a request that transfers the given number of payload bytes.
+    /// Every started 64 KiB of payload consumes one credit, but at least one credit is charged.
+    pub fn calculate_credit_charge(payload_size: u32) -> u16 {
+        let credit_charge = payload_size.saturating_sub(1) / CREDIT_PAYLOAD_SIZE + 1;
+
+        credit_charge.min(u16::MAX as u32) as u16
+    }
+
+    /// Allocates the message id of a request with the given credit charge.
+    /// The request consumes as many message ids as it is charged credits.
+    /// If the granted credits do not suffice, the message id is allocated nevertheless.
+    pub fn allocate_message_id(&mut self, credit_charge: u16) -> u64 {
+        let credit_charge = credit_charge.max(1) as u32;
+        if credit_charge > self.available_credits {
+            println!(
+                "Insufficient credits: charged {}, granted {}.",
+                credit_charge, self.available_credits
+            );
+        }
+
+        let message_id = self.next_message_id;
+        self.next_message_id += credit_charge as u64;
+        self.available_credits = self.available_credits.saturating_sub(credit_charge);
+        self.last_message_id = Some(message_id);
+
+        message_id
+    }
+
+    /// Adds the credits granted in the headers of the response (including the netbios session prefix).
+    /// The credits of every response in a compound response are added.
+    pub fn grant_credits(&mut self, response: &[u8]) {
+        if response.len() < 4 + HEADER_LENGTH || response[4..8] != PROTOCOL_ID[..] {
+            return;
+        }
+
+        for response in split_compound_response(&response[4..]) {
+            let granted_credits = convert_byte_array_to_int(response[14..16].to_vec(), false);
+            self.available_credits = self.available_credits.saturating_add(granted_credits);
+        }
+    }
+
+    /// Sets the credit charge and the message id of every request in the serialized request
+    /// (including the netbios session prefix), following the NextCommand fields of compound requests.
+    /// Cancel requests carry the message id of the request they cancel and consume no credits.
+    /// Negotiate requests keep their credit charge, since the dialect is not known yet.
+    /// Unless the negotiated connection supports multi-credit requests, the credit charge is 0
+    /// and every request consumes a single message id.
+    pub fn sequence_request(&mut self, mut request: Vec<u8>) -> Vec<u8> {
+        let supports_multi_credit = self
+            .negotiated_connection
+            .as_ref()
+            .is_some_and(NegotiatedConnection::supports_multi_credit);
+        let mut offset = 4;
+
+        while request.len() >= offset + HEADER_LENGTH {
+            let command = request[offset + 12..offset + 14].to_vec();
+            let next_command =
+                convert_byte_array_to_int(request[offset + 20..offset + 24].to_vec(), false);
+
+            if command != Commands::Cancel.unpack_byte_code() {
+                let credit_charge = if supports_multi_credit {
+                    Self::calculate_credit_charge(calculate_payload_size(
+                        &command,
+                        &request[offset + HEADER_LENGTH..],
+                    ))
+                } else {
+                    0
+                };
+                let message_id = self.allocate_message_id(credit_charge);
+
+                if command != Commands::Negotiate.unpack_byte_code() {
+                    request[offset + 6..offset + 8].copy_from_slice(&credit_charge.to_le_bytes());
+                }
+                request[offset + 24..offset + 32].copy_from_slice(&message_id.to_le_bytes());
+            }
+
+            if (next_command as usize) < HEADER_LENGTH {
+                break;
+            }
+            offset += next_command as usize;
+        }
+
+        request
+    }
+
+    /// Sequences and, depending on the session, encrypts or signs the serialized request.
+    /// Negotiate and session setup requests are added to the preauth integrity hash as plain
+    /// SMB2 messages, i.e. after signing, but before encryption.
+    pub fn prepare_request(&mut self, request: Vec<u8>) -> Vec<u8> {
+        let request = self.sequence_request(request);
+        let request = match self.encryption_key {
+            Some(_) => request,
+            None => self.sign_request(request),
+        };
+        self.update_preauth_integrity_hash(&request);
+
+        self.encrypt_request(request, None)
+    }
+
+    /// Adds the message (including the netbios session prefix) to the preauth integrity hash.
+    /// Negotiate requests and responses continue the hash of the connection, session setup
+    /// requests and responses continue the hash of the session, which starts with the hash of
+    /// the connection. The final, successful session setup response is not part of the hash.
+    /// The hash is kept regardless of the dialect, but it is only used by the SMB 3.1.1 dialect.
+    pub fn update_preauth_integrity_hash(&mut self, message: &[u8]) {
+        if message.len() < 4 + HEADER_LENGTH || message[4..8] != PROTOCOL_ID[..] {
+            return;
+        }
+
+        let message_length = u32::from_be_bytes([0, message[1], message[2], message[3]]) as usize;
+        let mut hash_input = message[4..(4 + message_length).min(message.len())].to_vec();
+        let command = hash_input[12..14].to_vec();
+        let is_response = hash_input[16] & Flags::ServerToRedir.unpack_byte_code()[0] != 0;
+
+        if self.tamper_preauth_integrity_hash {
+            let mut rng = rand::thread_rng();
+            let index = rng.gen_range(0..hash_input.len());
+            hash_input[index] ^= 1 << rng.gen_range(0..8);
+        }
+
+        if command == Commands::Negotiate.unpack_byte_code() {
+            self.preauth_integrity_hash =
+                calculate_preauth_integrity_hash(&self.preauth_integrity_hash, &hash_input);
+        } else if command == Commands::SessionSetup.unpack_byte_code()
+            && !(is_response && message[4 + 8..4 + 12] == [0; 4])
+        {
+            let session_hash = self
+                .session_preauth_integrity_hash
+                .take()
+                .unwrap_or_else(|| self.preauth_integrity_hash.clone());
+            self.session_preauth_integrity_hash =
+                Some(calculate_preauth_integrity_hash(&session_hash, &hash_input));
+        }
+    }
+
+    /// Returns the dialect the server selected in the negotiate response, if the connection is negotiated.
+    pub fn negotiated_dialect(&self) -> Option<DialectRevision> {
+        self.negotiated_connection
+            .as_ref()
+            .and_then(|connection| connection.dialect.clone())
+    }
+
+    /// Stores the parameters of a successful negotiate response (including the netbios session prefix)
+    /// as the negotiated connection. Failed and malformed responses and the wildcard dialect are ignored.
+    pub fn set_negotiated_connection(&mut self, response: &[u8]) {
+        if response.len() < 4 + HEADER_LENGTH
+            || response[4 + 8..4 + 12] != [0; 4]
+            || response[4 + 12..4 + 14] != Commands::Negotiate.unpack_byte_code()[..]
+        {
+            return;
+        }
+
+        if let Some(negotiate_response) =
+            decode_negotiate_response_body(response[4 + HEADER_LENGTH..].to_vec())
+        {
+            if negotiate_response.dialect_revision != Some(DialectRevision::Wildcard) {
+                self.negotiated_connection = Some(NegotiatedConnection::from_negotiate_response(
+                    &negotiate_response,
+                ));
+            }
+        }
+    }
+
+    /// Stores the SPNEGO token of the security buffer of the negotiate response
+    /// (including the netbios session prefix). Missing or malformed tokens are ignored.
+    pub fn set_server_negotiation_token(&mut self, response: &[u8]) {
+        if response.len() < 4 + HEADER_LENGTH + 60
+            || response[4 + 12..4 + 14] != Commands::Negotiate.unpack_byte_code()[..]
+        {
+            return;
+        }
+
+        let offset = convert_byte_array_to_int(
+            response[4 + HEADER_LENGTH + 56..4 + HEADER_LENGTH + 58].to_vec(),
+            false,
+        ) as usize;
+        let length = convert_byte_array_to_int(
+            response[4 + HEADER_LENGTH + 58..4 + HEADER_LENGTH + 60].to_vec(),
+            false,
+        ) as usize;
+        let security_buffer = match response.get(4 + offset..4 + offset + length) {
+            Some(security_buffer) => security_buffer,
+            None => return,
+        };
+
+        self.server_negotiation_token = match decode_negotiation_token(security_buffer) {
+            Some(NegotiationToken::NegTokenInit2(token)) => Some(token),
+            Some(NegotiationToken::NegTokenInit(token)) => {
+                let mut token_init2 = NegTokenInit2::default();
+                token_init2.mech_types = token.mech_types;
+                token_init2.req_flags = token.req_flags;
+                token_init2.mech_token = token.mech_token;
+                token_init2.mech_list_mic = token.mech_list_mic;
+                Some(token_init2)
+            }
+            _ => None,
+        };
+    }
+
+    /// Stores the session flags of a successful session setup response (including the netbios
+    /// session prefix). Failed responses and unknown flags leave the session flags unset.
+    pub fn set_session_flags(&mut self, response: &[u8]) {
+        if response.len() < 4 + HEADER_LENGTH + 4
+            || response[4 + 8..4 + 12] != [0; 4]
+            || response[4 + 12..4 + 14] != Commands::SessionSetup.unpack_byte_code()[..]
+        {
+            return;
+        }
+
+        self.session_flags = match response[4 + HEADER_LENGTH + 2] {
+            0 | 1 | 2 | 4 => Some(SessionFlags::map_byte_code_to_session_flags(
+                response[4 + HEADER_LENGTH + 2..4 + HEADER_LENGTH + 4].to_vec(),
+            )),
+            _ => None,
+        };
+    }
+
+    /// Returns whether the session was authenticated as guest or anonymous session.
+    /// These sessions are neither signed nor encrypted.
+    pub fn is_guest_or_null_session(&self) -> bool {
+        matches!(
+            self.session_flags,
+            Some(SessionFlags::IsGuest) | Some(SessionFlags::IsNull)
+        )
+    }
+
+    /// Returns the preauth integrity hash that the SMB 3.1.1 dialect uses as the KDF context:
+    /// the hash of the session, or the hash of the connection if no session setup was sent yet.
+    pub fn session_key_derivation_context(&self) -> Vec<u8> {
+        self.session_preauth_integrity_hash
+            .clone()
+            .unwrap_or_else(|| self.preauth_integrity_hash.clone())
+    }
+
+    /// Establishes signing with the key derived from the session key. The signing algorithm
+    /// is selected by the negotiated dialect and the algorithm id of the SIGNING_CAPABILITIES
+    /// context. Without a given algorithm id, the one of the negotiated connection is used.
+    /// Without a negotiated dialect, SMB 2.0.2 is assumed.
+    pub fn establish_signing(&mut self, session_key: &[u8], signing_algorithm_id: Option<&[u8]>) {
+        let dialect = self.negotiated_dialect().unwrap_or(DialectRevision::Smb202);
+        let preauth_integrity_hash = self.session_key_derivation_context();
+        let negotiated_signing_algorithm_id = self
+            .negotiated_connection
+            .as_ref()
+            .and_then(|connection| connection.signing_algorithm.as_ref())
+            .map(|algorithm| algorithm.unpack_byte_code());
+
+        self.signing_algorithm = Some(SigningAlgorithm::select_signing_algorithm(
+            &dialect,
+            signing_algorithm_id.or(negotiated_signing_algorithm_id.as_deref()),
+        ));
+        self.signing_key = Some(signing::derive_signing_key(
+            session_key,
+            &dialect,
+            &preauth_integrity_hash,
+        ));
+    }
+
+    /// Signs the serialized request if signing is established. Otherwise the request is returned unchanged.
+    pub fn sign_request(&self, request: Vec<u8>) -> Vec<u8> {
+        match (&self.signing_key, &self.signing_algorithm) {
+            (Some(signing_key), Some(algorithm)) => {
+                signing::sign_request(request, signing_key, algorithm)
+            }
+            _ => request,
+        }
+    }
+
+    /// Establishes encryption with the keys derived from the session key. The session is encrypted
+    /// with the cipher of the negotiated connection, unless a session cipher is forced on it.
+    /// Without either cipher, the session is not encrypted.
+    /// Without a negotiated dialect, SMB 3.0 is assumed.
+    pub fn establish_encryption(&mut self, session_key: &[u8]) {
+        let negotiated_cipher = self
+            .negotiated_connection
+            .as_ref()
+            .and_then(|connection| connection.cipher.clone());
+
+        if let Some(cipher) = self.session_cipher.clone().or(negotiated_cipher) {
+            let dialect = self.negotiated_dialect().unwrap_or(DialectRevision::Smb30);
+            let (encryption_key, decryption_key) = encryption::derive_encryption_keys(
+                session_key,
+                &dialect,
+                &self.session_key_derivation_context(),
+                &cipher,
+            );
+
+            self.encryption_cipher = Some(cipher);
+            self.encryption_key = Some(encryption_key);
+            self.decryption_key = Some(decryption_key);
+        }
+    }
+
+    /// Encrypts the serialized request (including the netbios session prefix) if encryption is
+    /// established. The transform header carries the session id of the first request and is fuzzed
+    /// with the given strategy before the encryption. Otherwise the request is returned unchanged.
+    pub fn encrypt_request(
+        &self,
+        request: Vec<u8>,
+        transform_header_fuzzing_strategy: Option<&FuzzingStrategy>,
+    ) -> Vec<u8> {
+        match (&self.encryption_key, &self.encryption_cipher) {
+            (Some(encryption_key), Some(cipher)) if request.len() >= 4 + HEADER_LENGTH => {
+                let mut header = encryption::build_transform_header(
+                    request.len() - 4,
+                    request[4 + 40..4 + 48].to_vec(),
+                    cipher,
+                );
+                if let Some(strategy) = transform_header_fuzzing_strategy {
+                    header = fuzz_transform_header(header, strategy);
+                }
+
+                encryption::encrypt_request(request, header, encryption_key, cipher)
+            }
+            _ => request,
+        }
+    }
+
+    /// Decrypts the response (including the netbios session prefix) if it is encrypted and
+    /// encryption is established. Returns the decrypted message with a recomputed netbios session
+    /// prefix, or the response unchanged if it is not encrypted or fails to decrypt.
+    pub fn decrypt_response(&self, response: &[u8]) -> Vec<u8> {
+        if response.len() < 4 || !is_transform_message(&response[4..]) {
+            return response.to_vec();
+        }
+
+        let message_length =
+            u32::from_be_bytes([0, response[1], response[2], response[3]]) as usize;
+        let transform_message = &response[4..(4 + message_length).min(response.len())];
+
+        match (&self.decryption_key, &self.encryption_cipher) {
+            (Some(decryption_key), Some(cipher)) => {
+                match encryption::decrypt_message(transform_message, decryption_key, cipher) {
+                    Some(mut message) => {
+                        let mut decrypted_response =
+                            serialize_netbios_session_prefix(message.len());
+                        decrypted_response.append(&mut message);
+                        decrypted_response
+                    }
+                    None => {
+                        println!("Failed to decrypt response.");
+                        response.to_vec()
+                    }
+                }
+            }
+            _ => response.to_vec(),
+        }
+    }
+
+    /// Verifies the signatures of the response if signing is established.
+    pub fn verify_response(&self, response: &[u8]) -> bool {
+        match (&self.signing_key, &self.signing_algorithm) {
+            (Some(signing_key), Some(algorithm)) => {
+                signing::verify_response(response, signing_key, algorithm)
+            }
+            _ => true,
+        }
+    }
+
+    /// Applies the sequence violation to the first request of the sequenced request.
+    pub fn apply_sequence_violation(
+        &mut self,
+        mut request: Vec<u8>,
+        violation: &SequenceViolation,
+    ) -> Vec<u8> {
+        if request.len() < 4 + HEADER_LENGTH {
+            return request;
+        }
+
+        let mut rng = rand::thread_rng();
+        match violation {
+            SequenceViolation::CreditExhaustion => {
+                let credit_charge = self
+                    .available_credits
+                    .saturating_add(rng.gen_range(2..=64))
+                    .min(u16::MAX as u32) as u16;
+                self.available_credits = 0;
+                request[4 + 6..4 + 8].copy_from_slice(&credit_charge.to_le_bytes());
+            }
+            SequenceViolation::DuplicateMessageId => {
+                let mut message_id = [0u8; 8];
+                message_id.copy_from_slice(&request[4 + 24..4 + 32]);
+                let duplicate_id = u64::from_le_bytes(message_id).saturating_sub(1);
+                request[4 + 24..4 + 32].copy_from_slice(&duplicate_id.to_le_bytes());
+            }
+            SequenceViolation::SkippedWindow => {
+                let skipped_id = self.next_message_id
+                    + self.available_credits as u64
+                    + rng.gen_range(1..=0x1000);
+                request[4 + 24..4 + 32].copy_from_slice(&skipped_id.to_le_bytes());
+            }
+        }
+
+        request
+    }
+}
+
+/// Returns the number of payload bytes the request transfers according to its body.
+/// Read, write, query info and change notify requests carry the length of
+/// the requested or transferred payload at offset 4 of the body.
+pub fn calculate_payload_size(command: &[u8], body: &[u8]) -> u32 {
+    let payload_commands = [
+        Commands::Read,
+        Commands::Write,
+        Commands::QueryInfo,
+        Commands::ChangeNotify,
+    ];
+
+    if body.len() >= 8
+        && payload_commands
+            .iter()
+            .any(|payload_command| payload_command.unpack_byte_code() == command)
+    {
+        convert_byte_array_to_int(body[4..8].to_vec(), false)
+    } else {
+        0
+    }
+}
+
+/// The controlled violations of the credit and message id sequencing.
+///
+/// *Credit Exhaustion*:
+///     - The request is charged more credits than the server has granted.
+///
+/// *Duplicate Message Id*:
+///     - The request reuses the message id of the previous request.
+///
+/// *Skipped Window*:
+///     - The message id of the request lies beyond the window of granted credits.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SequenceViolation {
+    CreditExhaustion,
+    DuplicateMessageId,
+    SkippedWindow,
+}
+
+impl SequenceViolation {
+    /// Maps a user input string to a sequence violation.
+    pub fn map_string_to_sequence_violation(violation: &str) -> Self {
+        match violation {
+            "exhaustion" => SequenceViolation::CreditExhaustion,
+            "duplicate" => SequenceViolation::DuplicateMessageId,
+            "skip" => SequenceViolation::SkippedWindow,
+            _ => panic!("Invalid sequence violation."),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        builder::build_sync_header,
+        format::encoder::{serialize_request, spnego_encoder::encode_negotiation_token},
+        gss::NTLMSSP_OID,
+        smb2::{
+            helper_functions::fields::Capabilities,
+            requests::{echo::Echo, read::Read, RequestType},
+        },
+    };
+
+    #[test]
+    fn test_calculate_credit_charge() {
+        assert_eq!(1, ConnectionContext::calculate_credit_charge(0));
+        assert_eq!(1, ConnectionContext::calculate_credit_charge(0x10000));
+        assert_eq!(2, ConnectionContext::calculate_credit_charge(0x10001));
+        assert_eq!(16, ConnectionContext::calculate_credit_charge(0x100000));
+    }
+
+    #[test]
+    fn test_sequence_request() {
+        let mut context = ConnectionContext::default();
+        context.available_credits = 8;
+        let mut negotiated_connection = NegotiatedConnection::default();
+        negotiated_connection.dialect = Some(DialectRevision::Smb311);
+        negotiated_connection.capabilities = Capabilities::GlobalCapLargeMtu.unpack_byte_code();
+        context.negotiated_connection = Some(negotiated_connection);
+
+        let echo_header = build_sync_header(Commands::Echo, 0, 1, None, None, 6);
+        let echo_request = serialize_request(&echo_header, &RequestType::Echo(Echo::default()));
+        let sequenced_echo = context.sequence_request(echo_request);
+        assert_eq!(vec![1, 0], sequenced_echo[10..12].to_vec());
+        assert_eq!(0u64.to_le_bytes().to_vec(), sequenced_echo[28..36].to_vec());
+
+        let mut read = Read::default();
+        read.length = 0x30000u32.to_le_bytes().to_vec();
+        let read_header = build_sync_header(Commands::Read, 1, 1, None, None, 4);
+        let read_request = serialize_request(&read_header, &RequestType::Read(read));
+        let sequenced_read = context.sequence_request(read_request);
+        assert_eq!(vec![3, 0], sequenced_read[10..12].to_vec());
+        assert_eq!(1u64.to_le_bytes().to_vec(), sequenced_read[28..36].to_vec());
+
+        assert_eq!(4, context.next_message_id);
+        assert_eq!(4, context.available_credits);
+        assert_eq!(Some(1), context.last_message_id);
+
+        if let Some(negotiated_connection) = context.negotiated_connection.as_mut() {
+            negotiated_connection.dialect = Some(DialectRevision::Smb202);
+        }
+        let mut read = Read::default();
+        read.length = 0x30000u32.to_le_bytes().to_vec();
+        let read_request = serialize_request(&read_header, &RequestType::Read(read));
+        let sequenced_read = context.sequence_request(read_request);
+        assert_eq!(vec![0, 0], sequenced_read[10..12].to_vec());
+        assert_eq!(4u64.to_le_bytes().to_vec(), sequenced_read[28..36].to_vec());
+        assert_eq!(5, context.next_message_id);
+        assert_eq!(3, context.available_credits);
+    }
+
+    #[test]
+    fn test_grant_credits() {
+        let mut context = ConnectionContext::default();
+        let response_header = build_sync_header(Commands::Negotiate, 0, 31, None, None, 0);
+        let response = serialize_request(&response_header, &RequestType::Echo(Echo::default()));
+
+        context.grant_credits(&response);
+        assert_eq!(32, context.available_credits);
+
+        context.grant_credits(&[0; 300]);
+        assert_eq!(32, context.available_credits);
+    }
+
+    #[test]
+    fn test_set_server_negotiation_token() {
+        let mut token = NegTokenInit2::default();
+        token.mech_types = vec![NTLMSSP_OID.to_vec()];
+        token.neg_hints.hint_name = b"not_defined_in_RFC4178@please_ignore".to_vec();
+        let security_buffer =
+            encode_negotiation_token(NegotiationToken::NegTokenInit2(token.clone()));
+
+        let mut response = vec![0; 4 + HEADER_LENGTH + 64];
+        response[4 + 12..4 + 14].copy_from_slice(&Commands::Negotiate.unpack_byte_code());
+        response[4 + HEADER_LENGTH + 56..4 + HEADER_LENGTH + 58]
+            .copy_from_slice(&(HEADER_LENGTH as u16 + 64).to_le_bytes());
+        response[4 + HEADER_LENGTH + 58..4 + HEADER_LENGTH + 60]
+            .copy_from_slice(&(security_buffer.len() as u16).to_le_bytes());
+        response.extend_from_slice(&security_buffer);
+
+        let mut context = ConnectionContext::default();
+        context.set_server_negotiation_token(&response);
+        assert_eq!(Some(token), context.server_negotiation_token);
+
+        let mut context = ConnectionContext::default();
+        context.set_server_negotiation_token(&response[..response.len() - 1]);
+        assert_eq!(None, context.server_negotiation_token);
+    }
+
+    #[test]
+    fn test_set_session_flags() {
+        let mut response = vec![0; 4 + HEADER_LENGTH + 8];
+        response[4 + 12..4 + 14].copy_from_slice(&Commands::SessionSetup.unpack_byte_code());
+        response[4 + HEADER_LENGTH + 2] = 1;
+
+        let mut context = ConnectionContext::default();
+        context.set_session_flags(&response);
+        assert_eq!(Some(SessionFlags::IsGuest), context.session_flags);
+        assert!(context.is_guest_or_null_session());
+
+        response[4 + HEADER_LENGTH + 2] = 2;
+        context.set_session_flags(&response);
+        assert_eq!(Some(SessionFlags::IsNull), context.session_flags);
+
+        response[4 + HEADER_LENGTH + 2] = 0;
+        context.set_session_flags(&response);
+        assert!(!context.is_guest_or_null_session());
+
+        let mut context = ConnectionContext::default();
+        response[4 + HEADER_LENGTH + 2] = 1;
+        response[4 + 8] = 0x6d;
+        context.set_session_flags(&response);
+        assert_eq!(None, context.session_flags);
+    }
+
+    #[test]
+    fn test_establish_signing() {
+        let mut context = ConnectionContext::default();
+        let echo_header = build_sync_header(Commands::Echo, 1, 1, None, None, 6);
+        let echo_request = serialize_request(&echo_header, &RequestType::Echo(Echo::default()));
+        assert_eq!(echo_request, context.sign_request(echo_request.clone()));
+
+        let mut negotiated_connection = NegotiatedConnection::default();
+        negotiated_connection.dialect = Some(DialectRevision::Smb30);
+        context.negotiated_connection = Some(negotiated_connection.clone());
+        context.establish_signing(&[0x11; 16], None);
+        assert_eq!(Some(SigningAlgorithm::AesCmac), context.signing_algorithm);
+
+        let mut gmac_connection = negotiated_connection.clone();
+        gmac_connection.dialect = Some(DialectRevision::Smb311);
+        gmac_connection.signing_algorithm = Some(SigningAlgorithm::AesGmac);
+        context.negotiated_connection = Some(gmac_connection);
+        context.establish_signing(&[0x11; 16], None);
+        assert_eq!(Some(SigningAlgorithm::AesGmac), context.signing_algorithm);
+        context.establish_signing(&[0x11; 16], Some(&[0, 0]));
+        assert_eq!(
+            Some(SigningAlgorithm::HmacSha256),
+            context.signing_algorithm
+        );
+        context.negotiated_connection = Some(negotiated_connection);
+        context.establish_signing(&[0x11; 16], None);
+
+        let signed_request = context.prepare_request(echo_request);
+        assert!(context.verify_response(&signed_request));
+        assert_ne!(vec![0; 16], signed_request[52..68].to_vec());
+    }
+
+    #[test]
+    fn test_update_preauth_integrity_hash() {
+        let mut context = ConnectionContext::default();
+        let echo_header = build_sync_header(Commands::Echo, 1, 1, None, None, 0);
+        let echo_request = serialize_request(&echo_header, &RequestType::Echo(Echo::default()));
+        let negotiate_request = crate::networking::packets::prepare_negotiate_packet(None);
+
+        context.update_preauth_integrity_hash(&echo_request);
+        assert_eq!(vec![0; 64], context.preauth_integrity_hash);
+
+        let negotiate_request = context.prepare_request(negotiate_request);
+        let expected_hash = calculate_preauth_integrity_hash(&[0; 64], &negotiate_request[4..]);
+        assert_eq!(expected_hash, context.preauth_integrity_hash);
+        assert_eq!(expected_hash, context.session_key_derivation_context());
+
+        let mut session_setup_request = negotiate_request.clone();
+        session_setup_request[4 + 12..4 + 14]
+            .copy_from_slice(&Commands::SessionSetup.unpack_byte_code());
+        context.update_preauth_integrity_hash(&session_setup_request);
+        assert_eq!(expected_hash, context.preauth_integrity_hash);
+        assert_eq!(
+            Some(calculate_preauth_integrity_hash(
+                &expected_hash,
+                &session_setup_request[4..]
+            )),
+            context.session_preauth_integrity_hash
+        );
+
+        let session_hash = context.session_preauth_integrity_hash.clone();
+        let mut final_response = session_setup_request;
+        final_response[4 + 16] |= Flags::ServerToRedir.unpack_byte_code()[0];
+        context.update_preauth_integrity_hash(&final_response);
+        assert_eq!(session_hash, context.session_preauth_integrity_hash);
+
+        context.tamper_preauth_integrity_hash = true;
+        context.update_preauth_integrity_hash(&negotiate_request);
+        assert_ne!(
+            calculate_preauth_integrity_hash(&expected_hash, &negotiate_request[4..]),
+            context.preauth_integrity_hash
+        );
+    }
+
+    #[test]
+    fn test_establish_encryption() {
+        let mut context = ConnectionContext::default();
+        let echo_header = build_sync_header(Commands::Echo, 0, 1, None, Some(vec![7; 8]), 6);
+        let echo_request = serialize_request(&echo_header, &RequestType::Echo(Echo::default()));
+
+        context.establish_encryption(&[0x11; 16]);
+        assert_eq!(None, context.encryption_key);
+
+        let mut negotiated_connection = NegotiatedConnection::default();
+        negotiated_connection.cipher = Some(Ciphers::Aes256Ccm);
+        context.negotiated_connection = Some(negotiated_connection);
+        context.establish_encryption(&[0x11; 16]);
+        assert_eq!(Some(Ciphers::Aes256Ccm), context.encryption_cipher);
+
+        context.session_cipher = Some(Ciphers::Aes128Gcm);
+        context.establish_encryption(&[0x11; 16]);
+        assert_eq!(Some(Ciphers::Aes128Gcm), context.encryption_cipher);
+        let encrypted_request = context.prepare_request(echo_request.clone());
+        assert!(is_transform_message(&encrypted_request[4..]));
+        assert_eq!(vec![7; 8], encrypted_request[4 + 44..4 + 52].to_vec());
+
+        // The server decrypts with the client's encryption key.
+        context.decryption_key = context.encryption_key.clone();
+        let decrypted_request = context.decrypt_response(&encrypted_request);
+        assert_eq!(echo_request[..4 + 24], decrypted_request[..4 + 24]);
+        assert_eq!(echo_request.len(), decrypted_request.len());
+
+        // Encrypted session setup requests are hashed as plain messages.
+        let mut session_setup_request = echo_request;
+        session_setup_request[4 + 12..4 + 14]
+            .copy_from_slice(&Commands::SessionSetup.unpack_byte_code());
+        let encrypted_request = context.prepare_request(session_setup_request);
+        let decrypted_request = context.decrypt_response(&encrypted_request);
+        assert_eq!(
+            Some(calculate_preauth_integrity_hash(
+                &[0; 64],
+                &decrypted_request[4..]
+            )),
+            context.session_preauth_integrity_hash
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! This module obtains the service ticket of the SMB server from the KDC over TCP (RFC 4120 7.2.2).
+//! The AS exchange pre-authenticates the user with the encrypted timestamp and yields the ticket
+//! granting ticket, with which the TGS exchange yields the ticket of the service principal.
+
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use crate::builder::kdc_request::{
+    build_as_req, build_pa_enc_timestamp, build_tgs_req, generate_nonce,
+};
+use crate::crypto::kerberos::{self, DEFAULT_ITERATION_COUNT};
+use crate::format::{
+    convert_byte_array_to_int,
+    decoder::kerberos_decoder::{
+        decode_enc_kdc_rep_part, decode_etype_info2, decode_integer, decode_kdc_rep,
+        decode_krb_error,
+    },
+    encoder::kerberos_encoder::{encode_kdc_req, AS_REQ_TAG, TGS_REQ_TAG},
+};
+use crate::kerberos::{
+    EncryptionType, EtypeInfo2Entry, KdcRep, KerberosConfig, ServiceTicket,
+    KDC_ERR_PREAUTH_REQUIRED, KEY_USAGE_AS_REP_ENC_PART, KEY_USAGE_TGS_REP_ENC_PART,
+};
+use crate::ntlmssp::credentials::Credentials;
+
+/// The maximum length of a KDC reply that is accepted.
+const MAX_REPLY_LENGTH: usize = 0x10_0000;
+
+/// Obtains the service ticket of the configured service principal for the user of the credentials.
+/// The realm is the upper case domain name of the credentials.
+pub fn obtain_service_ticket(
+    config: &KerberosConfig,
+    credentials: &Credentials,
+) -> io::Result<ServiceTicket> {
+    let tgt = request_ticket_granting_ticket(config, credentials)?;
+    println!("Obtained the ticket granting ticket from the KDC.");
+    let service_ticket = request_service_ticket(config, &tgt)?;
+    println!(
+        "Obtained the service ticket for {} from the KDC.",
+        config.service_principal
+    );
+
+    Ok(service_ticket)
+}
+
+/// Sends the AS-REQ and returns the ticket granting ticket. If the KDC requires the
+/// pre-authentication, the AS-REQ is repeated with the timestamp encrypted with the key
+/// that is derived from the password and the salt of the ETYPE-INFO2.
+pub fn request_ticket_granting_ticket(
+    config: &KerberosConfig,
+    credentials: &Credentials,
+) -> io::Result<ServiceTicket> {
+    let realm = credentials.domain_name.to_uppercase();
+    let nonce = generate_nonce();
+    let as_req = build_as_req(&credentials.user_name, &realm, Vec::new(), nonce);
+    let reply = send_kdc_request(&config.kdc_address, &encode_kdc_req(AS_REQ_TAG, &as_req))?;
+
+    let default_salt = format!("{}{}", realm, credentials.user_name).into_bytes();
+    let (reply, etype_info) = match decode_krb_error(&reply) {
+        Some(error) => {
+            let error_code = decode_integer(&error.error_code).unwrap_or_default();
+            if error_code != KDC_ERR_PREAUTH_REQUIRED {
+                return Err(kdc_error(&format!(
+                    "The KDC rejected the AS-REQ with error code {}.",
+                    error_code
+                )));
+            }
+            let etype_info = select_etype_info(
+                &decode_etype_info2(&error.e_data).unwrap_or_default(),
+                &default_salt,
+            )
+            .ok_or_else(|| kdc_error("The KDC supports none of the encryption types."))?;
+
+            let (etype, salt, iteration_count) = &etype_info;
+            let key = kerberos::string_to_key(etype, &credentials.password, salt, *iteration_count);
+            let padata = vec![build_pa_enc_timestamp(etype, &key)];
+            let as_req = build_as_req(&credentials.user_name, &realm, padata, nonce);
+            let reply =
+                send_kdc_request(&config.kdc_address, &encode_kdc_req(AS_REQ_TAG, &as_req))?;
+
+            (reply, Some(etype_info))
+        }
+        None => (reply, None),
+    };
+
+    let reply = decode_reply(&reply)?;
+    let etype = EncryptionType::map_byte_code_to_encryption_type(&reply.enc_part.etype)
+        .ok_or_else(|| kdc_error("The AS-REP is encrypted with an unsupported encryption type."))?;
+    let (salt, iteration_count) = match etype_info {
+        Some((_, salt, iteration_count)) => (salt, iteration_count),
+        None => (default_salt, DEFAULT_ITERATION_COUNT),
+    };
+    let key = kerberos::string_to_key(&etype, &credentials.password, &salt, iteration_count);
+
+    extract_ticket(reply, &etype, &key, KEY_USAGE_AS_REP_ENC_PART, nonce)
+}
+
+/// Sends the TGS-REQ with the ticket granting ticket and returns the ticket of the service principal.
+pub fn request_service_ticket(
+    config: &KerberosConfig,
+    tgt: &ServiceTicket,
+) -> io::Result<ServiceTicket> {
+    let nonce = generate_nonce();
+    let tgs_req = build_tgs_req(tgt, &config.service_principal, nonce);
+    let reply = send_kdc_request(&config.kdc_address, &encode_kdc_req(TGS_REQ_TAG, &tgs_req))?;
+
+    let reply = decode_reply(&reply)?;
+    let etype = EncryptionType::map_byte_code_to_encryption_type(&tgt.session_key.key_type)
+        .ok_or_else(|| kdc_error("The TGT session key has an unsupported encryption type."))?;
+
+    extract_ticket(
+        reply,
+        &etype,
+        &tgt.session_key.key_value,
+        KEY_USAGE_TGS_REP_ENC_PART,
+        nonce,
+    )
+}
+
+/// Sends the KDC request with its four byte length prefix and returns the reply without it.
+pub fn send_kdc_request(kdc_address: &str, request: &[u8]) -> io::Result<Vec<u8>> {
+    let mut stream = TcpStream::connect(kdc_address)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut message = (request.len() as u32).to_be_bytes().to_vec();
+    message.extend_from_slice(request);
+    stream.write_all(&message)?;
+
+    let mut length = [0; 4];
+    stream.read_exact(&mut length)?;
+    let length = convert_byte_array_to_int(length.to_vec(), true) as usize;
+    if length > MAX_REPLY_LENGTH {
+        return Err(kdc_error("The KDC reply exceeds the maximum length."));
+    }
+    let mut reply = vec![0; length];
+    stream.read_exact(&mut reply)?;
+
+    Ok(reply)
+}
+
+/// Selects the first supported encryption type of the ETYPE-INFO2 with its salt and iteration count.
+/// Without ETYPE-INFO2, the preferred encryption type with the default salt is selected.
+pub fn select_etype_info(
+    etype_info: &[EtypeInfo2Entry],
+    default_salt: &[u8],
+) -> Option<(EncryptionType, Vec<u8>, u32)> {
+    if etype_info.is_empty() {
+        return Some((
+            EncryptionType::Aes256CtsHmacSha196,
+            default_salt.to_vec(),
+            DEFAULT_ITERATION_COUNT,
+        ));
+    }
+
+    etype_info.iter().find_map(|entry| {
+        let etype = EncryptionType::map_byte_code_to_encryption_type(&entry.etype)?;
+        let salt = if entry.salt.is_empty() {
+            default_salt.to_vec()
+        } else {
+            entry.salt.clone()
+        };
+        let iteration_count = match entry.s2kparams.as_slice() {
+            [a, b, c, d] => u32::from_be_bytes([*a, *b, *c, *d]),
+            _ => DEFAULT_ITERATION_COUNT,
+        };
+
+        Some((etype, salt, iteration_count))
+    })
+}
+
+/// Decodes the AS-REP or TGS-REP. A KRB-ERROR is returned as error.
+fn decode_reply(reply: &[u8]) -> io::Result<KdcRep> {
+    if let Some(error) = decode_krb_error(reply) {
+        return Err(kdc_error(&format!(
+            "The KDC replied with error code {} {}.",
+            decode_integer(&error.error_code).unwrap_or_default(),
+            String::from_utf8_lossy(&error.e_text)
+        )));
+    }
+
+    decode_kdc_rep(reply).ok_or_else(|| kdc_error("Invalid KDC reply."))
+}
+
+/// Decrypts the encrypted part of the reply, checks its nonce and returns the ticket with its session key.
+fn extract_ticket(
+    reply: KdcRep,
+    etype: &EncryptionType,
+    key: &[u8],
+    key_usage: u32,
+    nonce: u32,
+) -> io::Result<ServiceTicket> {
+    let enc_part = kerberos::decrypt(etype, key, key_usage, &reply.enc_part.cipher)
+        .and_then(|plaintext| decode_enc_kdc_rep_part(&plaintext))
+        .ok_or_else(|| kdc_error("Failed to decrypt the KDC reply, the password may be wrong."))?;
+    if decode_integer(&enc_part.nonce) != Some(nonce as i64) {
+        return Err(kdc_error("The nonce of the KDC reply does not match."));
+    }
+
+    Ok(ServiceTicket {
+        cname: reply.cname,
+        crealm: reply.crealm,
+        ticket: reply.ticket,
+        session_key: enc_part.key,
+    })
+}
+
+fn kdc_error(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_etype_info() {
+        let mut rc4 = EtypeInfo2Entry::default();
+        rc4.etype = EncryptionType::Rc4Hmac.unpack_byte_code();
+        let mut des = EtypeInfo2Entry::default();
+        des.etype = vec![0x03];
+        let mut aes = EtypeInfo2Entry::default();
+        aes.etype = EncryptionType::Aes128CtsHmacSha196.unpack_byte_code();
+        aes.salt = b"DOMAIN.LOCALbob".to_vec();
+        aes.s2kparams = vec![0x00, 0x00, 0x10, 0x01];
+
+        assert_eq!(
+            Some((
+                EncryptionType::Aes128CtsHmacSha196,
+                b"DOMAIN.LOCALbob".to_vec(),
+                4097
+            )),
+            select_etype_info(&[des.clone(), aes], b"salt")
+        );
+        assert_eq!(
+            Some((
+                EncryptionType::Rc4Hmac,
+                b"salt".to_vec(),
+                DEFAULT_ITERATION_COUNT
+            )),
+            select_etype_info(&[rc4], b"salt")
+        );
+        assert_eq!(None, select_etype_info(&[des], b"salt"));
+    }
+}
@@ -1,3 +1,6 @@
 pub mod connect;
+pub mod connection_context;
+pub mod kdc;
+pub mod negotiated_connection;
 pub mod packets;
 pub mod state_transition_engine;
@@ -0,0 +1,270 @@
+//! The negotiated connection holds the parameters the server selected in the negotiate response,
+//! so that the signing, encryption and compression of the connection can consult them.
+//! For the SMB 3.1.1 dialect, these include the values of the typed negotiate contexts.
+
+use crate::crypto::signing::SigningAlgorithm;
+use crate::format::convert_byte_array_to_int;
+use crate::smb2::{
+    helper_functions::{
+        fields::{Capabilities, SecurityMode},
+        negotiate_context::{
+            Ciphers, CompressionAlgorithms, ContextType, Flags, HashAlgorithms, RdmaTransformIds,
+        },
+    },
+    responses::negotiate::{DialectRevision, Negotiate},
+};
+
+/// The SMB2_ACCEPT_TRANSPORT_LEVEL_SECURITY flag of the transport capabilities.
+pub const ACCEPT_TRANSPORT_LEVEL_SECURITY: u8 = 0x01;
+
+/// The parameters of the connection as negotiated by the server.
+/// Algorithm ids the client does not know are ignored.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct NegotiatedConnection {
+    /// The dialect the server selected.
+    pub dialect: Option<DialectRevision>,
+    /// The security mode of the server.
+    pub security_mode: Option<SecurityMode>,
+    /// The guid that identifies the server.
+    pub server_guid: Vec<u8>,
+    /// The protocol capabilities of the server.
+    pub capabilities: u32,
+    /// The maximum buffer size of QUERY_INFO, QUERY_DIRECTORY, SET_INFO and CHANGE_NOTIFY operations.
+    pub max_transact_size: u32,
+    /// The maximum length of a read request.
+    pub max_read_size: u32,
+    /// The maximum length of a write request.
+    pub max_write_size: u32,
+    /// The hash algorithm of the preauth integrity hash the server selected.
+    pub preauth_hash_algorithm: Option<HashAlgorithms>,
+    /// The salt of the preauth integrity capabilities of the server.
+    pub preauth_salt: Vec<u8>,
+    /// The cipher the server selected. The SMB 3.0 and 3.0.2 dialects use AES-128-CCM if the
+    /// server supports encryption. Without a cipher, the server does not support encryption,
+    /// did not send the encryption capabilities or supports none of the offered ciphers.
+    pub cipher: Option<Ciphers>,
+    /// The signing algorithm the server selected in the signing capabilities.
+    pub signing_algorithm: Option<SigningAlgorithm>,
+    /// The offered compression algorithms the server supports, in order of preference.
+    pub compression_algorithms: Vec<CompressionAlgorithms>,
+    /// Whether the server supports chained compression.
+    pub chained_compression: bool,
+    /// Whether the server accepts transport level security in place of SMB encryption.
+    pub accept_transport_level_security: bool,
+    /// The offered RDMA transforms the server supports.
+    pub rdma_transform_ids: Vec<RdmaTransformIds>,
+}
+
+impl NegotiatedConnection {
+    /// Creates a new negotiated connection without any negotiated parameters.
+    pub fn default() -> Self {
+        NegotiatedConnection {
+            dialect: None,
+            security_mode: None,
+            server_guid: Vec::new(),
+            capabilities: 0,
+            max_transact_size: 0,
+            max_read_size: 0,
+            max_write_size: 0,
+            preauth_hash_algorithm: None,
+            preauth_salt: Vec::new(),
+            cipher: None,
+            signing_algorithm: None,
+            compression_algorithms: Vec::new(),
+            chained_compression: false,
+            accept_transport_level_security: false,
+            rdma_transform_ids: Vec::new(),
+        }
+    }
+
+    /// Creates the negotiated connection from the decoded negotiate response.
+    pub fn from_negotiate_response(response: &Negotiate) -> Self {
+        let mut negotiated_connection = NegotiatedConnection::default();
+
+        negotiated_connection.dialect = response.dialect_revision.clone();
+        negotiated_connection.security_mode = response.security_mode.clone();
+        negotiated_connection.server_guid = response.server_guid.clone();
+        negotiated_connection.capabilities =
+            convert_byte_array_to_int(response.capabilities.clone(), false);
+        negotiated_connection.max_transact_size =
+            convert_byte_array_to_int(response.max_transact_size.clone(), false);
+        negotiated_connection.max_read_size =
+            convert_byte_array_to_int(response.max_read_size.clone(), false);
+        negotiated_connection.max_write_size =
+            convert_byte_array_to_int(response.max_write_size.clone(), false);
+
+        if matches!(
+            negotiated_connection.dialect,
+            Some(DialectRevision::Smb30) | Some(DialectRevision::Smb302)
+        ) && negotiated_connection.capabilities
+            & Capabilities::GlobalCapEncryption.unpack_byte_code()
+            != 0
+        {
+            negotiated_connection.cipher = Some(Ciphers::Aes128Ccm);
+        }
+
+        for context in response.negotiate_context_list.iter() {
+            if let Some(data) = &context.data {
+                negotiated_connection.apply_negotiate_context(data);
+            }
+        }
+
+        negotiated_connection
+    }
+
+    /// Stores the values the server selected in the given negotiate context.
+    pub fn apply_negotiate_context(&mut self, context: &ContextType) {
+        match context {
+            ContextType::PreauthIntegrityCapabilities(preauth) => {
+                self.preauth_hash_algorithm =
+                    match preauth.hash_algorithms.first().map(Vec::as_slice) {
+                        Some(id @ [1, 0]) => {
+                            Some(HashAlgorithms::map_byte_code_to_hash_algorithm(id.to_vec()))
+                        }
+                        _ => None,
+                    };
+                self.preauth_salt = preauth.salt.clone();
+            }
+            ContextType::EncryptionCapabilities(encryption) => {
+                self.cipher = match encryption.ciphers.first().map(Vec::as_slice) {
+                    Some(id @ [1..=4, 0]) => Some(Ciphers::map_byte_code_to_cipher(id.to_vec())),
+                    _ => None,
+                };
+            }
+            ContextType::CompressionCapabilities(compression) => {
+                self.compression_algorithms = compression
+                    .compression_algorithms
+                    .iter()
+                    .filter(|id| matches!(id.as_slice(), [0..=4, 0]))
+                    .map(|id| {
+                        CompressionAlgorithms::map_byte_code_to_compression_algorithm(id.clone())
+                    })
+                    .collect();
+                self.chained_compression = compression.flags
+                    == Flags::CompressionCapabilitiesFlagChained.unpack_byte_code();
+            }
+            ContextType::NetnameNegotiateContextId(_) | ContextType::Unknown { .. } => {}
+            ContextType::TransportCapabilities(transport) => {
+                self.accept_transport_level_security = matches!(
+                    transport.reserved.first(),
+                    Some(flags) if flags & ACCEPT_TRANSPORT_LEVEL_SECURITY != 0
+                );
+            }
+            ContextType::RdmaTransformCapabilities(rdma) => {
+                self.rdma_transform_ids = rdma
+                    .rdma_transform_ids
+                    .iter()
+                    .filter(|id| matches!(id.as_slice(), [0..=1, 0]))
+                    .map(|id| RdmaTransformIds::map_byte_code_to_rdma_transform_id(id.clone()))
+                    .collect();
+            }
+            ContextType::SigningCapabilities(signing) => {
+                self.signing_algorithm = match signing.signing_algorithms.first().map(Vec::as_slice)
+                {
+                    Some(id @ [0..=2, 0]) => Some(
+                        SigningAlgorithm::map_byte_code_to_signing_algorithm(id.to_vec()),
+                    ),
+                    _ => None,
+                };
+            }
+        }
+    }
+
+    /// Returns whether the server supports the given compression algorithm on the connection.
+    pub fn supports_compression_algorithm(&self, algorithm: &CompressionAlgorithms) -> bool {
+        self.compression_algorithms.contains(algorithm)
+    }
+
+    /// Returns whether the connection supports multi-credit requests, i.e. whether the server
+    /// selected a dialect other than SMB 2.0.2 and announced the GlobalCapLargeMtu capability.
+    pub fn supports_multi_credit(&self) -> bool {
+        self.dialect.is_some()
+            && self.dialect != Some(DialectRevision::Smb202)
+            && self.capabilities & Capabilities::GlobalCapLargeMtu.unpack_byte_code() != 0
+    }
+
+    /// Returns whether the server accepts messages compressed with the given algorithm
+    /// in a chained or unchained compression transform.
+    pub fn supports_compression(&self, algorithm: &CompressionAlgorithms, chained: bool) -> bool {
+        self.supports_compression_algorithm(algorithm) && (!chained || self.chained_compression)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smb2::helper_functions::negotiate_context::{
+        CompressionCapabilities, EncryptionCapabilities, NegotiateContext, SigningCapabilities,
+    };
+
+    #[test]
+    fn test_from_negotiate_response() {
+        let mut encryption = EncryptionCapabilities::default();
+        encryption.cipher_count = vec![1, 0];
+        encryption.ciphers = vec![Ciphers::Aes256Gcm.unpack_byte_code()];
+
+        let mut compression = CompressionCapabilities::default();
+        compression.compression_algorithm_count = vec![2, 0];
+        compression.flags = Flags::CompressionCapabilitiesFlagChained.unpack_byte_code();
+        compression.compression_algorithms = vec![vec![0x77, 0], vec![2, 0]];
+
+        let mut signing = SigningCapabilities::default();
+        signing.signing_algorithm_count = vec![1, 0];
+        signing.signing_algorithms = vec![SigningAlgorithm::AesGmac.unpack_byte_code()];
+
+        let mut response = Negotiate::default();
+        response.dialect_revision = Some(DialectRevision::Smb311);
+        response.max_read_size = vec![0, 0, 0x80, 0];
+        response.negotiate_context_list = vec![
+            ContextType::EncryptionCapabilities(encryption),
+            ContextType::CompressionCapabilities(compression),
+            ContextType::SigningCapabilities(signing),
+        ]
+        .into_iter()
+        .map(|data| {
+            let mut context = NegotiateContext::default();
+            context.context_type = data.unpack_byte_code();
+            context.data = Some(data);
+            context
+        })
+        .collect();
+
+        let negotiated_connection = NegotiatedConnection::from_negotiate_response(&response);
+
+        assert_eq!(Some(DialectRevision::Smb311), negotiated_connection.dialect);
+        assert_eq!(0x800000, negotiated_connection.max_read_size);
+        assert_eq!(Some(Ciphers::Aes256Gcm), negotiated_connection.cipher);
+        assert_eq!(
+            Some(SigningAlgorithm::AesGmac),
+            negotiated_connection.signing_algorithm
+        );
+        assert_eq!(
+            vec![CompressionAlgorithms::Lz77],
+            negotiated_connection.compression_algorithms
+        );
+        assert!(negotiated_connection.chained_compression);
+        assert!(negotiated_connection.supports_compression(&CompressionAlgorithms::Lz77, true));
+        assert!(!negotiated_connection.supports_compression(&CompressionAlgorithms::Lznt1, false));
+        assert_eq!(None, negotiated_connection.preauth_hash_algorithm);
+        assert!(!negotiated_connection.supports_multi_credit());
+
+        let mut response = Negotiate::default();
+        response.dialect_revision = Some(DialectRevision::Smb302);
+        response.capabilities = Capabilities::GlobalCapEncryption
+            .unpack_byte_code()
+            .to_le_bytes()
+            .to_vec();
+        assert_eq!(
+            Some(Ciphers::Aes128Ccm),
+            NegotiatedConnection::from_negotiate_response(&response).cipher
+        );
+
+        response.capabilities = Capabilities::GlobalCapLargeMtu
+            .unpack_byte_code()
+            .to_le_bytes()
+            .to_vec();
+        assert!(NegotiatedConnection::from_negotiate_response(&response).supports_multi_credit());
+        response.dialect_revision = Some(DialectRevision::Smb202);
+        assert!(!NegotiatedConnection::from_negotiate_response(&response).supports_multi_credit());
+    }
+}
@@ -1,18 +1,31 @@
+use std::convert::TryFrom;
+
 use crate::{
     builder::{
-        self, session_setup_authenticate_request::build_default_session_setup_authenticate_request,
+        self, durable_handle_request::DurableOpen,
+        session_setup_authenticate_request::build_default_session_setup_authenticate_request,
+        session_setup_kerberos_request::build_default_session_setup_kerberos_request,
         session_setup_negotiate_request::build_default_session_setup_negotiate_request,
     },
-    format,
-    fuzzer::{self, FuzzingStrategy},
-    ntlmssp::MessageType,
+    compression, format,
+    fuzzer::{
+        self, change_notify_fuzzer::RacingOperation,
+        durable_handle_fuzzer::DurableReconnectMutation,
+        handshake::session_scenario_fuzzer::SessionScenario, FuzzingStrategy,
+    },
+    gss,
+    kerberos::{EncryptionKey, ServiceTicket},
+    ntlmssp::{challenge::Challenge, credentials::Credentials, MessageType},
     smb2::{
+        compression_transform::CompressionTransform,
         header,
+        helper_functions::negotiate_context::CompressionAlgorithms,
         requests::{
-            self, close::Close, create::Create, echo::Echo, negotiate::Negotiate,
+            self, cancel::Cancel, change_notify::ChangeNotify, close::Close, create::Create,
+            echo::Echo, flush::Flush, lock::Lock, negotiate::Negotiate, oplock_break::OplockBreak,
             query_info::QueryInfo, tree_connect::TreeConnect, RequestType,
         },
-        responses,
+        responses::{self, oplock_break::BreakNotification},
     },
 };
 
@@ -88,25 +101,21 @@ pub fn prepare_session_setup_negotiate_packet(
 }
 
 /// Builds the second session setup packet according to the fuzzing strategy if given.
-/// Otherwise the default session setup 2 packet is built.
+/// Otherwise the default session setup 2 packet is built, which authenticates with the credentials.
+/// The session key is returned alongside the packet if the default packet is built.
 pub fn prepare_session_setup_authenticate_packet(
     fuzzing_strategy: Option<FuzzingStrategy>,
     session_id: Vec<u8>,
     session_setup_response_body: responses::session_setup::SessionSetup,
-) -> Vec<u8> {
+    credentials: &Credentials,
+) -> (Vec<u8>, Option<Vec<u8>>) {
     let mut session_setup_request: (
         Option<header::SyncHeader>,
         Option<requests::session_setup::SessionSetup>,
     ) = (None, None);
-    let challenge_struct = match format::decoder::security_blob_decoder::decode_security_response(
-        session_setup_response_body.buffer,
-    )
-    .message
-    .unwrap()
-    {
-        MessageType::Challenge(challenge) => challenge,
-        _ => panic!("Invalid message type in server response."),
-    };
+    let mut session_key = None;
+    let (challenge_struct, challenge_message) =
+        decode_ntlm_challenge(session_setup_response_body.buffer);
     if let Some(strategy) = fuzzing_strategy {
         session_setup_request.0 = Some(builder::build_sync_header(
             header::Commands::SessionSetup,
@@ -118,23 +127,180 @@ pub fn prepare_session_setup_authenticate_packet(
         ));
         session_setup_request.1 = Some(
             match strategy {
-                FuzzingStrategy::Predefined => fuzzer::handshake::session_setup_fuzzer::fuzz_session_setup_authenticate_with_predefined_values(challenge_struct),
-                FuzzingStrategy::RandomFields => fuzzer::handshake::session_setup_fuzzer::fuzz_session_setup_with_random_fields(),
-                FuzzingStrategy::CompletelyRandom => fuzzer::handshake::session_setup_fuzzer::fuzz_session_setup_completely_random(),
+                FuzzingStrategy::Predefined => fuzzer::handshake::session_setup_fuzzer::fuzz_session_setup_authenticate_with_predefined_values(challenge_struct, &challenge_message, credentials),
+                FuzzingStrategy::RandomFields => fuzzer::handshake::session_setup_fuzzer::fuzz_session_setup_authenticate_with_random_fields(),
+                FuzzingStrategy::CompletelyRandom => fuzzer::handshake::session_setup_fuzzer::fuzz_session_setup_authenticate_completely_random(),
             }
         );
     } else {
-        session_setup_request =
-            build_default_session_setup_authenticate_request(session_id, challenge_struct);
+        let (head, body, key) = build_default_session_setup_authenticate_request(
+            session_id,
+            challenge_struct,
+            &challenge_message,
+            credentials,
+        );
+        session_setup_request = (head, body);
+        session_key = Some(key);
     }
 
     if let (Some(head), Some(body)) = session_setup_request {
-        format::encoder::serialize_request(&head, &RequestType::SessionSetupAuth(body))
+        (
+            format::encoder::serialize_request(&head, &RequestType::SessionSetupAuth(body)),
+            session_key,
+        )
     } else {
         panic!("Could not populate session setup 2 packet.")
     }
 }
 
+/// Decodes the NTLM CHALLENGE_MESSAGE in the security buffer of the session setup response
+/// and returns it alongside its encoding, over which the MIC is computed.
+fn decode_ntlm_challenge(security_buffer: Vec<u8>) -> (Challenge, Vec<u8>) {
+    let challenge_message = format::decoder::security_blob_decoder::extract_ntlm_challenge_message(
+        security_buffer.clone(),
+    );
+    let challenge_struct =
+        match format::decoder::security_blob_decoder::decode_security_response(security_buffer)
+            .message
+            .unwrap()
+        {
+            MessageType::Challenge(challenge) => challenge,
+            _ => panic!("Invalid message type in server response."),
+        };
+
+    (challenge_struct, challenge_message)
+}
+
+/// Builds the first session setup packet of the scenario on the established session.
+/// The fields that carry the scenario are fuzzed according to the fuzzing strategy if given.
+pub fn prepare_session_scenario_negotiate_packet(
+    fuzzing_strategy: Option<FuzzingStrategy>,
+    scenario: &SessionScenario,
+    session_id: Vec<u8>,
+) -> Vec<u8> {
+    let session_setup_request =
+        builder::session_scenario_request::build_session_scenario_negotiate_request(
+            scenario,
+            &session_id,
+        );
+
+    if let (Some(mut head), Some(mut body)) = session_setup_request {
+        if let Some(strategy) = fuzzing_strategy {
+            fuzz_session_scenario_fields(&mut head, &mut body, scenario, &session_id, strategy);
+        }
+        format::encoder::serialize_request(&head, &RequestType::SessionSetupNeg(body))
+    } else {
+        panic!("Could not populate session scenario packet 1.")
+    }
+}
+
+/// Builds the second session setup packet of the scenario on the established session, which
+/// authenticates with the credentials. The fields that carry the scenario are fuzzed according
+/// to the fuzzing strategy if given. The session key is returned alongside the packet.
+pub fn prepare_session_scenario_authenticate_packet(
+    fuzzing_strategy: Option<FuzzingStrategy>,
+    scenario: &SessionScenario,
+    session_id: Vec<u8>,
+    response_session_id: Vec<u8>,
+    session_setup_response_body: responses::session_setup::SessionSetup,
+    credentials: &Credentials,
+) -> (Vec<u8>, Vec<u8>) {
+    let (challenge_struct, challenge_message) =
+        decode_ntlm_challenge(session_setup_response_body.buffer);
+    let (head, body, session_key) =
+        builder::session_scenario_request::build_session_scenario_authenticate_request(
+            scenario,
+            &session_id,
+            response_session_id,
+            challenge_struct,
+            &challenge_message,
+            credentials,
+        );
+
+    if let (Some(mut head), Some(mut body)) = (head, body) {
+        if let Some(strategy) = fuzzing_strategy {
+            fuzz_session_scenario_fields(&mut head, &mut body, scenario, &session_id, strategy);
+        }
+        (
+            format::encoder::serialize_request(&head, &RequestType::SessionSetupAuth(body)),
+            session_key,
+        )
+    } else {
+        panic!("Could not populate session scenario packet 2.")
+    }
+}
+
+/// Fuzzes the fields of the session setup request that carry the session scenario with the given strategy.
+fn fuzz_session_scenario_fields(
+    head: &mut header::SyncHeader,
+    body: &mut requests::session_setup::SessionSetup,
+    scenario: &SessionScenario,
+    session_id: &[u8],
+    strategy: FuzzingStrategy,
+) {
+    match strategy {
+        FuzzingStrategy::Predefined => {
+            fuzzer::handshake::session_scenario_fuzzer::fuzz_session_scenario_with_predefined_values(
+                head, body, scenario, session_id,
+            )
+        }
+        FuzzingStrategy::RandomFields => {
+            fuzzer::handshake::session_scenario_fuzzer::fuzz_session_scenario_with_random_fields(
+                head, body,
+            )
+        }
+        FuzzingStrategy::CompletelyRandom => {
+            fuzzer::handshake::session_scenario_fuzzer::fuzz_session_scenario_completely_random(
+                head, body,
+            )
+        }
+    }
+}
+
+/// Builds the Kerberos session setup packet with the AP-REQ for the service ticket according to
+/// the fuzzing strategy if given. Otherwise the default Kerberos session setup packet is built.
+/// The subkey of the authenticator is returned alongside the packet if the default packet is built.
+pub fn prepare_session_setup_kerberos_packet(
+    fuzzing_strategy: Option<FuzzingStrategy>,
+    service_ticket: &ServiceTicket,
+) -> (Vec<u8>, Option<EncryptionKey>) {
+    let mut session_setup_request: (
+        Option<header::SyncHeader>,
+        Option<requests::session_setup::SessionSetup>,
+    ) = (None, None);
+    let mut subkey = None;
+    if let Some(strategy) = fuzzing_strategy {
+        session_setup_request.0 = Some(builder::build_sync_header(
+            header::Commands::SessionSetup,
+            1,
+            8192,
+            None,
+            None,
+            1,
+        ));
+        session_setup_request.1 = Some(
+            match strategy {
+                FuzzingStrategy::Predefined => fuzzer::handshake::session_setup_fuzzer::fuzz_session_setup_kerberos_with_predefined_values(service_ticket),
+                FuzzingStrategy::RandomFields => fuzzer::handshake::session_setup_fuzzer::fuzz_session_setup_kerberos_with_random_fields(),
+                FuzzingStrategy::CompletelyRandom => fuzzer::handshake::session_setup_fuzzer::fuzz_session_setup_kerberos_completely_random(),
+            }
+        );
+    } else {
+        let (head, body, key) = build_default_session_setup_kerberos_request(service_ticket);
+        session_setup_request = (head, body);
+        subkey = Some(key);
+    }
+
+    if let (Some(head), Some(body)) = session_setup_request {
+        (
+            format::encoder::serialize_request(&head, &RequestType::SessionSetupNeg(body)),
+            subkey,
+        )
+    } else {
+        panic!("Could not populate Kerberos session setup packet.")
+    }
+}
+
 /// Builds the tree connect packet according to the fuzzing strategy if given.
 /// Otherwise the default tree connect packet is built.
 pub fn prepare_tree_connect_packet(
@@ -213,6 +379,62 @@ pub fn prepare_create_packet(
     }
 }
 
+/// Builds the create packet that opens the default file with the durable handle of the durable open.
+pub fn prepare_durable_create_packet(
+    session_id: Vec<u8>,
+    tree_id: Vec<u8>,
+    durable_open: &DurableOpen,
+) -> Vec<u8> {
+    if let (Some(head), Some(body)) = builder::durable_handle_request::build_durable_create_request(
+        tree_id,
+        session_id,
+        durable_open,
+    ) {
+        format::encoder::serialize_request(&head, &RequestType::Create(body))
+    } else {
+        panic!("Could not populate durable create packet.")
+    }
+}
+
+/// Builds the create packet that reclaims the durable open. The reclaiming create contexts
+/// are fuzzed according to the fuzzing strategy if given, the predefined strategy applies the mutations.
+/// Otherwise the default reclaim is built.
+pub fn prepare_durable_reconnect_packet(
+    fuzzing_strategy: Option<FuzzingStrategy>,
+    session_id: Vec<u8>,
+    tree_id: Vec<u8>,
+    durable_open: &DurableOpen,
+    mutations: &[DurableReconnectMutation],
+) -> Vec<u8> {
+    let create_contexts = match fuzzing_strategy {
+        Some(FuzzingStrategy::Predefined) => {
+            fuzzer::durable_handle_fuzzer::fuzz_durable_reconnect_with_predefined_values(
+                durable_open,
+                mutations,
+            )
+        }
+        Some(FuzzingStrategy::RandomFields) => {
+            fuzzer::durable_handle_fuzzer::fuzz_durable_reconnect_with_random_fields(durable_open)
+        }
+        Some(FuzzingStrategy::CompletelyRandom) => {
+            fuzzer::durable_handle_fuzzer::fuzz_durable_reconnect_completely_random()
+        }
+        None => builder::durable_handle_request::build_durable_reconnect_contexts(durable_open),
+    };
+
+    if let (Some(head), Some(body)) =
+        builder::durable_handle_request::build_create_request_with_contexts(
+            tree_id,
+            session_id,
+            create_contexts,
+        )
+    {
+        format::encoder::serialize_request(&head, &RequestType::Create(body))
+    } else {
+        panic!("Could not populate durable reconnect packet.")
+    }
+}
+
 /// Builds the query info packet according to the fuzzing strategy if given.
 /// Otherwise the default query info packet is built.
 pub fn prepare_query_info_packet(
@@ -322,6 +544,430 @@ pub fn prepare_close_packet(
     }
 }
 
+/// Builds the lock packet according to the fuzzing strategy if given.
+/// Otherwise the default lock packet is built.
+pub fn prepare_lock_packet(
+    fuzzing_strategy: Option<FuzzingStrategy>,
+    session_id: Vec<u8>,
+    tree_id: Vec<u8>,
+    file_id: Vec<u8>,
+) -> Vec<u8> {
+    let mut lock_request: (Option<header::SyncHeader>, Option<Lock>) = (None, None);
+    if let Some(strategy) = fuzzing_strategy {
+        lock_request.0 = Some(builder::build_sync_header(
+            header::Commands::Lock,
+            1,
+            7840,
+            Some(tree_id),
+            Some(session_id),
+            8,
+        ));
+        lock_request.1 = Some(match strategy {
+            FuzzingStrategy::Predefined => {
+                fuzzer::lock_fuzzer::fuzz_lock_with_predefined_values(file_id)
+            }
+            FuzzingStrategy::RandomFields => fuzzer::lock_fuzzer::fuzz_lock_with_random_fields(),
+            FuzzingStrategy::CompletelyRandom => fuzzer::lock_fuzzer::fuzz_lock_completely_random(),
+        });
+    } else {
+        lock_request =
+            builder::lock_request::build_default_lock_request(tree_id, session_id, file_id);
+    }
+
+    if let (Some(head), Some(body)) = lock_request {
+        format::encoder::serialize_request(&head, &RequestType::Lock(body))
+    } else {
+        panic!("Could not populate lock request.")
+    }
+}
+
+/// Builds the flush packet according to the fuzzing strategy if given.
+/// Otherwise the default flush packet is built.
+pub fn prepare_flush_packet(
+    fuzzing_strategy: Option<FuzzingStrategy>,
+    session_id: Vec<u8>,
+    tree_id: Vec<u8>,
+    file_id: Vec<u8>,
+) -> Vec<u8> {
+    let mut flush_request: (Option<header::SyncHeader>, Option<Flush>) = (None, None);
+    if let Some(strategy) = fuzzing_strategy {
+        flush_request.0 = Some(builder::build_sync_header(
+            header::Commands::Flush,
+            1,
+            7808,
+            Some(tree_id),
+            Some(session_id),
+            9,
+        ));
+        flush_request.1 = Some(match strategy {
+            FuzzingStrategy::Predefined => {
+                fuzzer::flush_fuzzer::fuzz_flush_with_predefined_values(file_id)
+            }
+            FuzzingStrategy::RandomFields => fuzzer::flush_fuzzer::fuzz_flush_with_random_fields(),
+            FuzzingStrategy::CompletelyRandom => {
+                fuzzer::flush_fuzzer::fuzz_flush_completely_random()
+            }
+        });
+    } else {
+        flush_request =
+            builder::flush_request::build_default_flush_request(tree_id, session_id, file_id);
+    }
+
+    if let (Some(head), Some(body)) = flush_request {
+        format::encoder::serialize_request(&head, &RequestType::Flush(body))
+    } else {
+        panic!("Could not populate flush request.")
+    }
+}
+
+/// Builds the cancel packet for the request with the given message id according to
+/// the fuzzing strategy if given. The predefined strategy also targets message ids
+/// of requests that are not outstanding.
+/// Otherwise the default cancel packet is built.
+pub fn prepare_cancel_packet(
+    fuzzing_strategy: Option<FuzzingStrategy>,
+    session_id: Vec<u8>,
+    message_id: u64,
+) -> Vec<u8> {
+    let mut cancel_request: (Option<header::SyncHeader>, Option<Cancel>) = (None, None);
+    if let Some(strategy) = fuzzing_strategy {
+        cancel_request.0 = Some(builder::build_sync_header(
+            header::Commands::Cancel,
+            0,
+            0,
+            None,
+            Some(session_id),
+            fuzzer::cancel_fuzzer::sample_cancel_message_id(message_id),
+        ));
+        cancel_request.1 = Some(fuzz_cancel_body(strategy));
+    } else {
+        cancel_request = builder::cancel_request::build_cancel_request(session_id, message_id);
+    }
+
+    if let (Some(head), Some(body)) = cancel_request {
+        format::encoder::serialize_request(&head, &RequestType::Cancel(body))
+    } else {
+        panic!("Could not populate cancel request.")
+    }
+}
+
+/// Builds the async cancel packet for the pending operation with the given async id according
+/// to the fuzzing strategy if given. The predefined strategy also targets altered async ids.
+/// Otherwise the default async cancel packet is built.
+pub fn prepare_async_cancel_packet(
+    fuzzing_strategy: Option<FuzzingStrategy>,
+    session_id: Vec<u8>,
+    async_id: Vec<u8>,
+    message_id: u64,
+) -> Vec<u8> {
+    let mut cancel_request: (Option<header::AsyncHeader>, Option<Cancel>) = (None, None);
+    if let Some(strategy) = fuzzing_strategy {
+        cancel_request.0 = Some(builder::build_async_header(
+            header::Commands::Cancel,
+            0,
+            0,
+            fuzzer::cancel_fuzzer::sample_cancel_async_id(async_id),
+            Some(session_id),
+            message_id,
+        ));
+        cancel_request.1 = Some(fuzz_cancel_body(strategy));
+    } else {
+        cancel_request =
+            builder::cancel_request::build_async_cancel_request(session_id, async_id, message_id);
+    }
+
+    if let (Some(head), Some(body)) = cancel_request {
+        format::encoder::serialize_async_request(&head, &RequestType::Cancel(body))
+    } else {
+        panic!("Could not populate async cancel request.")
+    }
+}
+
+/// Builds the create packet that opens the root directory of the share.
+pub fn prepare_directory_create_packet(session_id: Vec<u8>, tree_id: Vec<u8>) -> Vec<u8> {
+    if let (Some(head), Some(body)) =
+        builder::create_request::build_directory_create_request(tree_id, session_id)
+    {
+        format::encoder::serialize_request(&head, &RequestType::Create(body))
+    } else {
+        panic!("Could not populate directory create request.")
+    }
+}
+
+/// Builds the change notify packet according to the fuzzing strategy if given.
+/// Otherwise the default change notify packet is built.
+pub fn prepare_change_notify_packet(
+    fuzzing_strategy: Option<FuzzingStrategy>,
+    session_id: Vec<u8>,
+    tree_id: Vec<u8>,
+    file_id: Vec<u8>,
+) -> Vec<u8> {
+    let mut change_notify_request: (Option<header::SyncHeader>, Option<ChangeNotify>) =
+        (None, None);
+    if let Some(strategy) = fuzzing_strategy {
+        change_notify_request.0 = Some(builder::build_sync_header(
+            header::Commands::ChangeNotify,
+            1,
+            7776,
+            Some(tree_id),
+            Some(session_id),
+            10,
+        ));
+        change_notify_request.1 = Some(match strategy {
+            FuzzingStrategy::Predefined => {
+                fuzzer::change_notify_fuzzer::fuzz_change_notify_with_predefined_values(file_id)
+            }
+            FuzzingStrategy::RandomFields => {
+                fuzzer::change_notify_fuzzer::fuzz_change_notify_with_random_fields()
+            }
+            FuzzingStrategy::CompletelyRandom => {
+                fuzzer::change_notify_fuzzer::fuzz_change_notify_completely_random()
+            }
+        });
+    } else {
+        change_notify_request = builder::change_notify_request::build_default_change_notify_request(
+            tree_id, session_id, file_id,
+        );
+    }
+
+    if let (Some(head), Some(body)) = change_notify_request {
+        format::encoder::serialize_request(&head, &RequestType::ChangeNotify(body))
+    } else {
+        panic!("Could not populate change notify request.")
+    }
+}
+
+/// Builds the default packet of the operation that races a pending change notify request.
+pub fn prepare_racing_operation_packet(
+    operation: RacingOperation,
+    session_id: Vec<u8>,
+    tree_id: Vec<u8>,
+    file_id: Vec<u8>,
+) -> Vec<u8> {
+    match operation {
+        RacingOperation::SetInfo => {
+            if let (Some(head), Some(body)) =
+                builder::set_info_request::build_default_set_info_request(
+                    tree_id, session_id, file_id,
+                )
+            {
+                format::encoder::serialize_request(&head, &RequestType::SetInfo(body))
+            } else {
+                panic!("Could not populate set info request.")
+            }
+        }
+        RacingOperation::Close => prepare_close_packet(None, session_id, tree_id, file_id),
+        RacingOperation::Logoff => {
+            if let (Some(head), Some(body)) = builder::build_logoff_request(session_id) {
+                format::encoder::serialize_request(&head, &RequestType::Logoff(body))
+            } else {
+                panic!("Could not populate logoff request.")
+            }
+        }
+    }
+}
+
+/// Builds the create packet that opens the default file with a batch oplock.
+pub fn prepare_oplock_create_packet(session_id: Vec<u8>, tree_id: Vec<u8>) -> Vec<u8> {
+    if let (Some(head), Some(body)) =
+        builder::create_request::build_oplock_create_request(tree_id, session_id)
+    {
+        format::encoder::serialize_request(&head, &RequestType::Create(body))
+    } else {
+        panic!("Could not populate oplock create request.")
+    }
+}
+
+/// Builds the acknowledgment packet for the given break notification according to
+/// the fuzzing strategy if given. Otherwise the default acknowledgment packet is built.
+pub fn prepare_oplock_break_packet(
+    fuzzing_strategy: Option<FuzzingStrategy>,
+    session_id: Vec<u8>,
+    tree_id: Vec<u8>,
+    notification: BreakNotification,
+) -> Vec<u8> {
+    let mut oplock_break_request: (Option<header::SyncHeader>, Option<OplockBreak>) = (None, None);
+    if let Some(strategy) = fuzzing_strategy {
+        oplock_break_request.0 = Some(builder::build_sync_header(
+            header::Commands::OplockBreak,
+            1,
+            7680,
+            Some(tree_id),
+            Some(session_id),
+            13,
+        ));
+        oplock_break_request.1 = Some(match strategy {
+            FuzzingStrategy::Predefined => {
+                fuzzer::oplock_break_fuzzer::fuzz_oplock_break_with_predefined_values(notification)
+            }
+            FuzzingStrategy::RandomFields => {
+                fuzzer::oplock_break_fuzzer::fuzz_oplock_break_with_random_fields(notification)
+            }
+            FuzzingStrategy::CompletelyRandom => {
+                fuzzer::oplock_break_fuzzer::fuzz_oplock_break_completely_random(notification)
+            }
+        });
+    } else {
+        oplock_break_request = builder::oplock_break_request::build_default_oplock_break_request(
+            tree_id,
+            session_id,
+            notification,
+        );
+    }
+
+    if let (Some(head), Some(body)) = oplock_break_request {
+        format::encoder::serialize_request(&head, &RequestType::OplockBreak(body))
+    } else {
+        panic!("Could not populate oplock break request.")
+    }
+}
+
+/// Fuzzes the cancel request body with the given strategy.
+fn fuzz_cancel_body(strategy: FuzzingStrategy) -> Cancel {
+    match strategy {
+        FuzzingStrategy::Predefined => fuzzer::cancel_fuzzer::fuzz_cancel_with_predefined_values(),
+        FuzzingStrategy::RandomFields => fuzzer::cancel_fuzzer::fuzz_cancel_with_random_fields(),
+        FuzzingStrategy::CompletelyRandom => fuzzer::cancel_fuzzer::fuzz_cancel_completely_random(),
+    }
+}
+
+/// Builds the compound packet according to the fuzzing strategy if given.
+/// Otherwise the default compound packet that creates, reads and closes the default file is built.
+pub fn prepare_compound_packet(
+    fuzzing_strategy: Option<FuzzingStrategy>,
+    session_id: Vec<u8>,
+    tree_id: Vec<u8>,
+) -> Vec<u8> {
+    let compound_request = match fuzzing_strategy {
+        Some(FuzzingStrategy::Predefined) => {
+            fuzzer::compound_fuzzer::fuzz_compound_with_predefined_values(tree_id, session_id)
+        }
+        Some(FuzzingStrategy::RandomFields) => {
+            fuzzer::compound_fuzzer::fuzz_compound_with_random_fields(tree_id, session_id)
+        }
+        Some(FuzzingStrategy::CompletelyRandom) => {
+            fuzzer::compound_fuzzer::fuzz_compound_completely_random(tree_id, session_id)
+        }
+        None => builder::compound_request::build_default_compound_request(tree_id, session_id),
+    };
+
+    format::encoder::compound_encoder::serialize_compound_request(&compound_request)
+}
+
+/// Fuzzes the sync or async header of the serialized packet according to the header fuzzing strategy.
+/// The async id of an async header takes the place of the reserved and tree id fields of the sync
+/// header and is fuzzed on its own, keeping the ASYNC_COMMAND flag. The body of the packet remains unchanged.
+pub fn fuzz_packet_header(packet: Vec<u8>, header_fuzzing_strategy: &FuzzingStrategy) -> Vec<u8> {
+    if packet.len() < 4 + format::HEADER_LENGTH {
+        return packet;
+    }
+
+    let header = format::decoder::decode_request_header(packet[4..68].to_vec());
+    let async_id = [header.reserved.clone(), header.tree_id.clone()].concat();
+    let fuzzed_header = match header_fuzzing_strategy {
+        FuzzingStrategy::Predefined => {
+            fuzzer::header_fuzzer::fuzz_sync_header_with_predefined_values(header)
+        }
+        FuzzingStrategy::RandomFields => {
+            fuzzer::header_fuzzer::fuzz_sync_header_with_random_fields(header)
+        }
+        FuzzingStrategy::CompletelyRandom => {
+            fuzzer::header_fuzzer::fuzz_sync_header_completely_random(header)
+        }
+    };
+
+    let fuzzed_header = if format::decoder::is_async_header(&packet[4..]) {
+        header::Header::Async(header::AsyncHeader::from_sync_header(
+            fuzzed_header,
+            fuzzer::header_fuzzer::fuzz_async_id(async_id, header_fuzzing_strategy),
+        ))
+    } else {
+        header::Header::Sync(fuzzed_header)
+    };
+
+    format::encoder::replace_header(packet, &fuzzed_header)
+}
+
+/// Fuzzes the GSS layer of the security buffer of a serialized session setup packet according to
+/// the SPNEGO fuzzing strategy. The token of the mechanism is kept, so that it can be fuzzed
+/// independently by the strategy for the message body. A security buffer that is no SPNEGO token
+/// is wrapped as the token of the mechanism. The security buffer length is only updated
+/// if it matched the security buffer, and is set to its maximum if the fuzzed token exceeds it.
+/// Other packets remain unchanged.
+pub fn fuzz_packet_spnego(packet: Vec<u8>, spnego_fuzzing_strategy: &FuzzingStrategy) -> Vec<u8> {
+    let buffer_start = 4 + format::HEADER_LENGTH + 24;
+    if packet.len() < buffer_start
+        || packet[4 + 12..4 + 14] != header::Commands::SessionSetup.unpack_byte_code()[..]
+    {
+        return packet;
+    }
+
+    let security_buffer = &packet[buffer_start..];
+    let token = match format::decoder::spnego_decoder::decode_negotiation_token(security_buffer) {
+        Some(token) => token,
+        None => {
+            let mut init = gss::NegTokenInit::default();
+            init.mech_types = vec![gss::NTLMSSP_OID.to_vec()];
+            init.mech_token = security_buffer.to_vec();
+            gss::NegotiationToken::NegTokenInit(init)
+        }
+    };
+    let mut fuzzed_buffer =
+        fuzzer::handshake::spnego_fuzzer::fuzz_spnego_token(token, spnego_fuzzing_strategy);
+
+    let mut fuzzed_packet =
+        format::encoder::serialize_netbios_session_prefix(buffer_start - 4 + fuzzed_buffer.len());
+    fuzzed_packet.extend_from_slice(&packet[4..buffer_start]);
+    let length_field = 4 + format::HEADER_LENGTH + 14..4 + format::HEADER_LENGTH + 16;
+    if u16::try_from(security_buffer.len())
+        .is_ok_and(|length| fuzzed_packet[length_field.clone()] == length.to_le_bytes())
+    {
+        let fuzzed_length = u16::try_from(fuzzed_buffer.len()).unwrap_or(u16::MAX);
+        fuzzed_packet[length_field].copy_from_slice(&fuzzed_length.to_le_bytes());
+    }
+    fuzzed_packet.append(&mut fuzzed_buffer);
+
+    fuzzed_packet
+}
+
+/// Compresses the serialized packet (including the netbios session prefix) with the compression
+/// algorithm into a chained or unchained compression transform. The unchained transform keeps the
+/// SMB2 header uncompressed. The transform is fuzzed according to the fuzzing strategy if given.
+pub fn compress_packet(
+    packet: Vec<u8>,
+    algorithm: &CompressionAlgorithms,
+    chained: bool,
+    compression_fuzzing_strategy: Option<&FuzzingStrategy>,
+) -> Vec<u8> {
+    if packet.len() < 4 {
+        return packet;
+    }
+
+    let message = &packet[4..];
+    let mut transform = if chained {
+        CompressionTransform::Chained(compression::build_chained_compression_transform(
+            message, algorithm,
+        ))
+    } else {
+        CompressionTransform::Unchained(compression::build_unchained_compression_transform(
+            message,
+            algorithm,
+            format::HEADER_LENGTH,
+        ))
+    };
+    if let Some(strategy) = compression_fuzzing_strategy {
+        transform = fuzzer::compression_fuzzer::fuzz_compression_transform(transform, strategy);
+    }
+
+    let mut serialized_transform =
+        format::encoder::compression_encoder::serialize_compression_transform(&transform);
+    let mut compressed_packet =
+        format::encoder::serialize_netbios_session_prefix(serialized_transform.len());
+    compressed_packet.append(&mut serialized_transform);
+
+    compressed_packet
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -354,6 +1000,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_fuzz_packet_spnego() {
+        let packet = prepare_session_setup_negotiate_packet(None);
+        let buffer_start = 4 + format::HEADER_LENGTH + 24;
+        let mech_token = match format::decoder::spnego_decoder::decode_negotiation_token(
+            &packet[buffer_start..],
+        ) {
+            Some(gss::NegotiationToken::NegTokenInit(init)) => init.mech_token,
+            _ => panic!("Invalid SPNEGO token."),
+        };
+
+        let fuzzed_packet = fuzz_packet_spnego(packet.clone(), &FuzzingStrategy::RandomFields);
+        let length_field = 4 + format::HEADER_LENGTH + 14;
+        assert_eq!(packet[4..length_field], fuzzed_packet[4..length_field]);
+        assert_eq!(
+            u16::try_from(fuzzed_packet.len() - buffer_start)
+                .unwrap_or(u16::MAX)
+                .to_le_bytes(),
+            fuzzed_packet[length_field..length_field + 2]
+        );
+        match format::decoder::spnego_decoder::decode_negotiation_token(
+            &fuzzed_packet[buffer_start..],
+        ) {
+            Some(gss::NegotiationToken::NegTokenInit(init)) => {
+                assert_eq!(mech_token, init.mech_token)
+            }
+            _ => panic!("Invalid SPNEGO token."),
+        }
+
+        let echo_packet = prepare_echo_packet(None);
+        assert_eq!(
+            echo_packet,
+            fuzz_packet_spnego(echo_packet.clone(), &FuzzingStrategy::Predefined)
+        );
+    }
+
     #[test]
     fn test_prepare_session_setup_authenticate_packet() {
         let security_buffer = b"\xa1\x81\xce\x30\x81\xcb\xa0\x03\x0a\x01\x01\xa1\x0c\x06\x0a\x2b\
@@ -382,24 +1064,33 @@ mod tests {
                 MessageType::Challenge(challenge) => challenge,
                 _ => panic!("Invalid message type in server response."),
             };
-        let (expected_default_header, expected_default_body) =
+        let challenge_message =
+            format::decoder::security_blob_decoder::extract_ntlm_challenge_message(
+                session_setup_response.buffer.clone(),
+            );
+        let (expected_default_header, expected_default_body, _) =
             builder::session_setup_authenticate_request::build_default_session_setup_authenticate_request(
                 vec![0, 1, 2, 3, 4, 5, 6, 7],
                 challenge_struct,
+                &challenge_message,
+                &Credentials::default(),
             );
         let expected_default_request = format::encoder::serialize_request(
             &expected_default_header.unwrap(),
             &RequestType::SessionSetupAuth(expected_default_body.unwrap()),
         );
 
-        assert_eq!(
-            expected_default_request,
-            prepare_session_setup_authenticate_packet(
-                None,
-                vec![0, 1, 2, 3, 4, 5, 6, 7],
-                session_setup_response
-            )
+        // The client challenge and the exported session key are random, so only the
+        // header, the fixed fields of the body and the length of the buffer are compared.
+        let (default_request, session_key) = prepare_session_setup_authenticate_packet(
+            None,
+            vec![0, 1, 2, 3, 4, 5, 6, 7],
+            session_setup_response,
+            &Credentials::default(),
         );
+        assert_eq!(expected_default_request.len(), default_request.len());
+        assert_eq!(expected_default_request[..92], default_request[..92]);
+        assert_eq!(Some(16), session_key.map(|key| key.len()));
     }
 
     #[test]
@@ -495,4 +1186,161 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn test_prepare_lock_packet() {
+        let (expected_default_header, expected_default_body) =
+            builder::lock_request::build_default_lock_request(
+                vec![0, 1, 2, 3],
+                vec![0, 1, 2, 3, 4, 5, 6, 7],
+                vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+            );
+        let expected_default_request = format::encoder::serialize_request(
+            &expected_default_header.unwrap(),
+            &RequestType::Lock(expected_default_body.unwrap()),
+        );
+
+        assert_eq!(
+            expected_default_request,
+            prepare_lock_packet(
+                None,
+                vec![0, 1, 2, 3, 4, 5, 6, 7],
+                vec![0, 1, 2, 3],
+                vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]
+            )
+        );
+    }
+
+    #[test]
+    fn test_prepare_flush_packet() {
+        let (expected_default_header, expected_default_body) =
+            builder::flush_request::build_default_flush_request(
+                vec![0, 1, 2, 3],
+                vec![0, 1, 2, 3, 4, 5, 6, 7],
+                vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+            );
+        let expected_default_request = format::encoder::serialize_request(
+            &expected_default_header.unwrap(),
+            &RequestType::Flush(expected_default_body.unwrap()),
+        );
+
+        assert_eq!(
+            expected_default_request,
+            prepare_flush_packet(
+                None,
+                vec![0, 1, 2, 3, 4, 5, 6, 7],
+                vec![0, 1, 2, 3],
+                vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]
+            )
+        );
+    }
+
+    #[test]
+    fn test_prepare_async_cancel_packet() {
+        let cancel_packet = prepare_async_cancel_packet(
+            None,
+            vec![0, 1, 2, 3, 4, 5, 6, 7],
+            vec![8, 0, 0, 0, 0, 0, 0, 0],
+            10,
+        );
+
+        assert_eq!(vec![0, 0, 0, 68], cancel_packet[..4].to_vec());
+        assert_eq!(vec![0x0c, 0x00], cancel_packet[16..18].to_vec());
+        assert_eq!(vec![0x12, 0, 0, 0], cancel_packet[20..24].to_vec());
+        assert_eq!(vec![8, 0, 0, 0, 0, 0, 0, 0], cancel_packet[36..44].to_vec());
+        assert_eq!(vec![4, 0, 0, 0], cancel_packet[68..].to_vec());
+    }
+
+    #[test]
+    fn test_fuzz_packet_header_keeps_async_header() {
+        let cancel_packet = prepare_async_cancel_packet(
+            None,
+            vec![0, 1, 2, 3, 4, 5, 6, 7],
+            vec![8, 0, 0, 0, 0, 0, 0, 0],
+            10,
+        );
+
+        for _ in 0..32 {
+            let fuzzed_packet =
+                fuzz_packet_header(cancel_packet.clone(), &FuzzingStrategy::Predefined);
+
+            assert_eq!(cancel_packet.len(), fuzzed_packet.len());
+            assert!(format::decoder::is_async_header(&fuzzed_packet[4..]));
+            assert_eq!(cancel_packet[16..18], fuzzed_packet[16..18]);
+            assert_eq!(cancel_packet[68..], fuzzed_packet[68..]);
+        }
+    }
+
+    #[test]
+    fn test_prepare_change_notify_packet() {
+        let (expected_default_header, expected_default_body) =
+            builder::change_notify_request::build_default_change_notify_request(
+                vec![0, 1, 2, 3],
+                vec![0, 1, 2, 3, 4, 5, 6, 7],
+                vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+            );
+        let expected_default_request = format::encoder::serialize_request(
+            &expected_default_header.unwrap(),
+            &RequestType::ChangeNotify(expected_default_body.unwrap()),
+        );
+
+        assert_eq!(
+            expected_default_request,
+            prepare_change_notify_packet(
+                None,
+                vec![0, 1, 2, 3, 4, 5, 6, 7],
+                vec![0, 1, 2, 3],
+                vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]
+            )
+        );
+    }
+
+    #[test]
+    fn test_prepare_racing_operation_packet() {
+        let logoff_packet = prepare_racing_operation_packet(
+            RacingOperation::Logoff,
+            vec![0, 1, 2, 3, 4, 5, 6, 7],
+            vec![0, 1, 2, 3],
+            vec![0; 16],
+        );
+
+        assert_eq!(vec![0, 0, 0, 68], logoff_packet[..4].to_vec());
+        assert_eq!(vec![0x02, 0x00], logoff_packet[16..18].to_vec());
+        assert_eq!(vec![4, 0, 0, 0], logoff_packet[68..].to_vec());
+
+        let set_info_packet = prepare_racing_operation_packet(
+            RacingOperation::SetInfo,
+            vec![0, 1, 2, 3, 4, 5, 6, 7],
+            vec![0, 1, 2, 3],
+            vec![0; 16],
+        );
+
+        assert_eq!(vec![0, 0, 0, 136], set_info_packet[..4].to_vec());
+        assert_eq!(vec![0x11, 0x00], set_info_packet[16..18].to_vec());
+    }
+
+    #[test]
+    fn test_prepare_oplock_break_packet() {
+        let mut notification = responses::oplock_break::OplockBreak::default();
+        notification.oplock_level = vec![1];
+        notification.file_id = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+        let oplock_break_packet = prepare_oplock_break_packet(
+            None,
+            vec![0, 1, 2, 3, 4, 5, 6, 7],
+            vec![0, 1, 2, 3],
+            BreakNotification::Oplock(notification),
+        );
+
+        assert_eq!(vec![0, 0, 0, 88], oplock_break_packet[..4].to_vec());
+        assert_eq!(vec![0x12, 0x00], oplock_break_packet[16..18].to_vec());
+        assert_eq!(
+            b"\x18\x00\x01\x00\x00\x00\x00\x00".to_vec(),
+            oplock_break_packet[68..76].to_vec()
+        );
+        assert_eq!(
+            vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+            oplock_break_packet[76..].to_vec()
+        );
+    }
 }
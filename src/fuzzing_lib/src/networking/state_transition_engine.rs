@@ -1,18 +1,22 @@
 use std::net::TcpStream;
 
 use crate::{
+    builder::durable_handle_request::DurableOpen,
     format::{
         self,
         decoder::{create_decoder::decode_create_response_body, decode_response_header},
+        HEADER_LENGTH,
     },
     smb2::responses::session_setup::SessionSetup,
 };
 
-use super::connect;
+use super::{connect, connection_context::ConnectionContext};
 
 /// The State Enum represents the implemented states the SMB fuzzer can reach.
 /// The Negotiate, SessionSetupNeg, SessionSetupAuth and TreeConnect state are part of the SMB handshake.
-/// The remaining states are reached after a successful handshake.
+/// The remaining states are reached after a successful handshake. In the DurableOpen state, the file
+/// is open with a durable handle. In the DurableReconnect state, the durable handle has been reclaimed
+/// after the connection was dropped.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum State {
     Initial,
@@ -22,6 +26,8 @@ pub enum State {
     TreeConnect,
     Create,
     Close,
+    DurableOpen,
+    DurableReconnect,
 }
 
 pub enum ResponseType {
@@ -32,6 +38,7 @@ pub enum ResponseType {
     TreeConnect((Vec<u8>, Vec<u8>)),
     Create((Vec<u8>, Vec<u8>, Vec<u8>)),
     Close((Vec<u8>, Vec<u8>)),
+    DurableOpen((Vec<u8>, Vec<u8>, DurableOpen)),
 }
 
 impl State {
@@ -45,42 +52,60 @@ impl State {
             "-tree_state" => State::TreeConnect,
             "-create_state" => State::Create,
             "-close_state" => State::Close,
+            "-durable_open_state" => State::DurableOpen,
+            "-durable_reconnect_state" => State::DurableReconnect,
             _ => panic!("Invalid state."),
         }
     }
 
     /// Goes to the state specified by the state enum and returns a payload if needed.
-    pub fn go_to_state(&self, stream: &mut TcpStream) -> ResponseType {
+    pub fn go_to_state(
+        &self,
+        stream: &mut TcpStream,
+        context: &mut ConnectionContext,
+    ) -> ResponseType {
         match self {
             State::Initial => ResponseType::Initial,
             State::Negotiate => {
-                Self::go_to_negotiate_state(stream);
+                Self::go_to_negotiate_state(stream, context);
                 ResponseType::Negotiate
             }
-            State::SessionSetupNeg => {
-                ResponseType::SessionSetupNeg(Self::go_to_session_setup_negotiate_state(stream))
+            State::SessionSetupNeg => ResponseType::SessionSetupNeg(
+                Self::go_to_session_setup_negotiate_state(stream, context),
+            ),
+            State::SessionSetupAuth => ResponseType::SessionSetupAuth(
+                Self::go_to_session_setup_authenticate_state(stream, context),
+            ),
+            State::TreeConnect => {
+                ResponseType::TreeConnect(Self::go_to_tree_connect_state(stream, context))
             }
-            State::SessionSetupAuth => {
-                ResponseType::SessionSetupAuth(Self::go_to_session_setup_authenticate_state(stream))
+            State::Create => ResponseType::Create(Self::go_to_create_state(stream, context)),
+            State::Close => ResponseType::Close(Self::go_to_close_state(stream, context)),
+            State::DurableOpen => {
+                ResponseType::DurableOpen(Self::go_to_durable_open_state(stream, context))
+            }
+            State::DurableReconnect => {
+                ResponseType::Create(Self::go_to_durable_reconnect_state(stream, context))
             }
-            State::TreeConnect => ResponseType::TreeConnect(Self::go_to_tree_connect_state(stream)),
-            State::Create => ResponseType::Create(Self::go_to_create_state(stream)),
-            State::Close => ResponseType::Close(Self::go_to_close_state(stream)),
         }
     }
 
     /// Sends a negotiate message to the server, entering the protocol
     /// state after the negotiate response.
-    pub fn go_to_negotiate_state(stream: &mut TcpStream) {
-        connect::send_negotiate(stream, None);
+    pub fn go_to_negotiate_state(stream: &mut TcpStream, context: &mut ConnectionContext) {
+        connect::send_negotiate(stream, context, None);
     }
 
     /// Sends the first session setup message to the server, entering the protocol
     /// state after the first session setup response.
     /// Returns the session id.
-    pub fn go_to_session_setup_negotiate_state(stream: &mut TcpStream) -> (SessionSetup, Vec<u8>) {
-        Self::go_to_negotiate_state(stream);
-        let response = connect::send_session_setup_negotiate_request_and_get_response(stream, None);
+    pub fn go_to_session_setup_negotiate_state(
+        stream: &mut TcpStream,
+        context: &mut ConnectionContext,
+    ) -> (SessionSetup, Vec<u8>) {
+        Self::go_to_negotiate_state(stream, context);
+        let response =
+            connect::send_session_setup_negotiate_request_and_get_response(stream, context, None);
         let (response_header, session_setup_response_body) =
             format::decoder::decode_session_setup_response(response.to_vec());
 
@@ -88,13 +113,22 @@ impl State {
     }
 
     /// Sends the second session setup message to the server, entering the protocol
-    /// state after the second session setup response.
+    /// state after the second session setup response. If Kerberos is configured,
+    /// the session is authenticated with a single Kerberos session setup message instead.
     /// Returns the newly created session id.
-    pub fn go_to_session_setup_authenticate_state(stream: &mut TcpStream) -> Vec<u8> {
+    pub fn go_to_session_setup_authenticate_state(
+        stream: &mut TcpStream,
+        context: &mut ConnectionContext,
+    ) -> Vec<u8> {
+        if context.kerberos.is_some() {
+            Self::go_to_negotiate_state(stream, context);
+            return connect::send_session_setup_kerberos_request(stream, context, None);
+        }
         let (session_setup_response_body, session_id) =
-            Self::go_to_session_setup_negotiate_state(stream);
+            Self::go_to_session_setup_negotiate_state(stream, context);
         connect::send_session_setup_authenticate_request(
             stream,
+            context,
             session_setup_response_body,
             session_id.clone(),
             None,
@@ -106,41 +140,124 @@ impl State {
     /// Sends the tree connect message to the server, entering the protocol
     /// state after the tree connect response
     /// Returns session and newly created tree id.
-    pub fn go_to_tree_connect_state(stream: &mut TcpStream) -> (Vec<u8>, Vec<u8>) {
-        let session_id = Self::go_to_session_setup_authenticate_state(stream);
-        let tree_connect_response =
-            connect::send_tree_connect_request_and_get_response(stream, session_id.clone(), None);
-        (
-            session_id,
-            decode_response_header(tree_connect_response[4..68].to_vec()).tree_id,
-        )
+    pub fn go_to_tree_connect_state(
+        stream: &mut TcpStream,
+        context: &mut ConnectionContext,
+    ) -> (Vec<u8>, Vec<u8>) {
+        let session_id = Self::go_to_session_setup_authenticate_state(stream, context);
+        let tree_connect_response = connect::send_tree_connect_request_and_get_response(
+            stream,
+            context,
+            session_id.clone(),
+            None,
+        );
+        let tree_id = if tree_connect_response.len() >= 4 + HEADER_LENGTH {
+            decode_response_header(tree_connect_response[4..4 + HEADER_LENGTH].to_vec()).tree_id
+        } else {
+            vec![0; 4]
+        };
+
+        (session_id, tree_id)
     }
 
     /// Sends the create message to the server, entering the protocol
     /// state after the create response and after the file has been opened/created.
     /// Returns the session, tree and newly created file id.
-    pub fn go_to_create_state(stream: &mut TcpStream) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
-        let (session_id, tree_id) = Self::go_to_tree_connect_state(stream);
+    pub fn go_to_create_state(
+        stream: &mut TcpStream,
+        context: &mut ConnectionContext,
+    ) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let (session_id, tree_id) = Self::go_to_tree_connect_state(stream, context);
         let create_response = connect::send_create_request_and_get_response(
             stream,
+            context,
             session_id.clone(),
             tree_id.clone(),
             None,
         );
 
-        (
-            session_id,
-            tree_id,
-            decode_create_response_body(create_response[68..].to_vec()).file_id,
-        )
+        let file_id = if create_response.len() >= 4 + HEADER_LENGTH + 88 {
+            decode_create_response_body(create_response[4 + HEADER_LENGTH..].to_vec()).file_id
+        } else {
+            vec![0; 16]
+        };
+
+        (session_id, tree_id, file_id)
     }
 
     /// Sends the close message to the server, entering the protocol
     /// state after the close response and after the file has been closed.
     /// Returns the session and tree id
-    pub fn go_to_close_state(stream: &mut TcpStream) -> (Vec<u8>, Vec<u8>) {
-        let (session_id, tree_id, file_id) = Self::go_to_create_state(stream);
-        connect::send_close_request(stream, session_id.clone(), tree_id.clone(), file_id, None);
+    pub fn go_to_close_state(
+        stream: &mut TcpStream,
+        context: &mut ConnectionContext,
+    ) -> (Vec<u8>, Vec<u8>) {
+        let (session_id, tree_id, file_id) = Self::go_to_create_state(stream, context);
+        connect::send_close_request(
+            stream,
+            context,
+            session_id.clone(),
+            tree_id.clone(),
+            file_id,
+            None,
+        );
         (session_id, tree_id)
     }
+
+    /// Opens the default file with a durable handle of the configured version, entering the protocol
+    /// state after the create response. Returns the session and tree id and the durable open.
+    pub fn go_to_durable_open_state(
+        stream: &mut TcpStream,
+        context: &mut ConnectionContext,
+    ) -> (Vec<u8>, Vec<u8>, DurableOpen) {
+        let (session_id, tree_id) = Self::go_to_tree_connect_state(stream, context);
+        let mut durable_open = DurableOpen::new(context.durable_handle_version.clone());
+        connect::send_durable_create_request_and_get_response(
+            stream,
+            context,
+            session_id.clone(),
+            tree_id.clone(),
+            &mut durable_open,
+        );
+
+        (session_id, tree_id, durable_open)
+    }
+
+    /// Opens the default file with a durable handle, drops the connection, reconnects with the same
+    /// credentials and reclaims the durable handle, entering the protocol state after the reclaiming
+    /// create response. Returns the session, tree and file id on the new connection.
+    /// If the reconnect fails, zeroed session and tree ids and the durable file id are returned.
+    pub fn go_to_durable_reconnect_state(
+        stream: &mut TcpStream,
+        context: &mut ConnectionContext,
+    ) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let (_, _, durable_open) = Self::go_to_durable_open_state(stream, context);
+        let (session_id, tree_id) = match connect::reconnect_after_connection_loss(
+            stream,
+            context,
+            context.credentials.clone(),
+        ) {
+            Ok(ids) => ids,
+            Err(e) => {
+                println!("Failed to reconnect to the server: {}", e);
+                return (vec![0; 8], vec![0; 4], durable_open.file_id);
+            }
+        };
+        let reconnect_response = connect::send_durable_reconnect_request_and_get_response(
+            stream,
+            context,
+            session_id.clone(),
+            tree_id.clone(),
+            &durable_open,
+            None,
+        );
+
+        let file_id = if reconnect_response.len() >= 4 + HEADER_LENGTH + 88 {
+            decode_create_response_body(reconnect_response[4 + HEADER_LENGTH..].to_vec()).file_id
+        } else {
+            durable_open.file_id
+        };
+
+        (session_id, tree_id, file_id)
+    }
 }
@@ -302,6 +302,9 @@ pub struct NtlmV2ClientChallenge {
     /// AV_PAIR structures. The sequence contains the server-naming
     /// context and is terminated by an AV_PAIR structure with an AvId field of MsvAvEOL.
     pub av_pairs: Vec<AvPair>,
+    /// Padding (4 bytes): The four zero bytes that end the temp structure over which
+    /// the NTProofStr is calculated.
+    pub padding: Vec<u8>,
 }
 
 impl NtlmV2ClientChallenge {
@@ -315,6 +318,7 @@ impl NtlmV2ClientChallenge {
             challenge_from_client: b"\x22\x10\x50\xcd\x22\xf4\xa4\x14".to_vec(),
             reserved3: vec![0; 4],
             av_pairs: Vec::new(),
+            padding: vec![0; 4],
         }
     }
 }
@@ -0,0 +1,121 @@
+//! The credentials with which the client authenticates itself in the AUTHENTICATE_MESSAGE.
+//! Besides a user, the client can log on as the guest account or anonymously.
+
+/// The user, domain and workstation names and the password of the NTLM authentication.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Credentials {
+    /// The name of the user to be authenticated.
+    pub user_name: String,
+    /// The domain or computer name hosting the user account.
+    pub domain_name: String,
+    /// The password of the user, from which the NTLMv2 response key is derived.
+    pub password: String,
+    /// The name of the computer to which the user is logged on.
+    pub workstation: String,
+    /// The identity under which the session is set up.
+    pub identity: Identity,
+}
+
+impl Credentials {
+    /// Creates the default credentials of user "tom" in the domain "WORKGROUP" with an empty password.
+    pub fn default() -> Self {
+        Credentials {
+            user_name: "tom".to_string(),
+            domain_name: "WORKGROUP".to_string(),
+            password: String::new(),
+            workstation: "TOM".to_string(),
+            identity: Identity::User,
+        }
+    }
+
+    /// Creates the credentials of the guest account with an empty password.
+    pub fn guest() -> Self {
+        let mut credentials = Credentials::default();
+        credentials.user_name = "Guest".to_string();
+        credentials.identity = Identity::Guest;
+
+        credentials
+    }
+
+    /// Creates the credentials of the anonymous logon with empty user and domain names and password.
+    pub fn anonymous() -> Self {
+        let mut credentials = Credentials::default();
+        credentials.user_name = String::new();
+        credentials.domain_name = String::new();
+        credentials.identity = Identity::Anonymous;
+
+        credentials
+    }
+
+    /// Maps the user input to credentials. The user is given as "domain\user" or "user",
+    /// in which case the default domain is kept.
+    pub fn map_string_to_credentials(user: &str, password: &str) -> Self {
+        let mut credentials = Credentials::default();
+
+        match user.split_once('\\') {
+            Some((domain_name, user_name)) => {
+                credentials.domain_name = domain_name.to_string();
+                credentials.user_name = user_name.to_string();
+            }
+            None => credentials.user_name = user.to_string(),
+        }
+        credentials.password = password.to_string();
+
+        credentials
+    }
+}
+
+/// The identity under which the session is set up.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Identity {
+    /// Authenticates the user with NTLMv2.
+    User,
+    /// Authenticates the guest account with NTLMv2, which the server marks with SESSION_FLAG_IS_GUEST.
+    Guest,
+    /// Logs on anonymously with empty LM and NT challenge responses and a NULL user,
+    /// which the server marks with SESSION_FLAG_IS_NULL.
+    Anonymous,
+}
+
+impl Identity {
+    /// Maps the user input to the credentials of the identity.
+    /// The user identity takes the default credentials.
+    pub fn map_string_to_credentials(identity: &str) -> Credentials {
+        match identity {
+            "user" => Credentials::default(),
+            "guest" => Credentials::guest(),
+            "anonymous" => Credentials::anonymous(),
+            _ => panic!("Invalid identity."),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_string_to_credentials() {
+        let credentials = Credentials::map_string_to_credentials("DOMAIN\\alice", "secret");
+
+        assert_eq!("DOMAIN", credentials.domain_name);
+        assert_eq!("alice", credentials.user_name);
+        assert_eq!("secret", credentials.password);
+        assert_eq!(
+            "WORKGROUP",
+            Credentials::map_string_to_credentials("bob", "").domain_name
+        );
+    }
+
+    #[test]
+    fn test_map_string_to_identity_credentials() {
+        assert_eq!(
+            Identity::Guest,
+            Identity::map_string_to_credentials("guest").identity
+        );
+
+        let anonymous = Identity::map_string_to_credentials("anonymous");
+        assert_eq!(Identity::Anonymous, anonymous.identity);
+        assert!(anonymous.user_name.is_empty() && anonymous.domain_name.is_empty());
+    }
+}
@@ -2,10 +2,13 @@
 
 pub mod authenticate;
 pub mod challenge;
+pub mod credentials;
+pub mod negotiate;
 pub mod negotiate_flags;
 
 use authenticate::Authenticate;
 use challenge::Challenge;
+use negotiate::Negotiate;
 
 /// Signature 'N', 'T', 'L', 'M', 'S', 'S', 'P', '\0'
 const SIGNATURE: &[u8; 8] = b"\x4e\x54\x4c\x4d\x53\x53\x50\x00";
@@ -35,6 +38,7 @@ impl Header {
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum MessageType {
+    Negotiate(Negotiate),
     Challenge(Challenge),
     Authenticate(Box<Authenticate>),
 }
@@ -43,6 +47,7 @@ impl MessageType {
     /// Unpacks the byte code of NTLM message types.
     pub fn unpack_byte_code(&self) -> Vec<u8> {
         match self {
+            MessageType::Negotiate(_) => b"\x01\x00\x00\x00".to_vec(),
             MessageType::Challenge(_) => b"\x02\x00\x00\x00".to_vec(),
             MessageType::Authenticate(_) => b"\x03\x00\x00\x00".to_vec(),
         }
@@ -52,6 +57,7 @@ impl MessageType {
     pub fn map_byte_code_to_message_type(byte_code: Vec<u8>) -> MessageType {
         if let Some(code) = byte_code.get(0) {
             match code {
+                1 => MessageType::Negotiate(Negotiate::default()),
                 2 => MessageType::Challenge(Challenge::default()),
                 3 => MessageType::Authenticate(Box::new(Authenticate::default())),
                 _ => panic!("Invalid message type for NTLMSSP."),
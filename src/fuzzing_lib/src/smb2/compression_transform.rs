@@ -0,0 +1,121 @@
+//! This module represents the compression transform of compressed messages.
+//! The SMB2 COMPRESSION_TRANSFORM_HEADER is used by the client or server when sending
+//! compressed messages. It is only valid for the SMB 3.1.1 dialect and is either unchained,
+//! holding a single compressed segment, or chained, holding a sequence of compressed payloads.
+
+/// The protocol id of the compression transform header (0xFC, 'S', 'M', 'B').
+pub const COMPRESSION_PROTOCOL_ID: &[u8; 4] = b"\xfc\x53\x4d\x42";
+/// The SMB2_COMPRESSION_FLAG_CHAINED flag of chained compression transforms.
+pub const COMPRESSION_FLAG_CHAINED: &[u8; 2] = b"\x01\x00";
+/// The SMB2_COMPRESSION_FLAG_NONE flag of unchained compression transforms.
+pub const COMPRESSION_FLAG_NONE: &[u8; 2] = b"\x00\x00";
+
+/// A struct that represents the unchained SMB2 COMPRESSION_TRANSFORM_HEADER
+/// followed by the uncompressed and the compressed data.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct UnchainedCompressionTransform {
+    /// ProtocolId (4 bytes): The protocol identifier. The value MUST be (in network order) 0xFC, 'S', 'M', and 'B'.
+    pub protocol_id: Vec<u8>,
+    /// OriginalCompressedSegmentSize (4 bytes): The size, in bytes, of the uncompressed data segment.
+    pub original_compressed_segment_size: Vec<u8>,
+    /// CompressionAlgorithm (2 bytes): This field MUST contain one of the algorithms
+    /// used to compress the SMB2 message except "NONE".
+    pub compression_algorithm: Vec<u8>,
+    /// Flags (2 bytes): This field MUST be set to SMB2_COMPRESSION_FLAG_NONE.
+    pub flags: Vec<u8>,
+    /// Offset (4 bytes): The offset, in bytes, from the end of this structure to the start of compressed data segment.
+    pub offset: Vec<u8>,
+    /// The uncompressed data of offset bytes, which directly follows the header.
+    pub uncompressed_data: Vec<u8>,
+    /// The compressed data segment.
+    pub compressed_data: Vec<u8>,
+}
+
+impl UnchainedCompressionTransform {
+    /// Creates a new unchained compression transform by setting the protocol id and the flags initially.
+    pub fn default() -> Self {
+        UnchainedCompressionTransform {
+            protocol_id: COMPRESSION_PROTOCOL_ID.to_vec(),
+            original_compressed_segment_size: Vec::new(),
+            compression_algorithm: Vec::new(),
+            flags: COMPRESSION_FLAG_NONE.to_vec(),
+            offset: Vec::new(),
+            uncompressed_data: Vec::new(),
+            compressed_data: Vec::new(),
+        }
+    }
+}
+
+/// A struct that represents a single payload of a chained compression transform,
+/// consisting of the SMB2_COMPRESSION_CHAINED_PAYLOAD_HEADER and the payload data.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ChainedPayload {
+    /// CompressionAlgorithm (2 bytes): This field MUST contain one of the supported compression algorithms.
+    pub compression_algorithm: Vec<u8>,
+    /// Flags (2 bytes): This field MUST be set to SMB2_COMPRESSION_FLAG_CHAINED.
+    pub flags: Vec<u8>,
+    /// Length (4 bytes): The length, in bytes, of the compressed payload including the original payload size.
+    pub length: Vec<u8>,
+    /// OriginalPayloadSize (4 bytes): The size, in bytes, of the uncompressed payload.
+    /// This field is only present for the LZNT1, LZ77 and LZ77+Huffman algorithms.
+    pub original_payload_size: Vec<u8>,
+    /// The compressed payload data.
+    pub payload_data: Vec<u8>,
+}
+
+impl ChainedPayload {
+    /// Creates a new chained payload by setting the flags initially.
+    pub fn default() -> Self {
+        ChainedPayload {
+            compression_algorithm: Vec::new(),
+            flags: COMPRESSION_FLAG_CHAINED.to_vec(),
+            length: Vec::new(),
+            original_payload_size: Vec::new(),
+            payload_data: Vec::new(),
+        }
+    }
+}
+
+/// A struct that represents the chained SMB2 COMPRESSION_TRANSFORM_HEADER followed by its payloads.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ChainedCompressionTransform {
+    /// ProtocolId (4 bytes): The protocol identifier. The value MUST be (in network order) 0xFC, 'S', 'M', and 'B'.
+    pub protocol_id: Vec<u8>,
+    /// OriginalCompressedSegmentSize (4 bytes): The size, in bytes, of the uncompressed data segment.
+    pub original_compressed_segment_size: Vec<u8>,
+    /// The payloads of the chained compression transform.
+    pub payloads: Vec<ChainedPayload>,
+}
+
+impl ChainedCompressionTransform {
+    /// Creates a new chained compression transform by setting the protocol id initially.
+    pub fn default() -> Self {
+        ChainedCompressionTransform {
+            protocol_id: COMPRESSION_PROTOCOL_ID.to_vec(),
+            original_compressed_segment_size: Vec::new(),
+            payloads: Vec::new(),
+        }
+    }
+}
+
+/// The compression transform is either unchained or chained.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CompressionTransform {
+    Unchained(UnchainedCompressionTransform),
+    Chained(ChainedCompressionTransform),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_compression_transforms() {
+        let unchained = UnchainedCompressionTransform::default();
+        assert_eq!(COMPRESSION_PROTOCOL_ID.to_vec(), unchained.protocol_id);
+        assert_eq!(b"\x00\x00".to_vec(), unchained.flags);
+
+        let payload = ChainedPayload::default();
+        assert_eq!(b"\x01\x00".to_vec(), payload.flags);
+    }
+}
@@ -1,9 +1,19 @@
 //! This file contains all necessary information needed to construct a SMB2 packet.
 
+use rand::{
+    distributions::{Distribution, Standard},
+    Rng,
+};
+
 /// Protocol id with fixed value
-const PROTOCOL_ID: &[u8; 4] = b"\xfe\x53\x4d\x42";
+pub const PROTOCOL_ID: &[u8; 4] = b"\xfe\x53\x4d\x42";
+/// Protocol id of the SMB2 TRANSFORM_HEADER with fixed value
+pub const TRANSFORM_PROTOCOL_ID: &[u8; 4] = b"\xfd\x53\x4d\x42";
 /// SMB head size of 64 bytes
 const STRUCTURE_SIZE: &[u8; 2] = b"\x40\x00";
+/// STATUS_PENDING (4 bytes) is set in the interim response with which the server
+/// announces the asynchronous processing of a request.
+pub const STATUS_PENDING: &[u8; 4] = b"\x03\x01\x00\x00";
 
 /// All commands that could be in the command field.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -109,6 +119,30 @@ impl Flags {
             Flags::NoFlags => b"\x00\x00\x00\x00".to_vec(),
         }
     }
+
+    /// Returns a sum of the given flags as a 4 byte array.
+    pub fn return_sum_of_chosen_flags(flags: Vec<Flags>) -> Vec<u8> {
+        let combined_flags: u8 = flags
+            .iter()
+            .fold(0u8, |acc, flag| acc | flag.unpack_byte_code()[0]);
+
+        vec![combined_flags, 0, 0, 0]
+    }
+}
+
+impl Distribution<Flags> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Flags {
+        match rng.gen_range(0..=7) {
+            0 => Flags::ServerToRedir,
+            1 => Flags::AsyncCommand,
+            2 => Flags::RelatedOperations,
+            3 => Flags::Signed,
+            4 => Flags::PriorityMask,
+            5 => Flags::DfsOperations,
+            6 => Flags::ReplayOperation,
+            _ => Flags::NoFlags,
+        }
+    }
 }
 
 /// The SMB header struct contains all fields necessary to build a SMB header.
@@ -216,6 +250,23 @@ impl AsyncHeader {
             signature: Vec::new(),
         }
     }
+
+    /// Converts the sync header into an async header with the given async id.
+    /// The ASYNC_COMMAND flag is added to the existing flags, and the async id
+    /// replaces the reserved and tree id fields.
+    pub fn from_sync_header(sync_header: SyncHeader, async_id: Vec<u8>) -> Self {
+        let mut async_header = AsyncHeader::default();
+
+        async_header.generic = sync_header.generic;
+        if let Some(flags) = async_header.generic.flags.first_mut() {
+            *flags |= Flags::AsyncCommand.unpack_byte_code()[0];
+        }
+        async_header.async_id = async_id;
+        async_header.session_id = sync_header.session_id;
+        async_header.signature = sync_header.signature;
+
+        async_header
+    }
 }
 
 impl std::fmt::Display for AsyncHeader {
@@ -281,3 +332,67 @@ impl std::fmt::Display for SyncHeader {
         )
     }
 }
+
+/// The SMB2 TRANSFORM_HEADER is used by the client or server when sending encrypted messages.
+/// It is only valid for the SMB 3.x dialect family.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TransformHeader {
+    /// ProtocolId (4 bytes): The protocol identifier. The value MUST be (in network order) 0xFD, 'S', 'M', and 'B'.
+    pub protocol_id: Vec<u8>,
+    /// Signature (16 bytes): The 16-byte signature of the encrypted message generated by using Session.EncryptionKey.
+    pub signature: Vec<u8>,
+    /// Nonce (16 bytes): An implementation-specific value assigned for every encrypted message.
+    /// This MUST NOT be reused for all encrypted messages within a session.
+    /// For AES-CCM, the first 11 bytes and for AES-GCM, the first 12 bytes are the nonce,
+    /// the remaining bytes MUST be 0.
+    pub nonce: Vec<u8>,
+    /// OriginalMessageSize (4 bytes): The size, in bytes, of the SMB2 message.
+    pub original_message_size: Vec<u8>,
+    /// Reserved (2 bytes): This field MUST NOT be used and MUST be reserved.
+    /// The sender MUST set this to 0, and the receiver MUST ignore it.
+    pub reserved: Vec<u8>,
+    /// Flags/EncryptionAlgorithm (2 bytes): For the SMB 3.1.1 dialect, the flags field
+    /// MUST be set to 0x0001 (Encrypted). For the SMB 3.0 and 3.0.2 dialects,
+    /// the encryption algorithm field MUST be set to 0x0001 (SMB2_ENCRYPTION_AES128_CCM).
+    pub flags: Vec<u8>,
+    /// SessionId (8 bytes): Uniquely identifies the established session for the command.
+    pub session_id: Vec<u8>,
+}
+
+impl TransformHeader {
+    /// Creates a new transform header by setting the protocol id, the reserved field and the flags initially.
+    pub fn default() -> Self {
+        TransformHeader {
+            protocol_id: TRANSFORM_PROTOCOL_ID.to_vec(),
+            signature: vec![0; 16],
+            nonce: Vec::new(),
+            original_message_size: Vec::new(),
+            reserved: vec![0; 2],
+            flags: b"\x01\x00".to_vec(),
+            session_id: Vec::new(),
+        }
+    }
+}
+
+impl std::fmt::Display for TransformHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Transform Header: \n\t{:?}\n\t{:?}\n\t{:?}\n\t{:?}\n\t{:?}\n\t{:?}\n\t{:?}",
+            self.protocol_id,
+            self.signature,
+            self.nonce,
+            self.original_message_size,
+            self.reserved,
+            self.flags,
+            self.session_id,
+        )
+    }
+}
+
+/// The SMB2 header is either sync or async, depending on the ASYNC_COMMAND flag.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Header {
+    Sync(SyncHeader),
+    Async(AsyncHeader),
+}
@@ -35,11 +35,12 @@ impl SecurityMode {
     }
 
     /// Maps the byte code of an incoming response to the corresponding security mode.
+    /// Servers that require signing may set both bits, which maps to the required mode.
     pub fn map_byte_code_to_mode(byte_code: Vec<u8>) -> SecurityMode {
         if let Some(code) = byte_code.get(0) {
             match code {
                 1 => SecurityMode::NegotiateSigningEnabled,
-                2 => SecurityMode::NegotiateSigningRequired,
+                2 | 3 => SecurityMode::NegotiateSigningRequired,
                 _ => panic!("Invalid security mode."),
             }
         } else {
@@ -179,6 +180,54 @@ impl Distribution<OplockLevel> for Standard {
     }
 }
 
+/// LeaseState (4 bytes): The caching state of a lease.
+/// This field MUST be constructed as a combination of zero or more of the following values.
+///
+/// *Read Caching*:
+///     - A read caching lease is requested or granted.
+///
+/// *Handle Caching*:
+///     - A handle caching lease is requested or granted.
+///
+/// *Write Caching*:
+///     - A write caching lease is requested or granted.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum LeaseState {
+    ReadCaching,
+    HandleCaching,
+    WriteCaching,
+}
+
+impl LeaseState {
+    /// Unpacks the byte code of the corresponding lease state.
+    pub fn unpack_byte_code(&self) -> u32 {
+        match self {
+            LeaseState::ReadCaching => 0x00000001,
+            LeaseState::HandleCaching => 0x00000002,
+            LeaseState::WriteCaching => 0x00000004,
+        }
+    }
+
+    /// Returns a sum of the given lease states as a 4 byte array.
+    pub fn return_sum_of_chosen_lease_states(lease_states: Vec<LeaseState>) -> Vec<u8> {
+        let combined_states: u32 = lease_states
+            .iter()
+            .fold(0u32, |acc, state| acc | state.unpack_byte_code());
+
+        combined_states.to_le_bytes().to_vec()
+    }
+}
+
+impl Distribution<LeaseState> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> LeaseState {
+        match rng.gen_range(0..=2) {
+            0 => LeaseState::ReadCaching,
+            1 => LeaseState::HandleCaching,
+            _ => LeaseState::WriteCaching,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -200,4 +249,16 @@ mod tests {
             Capabilities::return_sum_of_chosen_capabilities(chosen)
         );
     }
+
+    #[test]
+    fn test_return_sum_of_chosen_lease_states() {
+        assert_eq!(
+            vec![5, 0, 0, 0],
+            LeaseState::return_sum_of_chosen_lease_states(vec![
+                LeaseState::ReadCaching,
+                LeaseState::WriteCaching,
+                LeaseState::ReadCaching,
+            ])
+        );
+    }
 }
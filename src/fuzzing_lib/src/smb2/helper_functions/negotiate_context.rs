@@ -9,14 +9,19 @@ use rand::{
 };
 
 use crate::{
-    format::convert_byte_array_to_int, fuzzer::create_random_byte_array_of_predefined_length,
+    crypto::signing::SigningAlgorithm, format::convert_byte_array_to_int,
+    fuzzer::create_random_byte_array_of_predefined_length,
 };
 
 pub trait DataSize {
     fn get_data_length(&self) -> Vec<u8>;
 }
 
+/// The registered context type ids of the negotiate contexts.
+pub const REGISTERED_CONTEXT_TYPES: [u16; 7] = [0x01, 0x02, 0x03, 0x05, 0x06, 0x07, 0x08];
+
 /// ContextType (2 bytes): Specifies the type of context in the Data field.
+/// Context types that are not registered carry their type id and raw data.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum ContextType {
     PreauthIntegrityCapabilities(PreauthIntegrityCapabilities),
@@ -25,6 +30,11 @@ pub enum ContextType {
     NetnameNegotiateContextId(NetnameNegotiateContextId),
     TransportCapabilities(TransportCapabilities),
     RdmaTransformCapabilities(RdmaTransformCapabilities),
+    SigningCapabilities(SigningCapabilities),
+    Unknown {
+        context_type: Vec<u8>,
+        data: Vec<u8>,
+    },
 }
 
 impl ContextType {
@@ -37,26 +47,49 @@ impl ContextType {
             ContextType::NetnameNegotiateContextId(_) => b"\x05\x00".to_vec(),
             ContextType::TransportCapabilities(_) => b"\x06\x00".to_vec(),
             ContextType::RdmaTransformCapabilities(_) => b"\x07\x00".to_vec(),
+            ContextType::SigningCapabilities(_) => b"\x08\x00".to_vec(),
+            ContextType::Unknown { context_type, .. } => context_type.clone(),
         }
     }
 
     /// Maps the byte code of an incoming response to the corresponding context type.
+    /// Unregistered byte codes are mapped to an unknown context type without data.
     pub fn map_byte_code_to_context_type(byte_code: Vec<u8>) -> ContextType {
-        if let Some(code) = byte_code.get(0) {
-            match code {
-                1 => ContextType::PreauthIntegrityCapabilities(
-                    PreauthIntegrityCapabilities::default(),
-                ),
-                2 => ContextType::EncryptionCapabilities(EncryptionCapabilities::default()),
-                3 => ContextType::CompressionCapabilities(CompressionCapabilities::default()),
-                5 => ContextType::NetnameNegotiateContextId(NetnameNegotiateContextId::default()),
-                6 => ContextType::TransportCapabilities(TransportCapabilities::default()),
-                7 => ContextType::RdmaTransformCapabilities(RdmaTransformCapabilities::default()),
-                _ => panic!("Invalid context type in parsed response."),
+        match byte_code.as_slice() {
+            [1, 0] => {
+                ContextType::PreauthIntegrityCapabilities(PreauthIntegrityCapabilities::default())
             }
-        } else {
-            panic!("Empty context type in parsed response.")
-        }
+            [2, 0] => ContextType::EncryptionCapabilities(EncryptionCapabilities::default()),
+            [3, 0] => ContextType::CompressionCapabilities(CompressionCapabilities::default()),
+            [5, 0] => ContextType::NetnameNegotiateContextId(NetnameNegotiateContextId::default()),
+            [6, 0] => ContextType::TransportCapabilities(TransportCapabilities::default()),
+            [7, 0] => ContextType::RdmaTransformCapabilities(RdmaTransformCapabilities::default()),
+            [8, 0] => ContextType::SigningCapabilities(SigningCapabilities::default()),
+            _ => ContextType::Unknown {
+                context_type: byte_code,
+                data: Vec::new(),
+            },
+        }
+    }
+
+    /// Samples a context type id that is not registered. Besides random ids,
+    /// the unused id 0x0004, zero and the maximum id are chosen.
+    pub fn sample_unregistered_context_type() -> Vec<u8> {
+        let mut rng = rand::thread_rng();
+
+        let context_type = match rng.gen_range(0..=3) {
+            0 => 0x0004,
+            1 => 0x0000,
+            2 => u16::MAX,
+            _ => loop {
+                let context_type = rng.gen::<u16>();
+                if !REGISTERED_CONTEXT_TYPES.contains(&context_type) {
+                    break context_type;
+                }
+            },
+        };
+
+        context_type.to_le_bytes().to_vec()
     }
 
     /// Calls get data length for the corresponding capability.
@@ -68,13 +101,15 @@ impl ContextType {
             ContextType::NetnameNegotiateContextId(netname) => netname.get_data_length(),
             ContextType::TransportCapabilities(transport) => transport.get_data_length(),
             ContextType::RdmaTransformCapabilities(rdma) => rdma.get_data_length(),
+            ContextType::SigningCapabilities(signing) => signing.get_data_length(),
+            ContextType::Unknown { data, .. } => (data.len() as u16).to_le_bytes().to_vec(),
         }
     }
 }
 
 impl Distribution<ContextType> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> ContextType {
-        match rng.gen_range(0..=4) {
+        match rng.gen_range(0..=7) {
             0 => ContextType::PreauthIntegrityCapabilities(
                 PreauthIntegrityCapabilities::fuzz_with_predefined_length(),
             ),
@@ -88,9 +123,16 @@ impl Distribution<ContextType> for Standard {
             4 => ContextType::TransportCapabilities(
                 TransportCapabilities::fuzz_with_predefined_length(),
             ),
-            _ => ContextType::RdmaTransformCapabilities(
+            5 => ContextType::RdmaTransformCapabilities(
                 RdmaTransformCapabilities::fuzz_with_predefined_length(),
             ),
+            6 => {
+                ContextType::SigningCapabilities(SigningCapabilities::fuzz_with_predefined_length())
+            }
+            _ => ContextType::Unknown {
+                context_type: ContextType::sample_unregistered_context_type(),
+                data: create_random_byte_array_of_predefined_length(rng.gen_range(0..100)),
+            },
         }
     }
 }
@@ -116,6 +158,16 @@ impl std::fmt::Display for ContextType {
             ContextType::RdmaTransformCapabilities(rdma) => {
                 write!(f, "{}", rdma)
             }
+            ContextType::SigningCapabilities(signing) => {
+                write!(f, "{}", signing)
+            }
+            ContextType::Unknown { context_type, data } => {
+                write!(
+                    f,
+                    "\n\t\t\tUnknown Context: \n\t\t\t\tcontext type: {:?}\n\t\t\t\tdata: {:?}",
+                    context_type, data
+                )
+            }
         }
     }
 }
@@ -186,6 +238,14 @@ impl HashAlgorithms {
             HashAlgorithms::Sha512 => b"\x01\x00".to_vec(),
         }
     }
+
+    /// Maps the byte code of a hash algorithm id to the corresponding hash algorithm.
+    pub fn map_byte_code_to_hash_algorithm(byte_code: Vec<u8>) -> HashAlgorithms {
+        match byte_code.as_slice() {
+            [1, 0] => HashAlgorithms::Sha512,
+            _ => panic!("Invalid hash algorithm."),
+        }
+    }
 }
 
 impl Distribution<HashAlgorithms> for Standard {
@@ -286,6 +346,28 @@ impl Ciphers {
             Ciphers::Aes256Gcm => b"\x04\x00".to_vec(),
         }
     }
+
+    /// Maps the byte code of a cipher id to the corresponding cipher.
+    pub fn map_byte_code_to_cipher(byte_code: Vec<u8>) -> Ciphers {
+        match byte_code.as_slice() {
+            [1, 0] => Ciphers::Aes128Ccm,
+            [2, 0] => Ciphers::Aes128Gcm,
+            [3, 0] => Ciphers::Aes256Ccm,
+            [4, 0] => Ciphers::Aes256Gcm,
+            _ => panic!("Invalid cipher."),
+        }
+    }
+
+    /// Maps a user input string to a cipher.
+    pub fn map_string_to_cipher(cipher: &str) -> Ciphers {
+        match cipher {
+            "aes128ccm" => Ciphers::Aes128Ccm,
+            "aes128gcm" => Ciphers::Aes128Gcm,
+            "aes256ccm" => Ciphers::Aes256Ccm,
+            "aes256gcm" => Ciphers::Aes256Gcm,
+            _ => panic!("Invalid cipher."),
+        }
+    }
 }
 
 impl Distribution<Ciphers> for Standard {
@@ -431,6 +513,30 @@ impl CompressionAlgorithms {
             CompressionAlgorithms::PatternV1 => b"\x04\x00".to_vec(),
         }
     }
+
+    /// Maps the byte code of a compression algorithm id to the corresponding compression algorithm.
+    pub fn map_byte_code_to_compression_algorithm(byte_code: Vec<u8>) -> CompressionAlgorithms {
+        match byte_code.as_slice() {
+            [0, 0] => CompressionAlgorithms::None,
+            [1, 0] => CompressionAlgorithms::Lznt1,
+            [2, 0] => CompressionAlgorithms::Lz77,
+            [3, 0] => CompressionAlgorithms::Lz77Huffman,
+            [4, 0] => CompressionAlgorithms::PatternV1,
+            _ => panic!("Invalid compression algorithm."),
+        }
+    }
+
+    /// Maps a user input string to a compression algorithm.
+    pub fn map_string_to_compression_algorithm(algorithm: &str) -> CompressionAlgorithms {
+        match algorithm {
+            "none" => CompressionAlgorithms::None,
+            "lznt1" => CompressionAlgorithms::Lznt1,
+            "lz77" => CompressionAlgorithms::Lz77,
+            "lz77huffman" => CompressionAlgorithms::Lz77Huffman,
+            "patternv1" => CompressionAlgorithms::PatternV1,
+            _ => panic!("Invalid compression algorithm."),
+        }
+    }
 }
 
 impl Distribution<CompressionAlgorithms> for Standard {
@@ -627,6 +733,15 @@ impl RdmaTransformIds {
             RdmaTransformIds::RdmaTransformEncryption => b"\x01\x00".to_vec(),
         }
     }
+
+    /// Maps the byte code of an RDMA transform id to the corresponding RDMA transform.
+    pub fn map_byte_code_to_rdma_transform_id(byte_code: Vec<u8>) -> RdmaTransformIds {
+        match byte_code.as_slice() {
+            [0, 0] => RdmaTransformIds::RdmaTransformNone,
+            [1, 0] => RdmaTransformIds::RdmaTransformEncryption,
+            _ => panic!("Invalid RDMA transform id."),
+        }
+    }
 }
 
 impl Distribution<RdmaTransformIds> for Standard {
@@ -710,3 +825,64 @@ impl std::fmt::Display for RdmaTransformCapabilities {
         )
     }
 }
+
+/// The SMB2_SIGNING_CAPABILITIES context is specified in an SMB2 NEGOTIATE request
+/// by the client to indicate which signing algorithms the client supports.
+/// The server selects one of them in the SMB2 NEGOTIATE response.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SigningCapabilities {
+    /// SigningAlgorithmCount (2 bytes): The number of signing algorithms in
+    /// the SigningAlgorithms array. This value MUST be greater than zero.
+    pub signing_algorithm_count: Vec<u8>,
+    /// SigningAlgorithms (variable): An array of SigningAlgorithmCount 16-bit integer IDs
+    /// specifying the supported signing algorithms. These IDs MUST be in an order such that
+    /// the most preferred signing algorithm MUST be at the beginning of the array and
+    /// least preferred signing algorithm at the end of the array.
+    pub signing_algorithms: Vec<Vec<u8>>,
+}
+
+impl SigningCapabilities {
+    /// Creates a new SigningCapabilities instance.
+    pub fn default() -> Self {
+        SigningCapabilities {
+            signing_algorithm_count: Vec::new(),
+            signing_algorithms: Vec::new(),
+        }
+    }
+
+    /// Fuzzes the signing capabilities with the predefined length.
+    pub fn fuzz_with_predefined_length() -> Self {
+        let mut random_algorithms: Vec<SigningAlgorithm> = Vec::new();
+        for _ in 0..rand::thread_rng().gen_range(0..100) {
+            random_algorithms.push(rand::random());
+        }
+        SigningCapabilities {
+            signing_algorithm_count: (random_algorithms.len() as u16).to_le_bytes().to_vec(),
+            signing_algorithms: random_algorithms
+                .into_iter()
+                .map(|algorithm| algorithm.unpack_byte_code())
+                .collect(),
+        }
+    }
+}
+
+impl DataSize for SigningCapabilities {
+    /// Gets the data length of the signing capabilities.
+    fn get_data_length(&self) -> Vec<u8> {
+        let length = self.signing_algorithm_count.len()
+            + 2 * convert_byte_array_to_int(self.signing_algorithm_count.clone(), false) as usize;
+
+        (length as u16).to_le_bytes().to_vec()
+    }
+}
+
+impl std::fmt::Display for SigningCapabilities {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "\n\t\t\tSigning Capabilities: \n\t\t\t\tsigning algorithm count: {:?}\
+                   \n\t\t\t\tsigning algorithms: {:?}",
+            self.signing_algorithm_count, self.signing_algorithms
+        )
+    }
+}
@@ -12,10 +12,10 @@ const STRUCTURE_SIZE: &[u8; 2] = b"\x04\x00";
 pub struct LogOff {
     /// StructureSize (2 bytes): The client/server MUST set this field to 4,
     /// indicating the size of the request structure not including the header.
-    structure_size: Vec<u8>,
+    pub structure_size: Vec<u8>,
     /// Reserved (2 bytes): This field MUST NOT be used and MUST be reserved.
     /// The client/server MUST set this to 0, and the server/client MUST ignore it on receipt.
-    reserved: Vec<u8>,
+    pub reserved: Vec<u8>,
 }
 
 impl LogOff {
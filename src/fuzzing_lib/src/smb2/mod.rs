@@ -1,3 +1,4 @@
+pub mod compression_transform;
 pub mod header;
 pub mod helper_functions;
 pub mod logoff;
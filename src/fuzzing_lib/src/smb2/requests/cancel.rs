@@ -0,0 +1,26 @@
+/// Cancel request size of 4 bytes
+const STRUCTURE_SIZE: &[u8; 2] = b"\x04\x00";
+
+/// The SMB2 CANCEL Request packet is sent by the client to cancel a previously sent
+/// message on the same SMB2 transport connection. The outstanding request is identified
+/// by the MessageId of a sync header or by the AsyncId of an async header.
+/// The server does not send a response to a cancel request.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Cancel {
+    /// StructureSize (2 bytes): The client MUST set this field to 4,
+    /// indicating the size of the request structure, not including the header.
+    pub structure_size: Vec<u8>,
+    /// Reserved (2 bytes): This field MUST NOT be used and MUST be reserved.
+    /// The client MUST set this to 0, and the server MUST ignore it on receipt.
+    pub reserved: Vec<u8>,
+}
+
+impl Cancel {
+    /// Creates a new instance of the cancel request.
+    pub fn default() -> Self {
+        Cancel {
+            structure_size: STRUCTURE_SIZE.to_vec(),
+            reserved: vec![0; 2],
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use rand::{
+    distributions::{Distribution, Standard},
+    Rng,
+};
+
+/// Change notify request size of 32 bytes
+const STRUCTURE_SIZE: &[u8; 2] = b"\x20\x00";
+
+/// The SMB2 CHANGE_NOTIFY Request packet is sent by the client to request change notifications
+/// on a directory. The server usually answers with an interim response that carries an
+/// async id and completes the request once a change matching the completion filter occurs.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ChangeNotify {
+    /// StructureSize (2 bytes): The client MUST set this field to 32,
+    /// indicating the size of the request structure, not including the header.
+    pub structure_size: Vec<u8>,
+    /// Flags (2 bytes): Flags indicating how the operation MUST be processed.
+    /// This field MUST be either zero or SMB2_WATCH_TREE.
+    pub flags: Vec<u8>,
+    /// OutputBufferLength (4 bytes): The maximum number of bytes the server is allowed
+    /// to return in the SMB2 CHANGE_NOTIFY Response.
+    pub output_buffer_length: Vec<u8>,
+    /// FileId (16 bytes): An SMB2_FILEID identifier of the directory to monitor for changes.
+    pub file_id: Vec<u8>,
+    /// CompletionFilter (4 bytes): Specifies the types of changes to monitor.
+    /// It is valid to choose multiple trigger conditions.
+    pub completion_filter: Vec<u8>,
+    /// Reserved (4 bytes): This field MUST NOT be used and MUST be reserved.
+    /// The client MUST set this to 0, and the server MUST ignore it on receipt.
+    pub reserved: Vec<u8>,
+}
+
+impl ChangeNotify {
+    /// Creates a new instance of the change notify request.
+    pub fn default() -> Self {
+        ChangeNotify {
+            structure_size: STRUCTURE_SIZE.to_vec(),
+            flags: vec![0; 2],
+            output_buffer_length: Vec::new(),
+            file_id: Vec::new(),
+            completion_filter: Vec::new(),
+            reserved: vec![0; 4],
+        }
+    }
+}
+
+/// Flags (2 bytes): Flags indicating how the operation MUST be processed.
+///
+/// *Watch Tree*:
+///     - The request MUST monitor changes on any file or directory contained beneath
+///       the directory specified by FileId.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ChangeNotifyFlags {
+    WatchTree,
+}
+
+impl ChangeNotifyFlags {
+    /// Unpacks the byte code of the corresponding change notify flag.
+    pub fn unpack_byte_code(&self) -> Vec<u8> {
+        match self {
+            ChangeNotifyFlags::WatchTree => b"\x01\x00".to_vec(),
+        }
+    }
+}
+
+/// CompletionFilter (4 bytes): Specifies the types of changes to monitor.
+/// It is valid to choose multiple trigger conditions. In this case,
+/// if any condition is met, the client is notified of the change and the CHANGE_NOTIFY operation is completed.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CompletionFilter {
+    FileName,
+    DirName,
+    Attributes,
+    Size,
+    LastWrite,
+    LastAccess,
+    Creation,
+    Ea,
+    Security,
+    StreamName,
+    StreamSize,
+    StreamWrite,
+}
+
+impl CompletionFilter {
+    /// Unpacks the byte code of the corresponding completion filter.
+    pub fn unpack_byte_code(&self) -> u32 {
+        match self {
+            CompletionFilter::FileName => 0x00000001,
+            CompletionFilter::DirName => 0x00000002,
+            CompletionFilter::Attributes => 0x00000004,
+            CompletionFilter::Size => 0x00000008,
+            CompletionFilter::LastWrite => 0x00000010,
+            CompletionFilter::LastAccess => 0x00000020,
+            CompletionFilter::Creation => 0x00000040,
+            CompletionFilter::Ea => 0x00000080,
+            CompletionFilter::Security => 0x00000100,
+            CompletionFilter::StreamName => 0x00000200,
+            CompletionFilter::StreamSize => 0x00000400,
+            CompletionFilter::StreamWrite => 0x00000800,
+        }
+    }
+
+    /// Returns a sum of the given completion filters as a 4 byte array.
+    pub fn return_sum_of_chosen_completion_filters(filters: Vec<CompletionFilter>) -> Vec<u8> {
+        let combined_filters: u32 = filters
+            .iter()
+            .fold(0u32, |acc, filter| acc | filter.unpack_byte_code());
+
+        combined_filters.to_le_bytes().to_vec()
+    }
+}
+
+impl Distribution<CompletionFilter> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> CompletionFilter {
+        match rng.gen_range(0..=11) {
+            0 => CompletionFilter::FileName,
+            1 => CompletionFilter::DirName,
+            2 => CompletionFilter::Attributes,
+            3 => CompletionFilter::Size,
+            4 => CompletionFilter::LastWrite,
+            5 => CompletionFilter::LastAccess,
+            6 => CompletionFilter::Creation,
+            7 => CompletionFilter::Ea,
+            8 => CompletionFilter::Security,
+            9 => CompletionFilter::StreamName,
+            10 => CompletionFilter::StreamSize,
+            _ => CompletionFilter::StreamWrite,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_return_sum_of_chosen_completion_filters() {
+        assert_eq!(
+            vec![0x13, 0x08, 0x00, 0x00],
+            CompletionFilter::return_sum_of_chosen_completion_filters(vec![
+                CompletionFilter::FileName,
+                CompletionFilter::DirName,
+                CompletionFilter::LastWrite,
+                CompletionFilter::StreamWrite,
+            ])
+        );
+    }
+}
@@ -0,0 +1,28 @@
+//! Multiple requests can be compounded into a single transport frame. Each request but the last
+//! is padded to an 8-byte boundary, and the NextCommand field of its header contains the offset
+//! of the following header. Related operations set SMB2_FLAGS_RELATED_OPERATIONS and inherit
+//! the session, tree and file of the previous operation.
+
+use crate::smb2::header::SyncHeader;
+
+use super::RequestType;
+
+/// The FileId (16 bytes) of a related operation that inherits the file id of the previous operation.
+pub const RELATED_FILE_ID: &[u8; 16] =
+    b"\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff";
+
+/// A single request of a compound chain.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CompoundEntry {
+    /// The sync header of the request. Its NextCommand field chains the entry to the following one.
+    pub header: SyncHeader,
+    /// The body of the request.
+    pub body: RequestType,
+}
+
+impl CompoundEntry {
+    /// Creates a new compound entry from the given header and body.
+    pub fn new(header: SyncHeader, body: RequestType) -> Self {
+        CompoundEntry { header, body }
+    }
+}
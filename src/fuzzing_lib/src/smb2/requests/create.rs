@@ -1,6 +1,8 @@
+pub mod create_context;
 pub mod create_options;
 pub mod file_access_mask;
 
+use self::create_context::CreateContext;
 use rand::{
     distributions::{Distribution, Standard},
     Rng,
@@ -78,6 +80,8 @@ pub struct Create {
     /// The file name (after DFS normalization if needed) MUST conform to the specification of a
     /// relative pathname.
     pub buffer: Vec<u8>,
+    /// The list of SMB2_CREATE_CONTEXT structures that follows the 8-byte aligned file name.
+    pub create_contexts: Vec<CreateContext>,
 }
 
 impl Create {
@@ -99,6 +103,7 @@ impl Create {
             create_contexts_offset: Vec::new(),
             create_contexts_length: Vec::new(),
             buffer: Vec::new(),
+            create_contexts: Vec::new(),
         }
     }
 }
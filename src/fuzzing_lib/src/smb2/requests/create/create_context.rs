@@ -0,0 +1,463 @@
+use rand::{
+    distributions::{Distribution, Standard},
+    Rng,
+};
+
+/// The offset of the name of a create context that directly follows the create context header.
+pub const CREATE_CONTEXT_HEADER_LENGTH: u16 = 16;
+
+/// The SMB2_CREATE_CONTEXT structure is used by the SMB2 CREATE Request and the SMB2 CREATE Response
+/// to encode additional flags and attributes. Create contexts are chained via the Next field,
+/// and each create context MUST be aligned on an 8-byte boundary.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CreateContext {
+    /// Next (4 bytes): The offset from the beginning of this create context to the beginning
+    /// of the next 8-byte aligned create context. If this value is 0, it is the last create context.
+    pub next: Vec<u8>,
+    /// NameOffset (2 bytes): The offset from the beginning of this structure
+    /// to its 8-byte aligned name value.
+    pub name_offset: Vec<u8>,
+    /// NameLength (2 bytes): The length, in bytes, of the create context name.
+    pub name_length: Vec<u8>,
+    /// Reserved (2 bytes): This field MUST NOT be used and MUST be reserved.
+    /// This value MUST be set to 0 by the client, and ignored by the server.
+    pub reserved: Vec<u8>,
+    /// DataOffset (2 bytes): The offset, in bytes, from the beginning of this structure
+    /// to the 8-byte aligned data payload. If DataLength is 0, the client SHOULD set this value to 0.
+    pub data_offset: Vec<u8>,
+    /// DataLength (4 bytes): The length, in bytes, of the data.
+    pub data_length: Vec<u8>,
+    /// Buffer (variable): A variable-length buffer that contains the name and data fields,
+    /// as defined by NameOffset, NameLength, DataOffset, and DataLength.
+    /// The name is represented as four or sixteen bytes.
+    pub name: Vec<u8>,
+    /// The data of the create context that follows the 8-byte aligned name.
+    pub data: Vec<u8>,
+}
+
+impl CreateContext {
+    /// Creates a new instance of the create context.
+    pub fn default() -> Self {
+        CreateContext {
+            next: vec![0; 4],
+            name_offset: CREATE_CONTEXT_HEADER_LENGTH.to_le_bytes().to_vec(),
+            name_length: Vec::new(),
+            reserved: vec![0; 2],
+            data_offset: Vec::new(),
+            data_length: Vec::new(),
+            name: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    /// Creates a create context with the given name and data.
+    /// The data offset points to the first 8-byte boundary after the name.
+    pub fn from_name_and_data(name: CreateContextName, data: Vec<u8>) -> Self {
+        let mut create_context = CreateContext::default();
+
+        create_context.name = name.unpack_byte_code();
+        create_context.name_length = (create_context.name.len() as u16).to_le_bytes().to_vec();
+        create_context.data_offset = if data.is_empty() {
+            vec![0; 2]
+        } else {
+            (align_to_eight_bytes(CREATE_CONTEXT_HEADER_LENGTH as usize + create_context.name.len())
+                as u16)
+                .to_le_bytes()
+                .to_vec()
+        };
+        create_context.data_length = (data.len() as u32).to_le_bytes().to_vec();
+        create_context.data = data;
+
+        create_context
+    }
+}
+
+/// Rounds the given length up to the next 8-byte boundary.
+pub fn align_to_eight_bytes(length: usize) -> usize {
+    (length + 7) & !7
+}
+
+/// The names of the create contexts a client can send in a create request.
+///
+/// *Ea Buffer*:
+///     - The data contains the extended attributes that MUST be stored on the created file.
+///
+/// *Sd Buffer*:
+///     - The data contains a security descriptor that MUST be stored on the created file.
+///
+/// *Durable Handle Request*:
+///     - The client is requesting the open to be durable.
+///
+/// *Durable Handle Reconnect*:
+///     - The client is requesting to reestablish a durable open.
+///
+/// *Allocation Size*:
+///     - The data contains the required allocation size of the newly created file.
+///
+/// *Query Maximal Access Request*:
+///     - The client is requesting that the server return maximal access information.
+///
+/// *Timewarp Token*:
+///     - The client is requesting that the server open an earlier version of the file.
+///
+/// *Query On Disk Id*:
+///     - The client is requesting that the server return an identifier for the open file.
+///
+/// *Request Lease*:
+///     - The client is requesting a lease. The data is either a version 1 or version 2 lease request.
+///
+/// *Durable Handle Request V2*:
+///     - The client is requesting the open to be durable or persistent.
+///
+/// *Durable Handle Reconnect V2*:
+///     - The client is requesting to reestablish a durable or persistent open.
+///
+/// *App Instance Id*:
+///     - The client is supplying an identifier provided by an application.
+///
+/// *App Instance Version*:
+///     - The client is supplying a version to correspond to the application instance identifier.
+///
+/// *Posix*:
+///     - The client is requesting POSIX semantics for the open.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CreateContextName {
+    EaBuffer,
+    SdBuffer,
+    DurableHandleRequest,
+    DurableHandleReconnect,
+    AllocationSize,
+    QueryMaximalAccessRequest,
+    TimewarpToken,
+    QueryOnDiskId,
+    RequestLease,
+    DurableHandleRequestV2,
+    DurableHandleReconnectV2,
+    AppInstanceId,
+    AppInstanceVersion,
+    Posix,
+}
+
+impl CreateContextName {
+    /// Unpacks the byte code of the corresponding create context name.
+    pub fn unpack_byte_code(&self) -> Vec<u8> {
+        match self {
+            CreateContextName::EaBuffer => b"ExtA".to_vec(),
+            CreateContextName::SdBuffer => b"SecD".to_vec(),
+            CreateContextName::DurableHandleRequest => b"DHnQ".to_vec(),
+            CreateContextName::DurableHandleReconnect => b"DHnC".to_vec(),
+            CreateContextName::AllocationSize => b"AlSi".to_vec(),
+            CreateContextName::QueryMaximalAccessRequest => b"MxAc".to_vec(),
+            CreateContextName::TimewarpToken => b"TWrp".to_vec(),
+            CreateContextName::QueryOnDiskId => b"QFid".to_vec(),
+            CreateContextName::RequestLease => b"RqLs".to_vec(),
+            CreateContextName::DurableHandleRequestV2 => b"DH2Q".to_vec(),
+            CreateContextName::DurableHandleReconnectV2 => b"DH2C".to_vec(),
+            CreateContextName::AppInstanceId => {
+                b"\x45\xBC\xA6\x6A\xEF\xA7\xF7\x4A\x90\x08\xFA\x46\x2E\x14\x4D\x74".to_vec()
+            }
+            CreateContextName::AppInstanceVersion => {
+                b"\xB9\x82\xD0\xB7\x3B\x56\x07\x4F\xA0\x7B\x52\x4A\x81\x16\xA0\x10".to_vec()
+            }
+            CreateContextName::Posix => {
+                b"\x93\xAD\x25\x50\x9C\xB4\x11\xE7\xB4\x23\x83\xDE\x96\x8B\xCD\x7C".to_vec()
+            }
+        }
+    }
+}
+
+impl Distribution<CreateContextName> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> CreateContextName {
+        match rng.gen_range(0..=13) {
+            0 => CreateContextName::EaBuffer,
+            1 => CreateContextName::SdBuffer,
+            2 => CreateContextName::DurableHandleRequest,
+            3 => CreateContextName::DurableHandleReconnect,
+            4 => CreateContextName::AllocationSize,
+            5 => CreateContextName::QueryMaximalAccessRequest,
+            6 => CreateContextName::TimewarpToken,
+            7 => CreateContextName::QueryOnDiskId,
+            8 => CreateContextName::RequestLease,
+            9 => CreateContextName::DurableHandleRequestV2,
+            10 => CreateContextName::DurableHandleReconnectV2,
+            11 => CreateContextName::AppInstanceId,
+            12 => CreateContextName::AppInstanceVersion,
+            _ => CreateContextName::Posix,
+        }
+    }
+}
+
+/// The typed data of the create contexts a client can send in a create request.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CreateContextData {
+    /// A chain of FILE_FULL_EA_INFORMATION entries.
+    EaBuffer(Vec<FileFullEaInformation>),
+    /// A self-relative security descriptor.
+    SdBuffer(Vec<u8>),
+    /// 16 reserved bytes that MUST be zero.
+    DurableHandleRequest(Vec<u8>),
+    /// The file id of the durable open to reconnect.
+    DurableHandleReconnect(Vec<u8>),
+    /// The 8 byte allocation size of the newly created file.
+    AllocationSize(Vec<u8>),
+    /// An optional 8 byte timestamp. Empty if no timestamp is sent.
+    QueryMaximalAccessRequest(Vec<u8>),
+    /// The 8 byte timestamp of the version of the file to open.
+    TimewarpToken(Vec<u8>),
+    /// The request MUST NOT contain any data.
+    QueryOnDiskId,
+    RequestLease(RequestLease),
+    DurableHandleRequestV2(DurableHandleRequestV2),
+    DurableHandleReconnectV2(DurableHandleReconnectV2),
+    AppInstanceId(AppInstanceId),
+    AppInstanceVersion(AppInstanceVersion),
+    /// The 4 byte POSIX permissions of the file.
+    Posix(Vec<u8>),
+}
+
+impl CreateContextData {
+    /// Returns the name of the create context that carries the data.
+    pub fn name(&self) -> CreateContextName {
+        match self {
+            CreateContextData::EaBuffer(_) => CreateContextName::EaBuffer,
+            CreateContextData::SdBuffer(_) => CreateContextName::SdBuffer,
+            CreateContextData::DurableHandleRequest(_) => CreateContextName::DurableHandleRequest,
+            CreateContextData::DurableHandleReconnect(_) => {
+                CreateContextName::DurableHandleReconnect
+            }
+            CreateContextData::AllocationSize(_) => CreateContextName::AllocationSize,
+            CreateContextData::QueryMaximalAccessRequest(_) => {
+                CreateContextName::QueryMaximalAccessRequest
+            }
+            CreateContextData::TimewarpToken(_) => CreateContextName::TimewarpToken,
+            CreateContextData::QueryOnDiskId => CreateContextName::QueryOnDiskId,
+            CreateContextData::RequestLease(_) => CreateContextName::RequestLease,
+            CreateContextData::DurableHandleRequestV2(_) => {
+                CreateContextName::DurableHandleRequestV2
+            }
+            CreateContextData::DurableHandleReconnectV2(_) => {
+                CreateContextName::DurableHandleReconnectV2
+            }
+            CreateContextData::AppInstanceId(_) => CreateContextName::AppInstanceId,
+            CreateContextData::AppInstanceVersion(_) => CreateContextName::AppInstanceVersion,
+            CreateContextData::Posix(_) => CreateContextName::Posix,
+        }
+    }
+}
+
+/// The FILE_FULL_EA_INFORMATION structure contains a single extended attribute
+/// (MS-FSCC Section 2.4.15).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FileFullEaInformation {
+    /// NextEntryOffset (4 bytes): The offset of the next FILE_FULL_EA_INFORMATION entry,
+    /// in bytes, from the beginning of this entry. 0 indicates the last entry.
+    pub next_entry_offset: Vec<u8>,
+    /// Flags (1 byte): Can contain FILE_NEED_EA (0x80).
+    pub flags: Vec<u8>,
+    /// EaNameLength (1 byte): The length, in bytes, of the EaName field, excluding the null terminator.
+    pub ea_name_length: Vec<u8>,
+    /// EaValueLength (2 bytes): The length, in bytes, of the EaValue field.
+    pub ea_value_length: Vec<u8>,
+    /// EaName (variable): The null-terminated ASCII name of the extended attribute.
+    pub ea_name: Vec<u8>,
+    /// EaValue (variable): The value of the extended attribute.
+    pub ea_value: Vec<u8>,
+}
+
+impl FileFullEaInformation {
+    /// Creates a new instance of the FILE_FULL_EA_INFORMATION entry.
+    pub fn default() -> Self {
+        FileFullEaInformation {
+            next_entry_offset: vec![0; 4],
+            flags: vec![0],
+            ea_name_length: Vec::new(),
+            ea_value_length: Vec::new(),
+            ea_name: Vec::new(),
+            ea_value: Vec::new(),
+        }
+    }
+
+    /// Creates an extended attribute entry with the given name and value.
+    pub fn from_name_and_value(name: &str, value: Vec<u8>) -> Self {
+        let mut ea_information = FileFullEaInformation::default();
+
+        ea_information.ea_name_length = vec![name.len() as u8];
+        ea_information.ea_value_length = (value.len() as u16).to_le_bytes().to_vec();
+        ea_information.ea_name = name.as_bytes().to_vec();
+        ea_information.ea_name.push(0);
+        ea_information.ea_value = value;
+
+        ea_information
+    }
+}
+
+/// The SMB2_CREATE_REQUEST_LEASE and SMB2_CREATE_REQUEST_LEASE_V2 structures are used
+/// by the client to request a lease. The version 2 structure is sent if the parent lease key,
+/// the epoch and the second reserved field are not empty.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RequestLease {
+    /// LeaseKey (16 bytes): A client-generated key that identifies the owner of the lease.
+    pub lease_key: Vec<u8>,
+    /// LeaseState (4 bytes): The requested lease state.
+    pub lease_state: Vec<u8>,
+    /// LeaseFlags (4 bytes): SMB2_LEASE_FLAG_PARENT_LEASE_KEY_SET (0x04) is only valid
+    /// in the version 2 structure.
+    pub lease_flags: Vec<u8>,
+    /// LeaseDuration (8 bytes): This field MUST NOT be used and MUST be reserved.
+    pub lease_duration: Vec<u8>,
+    /// ParentLeaseKey (16 bytes): The key of the lease on the parent directory.
+    pub parent_lease_key: Vec<u8>,
+    /// Epoch (2 bytes): The epoch of the lease. It MUST be ignored by the server.
+    pub epoch: Vec<u8>,
+    /// Reserved (2 bytes): This field MUST NOT be used and MUST be reserved.
+    pub reserved: Vec<u8>,
+}
+
+impl RequestLease {
+    /// Creates a new instance of the version 1 lease request.
+    pub fn default() -> Self {
+        RequestLease {
+            lease_key: Vec::new(),
+            lease_state: Vec::new(),
+            lease_flags: vec![0; 4],
+            lease_duration: vec![0; 8],
+            parent_lease_key: Vec::new(),
+            epoch: Vec::new(),
+            reserved: Vec::new(),
+        }
+    }
+
+    /// Creates a new instance of the version 2 lease request without a parent lease key.
+    pub fn default_v2() -> Self {
+        let mut request_lease = RequestLease::default();
+
+        request_lease.parent_lease_key = vec![0; 16];
+        request_lease.epoch = vec![0; 2];
+        request_lease.reserved = vec![0; 2];
+
+        request_lease
+    }
+}
+
+/// The SMB2_CREATE_DURABLE_HANDLE_REQUEST_V2 structure is used by the client
+/// to request a durable or persistent open.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DurableHandleRequestV2 {
+    /// Timeout (4 bytes): The timeout in milliseconds for which the server reserves the handle.
+    pub timeout: Vec<u8>,
+    /// Flags (4 bytes): SMB2_DHANDLE_FLAG_PERSISTENT (0x02) requests a persistent handle.
+    pub flags: Vec<u8>,
+    /// Reserved (8 bytes): This field MUST NOT be used and MUST be reserved.
+    pub reserved: Vec<u8>,
+    /// CreateGuid (16 bytes): A unique GUID that identifies the create request.
+    pub create_guid: Vec<u8>,
+}
+
+impl DurableHandleRequestV2 {
+    /// Creates a new instance of the durable handle request v2.
+    pub fn default() -> Self {
+        DurableHandleRequestV2 {
+            timeout: vec![0; 4],
+            flags: vec![0; 4],
+            reserved: vec![0; 8],
+            create_guid: Vec::new(),
+        }
+    }
+}
+
+/// The SMB2_CREATE_DURABLE_HANDLE_RECONNECT_V2 structure is used by the client
+/// to reestablish a durable or persistent open.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DurableHandleReconnectV2 {
+    /// FileId (16 bytes): The file id of the open that is being reestablished.
+    pub file_id: Vec<u8>,
+    /// CreateGuid (16 bytes): The GUID of the create request that established the open.
+    pub create_guid: Vec<u8>,
+    /// Flags (4 bytes): SMB2_DHANDLE_FLAG_PERSISTENT (0x02) reconnects a persistent handle.
+    pub flags: Vec<u8>,
+}
+
+impl DurableHandleReconnectV2 {
+    /// Creates a new instance of the durable handle reconnect v2.
+    pub fn default() -> Self {
+        DurableHandleReconnectV2 {
+            file_id: Vec::new(),
+            create_guid: Vec::new(),
+            flags: vec![0; 4],
+        }
+    }
+}
+
+/// The SMB2_CREATE_APP_INSTANCE_ID structure is used by the client to supply
+/// an identifier provided by an application.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AppInstanceId {
+    /// StructureSize (2 bytes): This field MUST be set to 20.
+    pub structure_size: Vec<u8>,
+    /// Reserved (2 bytes): This field MUST NOT be used and MUST be reserved.
+    pub reserved: Vec<u8>,
+    /// AppInstanceId (16 bytes): The unique identifier of the application instance.
+    pub app_instance_id: Vec<u8>,
+}
+
+impl AppInstanceId {
+    /// Creates a new instance of the app instance id.
+    pub fn default() -> Self {
+        AppInstanceId {
+            structure_size: b"\x14\x00".to_vec(),
+            reserved: vec![0; 2],
+            app_instance_id: Vec::new(),
+        }
+    }
+}
+
+/// The SMB2_CREATE_APP_INSTANCE_VERSION structure is used by the client to supply
+/// a version for the app instance identifier.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AppInstanceVersion {
+    /// StructureSize (2 bytes): This field MUST be set to 24.
+    pub structure_size: Vec<u8>,
+    /// Reserved (2 bytes): This field MUST NOT be used and MUST be reserved.
+    pub reserved: Vec<u8>,
+    /// Padding (4 bytes): This field MUST NOT be used and MUST be reserved.
+    pub padding: Vec<u8>,
+    /// AppInstanceVersionHigh (8 bytes): The high order bits of the version.
+    pub app_instance_version_high: Vec<u8>,
+    /// AppInstanceVersionLow (8 bytes): The low order bits of the version.
+    pub app_instance_version_low: Vec<u8>,
+}
+
+impl AppInstanceVersion {
+    /// Creates a new instance of the app instance version.
+    pub fn default() -> Self {
+        AppInstanceVersion {
+            structure_size: b"\x18\x00".to_vec(),
+            reserved: vec![0; 2],
+            padding: vec![0; 4],
+            app_instance_version_high: Vec::new(),
+            app_instance_version_low: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_context_from_name_and_data() {
+        let create_context =
+            CreateContext::from_name_and_data(CreateContextName::AllocationSize, vec![1; 8]);
+
+        assert_eq!(b"\x10\x00".to_vec(), create_context.name_offset);
+        assert_eq!(b"\x04\x00".to_vec(), create_context.name_length);
+        assert_eq!(b"\x18\x00".to_vec(), create_context.data_offset);
+        assert_eq!(b"\x08\x00\x00\x00".to_vec(), create_context.data_length);
+
+        let create_context =
+            CreateContext::from_name_and_data(CreateContextName::QueryOnDiskId, Vec::new());
+
+        assert_eq!(vec![0; 2], create_context.data_offset);
+        assert_eq!(vec![0; 4], create_context.data_length);
+    }
+}
@@ -0,0 +1,32 @@
+/// Flush request size of 24 bytes
+const STRUCTURE_SIZE: &[u8; 2] = b"\x18\x00";
+
+/// The SMB2 FLUSH Request packet is sent by a client to request that a server
+/// flush all cached file information for a specified open of a file to the
+/// persistent store that backs the file.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Flush {
+    /// StructureSize (2 bytes): The client MUST set this field to 24,
+    /// indicating the size of the request structure, not including the header.
+    pub structure_size: Vec<u8>,
+    /// Reserved1 (2 bytes): This field MUST NOT be used and MUST be reserved.
+    /// The client MUST set this field to 0, and the server MUST ignore it on receipt.
+    pub reserved_1: Vec<u8>,
+    /// Reserved2 (4 bytes): This field MUST NOT be used and MUST be reserved.
+    /// The client MUST set this field to 0, and the server MUST ignore it on receipt.
+    pub reserved_2: Vec<u8>,
+    /// FileId (16 bytes): An SMB2_FILEID of the file being flushed.
+    pub file_id: Vec<u8>,
+}
+
+impl Flush {
+    /// Creates a new instance of the flush request.
+    pub fn default() -> Self {
+        Flush {
+            structure_size: STRUCTURE_SIZE.to_vec(),
+            reserved_1: vec![0; 2],
+            reserved_2: vec![0; 4],
+            file_id: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use rand::{
+    distributions::{Distribution, Standard},
+    Rng,
+};
+
+/// Lock request size of 48 bytes
+const STRUCTURE_SIZE: &[u8; 2] = b"\x30\x00";
+
+/// The SMB2 LOCK Request packet is sent by the client to either lock
+/// or unlock portions of a file. Several different segments of the file
+/// can be affected with a single SMB2 LOCK Request packet.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Lock {
+    /// StructureSize (2 bytes): The client MUST set this field to 48,
+    /// indicating the size of an SMB2 LOCK Request with a single
+    /// SMB2_LOCK_ELEMENT structure, not including the header.
+    /// The client MUST set this field to this value regardless of the
+    /// number of SMB2_LOCK_ELEMENTs that are sent.
+    pub structure_size: Vec<u8>,
+    /// LockCount (2 bytes): The number of SMB2_LOCK_ELEMENT structures
+    /// that are contained in the Locks[] array. The lock count MUST be greater than or equal to 1.
+    pub lock_count: Vec<u8>,
+    /// LockSequenceNumber/LockSequenceIndex (4 bytes): In the SMB 2.0.2 dialect,
+    /// this field is unused and MUST be reserved. In all other dialects, the lower
+    /// 4 bits MUST contain the LockSequenceNumber and the remaining 28 bits
+    /// MUST contain the LockSequenceIndex of a resilient or persistent handle.
+    pub lock_sequence: Vec<u8>,
+    /// FileId (16 bytes): An SMB2_FILEID that identifies the file on which to perform the byte range locks or unlocks.
+    pub file_id: Vec<u8>,
+    /// Locks (variable): An array of LockCount SMB2_LOCK_ELEMENT structures that define
+    /// the ranges to be locked or unlocked.
+    pub locks: Vec<LockElement>,
+}
+
+impl Lock {
+    /// Creates a new instance of the lock request.
+    pub fn default() -> Self {
+        Lock {
+            structure_size: STRUCTURE_SIZE.to_vec(),
+            lock_count: Vec::new(),
+            lock_sequence: vec![0; 4],
+            file_id: Vec::new(),
+            locks: Vec::new(),
+        }
+    }
+
+    /// Combines the 4 bit lock sequence number and the 28 bit lock sequence index
+    /// into the 4 byte lock sequence field.
+    pub fn build_lock_sequence(sequence_number: u8, sequence_index: u32) -> Vec<u8> {
+        let lock_sequence = (sequence_index << 4) | (sequence_number as u32 & 0x0f);
+
+        lock_sequence.to_le_bytes().to_vec()
+    }
+}
+
+/// The SMB2_LOCK_ELEMENT Structure packet is used by the SMB2 LOCK Request
+/// packet to indicate segments of files that are to be locked or unlocked.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LockElement {
+    /// Offset (8 bytes): The starting offset, in bytes, in the destination file from where the range being locked or unlocked starts.
+    pub offset: Vec<u8>,
+    /// Length (8 bytes): The length, in bytes, of the range being locked or unlocked.
+    pub length: Vec<u8>,
+    /// Flags (4 bytes): The description of how the range is being locked or unlocked
+    /// and how to process the operation.
+    pub flags: Vec<u8>,
+    /// Reserved (4 bytes): This field MUST NOT be used and MUST be reserved.
+    /// The client MUST set this field to 0, and the server MUST ignore it on receipt.
+    pub reserved: Vec<u8>,
+}
+
+impl LockElement {
+    /// Creates a new instance of the lock element.
+    pub fn default() -> Self {
+        LockElement {
+            offset: Vec::new(),
+            length: Vec::new(),
+            flags: Vec::new(),
+            reserved: vec![0; 4],
+        }
+    }
+
+    /// Creates a lock element for the given range and flags.
+    pub fn from_range(offset: u64, length: u64, flags: Vec<LockFlags>) -> Self {
+        let mut lock_element = LockElement::default();
+
+        lock_element.offset = offset.to_le_bytes().to_vec();
+        lock_element.length = length.to_le_bytes().to_vec();
+        lock_element.flags = LockFlags::return_sum_of_chosen_lock_flags(flags);
+
+        lock_element
+    }
+}
+
+/// Flags (4 bytes): The description of how the range is being locked or unlocked
+/// and how to process the operation. The following combinations are valid:
+/// SharedLock, ExclusiveLock, Unlock, SharedLock | FailImmediately
+/// and ExclusiveLock | FailImmediately.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum LockFlags {
+    SharedLock,
+    ExclusiveLock,
+    Unlock,
+    FailImmediately,
+}
+
+impl LockFlags {
+    /// Unpacks the byte code of the corresponding lock flag.
+    pub fn unpack_byte_code(&self) -> u32 {
+        match self {
+            LockFlags::SharedLock => 0x00000001,
+            LockFlags::ExclusiveLock => 0x00000002,
+            LockFlags::Unlock => 0x00000004,
+            LockFlags::FailImmediately => 0x00000010,
+        }
+    }
+
+    /// Returns a sum of the given lock flags as a 4 byte array.
+    pub fn return_sum_of_chosen_lock_flags(flags: Vec<LockFlags>) -> Vec<u8> {
+        let combined_flags: u32 = flags
+            .iter()
+            .fold(0u32, |acc, flag| acc | flag.unpack_byte_code());
+
+        combined_flags.to_le_bytes().to_vec()
+    }
+}
+
+impl Distribution<LockFlags> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> LockFlags {
+        match rng.gen_range(0..=3) {
+            0 => LockFlags::SharedLock,
+            1 => LockFlags::ExclusiveLock,
+            2 => LockFlags::Unlock,
+            _ => LockFlags::FailImmediately,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_lock_sequence() {
+        assert_eq!(
+            vec![0x35, 0x00, 0x00, 0x00],
+            Lock::build_lock_sequence(5, 3)
+        );
+        assert_eq!(
+            vec![0xff, 0xff, 0xff, 0xff],
+            Lock::build_lock_sequence(0xff, 0x0fff_ffff)
+        );
+    }
+
+    #[test]
+    fn test_lock_element_from_range() {
+        let lock_element = LockElement::from_range(
+            16,
+            u64::MAX,
+            vec![LockFlags::ExclusiveLock, LockFlags::FailImmediately],
+        );
+
+        assert_eq!(vec![16, 0, 0, 0, 0, 0, 0, 0], lock_element.offset);
+        assert_eq!(vec![0xff; 8], lock_element.length);
+        assert_eq!(vec![0x12, 0, 0, 0], lock_element.flags);
+    }
+}
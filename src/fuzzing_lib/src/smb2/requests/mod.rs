@@ -1,14 +1,26 @@
 use self::{
-    close::Close, create::Create, echo::Echo, negotiate::Negotiate, query_info::QueryInfo,
-    session_setup::SessionSetup, tree_connect::TreeConnect,
+    cancel::Cancel, change_notify::ChangeNotify, close::Close, compound::CompoundEntry,
+    create::Create, echo::Echo, flush::Flush, lock::Lock, negotiate::Negotiate,
+    oplock_break::OplockBreak, query_info::QueryInfo, read::Read, session_setup::SessionSetup,
+    set_info::SetInfo, tree_connect::TreeConnect,
 };
 
+use super::logoff::LogOff;
+
+pub mod cancel;
+pub mod change_notify;
 pub mod close;
+pub mod compound;
 pub mod create;
 pub mod echo;
+pub mod flush;
+pub mod lock;
 pub mod negotiate;
+pub mod oplock_break;
 pub mod query_info;
+pub mod read;
 pub mod session_setup;
+pub mod set_info;
 pub mod tree_connect;
 
 /// The request type determines which message request is sent to the server.
@@ -22,6 +34,16 @@ pub enum RequestType {
     QueryInfo(QueryInfo),
     Close(Close),
     Echo(Echo),
+    Lock(Lock),
+    Flush(Flush),
+    Cancel(Cancel),
+    ChangeNotify(ChangeNotify),
+    SetInfo(SetInfo),
+    Logoff(LogOff),
+    OplockBreak(OplockBreak),
+    Read(Read),
+    /// A chain of requests that is sent in a single transport frame.
+    Compound(Vec<CompoundEntry>),
 }
 
 impl RequestType {
@@ -42,6 +64,16 @@ impl RequestType {
             "-q" | "--query_info" | "--Query_info" => RequestType::QueryInfo(QueryInfo::default()),
             "-cl" | "--close" | "--Close" => RequestType::Close(Close::default()),
             "-e" | "--echo" | "--Echo" => RequestType::Echo(Echo::default()),
+            "-l" | "--lock" | "--Lock" => RequestType::Lock(Lock::default()),
+            "-f" | "--flush" | "--Flush" => RequestType::Flush(Flush::default()),
+            "-ca" | "--cancel" | "--Cancel" => RequestType::Cancel(Cancel::default()),
+            "-cn" | "--change_notify" | "--Change_notify" => {
+                RequestType::ChangeNotify(ChangeNotify::default())
+            }
+            "-ob" | "--oplock_break" | "--Oplock_break" => RequestType::OplockBreak(
+                OplockBreak::Oplock(oplock_break::OplockBreakAcknowledgment::default()),
+            ),
+            "-co" | "--compound" | "--Compound" => RequestType::Compound(Vec::new()),
             _ => panic!("Invalid Request Type."),
         }
     }
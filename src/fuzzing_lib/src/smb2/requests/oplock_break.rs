@@ -0,0 +1,83 @@
+/// Oplock break acknowledgment size of 24 bytes
+const OPLOCK_BREAK_ACKNOWLEDGMENT_STRUCTURE_SIZE: &[u8; 2] = b"\x18\x00";
+/// Lease break acknowledgment size of 36 bytes
+const LEASE_BREAK_ACKNOWLEDGMENT_STRUCTURE_SIZE: &[u8; 2] = b"\x24\x00";
+
+/// The SMB2 OPLOCK_BREAK Acknowledgment is sent by the client in response to an
+/// SMB2 OPLOCK_BREAK Notification from the server. It is either an oplock or a lease
+/// break acknowledgment, depending on the notification it answers.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum OplockBreak {
+    Oplock(OplockBreakAcknowledgment),
+    Lease(LeaseBreakAcknowledgment),
+}
+
+/// The Oplock Break Acknowledgment packet is sent by the client in response to an
+/// SMB2 Oplock Break Notification packet from the server.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OplockBreakAcknowledgment {
+    /// StructureSize (2 bytes): The client MUST set this to 24,
+    /// indicating the size of the request structure, not including the header.
+    pub structure_size: Vec<u8>,
+    /// OplockLevel (1 byte): The resulting oplock level. This field MUST be
+    /// SMB2_OPLOCK_LEVEL_NONE or SMB2_OPLOCK_LEVEL_II.
+    pub oplock_level: Vec<u8>,
+    /// Reserved (1 byte): This field MUST NOT be used and MUST be reserved.
+    /// The client MUST set this to 0, and the server MUST ignore it on receipt.
+    pub reserved: Vec<u8>,
+    /// Reserved2 (4 bytes): This field MUST NOT be used and MUST be reserved.
+    /// The client MUST set this to 0, and the server MUST ignore it on receipt.
+    pub reserved_2: Vec<u8>,
+    /// FileId (16 bytes): An SMB2_FILEID of the file or pipe on which the oplock break occurred.
+    pub file_id: Vec<u8>,
+}
+
+impl OplockBreakAcknowledgment {
+    /// Creates a new instance of the oplock break acknowledgment.
+    pub fn default() -> Self {
+        OplockBreakAcknowledgment {
+            structure_size: OPLOCK_BREAK_ACKNOWLEDGMENT_STRUCTURE_SIZE.to_vec(),
+            oplock_level: Vec::new(),
+            reserved: vec![0],
+            reserved_2: vec![0; 4],
+            file_id: Vec::new(),
+        }
+    }
+}
+
+/// The Lease Break Acknowledgment packet is sent by the client in response to an
+/// SMB2 Lease Break Notification packet from the server.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LeaseBreakAcknowledgment {
+    /// StructureSize (2 bytes): The client MUST set this to 36,
+    /// indicating the size of the request structure, not including the header.
+    pub structure_size: Vec<u8>,
+    /// Reserved (2 bytes): This field MUST NOT be used and MUST be reserved.
+    /// The client MUST set this to 0, and the server MUST ignore it on receipt.
+    pub reserved: Vec<u8>,
+    /// Flags (4 bytes): This field MUST NOT be used and MUST be reserved.
+    /// The client MUST set this to 0, and the server MUST ignore it on receipt.
+    pub flags: Vec<u8>,
+    /// LeaseKey (16 bytes): The client-generated key that identifies the lease being acknowledged.
+    pub lease_key: Vec<u8>,
+    /// LeaseState (4 bytes): The requested lease state. It MUST be a subset
+    /// of the lease state granted by the server in the lease break notification.
+    pub lease_state: Vec<u8>,
+    /// LeaseDuration (8 bytes): This field MUST NOT be used and MUST be reserved.
+    /// The client MUST set this to 0, and the server MUST ignore it on receipt.
+    pub lease_duration: Vec<u8>,
+}
+
+impl LeaseBreakAcknowledgment {
+    /// Creates a new instance of the lease break acknowledgment.
+    pub fn default() -> Self {
+        LeaseBreakAcknowledgment {
+            structure_size: LEASE_BREAK_ACKNOWLEDGMENT_STRUCTURE_SIZE.to_vec(),
+            reserved: vec![0; 2],
+            flags: vec![0; 4],
+            lease_key: Vec::new(),
+            lease_state: Vec::new(),
+            lease_duration: vec![0; 8],
+        }
+    }
+}
@@ -1,3 +1,7 @@
+pub mod additional_information;
+pub mod file_information_class;
+pub mod fs_information_class;
+
 /// Query Info structure size of 41 bytes
 const STRUCTURE_SIZE: &[u8; 2] = b"\x29\x00";
 
@@ -17,10 +21,12 @@ pub struct QueryInfo {
     pub structure_size: Vec<u8>,
     /// InfoType (1 byte): The type of information queried.
     pub info_type: Vec<u8>,
-    /// FileInfoClass (1 byte): For file information queries.
-    /// TODO: implement enum for all file information classes.
-    /// For now, its default value will be level 18,
-    /// which requests all file information about the specified file from the server.
+    /// FileInfoClass (1 byte): For file information queries, this field MUST contain
+    /// one of the FileInformationClass values. For underlying object store queries,
+    /// this field MUST contain one of the FsInformationClass values.
+    /// For security and quota queries, this field MUST be set to 0.
+    /// Its default value is level 18, which requests all file information
+    /// about the specified file from the server.
     /// Check MS-FSCC Section 2.4 and 2.5 for all information.
     pub file_info_class: Vec<u8>,
     /// OutputBufferLength (4 bytes): The maximum number of bytes of information
@@ -41,8 +47,9 @@ pub struct QueryInfo {
     /// For other information queries, this field SHOULD be set to 0 and the server MUST ignore it on receipt.
     pub input_buffer_length: Vec<u8>,
     /// AdditionalInformation (4 bytes): Provides additional information to the server.
-    /// TODO: Implement enum for all additional information types.
-    /// For now, this field will be set to zero.
+    /// For security queries, this field MUST be a combination of AdditionalInformation flags.
+    /// For FileFullEaInformation queries, it contains the index of the EA to start with.
+    /// For all other queries, this field SHOULD be set to 0.
     pub additional_information: Vec<u8>,
     /// Flags (4 bytes): The flags MUST be set to a combination of zero or more of these bit values
     /// for a FileFullEaInformation query.
@@ -79,6 +86,45 @@ impl QueryInfo {
     }
 }
 
+/// The SMB2_QUERY_QUOTA_INFO packet specifies the quota information to return.
+/// It is sent in the input buffer of quota requests.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct QueryQuotaInfo {
+    /// ReturnSingle (1 byte): A Boolean value, where zero represents FALSE and nonzero represents TRUE.
+    /// If TRUE, the server MUST return a single value. Otherwise, the server SHOULD
+    /// return the maximum number of entries that will fit in the maximum output size.
+    pub return_single: Vec<u8>,
+    /// RestartScan (1 byte): A Boolean value, where zero represents FALSE and nonzero represents TRUE.
+    /// If TRUE, the quota information MUST be read from the beginning.
+    pub restart_scan: Vec<u8>,
+    /// Reserved (2 bytes): This field is unused and MUST be ignored on receipt.
+    pub reserved: Vec<u8>,
+    /// SidListLength (4 bytes): The length, in bytes, of the SidBuffer sent by the client.
+    pub sid_list_length: Vec<u8>,
+    /// StartSidLength (4 bytes): The length, in bytes, of the SID to start the scan with.
+    pub start_sid_length: Vec<u8>,
+    /// StartSidOffset (4 bytes): The offset, in bytes, from the start of SidBuffer to the start SID.
+    pub start_sid_offset: Vec<u8>,
+    /// SidBuffer (variable): A list of FILE_GET_QUOTA_INFORMATION structures or a single SID.
+    pub sid_buffer: Vec<u8>,
+}
+
+impl QueryQuotaInfo {
+    /// Creates a new instance of the query quota info that restarts the scan
+    /// and asks for all quota entries.
+    pub fn default() -> Self {
+        QueryQuotaInfo {
+            return_single: vec![0],
+            restart_scan: vec![1],
+            reserved: vec![0; 2],
+            sid_list_length: vec![0; 4],
+            start_sid_length: vec![0; 4],
+            start_sid_offset: vec![0; 4],
+            sid_buffer: Vec::new(),
+        }
+    }
+}
+
 /// InfoType (1 byte): The type of information queried.
 /// This field MUST contain one of the following values:
 #[derive(Debug, PartialEq, Eq, Clone)]
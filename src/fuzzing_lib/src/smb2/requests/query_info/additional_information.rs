@@ -0,0 +1,84 @@
+//! The security information flags that can be requested in the AdditionalInformation
+//! field of an SMB2 QUERY_INFO Request with InfoType SMB2_0_INFO_SECURITY.
+
+use rand::{
+    distributions::{Distribution, Standard},
+    Rng,
+};
+
+/// AdditionalInformation (4 bytes): For security queries, this field MUST be set to
+/// a combination of zero or more of the following bit values.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum AdditionalInformation {
+    OwnerSecurityInformation,
+    GroupSecurityInformation,
+    DaclSecurityInformation,
+    SaclSecurityInformation,
+    LabelSecurityInformation,
+    AttributeSecurityInformation,
+    ScopeSecurityInformation,
+    BackupSecurityInformation,
+}
+
+impl AdditionalInformation {
+    /// Unpacks the byte code of the corresponding security information flag.
+    pub fn unpack_byte_code(&self) -> u32 {
+        match self {
+            AdditionalInformation::OwnerSecurityInformation => 0x00000001,
+            AdditionalInformation::GroupSecurityInformation => 0x00000002,
+            AdditionalInformation::DaclSecurityInformation => 0x00000004,
+            AdditionalInformation::SaclSecurityInformation => 0x00000008,
+            AdditionalInformation::LabelSecurityInformation => 0x00000010,
+            AdditionalInformation::AttributeSecurityInformation => 0x00000020,
+            AdditionalInformation::ScopeSecurityInformation => 0x00000040,
+            AdditionalInformation::BackupSecurityInformation => 0x00010000,
+        }
+    }
+
+    /// Returns a sum of the given security information flags as a 4 byte array.
+    /// Duplicates are only counted once.
+    pub fn return_sum_of_chosen_additional_information(
+        information: Vec<AdditionalInformation>,
+    ) -> Vec<u8> {
+        let combined_information: u32 = information
+            .iter()
+            .fold(0u32, |acc, info| acc | info.unpack_byte_code());
+
+        combined_information.to_le_bytes().to_vec()
+    }
+}
+
+impl Distribution<AdditionalInformation> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> AdditionalInformation {
+        match rng.gen_range(0..=7) {
+            0 => AdditionalInformation::OwnerSecurityInformation,
+            1 => AdditionalInformation::GroupSecurityInformation,
+            2 => AdditionalInformation::DaclSecurityInformation,
+            3 => AdditionalInformation::SaclSecurityInformation,
+            4 => AdditionalInformation::LabelSecurityInformation,
+            5 => AdditionalInformation::AttributeSecurityInformation,
+            6 => AdditionalInformation::ScopeSecurityInformation,
+            _ => AdditionalInformation::BackupSecurityInformation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_return_sum_of_chosen_additional_information() {
+        let information = vec![
+            AdditionalInformation::OwnerSecurityInformation,
+            AdditionalInformation::DaclSecurityInformation,
+            AdditionalInformation::DaclSecurityInformation,
+            AdditionalInformation::BackupSecurityInformation,
+        ];
+
+        assert_eq!(
+            vec![5, 0, 1, 0],
+            AdditionalInformation::return_sum_of_chosen_additional_information(information)
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! The file information classes that are valid for an SMB2 QUERY_INFO Request
+//! with InfoType SMB2_0_INFO_FILE (MS-FSCC Section 2.4).
+
+use rand::{
+    distributions::{Distribution, Standard},
+    Rng,
+};
+
+/// The maximum length in bytes that is reserved for variable-length
+/// file names or stream lists in the output buffer.
+pub const VARIABLE_NAME_LENGTH: u32 = 0x0200;
+
+/// FileInfoClass (1 byte): For file information queries, this field MUST contain
+/// one of the following FILE_INFORMATION_CLASS values.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FileInformationClass {
+    FileAccessInformation,
+    FileAlignmentInformation,
+    FileAllInformation,
+    FileAlternateNameInformation,
+    FileAttributeTagInformation,
+    FileBasicInformation,
+    FileCompressionInformation,
+    FileEaInformation,
+    FileFullEaInformation,
+    FileIdInformation,
+    FileInternalInformation,
+    FileModeInformation,
+    FileNetworkOpenInformation,
+    FileNormalizedNameInformation,
+    FilePipeInformation,
+    FilePipeLocalInformation,
+    FilePipeRemoteInformation,
+    FilePositionInformation,
+    FileStandardInformation,
+    FileStreamInformation,
+}
+
+impl FileInformationClass {
+    /// Unpacks the byte code of the corresponding file information class.
+    pub fn unpack_byte_code(&self) -> Vec<u8> {
+        match self {
+            FileInformationClass::FileAccessInformation => vec![8],
+            FileInformationClass::FileAlignmentInformation => vec![17],
+            FileInformationClass::FileAllInformation => vec![18],
+            FileInformationClass::FileAlternateNameInformation => vec![21],
+            FileInformationClass::FileAttributeTagInformation => vec![35],
+            FileInformationClass::FileBasicInformation => vec![4],
+            FileInformationClass::FileCompressionInformation => vec![28],
+            FileInformationClass::FileEaInformation => vec![7],
+            FileInformationClass::FileFullEaInformation => vec![15],
+            FileInformationClass::FileIdInformation => vec![59],
+            FileInformationClass::FileInternalInformation => vec![6],
+            FileInformationClass::FileModeInformation => vec![16],
+            FileInformationClass::FileNetworkOpenInformation => vec![34],
+            FileInformationClass::FileNormalizedNameInformation => vec![48],
+            FileInformationClass::FilePipeInformation => vec![23],
+            FileInformationClass::FilePipeLocalInformation => vec![24],
+            FileInformationClass::FilePipeRemoteInformation => vec![25],
+            FileInformationClass::FilePositionInformation => vec![14],
+            FileInformationClass::FileStandardInformation => vec![5],
+            FileInformationClass::FileStreamInformation => vec![22],
+        }
+    }
+
+    /// Returns the number of bytes the server needs to return the complete
+    /// information structure. Variable-length structures reserve
+    /// VARIABLE_NAME_LENGTH bytes for their trailing names.
+    pub fn output_buffer_length(&self) -> u32 {
+        match self {
+            FileInformationClass::FileAccessInformation => 4,
+            FileInformationClass::FileAlignmentInformation => 4,
+            FileInformationClass::FileAllInformation => 100 + VARIABLE_NAME_LENGTH,
+            FileInformationClass::FileAlternateNameInformation => 4 + VARIABLE_NAME_LENGTH,
+            FileInformationClass::FileAttributeTagInformation => 8,
+            FileInformationClass::FileBasicInformation => 40,
+            FileInformationClass::FileCompressionInformation => 16,
+            FileInformationClass::FileEaInformation => 4,
+            FileInformationClass::FileFullEaInformation => 8 + VARIABLE_NAME_LENGTH,
+            FileInformationClass::FileIdInformation => 24,
+            FileInformationClass::FileInternalInformation => 8,
+            FileInformationClass::FileModeInformation => 4,
+            FileInformationClass::FileNetworkOpenInformation => 56,
+            FileInformationClass::FileNormalizedNameInformation => 4 + VARIABLE_NAME_LENGTH,
+            FileInformationClass::FilePipeInformation => 8,
+            FileInformationClass::FilePipeLocalInformation => 40,
+            FileInformationClass::FilePipeRemoteInformation => 12,
+            FileInformationClass::FilePositionInformation => 8,
+            FileInformationClass::FileStandardInformation => 24,
+            FileInformationClass::FileStreamInformation => 24 + VARIABLE_NAME_LENGTH,
+        }
+    }
+}
+
+impl Distribution<FileInformationClass> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> FileInformationClass {
+        match rng.gen_range(0..=19) {
+            0 => FileInformationClass::FileAccessInformation,
+            1 => FileInformationClass::FileAlignmentInformation,
+            2 => FileInformationClass::FileAllInformation,
+            3 => FileInformationClass::FileAlternateNameInformation,
+            4 => FileInformationClass::FileAttributeTagInformation,
+            5 => FileInformationClass::FileBasicInformation,
+            6 => FileInformationClass::FileCompressionInformation,
+            7 => FileInformationClass::FileEaInformation,
+            8 => FileInformationClass::FileFullEaInformation,
+            9 => FileInformationClass::FileIdInformation,
+            10 => FileInformationClass::FileInternalInformation,
+            11 => FileInformationClass::FileModeInformation,
+            12 => FileInformationClass::FileNetworkOpenInformation,
+            13 => FileInformationClass::FileNormalizedNameInformation,
+            14 => FileInformationClass::FilePipeInformation,
+            15 => FileInformationClass::FilePipeLocalInformation,
+            16 => FileInformationClass::FilePipeRemoteInformation,
+            17 => FileInformationClass::FilePositionInformation,
+            18 => FileInformationClass::FileStandardInformation,
+            _ => FileInformationClass::FileStreamInformation,
+        }
+    }
+}
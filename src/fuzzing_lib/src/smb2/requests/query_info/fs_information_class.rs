@@ -0,0 +1,70 @@
+//! The file system information classes that are valid for an SMB2 QUERY_INFO Request
+//! with InfoType SMB2_0_INFO_FILESYSTEM (MS-FSCC Section 2.5).
+
+use rand::{
+    distributions::{Distribution, Standard},
+    Rng,
+};
+
+use super::file_information_class::VARIABLE_NAME_LENGTH;
+
+/// FileInfoClass (1 byte): For underlying object store information queries,
+/// this field MUST contain one of the following FS_INFORMATION_CLASS values.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FsInformationClass {
+    FileFsAttributeInformation,
+    FileFsControlInformation,
+    FileFsDeviceInformation,
+    FileFsFullSizeInformation,
+    FileFsObjectIdInformation,
+    FileFsSectorSizeInformation,
+    FileFsSizeInformation,
+    FileFsVolumeInformation,
+}
+
+impl FsInformationClass {
+    /// Unpacks the byte code of the corresponding file system information class.
+    pub fn unpack_byte_code(&self) -> Vec<u8> {
+        match self {
+            FsInformationClass::FileFsAttributeInformation => vec![5],
+            FsInformationClass::FileFsControlInformation => vec![6],
+            FsInformationClass::FileFsDeviceInformation => vec![4],
+            FsInformationClass::FileFsFullSizeInformation => vec![7],
+            FsInformationClass::FileFsObjectIdInformation => vec![8],
+            FsInformationClass::FileFsSectorSizeInformation => vec![11],
+            FsInformationClass::FileFsSizeInformation => vec![3],
+            FsInformationClass::FileFsVolumeInformation => vec![1],
+        }
+    }
+
+    /// Returns the number of bytes the server needs to return the complete
+    /// information structure. Variable-length structures reserve
+    /// VARIABLE_NAME_LENGTH bytes for their trailing names.
+    pub fn output_buffer_length(&self) -> u32 {
+        match self {
+            FsInformationClass::FileFsAttributeInformation => 12 + VARIABLE_NAME_LENGTH,
+            FsInformationClass::FileFsControlInformation => 48,
+            FsInformationClass::FileFsDeviceInformation => 8,
+            FsInformationClass::FileFsFullSizeInformation => 32,
+            FsInformationClass::FileFsObjectIdInformation => 64,
+            FsInformationClass::FileFsSectorSizeInformation => 28,
+            FsInformationClass::FileFsSizeInformation => 24,
+            FsInformationClass::FileFsVolumeInformation => 18 + VARIABLE_NAME_LENGTH,
+        }
+    }
+}
+
+impl Distribution<FsInformationClass> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> FsInformationClass {
+        match rng.gen_range(0..=7) {
+            0 => FsInformationClass::FileFsAttributeInformation,
+            1 => FsInformationClass::FileFsControlInformation,
+            2 => FsInformationClass::FileFsDeviceInformation,
+            3 => FsInformationClass::FileFsFullSizeInformation,
+            4 => FsInformationClass::FileFsObjectIdInformation,
+            5 => FsInformationClass::FileFsSectorSizeInformation,
+            6 => FsInformationClass::FileFsSizeInformation,
+            _ => FsInformationClass::FileFsVolumeInformation,
+        }
+    }
+}
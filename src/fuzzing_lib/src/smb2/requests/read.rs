@@ -0,0 +1,63 @@
+/// Read request size of 49 bytes
+const STRUCTURE_SIZE: &[u8; 2] = b"\x31\x00";
+
+/// The SMB2 READ Request packet is sent by the client to request
+/// a read operation on the file that is specified by the FileId.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Read {
+    /// StructureSize (2 bytes): The client MUST set this field to 49,
+    /// indicating the size of the request structure, not including the header.
+    /// The client MUST set it to this value regardless of how long Buffer[]
+    /// actually is in the request being sent.
+    pub structure_size: Vec<u8>,
+    /// Padding (1 byte): The requested offset from the start of the SMB2 header,
+    /// in bytes, at which to place the data read in the SMB2 READ Response.
+    pub padding: Vec<u8>,
+    /// Flags (1 byte): For the SMB 3.0.2 and SMB 3.1.1 dialects, this field MUST contain
+    /// zero or more of the read flags. For all other dialects, this field MUST NOT be used and MUST be reserved.
+    pub flags: Vec<u8>,
+    /// Length (4 bytes): The length of the data to read from the specified file or pipe.
+    pub length: Vec<u8>,
+    /// Offset (8 bytes): The offset, in bytes, into the file from which the data MUST be read.
+    pub offset: Vec<u8>,
+    /// FileId (16 bytes): An SMB2_FILEID of the file or pipe from which the data is read.
+    pub file_id: Vec<u8>,
+    /// MinimumCount (4 bytes): The minimum number of bytes to be read for this operation to be successful.
+    pub minimum_count: Vec<u8>,
+    /// Channel (4 bytes): For the SMB 2.0.2 and 2.1 dialects, this field MUST NOT be used and MUST be reserved.
+    /// For the SMB 3.x dialect family, this field MUST contain exactly one of the channel values.
+    pub channel: Vec<u8>,
+    /// RemainingBytes (4 bytes): The number of subsequent bytes that the client intends to read
+    /// from the file after this operation completes.
+    pub remaining_bytes: Vec<u8>,
+    /// ReadChannelInfoOffset (2 bytes): For the SMB 2.0.2 and 2.1 dialects, this field MUST NOT be used
+    /// and MUST be reserved. Otherwise, it contains the offset, in bytes, from the beginning
+    /// of the SMB2 header to the channel data.
+    pub read_channel_info_offset: Vec<u8>,
+    /// ReadChannelInfoLength (2 bytes): For the SMB 2.0.2 and 2.1 dialects, this field MUST NOT be used
+    /// and MUST be reserved. Otherwise, it contains the length, in bytes, of the channel data.
+    pub read_channel_info_length: Vec<u8>,
+    /// Buffer (variable): A variable-length buffer that contains the read channel information.
+    /// The client MUST set one byte of this field to 0, and the server MUST ignore it on receipt.
+    pub buffer: Vec<u8>,
+}
+
+impl Read {
+    /// Creates a new instance of the read request.
+    pub fn default() -> Self {
+        Read {
+            structure_size: STRUCTURE_SIZE.to_vec(),
+            padding: vec![0x50],
+            flags: vec![0],
+            length: Vec::new(),
+            offset: Vec::new(),
+            file_id: Vec::new(),
+            minimum_count: vec![0; 4],
+            channel: vec![0; 4],
+            remaining_bytes: vec![0; 4],
+            read_channel_info_offset: vec![0; 2],
+            read_channel_info_length: vec![0; 2],
+            buffer: vec![0],
+        }
+    }
+}
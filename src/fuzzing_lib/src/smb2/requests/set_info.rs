@@ -0,0 +1,55 @@
+/// Set Info structure size of 33 bytes
+const STRUCTURE_SIZE: &[u8; 2] = b"\x21\x00";
+
+/// The SMB2 SET_INFO Request packet is sent by a client to set information on a file
+/// or underlying object store.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SetInfo {
+    /// StructureSize (2 bytes): The client MUST set this field to 33,
+    /// indicating the size of the request structure, not including the header.
+    /// The client MUST set this field to this value regardless of how long Buffer[]
+    /// actually is in the request being sent.
+    pub structure_size: Vec<u8>,
+    /// InfoType (1 byte): The type of information being set.
+    pub info_type: Vec<u8>,
+    /// FileInfoClass (1 byte): For setting file information, this field MUST contain
+    /// one of the FileInformationClass values. For setting underlying object store information,
+    /// this field MUST contain one of the FsInformationClass values.
+    /// For setting security and quota information, this field MUST be 0.
+    pub file_info_class: Vec<u8>,
+    /// BufferLength (4 bytes): The length, in bytes, of the information to be set.
+    pub buffer_length: Vec<u8>,
+    /// BufferOffset (2 bytes): The offset, in bytes, from the beginning of the SMB2 header
+    /// to the information to be set.
+    pub buffer_offset: Vec<u8>,
+    /// Reserved (2 bytes): This field MUST NOT be used and MUST be reserved.
+    /// The client MUST set this field to 0, and the server MUST ignore it on receipt.
+    pub reserved: Vec<u8>,
+    /// AdditionalInformation (4 bytes): Provides additional information to the server.
+    /// For security information, this field MUST be a combination of AdditionalInformation flags.
+    /// For all other information, this field MUST be set to 0.
+    pub additional_information: Vec<u8>,
+    /// FileId (16 bytes): An SMB2_FILEID that identifies the file or named pipe
+    /// on which to perform the set.
+    pub file_id: Vec<u8>,
+    /// Buffer (variable): A variable-length buffer that contains the information being set
+    /// for the request, as described by the BufferOffset and BufferLength fields.
+    pub buffer: Vec<u8>,
+}
+
+impl SetInfo {
+    /// Creates a new instance of the set info request.
+    pub fn default() -> Self {
+        SetInfo {
+            structure_size: STRUCTURE_SIZE.to_vec(),
+            info_type: Vec::new(),
+            file_info_class: Vec::new(),
+            buffer_length: Vec::new(),
+            buffer_offset: b"\x60\x00".to_vec(),
+            reserved: vec![0; 2],
+            additional_information: vec![0; 4],
+            file_id: Vec::new(),
+            buffer: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! The SMB2 CHANGE_NOTIFY Response packet is sent by the server to transmit the results
+//! of a client's SMB2 CHANGE_NOTIFY Request. It completes the request after the server has
+//! announced its asynchronous processing with an interim response.
+//! This response consists of an SMB2 header, followed by this response structure.
+
+/// Represents the structure size of the change notify response.
+const STRUCTURE_SIZE: &[u8; 2] = b"\x09\x00";
+
+/// A struct that represents a change notify response.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ChangeNotify {
+    /// StructureSize (2 bytes): The server MUST set this field to 9, indicating the size of the
+    /// request structure, not including the header. The server MUST set the field to this value
+    /// regardless of how long Buffer[] actually is in the request.
+    pub structure_size: Vec<u8>,
+    /// OutputBufferOffset (2 bytes): The offset, in bytes, from the beginning of the SMB2 header
+    /// to the change information being returned.
+    pub output_buffer_offset: Vec<u8>,
+    /// OutputBufferLength (4 bytes): The length, in bytes, of the change information being returned.
+    pub output_buffer_length: Vec<u8>,
+    /// Buffer (variable): A variable-length buffer containing the change information being returned
+    /// in the response, as described by the OutputBufferOffset and OutputBufferLength fields.
+    /// This field is an array of FILE_NOTIFY_INFORMATION structures.
+    pub buffer: Vec<u8>,
+}
+
+impl ChangeNotify {
+    /// Creates a new instance of the change notify response.
+    pub fn default() -> Self {
+        ChangeNotify {
+            structure_size: STRUCTURE_SIZE.to_vec(),
+            output_buffer_offset: Vec::new(),
+            output_buffer_length: Vec::new(),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+/// The FILE_NOTIFY_INFORMATION structure contains the change information
+/// for a single file or directory (MS-FSCC Section 2.7.1).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FileNotifyInformation {
+    /// NextEntryOffset (4 bytes): The offset, in bytes, from the beginning of this structure
+    /// to the subsequent FILE_NOTIFY_INFORMATION structure. A value of 0 indicates the last entry.
+    pub next_entry_offset: Vec<u8>,
+    /// Action (4 bytes): The changes that occurred on the file.
+    pub action: Vec<u8>,
+    /// FileNameLength (4 bytes): The length, in bytes, of the file name in the FileName field.
+    pub file_name_length: Vec<u8>,
+    /// FileName (variable): A Unicode string with the name of the file that changed.
+    pub file_name: Vec<u8>,
+}
@@ -0,0 +1,40 @@
+//! The SMB2 ERROR Response packet is sent by the server to respond to a request
+//! that has failed or encountered an error. It is also the body of the interim response
+//! with the status STATUS_PENDING that announces the asynchronous processing of a request.
+//! This response consists of an SMB2 header, followed by this response structure.
+
+/// Represents the structure size of the error response.
+const STRUCTURE_SIZE: &[u8; 2] = b"\x09\x00";
+
+/// A struct that represents an error response.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Error {
+    /// StructureSize (2 bytes): The server MUST set this field to 9, indicating the size of the
+    /// response structure, not including the header. The server MUST set it to this value
+    /// regardless of how long ErrorData[] actually is in the response being sent.
+    pub structure_size: Vec<u8>,
+    /// ErrorContextCount (1 byte): This field MUST be set to 0 for SMB dialects other than 3.1.1.
+    /// For the SMB dialect 3.1.1, if this field is nonzero, the ErrorData field MUST be formatted
+    /// as a variable-length array of SMB2 ERROR Context structures.
+    pub error_context_count: Vec<u8>,
+    /// Reserved (1 byte): This field MUST NOT be used and MUST be reserved.
+    /// The server MUST set this to 0, and the client MUST ignore it on receipt.
+    pub reserved: Vec<u8>,
+    /// ByteCount (4 bytes): The number of bytes of data contained in ErrorData[].
+    pub byte_count: Vec<u8>,
+    /// ErrorData (variable): A variable-length data field that contains extended error information.
+    pub error_data: Vec<u8>,
+}
+
+impl Error {
+    /// Creates a new instance of the error response.
+    pub fn default() -> Self {
+        Error {
+            structure_size: STRUCTURE_SIZE.to_vec(),
+            error_context_count: vec![0],
+            reserved: vec![0],
+            byte_count: vec![0; 4],
+            error_data: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! The SMB2 LOCK Response packet is sent by a server in response to an SMB2 LOCK Request packet.
+//! This response consists of an SMB2 header, followed by this response structure.
+
+/// Represents the structure size of the lock response.
+const STRUCTURE_SIZE: &[u8; 2] = b"\x04\x00";
+
+/// A struct that represents a lock response.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Lock {
+    /// StructureSize (2 bytes): The server MUST set this to 4, indicating the size of the response structure,
+    /// not including the header.
+    pub structure_size: Vec<u8>,
+    /// Reserved (2 bytes): This field MUST NOT be used and MUST be reserved.
+    /// The server MUST set this field to 0, and the client MUST ignore it on receipt.
+    pub reserved: Vec<u8>,
+}
+
+impl Lock {
+    /// Creates a new instance of the lock response.
+    pub fn default() -> Self {
+        Lock {
+            structure_size: STRUCTURE_SIZE.to_vec(),
+            reserved: vec![0; 2],
+        }
+    }
+}
@@ -1,4 +1,10 @@
+pub mod change_notify;
 pub mod create;
+pub mod error;
+pub mod flush;
+pub mod lock;
 pub mod negotiate;
+pub mod oplock_break;
+pub mod query_info;
 pub mod session_setup;
 pub mod tree_connect;
@@ -0,0 +1,128 @@
+//! The SMB2 OPLOCK_BREAK Notification packet is sent by the server when the underlying
+//! object store indicates that an oplock or a lease is being broken.
+//! It is sent unsolicited with a MessageId of 0xFFFFFFFFFFFFFFFF.
+//! The SMB2 OPLOCK_BREAK Response packet is sent by the server in response to an
+//! SMB2 OPLOCK_BREAK Acknowledgment from the client.
+//! Both consist of an SMB2 header, followed by one of the structures below.
+
+/// Represents the structure size of the oplock break notification and response.
+const OPLOCK_BREAK_STRUCTURE_SIZE: &[u8; 2] = b"\x18\x00";
+/// Represents the structure size of the lease break notification.
+const LEASE_BREAK_NOTIFICATION_STRUCTURE_SIZE: &[u8; 2] = b"\x2c\x00";
+/// Represents the structure size of the lease break response.
+const LEASE_BREAK_RESPONSE_STRUCTURE_SIZE: &[u8; 2] = b"\x24\x00";
+
+/// The break notification the server sends, distinguished by its structure size.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BreakNotification {
+    Oplock(OplockBreak),
+    Lease(LeaseBreakNotification),
+    Raw(Vec<u8>),
+}
+
+/// A struct that represents an oplock break notification and an oplock break response.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OplockBreak {
+    /// StructureSize (2 bytes): The server MUST set this to 24,
+    /// indicating the size of the response structure, not including the header.
+    pub structure_size: Vec<u8>,
+    /// OplockLevel (1 byte): The oplock level to which the server is breaking
+    /// or which the server granted. This field MUST be SMB2_OPLOCK_LEVEL_NONE or SMB2_OPLOCK_LEVEL_II.
+    pub oplock_level: Vec<u8>,
+    /// Reserved (1 byte): This field MUST NOT be used and MUST be reserved.
+    /// The server MUST set this to 0, and the client MUST ignore it on receipt.
+    pub reserved: Vec<u8>,
+    /// Reserved2 (4 bytes): This field MUST NOT be used and MUST be reserved.
+    /// The server MUST set this to 0, and the client MUST ignore it on receipt.
+    pub reserved_2: Vec<u8>,
+    /// FileId (16 bytes): An SMB2_FILEID of the file or pipe on which the oplock break occurred.
+    pub file_id: Vec<u8>,
+}
+
+impl OplockBreak {
+    /// Creates a new instance of the oplock break notification.
+    pub fn default() -> Self {
+        OplockBreak {
+            structure_size: OPLOCK_BREAK_STRUCTURE_SIZE.to_vec(),
+            oplock_level: Vec::new(),
+            reserved: vec![0],
+            reserved_2: vec![0; 4],
+            file_id: Vec::new(),
+        }
+    }
+}
+
+/// A struct that represents a lease break notification.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LeaseBreakNotification {
+    /// StructureSize (2 bytes): The server MUST set this to 44,
+    /// indicating the size of the response structure, not including the header.
+    pub structure_size: Vec<u8>,
+    /// NewEpoch (2 bytes): For the SMB 3.x dialect family, this field contains the epoch
+    /// of the lease being broken. Otherwise, it MUST NOT be used and MUST be reserved.
+    pub new_epoch: Vec<u8>,
+    /// Flags (4 bytes): SMB2_NOTIFY_BREAK_LEASE_FLAG_ACK_REQUIRED is set if
+    /// the client has to send an acknowledgment.
+    pub flags: Vec<u8>,
+    /// LeaseKey (16 bytes): The client-generated key that identifies the lease being broken.
+    pub lease_key: Vec<u8>,
+    /// CurrentLeaseState (4 bytes): The current lease state of the open.
+    pub current_lease_state: Vec<u8>,
+    /// NewLeaseState (4 bytes): The new lease state of the open.
+    pub new_lease_state: Vec<u8>,
+    /// BreakReason (4 bytes): This field MUST NOT be used and MUST be reserved.
+    pub break_reason: Vec<u8>,
+    /// AccessMaskHint (4 bytes): This field MUST NOT be used and MUST be reserved.
+    pub access_mask_hint: Vec<u8>,
+    /// ShareMaskHint (4 bytes): This field MUST NOT be used and MUST be reserved.
+    pub share_mask_hint: Vec<u8>,
+}
+
+impl LeaseBreakNotification {
+    /// Creates a new instance of the lease break notification.
+    pub fn default() -> Self {
+        LeaseBreakNotification {
+            structure_size: LEASE_BREAK_NOTIFICATION_STRUCTURE_SIZE.to_vec(),
+            new_epoch: Vec::new(),
+            flags: Vec::new(),
+            lease_key: Vec::new(),
+            current_lease_state: Vec::new(),
+            new_lease_state: Vec::new(),
+            break_reason: vec![0; 4],
+            access_mask_hint: vec![0; 4],
+            share_mask_hint: vec![0; 4],
+        }
+    }
+}
+
+/// A struct that represents a lease break response.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LeaseBreakResponse {
+    /// StructureSize (2 bytes): The server MUST set this to 36,
+    /// indicating the size of the response structure, not including the header.
+    pub structure_size: Vec<u8>,
+    /// Reserved (2 bytes): This field MUST NOT be used and MUST be reserved.
+    pub reserved: Vec<u8>,
+    /// Flags (4 bytes): This field MUST NOT be used and MUST be reserved.
+    pub flags: Vec<u8>,
+    /// LeaseKey (16 bytes): The client-generated key that identifies the owner of the lease.
+    pub lease_key: Vec<u8>,
+    /// LeaseState (4 bytes): The requested lease state granted to the client.
+    pub lease_state: Vec<u8>,
+    /// LeaseDuration (8 bytes): This field MUST NOT be used and MUST be reserved.
+    pub lease_duration: Vec<u8>,
+}
+
+impl LeaseBreakResponse {
+    /// Creates a new instance of the lease break response.
+    pub fn default() -> Self {
+        LeaseBreakResponse {
+            structure_size: LEASE_BREAK_RESPONSE_STRUCTURE_SIZE.to_vec(),
+            reserved: vec![0; 2],
+            flags: vec![0; 4],
+            lease_key: Vec::new(),
+            lease_state: Vec::new(),
+            lease_duration: vec![0; 8],
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! The SMB2 QUERY_INFO Response packet is sent by a server in response to an SMB2 QUERY_INFO Request packet.
+//! This response consists of an SMB2 header, followed by this response structure.
+
+pub mod file_information;
+pub mod fs_information;
+
+use self::{
+    file_information::{
+        FileAllInformation, FileBasicInformation, FileInternalInformation,
+        FileNetworkOpenInformation, FilePositionInformation, FileStandardInformation,
+    },
+    fs_information::{
+        FileFsAttributeInformation, FileFsDeviceInformation, FileFsFullSizeInformation,
+        FileFsSizeInformation, FileFsVolumeInformation,
+    },
+};
+
+/// Represents the structure size of the query info response.
+const STRUCTURE_SIZE: &[u8; 2] = b"\x09\x00";
+
+/// A struct that represents a query info response.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct QueryInfo {
+    /// StructureSize (2 bytes): The server MUST set this field to 9, indicating the size of the
+    /// request structure, not including the header. The server MUST set this field to this value
+    /// regardless of how long Buffer[] actually is in the request being sent.
+    pub structure_size: Vec<u8>,
+    /// OutputBufferOffset (2 bytes): The offset, in bytes, from the beginning of the SMB2 header
+    /// to the information being returned.
+    pub output_buffer_offset: Vec<u8>,
+    /// OutputBufferLength (4 bytes): The length, in bytes, of the information being returned.
+    pub output_buffer_length: Vec<u8>,
+    /// Buffer (variable): A variable-length buffer that contains the information that is returned
+    /// in the response, as described by the OutputBufferOffset and OutputBufferLength fields.
+    pub buffer: Vec<u8>,
+}
+
+impl QueryInfo {
+    /// Creates a new instance of the query info response.
+    pub fn default() -> Self {
+        QueryInfo {
+            structure_size: STRUCTURE_SIZE.to_vec(),
+            output_buffer_offset: Vec::new(),
+            output_buffer_length: Vec::new(),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+/// The typed content of the output buffer of a query info response.
+/// Information classes without a typed representation are kept as raw bytes.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum OutputBuffer {
+    FileBasic(FileBasicInformation),
+    FileStandard(FileStandardInformation),
+    FileInternal(FileInternalInformation),
+    FilePosition(FilePositionInformation),
+    FileNetworkOpen(FileNetworkOpenInformation),
+    FileAll(Box<FileAllInformation>),
+    FsVolume(FileFsVolumeInformation),
+    FsSize(FileFsSizeInformation),
+    FsFullSize(FileFsFullSizeInformation),
+    FsDevice(FileFsDeviceInformation),
+    FsAttribute(FileFsAttributeInformation),
+    Raw(Vec<u8>),
+}
+
+impl OutputBuffer {
+    /// Returns the bytes of all reserved fields of the information structure.
+    /// The server is required to zero these, so any other value hints at
+    /// uninitialized memory being sent to the client.
+    pub fn reserved_bytes(&self) -> Vec<u8> {
+        match self {
+            OutputBuffer::FileBasic(info) => info.reserved.clone(),
+            OutputBuffer::FileStandard(info) => info.reserved.clone(),
+            OutputBuffer::FileNetworkOpen(info) => info.reserved.clone(),
+            OutputBuffer::FileAll(info) => {
+                let mut reserved = info.basic_information.reserved.clone();
+                reserved.append(&mut info.standard_information.reserved.clone());
+                reserved
+            }
+            OutputBuffer::FsVolume(info) => info.reserved.clone(),
+            _ => Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! The file information structures that are returned in the output buffer
+//! of a query info response for InfoType SMB2_0_INFO_FILE (MS-FSCC Section 2.4).
+
+/// FILE_BASIC_INFORMATION is used to query the timestamps and attributes of a file (40 bytes).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FileBasicInformation {
+    /// CreationTime (8 bytes): The time when the file was created.
+    pub creation_time: Vec<u8>,
+    /// LastAccessTime (8 bytes): The last time the file was accessed.
+    pub last_access_time: Vec<u8>,
+    /// LastWriteTime (8 bytes): The last time information was written to the file.
+    pub last_write_time: Vec<u8>,
+    /// ChangeTime (8 bytes): The last time the file was changed.
+    pub change_time: Vec<u8>,
+    /// FileAttributes (4 bytes): The file attributes.
+    pub file_attributes: Vec<u8>,
+    /// Reserved (4 bytes): A 32-bit field. This field is reserved.
+    /// This field can be set to any value, and MUST be ignored.
+    pub reserved: Vec<u8>,
+}
+
+impl FileBasicInformation {
+    /// Creates a new instance of the file basic information.
+    pub fn default() -> Self {
+        FileBasicInformation {
+            creation_time: Vec::new(),
+            last_access_time: Vec::new(),
+            last_write_time: Vec::new(),
+            change_time: Vec::new(),
+            file_attributes: Vec::new(),
+            reserved: Vec::new(),
+        }
+    }
+}
+
+/// FILE_STANDARD_INFORMATION is used to query the size and link information of a file (24 bytes).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FileStandardInformation {
+    /// AllocationSize (8 bytes): The file allocation size in bytes.
+    pub allocation_size: Vec<u8>,
+    /// EndOfFile (8 bytes): The absolute new end-of-file position as a byte offset from the start of the file.
+    pub end_of_file: Vec<u8>,
+    /// NumberOfLinks (4 bytes): The number of non-deleted links to this file.
+    pub number_of_links: Vec<u8>,
+    /// DeletePending (1 byte): Set to 1 to indicate that a file deletion has been requested.
+    pub delete_pending: Vec<u8>,
+    /// Directory (1 byte): Set to 1 to indicate that the file is a directory.
+    pub directory: Vec<u8>,
+    /// Reserved (2 bytes): A 16-bit field. This field is reserved.
+    /// This field can be set to any value, and MUST be ignored.
+    pub reserved: Vec<u8>,
+}
+
+impl FileStandardInformation {
+    /// Creates a new instance of the file standard information.
+    pub fn default() -> Self {
+        FileStandardInformation {
+            allocation_size: Vec::new(),
+            end_of_file: Vec::new(),
+            number_of_links: Vec::new(),
+            delete_pending: Vec::new(),
+            directory: Vec::new(),
+            reserved: Vec::new(),
+        }
+    }
+}
+
+/// FILE_INTERNAL_INFORMATION is used to query the file system's 8-byte file reference number (8 bytes).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FileInternalInformation {
+    /// IndexNumber (8 bytes): A 64-bit signed integer that contains the 8-byte file reference number for the file.
+    pub index_number: Vec<u8>,
+}
+
+impl FileInternalInformation {
+    /// Creates a new instance of the file internal information.
+    pub fn default() -> Self {
+        FileInternalInformation {
+            index_number: Vec::new(),
+        }
+    }
+}
+
+/// FILE_POSITION_INFORMATION is used to query the current byte offset of the file pointer (8 bytes).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FilePositionInformation {
+    /// CurrentByteOffset (8 bytes): The byte offset of the file pointer from the beginning of the file.
+    pub current_byte_offset: Vec<u8>,
+}
+
+impl FilePositionInformation {
+    /// Creates a new instance of the file position information.
+    pub fn default() -> Self {
+        FilePositionInformation {
+            current_byte_offset: Vec::new(),
+        }
+    }
+}
+
+/// FILE_NETWORK_OPEN_INFORMATION is used to query the attributes
+/// that are commonly needed when opening a file over the network (56 bytes).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FileNetworkOpenInformation {
+    /// CreationTime (8 bytes): The time when the file was created.
+    pub creation_time: Vec<u8>,
+    /// LastAccessTime (8 bytes): The time when the file was last accessed.
+    pub last_access_time: Vec<u8>,
+    /// LastWriteTime (8 bytes): The time when data was last written to the file.
+    pub last_write_time: Vec<u8>,
+    /// ChangeTime (8 bytes): The time when the file was last changed.
+    pub change_time: Vec<u8>,
+    /// AllocationSize (8 bytes): The number of bytes that are allocated to the file.
+    pub allocation_size: Vec<u8>,
+    /// EndOfFile (8 bytes): The end of file location as a byte offset.
+    pub end_of_file: Vec<u8>,
+    /// FileAttributes (4 bytes): The file attributes.
+    pub file_attributes: Vec<u8>,
+    /// Reserved (4 bytes): A 32-bit field. This field is reserved.
+    /// This field can be set to any value, and MUST be ignored.
+    pub reserved: Vec<u8>,
+}
+
+impl FileNetworkOpenInformation {
+    /// Creates a new instance of the file network open information.
+    pub fn default() -> Self {
+        FileNetworkOpenInformation {
+            creation_time: Vec::new(),
+            last_access_time: Vec::new(),
+            last_write_time: Vec::new(),
+            change_time: Vec::new(),
+            allocation_size: Vec::new(),
+            end_of_file: Vec::new(),
+            file_attributes: Vec::new(),
+            reserved: Vec::new(),
+        }
+    }
+}
+
+/// FILE_ALL_INFORMATION is used to query a collection of file information structures.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FileAllInformation {
+    /// BasicInformation (40 bytes): A FILE_BASIC_INFORMATION structure.
+    pub basic_information: FileBasicInformation,
+    /// StandardInformation (24 bytes): A FILE_STANDARD_INFORMATION structure.
+    pub standard_information: FileStandardInformation,
+    /// InternalInformation (8 bytes): A FILE_INTERNAL_INFORMATION structure.
+    pub internal_information: FileInternalInformation,
+    /// EaInformation (4 bytes): The combined length, in bytes, of the extended attributes for the file.
+    pub ea_size: Vec<u8>,
+    /// AccessInformation (4 bytes): The access rights that were granted when the file was opened.
+    pub access_flags: Vec<u8>,
+    /// PositionInformation (8 bytes): A FILE_POSITION_INFORMATION structure.
+    pub position_information: FilePositionInformation,
+    /// ModeInformation (4 bytes): The mode in which the file was opened.
+    pub mode: Vec<u8>,
+    /// AlignmentInformation (4 bytes): The buffer alignment required by the underlying device.
+    pub alignment_requirement: Vec<u8>,
+    /// FileNameLength (4 bytes): The length, in bytes, of the file name.
+    pub file_name_length: Vec<u8>,
+    /// FileName (variable): The full path name of the file in Unicode.
+    pub file_name: Vec<u8>,
+}
+
+impl FileAllInformation {
+    /// Creates a new instance of the file all information.
+    pub fn default() -> Self {
+        FileAllInformation {
+            basic_information: FileBasicInformation::default(),
+            standard_information: FileStandardInformation::default(),
+            internal_information: FileInternalInformation::default(),
+            ea_size: Vec::new(),
+            access_flags: Vec::new(),
+            position_information: FilePositionInformation::default(),
+            mode: Vec::new(),
+            alignment_requirement: Vec::new(),
+            file_name_length: Vec::new(),
+            file_name: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! The file system information structures that are returned in the output buffer
+//! of a query info response for InfoType SMB2_0_INFO_FILESYSTEM (MS-FSCC Section 2.5).
+
+/// FILE_FS_VOLUME_INFORMATION is used to query information on a volume on which a file system is mounted.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FileFsVolumeInformation {
+    /// VolumeCreationTime (8 bytes): The time when the volume was created.
+    pub volume_creation_time: Vec<u8>,
+    /// VolumeSerialNumber (4 bytes): The serial number of the volume.
+    pub volume_serial_number: Vec<u8>,
+    /// VolumeLabelLength (4 bytes): The length, in bytes, of the name of the volume.
+    pub volume_label_length: Vec<u8>,
+    /// SupportsObjects (1 byte): Set to 1 if the file system supports object IDs.
+    pub supports_objects: Vec<u8>,
+    /// Reserved (1 byte): An 8-bit field. This field is reserved and MUST be ignored.
+    pub reserved: Vec<u8>,
+    /// VolumeLabel (variable): The name of the volume in Unicode.
+    pub volume_label: Vec<u8>,
+}
+
+impl FileFsVolumeInformation {
+    /// Creates a new instance of the file system volume information.
+    pub fn default() -> Self {
+        FileFsVolumeInformation {
+            volume_creation_time: Vec::new(),
+            volume_serial_number: Vec::new(),
+            volume_label_length: Vec::new(),
+            supports_objects: Vec::new(),
+            reserved: Vec::new(),
+            volume_label: Vec::new(),
+        }
+    }
+}
+
+/// FILE_FS_SIZE_INFORMATION is used to query sector size information for a file system volume (24 bytes).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FileFsSizeInformation {
+    /// TotalAllocationUnits (8 bytes): The total number of allocation units on the volume.
+    pub total_allocation_units: Vec<u8>,
+    /// AvailableAllocationUnits (8 bytes): The total number of free allocation units on the volume.
+    pub available_allocation_units: Vec<u8>,
+    /// SectorsPerAllocationUnit (4 bytes): The number of sectors in each allocation unit.
+    pub sectors_per_allocation_unit: Vec<u8>,
+    /// BytesPerSector (4 bytes): The number of bytes in each sector.
+    pub bytes_per_sector: Vec<u8>,
+}
+
+impl FileFsSizeInformation {
+    /// Creates a new instance of the file system size information.
+    pub fn default() -> Self {
+        FileFsSizeInformation {
+            total_allocation_units: Vec::new(),
+            available_allocation_units: Vec::new(),
+            sectors_per_allocation_unit: Vec::new(),
+            bytes_per_sector: Vec::new(),
+        }
+    }
+}
+
+/// FILE_FS_FULL_SIZE_INFORMATION is used to query sector size information
+/// for a file system volume including the caller quota (32 bytes).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FileFsFullSizeInformation {
+    /// TotalAllocationUnits (8 bytes): The total number of allocation units on the volume.
+    pub total_allocation_units: Vec<u8>,
+    /// CallerAvailableAllocationUnits (8 bytes): The total number of free allocation units
+    /// on the volume that are available to the user associated with the calling thread.
+    pub caller_available_allocation_units: Vec<u8>,
+    /// ActualAvailableAllocationUnits (8 bytes): The total number of free allocation units on the volume.
+    pub actual_available_allocation_units: Vec<u8>,
+    /// SectorsPerAllocationUnit (4 bytes): The number of sectors in each allocation unit.
+    pub sectors_per_allocation_unit: Vec<u8>,
+    /// BytesPerSector (4 bytes): The number of bytes in each sector.
+    pub bytes_per_sector: Vec<u8>,
+}
+
+impl FileFsFullSizeInformation {
+    /// Creates a new instance of the file system full size information.
+    pub fn default() -> Self {
+        FileFsFullSizeInformation {
+            total_allocation_units: Vec::new(),
+            caller_available_allocation_units: Vec::new(),
+            actual_available_allocation_units: Vec::new(),
+            sectors_per_allocation_unit: Vec::new(),
+            bytes_per_sector: Vec::new(),
+        }
+    }
+}
+
+/// FILE_FS_DEVICE_INFORMATION is used to query device information
+/// associated with a file system volume (8 bytes).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FileFsDeviceInformation {
+    /// DeviceType (4 bytes): The type of the storage device.
+    pub device_type: Vec<u8>,
+    /// Characteristics (4 bytes): The characteristics of the storage device.
+    pub characteristics: Vec<u8>,
+}
+
+impl FileFsDeviceInformation {
+    /// Creates a new instance of the file system device information.
+    pub fn default() -> Self {
+        FileFsDeviceInformation {
+            device_type: Vec::new(),
+            characteristics: Vec::new(),
+        }
+    }
+}
+
+/// FILE_FS_ATTRIBUTE_INFORMATION is used to query attribute information for a file system.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FileFsAttributeInformation {
+    /// FileSystemAttributes (4 bytes): The attributes of the file system.
+    pub file_system_attributes: Vec<u8>,
+    /// MaximumComponentNameLength (4 bytes): The maximum file name component length, in bytes.
+    pub maximum_component_name_length: Vec<u8>,
+    /// FileSystemNameLength (4 bytes): The length, in bytes, of the file system name.
+    pub file_system_name_length: Vec<u8>,
+    /// FileSystemName (variable): The name of the file system in Unicode.
+    pub file_system_name: Vec<u8>,
+}
+
+impl FileFsAttributeInformation {
+    /// Creates a new instance of the file system attribute information.
+    pub fn default() -> Self {
+        FileFsAttributeInformation {
+            file_system_attributes: Vec::new(),
+            maximum_component_name_length: Vec::new(),
+            file_system_name_length: Vec::new(),
+            file_system_name: Vec::new(),
+        }
+    }
+}
@@ -40,6 +40,16 @@ impl SessionSetup {
             buffer: Vec::new(),
         }
     }
+
+    /// Returns whether the server authenticated the session as guest.
+    pub fn is_guest(&self) -> bool {
+        self.session_flags == Some(SessionFlags::IsGuest)
+    }
+
+    /// Returns whether the server authenticated the session as anonymous (null) session.
+    pub fn is_null(&self) -> bool {
+        self.session_flags == Some(SessionFlags::IsNull)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]